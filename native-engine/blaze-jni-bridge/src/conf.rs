@@ -14,7 +14,7 @@
 
 use datafusion::common::Result;
 
-use crate::{jni_call_static, jni_new_string};
+use crate::{jni_call_static, jni_get_string, jni_new_string};
 
 macro_rules! define_conf {
     ($conftype:ty, $name:ident) => {
@@ -41,6 +41,23 @@ define_conf!(BooleanConf, IGNORE_CORRUPTED_FILES);
 define_conf!(BooleanConf, PARTIAL_AGG_SKIPPING_ENABLE);
 define_conf!(DoubleConf, PARTIAL_AGG_SKIPPING_RATIO);
 define_conf!(IntConf, PARTIAL_AGG_SKIPPING_MIN_ROWS);
+define_conf!(IntConf, SINK_MAX_OPEN_WRITERS);
+define_conf!(LongConf, SINK_MAX_RECORDS_PER_FILE);
+define_conf!(LongConf, SINK_TARGET_FILE_SIZE);
+define_conf!(BooleanConf, PARQUET_BLOOM_FILTER_ENABLE);
+define_conf!(LongConf, PARQUET_METADATA_CACHE_SIZE);
+define_conf!(BooleanConf, PARQUET_PAGE_INDEX_ENABLE);
+define_conf!(IntConf, PARQUET_READ_COALESCE_GAP);
+define_conf!(IntConf, PARQUET_READ_MAX_COALESCED_SIZE);
+define_conf!(IntConf, PARQUET_READ_PARALLELISM);
+define_conf!(BooleanConf, PARQUET_READ_AHEAD_ENABLE);
+define_conf!(IntConf, SHUFFLE_READ_PREFETCH_SEGMENTS);
+define_conf!(StringConf, SHUFFLE_COMPRESSION_CODEC);
+define_conf!(StringConf, RSS_COMPRESSION_CODEC);
+define_conf!(StringConf, BROADCAST_COMPRESSION_CODEC);
+define_conf!(StringConf, SPILL_COMPRESSION_CODEC);
+define_conf!(IntConf, COMPRESSION_ZSTD_LEVEL);
+define_conf!(IntConf, BATCH_SERDE_WRITE_VERSION);
 
 pub trait BooleanConf {
     fn key(&self) -> &'static str;
@@ -73,3 +90,12 @@ pub trait DoubleConf {
         jni_call_static!(BlazeConf.doubleConf(key.as_obj()) -> f64)
     }
 }
+
+pub trait StringConf {
+    fn key(&self) -> &'static str;
+    fn value(&self) -> Result<String> {
+        let key = jni_new_string!(self.key())?;
+        let value = jni_call_static!(BlazeConf.stringConf(key.as_obj()) -> JObject)?;
+        jni_get_string!(value.as_obj().into())
+    }
+}
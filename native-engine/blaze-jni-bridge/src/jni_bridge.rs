@@ -403,6 +403,7 @@ pub struct JavaClasses<'a> {
     pub cBlazeCallNativeWrapper: BlazeCallNativeWrapper<'a>,
     pub cBlazeOnHeapSpillManager: BlazeOnHeapSpillManager<'a>,
     pub cBlazeNativeParquetSinkUtils: BlazeNativeParquetSinkUtils<'a>,
+    pub cBlazeChecksummedFileSegment: BlazeChecksummedFileSegment<'a>,
 }
 
 #[allow(clippy::non_send_fields_in_send_ty)]
@@ -467,6 +468,7 @@ impl JavaClasses<'static> {
                 cBlazeCallNativeWrapper: BlazeCallNativeWrapper::new(env).unwrap(),
                 cBlazeOnHeapSpillManager: BlazeOnHeapSpillManager::new(env).unwrap(),
                 cBlazeNativeParquetSinkUtils: BlazeNativeParquetSinkUtils::new(env).unwrap(),
+                cBlazeChecksummedFileSegment: BlazeChecksummedFileSegment::new(env).unwrap(),
             };
             log::info!("Initializing JavaClasses finished");
             java_classes
@@ -1132,6 +1134,8 @@ pub struct BlazeConf<'a> {
     pub method_longConf_ret: ReturnType,
     pub method_doubleConf: JStaticMethodID,
     pub method_doubleConf_ret: ReturnType,
+    pub method_stringConf: JStaticMethodID,
+    pub method_stringConf_ret: ReturnType,
 }
 
 impl<'a> BlazeConf<'_> {
@@ -1157,6 +1161,14 @@ impl<'a> BlazeConf<'_> {
                 .get_static_method_id(class, "doubleConf", "(Ljava/lang/String;)D")
                 .unwrap(),
             method_doubleConf_ret: ReturnType::Primitive(Primitive::Double),
+            method_stringConf: env
+                .get_static_method_id(
+                    class,
+                    "stringConf",
+                    "(Ljava/lang/String;)Ljava/lang/String;",
+                )
+                .unwrap(),
+            method_stringConf_ret: ReturnType::Object,
         })
     }
 }
@@ -1326,7 +1338,7 @@ impl<'a> BlazeNativeParquetSinkUtils<'a> {
         Ok(BlazeNativeParquetSinkUtils {
             class,
             method_getTaskOutputPath: env
-                .get_static_method_id(class, "getTaskOutputPath", "()Ljava/lang/String;")
+                .get_static_method_id(class, "getTaskOutputPath", "(I)Ljava/lang/String;")
                 .unwrap(),
             method_getTaskOutputPath_ret: ReturnType::Object,
             method_completeOutput: env
@@ -1337,6 +1349,38 @@ impl<'a> BlazeNativeParquetSinkUtils<'a> {
     }
 }
 
+#[allow(non_snake_case)]
+pub struct BlazeChecksummedFileSegment<'a> {
+    pub class: JClass<'a>,
+    pub method_segment: JMethodID,
+    pub method_segment_ret: ReturnType,
+    pub method_algorithm: JMethodID,
+    pub method_algorithm_ret: ReturnType,
+    pub method_checksums: JMethodID,
+    pub method_checksums_ret: ReturnType,
+}
+impl<'a> BlazeChecksummedFileSegment<'a> {
+    pub const SIG_TYPE: &'static str =
+        "org/apache/spark/sql/execution/blaze/shuffle/ChecksummedFileSegment";
+
+    pub fn new(env: &JNIEnv<'a>) -> JniResult<BlazeChecksummedFileSegment<'a>> {
+        let class = get_global_jclass(env, Self::SIG_TYPE)?;
+        Ok(BlazeChecksummedFileSegment {
+            class,
+            method_segment: env.get_method_id(
+                class,
+                "segment",
+                "()Lorg/apache/spark/storage/FileSegment;",
+            )?,
+            method_segment_ret: ReturnType::Object,
+            method_algorithm: env.get_method_id(class, "algorithm", "()Ljava/lang/String;")?,
+            method_algorithm_ret: ReturnType::Object,
+            method_checksums: env.get_method_id(class, "checksums", "()[B")?,
+            method_checksums_ret: ReturnType::Array,
+        })
+    }
+}
+
 fn get_global_jclass(env: &JNIEnv<'_>, cls: &str) -> JniResult<JClass<'static>> {
     let local_jclass = env.find_class(cls)?;
     Ok(get_global_ref_jobject(env, local_jclass.into())?.into())
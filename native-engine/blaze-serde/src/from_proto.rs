@@ -15,11 +15,13 @@
 //! Serde code to convert from protocol buffers to Rust data structures.
 
 use std::{
+    any::Any,
+    collections::HashMap,
     convert::{TryFrom, TryInto},
     sync::Arc,
 };
 
-use arrow::datatypes::{FieldRef, SchemaRef};
+use arrow::datatypes::{Field, FieldRef, SchemaRef};
 use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
 use chrono::DateTime;
 use datafusion::{
@@ -47,10 +49,17 @@ use datafusion::{
         ColumnStatistics, ExecutionPlan, Partitioning, PhysicalExpr, Statistics,
     },
 };
-use datafusion_ext_commons::streams::ipc_stream::IpcReadMode;
+use datafusion_ext_commons::{
+    checksum::ChecksumAlgorithm,
+    spark_datetime::{DateTimePattern, ZoneId},
+    streams::ipc_stream::IpcReadMode,
+};
 use datafusion_ext_exprs::{
-    cast::TryCastExpr, get_indexed_field::GetIndexedFieldExpr, get_map_value::GetMapValueExpr,
-    named_struct::NamedStructExpr, spark_scalar_subquery_wrapper::SparkScalarSubqueryWrapperExpr,
+    cast::TryCastExpr, checked_arithmetic::CheckedArithmeticExpr,
+    get_indexed_field::GetIndexedFieldExpr, get_map_value::GetMapValueExpr,
+    higher_order_function::HigherOrderFunctionExpr, lambda_function::LambdaFunctionExpr,
+    lambda_variable::LambdaVariableExpr, named_struct::NamedStructExpr,
+    spark_scalar_subquery_wrapper::SparkScalarSubqueryWrapperExpr,
     spark_udf_wrapper::SparkUDFWrapperExpr, string_contains::StringContainsExpr,
     string_ends_with::StringEndsWithExpr, string_starts_with::StringStartsWithExpr,
 };
@@ -59,6 +68,15 @@ use datafusion_ext_plans::{
     agg_exec::AggExec,
     broadcast_join_exec::BroadcastJoinExec,
     broadcast_nested_loop_join_exec::BroadcastNestedLoopJoinExec,
+    common::{
+        bucketing::{BucketHash, BucketSpec},
+        datetime_rebase::{RebaseConf, RebaseMode},
+        dyn_part_sink::DynPartSinkOutput,
+        row_deletes::{DeleteFile, DeletionVector},
+        schema_adapter::prune_nested_fields,
+        text_scan::{ParseMode, TextParseOptions},
+    },
+    csv_exec::{CsvExec, CsvFormat},
     debug_exec::DebugExec,
     empty_partitions_exec::EmptyPartitionsExec,
     expand_exec::ExpandExec,
@@ -68,13 +86,16 @@ use datafusion_ext_plans::{
     generate_exec::GenerateExec,
     ipc_reader_exec::IpcReaderExec,
     ipc_writer_exec::IpcWriterExec,
+    json_exec::JsonExec,
     limit_exec::LimitExec,
-    parquet_exec::ParquetExec,
+    orc_exec::OrcExec,
+    orc_sink_exec::OrcSinkExec,
+    parquet_exec::{FileMetadataColumn, ParquetExec},
     parquet_sink_exec::ParquetSinkExec,
     project_exec::ProjectExec,
     rename_columns_exec::RenameColumnsExec,
     rss_shuffle_writer_exec::RssShuffleWriterExec,
-    shuffle_writer_exec::ShuffleWriterExec,
+    shuffle_writer_exec::{ShuffleChecksumConf, ShuffleWriterExec},
     sort_exec::SortExec,
     sort_merge_join_exec::SortMergeJoinExec,
     window::{WindowExpr, WindowFunction, WindowRankType},
@@ -87,7 +108,8 @@ use crate::{
     error::PlanSerDeError,
     from_proto_binary_op, into_required, proto_error, protobuf,
     protobuf::{
-        physical_expr_node::ExprType, physical_plan_node::PhysicalPlanType, GenerateFunction,
+        delete_file::DeleteFileType, deletion_vector::Storage, physical_expr_node::ExprType,
+        physical_plan_node::PhysicalPlanType, GenerateFunction,
     },
     Schema,
 };
@@ -171,10 +193,82 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     .fold(phys_expr::lit(true), |a, b| {
                         Arc::new(BinaryExpr::new(a, Operator::And, b))
                     });
+                let metadata_columns = scan
+                    .base_conf
+                    .as_ref()
+                    .unwrap()
+                    .metadata_columns
+                    .iter()
+                    .map(|column| {
+                        let metadata_column = FileMetadataColumn::try_from(column)?;
+                        Ok((column.name.clone(), metadata_column))
+                    })
+                    .collect::<Result<Vec<_>, PlanSerDeError>>()?;
+                let rebase_conf = match &scan.rebase_conf {
+                    Some(rebase_conf) => rebase_conf.try_into()?,
+                    None => RebaseConf::default(),
+                };
+                let bucket_spec = scan
+                    .base_conf
+                    .as_ref()
+                    .unwrap()
+                    .bucket_spec
+                    .as_ref()
+                    .map(BucketSpec::try_from)
+                    .transpose()?;
                 Ok(Arc::new(ParquetExec::new(
                     conf,
                     scan.fs_resource_id.clone(),
                     Some(predicate),
+                    metadata_columns,
+                    rebase_conf,
+                    bucket_spec,
+                )))
+            }
+            PhysicalPlanType::OrcScan(scan) => {
+                let conf: FileScanConfig = scan.base_conf.as_ref().unwrap().try_into()?;
+                let predicate = scan
+                    .pruning_predicates
+                    .iter()
+                    .filter_map(|predicate| {
+                        try_parse_physical_expr(predicate, &conf.file_schema).ok()
+                    })
+                    .fold(phys_expr::lit(true), |a, b| {
+                        Arc::new(BinaryExpr::new(a, Operator::And, b))
+                    });
+                Ok(Arc::new(OrcExec::new(
+                    conf,
+                    scan.fs_resource_id.clone(),
+                    Some(predicate),
+                )))
+            }
+            PhysicalPlanType::CsvScan(scan) => {
+                let conf: FileScanConfig = scan.base_conf.as_ref().unwrap().try_into()?;
+                let parse_options = scan.options.as_ref().unwrap().try_into()?;
+                let csv_char = |name: &str, value: &str| match value.as_bytes() {
+                    &[c] => Ok(c),
+                    _ => Err(proto_error(format!("invalid csv {name}: {value:?}"))),
+                };
+                let format = CsvFormat {
+                    delimiter: csv_char("delimiter", &scan.delimiter)?,
+                    quote: csv_char("quote", &scan.quote)?,
+                    escape: csv_char("escape", &scan.escape)?,
+                    header: scan.header,
+                };
+                Ok(Arc::new(CsvExec::new(
+                    conf,
+                    scan.fs_resource_id.clone(),
+                    format,
+                    parse_options,
+                )))
+            }
+            PhysicalPlanType::JsonScan(scan) => {
+                let conf: FileScanConfig = scan.base_conf.as_ref().unwrap().try_into()?;
+                let parse_options = scan.options.as_ref().unwrap().try_into()?;
+                Ok(Arc::new(JsonExec::new(
+                    conf,
+                    scan.fs_resource_id.clone(),
+                    parse_options,
                 )))
             }
             PhysicalPlanType::SortMergeJoin(sort_merge_join) => {
@@ -254,11 +348,32 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     shuffle_writer.output_partitioning.as_ref(),
                 )?;
 
+                let checksum_conf = if !shuffle_writer.output_checksum_file.is_empty() {
+                    let algorithm_id = shuffle_writer.checksum_algorithm;
+                    let algorithm = protobuf::ShuffleChecksumAlgorithm::from_i32(algorithm_id)
+                        .ok_or_else(|| {
+                            proto_error(format!("invalid ShuffleChecksumAlgorithm {algorithm_id}"))
+                        })
+                        .map(|algorithm| match algorithm {
+                            protobuf::ShuffleChecksumAlgorithm::Adler32 => {
+                                ChecksumAlgorithm::Adler32
+                            }
+                            protobuf::ShuffleChecksumAlgorithm::Crc32 => ChecksumAlgorithm::Crc32,
+                        })?;
+                    Some(ShuffleChecksumConf {
+                        algorithm,
+                        output_checksum_file: shuffle_writer.output_checksum_file.clone(),
+                    })
+                } else {
+                    None
+                };
+
                 Ok(Arc::new(ShuffleWriterExec::try_new(
                     input,
                     output_partitioning.unwrap(),
                     shuffle_writer.output_data_file.clone(),
                     shuffle_writer.output_index_file.clone(),
+                    checksum_conf,
                 )?))
             }
             PhysicalPlanType::RssShuffleWriter(rss_shuffle_writer) => {
@@ -787,10 +902,40 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                 for prop in &parquet_sink.prop {
                     props.push((prop.key.clone(), prop.value.clone()));
                 }
+                let output = if parquet_sink.file_stats_output_dir.is_empty() {
+                    DynPartSinkOutput::Identity
+                } else {
+                    DynPartSinkOutput::FileStats(parquet_sink.file_stats_output_dir.clone())
+                };
+                let rebase_conf = match &parquet_sink.rebase_conf {
+                    Some(rebase_conf) => rebase_conf.try_into()?,
+                    None => RebaseConf::default(),
+                };
+                let bucket_spec = parquet_sink
+                    .bucket_spec
+                    .as_ref()
+                    .map(BucketSpec::try_from)
+                    .transpose()?;
                 Ok(Arc::new(ParquetSinkExec::new(
                     convert_box_required!(parquet_sink.input)?,
                     parquet_sink.fs_resource_id.clone(),
                     parquet_sink.num_dyn_parts as usize,
+                    bucket_spec,
+                    output,
+                    props,
+                    rebase_conf,
+                )))
+            }
+            PhysicalPlanType::OrcSink(orc_sink) => {
+                let mut props: Vec<(String, String)> = vec![];
+                for prop in &orc_sink.prop {
+                    props.push((prop.key.clone(), prop.value.clone()));
+                }
+                Ok(Arc::new(OrcSinkExec::new(
+                    convert_box_required!(orc_sink.input)?,
+                    orc_sink.fs_resource_id.clone(),
+                    orc_sink.num_dyn_parts as usize,
+                    Arc::new(convert_required!(orc_sink.schema)?),
                     props,
                 )))
             }
@@ -904,11 +1049,16 @@ fn try_parse_physical_expr(
             let pcol: Column = bound_reference.into();
             Arc::new(pcol)
         }
-        ExprType::BinaryExpr(binary_expr) => Arc::new(BinaryExpr::new(
-            try_parse_physical_expr_box_required(&binary_expr.l.clone(), input_schema)?,
-            from_proto_binary_op(&binary_expr.op)?,
-            try_parse_physical_expr_box_required(&binary_expr.r.clone(), input_schema)?,
-        )),
+        ExprType::BinaryExpr(binary_expr) => {
+            let l = try_parse_physical_expr_box_required(&binary_expr.l.clone(), input_schema)?;
+            let op = from_proto_binary_op(&binary_expr.op)?;
+            let r = try_parse_physical_expr_box_required(&binary_expr.r.clone(), input_schema)?;
+            if binary_expr.fail_on_error {
+                Arc::new(CheckedArithmeticExpr::try_new(l, op, r)?)
+            } else {
+                Arc::new(BinaryExpr::new(l, op, r))
+            }
+        }
         ExprType::AggExpr(_) => {
             return Err(PlanSerDeError::General(
                 "Cannot convert aggregate expr node to physical expression".to_owned(),
@@ -970,7 +1120,18 @@ fn try_parse_physical_expr(
         ExprType::TryCast(e) => {
             let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
             let cast_type = convert_required!(e.arrow_type)?;
-            Arc::new(TryCastExpr::new(expr, cast_type))
+            let zone = match e.timezone.as_str() {
+                "" => ZoneId::UTC,
+                timezone => ZoneId::parse(timezone).ok_or_else(|| {
+                    PlanSerDeError::General(format!("invalid cast timezone: {timezone}"))
+                })?,
+            };
+            Arc::new(TryCastExpr::new_with_options(
+                expr,
+                cast_type,
+                e.fail_on_error,
+                zone,
+            ))
         }
         ExprType::ScalarFunction(e) => {
             let scalar_function = protobuf::ScalarFunction::from_i32(e.fun).ok_or_else(|| {
@@ -1016,7 +1177,11 @@ fn try_parse_physical_expr(
         ExprType::GetIndexedFieldExpr(e) => {
             let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
             let key = convert_required!(e.key)?;
-            Arc::new(GetIndexedFieldExpr::new(expr, key))
+            Arc::new(GetIndexedFieldExpr::new_with_fail_on_error(
+                expr,
+                key,
+                e.fail_on_error,
+            ))
         }
         ExprType::GetMapValueExpr(e) => {
             let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
@@ -1062,11 +1227,83 @@ fn try_parse_physical_expr(
                 data_type,
             )?)
         }
+        ExprType::LambdaVariable(e) => {
+            let index = input_schema.index_of(&e.name)?;
+            Arc::new(LambdaVariableExpr::new(
+                e.name.clone(),
+                input_schema.fields().len() - index,
+                convert_required!(e.data_type)?,
+                e.nullable,
+            ))
+        }
+        ExprType::LambdaFunction(e) => try_parse_lambda_function(e, input_schema)?,
+        ExprType::HigherOrderFunction(e) => {
+            let func = protobuf::HigherOrderFunction::from_i32(e.func).ok_or_else(|| {
+                proto_error(format!(
+                    "Received an unknown higher-order function: {}",
+                    e.func
+                ))
+            })?;
+            Arc::new(HigherOrderFunctionExpr::try_new(
+                func.into(),
+                e.args
+                    .iter()
+                    .map(|x| try_parse_physical_expr(x, input_schema))
+                    .collect::<Result<Vec<_>, _>>()?,
+                e.lambdas
+                    .iter()
+                    .map(|x| try_parse_lambda_function(x, input_schema))
+                    .collect::<Result<Vec<_>, _>>()?,
+                convert_required!(e.return_type)?,
+            )?)
+        }
     };
 
     Ok(pexpr)
 }
 
+// lambda arguments are appended to the input schema when parsing the lambda
+// body, they are marked with this metadata key to be distinguished from the
+// input columns
+const LAMBDA_ARGUMENT_METADATA_KEY: &str = "__lambda_argument__";
+
+fn try_parse_lambda_function(
+    e: &protobuf::PhysicalLambdaFunctionExprNode,
+    input_schema: &SchemaRef,
+) -> Result<Arc<dyn PhysicalExpr>, PlanSerDeError> {
+    let num_enclosing_args = input_schema
+        .fields()
+        .iter()
+        .filter(|field| field.metadata().contains_key(LAMBDA_ARGUMENT_METADATA_KEY))
+        .count();
+    let arg_fields = e
+        .arguments
+        .iter()
+        .map(|arg| {
+            let field = Field::new(&arg.name, convert_required!(arg.data_type)?, arg.nullable)
+                .with_metadata(HashMap::from([(
+                    LAMBDA_ARGUMENT_METADATA_KEY.to_string(),
+                    String::new(),
+                )]));
+            Ok(Arc::new(field))
+        })
+        .collect::<Result<Vec<FieldRef>, PlanSerDeError>>()?;
+    let lambda_schema = Arc::new(Schema::new(
+        input_schema
+            .fields()
+            .iter()
+            .chain(&arg_fields)
+            .cloned()
+            .collect::<Vec<_>>(),
+    ));
+    let body = try_parse_physical_expr_box_required(&e.body, &lambda_schema)?;
+    Ok(Arc::new(LambdaFunctionExpr::new(
+        body,
+        arg_fields,
+        num_enclosing_args,
+    )))
+}
+
 fn try_parse_physical_expr_required(
     proto: &Option<protobuf::PhysicalExprNode>,
     input_schema: &SchemaRef,
@@ -1117,20 +1354,70 @@ impl TryFrom<&protobuf::PartitionedFile> for PartitionedFile {
     type Error = PlanSerDeError;
 
     fn try_from(val: &protobuf::PartitionedFile) -> Result<Self, Self::Error> {
+        // delete files are passed to file openers as extensions
+        let delete_files = val
+            .delete_files
+            .iter()
+            .map(|delete_file| delete_file.try_into())
+            .collect::<Result<Vec<DeleteFile>, _>>()?;
         Ok(PartitionedFile {
-            object_meta: ObjectMeta {
-                location: Path::from(format!("/{}", BASE64_URL_SAFE_NO_PAD.encode(&val.path))),
-                size: val.size as usize,
-                last_modified: DateTime::default(),
-                e_tag: None,
-            },
+            object_meta: file_object_meta(&val.path, val.size, val.last_modified_ns),
             partition_values: val
                 .partition_values
                 .iter()
                 .map(|v| v.try_into())
                 .collect::<Result<Vec<_>, _>>()?,
             range: val.range.as_ref().map(|v| v.try_into()).transpose()?,
-            extensions: None,
+            extensions: (!delete_files.is_empty())
+                .then(|| Arc::new(delete_files) as Arc<dyn Any + Send + Sync>),
+        })
+    }
+}
+
+// file paths are encoded into object store paths, and decoded by file readers
+fn file_object_meta(path: &str, size: u64, last_modified_ns: u64) -> ObjectMeta {
+    ObjectMeta {
+        location: Path::from(format!("/{}", BASE64_URL_SAFE_NO_PAD.encode(path))),
+        size: size as usize,
+        last_modified: DateTime::from_timestamp(
+            (last_modified_ns / 1_000_000_000) as i64,
+            (last_modified_ns % 1_000_000_000) as u32,
+        )
+        .unwrap_or_default(),
+        e_tag: None,
+    }
+}
+
+impl TryFrom<&protobuf::DeleteFile> for DeleteFile {
+    type Error = PlanSerDeError;
+
+    fn try_from(val: &protobuf::DeleteFile) -> Result<Self, Self::Error> {
+        let delete_file_type = val
+            .delete_file_type
+            .as_ref()
+            .ok_or_else(|| proto_error("Unexpected empty delete file"))?;
+        Ok(match delete_file_type {
+            DeleteFileType::PositionDeletes(position_deletes) => DeleteFile::PositionDeletes(
+                file_object_meta(&position_deletes.path, position_deletes.size, 0),
+            ),
+            DeleteFileType::EqualityDeletes(equality_deletes) => DeleteFile::EqualityDeletes {
+                meta: file_object_meta(&equality_deletes.path, equality_deletes.size, 0),
+                equality_columns: equality_deletes.equality_columns.clone(),
+            },
+            DeleteFileType::DeletionVector(deletion_vector) => {
+                let storage = deletion_vector
+                    .storage
+                    .as_ref()
+                    .ok_or_else(|| proto_error("Unexpected empty deletion vector"))?;
+                DeleteFile::DeletionVector(match storage {
+                    Storage::InlineData(data) => DeletionVector::Inline(data.clone().into()),
+                    Storage::Stored(stored) => DeletionVector::Stored {
+                        path: stored.path.clone(),
+                        offset: stored.offset,
+                        size: stored.size as usize,
+                    },
+                })
+            }
         })
     }
 }
@@ -1195,7 +1482,13 @@ impl TryInto<FileScanConfig> for &protobuf::FileScanExecConf {
     type Error = PlanSerDeError;
 
     fn try_into(self) -> Result<FileScanConfig, Self::Error> {
-        let schema: SchemaRef = Arc::new(convert_required!(self.schema)?);
+        let schema: Schema = convert_required!(self.schema)?;
+        let nested_projection = self
+            .nested_projection
+            .iter()
+            .map(|path| path.names.clone())
+            .collect::<Vec<_>>();
+        let schema: SchemaRef = Arc::new(prune_nested_fields(&schema, &nested_projection));
         let projection = self
             .projection
             .iter()
@@ -1240,3 +1533,116 @@ impl TryInto<FileScanConfig> for &protobuf::FileScanExecConf {
         })
     }
 }
+
+impl TryFrom<&protobuf::BucketSpec> for BucketSpec {
+    type Error = PlanSerDeError;
+
+    fn try_from(bucket_spec: &protobuf::BucketSpec) -> Result<Self, Self::Error> {
+        if bucket_spec.num_buckets == 0 {
+            return Err(proto_error("num_buckets of BucketSpec must be positive"));
+        }
+        let hash = protobuf::BucketHash::from_i32(bucket_spec.hash)
+            .ok_or_else(|| proto_error(format!("invalid BucketHash: {}", bucket_spec.hash)))?;
+        Ok(BucketSpec {
+            bucket_columns: bucket_spec
+                .bucket_column_indices
+                .iter()
+                .map(|&idx| idx as usize)
+                .collect(),
+            num_buckets: bucket_spec.num_buckets as usize,
+            sort_columns: bucket_spec
+                .sort_column_indices
+                .iter()
+                .map(|&idx| idx as usize)
+                .collect(),
+            hash: match hash {
+                protobuf::BucketHash::Murmur3 => BucketHash::Murmur3,
+                protobuf::BucketHash::HiveHash => BucketHash::HiveHash,
+            },
+        })
+    }
+}
+
+impl TryFrom<&protobuf::FileMetadataColumn> for FileMetadataColumn {
+    type Error = PlanSerDeError;
+
+    fn try_from(column: &protobuf::FileMetadataColumn) -> Result<Self, Self::Error> {
+        let kind = protobuf::FileMetadataColumnKind::from_i32(column.kind).ok_or_else(|| {
+            proto_error(format!("invalid FileMetadataColumnKind: {}", column.kind))
+        })?;
+        Ok(match kind {
+            protobuf::FileMetadataColumnKind::FilePath => FileMetadataColumn::FilePath,
+            protobuf::FileMetadataColumnKind::FileName => FileMetadataColumn::FileName,
+            protobuf::FileMetadataColumnKind::FileSize => FileMetadataColumn::FileSize,
+            protobuf::FileMetadataColumnKind::FileModificationTime => {
+                FileMetadataColumn::FileModificationTime
+            }
+            protobuf::FileMetadataColumnKind::RowIndex => FileMetadataColumn::RowIndex,
+        })
+    }
+}
+
+impl TryFrom<&protobuf::ParquetRebaseConf> for RebaseConf {
+    type Error = PlanSerDeError;
+
+    fn try_from(conf: &protobuf::ParquetRebaseConf) -> Result<Self, Self::Error> {
+        let rebase_mode = |mode: i32| -> Result<RebaseMode, PlanSerDeError> {
+            Ok(
+                match protobuf::RebaseMode::from_i32(mode)
+                    .ok_or_else(|| proto_error(format!("invalid RebaseMode: {mode}")))?
+                {
+                    protobuf::RebaseMode::Exception => RebaseMode::Exception,
+                    protobuf::RebaseMode::Corrected => RebaseMode::Corrected,
+                    protobuf::RebaseMode::Legacy => RebaseMode::Legacy,
+                },
+            )
+        };
+        let time_zone = match conf.time_zone.as_str() {
+            "" => "UTC".to_string(),
+            time_zone => {
+                ZoneId::parse(time_zone)
+                    .ok_or_else(|| proto_error(format!("invalid timezone: {time_zone}")))?;
+                time_zone.to_string()
+            }
+        };
+        Ok(Self {
+            datetime_rebase_mode: rebase_mode(conf.datetime_rebase_mode)?,
+            int96_rebase_mode: rebase_mode(conf.int96_rebase_mode)?,
+            time_zone,
+            int96_timestamp_conversion: conf.int96_timestamp_conversion,
+            spark_version: conf.spark_version.clone(),
+        })
+    }
+}
+
+impl TryFrom<&protobuf::TextScanOptions> for TextParseOptions {
+    type Error = PlanSerDeError;
+
+    fn try_from(options: &protobuf::TextScanOptions) -> Result<Self, Self::Error> {
+        let mode = match protobuf::TextParseMode::from_i32(options.mode)
+            .ok_or_else(|| proto_error(format!("invalid TextParseMode: {}", options.mode)))?
+        {
+            protobuf::TextParseMode::Permissive => ParseMode::Permissive,
+            protobuf::TextParseMode::DropMalformed => ParseMode::DropMalformed,
+            protobuf::TextParseMode::FailFast => ParseMode::FailFast,
+        };
+        let parse_pattern = |pattern: &str| match pattern {
+            "" => Ok(None),
+            pattern => DateTimePattern::parse(pattern)
+                .map(Some)
+                .ok_or_else(|| proto_error(format!("unsupported datetime pattern: {pattern}"))),
+        };
+        let zone = match options.timezone.as_str() {
+            "" => ZoneId::UTC,
+            timezone => ZoneId::parse(timezone)
+                .ok_or_else(|| proto_error(format!("invalid timezone: {timezone}")))?,
+        };
+        Ok(Self {
+            mode,
+            multi_line: options.multi_line,
+            timestamp_format: parse_pattern(&options.timestamp_format)?,
+            date_format: parse_pattern(&options.date_format)?,
+            zone,
+        })
+    }
+}
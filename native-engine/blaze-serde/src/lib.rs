@@ -12,13 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use arrow::datatypes::{DataType, Field, Fields, IntervalUnit, Schema, TimeUnit};
 use datafusion::{
-    logical_expr::Operator, physical_plan::joins::utils::JoinSide, prelude::JoinType,
-    scalar::ScalarValue,
+    logical_expr::Operator, parquet::arrow::PARQUET_FIELD_ID_META_KEY,
+    physical_plan::joins::utils::JoinSide, prelude::JoinType, scalar::ScalarValue,
 };
+use datafusion_ext_exprs::higher_order_function::HigherOrderFunction;
 use datafusion_ext_plans::agg::AggFunction;
 
 use crate::error::PlanSerDeError;
@@ -141,6 +142,21 @@ impl From<protobuf::AggFunction> for AggFunction {
     }
 }
 
+impl From<protobuf::HigherOrderFunction> for HigherOrderFunction {
+    fn from(func: protobuf::HigherOrderFunction) -> HigherOrderFunction {
+        match func {
+            protobuf::HigherOrderFunction::Transform => HigherOrderFunction::Transform,
+            protobuf::HigherOrderFunction::Filter => HigherOrderFunction::Filter,
+            protobuf::HigherOrderFunction::Exists => HigherOrderFunction::Exists,
+            protobuf::HigherOrderFunction::Forall => HigherOrderFunction::Forall,
+            protobuf::HigherOrderFunction::Aggregate => HigherOrderFunction::Aggregate,
+            protobuf::HigherOrderFunction::ZipWith => HigherOrderFunction::ZipWith,
+            protobuf::HigherOrderFunction::MapFilter => HigherOrderFunction::MapFilter,
+            protobuf::HigherOrderFunction::TransformValues => HigherOrderFunction::TransformValues,
+        }
+    }
+}
+
 impl protobuf::TimeUnit {
     pub fn from_arrow_time_unit(val: &TimeUnit) -> Self {
         match val {
@@ -419,11 +435,18 @@ impl TryInto<Field> for &protobuf::Field {
             )
         })?;
 
-        Ok(Field::new(
+        let field = Field::new(
             self.name.as_str(),
             pb_datatype.as_ref().try_into()?,
             self.nullable,
-        ))
+        );
+        Ok(match self.field_id {
+            Some(field_id) => field.with_metadata(HashMap::from([(
+                PARQUET_FIELD_ID_META_KEY.to_string(),
+                field_id.to_string(),
+            )])),
+            None => field,
+        })
     }
 }
 
@@ -434,18 +457,8 @@ impl TryInto<Schema> for &protobuf::Schema {
         let fields = self
             .columns
             .iter()
-            .map(|c| {
-                let pb_arrow_type_res = c
-                    .arrow_type
-                    .as_ref()
-                    .ok_or_else(|| proto_error("Protobuf deserialization error: Field message was missing required field 'arrow_type'"));
-                let pb_arrow_type: &protobuf::ArrowType = match pb_arrow_type_res {
-                    Ok(res) => res,
-                    Err(e) => return Err(e),
-                };
-                Ok(Field::new(&c.name, pb_arrow_type.try_into()?, c.nullable))
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+            .map(|c| c.try_into())
+            .collect::<Result<Vec<Field>, _>>()?;
         Ok(Schema::new(fields))
     }
 }
@@ -14,29 +14,84 @@
 
 use std::{str::FromStr, sync::Arc};
 
-use arrow::{array::*, datatypes::*};
+use arrow::{array::*, datatypes::*, util::display::array_value_to_string};
 use bigdecimal::{FromPrimitive, ToPrimitive};
 use datafusion::common::{
     cast::{as_float32_array, as_float64_array},
     Result,
 };
-use num::{cast::AsPrimitive, Bounded, Integer, Signed};
+use num::{
+    cast::{AsPrimitive, NumCast},
+    Bounded, Integer, Signed,
+};
 use paste::paste;
 
-use crate::df_execution_err;
+use crate::{
+    df_execution_err,
+    spark_datetime::{
+        date_to_string, date_to_timestamp, string_to_date, string_to_timestamp, timestamp_to_date,
+        timestamp_to_string, trim_all, ZoneId,
+    },
+    spark_errors::{
+        cast_invalid_input_error, cast_overflow_error, numeric_value_out_of_range_error,
+    },
+};
 
 pub fn cast(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
-    return cast_impl(array, cast_type, false);
+    return cast_impl(array, cast_type, false, false, ZoneId::UTC);
+}
+
+/// cast in spark ANSI mode, raises errors on malformed input or overflow
+/// instead of returning nulls
+pub fn cast_ansi(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    return cast_impl(array, cast_type, false, true, ZoneId::UTC);
+}
+
+/// cast with the session time zone, which is used in casting between
+/// strings, dates and timestamps
+pub fn cast_with_zone(
+    array: &dyn Array,
+    cast_type: &DataType,
+    fail_on_error: bool,
+    zone: ZoneId,
+) -> Result<ArrayRef> {
+    return cast_impl(array, cast_type, false, fail_on_error, zone);
 }
 
 pub fn cast_scan_input_array(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
-    return cast_impl(array, cast_type, true);
+    return cast_impl(array, cast_type, true, false, ZoneId::UTC);
 }
 
 pub fn cast_impl(
     array: &dyn Array,
     cast_type: &DataType,
     match_struct_fields: bool,
+    fail_on_error: bool,
+    zone: ZoneId,
+) -> Result<ArrayRef> {
+    let casted = cast_unchecked(array, cast_type, match_struct_fields, fail_on_error, zone)?;
+
+    // nested types are checked in the casting of their children
+    let is_nested = matches!(
+        cast_type,
+        DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::FixedSizeList(..)
+            | DataType::Struct(_)
+            | DataType::Map(..)
+    );
+    if fail_on_error && !is_nested {
+        check_ansi_cast(array, &casted)?;
+    }
+    Ok(casted)
+}
+
+fn cast_unchecked(
+    array: &dyn Array,
+    cast_type: &DataType,
+    match_struct_fields: bool,
+    fail_on_error: bool,
+    zone: ZoneId,
 ) -> Result<ArrayRef> {
     Ok(match (&array.data_type(), cast_type) {
         (&t1, t2) if t1 == t2 => make_array(array.to_data()),
@@ -46,27 +101,35 @@ pub fn cast_impl(
         // float to int
         (&DataType::Float32, &DataType::Int8) => Arc::new(cast_float_to_integer::<_, Int8Type>(
             as_float32_array(array)?,
+            fail_on_error,
         )),
         (&DataType::Float32, &DataType::Int16) => Arc::new(cast_float_to_integer::<_, Int16Type>(
             as_float32_array(array)?,
+            fail_on_error,
         )),
         (&DataType::Float32, &DataType::Int32) => Arc::new(cast_float_to_integer::<_, Int32Type>(
             as_float32_array(array)?,
+            fail_on_error,
         )),
         (&DataType::Float32, &DataType::Int64) => Arc::new(cast_float_to_integer::<_, Int64Type>(
             as_float32_array(array)?,
+            fail_on_error,
         )),
         (&DataType::Float64, &DataType::Int8) => Arc::new(cast_float_to_integer::<_, Int8Type>(
             as_float64_array(array)?,
+            fail_on_error,
         )),
         (&DataType::Float64, &DataType::Int16) => Arc::new(cast_float_to_integer::<_, Int16Type>(
             as_float64_array(array)?,
+            fail_on_error,
         )),
         (&DataType::Float64, &DataType::Int32) => Arc::new(cast_float_to_integer::<_, Int32Type>(
             as_float64_array(array)?,
+            fail_on_error,
         )),
         (&DataType::Float64, &DataType::Int64) => Arc::new(cast_float_to_integer::<_, Int64Type>(
             as_float64_array(array)?,
+            fail_on_error,
         )),
 
         (&DataType::Utf8, &DataType::Int8)
@@ -84,6 +147,71 @@ pub fn cast_impl(
             // spark compatible decimal to string cast
             try_cast_decimal_array_to_string(array, cast_type)?
         }
+        (&DataType::Utf8, &DataType::Boolean) => {
+            // spark compatible string to boolean cast
+            let array = as_string_array(array);
+            Arc::new(
+                array
+                    .iter()
+                    .map(|s| s.and_then(string_to_boolean))
+                    .collect::<BooleanArray>(),
+            )
+        }
+        (&DataType::Utf8, &DataType::Float32) => {
+            // spark compatible string to float cast
+            cast_string_array_with::<Float32Type>(array, string_to_float)
+        }
+        (&DataType::Utf8, &DataType::Float64) => {
+            // spark compatible string to double cast
+            cast_string_array_with::<Float64Type>(array, string_to_float)
+        }
+        (&DataType::Float32, &DataType::Utf8) => {
+            // spark compatible float to string cast, like java's Float.toString()
+            cast_array_to_string_with(as_float32_array(array)?, |v| Some(float_to_string(v)))
+        }
+        (&DataType::Float64, &DataType::Utf8) => {
+            // spark compatible double to string cast, like java's Double.toString()
+            cast_array_to_string_with(as_float64_array(array)?, |v| Some(float_to_string(v)))
+        }
+        (&DataType::Utf8, &DataType::Date32) => {
+            // spark compatible string to date cast
+            cast_string_array_with::<Date32Type>(array, string_to_date)
+        }
+        (&DataType::Utf8, &DataType::Timestamp(TimeUnit::Microsecond, tz)) => {
+            // spark compatible string to timestamp cast
+            let array = as_string_array(array);
+            Arc::new(
+                array
+                    .iter()
+                    .map(|s| s.and_then(|s| string_to_timestamp(s, zone)))
+                    .collect::<TimestampMicrosecondArray>()
+                    .with_timezone_opt(tz.clone()),
+            )
+        }
+        (&DataType::Date32, &DataType::Utf8) => {
+            // spark compatible date to string cast
+            cast_array_to_string_with(as_primitive_array::<Date32Type>(array), date_to_string)
+        }
+        (&DataType::Timestamp(TimeUnit::Microsecond, _), &DataType::Utf8) => {
+            // spark compatible timestamp to string cast
+            cast_array_to_string_with(as_primitive_array::<TimestampMicrosecondType>(array), |v| {
+                timestamp_to_string(v, zone)
+            })
+        }
+        (&DataType::Timestamp(TimeUnit::Microsecond, _), &DataType::Date32) => {
+            // timestamp to date in session time zone
+            let array = as_primitive_array::<TimestampMicrosecondType>(array);
+            let casted: Date32Array =
+                arrow::compute::unary_opt(array, |v| timestamp_to_date(v, zone));
+            Arc::new(casted)
+        }
+        (&DataType::Date32, &DataType::Timestamp(TimeUnit::Microsecond, tz)) => {
+            // date to timestamp at the beginning of the day in session time zone
+            let array = as_primitive_array::<Date32Type>(array);
+            let casted: TimestampMicrosecondArray =
+                arrow::compute::unary_opt(array, |v| date_to_timestamp(v, zone));
+            Arc::new(casted.with_timezone_opt(tz.clone()))
+        }
         (&DataType::Timestamp(..), DataType::Float64) => {
             // timestamp to f64 = timestamp to i64 to f64, only used in agg.sum()
             arrow::compute::cast(
@@ -97,7 +225,13 @@ pub fn cast_impl(
         }
         (&DataType::List(_), DataType::List(to_field)) => {
             let list = as_list_array(array);
-            let items = cast_impl(list.values(), to_field.data_type(), match_struct_fields)?;
+            let items = cast_impl(
+                list.values(),
+                to_field.data_type(),
+                match_struct_fields,
+                fail_on_error,
+                zone,
+            )?;
             make_array(
                 list.to_data()
                     .into_builder()
@@ -106,6 +240,42 @@ pub fn cast_impl(
                     .build()?,
             )
         }
+        (&DataType::LargeList(_), DataType::LargeList(to_field)) => {
+            let list = as_large_list_array(array);
+            let items = cast_impl(
+                list.values(),
+                to_field.data_type(),
+                match_struct_fields,
+                fail_on_error,
+                zone,
+            )?;
+            make_array(
+                list.to_data()
+                    .into_builder()
+                    .data_type(DataType::LargeList(to_field.clone()))
+                    .child_data(vec![items.into_data()])
+                    .build()?,
+            )
+        }
+        (&DataType::FixedSizeList(_, size), DataType::FixedSizeList(to_field, to_size))
+            if size == to_size =>
+        {
+            let list = as_fixed_size_list_array(array);
+            let items = cast_impl(
+                list.values(),
+                to_field.data_type(),
+                match_struct_fields,
+                fail_on_error,
+                zone,
+            )?;
+            make_array(
+                list.to_data()
+                    .into_builder()
+                    .data_type(DataType::FixedSizeList(to_field.clone(), *to_size))
+                    .child_data(vec![items.into_data()])
+                    .build()?,
+            )
+        }
         (&DataType::Struct(_), DataType::Struct(to_fields)) => {
             let struct_ = as_struct_array(array);
 
@@ -119,7 +289,13 @@ pub fn cast_impl(
                     .iter()
                     .zip(to_fields)
                     .map(|(column, to_field)| {
-                        cast_impl(column, to_field.data_type(), match_struct_fields)
+                        cast_impl(
+                            column,
+                            to_field.data_type(),
+                            match_struct_fields,
+                            fail_on_error,
+                            zone,
+                        )
                     })
                     .collect::<Result<Vec<_>>>()?;
 
@@ -143,7 +319,13 @@ pub fn cast_impl(
                     .map(|field| {
                         let col = struct_.column_by_name(field.name().as_str());
                         if col.is_some() {
-                            cast_impl(col.unwrap(), field.data_type(), match_struct_fields)
+                            cast_impl(
+                                col.unwrap(),
+                                field.data_type(),
+                                match_struct_fields,
+                                fail_on_error,
+                                zone,
+                            )
                         } else {
                             null_column_name.push(field.name().clone());
                             Ok(new_null_array(field.data_type(), struct_.len()))
@@ -155,7 +337,7 @@ pub fn cast_impl(
                     .iter()
                     .map(|field: &FieldRef| {
                         if null_column_name.contains(field.name()) {
-                            Arc::new(Field::new(field.name(), field.data_type().clone(), true))
+                            Arc::new(field.as_ref().clone().with_nullable(true))
                         } else {
                             field.clone()
                         }
@@ -183,6 +365,8 @@ pub fn cast_impl(
                 map.entries(),
                 to_entries_field.data_type(),
                 match_struct_fields,
+                fail_on_error,
+                zone,
             )?;
             make_array(
                 map.to_data()
@@ -199,6 +383,28 @@ pub fn cast_impl(
     })
 }
 
+/// in ANSI mode, any non-null value casted to null is treated as an error
+fn check_ansi_cast(from: &dyn Array, to: &dyn Array) -> Result<()> {
+    let (from_type, to_type) = (from.data_type(), to.data_type());
+    if from_type == &DataType::Null || to_type == &DataType::Null {
+        return Ok(());
+    }
+    if from.null_count() == to.null_count() {
+        return Ok(());
+    }
+    for i in 0..from.len() {
+        if from.is_valid(i) && to.is_null(i) {
+            let value = array_value_to_string(from, i)?;
+            return Err(match (from_type, to_type) {
+                (DataType::Utf8, _) => cast_invalid_input_error(&value, from_type, to_type),
+                (_, DataType::Decimal128(..)) => numeric_value_out_of_range_error(&value, to_type),
+                _ => cast_overflow_error(&value, from_type, to_type),
+            });
+        }
+    }
+    Ok(())
+}
+
 fn try_cast_string_array_to_integer(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
     macro_rules! cast {
         ($target_type:ident) => {{
@@ -208,7 +414,7 @@ fn try_cast_string_array_to_integer(array: &dyn Array, cast_type: &DataType) ->
 
             for v in array.iter() {
                 match v {
-                    Some(s) => builder.append_option(to_integer(s)),
+                    Some(s) => builder.append_option(to_integer(trim_all(s))),
                     None => builder.append_null(),
                 }
             }
@@ -232,7 +438,7 @@ fn try_cast_string_array_to_decimal(array: &dyn Array, cast_type: &DataType) ->
 
         for v in array.iter() {
             match v {
-                Some(s) => match to_decimal(s, precision, scale) {
+                Some(s) => match to_decimal(trim_all(s), precision, scale) {
                     Some(v) => builder.append_value(v),
                     None => builder.append_null(),
                 },
@@ -277,12 +483,126 @@ fn try_cast_boolean_array_to_string(array: &dyn Array, cast_type: &DataType) ->
     unreachable!("cast_type must be DataType::Utf8")
 }
 
+fn cast_string_array_with<T: ArrowPrimitiveType>(
+    array: &dyn Array,
+    f: impl Fn(&str) -> Option<T::Native>,
+) -> ArrayRef {
+    let array = as_string_array(array);
+    Arc::new(
+        array
+            .iter()
+            .map(|s| s.and_then(&f))
+            .collect::<PrimitiveArray<T>>(),
+    )
+}
+
+fn cast_array_to_string_with<T: ArrowPrimitiveType>(
+    array: &PrimitiveArray<T>,
+    f: impl Fn(T::Native) -> Option<String>,
+) -> ArrayRef {
+    Arc::new(
+        array
+            .iter()
+            .map(|v| v.and_then(&f))
+            .collect::<StringArray>(),
+    )
+}
+
+// see spark's StringUtils.isTrueString()/isFalseString()
+fn string_to_boolean(s: &str) -> Option<bool> {
+    match trim_all(s).to_ascii_lowercase().as_str() {
+        "t" | "true" | "y" | "yes" | "1" => Some(true),
+        "f" | "false" | "n" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+// like java's Double.parseDouble(), with special literals supported by spark
+fn string_to_float<F: FromStr>(s: &str) -> Option<F> {
+    let s = s.trim_matches(|c: char| c <= ' ');
+    match s {
+        "Infinity" | "+Infinity" => return F::from_str("inf").ok(),
+        "-Infinity" => return F::from_str("-inf").ok(),
+        "NaN" | "+NaN" | "-NaN" => return F::from_str("nan").ok(),
+        _ => {}
+    }
+    match s.to_ascii_lowercase().as_str() {
+        "inf" | "+inf" | "infinity" | "+infinity" => return F::from_str("inf").ok(),
+        "-inf" | "-infinity" => return F::from_str("-inf").ok(),
+        "nan" => return F::from_str("nan").ok(),
+        _ => {}
+    }
+
+    // java allows type suffixes like `1.5d`, other letters except exponents are
+    // rejected
+    let s = s.strip_suffix(['d', 'D', 'f', 'F']).unwrap_or(s);
+    if s.bytes()
+        .any(|b| b.is_ascii_alphabetic() && !matches!(b, b'e' | b'E'))
+    {
+        return None;
+    }
+    F::from_str(s).ok()
+}
+
+/// like java's Float.toString()/Double.toString():
+/// values in [1e-3, 1e7) are formatted in plain notation, otherwise in
+/// scientific notation like `1.0E10`
+pub fn float_to_string<F: std::fmt::LowerExp + num::Float>(v: F) -> String {
+    if v.is_nan() {
+        return "NaN".to_string();
+    }
+    if v.is_infinite() {
+        return if v.is_sign_negative() {
+            "-Infinity"
+        } else {
+            "Infinity"
+        }
+        .to_string();
+    }
+    if v.is_zero() {
+        return if v.is_sign_negative() { "-0.0" } else { "0.0" }.to_string();
+    }
+
+    // shortest representation in the form of `-d.ddde-n`
+    let formatted = format!("{v:e}");
+    let (mantissa, exp) = formatted.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits = mantissa.replace('.', "");
+
+    if (-3..7).contains(&exp) {
+        if exp >= 0 {
+            let int_len = exp as usize + 1;
+            let padded = format!("{digits:0<int_len$}");
+            let (int_part, frac_part) = padded.split_at(int_len);
+            let frac_part = if frac_part.is_empty() { "0" } else { frac_part };
+            format!("{sign}{int_part}.{frac_part}")
+        } else {
+            let leading_zeros = "0".repeat((-exp - 1) as usize);
+            format!("{sign}0.{leading_zeros}{digits}")
+        }
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let rest = if rest.is_empty() { "0" } else { rest };
+        format!("{sign}{first}.{rest}E{exp}")
+    }
+}
+
 fn cast_float_to_integer<F: ArrowPrimitiveType, T: ArrowPrimitiveType>(
     array: &PrimitiveArray<F>,
+    fail_on_error: bool,
 ) -> PrimitiveArray<T>
 where
-    F::Native: AsPrimitive<T::Native>,
+    F::Native: AsPrimitive<T::Native> + ToPrimitive,
+    T::Native: NumCast,
 {
+    if fail_on_error {
+        // NaN and out-of-range values are casted to nulls and then reported
+        return arrow::compute::unary_opt(array, |v| NumCast::from(v));
+    }
     arrow::compute::unary(array, |v| v.as_())
 }
 
@@ -410,4 +730,237 @@ mod test {
             ])
         );
     }
+    #[test]
+    fn test_ansi_cast() {
+        let f64_array: ArrayRef = Arc::new(Float64Array::from_iter(vec![
+            None,
+            Some(123.456),
+            Some(-987.654),
+        ]));
+        let casted = cast_ansi(&f64_array, &DataType::Int32).unwrap();
+        assert_eq!(
+            as_int32_array(&casted).unwrap(),
+            &Int32Array::from_iter(vec![None, Some(123), Some(-987)])
+        );
+
+        let f64_array: ArrayRef = Arc::new(Float64Array::from_iter(vec![
+            Some(1.0),
+            Some(i32::MAX as f64 + 10000.0),
+        ]));
+        let err = cast_ansi(&f64_array, &DataType::Int32).unwrap_err();
+        assert!(err.to_string().contains("[CAST_OVERFLOW]"));
+        let err = cast_ansi(&Float64Array::from(vec![f64::NAN]), &DataType::Int64).unwrap_err();
+        assert!(err.to_string().contains("[CAST_OVERFLOW]"));
+
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![Some("123"), None]));
+        let casted = cast_ansi(&string_array, &DataType::Int64).unwrap();
+        assert_eq!(
+            casted.as_any().downcast_ref::<Int64Array>().unwrap(),
+            &Int64Array::from(vec![Some(123), None])
+        );
+
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![Some("123"), Some("1x")]));
+        let err = cast_ansi(&string_array, &DataType::Int32).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("[CAST_INVALID_INPUT] The value '1x' of the type \"STRING\""));
+
+        // malformed elements of nested types are also reported
+        let list_array: ArrayRef =
+            Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+                Some(vec![Some(1), Some(i64::MAX)]),
+            ]));
+        let to_type = DataType::List(Arc::new(Field::new("item", DataType::Int32, true)));
+        assert!(cast(&list_array, &to_type).is_ok());
+        let err = cast_ansi(&list_array, &to_type).unwrap_err();
+        assert!(err.to_string().contains("[CAST_OVERFLOW]"));
+    }
+
+    // expected values are produced by spark
+    #[test]
+    fn test_spark_cast_matrix() {
+        let utc = ZoneId::UTC;
+        let la = ZoneId::parse("America/Los_Angeles").unwrap();
+        let ts_type = DataType::Timestamp(TimeUnit::Microsecond, None);
+        let cast_strings = |values: &[&str], to_type: &DataType, zone: ZoneId| {
+            let array = StringArray::from(values.to_vec());
+            cast_with_zone(&array, to_type, false, zone).unwrap()
+        };
+        let to_strings = |array: ArrayRef, zone: ZoneId| {
+            let casted = cast_with_zone(&array, &DataType::Utf8, false, zone).unwrap();
+            as_string_array(&casted)
+                .iter()
+                .map(|s| s.map(|s| s.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        // string to boolean
+        let table = [
+            (" true ", Some(true)),
+            ("T", Some(true)),
+            ("yes", Some(true)),
+            ("1", Some(true)),
+            ("false", Some(false)),
+            ("N", Some(false)),
+            ("0", Some(false)),
+            ("abc", None),
+            ("", None),
+        ];
+        let casted = cast_strings(&table.map(|t| t.0), &DataType::Boolean, utc);
+        assert_eq!(
+            as_boolean_array(&casted),
+            &BooleanArray::from(table.map(|t| t.1).to_vec()),
+        );
+
+        // string to integer
+        let table = [
+            (" 123 ", Some(123)),
+            ("\t-45.67\n", Some(-45)),
+            ("1 2", None),
+        ];
+        let casted = cast_strings(&table.map(|t| t.0), &DataType::Int32, utc);
+        assert_eq!(
+            as_primitive_array::<Int32Type>(&casted),
+            &Int32Array::from(table.map(|t| t.1).to_vec()),
+        );
+
+        // string to double
+        let table = [
+            ("1.5", Some(1.5)),
+            (" 1e10 ", Some(1e10)),
+            ("1.5d", Some(1.5)),
+            ("-.5", Some(-0.5)),
+            ("inf", Some(f64::INFINITY)),
+            ("-Infinity", Some(f64::NEG_INFINITY)),
+            ("0x10", None),
+            ("abc", None),
+        ];
+        let casted = cast_strings(&table.map(|t| t.0), &DataType::Float64, utc);
+        assert_eq!(
+            as_primitive_array::<Float64Type>(&casted),
+            &Float64Array::from(table.map(|t| t.1).to_vec()),
+        );
+        let casted = cast_strings(&["NaN"], &DataType::Float64, utc);
+        assert!(as_primitive_array::<Float64Type>(&casted).value(0).is_nan());
+
+        // double/float to string
+        let table = [
+            (1.0, "1.0"),
+            (123.456, "123.456"),
+            (1234567.0, "1234567.0"),
+            (1e7, "1.0E7"),
+            (1e10, "1.0E10"),
+            (0.001, "0.001"),
+            (0.0001, "1.0E-4"),
+            (-1.5e-10, "-1.5E-10"),
+            (-0.0, "-0.0"),
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "Infinity"),
+        ];
+        let array: ArrayRef = Arc::new(Float64Array::from(table.map(|t| t.0).to_vec()));
+        assert_eq!(
+            to_strings(array, utc),
+            table.map(|t| Some(t.1.to_string())).to_vec()
+        );
+        let array: ArrayRef = Arc::new(Float32Array::from(vec![1.1f32, 3.4028235e38, 1e-5]));
+        assert_eq!(
+            to_strings(array, utc),
+            vec![
+                Some("1.1".to_string()),
+                Some("3.4028235E38".to_string()),
+                Some("1.0E-5".to_string()),
+            ]
+        );
+
+        // string to date
+        let table = [
+            ("2020-01-01", Some(18262)),
+            (" 2020-1-1 ", Some(18262)),
+            ("2020-01", Some(18262)),
+            ("2020", Some(18262)),
+            ("2020-01-01T12:34:56", Some(18262)),
+            ("2020-01-01 abc", Some(18262)),
+            ("1969-12-31", Some(-1)),
+            ("2020-02-30", None),
+            ("20-01-01", None),
+            ("2020/01/01", None),
+            ("", None),
+        ];
+        let casted = cast_strings(&table.map(|t| t.0), &DataType::Date32, utc);
+        assert_eq!(
+            as_primitive_array::<Date32Type>(&casted),
+            &Date32Array::from(table.map(|t| t.1).to_vec()),
+        );
+
+        // date to string
+        let array: ArrayRef = Arc::new(Date32Array::from(vec![18262, 0, -1, -719893]));
+        assert_eq!(
+            to_strings(array, utc),
+            vec![
+                Some("2020-01-01".to_string()),
+                Some("1970-01-01".to_string()),
+                Some("1969-12-31".to_string()),
+                Some("-0001-01-01".to_string()),
+            ]
+        );
+
+        // string to timestamp
+        let table = [
+            ("2020-01-01 00:00:00", utc, Some(1577836800000000)),
+            ("2020-01-01 00:00:00", la, Some(1577865600000000)),
+            ("2020-01-01", la, Some(1577865600000000)),
+            ("2020-01-01T00:00:00Z", la, Some(1577836800000000)),
+            ("2020-01-01 00:00:00+08:00", la, Some(1577808000000000)),
+            ("2020-01-01 00:00:00 UTC", la, Some(1577836800000000)),
+            ("2020-01-01 12:00", utc, Some(1577880000000000)),
+            ("2020-01-01 00:00:00.123456", utc, Some(1577836800123456)),
+            ("2020-01-01 00:00:00.1234567", utc, Some(1577836800123456)),
+            (" 2020-01-01 00:00:00.1 ", utc, Some(1577836800100000)),
+            ("2020-13-01", utc, None),
+            ("2020-01-01 25:00:00", utc, None),
+            ("2020-01-01 00:00:00 Mars/Olympus", utc, None),
+            ("abc", utc, None),
+        ];
+        for (value, zone, expected) in table {
+            let casted = cast_strings(&[value], &ts_type, zone);
+            assert_eq!(
+                as_primitive_array::<TimestampMicrosecondType>(&casted),
+                &TimestampMicrosecondArray::from(vec![expected]),
+                "casting {value:?} to timestamp",
+            );
+        }
+
+        // timestamp to string
+        let array: ArrayRef = Arc::new(TimestampMicrosecondArray::from(vec![
+            1577836800000000,
+            1577836800123000,
+            1577836800000001,
+        ]));
+        assert_eq!(
+            to_strings(array.clone(), utc),
+            vec![
+                Some("2020-01-01 00:00:00".to_string()),
+                Some("2020-01-01 00:00:00.123".to_string()),
+                Some("2020-01-01 00:00:00.000001".to_string()),
+            ]
+        );
+        assert_eq!(
+            to_strings(array, la)[0].as_deref(),
+            Some("2019-12-31 16:00:00")
+        );
+
+        // timestamp to date and date to timestamp
+        let array: ArrayRef = Arc::new(TimestampMicrosecondArray::from(vec![1577836800000000]));
+        let casted = cast_with_zone(&array, &DataType::Date32, false, la).unwrap();
+        assert_eq!(
+            as_primitive_array::<Date32Type>(&casted),
+            &Date32Array::from(vec![18261]),
+        );
+        let array: ArrayRef = Arc::new(Date32Array::from(vec![18262]));
+        let casted = cast_with_zone(&array, &ts_type, false, la).unwrap();
+        assert_eq!(
+            as_primitive_array::<TimestampMicrosecondType>(&casted),
+            &TimestampMicrosecondArray::from(vec![1577865600000000]),
+        );
+    }
 }
@@ -0,0 +1,295 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checksums of shuffle partitions, compatible with spark's shuffle checksums
+//! (`spark.shuffle.checksum.enabled`, since spark 3.2). checksums are computed
+//! over the bytes of each partition in the data file, and a checksum file
+//! contains the checksums of all partitions as big-endian longs.
+
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufWriter, Error as IoError, ErrorKind, Read, Write},
+};
+
+use datafusion::common::Result;
+use flate2::Crc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Adler32,
+    Crc32,
+}
+
+impl ChecksumAlgorithm {
+    /// parses algorithm name used in `spark.shuffle.checksum.algorithm`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "ADLER32" => Some(ChecksumAlgorithm::Adler32),
+            "CRC32" => Some(ChecksumAlgorithm::Crc32),
+            _ => None,
+        }
+    }
+}
+
+const ADLER32_MOD: u32 = 65521;
+
+// max number of bytes to process before reducing adler32 sums without overflow
+const ADLER32_NMAX: usize = 5552;
+
+/// checksum with the same value as java's `java.util.zip.Checksum`
+pub enum Checksum {
+    Adler32 { a: u32, b: u32 },
+    Crc32(Crc),
+}
+
+impl Checksum {
+    pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Adler32 => Checksum::Adler32 { a: 1, b: 0 },
+            ChecksumAlgorithm::Crc32 => Checksum::Crc32(Crc::new()),
+        }
+    }
+
+    pub fn update(&mut self, buf: &[u8]) {
+        match self {
+            Checksum::Adler32 { a, b } => {
+                for chunk in buf.chunks(ADLER32_NMAX) {
+                    for &byte in chunk {
+                        *a += byte as u32;
+                        *b += *a;
+                    }
+                    *a %= ADLER32_MOD;
+                    *b %= ADLER32_MOD;
+                }
+            }
+            Checksum::Crc32(crc) => crc.update(buf),
+        }
+    }
+
+    pub fn value(&self) -> u64 {
+        match self {
+            Checksum::Adler32 { a, b } => ((*b as u64) << 16) | *a as u64,
+            Checksum::Crc32(crc) => crc.sum() as u64,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        match self {
+            Checksum::Adler32 { a, b } => {
+                *a = 1;
+                *b = 0;
+            }
+            Checksum::Crc32(crc) => crc.reset(),
+        }
+    }
+}
+
+/// checksums of all partitions of a shuffle output
+pub struct PartitionChecksums {
+    checksums: Vec<Checksum>,
+}
+
+impl PartitionChecksums {
+    pub fn new(algorithm: ChecksumAlgorithm, num_partitions: usize) -> Self {
+        Self {
+            checksums: (0..num_partitions)
+                .map(|_| Checksum::new(algorithm))
+                .collect(),
+        }
+    }
+
+    pub fn update(&mut self, partition_id: usize, buf: &[u8]) {
+        self.checksums[partition_id].update(buf);
+    }
+
+    /// wraps a writer, bytes written through it are added to the partition
+    pub fn writer<W: Write>(&mut self, partition_id: usize, inner: W) -> ChecksumWriter<W> {
+        ChecksumWriter {
+            inner,
+            checksum: &mut self.checksums[partition_id],
+        }
+    }
+
+    pub fn values(&self) -> Vec<u64> {
+        self.checksums
+            .iter()
+            .map(|checksum| checksum.value())
+            .collect()
+    }
+
+    /// writes checksums in spark's checksum file format
+    pub fn write_checksum_file(&self, path: &str) -> Result<()> {
+        let mut output = BufWriter::new(File::create(path)?);
+        for value in self.values() {
+            output.write_all(&(value as i64).to_be_bytes())?;
+        }
+        let output = output.into_inner().map_err(|e| e.into_error())?;
+        output.sync_data()?;
+        Ok(())
+    }
+}
+
+pub struct ChecksumWriter<'a, W: Write> {
+    inner: W,
+    checksum: &'a mut Checksum,
+}
+
+impl<W: Write> Write for ChecksumWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let num_written = self.inner.write(buf)?;
+        self.checksum.update(&buf[..num_written]);
+        Ok(num_written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// reader of consecutive shuffle partitions, verifying the checksum of each
+/// partition after it is fully read
+pub struct ChecksumVerifyReader<R: Read> {
+    inner: R,
+    checksum: Checksum,
+
+    /// lengths and expected checksums of the partitions not yet verified
+    partitions: VecDeque<(u64, u64)>,
+    num_read: u64,
+    location: String,
+}
+
+impl<R: Read> ChecksumVerifyReader<R> {
+    pub fn new(
+        inner: R,
+        algorithm: ChecksumAlgorithm,
+        partitions: impl IntoIterator<Item = (u64, u64)>,
+        location: String,
+    ) -> Self {
+        Self {
+            inner,
+            checksum: Checksum::new(algorithm),
+            // empty partitions are not read, their checksums are always the initial values
+            partitions: partitions
+                .into_iter()
+                .filter(|&(length, _)| length > 0)
+                .collect(),
+            num_read: 0,
+            location,
+        }
+    }
+}
+
+impl<R: Read> Read for ChecksumVerifyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let num_read = self.inner.read(buf)?;
+        let mut data = &buf[..num_read];
+
+        while let Some(&(length, expected)) = self.partitions.front() {
+            if data.is_empty() {
+                break;
+            }
+            let num_consumed = (length - self.num_read).min(data.len() as u64) as usize;
+            self.checksum.update(&data[..num_consumed]);
+            self.num_read += num_consumed as u64;
+            data = &data[num_consumed..];
+
+            if self.num_read == length {
+                let actual = self.checksum.value();
+                if actual != expected {
+                    return Err(IoError::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "shuffle block checksum mismatch in {}: expected {expected}, \
+                                actual {actual}, data may be corrupted",
+                            self.location,
+                        ),
+                    ));
+                }
+                self.checksum.reset();
+                self.num_read = 0;
+                self.partitions.pop_front();
+            }
+        }
+        Ok(num_read)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, Read, Write};
+
+    use crate::checksum::{Checksum, ChecksumAlgorithm, ChecksumVerifyReader, PartitionChecksums};
+
+    #[test]
+    fn test_checksum() {
+        // expected values are computed by java.util.zip.Adler32/CRC32
+        let data = b"Wikipedia";
+        let mut adler32 = Checksum::new(ChecksumAlgorithm::Adler32);
+        assert_eq!(adler32.value(), 1);
+        adler32.update(data);
+        assert_eq!(adler32.value(), 0x11E60398);
+
+        let mut crc32 = Checksum::new(ChecksumAlgorithm::Crc32);
+        assert_eq!(crc32.value(), 0);
+        crc32.update(b"123456789");
+        assert_eq!(crc32.value(), 0xCBF43926);
+        crc32.reset();
+        assert_eq!(crc32.value(), 0);
+
+        // large inputs are reduced without overflow
+        let data = vec![255u8; 100000];
+        let mut chunked = Checksum::new(ChecksumAlgorithm::Adler32);
+        data.chunks(7).for_each(|chunk| chunked.update(chunk));
+        let mut whole = Checksum::new(ChecksumAlgorithm::Adler32);
+        whole.update(&data);
+        assert_eq!(chunked.value(), whole.value());
+    }
+
+    #[test]
+    fn test_checksum_verify_reader() -> std::io::Result<()> {
+        let partitions: [&[u8]; 3] = [b"hello", b"", b"shuffle"];
+        let mut checksums = PartitionChecksums::new(ChecksumAlgorithm::Crc32, partitions.len());
+        let mut data = vec![];
+        for (partition_id, bytes) in partitions.iter().enumerate() {
+            checksums.writer(partition_id, &mut data).write_all(bytes)?;
+        }
+        let expected = partitions
+            .iter()
+            .map(|bytes| bytes.len() as u64)
+            .zip(checksums.values())
+            .collect::<Vec<_>>();
+
+        let mut reader = ChecksumVerifyReader::new(
+            Cursor::new(data.clone()),
+            ChecksumAlgorithm::Crc32,
+            expected.clone(),
+            "test".to_string(),
+        );
+        let mut output = vec![];
+        reader.read_to_end(&mut output)?;
+        assert_eq!(output, data);
+
+        // corrupted data
+        data[6] ^= 1;
+        let mut reader = ChecksumVerifyReader::new(
+            Cursor::new(data),
+            ChecksumAlgorithm::Crc32,
+            expected,
+            "test".to_string(),
+        );
+        assert!(reader.read_to_end(&mut vec![]).is_err());
+        Ok(())
+    }
+}
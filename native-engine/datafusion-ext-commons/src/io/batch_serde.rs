@@ -13,11 +13,8 @@
 // limitations under the License.
 
 use std::{
-    io::{BufReader, BufWriter, Read, Write},
-    sync::{
-        atomic::{AtomicUsize, Ordering::SeqCst},
-        Arc,
-    },
+    io::{Read, Write},
+    sync::Arc,
 };
 
 use arrow::{
@@ -31,23 +28,33 @@ use datafusion::{common::Result, parquet::data_type::AsBytes, scalar::ScalarValu
 
 use crate::{
     df_execution_err, df_unimplemented_err,
-    io::{read_bytes_slice, read_len, read_u8, write_len, write_u8},
+    io::{
+        column_encoding::{read_encoded_array, write_encoded_array},
+        compression::{IoCompressionReader, IoCompressionWriter},
+        read_bytes_slice, read_len, read_u8, write_len, write_u8, IoCompressionCodec,
+    },
 };
 
+/// latest version of the batch serde format, all versions up to it are readable:
+/// - 0: all columns are written as plain buffers
+/// - 1: top-level columns are written with adaptive encodings
+pub const BATCH_SERDE_VERSION: u8 = 1;
+
 pub fn write_batch<W: Write>(
     batch: &RecordBatch,
     output: &mut W,
-    compress: bool,
+    codec: IoCompressionCodec,
+    version: u8,
     uncompressed_size: Option<&mut usize>,
 ) -> Result<()> {
     struct CountWriter<W: Write> {
-        num_bytes_written: Arc<AtomicUsize>,
+        num_bytes_written: usize,
         inner: W,
     }
     impl<W: Write> Write for CountWriter<W> {
         fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
             let bytes_written = self.inner.write(buf)?;
-            self.num_bytes_written.fetch_add(bytes_written, SeqCst);
+            self.num_bytes_written += bytes_written;
             Ok(bytes_written)
         }
 
@@ -56,27 +63,9 @@ pub fn write_batch<W: Write>(
         }
     }
 
-    let num_bytes_written_uncompressed = Arc::new(AtomicUsize::new(0));
-    let mut output: Box<dyn Write> = if compress {
-        let w = zstd::Encoder::new(output, 1)?.auto_finish();
-        if uncompressed_size.is_some() {
-            Box::new(CountWriter {
-                num_bytes_written: num_bytes_written_uncompressed.clone(),
-                inner: w,
-            })
-        } else {
-            Box::new(w)
-        }
-    } else {
-        let w = BufWriter::new(output);
-        if uncompressed_size.is_some() {
-            Box::new(CountWriter {
-                num_bytes_written: num_bytes_written_uncompressed.clone(),
-                inner: w,
-            })
-        } else {
-            Box::new(w)
-        }
+    let mut output = CountWriter {
+        num_bytes_written: 0,
+        inner: IoCompressionWriter::try_new(codec, output)?,
     };
 
     let schema = batch.schema();
@@ -104,7 +93,12 @@ pub fn write_batch<W: Write>(
 
     // write columns
     for column in batch.columns() {
-        write_array(column, &mut output).map_err(|err| {
+        let written = if version >= 1 {
+            write_encoded_array(column, &mut output)
+        } else {
+            write_array(column, &mut output)
+        };
+        written.map_err(|err| {
             err.context(format!(
                 "batch_serde error writing column (data_type={})",
                 column.data_type()
@@ -112,17 +106,18 @@ pub fn write_batch<W: Write>(
         })?;
     }
     if let Some(uncompressed_size) = uncompressed_size {
-        *uncompressed_size = num_bytes_written_uncompressed.load(SeqCst);
+        *uncompressed_size = output.num_bytes_written;
     }
+    output.inner.finish()?;
     Ok(())
 }
 
-pub fn read_batch<R: Read>(input: &mut R, compress: bool) -> Result<RecordBatch> {
-    let mut input: Box<dyn Read> = if compress {
-        Box::new(BufReader::new(zstd::Decoder::new(input)?))
-    } else {
-        Box::new(BufReader::new(input))
-    };
+pub fn read_batch<R: Read>(
+    input: &mut R,
+    codec: IoCompressionCodec,
+    version: u8,
+) -> Result<RecordBatch> {
+    let mut input = IoCompressionReader::try_new(codec, input)?;
 
     // read number of columns and rows
     let num_columns = read_len(&mut input)?;
@@ -153,7 +148,12 @@ pub fn read_batch<R: Read>(input: &mut R, compress: bool) -> Result<RecordBatch>
     // read columns
     let columns = (0..num_columns)
         .map(|i| {
-            read_array(&mut input, &data_types[i], num_rows).map_err(|err| {
+            let array = if version >= 1 {
+                read_encoded_array(&mut input, &data_types[i], num_rows)
+            } else {
+                read_array(&mut input, &data_types[i], num_rows)
+            };
+            array.map_err(|err| {
                 err.context(format!(
                     "batch_serde error reading column (data_type={}, num_rows={})",
                     data_types[i], num_rows,
@@ -193,17 +193,63 @@ pub fn write_array<W: Write>(array: &dyn Array, output: &mut W) -> Result<()> {
         DataType::Float32 => write_primitive!(Float32),
         DataType::Float64 => write_primitive!(Float64),
         DataType::Decimal128(..) => write_primitive!(Decimal128),
+        DataType::Decimal256(..) => write_primitive!(Decimal256),
         DataType::Utf8 => write_bytes_array(as_string_array(array), output)?,
+        DataType::LargeUtf8 => write_bytes_array(as_largestring_array(array), output)?,
         DataType::Binary => write_bytes_array(as_generic_binary_array::<i32>(array), output)?,
+        DataType::LargeBinary => write_bytes_array(as_generic_binary_array::<i64>(array), output)?,
+        DataType::FixedSizeBinary(_) => write_fixed_size_binary_array(
+            array
+                .as_any()
+                .downcast_ref::<FixedSizeBinaryArray>()
+                .unwrap(),
+            output,
+        )?,
         DataType::Date32 => write_primitive!(Date32),
         DataType::Date64 => write_primitive!(Date64),
+        DataType::Time32(TimeUnit::Second) => write_primitive!(Time32Second),
+        DataType::Time32(TimeUnit::Millisecond) => write_primitive!(Time32Millisecond),
+        DataType::Time64(TimeUnit::Microsecond) => write_primitive!(Time64Microsecond),
+        DataType::Time64(TimeUnit::Nanosecond) => write_primitive!(Time64Nanosecond),
+        DataType::Duration(TimeUnit::Second) => write_primitive!(DurationSecond),
+        DataType::Duration(TimeUnit::Millisecond) => write_primitive!(DurationMillisecond),
+        DataType::Duration(TimeUnit::Microsecond) => write_primitive!(DurationMicrosecond),
+        DataType::Duration(TimeUnit::Nanosecond) => write_primitive!(DurationNanosecond),
+        DataType::Interval(IntervalUnit::YearMonth) => write_primitive!(IntervalYearMonth),
+        DataType::Interval(IntervalUnit::DayTime) => write_primitive!(IntervalDayTime),
+        DataType::Interval(IntervalUnit::MonthDayNano) => write_primitive!(IntervalMonthDayNano),
         DataType::Timestamp(TimeUnit::Second, _) => write_primitive!(TimestampSecond),
         DataType::Timestamp(TimeUnit::Millisecond, _) => write_primitive!(TimestampMillisecond),
         DataType::Timestamp(TimeUnit::Microsecond, _) => write_primitive!(TimestampMicrosecond),
         DataType::Timestamp(TimeUnit::Nanosecond, _) => write_primitive!(TimestampNanosecond),
         DataType::List(_field) => write_list_array(as_list_array(array), output)?,
+        DataType::LargeList(_field) => write_list_array(as_large_list_array(array), output)?,
+        DataType::FixedSizeList(..) => {
+            write_fixed_size_list_array(as_fixed_size_list_array(array), output)?
+        }
         DataType::Map(..) => write_map_array(as_map_array(array), output)?,
         DataType::Struct(_) => write_struct_array(as_struct_array(array), output)?,
+        DataType::Dictionary(key_type, _) => {
+            macro_rules! write_dictionary {
+                ($keyty:ident) => {{
+                    write_dictionary_array(
+                        as_dictionary_array::<paste::paste! {[<$keyty Type>]}>(array),
+                        output,
+                    )?
+                }};
+            }
+            match key_type.as_ref() {
+                DataType::Int8 => write_dictionary!(Int8),
+                DataType::Int16 => write_dictionary!(Int16),
+                DataType::Int32 => write_dictionary!(Int32),
+                DataType::Int64 => write_dictionary!(Int64),
+                DataType::UInt8 => write_dictionary!(UInt8),
+                DataType::UInt16 => write_dictionary!(UInt16),
+                DataType::UInt32 => write_dictionary!(UInt32),
+                DataType::UInt64 => write_dictionary!(UInt64),
+                other => df_unimplemented_err!("unsupported dictionary key type: {other}")?,
+            }
+        }
         other => df_unimplemented_err!("unsupported data type: {other}")?,
     }
     Ok(())
@@ -237,19 +283,62 @@ pub fn read_array<R: Read>(
                 .clone()
                 .with_precision_and_scale(*prec, *scale)?,
         ),
+        DataType::Decimal256(prec, scale) => Arc::new(
+            as_primitive_array::<Decimal256Type>(&read_primitive!(Decimal256))
+                .clone()
+                .with_precision_and_scale(*prec, *scale)?,
+        ),
         DataType::Date32 => read_primitive!(Date32),
         DataType::Date64 => read_primitive!(Date64),
+        DataType::Time32(TimeUnit::Second) => read_primitive!(Time32Second),
+        DataType::Time32(TimeUnit::Millisecond) => read_primitive!(Time32Millisecond),
+        DataType::Time64(TimeUnit::Microsecond) => read_primitive!(Time64Microsecond),
+        DataType::Time64(TimeUnit::Nanosecond) => read_primitive!(Time64Nanosecond),
+        DataType::Duration(TimeUnit::Second) => read_primitive!(DurationSecond),
+        DataType::Duration(TimeUnit::Millisecond) => read_primitive!(DurationMillisecond),
+        DataType::Duration(TimeUnit::Microsecond) => read_primitive!(DurationMicrosecond),
+        DataType::Duration(TimeUnit::Nanosecond) => read_primitive!(DurationNanosecond),
+        DataType::Interval(IntervalUnit::YearMonth) => read_primitive!(IntervalYearMonth),
+        DataType::Interval(IntervalUnit::DayTime) => read_primitive!(IntervalDayTime),
+        DataType::Interval(IntervalUnit::MonthDayNano) => read_primitive!(IntervalMonthDayNano),
         DataType::Timestamp(TimeUnit::Second, _) => read_primitive!(TimestampSecond),
         DataType::Timestamp(TimeUnit::Millisecond, _) => read_primitive!(TimestampMillisecond),
         DataType::Timestamp(TimeUnit::Microsecond, _) => read_primitive!(TimestampMicrosecond),
         DataType::Timestamp(TimeUnit::Nanosecond, _) => read_primitive!(TimestampNanosecond),
         DataType::Utf8 => read_bytes_array(num_rows, input, DataType::Utf8)?,
+        DataType::LargeUtf8 => read_bytes_array(num_rows, input, DataType::LargeUtf8)?,
         DataType::Binary => read_bytes_array(num_rows, input, DataType::Binary)?,
-        DataType::List(list_field) => read_list_array(num_rows, input, list_field)?,
+        DataType::LargeBinary => read_bytes_array(num_rows, input, DataType::LargeBinary)?,
+        DataType::FixedSizeBinary(size) => read_fixed_size_binary_array(num_rows, input, *size)?,
+        DataType::List(list_field) => read_list_array(num_rows, input, data_type, list_field)?,
+        DataType::LargeList(list_field) => read_list_array(num_rows, input, data_type, list_field)?,
+        DataType::FixedSizeList(list_field, size) => {
+            read_fixed_size_list_array(num_rows, input, list_field, *size)?
+        }
         DataType::Map(map_field, is_sorted) => {
             read_map_array(num_rows, input, map_field, *is_sorted)?
         }
         DataType::Struct(fields) => read_struct_array(num_rows, input, fields)?,
+        DataType::Dictionary(key_type, value_type) => {
+            macro_rules! read_dictionary {
+                ($keyty:ident) => {{
+                    read_dictionary_array::<_, paste::paste! {[<$keyty Type>]}>(
+                        num_rows, input, value_type,
+                    )?
+                }};
+            }
+            match key_type.as_ref() {
+                DataType::Int8 => read_dictionary!(Int8),
+                DataType::Int16 => read_dictionary!(Int16),
+                DataType::Int32 => read_dictionary!(Int32),
+                DataType::Int64 => read_dictionary!(Int64),
+                DataType::UInt8 => read_dictionary!(UInt8),
+                DataType::UInt16 => read_dictionary!(UInt16),
+                DataType::UInt32 => read_dictionary!(UInt32),
+                DataType::UInt64 => read_dictionary!(UInt64),
+                other => df_unimplemented_err!("unsupported dictionary key type: {other}")?,
+            }
+        }
         other => df_unimplemented_err!("unsupported data type: {other}")?,
     })
 }
@@ -292,6 +381,13 @@ fn nameless_field(field: &Field) -> Field {
 fn nameless_data_type(data_type: &DataType) -> DataType {
     match data_type {
         DataType::List(field) => DataType::List(Arc::new(nameless_field(field))),
+        DataType::LargeList(field) => DataType::LargeList(Arc::new(nameless_field(field))),
+        DataType::FixedSizeList(field, size) => {
+            DataType::FixedSizeList(Arc::new(nameless_field(field)), *size)
+        }
+        DataType::Dictionary(key_type, value_type) => {
+            DataType::Dictionary(key_type.clone(), Box::new(nameless_data_type(value_type)))
+        }
         DataType::Map(field, sorted) => DataType::Map(Arc::new(nameless_field(field)), *sorted),
         DataType::Struct(fields) => {
             DataType::Struct(fields.iter().map(|field| nameless_field(field)).collect())
@@ -368,7 +464,35 @@ fn read_primitive_array<R: Read, PT: ArrowPrimitiveType>(
     Ok(make_array(array_data))
 }
 
-fn write_list_array<W: Write>(array: &ListArray, output: &mut W) -> Result<()> {
+fn read_offsets_buffer<R: Read>(
+    num_rows: usize,
+    input: &mut R,
+    is_large: bool,
+) -> Result<(Buffer, usize)> {
+    let offset_size = if is_large { 8 } else { 4 };
+    let mut cur_offset = 0;
+    let mut offsets_buffer = MutableBuffer::new((num_rows + 1) * offset_size);
+    let mut push_offset = |offset: usize| {
+        if is_large {
+            offsets_buffer.push(offset as u64);
+        } else {
+            offsets_buffer.push(offset as u32);
+        }
+    };
+    push_offset(0);
+    for _ in 0..num_rows {
+        let len = read_len(input)?;
+        let offset = cur_offset + len;
+        push_offset(offset);
+        cur_offset = offset;
+    }
+    Ok((offsets_buffer.into(), cur_offset))
+}
+
+fn write_list_array<W: Write, O: OffsetSizeTrait>(
+    array: &GenericListArray<O>,
+    output: &mut W,
+) -> Result<()> {
     if let Some(null_buffer) = array.to_data().nulls() {
         write_len(1, output)?;
         write_bits_buffer(
@@ -383,12 +507,12 @@ fn write_list_array<W: Write>(array: &ListArray, output: &mut W) -> Result<()> {
 
     let value_offsets = array.value_offsets();
     for (beg, end) in value_offsets.iter().zip(&value_offsets[1..]) {
-        let len = end - beg;
-        write_len(len as usize, output)?;
+        let len = end.as_usize() - beg.as_usize();
+        write_len(len, output)?;
     }
     let values = array.values().slice(
-        value_offsets[0] as usize,
-        value_offsets[array.len()] as usize - value_offsets[0] as usize,
+        value_offsets[0].as_usize(),
+        value_offsets[array.len()].as_usize() - value_offsets[0].as_usize(),
     );
     write_array(&values, output)?;
     Ok(())
@@ -397,6 +521,7 @@ fn write_list_array<W: Write>(array: &ListArray, output: &mut W) -> Result<()> {
 fn read_list_array<R: Read>(
     num_rows: usize,
     input: &mut R,
+    data_type: &DataType,
     list_field: &FieldRef,
 ) -> Result<ArrayRef> {
     let has_null_buffer = read_len(input)? == 1;
@@ -406,21 +531,12 @@ fn read_list_array<R: Read>(
         None
     };
 
-    let mut cur_offset = 0;
-    let mut offsets_buffer = MutableBuffer::new((num_rows + 1) * 4);
-    offsets_buffer.push(0u32);
-    for _ in 0..num_rows {
-        let len = read_len(input)?;
-        let offset = cur_offset + len;
-        offsets_buffer.push(offset as u32);
-        cur_offset = offset;
-    }
-    let offsets_buffer: Buffer = offsets_buffer.into();
-    let values_len = cur_offset;
+    let is_large = matches!(data_type, DataType::LargeList(_));
+    let (offsets_buffer, values_len) = read_offsets_buffer(num_rows, input, is_large)?;
     let values = read_array(input, list_field.data_type(), values_len)?;
 
     let array_data = ArrayData::try_new(
-        DataType::List(list_field.clone()),
+        data_type.clone(),
         num_rows,
         null_buffer,
         0,
@@ -430,6 +546,54 @@ fn read_list_array<R: Read>(
     Ok(make_array(array_data))
 }
 
+fn write_fixed_size_list_array<W: Write>(array: &FixedSizeListArray, output: &mut W) -> Result<()> {
+    if let Some(null_buffer) = array.to_data().nulls() {
+        write_len(1, output)?;
+        write_bits_buffer(
+            null_buffer.buffer(),
+            null_buffer.offset(),
+            null_buffer.len(),
+            output,
+        )?;
+    } else {
+        write_len(0, output)?;
+    }
+
+    let values = array.values().slice(
+        array.value_offset(0) as usize,
+        array.len() * array.value_length() as usize,
+    );
+    write_array(&values, output)?;
+    Ok(())
+}
+
+fn read_fixed_size_list_array<R: Read>(
+    num_rows: usize,
+    input: &mut R,
+    list_field: &FieldRef,
+    size: i32,
+) -> Result<ArrayRef> {
+    let has_null_buffer = read_len(input)? == 1;
+    let null_buffer: Option<Buffer> = if has_null_buffer {
+        Some(read_bits_buffer(input, num_rows)?)
+    } else {
+        None
+    };
+
+    let values_len = num_rows * size as usize;
+    let values = read_array(input, list_field.data_type(), values_len)?;
+
+    let array_data = ArrayData::try_new(
+        DataType::FixedSizeList(list_field.clone(), size),
+        num_rows,
+        null_buffer,
+        0,
+        vec![],
+        vec![values.into_data()],
+    )?;
+    Ok(make_array(array_data))
+}
+
 fn write_map_array<W: Write>(array: &MapArray, output: &mut W) -> Result<()> {
     let array_data = array.to_data();
     if let Some(null_buffer) = array_data.nulls() {
@@ -562,6 +726,30 @@ fn read_struct_array<R: Read>(num_rows: usize, input: &mut R, fields: &Fields) -
     Ok(make_array(array_data))
 }
 
+fn write_dictionary_array<W: Write, K: ArrowDictionaryKeyType>(
+    array: &DictionaryArray<K>,
+    output: &mut W,
+) -> Result<()> {
+    write_primitive_array(array.keys(), output)?;
+    write_len(array.values().len(), output)?;
+    write_array(array.values(), output)?;
+    Ok(())
+}
+
+fn read_dictionary_array<R: Read, K: ArrowDictionaryKeyType>(
+    num_rows: usize,
+    input: &mut R,
+    value_type: &DataType,
+) -> Result<ArrayRef> {
+    let keys = read_primitive_array::<_, K>(num_rows, input)?;
+    let values_len = read_len(input)?;
+    let values = read_array(input, value_type, values_len)?;
+    Ok(Arc::new(DictionaryArray::<K>::try_new(
+        as_primitive_array::<K>(&keys).clone(),
+        values,
+    )?))
+}
+
 fn write_boolean_array<W: Write>(array: &BooleanArray, output: &mut W) -> Result<()> {
     let array_data = array.to_data();
     if let Some(null_buffer) = array_data.nulls() {
@@ -603,7 +791,7 @@ fn read_boolean_array<R: Read>(num_rows: usize, input: &mut R) -> Result<ArrayRe
     Ok(make_array(array_data))
 }
 
-fn write_bytes_array<T: ByteArrayType<Offset = i32>, W: Write>(
+fn write_bytes_array<T: ByteArrayType, W: Write>(
     array: &GenericByteArray<T>,
     output: &mut W,
 ) -> Result<()> {
@@ -622,11 +810,11 @@ fn write_bytes_array<T: ByteArrayType<Offset = i32>, W: Write>(
     let first_offset = array.value_offsets().first().cloned().unwrap_or_default();
     let mut cur_offset = first_offset;
     for &offset in array.value_offsets().iter().skip(1) {
-        let len = offset - cur_offset;
-        write_len(len as usize, output)?;
+        let len = offset.as_usize() - cur_offset.as_usize();
+        write_len(len, output)?;
         cur_offset = offset;
     }
-    output.write_all(&array.value_data()[first_offset as usize..cur_offset as usize])?;
+    output.write_all(&array.value_data()[first_offset.as_usize()..cur_offset.as_usize()])?;
     Ok(())
 }
 
@@ -642,18 +830,8 @@ fn read_bytes_array<R: Read>(
         None
     };
 
-    let mut cur_offset = 0;
-    let mut offsets_buffer = MutableBuffer::new((num_rows + 1) * 4);
-    offsets_buffer.push(0u32);
-    for _ in 0..num_rows {
-        let len = read_len(input)?;
-        let offset = cur_offset + len;
-        offsets_buffer.push(offset as u32);
-        cur_offset = offset;
-    }
-    let offsets_buffer: Buffer = offsets_buffer.into();
-
-    let data_len = cur_offset;
+    let is_large = matches!(data_type, DataType::LargeUtf8 | DataType::LargeBinary);
+    let (offsets_buffer, data_len) = read_offsets_buffer(num_rows, input, is_large)?;
     let data_buffer = Buffer::from(read_bytes_slice(input, data_len)?);
     let array_data = ArrayData::try_new(
         data_type,
@@ -666,16 +844,65 @@ fn read_bytes_array<R: Read>(
     Ok(make_array(array_data))
 }
 
+fn write_fixed_size_binary_array<W: Write>(
+    array: &FixedSizeBinaryArray,
+    output: &mut W,
+) -> Result<()> {
+    let item_size = array.value_length() as usize;
+    let array_data = array.to_data();
+    if let Some(null_buffer) = array_data.nulls() {
+        write_len(1, output)?;
+        write_bits_buffer(
+            null_buffer.buffer(),
+            null_buffer.offset(),
+            null_buffer.len(),
+            output,
+        )?;
+    } else {
+        write_len(0, output)?;
+    }
+    output.write_all(
+        &array_data.buffers()[0].as_slice()[item_size * array_data.offset()..]
+            [..item_size * array.len()],
+    )?;
+    Ok(())
+}
+
+fn read_fixed_size_binary_array<R: Read>(
+    num_rows: usize,
+    input: &mut R,
+    size: i32,
+) -> Result<ArrayRef> {
+    let has_null_buffer = read_len(input)? == 1;
+    let null_buffer: Option<Buffer> = if has_null_buffer {
+        Some(read_bits_buffer(input, num_rows)?)
+    } else {
+        None
+    };
+
+    let data_buffer = Buffer::from(read_bytes_slice(input, num_rows * size as usize)?);
+    let array_data = ArrayData::try_new(
+        DataType::FixedSizeBinary(size),
+        num_rows,
+        null_buffer,
+        0,
+        vec![data_buffer],
+        vec![],
+    )?;
+    Ok(make_array(array_data))
+}
+
 #[cfg(test)]
 mod test {
     use std::{io::Cursor, sync::Arc};
 
     use arrow::{array::*, datatypes::*, record_batch::RecordBatch};
     use datafusion::assert_batches_eq;
+    use rand::Rng;
 
     use crate::io::{
-        batch_serde::{read_batch, write_batch},
-        name_batch,
+        batch_serde::{read_batch, write_batch, BATCH_SERDE_VERSION},
+        name_batch, IoCompressionCodec,
     };
 
     #[test]
@@ -707,17 +934,41 @@ mod test {
 
         // test read after write
         let mut buf = vec![];
-        write_batch(&batch, &mut buf, true, None).unwrap();
+        write_batch(
+            &batch,
+            &mut buf,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+            None,
+        )
+        .unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(
+            &mut cursor,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+        )
+        .unwrap();
         assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
 
         // test read after write sliced
         let sliced = batch.slice(1, 2);
         let mut buf = vec![];
-        write_batch(&sliced, &mut buf, true, None).unwrap();
+        write_batch(
+            &sliced,
+            &mut buf,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+            None,
+        )
+        .unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(
+            &mut cursor,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+        )
+        .unwrap();
         assert_eq!(name_batch(decoded_batch, &sliced.schema()).unwrap(), sliced);
     }
 
@@ -753,9 +1004,21 @@ mod test {
 
         // test read after write
         let mut buf = vec![];
-        write_batch(&batch, &mut buf, true, None).unwrap();
+        write_batch(
+            &batch,
+            &mut buf,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+            None,
+        )
+        .unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(
+            &mut cursor,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+        )
+        .unwrap();
         assert_batches_eq!(
             vec![
                 "+-----------+-----------+",
@@ -773,9 +1036,21 @@ mod test {
         // test read after write sliced
         let sliced = batch.slice(1, 2);
         let mut buf = vec![];
-        write_batch(&sliced, &mut buf, true, None).unwrap();
+        write_batch(
+            &sliced,
+            &mut buf,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+            None,
+        )
+        .unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(
+            &mut cursor,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+        )
+        .unwrap();
         assert_batches_eq!(
             vec![
                 "+----------+----------+",
@@ -817,17 +1092,41 @@ mod test {
 
         // test read after write
         let mut buf = vec![];
-        write_batch(&batch, &mut buf, true, None).unwrap();
+        write_batch(
+            &batch,
+            &mut buf,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+            None,
+        )
+        .unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(
+            &mut cursor,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+        )
+        .unwrap();
         assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
 
         // test read after write sliced
         let sliced = batch.slice(1, 2);
         let mut buf = vec![];
-        write_batch(&sliced, &mut buf, true, None).unwrap();
+        write_batch(
+            &sliced,
+            &mut buf,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+            None,
+        )
+        .unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(
+            &mut cursor,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+        )
+        .unwrap();
         assert_eq!(name_batch(decoded_batch, &sliced.schema()).unwrap(), sliced);
     }
 
@@ -849,17 +1148,185 @@ mod test {
 
         // test read after write
         let mut buf = vec![];
-        write_batch(&batch, &mut buf, true, None).unwrap();
+        write_batch(
+            &batch,
+            &mut buf,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+            None,
+        )
+        .unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(
+            &mut cursor,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+        )
+        .unwrap();
         assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
 
         // test read after write sliced
         let sliced = batch.slice(1, 2);
         let mut buf = vec![];
-        write_batch(&sliced, &mut buf, true, None).unwrap();
+        write_batch(
+            &sliced,
+            &mut buf,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+            None,
+        )
+        .unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(
+            &mut cursor,
+            IoCompressionCodec::default(),
+            BATCH_SERDE_VERSION,
+        )
+        .unwrap();
         assert_eq!(name_batch(decoded_batch, &sliced.schema()).unwrap(), sliced);
     }
+
+    #[test]
+    fn test_write_and_read_batch_of_all_versions() {
+        let batch = RecordBatch::try_from_iter_with_nullable(vec![
+            (
+                "str",
+                Arc::new(StringArray::from_iter(
+                    (0..2000).map(|i| (i % 5 != 0).then(|| format!("s{}", i % 3))),
+                )) as ArrayRef,
+                true,
+            ),
+            (
+                "i64",
+                Arc::new(Int64Array::from_iter_values(0..2000)) as ArrayRef,
+                false,
+            ),
+            (
+                "bool",
+                Arc::new(BooleanArray::from_iter((0..2000).map(|i| Some(i < 1500)))) as ArrayRef,
+                true,
+            ),
+        ])
+        .unwrap();
+
+        for version in 0..=BATCH_SERDE_VERSION {
+            let mut buf = vec![];
+            write_batch(&batch, &mut buf, IoCompressionCodec::None, version, None).unwrap();
+            let mut cursor = Cursor::new(buf);
+            let decoded_batch = read_batch(&mut cursor, IoCompressionCodec::None, version).unwrap();
+            assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
+        }
+    }
+
+    #[test]
+    fn test_write_and_read_batch_for_extended_types() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let num_rows = rng.gen_range(1..=500);
+            let mut gen_opt = |max: i64| -> Vec<Option<i64>> {
+                (0..num_rows)
+                    .map(|_| (!rng.gen_bool(0.1)).then(|| rng.gen_range(-max..=max)))
+                    .collect()
+            };
+            let ints = gen_opt(1000000);
+            let small_ints = gen_opt(10);
+            let list_nulls = gen_opt(1);
+
+            let large_strings =
+                LargeStringArray::from_iter(ints.iter().map(|v| v.map(|v| format!("s{v}"))));
+            let large_binaries = LargeBinaryArray::from_iter(
+                ints.iter().map(|v| v.map(|v| v.to_le_bytes().to_vec())),
+            );
+            let fixed_size_binaries = FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                ints.iter().map(|v| v.map(|v| (v as i32).to_le_bytes())),
+                4,
+            )
+            .unwrap();
+            let fixed_size_lists = FixedSizeListArray::from_iter_primitive::<Int32Type, _, _>(
+                list_nulls
+                    .iter()
+                    .zip(&small_ints)
+                    .map(|(valid, v)| valid.map(|_| vec![v.map(|v| v as i32), Some(1), None])),
+                3,
+            );
+            let large_lists = LargeListArray::from_iter_primitive::<Int64Type, _, _>(
+                list_nulls.iter().zip(&small_ints).map(|(valid, v)| {
+                    valid.map(|_| vec![*v; v.unwrap_or(0).unsigned_abs() as usize])
+                }),
+            );
+            let dictionary_values = small_ints
+                .iter()
+                .map(|v| v.map(|v| format!("d{v}")))
+                .collect::<Vec<_>>();
+            let dictionaries = dictionary_values
+                .iter()
+                .map(|v| v.as_deref())
+                .collect::<DictionaryArray<Int16Type>>();
+            let decimals = Decimal256Array::from_iter(
+                ints.iter().map(|v| v.map(|v| i256::from_i128(v as i128))),
+            )
+            .with_precision_and_scale(50, 10)
+            .unwrap();
+
+            let batch = RecordBatch::try_from_iter_with_nullable(vec![
+                ("large_str", Arc::new(large_strings) as ArrayRef, true),
+                ("large_bin", Arc::new(large_binaries) as ArrayRef, true),
+                ("fixed_bin", Arc::new(fixed_size_binaries) as ArrayRef, true),
+                ("fixed_list", Arc::new(fixed_size_lists) as ArrayRef, true),
+                ("large_list", Arc::new(large_lists) as ArrayRef, true),
+                ("dict", Arc::new(dictionaries) as ArrayRef, true),
+                ("dec256", Arc::new(decimals) as ArrayRef, true),
+                (
+                    "interval_ym",
+                    Arc::new(IntervalYearMonthArray::from_iter(
+                        small_ints.iter().map(|v| v.map(|v| v as i32)),
+                    )) as ArrayRef,
+                    true,
+                ),
+                (
+                    "interval_dt",
+                    Arc::new(IntervalDayTimeArray::from_iter(ints.iter().cloned())) as ArrayRef,
+                    true,
+                ),
+                (
+                    "interval_mdn",
+                    Arc::new(IntervalMonthDayNanoArray::from_iter(
+                        ints.iter().map(|v| v.map(|v| v as i128)),
+                    )) as ArrayRef,
+                    true,
+                ),
+                (
+                    "duration",
+                    Arc::new(DurationMicrosecondArray::from_iter(ints.iter().cloned())) as ArrayRef,
+                    true,
+                ),
+                (
+                    "time32",
+                    Arc::new(Time32MillisecondArray::from_iter(
+                        ints.iter().map(|v| v.map(|v| v as i32)),
+                    )) as ArrayRef,
+                    true,
+                ),
+                (
+                    "time64",
+                    Arc::new(Time64NanosecondArray::from_iter(ints.iter().cloned())) as ArrayRef,
+                    true,
+                ),
+            ])
+            .unwrap();
+
+            let offset = rng.gen_range(0..num_rows);
+            let len = rng.gen_range(0..=num_rows - offset);
+            for batch in [batch.clone(), batch.slice(offset, len)] {
+                for version in 0..=BATCH_SERDE_VERSION {
+                    let mut buf = vec![];
+                    write_batch(&batch, &mut buf, IoCompressionCodec::None, version, None).unwrap();
+                    let mut cursor = Cursor::new(buf);
+                    let decoded_batch =
+                        read_batch(&mut cursor, IoCompressionCodec::None, version).unwrap();
+                    assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,532 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Adaptive encodings of top-level columns in batch serde format version 1.
+//! each column is prefixed with an encoding id, the encoding is chosen by
+//! probing the column data and falls back to plain buffers if no encoding is
+//! expected to save space:
+//! - dictionary: low-cardinality strings/binaries
+//! - rle: booleans with long runs
+//! - bit-packing: integers in a small range
+//! - delta: sorted integers with small gaps
+
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
+
+use arrow::{
+    array::*,
+    buffer::{BooleanBuffer, BooleanBufferBuilder, Buffer, MutableBuffer},
+    datatypes::*,
+};
+use datafusion::common::Result;
+
+use crate::{
+    df_execution_err,
+    io::{read_array, read_bytes_slice, read_len, read_u8, write_array, write_len, write_u8},
+};
+
+const ENCODING_PLAIN: u8 = 0;
+const ENCODING_DICTIONARY: u8 = 1;
+const ENCODING_RLE: u8 = 2;
+const ENCODING_BIT_PACKED: u8 = 3;
+const ENCODING_DELTA: u8 = 4;
+
+// number of leading rows to probe the cardinality before building dictionary
+const DICTIONARY_PROBE_ROWS: usize = 1024;
+
+// max ratio of distinct values to rows of dictionary encoded columns
+const DICTIONARY_MAX_CARDINALITY_RATIO: f64 = 0.5;
+
+pub fn write_encoded_array<W: Write>(array: &dyn Array, output: &mut W) -> Result<()> {
+    macro_rules! try_write_int {
+        ($ty:ident, $native:ty) => {{
+            let array = as_primitive_array::<$ty>(array);
+            try_write_int_array(array, |v| v as i64, <$native>::BITS, output)?
+        }};
+    }
+    let written = match array.data_type() {
+        DataType::Boolean => try_write_rle_boolean_array(as_boolean_array(array), output)?,
+        DataType::Int8 => try_write_int!(Int8Type, i8),
+        DataType::Int16 => try_write_int!(Int16Type, i16),
+        DataType::Int32 => try_write_int!(Int32Type, i32),
+        DataType::Int64 => try_write_int!(Int64Type, i64),
+        DataType::UInt8 => try_write_int!(UInt8Type, u8),
+        DataType::UInt16 => try_write_int!(UInt16Type, u16),
+        DataType::UInt32 => try_write_int!(UInt32Type, u32),
+        DataType::Date32 => try_write_int!(Date32Type, i32),
+        DataType::Date64 => try_write_int!(Date64Type, i64),
+        DataType::Timestamp(TimeUnit::Second, _) => try_write_int!(TimestampSecondType, i64),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            try_write_int!(TimestampMillisecondType, i64)
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            try_write_int!(TimestampMicrosecondType, i64)
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            try_write_int!(TimestampNanosecondType, i64)
+        }
+        DataType::Utf8 => try_write_dictionary_array(as_string_array(array), output)?,
+        DataType::Binary => {
+            try_write_dictionary_array(as_generic_binary_array::<i32>(array), output)?
+        }
+        _ => false,
+    };
+    if !written {
+        write_u8(ENCODING_PLAIN, output)?;
+        write_array(array, output)?;
+    }
+    Ok(())
+}
+
+pub fn read_encoded_array<R: Read>(
+    input: &mut R,
+    data_type: &DataType,
+    num_rows: usize,
+) -> Result<ArrayRef> {
+    macro_rules! read_int {
+        ($ty:ident, $native:ty, $encoding:expr) => {{
+            read_int_array::<_, $ty>(input, data_type, num_rows, $encoding, |v| v as $native)?
+        }};
+    }
+    Ok(match read_u8(input)? {
+        ENCODING_PLAIN => read_array(input, data_type, num_rows)?,
+        ENCODING_DICTIONARY => read_dictionary_array(input, data_type, num_rows)?,
+        ENCODING_RLE => read_rle_boolean_array(input, num_rows)?,
+        encoding @ (ENCODING_BIT_PACKED | ENCODING_DELTA) => match data_type {
+            DataType::Int8 => read_int!(Int8Type, i8, encoding),
+            DataType::Int16 => read_int!(Int16Type, i16, encoding),
+            DataType::Int32 => read_int!(Int32Type, i32, encoding),
+            DataType::Int64 => read_int!(Int64Type, i64, encoding),
+            DataType::UInt8 => read_int!(UInt8Type, u8, encoding),
+            DataType::UInt16 => read_int!(UInt16Type, u16, encoding),
+            DataType::UInt32 => read_int!(UInt32Type, u32, encoding),
+            DataType::Date32 => read_int!(Date32Type, i32, encoding),
+            DataType::Date64 => read_int!(Date64Type, i64, encoding),
+            DataType::Timestamp(TimeUnit::Second, _) => {
+                read_int!(TimestampSecondType, i64, encoding)
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
+                read_int!(TimestampMillisecondType, i64, encoding)
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                read_int!(TimestampMicrosecondType, i64, encoding)
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+                read_int!(TimestampNanosecondType, i64, encoding)
+            }
+            other => return df_execution_err!("invalid integer encoding of data type: {other}"),
+        },
+        other => return df_execution_err!("invalid column encoding: {other}"),
+    })
+}
+
+fn write_null_buffer<W: Write>(array_data: &ArrayData, output: &mut W) -> Result<()> {
+    match array_data.nulls() {
+        Some(null_buffer) => {
+            write_len(1, output)?;
+            let mut bits = vec![0u8; (null_buffer.len() + 7) / 8];
+            for i in 0..null_buffer.len() {
+                if null_buffer.is_valid(i) {
+                    arrow::util::bit_util::set_bit(&mut bits, i);
+                }
+            }
+            output.write_all(&bits)?;
+        }
+        None => write_len(0, output)?,
+    }
+    Ok(())
+}
+
+fn read_null_buffer<R: Read>(input: &mut R, num_rows: usize) -> Result<Option<Buffer>> {
+    if read_len(input)? == 1 {
+        return Ok(Some(Buffer::from(read_bytes_slice(
+            input,
+            (num_rows + 7) / 8,
+        )?)));
+    }
+    Ok(None)
+}
+
+fn bit_width(max_value: u64) -> u32 {
+    u64::BITS - max_value.leading_zeros()
+}
+
+fn write_bit_packed<W: Write>(values: &[u64], bit_width: u32, output: &mut W) -> Result<()> {
+    write_u8(bit_width as u8, output)?;
+    let mut buf = Vec::with_capacity((values.len() * bit_width as usize + 7) / 8);
+    let mut acc = 0u128;
+    let mut acc_bits = 0;
+    for &value in values {
+        acc |= (value as u128) << acc_bits;
+        acc_bits += bit_width;
+        while acc_bits >= 8 {
+            buf.push(acc as u8);
+            acc >>= 8;
+            acc_bits -= 8;
+        }
+    }
+    if acc_bits > 0 {
+        buf.push(acc as u8);
+    }
+    output.write_all(&buf)?;
+    Ok(())
+}
+
+fn read_bit_packed<R: Read>(input: &mut R, num_values: usize) -> Result<Vec<u64>> {
+    let bit_width = read_u8(input)? as u32;
+    if bit_width > u64::BITS {
+        return df_execution_err!("invalid bit width of bit-packed values: {bit_width}");
+    }
+    let buf = read_bytes_slice(input, (num_values * bit_width as usize + 7) / 8)?;
+    let mask = u64::MAX.checked_shr(u64::BITS - bit_width).unwrap_or(0);
+    let mut bytes = buf.iter();
+    let mut values = Vec::with_capacity(num_values);
+    let mut acc = 0u128;
+    let mut acc_bits = 0;
+    for _ in 0..num_values {
+        while acc_bits < bit_width {
+            acc |= (*bytes.next().unwrap_or(&0) as u128) << acc_bits;
+            acc_bits += 8;
+        }
+        values.push(acc as u64 & mask);
+        acc >>= bit_width;
+        acc_bits -= bit_width;
+    }
+    Ok(values)
+}
+
+fn try_write_int_array<PT: ArrowPrimitiveType, W: Write>(
+    array: &PrimitiveArray<PT>,
+    to_i64: impl Fn(PT::Native) -> i64,
+    native_bits: u32,
+    output: &mut W,
+) -> Result<bool> {
+    if array.len() < 2 || array.null_count() == array.len() {
+        return Ok(false);
+    }
+
+    // null slots are filled with the previous value, so they do not break the
+    // sortedness nor widen the range of values
+    let first_valid = (0..array.len()).find(|&i| array.is_valid(i)).unwrap();
+    let mut prev = to_i64(array.value(first_valid));
+    let values = (0..array.len())
+        .map(|i| {
+            if array.is_valid(i) {
+                prev = to_i64(array.value(i));
+            }
+            prev
+        })
+        .collect::<Vec<_>>();
+
+    let min = *values.iter().min().unwrap();
+    let max = *values.iter().max().unwrap();
+    let bit_packed_width = bit_width((max as i128 - min as i128) as u64);
+
+    let sorted = values.windows(2).all(|w| w[0] <= w[1]);
+    let delta_width = if sorted {
+        let max_delta = values
+            .windows(2)
+            .map(|w| (w[1] as i128 - w[0] as i128) as u64)
+            .max()
+            .unwrap_or(0);
+        bit_width(max_delta)
+    } else {
+        u32::MAX
+    };
+
+    if bit_packed_width.min(delta_width) >= native_bits {
+        return Ok(false);
+    }
+    let array_data = array.to_data();
+    if delta_width < bit_packed_width {
+        let deltas = values
+            .windows(2)
+            .map(|w| (w[1] as i128 - w[0] as i128) as u64)
+            .collect::<Vec<_>>();
+        write_u8(ENCODING_DELTA, output)?;
+        write_null_buffer(&array_data, output)?;
+        output.write_all(&values[0].to_le_bytes())?;
+        write_bit_packed(&deltas, delta_width, output)?;
+    } else {
+        let offsets = values
+            .iter()
+            .map(|&v| (v as i128 - min as i128) as u64)
+            .collect::<Vec<_>>();
+        write_u8(ENCODING_BIT_PACKED, output)?;
+        write_null_buffer(&array_data, output)?;
+        output.write_all(&min.to_le_bytes())?;
+        write_bit_packed(&offsets, bit_packed_width, output)?;
+    }
+    Ok(true)
+}
+
+fn read_int_array<R: Read, PT: ArrowPrimitiveType>(
+    input: &mut R,
+    data_type: &DataType,
+    num_rows: usize,
+    encoding: u8,
+    from_i64: impl Fn(i64) -> PT::Native,
+) -> Result<ArrayRef> {
+    let null_buffer = read_null_buffer(input, num_rows)?;
+    let mut base_buf = [0u8; 8];
+    input.read_exact(&mut base_buf)?;
+    let base = i64::from_le_bytes(base_buf) as i128;
+
+    let mut data_buffer = MutableBuffer::new(num_rows * PT::get_byte_width());
+    if encoding == ENCODING_DELTA {
+        let mut cur = base;
+        data_buffer.push(from_i64(cur as i64));
+        for delta in read_bit_packed(input, num_rows.saturating_sub(1))? {
+            cur += delta as i128;
+            data_buffer.push(from_i64(cur as i64));
+        }
+    } else {
+        for offset in read_bit_packed(input, num_rows)? {
+            data_buffer.push(from_i64((base + offset as i128) as i64));
+        }
+    }
+
+    let array_data = ArrayData::try_new(
+        data_type.clone(),
+        num_rows,
+        null_buffer,
+        0,
+        vec![data_buffer.into()],
+        vec![],
+    )?;
+    Ok(make_array(array_data))
+}
+
+fn try_write_rle_boolean_array<W: Write>(array: &BooleanArray, output: &mut W) -> Result<bool> {
+    if array.is_empty() {
+        return Ok(false);
+    }
+
+    // null slots are treated as the previous value to make longer runs
+    let mut runs: Vec<usize> = vec![];
+    let mut prev = None;
+    for i in 0..array.len() {
+        let value = if array.is_valid(i) {
+            array.value(i)
+        } else {
+            prev.unwrap_or(false)
+        };
+        match runs.last_mut() {
+            Some(run) if prev == Some(value) => *run += 1,
+            _ => runs.push(1),
+        }
+        prev = Some(value);
+    }
+
+    // each run length takes about 2 bytes
+    if runs.len() * 2 >= (array.len() + 7) / 8 {
+        return Ok(false);
+    }
+    let first_value = array.is_valid(0) && array.value(0);
+    write_u8(ENCODING_RLE, output)?;
+    write_null_buffer(&array.to_data(), output)?;
+    write_u8(first_value as u8, output)?;
+    write_len(runs.len(), output)?;
+    for run in runs {
+        write_len(run, output)?;
+    }
+    Ok(true)
+}
+
+fn read_rle_boolean_array<R: Read>(input: &mut R, num_rows: usize) -> Result<ArrayRef> {
+    let null_buffer = read_null_buffer(input, num_rows)?;
+    let mut value = read_u8(input)? == 1;
+    let num_runs = read_len(input)?;
+    let mut builder = BooleanBufferBuilder::new(num_rows);
+    for _ in 0..num_runs {
+        builder.append_n(read_len(input)?, value);
+        value = !value;
+    }
+    if builder.len() != num_rows {
+        return df_execution_err!("invalid rle boolean array: {} != {num_rows}", builder.len());
+    }
+
+    let array_data = ArrayData::try_new(
+        DataType::Boolean,
+        num_rows,
+        null_buffer,
+        0,
+        vec![builder.finish().into_inner()],
+        vec![],
+    )?;
+    Ok(make_array(array_data))
+}
+
+fn try_write_dictionary_array<T: ByteArrayType<Offset = i32>, W: Write>(
+    array: &GenericByteArray<T>,
+    output: &mut W,
+) -> Result<bool> {
+    let offsets = array.value_offsets();
+    let value_data = array.value_data();
+    let value = |i: usize| &value_data[offsets[i] as usize..offsets[i + 1] as usize];
+
+    // probe leading rows before building the whole dictionary
+    let num_probe_rows = array.len().min(DICTIONARY_PROBE_ROWS);
+    let max_cardinality =
+        |num_rows: usize| (num_rows as f64 * DICTIONARY_MAX_CARDINALITY_RATIO) as usize;
+    let mut dict: HashMap<&[u8], u64> = HashMap::new();
+    let mut dict_values: Vec<&[u8]> = vec![];
+    let mut keys = Vec::with_capacity(array.len());
+    for i in 0..array.len() {
+        if i == num_probe_rows && dict.len() > max_cardinality(num_probe_rows) {
+            return Ok(false);
+        }
+        let key = if array.is_valid(i) {
+            *dict.entry(value(i)).or_insert_with(|| {
+                dict_values.push(value(i));
+                dict_values.len() as u64 - 1
+            })
+        } else {
+            0
+        };
+        keys.push(key);
+    }
+    if dict.len() > max_cardinality(array.len()) {
+        return Ok(false);
+    }
+
+    write_u8(ENCODING_DICTIONARY, output)?;
+    write_null_buffer(&array.to_data(), output)?;
+    write_len(dict_values.len(), output)?;
+    for dict_value in &dict_values {
+        write_len(dict_value.len(), output)?;
+        output.write_all(dict_value)?;
+    }
+    let key_width = bit_width(dict_values.len().saturating_sub(1) as u64);
+    write_bit_packed(&keys, key_width, output)?;
+    Ok(true)
+}
+
+fn read_dictionary_array<R: Read>(
+    input: &mut R,
+    data_type: &DataType,
+    num_rows: usize,
+) -> Result<ArrayRef> {
+    let null_buffer = read_null_buffer(input, num_rows)?;
+    let num_dict_values = read_len(input)?;
+    let dict_values = (0..num_dict_values)
+        .map(|_| {
+            let len = read_len(input)?;
+            read_bytes_slice(input, len)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let keys = read_bit_packed(input, num_rows)?;
+
+    let nulls = null_buffer
+        .as_ref()
+        .map(|buffer| BooleanBuffer::new(buffer.clone(), 0, num_rows));
+    let mut offsets_buffer = MutableBuffer::new((num_rows + 1) * 4);
+    let mut data_buffer = MutableBuffer::new(0);
+    offsets_buffer.push(0i32);
+    for (i, &key) in keys.iter().enumerate() {
+        if nulls.as_ref().map(|nulls| nulls.value(i)).unwrap_or(true) {
+            match dict_values.get(key as usize) {
+                Some(dict_value) => data_buffer.extend_from_slice(dict_value),
+                None => return df_execution_err!("invalid dictionary key: {key}"),
+            }
+        }
+        offsets_buffer.push(data_buffer.len() as i32);
+    }
+
+    let array_data = ArrayData::try_new(
+        data_type.clone(),
+        num_rows,
+        null_buffer,
+        0,
+        vec![offsets_buffer.into(), data_buffer.into()],
+        vec![],
+    )?;
+    Ok(make_array(array_data))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Cursor, sync::Arc};
+
+    use arrow::{array::*, datatypes::*};
+    use datafusion::common::Result;
+
+    use crate::io::column_encoding::{
+        read_encoded_array, write_encoded_array, ENCODING_BIT_PACKED, ENCODING_DELTA,
+        ENCODING_DICTIONARY, ENCODING_PLAIN, ENCODING_RLE,
+    };
+
+    fn assert_encoding(array: ArrayRef, expected_encoding: u8) -> Result<()> {
+        let mut buf = vec![];
+        write_encoded_array(&array, &mut buf)?;
+        assert_eq!(buf[0], expected_encoding, "data_type={}", array.data_type());
+
+        let decoded = read_encoded_array(&mut Cursor::new(buf), array.data_type(), array.len())?;
+        assert_eq!(&decoded, &array);
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_encodings() -> Result<()> {
+        // low-cardinality strings
+        let strings = (0..3000).map(|i| match i % 7 {
+            0 => None,
+            i => Some(format!("value-{i}")),
+        });
+        let array: ArrayRef = Arc::new(StringArray::from_iter(strings));
+        assert_encoding(array.clone(), ENCODING_DICTIONARY)?;
+        assert_encoding(array.slice(5, 1000), ENCODING_DICTIONARY)?;
+
+        // high-cardinality strings
+        let array: ArrayRef = Arc::new(StringArray::from_iter_values(
+            (0..3000).map(|i| format!("value-{i}")),
+        ));
+        assert_encoding(array, ENCODING_PLAIN)?;
+
+        // booleans with long runs
+        let array: ArrayRef = Arc::new(BooleanArray::from_iter(
+            (0..3000).map(|i| (i % 500 != 0).then_some(i < 1000)),
+        ));
+        assert_encoding(array.clone(), ENCODING_RLE)?;
+        assert_encoding(array.slice(3, 2000), ENCODING_RLE)?;
+
+        // small-range integers
+        let array: ArrayRef =
+            Arc::new(Int64Array::from_iter((0..3000).map(|i| {
+                (i % 11 != 0).then_some(1_000_000_007 + (i * 37) % 100)
+            })));
+        assert_encoding(array.clone(), ENCODING_BIT_PACKED)?;
+        assert_encoding(array.slice(7, 100), ENCODING_BIT_PACKED)?;
+
+        // sorted integers
+        let array: ArrayRef = Arc::new(
+            TimestampMicrosecondArray::from_iter(
+                (0..3000).map(|i| (i % 13 != 0).then_some(1_600_000_000_000_000 + i * 1000)),
+            )
+            .with_timezone("UTC"),
+        );
+        assert_encoding(array, ENCODING_DELTA)?;
+        let array: ArrayRef = Arc::new(Int32Array::from_iter_values([i32::MIN, i32::MIN + 1, 0]));
+        assert_encoding(array, ENCODING_DELTA)?;
+
+        // wide-range integers
+        let array: ArrayRef = Arc::new(Int32Array::from_iter_values([i32::MAX, 0, i32::MIN]));
+        assert_encoding(array, ENCODING_PLAIN)?;
+        let array: ArrayRef = Arc::new(UInt8Array::from_iter([None, None]));
+        assert_encoding(array, ENCODING_PLAIN)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use blaze_jni_bridge::{
+    conf,
+    conf::{IntConf, StringConf},
+    is_jni_bridge_inited,
+};
+use datafusion::common::Result;
+
+use crate::df_execution_err;
+
+/// compression codec of batches written by `write_one_batch`. the codec id is
+/// written into each batch so readers do not need to know the codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoCompressionCodec {
+    None,
+    Lz4,
+    Zstd(i32),
+    Snappy,
+}
+
+/// where the compressed data is used, each site has its own codec conf
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoCompressionSite {
+    Shuffle,
+    Rss,
+    Broadcast,
+    Spill,
+}
+
+impl Default for IoCompressionCodec {
+    fn default() -> Self {
+        IoCompressionCodec::Zstd(1)
+    }
+}
+
+impl IoCompressionCodec {
+    pub fn try_new(name: &str, zstd_level: i32) -> Result<Self> {
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "none" => IoCompressionCodec::None,
+            "lz4" => IoCompressionCodec::Lz4,
+            "zstd" => IoCompressionCodec::Zstd(zstd_level),
+            "snappy" => IoCompressionCodec::Snappy,
+            other => return df_execution_err!("unsupported io compression codec: {other}"),
+        })
+    }
+
+    pub fn try_from_conf(site: IoCompressionSite) -> Result<Self> {
+        if !is_jni_bridge_inited() {
+            // only for testing
+            return Ok(Self::default());
+        }
+        let name = match site {
+            IoCompressionSite::Shuffle => conf::SHUFFLE_COMPRESSION_CODEC.value()?,
+            IoCompressionSite::Rss => conf::RSS_COMPRESSION_CODEC.value()?,
+            IoCompressionSite::Broadcast => conf::BROADCAST_COMPRESSION_CODEC.value()?,
+            IoCompressionSite::Spill => conf::SPILL_COMPRESSION_CODEC.value()?,
+        };
+        Self::try_new(&name, conf::COMPRESSION_ZSTD_LEVEL.value()?)
+    }
+
+    pub fn id(&self) -> u8 {
+        match self {
+            IoCompressionCodec::None => 0,
+            IoCompressionCodec::Lz4 => 1,
+            IoCompressionCodec::Zstd(_) => 2,
+            IoCompressionCodec::Snappy => 3,
+        }
+    }
+
+    /// codec of the given id, the zstd level is irrelevant for reading
+    pub fn try_from_id(id: u8) -> Result<Self> {
+        Ok(match id {
+            0 => IoCompressionCodec::None,
+            1 => IoCompressionCodec::Lz4,
+            2 => IoCompressionCodec::Zstd(0),
+            3 => IoCompressionCodec::Snappy,
+            other => return df_execution_err!("invalid io compression codec id: {other}"),
+        })
+    }
+}
+
+pub enum IoCompressionWriter<W: Write> {
+    None(BufWriter<W>),
+    Lz4(lz4_flex::frame::FrameEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+    Snappy(snap::write::FrameEncoder<W>),
+}
+
+impl<W: Write> IoCompressionWriter<W> {
+    pub fn try_new(codec: IoCompressionCodec, inner: W) -> Result<Self> {
+        Ok(match codec {
+            IoCompressionCodec::None => IoCompressionWriter::None(BufWriter::new(inner)),
+            IoCompressionCodec::Lz4 => {
+                IoCompressionWriter::Lz4(lz4_flex::frame::FrameEncoder::new(inner))
+            }
+            IoCompressionCodec::Zstd(level) => {
+                IoCompressionWriter::Zstd(zstd::Encoder::new(inner, level)?)
+            }
+            IoCompressionCodec::Snappy => {
+                IoCompressionWriter::Snappy(snap::write::FrameEncoder::new(inner))
+            }
+        })
+    }
+
+    /// writes all remaining compressed data into the inner writer
+    pub fn finish(self) -> Result<()> {
+        match self {
+            IoCompressionWriter::None(w) => {
+                w.into_inner().map_err(|err| err.into_error())?;
+            }
+            IoCompressionWriter::Lz4(w) => {
+                w.finish()
+                    .or_else(|err| df_execution_err!("lz4 compression error: {err}"))?;
+            }
+            IoCompressionWriter::Zstd(w) => {
+                w.finish()?;
+            }
+            IoCompressionWriter::Snappy(w) => {
+                w.into_inner().map_err(|err| err.into_error())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for IoCompressionWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            IoCompressionWriter::None(w) => w.write(buf),
+            IoCompressionWriter::Lz4(w) => w.write(buf),
+            IoCompressionWriter::Zstd(w) => w.write(buf),
+            IoCompressionWriter::Snappy(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            IoCompressionWriter::None(w) => w.flush(),
+            IoCompressionWriter::Lz4(w) => w.flush(),
+            IoCompressionWriter::Zstd(w) => w.flush(),
+            IoCompressionWriter::Snappy(w) => w.flush(),
+        }
+    }
+}
+
+pub enum IoCompressionReader<R: Read> {
+    None(BufReader<R>),
+    Lz4(BufReader<lz4_flex::frame::FrameDecoder<R>>),
+    Zstd(BufReader<zstd::Decoder<'static, BufReader<R>>>),
+    Snappy(BufReader<snap::read::FrameDecoder<R>>),
+}
+
+impl<R: Read> IoCompressionReader<R> {
+    pub fn try_new(codec: IoCompressionCodec, inner: R) -> Result<Self> {
+        Ok(match codec {
+            IoCompressionCodec::None => IoCompressionReader::None(BufReader::new(inner)),
+            IoCompressionCodec::Lz4 => {
+                IoCompressionReader::Lz4(BufReader::new(lz4_flex::frame::FrameDecoder::new(inner)))
+            }
+            IoCompressionCodec::Zstd(_) => {
+                IoCompressionReader::Zstd(BufReader::new(zstd::Decoder::new(inner)?))
+            }
+            IoCompressionCodec::Snappy => {
+                IoCompressionReader::Snappy(BufReader::new(snap::read::FrameDecoder::new(inner)))
+            }
+        })
+    }
+}
+
+impl<R: Read> Read for IoCompressionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            IoCompressionReader::None(r) => r.read(buf),
+            IoCompressionReader::Lz4(r) => r.read(buf),
+            IoCompressionReader::Zstd(r) => r.read(buf),
+            IoCompressionReader::Snappy(r) => r.read(buf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Cursor, sync::Arc};
+
+    use arrow::{
+        array::{ArrayRef, Int32Array, StringArray},
+        record_batch::RecordBatch,
+    };
+    use datafusion::common::Result;
+
+    use crate::io::{compression::IoCompressionCodec, read_one_batch, write_one_batch};
+
+    #[test]
+    fn test_io_compression_codecs() -> Result<()> {
+        let batch = RecordBatch::try_from_iter_with_nullable(vec![
+            (
+                "a",
+                Arc::new(Int32Array::from_iter((0..1000).map(Some))) as ArrayRef,
+                true,
+            ),
+            (
+                "b",
+                Arc::new(StringArray::from_iter(
+                    (0..1000).map(|i| Some(format!("s{}", i % 7))),
+                )) as ArrayRef,
+                true,
+            ),
+        ])?;
+
+        let codecs = [
+            IoCompressionCodec::None,
+            IoCompressionCodec::Lz4,
+            IoCompressionCodec::Zstd(1),
+            IoCompressionCodec::Zstd(9),
+            IoCompressionCodec::Snappy,
+        ];
+
+        // batches of different codecs can be read from the same stream
+        let mut buf = vec![];
+        let mut cursor = Cursor::new(&mut buf);
+        for codec in codecs {
+            write_one_batch(&batch, &mut cursor, codec, None)?;
+        }
+        let mut cursor = Cursor::new(&buf);
+        for _ in codecs {
+            let decoded = read_one_batch(&mut cursor, Some(batch.schema()))?;
+            assert_eq!(decoded, Some(batch.clone()));
+        }
+        assert_eq!(read_one_batch(&mut cursor, Some(batch.schema()))?, None);
+
+        assert_eq!(
+            IoCompressionCodec::try_new("ZSTD", 3)?,
+            IoCompressionCodec::Zstd(3)
+        );
+        assert!(IoCompressionCodec::try_new("gzip", 1).is_err());
+        Ok(())
+    }
+}
@@ -20,16 +20,29 @@ use arrow::{
     record_batch::RecordBatch,
 };
 pub use batch_serde::{read_array, read_data_type, write_array, write_data_type};
+use blaze_jni_bridge::{conf, conf::IntConf, is_jni_bridge_inited};
+pub use compression::{
+    IoCompressionCodec, IoCompressionReader, IoCompressionSite, IoCompressionWriter,
+};
 use datafusion::common::{cast::as_struct_array, Result};
+use once_cell::sync::OnceCell;
 pub use scalar_serde::{read_scalar, write_scalar};
 
+use crate::df_execution_err;
+
 mod batch_serde;
+mod column_encoding;
+mod compression;
 mod scalar_serde;
 
+/// writes a batch in the framing of `[ipc_length][header][compressed batch]`,
+/// where the header byte holds the batch serde version in the high 4 bits and
+/// the codec id in the low 4 bits. batches written before versioning have a
+/// zero version, so they are still readable.
 pub fn write_one_batch<W: Write + Seek>(
     batch: &RecordBatch,
     output: &mut W,
-    compress: bool,
+    codec: IoCompressionCodec,
     uncompressed_size: Option<&mut usize>,
 ) -> Result<usize> {
     if batch.num_rows() == 0 {
@@ -40,7 +53,9 @@ pub fn write_one_batch<W: Write + Seek>(
     output.write_all(&[0u8; 8])?;
 
     // write
-    batch_serde::write_batch(batch, output, compress, uncompressed_size)?;
+    let version = batch_serde_write_version()?;
+    write_u8(version << 4 | codec.id(), output)?;
+    batch_serde::write_batch(batch, output, codec, version, uncompressed_size)?;
     let end_pos = output.stream_position()?;
     let ipc_length = end_pos - start_pos - 8;
 
@@ -51,10 +66,28 @@ pub fn write_one_batch<W: Write + Seek>(
     Ok((end_pos - start_pos) as usize)
 }
 
+/// version of the batch serde format used for writing, it is configurable so
+/// that written batches stay readable by older native libraries
+fn batch_serde_write_version() -> Result<u8> {
+    static WRITE_VERSION: OnceCell<u8> = OnceCell::new();
+    WRITE_VERSION
+        .get_or_try_init(|| {
+            if !is_jni_bridge_inited() {
+                // only for testing
+                return Ok(batch_serde::BATCH_SERDE_VERSION);
+            }
+            let version = conf::BATCH_SERDE_WRITE_VERSION.value()?;
+            if !(0..=batch_serde::BATCH_SERDE_VERSION as i32).contains(&version) {
+                return df_execution_err!("unsupported batch serde write version: {version}");
+            }
+            Ok(version as u8)
+        })
+        .cloned()
+}
+
 pub fn read_one_batch<R: Read>(
     input: &mut R,
     schema: Option<SchemaRef>,
-    compress: bool,
 ) -> Result<Option<RecordBatch>> {
     // read ipc length
     let mut ipc_length_buf = [0u8; 8];
@@ -68,7 +101,13 @@ pub fn read_one_batch<R: Read>(
     let mut input = Box::new(input.take(ipc_length));
 
     // read
-    let nameless_batch = batch_serde::read_batch(&mut input, compress)?;
+    let header = read_u8(&mut input)?;
+    let version = header >> 4;
+    if version > batch_serde::BATCH_SERDE_VERSION {
+        return df_execution_err!("unsupported batch serde version: {version}");
+    }
+    let codec = IoCompressionCodec::try_from_id(header & 0x0f)?;
+    let nameless_batch = batch_serde::read_batch(&mut input, codec, version)?;
 
     // consume trailing bytes
     std::io::copy(&mut input, &mut std::io::sink())?;
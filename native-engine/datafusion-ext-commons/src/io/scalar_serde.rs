@@ -64,12 +64,17 @@ pub fn write_scalar<W: Write>(value: &ScalarValue, output: &mut W) -> Result<()>
         ScalarValue::Decimal128(Some(value), ..) => {
             write_primitive_valid_scalar(value.to_ne_bytes().as_slice(), output)?
         }
-        ScalarValue::Utf8(Some(value)) => {
+        ScalarValue::Decimal256(Some(value), ..) => {
+            write_primitive_valid_scalar(value.to_le_bytes().as_slice(), output)?
+        }
+        ScalarValue::Utf8(Some(value)) | ScalarValue::LargeUtf8(Some(value)) => {
             let value_bytes = value.as_bytes();
             write_len(value_bytes.len() + 1, output)?;
             output.write_all(value_bytes)?;
         }
-        ScalarValue::Binary(Some(value)) => {
+        ScalarValue::Binary(Some(value))
+        | ScalarValue::LargeBinary(Some(value))
+        | ScalarValue::FixedSizeBinary(_, Some(value)) => {
             let value_byte = value.as_bytes();
             write_len(value_byte.len() + 1, output)?;
             output.write_all(value_byte)?;
@@ -80,6 +85,28 @@ pub fn write_scalar<W: Write>(value: &ScalarValue, output: &mut W) -> Result<()>
         ScalarValue::Date64(Some(value)) => {
             write_primitive_valid_scalar(value.to_ne_bytes().as_slice(), output)?
         }
+        ScalarValue::Time32Second(Some(value)) | ScalarValue::Time32Millisecond(Some(value)) => {
+            write_primitive_valid_scalar(value.to_ne_bytes().as_slice(), output)?
+        }
+        ScalarValue::Time64Microsecond(Some(value))
+        | ScalarValue::Time64Nanosecond(Some(value)) => {
+            write_primitive_valid_scalar(value.to_ne_bytes().as_slice(), output)?
+        }
+        ScalarValue::DurationSecond(Some(value))
+        | ScalarValue::DurationMillisecond(Some(value))
+        | ScalarValue::DurationMicrosecond(Some(value))
+        | ScalarValue::DurationNanosecond(Some(value)) => {
+            write_primitive_valid_scalar(value.to_ne_bytes().as_slice(), output)?
+        }
+        ScalarValue::IntervalYearMonth(Some(value)) => {
+            write_primitive_valid_scalar(value.to_ne_bytes().as_slice(), output)?
+        }
+        ScalarValue::IntervalDayTime(Some(value)) => {
+            write_primitive_valid_scalar(value.to_ne_bytes().as_slice(), output)?
+        }
+        ScalarValue::IntervalMonthDayNano(Some(value)) => {
+            write_primitive_valid_scalar(value.to_ne_bytes().as_slice(), output)?
+        }
         ScalarValue::TimestampSecond(Some(value), _) => {
             write_primitive_valid_scalar(value.to_ne_bytes().as_slice(), output)?
         }
@@ -92,7 +119,7 @@ pub fn write_scalar<W: Write>(value: &ScalarValue, output: &mut W) -> Result<()>
         ScalarValue::TimestampNanosecond(Some(value), _) => {
             write_primitive_valid_scalar(value.to_ne_bytes().as_slice(), output)?
         }
-        ScalarValue::List(Some(value), field) => {
+        ScalarValue::List(Some(value), _) | ScalarValue::Fixedsizelist(Some(value), ..) => {
             write_len(value.len() + 1, output)?;
             if value.len() != 0 {
                 for element in value {
@@ -111,6 +138,9 @@ pub fn write_scalar<W: Write>(value: &ScalarValue, output: &mut W) -> Result<()>
         ScalarValue::Map(value, _bool) => {
             write_scalar(value, output)?;
         }
+        ScalarValue::Dictionary(_key_type, value) => {
+            write_scalar(value, output)?;
+        }
         ScalarValue::Boolean(None)
         | ScalarValue::Int8(None)
         | ScalarValue::Int16(None)
@@ -123,15 +153,31 @@ pub fn write_scalar<W: Write>(value: &ScalarValue, output: &mut W) -> Result<()>
         | ScalarValue::Float32(None)
         | ScalarValue::Float64(None)
         | ScalarValue::Decimal128(None, ..)
+        | ScalarValue::Decimal256(None, ..)
         | ScalarValue::Binary(None)
+        | ScalarValue::LargeBinary(None)
+        | ScalarValue::FixedSizeBinary(_, None)
         | ScalarValue::Utf8(None)
+        | ScalarValue::LargeUtf8(None)
         | ScalarValue::Date32(None)
         | ScalarValue::Date64(None)
+        | ScalarValue::Time32Second(None)
+        | ScalarValue::Time32Millisecond(None)
+        | ScalarValue::Time64Microsecond(None)
+        | ScalarValue::Time64Nanosecond(None)
+        | ScalarValue::DurationSecond(None)
+        | ScalarValue::DurationMillisecond(None)
+        | ScalarValue::DurationMicrosecond(None)
+        | ScalarValue::DurationNanosecond(None)
+        | ScalarValue::IntervalYearMonth(None)
+        | ScalarValue::IntervalDayTime(None)
+        | ScalarValue::IntervalMonthDayNano(None)
         | ScalarValue::TimestampSecond(None, _)
         | ScalarValue::TimestampMillisecond(None, _)
         | ScalarValue::TimestampMicrosecond(None, _)
         | ScalarValue::TimestampNanosecond(None, _)
         | ScalarValue::List(None, _)
+        | ScalarValue::Fixedsizelist(None, ..)
         | ScalarValue::Struct(None, ..) => write_len(0 as usize, output)?,
         other => df_unimplemented_err!("unsupported scalarValue type: {other}")?,
     }
@@ -172,8 +218,52 @@ pub fn read_scalar<R: Read>(input: &mut R, data_type: &DataType) -> Result<Scala
         DataType::Decimal128(precision, scale) => {
             ScalarValue::Decimal128(read_primitive_scalar!(input, 16, i128), *precision, *scale)
         }
+        DataType::Decimal256(precision, scale) => {
+            let valid = read_len(input)?;
+            let value = if valid != 0 {
+                let mut buf = [0; 32];
+                input.read_exact(&mut buf)?;
+                Some(i256::from_le_bytes(buf))
+            } else {
+                None
+            };
+            ScalarValue::Decimal256(value, *precision, *scale)
+        }
         DataType::Date32 => ScalarValue::Date32(read_primitive_scalar!(input, 4, i32)),
         DataType::Date64 => ScalarValue::Date64(read_primitive_scalar!(input, 8, i64)),
+        DataType::Time32(TimeUnit::Second) => {
+            ScalarValue::Time32Second(read_primitive_scalar!(input, 4, i32))
+        }
+        DataType::Time32(TimeUnit::Millisecond) => {
+            ScalarValue::Time32Millisecond(read_primitive_scalar!(input, 4, i32))
+        }
+        DataType::Time64(TimeUnit::Microsecond) => {
+            ScalarValue::Time64Microsecond(read_primitive_scalar!(input, 8, i64))
+        }
+        DataType::Time64(TimeUnit::Nanosecond) => {
+            ScalarValue::Time64Nanosecond(read_primitive_scalar!(input, 8, i64))
+        }
+        DataType::Duration(TimeUnit::Second) => {
+            ScalarValue::DurationSecond(read_primitive_scalar!(input, 8, i64))
+        }
+        DataType::Duration(TimeUnit::Millisecond) => {
+            ScalarValue::DurationMillisecond(read_primitive_scalar!(input, 8, i64))
+        }
+        DataType::Duration(TimeUnit::Microsecond) => {
+            ScalarValue::DurationMicrosecond(read_primitive_scalar!(input, 8, i64))
+        }
+        DataType::Duration(TimeUnit::Nanosecond) => {
+            ScalarValue::DurationNanosecond(read_primitive_scalar!(input, 8, i64))
+        }
+        DataType::Interval(IntervalUnit::YearMonth) => {
+            ScalarValue::IntervalYearMonth(read_primitive_scalar!(input, 4, i32))
+        }
+        DataType::Interval(IntervalUnit::DayTime) => {
+            ScalarValue::IntervalDayTime(read_primitive_scalar!(input, 8, i64))
+        }
+        DataType::Interval(IntervalUnit::MonthDayNano) => {
+            ScalarValue::IntervalMonthDayNano(read_primitive_scalar!(input, 16, i128))
+        }
         DataType::Timestamp(TimeUnit::Second, str) => {
             ScalarValue::TimestampSecond(read_primitive_scalar!(input, 8, i64), str.clone())
         }
@@ -186,24 +276,33 @@ pub fn read_scalar<R: Read>(input: &mut R, data_type: &DataType) -> Result<Scala
         DataType::Timestamp(TimeUnit::Nanosecond, str) => {
             ScalarValue::TimestampNanosecond(read_primitive_scalar!(input, 8, i64), str.clone())
         }
-        DataType::Binary => {
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => {
             let data_len = read_len(input)?;
-            if data_len > 0 {
+            let value = if data_len > 0 {
                 let data_len = data_len - 1;
-                ScalarValue::Binary(Some(read_bytes_slice(input, data_len)?.into()))
+                Some(read_bytes_slice(input, data_len)?.into())
             } else {
-                ScalarValue::Binary(None)
+                None
+            };
+            match data_type {
+                DataType::Binary => ScalarValue::Binary(value),
+                DataType::LargeBinary => ScalarValue::LargeBinary(value),
+                DataType::FixedSizeBinary(size) => ScalarValue::FixedSizeBinary(*size, value),
+                _ => unreachable!(),
             }
         }
-        DataType::Utf8 => {
+        DataType::Utf8 | DataType::LargeUtf8 => {
             let data_len = read_len(input)?;
-            if data_len > 0 {
+            let value = if data_len > 0 {
                 let data_len = data_len - 1;
                 let value_buf = read_bytes_slice(input, data_len)?;
-                let value = String::from_utf8_lossy(&value_buf);
-                ScalarValue::Utf8(Some(value.into()))
+                Some(String::from_utf8_lossy(&value_buf).into())
             } else {
-                ScalarValue::Utf8(None)
+                None
+            };
+            match data_type {
+                DataType::Utf8 => ScalarValue::Utf8(value),
+                _ => ScalarValue::LargeUtf8(value),
             }
         }
         DataType::List(field) => {
@@ -220,6 +319,20 @@ pub fn read_scalar<R: Read>(input: &mut R, data_type: &DataType) -> Result<Scala
                 ScalarValue::List(None, field.clone())
             }
         }
+        DataType::FixedSizeList(field, size) => {
+            let data_len = read_len(input)?;
+            if data_len > 0 {
+                let data_len = data_len - 1;
+                let mut list_data: Vec<ScalarValue> = Vec::with_capacity(data_len);
+                for _i in 0..data_len {
+                    let child_value = read_scalar(input, field.data_type())?;
+                    list_data.push(child_value);
+                }
+                ScalarValue::Fixedsizelist(Some(list_data), field.clone(), *size)
+            } else {
+                ScalarValue::Fixedsizelist(None, field.clone(), *size)
+            }
+        }
         DataType::Struct(fields) => {
             let data_len = read_len(input)?;
             if data_len > 0 {
@@ -238,6 +351,74 @@ pub fn read_scalar<R: Read>(input: &mut R, data_type: &DataType) -> Result<Scala
             let map_value = read_scalar(input, field.data_type())?;
             ScalarValue::Map(Box::new(map_value), *bool)
         }
+        DataType::Dictionary(key_type, value_type) => {
+            let value = read_scalar(input, value_type)?;
+            ScalarValue::Dictionary(key_type.clone(), Box::new(value))
+        }
         other => df_unimplemented_err!("unsupported data type: {other}")?,
     })
 }
+
+#[cfg(test)]
+mod test {
+    use std::{io::Cursor, sync::Arc};
+
+    use arrow::datatypes::*;
+    use datafusion::{common::Result, scalar::ScalarValue};
+    use rand::Rng;
+
+    use crate::io::{read_scalar, write_scalar};
+
+    #[test]
+    fn test_write_and_read_scalar_for_extended_types() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let v: i64 = rng.gen();
+            let valid = rng.gen_bool(0.8);
+            macro_rules! opt {
+                ($v:expr) => {{
+                    valid.then_some($v)
+                }};
+            }
+            let item_field = Arc::new(Field::new("item", DataType::Int32, true));
+
+            let values = [
+                ScalarValue::LargeUtf8(opt!(format!("s{v}"))),
+                ScalarValue::LargeBinary(opt!(v.to_le_bytes().to_vec())),
+                ScalarValue::FixedSizeBinary(8, opt!(v.to_be_bytes().to_vec())),
+                ScalarValue::Decimal256(opt!(i256::from_i128(v as i128 * v as i128)), 50, 6),
+                ScalarValue::Time32Second(opt!(v as i32)),
+                ScalarValue::Time32Millisecond(opt!(v as i32)),
+                ScalarValue::Time64Microsecond(opt!(v)),
+                ScalarValue::Time64Nanosecond(opt!(v)),
+                ScalarValue::DurationSecond(opt!(v)),
+                ScalarValue::DurationMillisecond(opt!(v)),
+                ScalarValue::DurationMicrosecond(opt!(v)),
+                ScalarValue::DurationNanosecond(opt!(v)),
+                ScalarValue::IntervalYearMonth(opt!(v as i32)),
+                ScalarValue::IntervalDayTime(opt!(v)),
+                ScalarValue::IntervalMonthDayNano(opt!(v as i128 * 3)),
+                ScalarValue::Fixedsizelist(
+                    opt!(vec![
+                        ScalarValue::Int32(Some(v as i32)),
+                        ScalarValue::Int32(None),
+                    ]),
+                    item_field.clone(),
+                    2,
+                ),
+                ScalarValue::Dictionary(
+                    Box::new(DataType::Int32),
+                    Box::new(ScalarValue::Utf8(opt!(format!("d{v}")))),
+                ),
+            ];
+
+            for value in values {
+                let mut buf = vec![];
+                write_scalar(&value, &mut buf)?;
+                let decoded = read_scalar(&mut Cursor::new(buf), &value.get_datatype())?;
+                assert_eq!(decoded, value);
+            }
+        }
+        Ok(())
+    }
+}
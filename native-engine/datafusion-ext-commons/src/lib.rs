@@ -18,11 +18,15 @@
 
 pub mod bytes_arena;
 pub mod cast;
+pub mod checksum;
 pub mod hadoop_fs;
 pub mod io;
 pub mod loser_tree;
+pub mod orc;
 pub mod rdxsort;
 pub mod slim_bytes;
+pub mod spark_datetime;
+pub mod spark_errors;
 pub mod spark_hash;
 pub mod streams;
 pub mod uda;
@@ -0,0 +1,175 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bloom filters of orc row groups, bit-compatible with
+//! `org.apache.orc.util.BloomFilter` so that they can be used by other
+//! readers.
+
+use crate::orc::proto;
+
+const MURMUR3_SEED: u64 = 104729;
+const MURMUR3_C1: u64 = 0x87c37b91114253d5;
+const MURMUR3_C2: u64 = 0x4cf5ad432745937f;
+
+#[derive(Debug, Clone)]
+pub struct OrcBloomFilter {
+    num_bits: usize,
+    num_hash_functions: usize,
+    bits: Vec<u64>,
+}
+
+impl OrcBloomFilter {
+    pub fn new(expected_entries: usize, fpp: f64) -> Self {
+        let n = expected_entries.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let nb = (-n * fpp.ln() / (ln2 * ln2)) as usize;
+        let num_bits = nb + (64 - nb % 64);
+        let num_hash_functions = ((num_bits as f64 / n * ln2).round() as usize).max(1);
+        Self {
+            num_bits,
+            num_hash_functions,
+            bits: vec![0; num_bits / 64],
+        }
+    }
+
+    /// adds a string or binary value
+    pub fn add_bytes(&mut self, value: &[u8]) {
+        self.add_hash(murmur3_hash64(value));
+    }
+
+    /// adds an integer, date or boolean value
+    pub fn add_long(&mut self, value: i64) {
+        self.add_hash(long_hash(value));
+    }
+
+    /// adds a float or double value
+    pub fn add_double(&mut self, value: f64) {
+        self.add_long(value.to_bits() as i64);
+    }
+
+    pub fn to_proto(&self) -> proto::BloomFilter {
+        proto::BloomFilter {
+            num_hash_functions: Some(self.num_hash_functions as u32),
+            bitset: vec![],
+            utf8bitset: Some(self.bits.iter().flat_map(|w| w.to_le_bytes()).collect()),
+        }
+    }
+
+    fn add_hash(&mut self, hash64: u64) {
+        for pos in self.bit_positions(hash64) {
+            self.bits[pos / 64] |= 1 << (pos % 64);
+        }
+    }
+
+    fn bit_positions(&self, hash64: u64) -> impl Iterator<Item = usize> {
+        let hash1 = hash64 as i32;
+        let hash2 = (hash64 >> 32) as i32;
+        let num_bits = self.num_bits as i32;
+        (1..=self.num_hash_functions as i32).map(move |i| {
+            let mut combined = hash1.wrapping_add(i.wrapping_mul(hash2));
+            if combined < 0 {
+                combined = !combined;
+            }
+            (combined % num_bits) as usize
+        })
+    }
+}
+
+// thomas wang's 64-bit integer hash, as used by orc for numeric values
+fn long_hash(key: i64) -> u64 {
+    let mut key = (!key).wrapping_add(key << 21);
+    key ^= key >> 24;
+    key = key.wrapping_add(key << 3).wrapping_add(key << 8);
+    key ^= key >> 14;
+    key = key.wrapping_add(key << 2).wrapping_add(key << 4);
+    key ^= key >> 28;
+    key = key.wrapping_add(key << 31);
+    key as u64
+}
+
+// 64-bit variant of murmur3 used by orc, which differs from the x64_128
+// variant in the way the tail and the length are mixed
+fn murmur3_hash64(data: &[u8]) -> u64 {
+    let mut hash = MURMUR3_SEED;
+    let mut blocks = data.chunks_exact(8);
+    for block in &mut blocks {
+        let mut k = u64::from_le_bytes(block.try_into().unwrap());
+        k = k.wrapping_mul(MURMUR3_C1);
+        k = k.rotate_left(31);
+        k = k.wrapping_mul(MURMUR3_C2);
+        hash ^= k;
+        hash = hash
+            .rotate_left(27)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dce729);
+    }
+
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0u64;
+        for (i, &b) in tail.iter().enumerate() {
+            k ^= (b as u64) << (i * 8);
+        }
+        k = k.wrapping_mul(MURMUR3_C1);
+        k = k.rotate_left(31);
+        k = k.wrapping_mul(MURMUR3_C2);
+        hash ^= k;
+    }
+
+    hash ^= data.len() as u64;
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^= hash >> 33;
+    hash
+}
+
+#[cfg(test)]
+mod test {
+    use crate::orc::bloom_filter::{long_hash, murmur3_hash64, OrcBloomFilter};
+
+    impl OrcBloomFilter {
+        fn test_hash(&self, hash64: u64) -> bool {
+            self.bit_positions(hash64)
+                .all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+        }
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let mut bf = OrcBloomFilter::new(10000, 0.05);
+        assert_eq!(bf.num_bits, 62400);
+        assert_eq!(bf.num_hash_functions, 4);
+
+        for i in 0..1000 {
+            bf.add_long(i * 3);
+            bf.add_bytes(format!("value-{i}").as_bytes());
+        }
+        bf.add_double(1.5);
+        for i in 0..1000 {
+            assert!(bf.test_hash(long_hash(i * 3)));
+            assert!(bf.test_hash(murmur3_hash64(format!("value-{i}").as_bytes())));
+        }
+        assert!(bf.test_hash(long_hash(1.5f64.to_bits() as i64)));
+
+        let false_positives = (0..1000)
+            .filter(|i| bf.test_hash(murmur3_hash64(format!("other-{i}").as_bytes())))
+            .count();
+        assert!(false_positives < 50);
+
+        let proto = bf.to_proto();
+        assert_eq!(proto.utf8bitset.unwrap().len(), 62400 / 8);
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{Read, Write};
+
+use datafusion::common::Result;
+
+use crate::{df_execution_err, df_unimplemented_err, orc::proto::CompressionKind};
+
+/// compression codec of an orc file, with the max size of a compressed chunk
+#[derive(Debug, Clone, Copy)]
+pub struct Compression {
+    pub kind: CompressionKind,
+    pub block_size: usize,
+}
+
+impl Compression {
+    /// decompresses a stream or a tail section. compressed streams are a
+    /// sequence of chunks, each with a 3-byte little-endian header containing
+    /// `chunk_len << 1 | is_original`.
+    pub fn decompress(&self, input: &[u8]) -> Result<Vec<u8>> {
+        if self.kind == CompressionKind::None {
+            return Ok(input.to_vec());
+        }
+
+        let mut output = Vec::with_capacity(input.len() * 2);
+        let mut pos = 0;
+        while pos < input.len() {
+            if pos + 3 > input.len() {
+                return df_execution_err!("orc: truncated compression chunk header");
+            }
+            let header = u32::from_le_bytes([input[pos], input[pos + 1], input[pos + 2], 0]);
+            let chunk_len = (header >> 1) as usize;
+            let is_original = header & 1 == 1;
+            pos += 3;
+
+            if pos + chunk_len > input.len() {
+                return df_execution_err!("orc: truncated compression chunk");
+            }
+            let chunk = &input[pos..pos + chunk_len];
+            pos += chunk_len;
+
+            if is_original {
+                output.extend_from_slice(chunk);
+            } else {
+                self.decompress_chunk(chunk, &mut output)?;
+            }
+        }
+        Ok(output)
+    }
+
+    /// compresses a stream or a tail section into chunks of at most
+    /// `block_size` bytes. chunks which are not smaller after compression are
+    /// stored as original.
+    pub fn compress(&self, input: &[u8]) -> Result<Vec<u8>> {
+        if self.kind == CompressionKind::None {
+            return Ok(input.to_vec());
+        }
+
+        let mut output = Vec::with_capacity(input.len() / 2);
+        for chunk in input.chunks(self.block_size) {
+            let compressed = self.compress_chunk(chunk)?;
+            let (header, data) = if compressed.len() < chunk.len() {
+                ((compressed.len() as u32) << 1, compressed.as_slice())
+            } else {
+                ((chunk.len() as u32) << 1 | 1, chunk)
+            };
+            output.extend_from_slice(&header.to_le_bytes()[..3]);
+            output.extend_from_slice(data);
+        }
+        Ok(output)
+    }
+
+    fn compress_chunk(&self, chunk: &[u8]) -> Result<Vec<u8>> {
+        Ok(match self.kind {
+            CompressionKind::None => chunk.to_vec(),
+            CompressionKind::Zlib => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(chunk)?;
+                encoder.finish()?
+            }
+            CompressionKind::Snappy => snap::raw::Encoder::new()
+                .compress_vec(chunk)
+                .or_else(|e| df_execution_err!("orc: snappy compression error: {e}"))?,
+            CompressionKind::Lz4 => lz4_flex::block::compress(chunk),
+            CompressionKind::Zstd => zstd::bulk::compress(chunk, 1)?,
+            CompressionKind::Lzo => {
+                return df_unimplemented_err!("orc: LZO compression is not supported");
+            }
+        })
+    }
+
+    fn decompress_chunk(&self, chunk: &[u8], output: &mut Vec<u8>) -> Result<()> {
+        match self.kind {
+            CompressionKind::None => output.extend_from_slice(chunk),
+            CompressionKind::Zlib => {
+                flate2::read::DeflateDecoder::new(chunk).read_to_end(output)?;
+            }
+            CompressionKind::Snappy => {
+                let decompressed = snap::raw::Decoder::new()
+                    .decompress_vec(chunk)
+                    .or_else(|e| df_execution_err!("orc: snappy decompression error: {e}"))?;
+                output.extend_from_slice(&decompressed);
+            }
+            CompressionKind::Lz4 => {
+                let decompressed = lz4_flex::block::decompress(chunk, self.block_size)
+                    .or_else(|e| df_execution_err!("orc: lz4 decompression error: {e}"))?;
+                output.extend_from_slice(&decompressed);
+            }
+            CompressionKind::Zstd => {
+                zstd::stream::read::Decoder::new(chunk)?.read_to_end(output)?;
+            }
+            CompressionKind::Lzo => {
+                return df_unimplemented_err!("orc: LZO compression is not supported");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use datafusion::common::Result;
+
+    use crate::orc::{compression::Compression, proto::CompressionKind};
+
+    #[test]
+    fn test_decompress() -> Result<()> {
+        let data = b"hello hello hello hello orc".repeat(10);
+
+        // original chunk followed by a zlib chunk
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&data)?;
+        let compressed = encoder.finish()?;
+
+        let mut input = vec![];
+        let header = (data.len() as u32) << 1 | 1;
+        input.extend_from_slice(&header.to_le_bytes()[..3]);
+        input.extend_from_slice(&data);
+        let header = (compressed.len() as u32) << 1;
+        input.extend_from_slice(&header.to_le_bytes()[..3]);
+        input.extend_from_slice(&compressed);
+
+        let compression = Compression {
+            kind: CompressionKind::Zlib,
+            block_size: 262144,
+        };
+        assert_eq!(
+            compression.decompress(&input)?,
+            [data.clone(), data].concat()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress() -> Result<()> {
+        let data = b"hello hello hello hello orc".repeat(100);
+        for kind in [
+            CompressionKind::None,
+            CompressionKind::Zlib,
+            CompressionKind::Snappy,
+            CompressionKind::Lz4,
+            CompressionKind::Zstd,
+        ] {
+            let compression = Compression {
+                kind,
+                block_size: 1000,
+            };
+            let compressed = compression.compress(&data)?;
+            assert_eq!(compression.decompress(&compressed)?, data);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1225 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, ops::Range, sync::Arc};
+
+use arrow::{
+    array::*,
+    buffer::{BooleanBuffer, BooleanBufferBuilder, Buffer, NullBuffer, OffsetBuffer},
+    datatypes::*,
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use datafusion::common::Result;
+use prost::Message;
+
+use crate::{
+    df_execution_err, df_unimplemented_err,
+    orc::{
+        compression::Compression,
+        proto::{
+            ColumnEncoding, ColumnEncodingKind, ColumnStatistics, Footer, Metadata, PostScript,
+            StreamKind, StripeFooter, StripeInformation, Type, TypeKind,
+        },
+        rle::{read_varint_i128, BoolRleDecoder, ByteRleDecoder, IntRleDecoder, RleVersion},
+    },
+    spark_datetime::{date_to_timestamp, ZoneId},
+};
+
+/// days from unix epoch to 2015-01-01, the base of orc timestamp seconds
+const ORC_TIMESTAMP_BASE_DAYS: i32 = 16436;
+const TAIL_READ_SIZE: u64 = 16384;
+
+/// random access input of an orc file
+pub trait OrcInput {
+    fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>>;
+}
+
+/// reads the tail of an orc file and decodes its stripes into record batches
+pub struct OrcFileReader<I: OrcInput> {
+    input: I,
+    compression: Compression,
+    footer: Footer,
+    metadata: Metadata,
+    schema: SchemaRef,
+}
+
+impl<I: OrcInput> OrcFileReader<I> {
+    pub fn try_new(input: I, file_size: u64) -> Result<Self> {
+        if file_size < 4 {
+            return df_execution_err!("orc: file is too small: {file_size} bytes");
+        }
+        let mut tail_start = file_size - file_size.min(TAIL_READ_SIZE);
+        let mut tail = input.read_range(tail_start..file_size)?;
+
+        let ps_len = tail[tail.len() - 1] as usize;
+        if ps_len + 1 > tail.len() {
+            return df_execution_err!("orc: invalid postscript length: {ps_len}");
+        }
+        let ps_start = tail.len() - 1 - ps_len;
+        let postscript: PostScript = decode_proto(&tail[ps_start..tail.len() - 1], "postscript")?;
+        if postscript.magic() != "ORC" {
+            return df_execution_err!("orc: invalid magic: {}", postscript.magic());
+        }
+        let compression = Compression {
+            kind: postscript.compression(),
+            block_size: postscript.compression_block_size.unwrap_or(262144) as usize,
+        };
+
+        // read again if footer and metadata are not covered by the first read
+        let footer_len = postscript.footer_length() as usize;
+        let metadata_len = postscript.metadata_length() as usize;
+        let tail_len = (1 + ps_len + footer_len + metadata_len) as u64;
+        if tail_len > file_size {
+            return df_execution_err!("orc: invalid file tail length: {tail_len}");
+        }
+        if file_size - tail_len < tail_start {
+            tail_start = file_size - tail_len;
+            tail = input.read_range(tail_start..file_size)?;
+        }
+
+        let footer_end = tail.len() - 1 - ps_len;
+        let footer_start = footer_end - footer_len;
+        let metadata_start = footer_start - metadata_len;
+        let footer: Footer = decode_proto(
+            &compression.decompress(&tail[footer_start..footer_end])?,
+            "footer",
+        )?;
+        let metadata: Metadata = decode_proto(
+            &compression.decompress(&tail[metadata_start..footer_start])?,
+            "metadata",
+        )?;
+        let schema = Arc::new(orc_schema(&footer.types)?);
+
+        Ok(Self {
+            input,
+            compression,
+            footer,
+            metadata,
+            schema,
+        })
+    }
+
+    /// arrow schema of the top-level columns
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    pub fn stripes(&self) -> &[StripeInformation] {
+        &self.footer.stripes
+    }
+
+    /// statistics of a top-level column in the specified stripe
+    pub fn stripe_column_statistics(
+        &self,
+        stripe_idx: usize,
+        field_idx: usize,
+    ) -> Option<&ColumnStatistics> {
+        let column_id = *self.footer.types[0].subtypes.get(field_idx)? as usize;
+        self.metadata
+            .stripe_stats
+            .get(stripe_idx)?
+            .col_stats
+            .get(column_id)
+    }
+
+    /// creates a reader of the projected top-level columns of the specified
+    /// stripe, which decodes the stripe incrementally into batches
+    pub fn stripe_reader(&self, stripe_idx: usize, projection: &[usize]) -> Result<StripeReader> {
+        let stripe = &self.footer.stripes[stripe_idx];
+        let num_rows = stripe.number_of_rows() as usize;
+        let footer_start = stripe.offset() + stripe.index_length() + stripe.data_length();
+        let stripe_footer: StripeFooter = decode_proto(
+            &self.compression.decompress(
+                &self
+                    .input
+                    .read_range(footer_start..footer_start + stripe.footer_length())?,
+            )?,
+            "stripe footer",
+        )?;
+        let writer_zone = match &stripe_footer.writer_timezone {
+            Some(tz) => match ZoneId::parse(tz) {
+                Some(zone) => zone,
+                None => return df_execution_err!("orc: unsupported writer timezone: {tz}"),
+            },
+            None => ZoneId::UTC,
+        };
+
+        let types = &self.footer.types;
+        let column_ids = projection
+            .iter()
+            .map(|&i| types[0].subtypes[i])
+            .collect::<Vec<_>>();
+        let mut included = vec![false; types.len()];
+        for &column_id in &column_ids {
+            include_column(types, column_id, &mut included);
+        }
+
+        // streams are stored contiguously from the beginning of the stripe,
+        // only the data streams of included columns are read
+        let mut streams = HashMap::new();
+        let mut stream_start = stripe.offset();
+        for stream in &stripe_footer.streams {
+            let range = stream_start..stream_start + stream.length();
+            stream_start = range.end;
+
+            let column = stream.column();
+            let kind = match stream.kind.and_then(|k| StreamKind::try_from(k).ok()) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            let is_data_stream = matches!(
+                kind,
+                StreamKind::Present
+                    | StreamKind::Data
+                    | StreamKind::Length
+                    | StreamKind::DictionaryData
+                    | StreamKind::Secondary
+            );
+            if is_data_stream && included.get(column as usize) == Some(&true) && !range.is_empty() {
+                let data = self
+                    .compression
+                    .decompress(&self.input.read_range(range)?)?;
+                streams.insert((column, kind), data);
+            }
+        }
+
+        let mut stripe_streams = StripeStreams {
+            types,
+            streams,
+            encodings: stripe_footer.columns,
+            writer_zone,
+        };
+        let schema = Arc::new(self.schema.project(projection)?);
+        let columns = column_ids
+            .iter()
+            .zip(schema.fields())
+            .map(|(&column_id, field)| stripe_streams.column_reader(column_id, field.data_type()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(StripeReader {
+            schema,
+            columns,
+            num_rows,
+            num_rows_read: 0,
+        })
+    }
+}
+
+/// decodes the projected columns of a stripe into batches. the data streams
+/// are decompressed once, while values are decoded batch by batch so that
+/// only the batch being read is materialized
+pub struct StripeReader {
+    schema: SchemaRef,
+    columns: Vec<ColumnReader>,
+    num_rows: usize,
+    num_rows_read: usize,
+}
+
+impl StripeReader {
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// decodes the next batch of at most `batch_size` rows, returns None if
+    /// all rows of the stripe are read
+    pub fn next_batch(&mut self, batch_size: usize) -> Result<Option<RecordBatch>> {
+        let num_rows = batch_size.max(1).min(self.num_rows - self.num_rows_read);
+        if num_rows == 0 {
+            return Ok(None);
+        }
+        let columns = self
+            .columns
+            .iter_mut()
+            .map(|column| column.next_array(num_rows))
+            .collect::<Result<Vec<_>>>()?;
+        self.num_rows_read += num_rows;
+        Ok(Some(RecordBatch::try_new_with_options(
+            self.schema.clone(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )?))
+    }
+}
+
+fn decode_proto<M: Message + Default>(bytes: &[u8], name: &str) -> Result<M> {
+    M::decode(bytes).or_else(|e| df_execution_err!("orc: error decoding {name}: {e}"))
+}
+
+fn include_column(types: &[Type], column_id: u32, included: &mut [bool]) {
+    included[column_id as usize] = true;
+    for &child in &types[column_id as usize].subtypes {
+        include_column(types, child, included);
+    }
+}
+
+fn orc_schema(types: &[Type]) -> Result<Schema> {
+    let root = match types.first() {
+        Some(root) if root.kind() == TypeKind::Struct => root,
+        _ => return df_execution_err!("orc: root type must be struct"),
+    };
+    let fields = root
+        .field_names
+        .iter()
+        .zip(&root.subtypes)
+        .map(|(name, &column_id)| Ok(Field::new(name, orc_data_type(types, column_id)?, true)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Schema::new(fields))
+}
+
+fn orc_data_type(types: &[Type], column_id: u32) -> Result<DataType> {
+    let ty = match types.get(column_id as usize) {
+        Some(ty) => ty,
+        None => return df_execution_err!("orc: missing type of column {column_id}"),
+    };
+    let child_type = |i: usize| match ty.subtypes.get(i) {
+        Some(&child) => orc_data_type(types, child),
+        None => df_execution_err!("orc: missing subtype of column {column_id}"),
+    };
+
+    Ok(match ty.kind() {
+        TypeKind::Boolean => DataType::Boolean,
+        TypeKind::Byte => DataType::Int8,
+        TypeKind::Short => DataType::Int16,
+        TypeKind::Int => DataType::Int32,
+        TypeKind::Long => DataType::Int64,
+        TypeKind::Float => DataType::Float32,
+        TypeKind::Double => DataType::Float64,
+        TypeKind::String | TypeKind::Varchar | TypeKind::Char => DataType::Utf8,
+        TypeKind::Binary => DataType::Binary,
+        TypeKind::Timestamp | TypeKind::TimestampInstant => {
+            DataType::Timestamp(TimeUnit::Microsecond, None)
+        }
+        TypeKind::Date => DataType::Date32,
+        TypeKind::Decimal => match ty.precision() {
+            0 => DataType::Decimal128(38, 10), // hive 0.11 decimals have no precision
+            precision => DataType::Decimal128(precision as u8, ty.scale() as i8),
+        },
+        TypeKind::List => DataType::List(Arc::new(Field::new("item", child_type(0)?, true))),
+        TypeKind::Map => {
+            let entries_type = DataType::Struct(Fields::from(vec![
+                Field::new("key", child_type(0)?, false),
+                Field::new("value", child_type(1)?, true),
+            ]));
+            DataType::Map(Arc::new(Field::new("entries", entries_type, false)), false)
+        }
+        TypeKind::Struct => DataType::Struct(
+            ty.field_names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| Ok(Field::new(name, child_type(i)?, true)))
+                .collect::<Result<Fields>>()?,
+        ),
+        TypeKind::Union => return df_unimplemented_err!("orc: union type is not supported"),
+    })
+}
+
+// decompressed data streams of a stripe, which are moved into the readers of
+// their columns
+struct StripeStreams<'a> {
+    types: &'a [Type],
+    streams: HashMap<(u32, StreamKind), Vec<u8>>,
+    encodings: Vec<ColumnEncoding>,
+    writer_zone: ZoneId,
+}
+
+impl StripeStreams<'_> {
+    fn take_stream(&mut self, column_id: u32, kind: StreamKind) -> Vec<u8> {
+        self.streams.remove(&(column_id, kind)).unwrap_or_default()
+    }
+
+    fn encoding(&self, column_id: u32) -> ColumnEncodingKind {
+        self.encodings
+            .get(column_id as usize)
+            .map(|encoding| encoding.kind())
+            .unwrap_or(ColumnEncodingKind::Direct)
+    }
+
+    fn int_decoder(&mut self, column_id: u32, kind: StreamKind, signed: bool) -> IntRleDecoder {
+        let rle = match self.encoding(column_id) {
+            ColumnEncodingKind::Direct | ColumnEncodingKind::Dictionary => RleVersion::V1,
+            ColumnEncodingKind::DirectV2 | ColumnEncodingKind::DictionaryV2 => RleVersion::V2,
+        };
+        IntRleDecoder::new(self.take_stream(column_id, kind), signed, rle)
+    }
+
+    fn column_reader(&mut self, column_id: u32, data_type: &DataType) -> Result<ColumnReader> {
+        let present = self
+            .streams
+            .remove(&(column_id, StreamKind::Present))
+            .map(BoolRleDecoder::new);
+
+        let values = match data_type {
+            DataType::Boolean => ValuesReader::Boolean(BoolRleDecoder::new(
+                self.take_stream(column_id, StreamKind::Data),
+            )),
+            DataType::Int8 => ValuesReader::Byte(ByteRleDecoder::new(
+                self.take_stream(column_id, StreamKind::Data),
+            )),
+            DataType::Int16 | DataType::Int32 | DataType::Int64 | DataType::Date32 => {
+                ValuesReader::Int(self.int_decoder(column_id, StreamKind::Data, true))
+            }
+            DataType::Float32 | DataType::Float64 => ValuesReader::Float(RawReader::new(
+                self.take_stream(column_id, StreamKind::Data),
+            )),
+            DataType::Utf8 | DataType::Binary => match self.encoding(column_id) {
+                ColumnEncodingKind::Dictionary | ColumnEncodingKind::DictionaryV2 => {
+                    // dictionary entries are materialized into direct values
+                    let dict_size = self.encodings[column_id as usize].dictionary_size() as usize;
+                    let dict_lengths = self
+                        .int_decoder(column_id, StreamKind::Length, false)
+                        .next_values(dict_size)?;
+                    let dict_data = self.take_stream(column_id, StreamKind::DictionaryData);
+                    let mut dict_offsets = Vec::with_capacity(dict_size + 1);
+                    dict_offsets.push(0usize);
+                    for length in dict_lengths {
+                        dict_offsets.push(dict_offsets[dict_offsets.len() - 1] + length as usize);
+                    }
+                    if dict_offsets[dict_size] > dict_data.len() {
+                        return df_execution_err!("orc: unexpected end of dictionary");
+                    }
+                    ValuesReader::Dictionary {
+                        indices: self.int_decoder(column_id, StreamKind::Data, false),
+                        dict_offsets,
+                        dict_data,
+                    }
+                }
+                _ => ValuesReader::Bytes {
+                    lengths: self.int_decoder(column_id, StreamKind::Length, false),
+                    data: RawReader::new(self.take_stream(column_id, StreamKind::Data)),
+                },
+            },
+            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+                let zone = match self.types[column_id as usize].kind() {
+                    TypeKind::TimestampInstant => ZoneId::UTC,
+                    _ => self.writer_zone,
+                };
+                let base_seconds = match date_to_timestamp(ORC_TIMESTAMP_BASE_DAYS, zone) {
+                    Some(base_micros) => base_micros / 1_000_000,
+                    None => return df_execution_err!("orc: invalid timestamp base"),
+                };
+                ValuesReader::Timestamp {
+                    seconds: self.int_decoder(column_id, StreamKind::Data, true),
+                    nanos: self.int_decoder(column_id, StreamKind::Secondary, false),
+                    base_seconds,
+                }
+            }
+            DataType::Decimal128(..) => ValuesReader::Decimal {
+                values: RawReader::new(self.take_stream(column_id, StreamKind::Data)),
+                scales: self.int_decoder(column_id, StreamKind::Secondary, true),
+            },
+            DataType::Struct(fields) => {
+                ValuesReader::Struct(self.children_readers(column_id, fields)?)
+            }
+            DataType::List(field) => {
+                let child_id = self.types[column_id as usize].subtypes[0];
+                ValuesReader::List {
+                    lengths: self.int_decoder(column_id, StreamKind::Length, false),
+                    child: Box::new(self.column_reader(child_id, field.data_type())?),
+                }
+            }
+            DataType::Map(entries_field, _) => {
+                let entry_fields = match entries_field.data_type() {
+                    DataType::Struct(fields) => fields,
+                    other => return df_execution_err!("orc: invalid map entries type: {other}"),
+                };
+                ValuesReader::Map {
+                    lengths: self.int_decoder(column_id, StreamKind::Length, false),
+                    entries: self.children_readers(column_id, entry_fields)?,
+                }
+            }
+            other => return df_unimplemented_err!("orc: reading {other} is not supported"),
+        };
+        Ok(ColumnReader {
+            data_type: data_type.clone(),
+            present,
+            values,
+        })
+    }
+
+    fn children_readers(&mut self, column_id: u32, fields: &Fields) -> Result<Vec<ColumnReader>> {
+        let child_ids = self.types[column_id as usize].subtypes.clone();
+        if child_ids.len() != fields.len() {
+            return df_execution_err!("orc: invalid number of subtypes of column {column_id}");
+        }
+        child_ids
+            .into_iter()
+            .zip(fields)
+            .map(|(child_id, field)| self.column_reader(child_id, field.data_type()))
+            .collect()
+    }
+}
+
+// reader of a column, which keeps the positions of its streams between batches
+struct ColumnReader {
+    data_type: DataType,
+    present: Option<BoolRleDecoder>,
+    values: ValuesReader,
+}
+
+enum ValuesReader {
+    Boolean(BoolRleDecoder),
+    Byte(ByteRleDecoder),
+    Int(IntRleDecoder),
+    Float(RawReader),
+    Bytes {
+        lengths: IntRleDecoder,
+        data: RawReader,
+    },
+    Dictionary {
+        indices: IntRleDecoder,
+        dict_offsets: Vec<usize>,
+        dict_data: Vec<u8>,
+    },
+    Timestamp {
+        seconds: IntRleDecoder,
+        nanos: IntRleDecoder,
+        base_seconds: i64,
+    },
+    Decimal {
+        values: RawReader,
+        scales: IntRleDecoder,
+    },
+    Struct(Vec<ColumnReader>),
+    List {
+        lengths: IntRleDecoder,
+        child: Box<ColumnReader>,
+    },
+    Map {
+        lengths: IntRleDecoder,
+        entries: Vec<ColumnReader>,
+    },
+}
+
+// a stream of unencoded values
+struct RawReader {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl RawReader {
+    fn new(data: Vec<u8>) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn read(&mut self, len: usize) -> Result<&[u8]> {
+        if self.pos + len > self.data.len() {
+            return df_execution_err!("orc: unexpected end of stream");
+        }
+        self.pos += len;
+        Ok(&self.data[self.pos - len..self.pos])
+    }
+}
+
+impl ColumnReader {
+    fn next_array(&mut self, num_rows: usize) -> Result<ArrayRef> {
+        // values of all streams (including children of nested columns) exist only
+        // for non-null rows
+        let present = match &mut self.present {
+            Some(present) => Some(present.next_values(num_rows)?),
+            None => None,
+        };
+        let num_values = match &present {
+            Some(present) => present.count_set_bits(),
+            None => num_rows,
+        };
+        let nulls = present.clone().map(NullBuffer::new);
+        let data_type = &self.data_type;
+
+        macro_rules! read_primitive {
+            ($arrowty:ty, $values:expr) => {{
+                let values = spread($values, &present);
+                Arc::new(PrimitiveArray::<$arrowty>::new(values.into(), nulls))
+            }};
+        }
+        macro_rules! read_ints {
+            ($arrowty:ty, $values:expr) => {{
+                read_primitive!(
+                    $arrowty,
+                    $values
+                        .into_iter()
+                        .map(|v| v as <$arrowty as ArrowPrimitiveType>::Native)
+                )
+            }};
+        }
+        macro_rules! read_floats {
+            ($arrowty:ty, $nativety:ty, $data:expr) => {{
+                const SIZE: usize = std::mem::size_of::<$nativety>();
+                let values = $data
+                    .read(num_values * SIZE)?
+                    .chunks_exact(SIZE)
+                    .map(|bytes| <$nativety>::from_le_bytes(bytes.try_into().unwrap()));
+                read_primitive!($arrowty, values)
+            }};
+        }
+
+        Ok(match &mut self.values {
+            ValuesReader::Boolean(decoder) => {
+                let values = spread(decoder.next_values(num_values)?.iter(), &present);
+                let mut builder = BooleanBufferBuilder::new(num_rows);
+                builder.append_slice(&values);
+                Arc::new(BooleanArray::new(builder.finish(), nulls))
+            }
+            ValuesReader::Byte(decoder) => {
+                let values = decoder.next_values(num_values)?;
+                read_primitive!(Int8Type, values.into_iter().map(|v| v as i8))
+            }
+            ValuesReader::Int(decoder) => {
+                let values = decoder.next_values(num_values)?;
+                match data_type {
+                    DataType::Int16 => read_ints!(Int16Type, values),
+                    DataType::Int32 => read_ints!(Int32Type, values),
+                    DataType::Int64 => read_ints!(Int64Type, values),
+                    _ => read_ints!(Date32Type, values),
+                }
+            }
+            ValuesReader::Float(data) => match data_type {
+                DataType::Float32 => read_floats!(Float32Type, f32, data),
+                _ => read_floats!(Float64Type, f64, data),
+            },
+            ValuesReader::Bytes { lengths, data } => {
+                let lengths = lengths.next_values(num_values)?;
+                let (offsets, total_len) = lengths_to_offsets(lengths, &present)?;
+                let values = Buffer::from_slice_ref(data.read(total_len)?);
+                bytes_array(data_type, offsets, values, nulls)?
+            }
+            ValuesReader::Dictionary {
+                indices,
+                dict_offsets,
+                dict_data,
+            } => {
+                let dict_size = dict_offsets.len() - 1;
+                let mut lengths = Vec::with_capacity(num_values);
+                let mut values = vec![];
+                for index in indices.next_values(num_values)? {
+                    let index = index as usize;
+                    if index >= dict_size {
+                        return df_execution_err!("orc: invalid dictionary index: {index}");
+                    }
+                    let entry = &dict_data[dict_offsets[index]..dict_offsets[index + 1]];
+                    lengths.push(entry.len() as i64);
+                    values.extend_from_slice(entry);
+                }
+                let (offsets, _) = lengths_to_offsets(lengths, &present)?;
+                bytes_array(data_type, offsets, Buffer::from_vec(values), nulls)?
+            }
+            ValuesReader::Timestamp {
+                seconds,
+                nanos,
+                base_seconds,
+            } => {
+                let seconds = seconds.next_values(num_values)?;
+                let nanos = nanos.next_values(num_values)?;
+                let values = seconds.into_iter().zip(nanos).map(|(seconds, nanos)| {
+                    // nanos are stored with the number of trailing zeros in the lowest 3 bits
+                    let zeros = nanos & 7;
+                    let mut nanos = nanos >> 3;
+                    if zeros != 0 {
+                        nanos *= 10i64.pow(zeros as u32 + 1);
+                    }
+                    let mut seconds = seconds + *base_seconds;
+                    if seconds < 0 && nanos > 999_999 {
+                        seconds -= 1;
+                    }
+                    seconds * 1_000_000 + nanos / 1000
+                });
+                read_primitive!(TimestampMicrosecondType, values)
+            }
+            ValuesReader::Decimal { values, scales } => {
+                let (precision, scale) = match data_type {
+                    &DataType::Decimal128(precision, scale) => (precision, scale),
+                    other => return df_execution_err!("orc: invalid decimal type: {other}"),
+                };
+                let decimals = scales
+                    .next_values(num_values)?
+                    .into_iter()
+                    .map(|value_scale| {
+                        let value = read_varint_i128(&values.data, &mut values.pos)?;
+                        match rescale_decimal(value, value_scale, scale as i64) {
+                            Some(value) => Ok(value),
+                            None => df_execution_err!("orc: decimal overflow: {value}"),
+                        }
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let decimals = spread(decimals, &present);
+                Arc::new(
+                    Decimal128Array::new(decimals.into(), nulls)
+                        .with_precision_and_scale(precision, scale)?,
+                )
+            }
+            ValuesReader::Struct(children) => {
+                let children = children
+                    .iter_mut()
+                    .map(|child| child.next_array(num_values))
+                    .collect::<Result<Vec<_>>>()?;
+                let children = spread_array(children, &present)?;
+                make_array(
+                    ArrayData::builder(data_type.clone())
+                        .len(num_rows)
+                        .child_data(children.into_iter().map(|c| c.to_data()).collect())
+                        .nulls(nulls)
+                        .build()?,
+                )
+            }
+            ValuesReader::List { lengths, child } => {
+                let lengths = lengths.next_values(num_values)?;
+                let (offsets, num_children) = lengths_to_offsets(lengths, &present)?;
+                let field = match data_type {
+                    DataType::List(field) => field.clone(),
+                    other => return df_execution_err!("orc: invalid list type: {other}"),
+                };
+                Arc::new(ListArray::new(
+                    field,
+                    OffsetBuffer::new(offsets.into()),
+                    child.next_array(num_children)?,
+                    nulls,
+                ))
+            }
+            ValuesReader::Map { lengths, entries } => {
+                let lengths = lengths.next_values(num_values)?;
+                let (offsets, num_children) = lengths_to_offsets(lengths, &present)?;
+                let entries_type = match data_type {
+                    DataType::Map(entries_field, _) => entries_field.data_type().clone(),
+                    other => return df_execution_err!("orc: invalid map type: {other}"),
+                };
+                let children = entries
+                    .iter_mut()
+                    .map(|child| Ok(child.next_array(num_children)?.to_data()))
+                    .collect::<Result<Vec<_>>>()?;
+                let entries = ArrayData::builder(entries_type)
+                    .len(num_children)
+                    .child_data(children)
+                    .build()?;
+                make_array(
+                    ArrayData::builder(data_type.clone())
+                        .len(num_rows)
+                        .add_buffer(Buffer::from_vec(offsets))
+                        .add_child_data(entries)
+                        .nulls(nulls)
+                        .build()?,
+                )
+            }
+        })
+    }
+}
+
+// converts lengths of non-null values to offsets of all rows, returns the
+// offsets and the total length
+fn lengths_to_offsets(
+    lengths: Vec<i64>,
+    present: &Option<BooleanBuffer>,
+) -> Result<(Vec<i32>, usize)> {
+    let lengths = spread(lengths, present);
+    let mut offsets = Vec::with_capacity(lengths.len() + 1);
+    let mut offset = 0i32;
+    offsets.push(offset);
+    for length in lengths {
+        offset = match i32::try_from(length)
+            .ok()
+            .and_then(|l| offset.checked_add(l))
+        {
+            Some(offset) => offset,
+            None => return df_execution_err!("orc: invalid length: {length}"),
+        };
+        offsets.push(offset);
+    }
+    Ok((offsets, offset as usize))
+}
+
+fn bytes_array(
+    data_type: &DataType,
+    offsets: Vec<i32>,
+    values: Buffer,
+    nulls: Option<NullBuffer>,
+) -> Result<ArrayRef> {
+    Ok(make_array(
+        ArrayData::builder(data_type.clone())
+            .len(offsets.len() - 1)
+            .add_buffer(Buffer::from_vec(offsets))
+            .add_buffer(values)
+            .nulls(nulls)
+            .build()?,
+    ))
+}
+
+// spreads values of non-null rows to all rows, filling default values in null
+// rows
+fn spread<T: Default>(
+    values: impl IntoIterator<Item = T>,
+    present: &Option<BooleanBuffer>,
+) -> Vec<T> {
+    let mut values = values.into_iter();
+    match present {
+        Some(present) => present
+            .iter()
+            .map(|valid| match valid {
+                true => values.next().unwrap_or_default(),
+                false => T::default(),
+            })
+            .collect(),
+        None => values.collect(),
+    }
+}
+
+fn spread_array(arrays: Vec<ArrayRef>, present: &Option<BooleanBuffer>) -> Result<Vec<ArrayRef>> {
+    let present = match present {
+        Some(present) if present.count_set_bits() < present.len() => present,
+        _ => return Ok(arrays),
+    };
+    let mut next_index = 0;
+    let indices = UInt32Array::from_iter(present.iter().map(|valid| {
+        valid.then(|| {
+            next_index += 1;
+            next_index - 1
+        })
+    }));
+    Ok(arrays
+        .iter()
+        .map(|array| arrow::compute::take(array, &indices, None))
+        .collect::<std::result::Result<_, _>>()?)
+}
+
+// rescales a decimal value, rounding half up like java's BigDecimal
+fn rescale_decimal(value: i128, from_scale: i64, to_scale: i64) -> Option<i128> {
+    if from_scale <= to_scale {
+        let factor = 10i128.checked_pow(u32::try_from(to_scale - from_scale).ok()?)?;
+        value.checked_mul(factor)
+    } else {
+        let factor = 10i128.checked_pow(u32::try_from(from_scale - to_scale).ok()?)?;
+        let (quotient, remainder) = (value / factor, value % factor);
+        if remainder.abs() * 2 >= factor {
+            Some(quotient + value.signum())
+        } else {
+            Some(quotient)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{ops::Range, sync::Arc};
+
+    use arrow::{array::*, compute::concat_batches, datatypes::*, record_batch::RecordBatch};
+    use datafusion::{assert_batches_eq, common::Result};
+    use prost::Message;
+
+    use crate::orc::{
+        compression::Compression,
+        proto::*,
+        reader::{rescale_decimal, OrcFileReader, OrcInput},
+        writer::{OrcFileWriter, OrcWriterOptions},
+    };
+
+    struct BytesInput(Vec<u8>);
+
+    impl OrcInput for BytesInput {
+        fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>> {
+            Ok(self.0[range.start as usize..range.end as usize].to_vec())
+        }
+    }
+
+    // an uncompressed file with columns a: int = [1, null, 3] and
+    // b: string = ["x", "yz", null]
+    fn build_test_file() -> Vec<u8> {
+        let streams = [
+            (1, StreamKind::Present, vec![0xff, 0xa0]),
+            (1, StreamKind::Data, vec![0xfe, 0x02, 0x06]),
+            (2, StreamKind::Present, vec![0xff, 0xc0]),
+            (2, StreamKind::Data, b"xyz".to_vec()),
+            (2, StreamKind::Length, vec![0xfe, 0x01, 0x02]),
+        ];
+        let mut file = b"ORC".to_vec();
+        let stripe_offset = file.len() as u64;
+        for (_, _, data) in &streams {
+            file.extend_from_slice(data);
+        }
+        let data_length = file.len() as u64 - stripe_offset;
+
+        let direct = ColumnEncoding {
+            kind: Some(ColumnEncodingKind::Direct as i32),
+            dictionary_size: None,
+        };
+        let stripe_footer = StripeFooter {
+            streams: streams
+                .iter()
+                .map(|(column, kind, data)| Stream {
+                    kind: Some(*kind as i32),
+                    column: Some(*column),
+                    length: Some(data.len() as u64),
+                })
+                .collect(),
+            columns: vec![direct.clone(), direct.clone(), direct],
+            writer_timezone: None,
+        };
+        let stripe_footer_bytes = stripe_footer.encode_to_vec();
+        file.extend_from_slice(&stripe_footer_bytes);
+
+        let metadata_bytes = Metadata::default().encode_to_vec();
+        file.extend_from_slice(&metadata_bytes);
+
+        let new_type = |kind: TypeKind| Type {
+            kind: Some(kind as i32),
+            ..Default::default()
+        };
+        let footer = Footer {
+            header_length: Some(3),
+            content_length: Some(file.len() as u64 - 3),
+            stripes: vec![StripeInformation {
+                offset: Some(stripe_offset),
+                index_length: Some(0),
+                data_length: Some(data_length),
+                footer_length: Some(stripe_footer_bytes.len() as u64),
+                number_of_rows: Some(3),
+            }],
+            types: vec![
+                Type {
+                    subtypes: vec![1, 2],
+                    field_names: vec!["a".to_string(), "b".to_string()],
+                    ..new_type(TypeKind::Struct)
+                },
+                new_type(TypeKind::Int),
+                new_type(TypeKind::String),
+            ],
+            number_of_rows: Some(3),
+            ..Default::default()
+        };
+        let footer_bytes = footer.encode_to_vec();
+        file.extend_from_slice(&footer_bytes);
+
+        let postscript = PostScript {
+            footer_length: Some(footer_bytes.len() as u64),
+            compression: Some(CompressionKind::None as i32),
+            metadata_length: Some(metadata_bytes.len() as u64),
+            magic: Some("ORC".to_string()),
+            ..Default::default()
+        };
+        let postscript_bytes = postscript.encode_to_vec();
+        file.extend_from_slice(&postscript_bytes);
+        file.push(postscript_bytes.len() as u8);
+        file
+    }
+
+    #[test]
+    fn test_read_stripe() -> Result<()> {
+        let file = build_test_file();
+        let file_size = file.len() as u64;
+        let reader = OrcFileReader::try_new(BytesInput(file), file_size)?;
+        assert_eq!(
+            reader.schema().as_ref(),
+            &Schema::new(vec![
+                Field::new("a", DataType::Int32, true),
+                Field::new("b", DataType::Utf8, true),
+            ])
+        );
+        assert_eq!(reader.stripes().len(), 1);
+
+        let batch = reader.stripe_reader(0, &[0, 1])?.next_batch(1024)?.unwrap();
+        assert_batches_eq!(
+            vec![
+                "+---+----+",
+                "| a | b  |",
+                "+---+----+",
+                "| 1 | x  |",
+                "|   | yz |",
+                "| 3 |    |",
+                "+---+----+",
+            ],
+            &[batch]
+        );
+
+        let mut stripe_reader = reader.stripe_reader(0, &[1])?;
+        assert_eq!(stripe_reader.num_rows(), 3);
+        let batch = stripe_reader.next_batch(2)?.unwrap();
+        let expected: ArrayRef = Arc::new(StringArray::from(vec![Some("x"), Some("yz")]));
+        assert_eq!(batch.column(0), &expected);
+        let batch = stripe_reader.next_batch(2)?.unwrap();
+        let expected: ArrayRef = Arc::new(StringArray::from(vec![None::<&str>]));
+        assert_eq!(batch.column(0), &expected);
+        assert!(stripe_reader.next_batch(2)?.is_none());
+        Ok(())
+    }
+
+    fn write_file(batch: &RecordBatch, options: OrcWriterOptions) -> Result<Vec<u8>> {
+        let mut writer = OrcFileWriter::try_new(vec![], batch.schema(), options)?;
+        writer.write(batch)?;
+        writer.close()
+    }
+
+    // reads the projected columns of all stripes in batches of `batch_size`
+    // rows, then concatenates them into a single batch
+    fn read_file(file: Vec<u8>, projection: &[usize], batch_size: usize) -> Result<RecordBatch> {
+        let file_size = file.len() as u64;
+        let reader = OrcFileReader::try_new(BytesInput(file), file_size)?;
+        let schema = Arc::new(reader.schema().project(projection)?);
+        let mut batches = vec![];
+        for stripe_idx in 0..reader.stripes().len() {
+            let mut stripe_reader = reader.stripe_reader(stripe_idx, projection)?;
+            while let Some(batch) = stripe_reader.next_batch(batch_size)? {
+                assert!(batch.num_rows() <= batch_size);
+                batches.push(batch);
+            }
+        }
+        Ok(concat_batches(&schema, &batches)?)
+    }
+
+    fn assert_round_trip(batch: &RecordBatch, batch_size: usize) -> Result<()> {
+        for kind in [CompressionKind::None, CompressionKind::Zlib] {
+            let options = OrcWriterOptions {
+                compression: Compression {
+                    kind,
+                    block_size: 1024,
+                },
+                row_index_stride: 4,
+                ..Default::default()
+            };
+            let file = write_file(batch, options)?;
+            let projection = (0..batch.num_columns()).collect::<Vec<_>>();
+            let read_batch = read_file(file, &projection, batch_size)?;
+            assert_eq!(read_batch.num_rows(), batch.num_rows());
+            for i in 0..batch.num_columns() {
+                assert_eq!(read_batch.column(i), batch.column(i), "column {i}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_nulls() -> Result<()> {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "bool",
+                Arc::new(BooleanArray::from(vec![
+                    Some(true),
+                    None,
+                    Some(false),
+                    Some(true),
+                    None,
+                    None,
+                    Some(false),
+                    Some(true),
+                    Some(true),
+                    None,
+                    Some(false),
+                ])) as ArrayRef,
+            ),
+            (
+                "byte",
+                Arc::new(Int8Array::from_iter(
+                    (0..11).map(|i| (i % 3 != 0).then_some(i as i8)),
+                )),
+            ),
+            (
+                "short",
+                Arc::new(Int16Array::from_iter(
+                    (0..11).map(|i| (i % 2 == 0).then_some(-i)),
+                )),
+            ),
+            (
+                "long",
+                Arc::new(Int64Array::from_iter(
+                    (0..11).map(|i| (i != 5).then_some(i64::MAX - i)),
+                )),
+            ),
+            (
+                "float",
+                Arc::new(Float32Array::from_iter(
+                    (0..11).map(|i| (i > 3).then_some(i as f32)),
+                )),
+            ),
+            ("double", Arc::new(Float64Array::from(vec![None; 11]))),
+            (
+                "string",
+                Arc::new(StringArray::from_iter(
+                    (0..11).map(|i| (i % 4 != 1).then(|| "s".repeat(i))),
+                )),
+            ),
+            (
+                "binary",
+                Arc::new(BinaryArray::from_iter(
+                    (0..11).map(|i| (i % 5 != 2).then(|| vec![i as u8; i % 3])),
+                )),
+            ),
+            (
+                "date",
+                Arc::new(Date32Array::from_iter(
+                    (0..11).map(|i| (i != 0).then_some(i * -1000)),
+                )),
+            ),
+            (
+                "decimal",
+                Arc::new(
+                    Decimal128Array::from_iter(
+                        (0..11).map(|i| (i % 3 != 2).then_some(i as i128 * -123456789)),
+                    )
+                    .with_precision_and_scale(20, 4)?,
+                ),
+            ),
+        ])?;
+        assert_round_trip(&batch, 3)
+    }
+
+    #[test]
+    fn test_round_trip_nested() -> Result<()> {
+        let list = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), None, Some(3)]),
+            None,
+            Some(vec![]),
+            Some(vec![Some(4)]),
+            None,
+            Some(vec![Some(5), Some(6), Some(7), None]),
+            Some(vec![Some(8)]),
+        ]);
+
+        let mut map_builder = MapBuilder::new(
+            Some(MapFieldNames {
+                entry: "entries".to_string(),
+                key: "key".to_string(),
+                value: "value".to_string(),
+            }),
+            StringBuilder::new(),
+            Int64Builder::new(),
+        );
+        for i in 0..7 {
+            if i % 3 == 1 {
+                map_builder.append(false)?;
+                continue;
+            }
+            for j in 0..i {
+                map_builder.keys().append_value(format!("k{j}"));
+                map_builder
+                    .values()
+                    .append_option((j != 1).then_some(j as i64));
+            }
+            map_builder.append(true)?;
+        }
+        let map = map_builder.finish();
+
+        // struct<a: string, b: list<int>> with null rows
+        let struct_fields = Fields::from(vec![
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", list.data_type().clone(), true),
+        ]);
+        let strukt = StructArray::new(
+            struct_fields.clone(),
+            vec![
+                Arc::new(StringArray::from_iter(
+                    (0..7).map(|i| (i != 3).then(|| format!("v{i}"))),
+                )),
+                Arc::new(list.clone()),
+            ],
+            Some(vec![true, true, false, true, false, true, true].into()),
+        );
+
+        // list<struct<a: string, b: list<int>>>
+        let list_of_struct = ListArray::new(
+            Arc::new(Field::new("item", DataType::Struct(struct_fields), true)),
+            OffsetBuffer::new(vec![0, 2, 2, 5, 5, 6, 7, 7].into()),
+            Arc::new(strukt.clone()),
+            Some(vec![true, true, true, false, true, true, true].into()),
+        );
+
+        let batch = RecordBatch::try_from_iter(vec![
+            ("list", Arc::new(list) as ArrayRef),
+            ("map", Arc::new(map)),
+            ("struct", Arc::new(strukt)),
+            ("list_of_struct", Arc::new(list_of_struct)),
+        ])?;
+        for batch_size in [1, 2, 5, 7] {
+            assert_round_trip(&batch, batch_size)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_timestamps() -> Result<()> {
+        let timestamps = TimestampMicrosecondArray::from(vec![
+            Some(-62_135_596_800_000_000), // 0001-01-01 00:00:00
+            Some(-2_208_988_800_000_001),  // 1899-12-31 23:59:59.999999
+            Some(-86_400_000_001),         // 1969-12-30 23:59:59.999999
+            Some(-1_500_000),              // 1969-12-31 23:59:58.5
+            None,
+            Some(0),
+            Some(2_147_483_648_000_000),   // 2038-01-19 03:14:08
+            Some(4_102_444_800_123_456),   // 2100-01-01 00:00:00.123456
+            Some(253_402_300_799_999_999), // 9999-12-31 23:59:59.999999
+        ]);
+        let batch = RecordBatch::try_from_iter(vec![("ts", Arc::new(timestamps) as ArrayRef)])?;
+        assert_round_trip(&batch, 4)
+    }
+
+    #[test]
+    fn test_multi_stripe_projection() -> Result<()> {
+        let num_rows = 100;
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "a",
+                Arc::new(Int32Array::from_iter(
+                    (0..num_rows).map(|i| (i % 7 != 0).then_some(i)),
+                )) as ArrayRef,
+            ),
+            (
+                "b",
+                Arc::new(StringArray::from_iter_values(
+                    (0..num_rows).map(|i| format!("row-{i}")),
+                )),
+            ),
+            (
+                "c",
+                Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(
+                    (0..num_rows)
+                        .map(|i| (i % 5 != 0).then(|| vec![Some(i as i64); i as usize % 4])),
+                )),
+            ),
+            (
+                "d",
+                Arc::new(Float64Array::from_iter_values(
+                    (0..num_rows).map(|i| i as f64 / 3.0),
+                )),
+            ),
+        ])?;
+
+        // every row group of 10 rows exceeds the stripe size and is flushed into
+        // its own stripe
+        let options = OrcWriterOptions {
+            stripe_size: 1,
+            row_index_stride: 10,
+            ..Default::default()
+        };
+        let file = write_file(&batch, options)?;
+        let file_size = file.len() as u64;
+        let reader = OrcFileReader::try_new(BytesInput(file.clone()), file_size)?;
+        assert_eq!(reader.stripes().len(), 10);
+
+        for projection in [vec![3, 1], vec![2], vec![0, 2, 3]] {
+            let read_batch = read_file(file.clone(), &projection, 7)?;
+            assert_eq!(read_batch.num_rows(), num_rows as usize);
+            for (i, &column_idx) in projection.iter().enumerate() {
+                assert_eq!(read_batch.column(i), batch.column(column_idx));
+            }
+        }
+
+        // columns are not decoded when nothing is projected
+        let read_batch = read_file(file, &[], 7)?;
+        assert_eq!(read_batch.num_rows(), num_rows as usize);
+        assert_eq!(read_batch.num_columns(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescale_decimal() {
+        assert_eq!(rescale_decimal(12345, 2, 4), Some(1234500));
+        assert_eq!(rescale_decimal(12345, 3, 1), Some(123));
+        assert_eq!(rescale_decimal(12355, 2, 1), Some(1236));
+        assert_eq!(rescale_decimal(-12355, 2, 1), Some(-1236));
+        assert_eq!(rescale_decimal(1, 0, 40), None);
+    }
+}
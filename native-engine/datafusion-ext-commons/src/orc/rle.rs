@@ -0,0 +1,716 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoders and encoders of the run length encodings used by orc streams, see
+//! https://orc.apache.org/specification/ORCv1/ for the details.
+
+use arrow::buffer::{BooleanBuffer, BooleanBufferBuilder};
+use datafusion::common::Result;
+
+use crate::df_execution_err;
+
+/// integer run length encoding version, v1 is used by `DIRECT` and
+/// `DICTIONARY` columns, v2 by `DIRECT_V2` and `DICTIONARY_V2` columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RleVersion {
+    V1,
+    V2,
+}
+
+/// decodes n bytes of a byte run length encoded stream
+pub fn read_byte_rle(data: &[u8], n: usize) -> Result<Vec<u8>> {
+    let mut pos = 0;
+    take_decoded(&mut Vec::with_capacity(n), n, |values| {
+        read_byte_rle_run(data, &mut pos, values)
+    })
+}
+
+/// decodes n bits of a boolean stream, which is byte run length encoded
+/// with the most significant bit first
+pub fn read_bool_rle(data: &[u8], n: usize) -> Result<BooleanBuffer> {
+    BoolRleDecoder::new(data.to_vec()).next_values(n)
+}
+
+/// decodes n integers of an integer run length encoded stream
+pub fn read_int_rle(data: &[u8], n: usize, signed: bool, version: RleVersion) -> Result<Vec<i64>> {
+    let mut pos = 0;
+    take_decoded(&mut Vec::with_capacity(n), n, |values| match version {
+        RleVersion::V1 => read_int_rle_v1_run(data, &mut pos, signed, values),
+        RleVersion::V2 => read_int_rle_v2_run(data, &mut pos, signed, values),
+    })
+}
+
+/// incremental decoder of a byte run length encoded stream, values of a
+/// partially consumed run are kept for the next call
+pub struct ByteRleDecoder {
+    data: Vec<u8>,
+    pos: usize,
+    pending: Vec<u8>,
+}
+
+impl ByteRleDecoder {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            pos: 0,
+            pending: vec![],
+        }
+    }
+
+    /// decodes the next n bytes
+    pub fn next_values(&mut self, n: usize) -> Result<Vec<u8>> {
+        let (data, pos) = (&self.data, &mut self.pos);
+        take_decoded(&mut self.pending, n, |values| {
+            read_byte_rle_run(data, pos, values)
+        })
+    }
+}
+
+/// incremental decoder of a boolean stream
+pub struct BoolRleDecoder {
+    bytes: ByteRleDecoder,
+    current: u8,
+    num_remaining_bits: usize,
+}
+
+impl BoolRleDecoder {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self {
+            bytes: ByteRleDecoder::new(data),
+            current: 0,
+            num_remaining_bits: 0,
+        }
+    }
+
+    /// decodes the next n bits
+    pub fn next_values(&mut self, n: usize) -> Result<BooleanBuffer> {
+        let num_bytes = (n.saturating_sub(self.num_remaining_bits) + 7) / 8;
+        let mut bytes = self.bytes.next_values(num_bytes)?.into_iter();
+        let mut builder = BooleanBufferBuilder::new(n);
+        for _ in 0..n {
+            if self.num_remaining_bits == 0 {
+                self.current = bytes.next().unwrap_or_default();
+                self.num_remaining_bits = 8;
+            }
+            builder.append(self.current & (0x80 >> (8 - self.num_remaining_bits)) != 0);
+            self.num_remaining_bits -= 1;
+        }
+        Ok(builder.finish())
+    }
+}
+
+/// incremental decoder of an integer run length encoded stream
+pub struct IntRleDecoder {
+    data: Vec<u8>,
+    pos: usize,
+    signed: bool,
+    version: RleVersion,
+    pending: Vec<i64>,
+}
+
+impl IntRleDecoder {
+    pub fn new(data: Vec<u8>, signed: bool, version: RleVersion) -> Self {
+        Self {
+            data,
+            pos: 0,
+            signed,
+            version,
+            pending: vec![],
+        }
+    }
+
+    /// decodes the next n integers
+    pub fn next_values(&mut self, n: usize) -> Result<Vec<i64>> {
+        let (data, pos, signed, version) = (&self.data, &mut self.pos, self.signed, self.version);
+        take_decoded(&mut self.pending, n, |values| match version {
+            RleVersion::V1 => read_int_rle_v1_run(data, pos, signed, values),
+            RleVersion::V2 => read_int_rle_v2_run(data, pos, signed, values),
+        })
+    }
+}
+
+// decodes runs into the pending values until there are n values, then takes
+// the first n of them
+fn take_decoded<T>(
+    pending: &mut Vec<T>,
+    n: usize,
+    mut decode_run: impl FnMut(&mut Vec<T>) -> Result<()>,
+) -> Result<Vec<T>> {
+    while pending.len() < n {
+        decode_run(pending)?;
+    }
+    let remaining = pending.split_off(n);
+    Ok(std::mem::replace(pending, remaining))
+}
+
+fn read_byte_rle_run(data: &[u8], pos: &mut usize, values: &mut Vec<u8>) -> Result<()> {
+    let control = read_u8(data, pos)? as i8;
+    if control >= 0 {
+        let value = read_u8(data, pos)?;
+        values.extend(std::iter::repeat(value).take(control as usize + 3));
+    } else {
+        let len = -(control as isize) as usize;
+        values.extend_from_slice(read_bytes(data, pos, len)?);
+    }
+    Ok(())
+}
+
+fn read_int_rle_v1_run(
+    data: &[u8],
+    pos: &mut usize,
+    signed: bool,
+    values: &mut Vec<i64>,
+) -> Result<()> {
+    let control = read_u8(data, pos)? as i8;
+    if control >= 0 {
+        let delta = read_u8(data, pos)? as i8 as i64;
+        let base = read_varint(data, pos, signed)?;
+        for i in 0..control as i64 + 3 {
+            values.push(base.wrapping_add(i * delta));
+        }
+    } else {
+        for _ in 0..-(control as i64) {
+            values.push(read_varint(data, pos, signed)?);
+        }
+    }
+    Ok(())
+}
+
+fn read_int_rle_v2_run(
+    data: &[u8],
+    pos: &mut usize,
+    signed: bool,
+    values: &mut Vec<i64>,
+) -> Result<()> {
+    let b0 = read_u8(data, pos)?;
+    let decode_sign = |v: u64| if signed { zigzag_decode(v) } else { v as i64 };
+
+    match b0 >> 6 {
+        // SHORT_REPEAT
+        0 => {
+            let width = ((b0 >> 3) & 7) as usize + 1;
+            let count = (b0 & 7) as usize + 3;
+            let value = decode_sign(read_be(data, pos, width)?);
+            values.extend(std::iter::repeat(value).take(count));
+        }
+
+        // DIRECT
+        1 => {
+            let width = decode_bit_width((b0 >> 1) & 0x1f);
+            let len = ((b0 as usize & 1) << 8 | read_u8(data, pos)? as usize) + 1;
+            for v in read_bit_packed(data, pos, len, width)? {
+                values.push(decode_sign(v));
+            }
+        }
+
+        // PATCHED_BASE
+        2 => {
+            let width = decode_bit_width((b0 >> 1) & 0x1f);
+            let len = ((b0 as usize & 1) << 8 | read_u8(data, pos)? as usize) + 1;
+            let b2 = read_u8(data, pos)?;
+            let b3 = read_u8(data, pos)?;
+            let base_width = ((b2 >> 5) & 7) as usize + 1;
+            let patch_width = decode_bit_width(b2 & 0x1f);
+            let patch_gap_width = ((b3 >> 5) & 7) as usize + 1;
+            let patch_list_len = (b3 & 0x1f) as usize;
+
+            // base value is stored in sign-magnitude form
+            let base = read_be(data, pos, base_width)?;
+            let sign_mask = 1u64 << (base_width * 8 - 1);
+            let base = if base & sign_mask != 0 {
+                -((base & !sign_mask) as i64)
+            } else {
+                base as i64
+            };
+
+            let unpacked = read_bit_packed(data, pos, len, width)?;
+            let patches = read_bit_packed(
+                data,
+                pos,
+                patch_list_len,
+                closest_fixed_bits(patch_gap_width + patch_width),
+            )?;
+            let patch_mask = bit_mask(patch_width);
+            let mut patches = patches
+                .into_iter()
+                .map(|entry| ((entry >> patch_width) as usize, entry & patch_mask));
+
+            // a gap of 255 with a zero patch means the gap continues in the next entry
+            let mut next_patch = || {
+                let mut gap = 0;
+                while let Some((patch_gap, patch)) = patches.next() {
+                    gap += patch_gap;
+                    if patch_gap != 255 || patch != 0 {
+                        return Some((gap, patch));
+                    }
+                }
+                None
+            };
+
+            let mut patch = next_patch();
+            let mut patch_idx = patch.map(|(gap, _)| gap);
+            for (i, v) in unpacked.into_iter().enumerate() {
+                match patch {
+                    Some((_, patch_value)) if patch_idx == Some(i) => {
+                        let patched = v | patch_value.checked_shl(width as u32).unwrap_or(0);
+                        values.push(base.wrapping_add(patched as i64));
+                        patch = next_patch();
+                        patch_idx = patch.map(|(gap, _)| i + gap);
+                    }
+                    _ => values.push(base.wrapping_add(v as i64)),
+                }
+            }
+        }
+
+        // DELTA
+        _ => {
+            let encoded_width = (b0 >> 1) & 0x1f;
+            let len = ((b0 as usize & 1) << 8 | read_u8(data, pos)? as usize) + 1;
+            let first = read_varint(data, pos, signed)?;
+            let delta_base = read_varint(data, pos, true)?;
+
+            values.push(first);
+            if encoded_width == 0 {
+                // fixed delta
+                let mut prev = first;
+                for _ in 1..len {
+                    prev = prev.wrapping_add(delta_base);
+                    values.push(prev);
+                }
+            } else if len > 1 {
+                let width = decode_bit_width(encoded_width);
+                let mut prev = first.wrapping_add(delta_base);
+                values.push(prev);
+                for delta in read_bit_packed(data, pos, len - 2, width)? {
+                    prev = if delta_base < 0 {
+                        prev.wrapping_sub(delta as i64)
+                    } else {
+                        prev.wrapping_add(delta as i64)
+                    };
+                    values.push(prev);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// reads a base 128 varint, zigzag decoded if signed
+pub fn read_varint(data: &[u8], pos: &mut usize, signed: bool) -> Result<i64> {
+    let mut v = 0u64;
+    let mut shift = 0;
+    loop {
+        let b = read_u8(data, pos)?;
+        if shift < 64 {
+            v |= ((b & 0x7f) as u64) << shift;
+        }
+        shift += 7;
+        if b & 0x80 == 0 {
+            break;
+        }
+    }
+    Ok(if signed { zigzag_decode(v) } else { v as i64 })
+}
+
+/// reads a zigzag encoded base 128 varint of unbounded length, which is used
+/// by the decimal values
+pub fn read_varint_i128(data: &[u8], pos: &mut usize) -> Result<i128> {
+    let mut v = 0u128;
+    let mut shift = 0;
+    loop {
+        let b = read_u8(data, pos)?;
+        if shift < 128 {
+            v |= ((b & 0x7f) as u128) << shift;
+        }
+        shift += 7;
+        if b & 0x80 == 0 {
+            break;
+        }
+    }
+    Ok((v >> 1) as i128 ^ -((v & 1) as i128))
+}
+
+/// encodes bytes with byte run length encoding: runs of 3 to 130 repeated
+/// bytes, or up to 128 literal bytes
+pub fn write_byte_rle(values: &[u8], output: &mut Vec<u8>) {
+    let mut pos = 0;
+    while pos < values.len() {
+        let run_len = values[pos..]
+            .iter()
+            .take(130)
+            .take_while(|&&v| v == values[pos])
+            .count();
+        if run_len >= 3 {
+            output.push((run_len - 3) as u8);
+            output.push(values[pos]);
+            pos += run_len;
+            continue;
+        }
+
+        // literals until the next run of 3 repeated bytes
+        let start = pos;
+        while pos < values.len() && pos - start < 128 {
+            if pos + 2 < values.len()
+                && values[pos] == values[pos + 1]
+                && values[pos] == values[pos + 2]
+            {
+                break;
+            }
+            pos += 1;
+        }
+        output.push((-((pos - start) as i32)) as u8);
+        output.extend_from_slice(&values[start..pos]);
+    }
+}
+
+/// encodes integers with the v1 integer run length encoding: runs of 3 to 130
+/// values with a fixed delta in [-128, 127], or up to 128 literal varints
+pub fn write_int_rle_v1(values: &[i64], signed: bool, output: &mut Vec<u8>) {
+    let run_at = |pos: usize| -> (usize, i64) {
+        let delta = match values
+            .get(pos + 1)
+            .and_then(|next| next.checked_sub(values[pos]))
+        {
+            Some(delta) if (-128..=127).contains(&delta) => delta,
+            _ => return (1, 0),
+        };
+        let mut len = 2;
+        while len < 130
+            && pos + len < values.len()
+            && values[pos + len].checked_sub(values[pos + len - 1]) == Some(delta)
+        {
+            len += 1;
+        }
+        (len, delta)
+    };
+
+    let mut pos = 0;
+    while pos < values.len() {
+        let (run_len, delta) = run_at(pos);
+        if run_len >= 3 {
+            output.push((run_len - 3) as u8);
+            output.push(delta as i8 as u8);
+            write_varint(values[pos], signed, output);
+            pos += run_len;
+            continue;
+        }
+
+        // literals until the next run of 3 values
+        let start = pos;
+        while pos < values.len() && pos - start < 128 && (pos == start || run_at(pos).0 < 3) {
+            pos += 1;
+        }
+        output.push((-((pos - start) as i32)) as u8);
+        for &value in &values[start..pos] {
+            write_varint(value, signed, output);
+        }
+    }
+}
+
+/// writes a base 128 varint, zigzag encoded if signed
+pub fn write_varint(value: i64, signed: bool, output: &mut Vec<u8>) {
+    let mut v = if signed {
+        ((value << 1) ^ (value >> 63)) as u64
+    } else {
+        value as u64
+    };
+    while v >= 0x80 {
+        output.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    output.push(v as u8);
+}
+
+/// writes a zigzag encoded base 128 varint of unbounded length
+pub fn write_varint_i128(value: i128, output: &mut Vec<u8>) {
+    let mut v = ((value << 1) ^ (value >> 127)) as u128;
+    while v >= 0x80 {
+        output.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    output.push(v as u8);
+}
+
+fn zigzag_decode(v: u64) -> i64 {
+    (v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+// maps the 5-bit encoded width to the actual bit width
+fn decode_bit_width(encoded: u8) -> usize {
+    match encoded {
+        0..=23 => encoded as usize + 1,
+        24 => 26,
+        25 => 28,
+        26 => 30,
+        27 => 32,
+        28 => 40,
+        29 => 48,
+        30 => 56,
+        _ => 64,
+    }
+}
+
+// rounds a bit width up to the closest width that can be encoded
+fn closest_fixed_bits(width: usize) -> usize {
+    match width {
+        0 => 1,
+        1..=24 => width,
+        25..=26 => 26,
+        27..=28 => 28,
+        29..=30 => 30,
+        31..=32 => 32,
+        33..=40 => 40,
+        41..=48 => 48,
+        49..=56 => 56,
+        _ => 64,
+    }
+}
+
+fn bit_mask(width: usize) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1u64 << width) - 1
+    }
+}
+
+// reads n values of the specified bit width, packed with the most
+// significant bit first. the packed values always end at a byte boundary
+fn read_bit_packed(data: &[u8], pos: &mut usize, n: usize, width: usize) -> Result<Vec<u64>> {
+    let bytes = read_bytes(data, pos, (n * width + 7) / 8)?;
+    let mut values = Vec::with_capacity(n);
+    let mut bit_pos = 0;
+    for _ in 0..n {
+        let mut v = 0u64;
+        let mut remaining = width;
+        while remaining > 0 {
+            let available = 8 - bit_pos % 8;
+            let take = available.min(remaining);
+            let bits = (bytes[bit_pos / 8] >> (available - take)) as u64 & bit_mask(take);
+            v = v << take | bits;
+            remaining -= take;
+            bit_pos += take;
+        }
+        values.push(v);
+    }
+    Ok(values)
+}
+
+// reads a big-endian unsigned integer of the specified bytes
+fn read_be(data: &[u8], pos: &mut usize, num_bytes: usize) -> Result<u64> {
+    Ok(read_bytes(data, pos, num_bytes)?
+        .iter()
+        .fold(0u64, |v, &b| v << 8 | b as u64))
+}
+
+fn read_u8(data: &[u8], pos: &mut usize) -> Result<u8> {
+    Ok(read_bytes(data, pos, 1)?[0])
+}
+
+fn read_bytes<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8]> {
+    if *pos + len > data.len() {
+        return df_execution_err!("orc: unexpected end of stream");
+    }
+    let bytes = &data[*pos..*pos + len];
+    *pos += len;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::common::Result;
+
+    use crate::orc::rle::{
+        read_bool_rle, read_byte_rle, read_int_rle, read_varint_i128, write_byte_rle,
+        write_int_rle_v1, write_varint_i128, BoolRleDecoder, ByteRleDecoder, IntRleDecoder,
+        RleVersion,
+    };
+
+    #[test]
+    fn test_byte_rle() -> Result<()> {
+        let data = [0x61, 0x00, 0xfe, 0x44, 0x45];
+        let mut expected = vec![0u8; 100];
+        expected.extend_from_slice(&[0x44, 0x45]);
+        assert_eq!(read_byte_rle(&data, 102)?, expected);
+
+        let bools = read_bool_rle(&[0xfe, 0x80, 0x40], 10)?;
+        assert_eq!(
+            bools.iter().collect::<Vec<_>>(),
+            vec![true, false, false, false, false, false, false, false, false, true]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_int_rle_v1() -> Result<()> {
+        let data = [0x61, 0x00, 0x07];
+        assert_eq!(
+            read_int_rle(&data, 100, false, RleVersion::V1)?,
+            vec![7; 100]
+        );
+
+        let data = [0x61, 0xff, 0x64];
+        assert_eq!(
+            read_int_rle(&data, 100, false, RleVersion::V1)?,
+            (1..=100).rev().collect::<Vec<_>>()
+        );
+
+        let data = [0xfb, 0x02, 0x03, 0x06, 0x07, 0xb];
+        assert_eq!(
+            read_int_rle(&data, 5, false, RleVersion::V1)?,
+            vec![2, 3, 6, 7, 11]
+        );
+        assert_eq!(
+            read_int_rle(&data, 5, true, RleVersion::V1)?,
+            vec![1, -2, 3, -4, -6]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_int_rle_v2() -> Result<()> {
+        // short repeat
+        let data = [0x0a, 0x27, 0x10];
+        assert_eq!(
+            read_int_rle(&data, 5, false, RleVersion::V2)?,
+            vec![10000; 5]
+        );
+
+        // direct
+        let data = [0x5e, 0x03, 0x5c, 0xa1, 0xab, 0x1e, 0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(
+            read_int_rle(&data, 4, false, RleVersion::V2)?,
+            vec![23713, 43806, 57005, 48879]
+        );
+
+        // patched base
+        let data = [
+            0x8e, 0x13, 0x2b, 0x21, 0x07, 0xd0, 0x1e, 0x00, 0x14, 0x70, 0x28, 0x32, 0x3c, 0x46,
+            0x50, 0x5a, 0x64, 0x6e, 0x78, 0x82, 0x8c, 0x96, 0xa0, 0xaa, 0xb4, 0xbe, 0xfc, 0xe8,
+        ];
+        assert_eq!(
+            read_int_rle(&data, 20, false, RleVersion::V2)?,
+            vec![
+                2030, 2000, 2020, 1000000, 2040, 2050, 2060, 2070, 2080, 2090, 2100, 2110, 2120,
+                2130, 2140, 2150, 2160, 2170, 2180, 2190,
+            ]
+        );
+
+        // delta
+        let data = [0xc6, 0x09, 0x02, 0x02, 0x22, 0x42, 0x42, 0x46];
+        assert_eq!(
+            read_int_rle(&data, 10, false, RleVersion::V2)?,
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+
+        // fixed delta, signed
+        let data = [0xc0, 0x04, 0x13, 0x03];
+        assert_eq!(
+            read_int_rle(&data, 5, true, RleVersion::V2)?,
+            vec![-10, -12, -14, -16, -18]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_varint_i128() -> Result<()> {
+        let mut pos = 0;
+        let data = [
+            0x80, 0x01, 0x03, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+        ];
+        assert_eq!(read_varint_i128(&data, &mut pos)?, 64);
+        assert_eq!(read_varint_i128(&data, &mut pos)?, -2);
+        assert_eq!(read_varint_i128(&data, &mut pos)?, -(1i128 << 69));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_rle() -> Result<()> {
+        let mut values = vec![0u8; 100];
+        values.extend_from_slice(&[0x44, 0x45]);
+        let mut data = vec![];
+        write_byte_rle(&values, &mut data);
+        assert_eq!(data, vec![0x61, 0x00, 0xfe, 0x44, 0x45]);
+
+        let values = (0..1000u32)
+            .map(|i| (i * 7 % 11) as u8 / 4)
+            .collect::<Vec<_>>();
+        let mut data = vec![];
+        write_byte_rle(&values, &mut data);
+        assert_eq!(read_byte_rle(&data, values.len())?, values);
+
+        let values = (1..=100).rev().collect::<Vec<i64>>();
+        let mut data = vec![];
+        write_int_rle_v1(&values, false, &mut data);
+        assert_eq!(data, vec![0x61, 0xff, 0x64]);
+
+        let mut values = vec![1, -2, 3, -4, -6, i64::MIN, i64::MAX, 0, 0, 0, 0];
+        values.extend((0..300).map(|i| i * 100));
+        values.extend((0..300).map(|i| i / 3));
+        let mut data = vec![];
+        write_int_rle_v1(&values, true, &mut data);
+        assert_eq!(
+            read_int_rle(&data, values.len(), true, RleVersion::V1)?,
+            values
+        );
+
+        let mut data = vec![];
+        for value in [64, -2, -(1i128 << 69)] {
+            write_varint_i128(value, &mut data);
+        }
+        let mut pos = 0;
+        assert_eq!(read_varint_i128(&data, &mut pos)?, 64);
+        assert_eq!(read_varint_i128(&data, &mut pos)?, -2);
+        assert_eq!(read_varint_i128(&data, &mut pos)?, -(1i128 << 69));
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_decoders() -> Result<()> {
+        let bytes = (0..1000u32)
+            .map(|i| (i * 7 % 11) as u8 / 4)
+            .collect::<Vec<_>>();
+        let mut data = vec![];
+        write_byte_rle(&bytes, &mut data);
+        let mut decoder = ByteRleDecoder::new(data.clone());
+        let mut decoded = vec![];
+        for n in [1, 2, 129, 300, 568] {
+            decoded.extend(decoder.next_values(n)?);
+        }
+        assert_eq!(decoded, bytes);
+
+        let mut decoder = BoolRleDecoder::new(data);
+        let mut decoded = vec![];
+        for n in [3, 5, 13, 1000, 6979] {
+            decoded.extend(decoder.next_values(n)?.iter());
+        }
+        let expected = bytes
+            .iter()
+            .flat_map(|b| (0..8).map(move |i| b & (0x80 >> i) != 0))
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, expected);
+
+        let ints = (0..1000).map(|i| i * i % 977 - 300).collect::<Vec<i64>>();
+        let mut data = vec![];
+        write_int_rle_v1(&ints, true, &mut data);
+        let mut decoder = IntRleDecoder::new(data, true, RleVersion::V1);
+        let mut decoded = vec![];
+        for n in [0, 1, 127, 128, 744] {
+            decoded.extend(decoder.next_values(n)?);
+        }
+        assert_eq!(decoded, ints);
+        assert!(decoder.next_values(1).is_err());
+        Ok(())
+    }
+}
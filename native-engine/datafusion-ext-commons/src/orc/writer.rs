@@ -0,0 +1,1141 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{io::Write, sync::Arc};
+
+use arrow::{array::*, buffer::BooleanBuffer, datatypes::*, record_batch::RecordBatch};
+use datafusion::common::Result;
+use prost::Message;
+
+use crate::{
+    df_unimplemented_err,
+    orc::{
+        bloom_filter::OrcBloomFilter,
+        compression::Compression,
+        proto::{
+            BinaryStatistics, BloomFilterIndex, BucketStatistics, ColumnEncoding,
+            ColumnEncodingKind, ColumnStatistics, CompressionKind, DateStatistics,
+            DecimalStatistics, DoubleStatistics, Footer, IntegerStatistics, Metadata, PostScript,
+            RowIndex, RowIndexEntry, Stream, StreamKind, StringStatistics, StripeFooter,
+            StripeInformation, StripeStatistics, Type, TypeKind,
+        },
+        rle::{write_byte_rle, write_int_rle_v1, write_varint_i128},
+    },
+};
+
+/// seconds from unix epoch to 2015-01-01 00:00:00 UTC, the base of orc
+/// timestamp seconds
+const ORC_TIMESTAMP_BASE_SECONDS: i64 = 1420070400;
+
+/// version of the writer recorded in the postscript, which tells readers that
+/// the row indexes and statistics are written by a fixed writer (ORC-135)
+const ORC_WRITER_VERSION: u32 = 6;
+
+#[derive(Debug, Clone)]
+pub struct OrcWriterOptions {
+    pub compression: Compression,
+    pub stripe_size: usize,
+    pub row_index_stride: usize,
+    pub bloom_filter_columns: Vec<String>,
+    pub bloom_filter_fpp: f64,
+}
+
+impl Default for OrcWriterOptions {
+    fn default() -> Self {
+        Self {
+            compression: Compression {
+                kind: CompressionKind::Zlib,
+                block_size: 262144,
+            },
+            stripe_size: 67108864,
+            row_index_stride: 10000,
+            bloom_filter_columns: vec![],
+            bloom_filter_fpp: 0.01,
+        }
+    }
+}
+
+/// writes record batches into an orc file. all columns are written with the
+/// `DIRECT` encoding, and every row group starts with a new run and a new
+/// compression chunk, so that the row index positions can be computed after
+/// the whole stripe is encoded.
+pub struct OrcFileWriter<W: Write> {
+    output: W,
+    options: OrcWriterOptions,
+    types: Vec<Type>,
+    root: ColumnWriter,
+    offset: u64,
+    num_rows: u64,
+    stripe_rows: usize,
+    stripes: Vec<StripeInformation>,
+    stripe_stats: Vec<StripeStatistics>,
+}
+
+impl<W: Write> OrcFileWriter<W> {
+    pub fn try_new(mut output: W, schema: SchemaRef, options: OrcWriterOptions) -> Result<Self> {
+        let mut types = vec![];
+        let bloom_filter = |name: &str| {
+            options
+                .bloom_filter_columns
+                .iter()
+                .any(|column| column.eq_ignore_ascii_case(name))
+                .then_some((options.row_index_stride, options.bloom_filter_fpp))
+        };
+        let root = ColumnWriter::try_new(
+            &DataType::Struct(schema.fields().clone()),
+            &mut types,
+            None,
+            &bloom_filter,
+        )?;
+
+        output.write_all(b"ORC")?;
+        Ok(Self {
+            output,
+            options,
+            types,
+            root,
+            offset: 3,
+            num_rows: 0,
+            stripe_rows: 0,
+            stripes: vec![],
+            stripe_stats: vec![],
+        })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        let stride = self.row_index_stride();
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            if self.stripe_rows % stride == 0 {
+                self.root.start_group();
+            }
+            let len = (stride - self.stripe_rows % stride).min(batch.num_rows() - offset);
+            let rows: ArrayRef = Arc::new(StructArray::from(batch.slice(offset, len)));
+            self.root.write(&rows)?;
+            self.stripe_rows += len;
+            offset += len;
+
+            if self.root.mem_size() >= self.options.stripe_size {
+                self.flush_stripe()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// size of the encoded data of the current stripe which is not flushed
+    pub fn buffered_size(&self) -> usize {
+        self.root.mem_size()
+    }
+
+    /// flushes the last stripe and writes the file tail
+    pub fn close(mut self) -> Result<W> {
+        self.flush_stripe()?;
+        let compression = self.options.compression;
+
+        let metadata = Metadata {
+            stripe_stats: std::mem::take(&mut self.stripe_stats),
+        };
+        let metadata_bytes = compression.compress(&metadata.encode_to_vec())?;
+        self.output.write_all(&metadata_bytes)?;
+
+        let mut statistics = vec![];
+        self.root.collect_file_statistics(&mut statistics);
+        let footer = Footer {
+            header_length: Some(3),
+            content_length: Some(self.offset),
+            stripes: std::mem::take(&mut self.stripes),
+            types: std::mem::take(&mut self.types),
+            metadata: vec![],
+            number_of_rows: Some(self.num_rows),
+            statistics,
+            row_index_stride: Some(self.options.row_index_stride as u32),
+            writer: None,
+        };
+        let footer_bytes = compression.compress(&footer.encode_to_vec())?;
+        self.output.write_all(&footer_bytes)?;
+
+        let postscript = PostScript {
+            footer_length: Some(footer_bytes.len() as u64),
+            compression: Some(compression.kind as i32),
+            compression_block_size: Some(compression.block_size as u64),
+            version: vec![0, 12],
+            metadata_length: Some(metadata_bytes.len() as u64),
+            writer_version: Some(ORC_WRITER_VERSION),
+            magic: Some("ORC".to_string()),
+        };
+        let postscript_bytes = postscript.encode_to_vec();
+        self.output.write_all(&postscript_bytes)?;
+        self.output.write_all(&[postscript_bytes.len() as u8])?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+
+    // a zero stride disables row indexes, rows of a stripe are then written
+    // into a single group
+    fn row_index_stride(&self) -> usize {
+        match self.options.row_index_stride {
+            0 => usize::MAX,
+            stride => stride,
+        }
+    }
+
+    fn flush_stripe(&mut self) -> Result<()> {
+        if self.stripe_rows == 0 {
+            return Ok(());
+        }
+        let compression = self.options.compression;
+        let mut stripe = StripeStreams::default();
+        self.root
+            .finish_stripe(&compression, self.options.row_index_stride > 0, &mut stripe)?;
+
+        // index streams are followed by data streams and the stripe footer
+        let stripe_offset = self.offset;
+        let mut streams = vec![];
+        let mut stream_lengths = [0u64; 2];
+        for (i, section) in [stripe.index_streams, stripe.data_streams]
+            .into_iter()
+            .enumerate()
+        {
+            for (column, kind, data) in section {
+                self.output.write_all(&data)?;
+                stream_lengths[i] += data.len() as u64;
+                streams.push(Stream {
+                    kind: Some(kind as i32),
+                    column: Some(column),
+                    length: Some(data.len() as u64),
+                });
+            }
+        }
+        let stripe_footer = StripeFooter {
+            streams,
+            columns: stripe.encodings,
+            writer_timezone: Some("UTC".to_string()),
+        };
+        let stripe_footer_bytes = compression.compress(&stripe_footer.encode_to_vec())?;
+        self.output.write_all(&stripe_footer_bytes)?;
+
+        let [index_length, data_length] = stream_lengths;
+        let footer_length = stripe_footer_bytes.len() as u64;
+        self.stripes.push(StripeInformation {
+            offset: Some(stripe_offset),
+            index_length: Some(index_length),
+            data_length: Some(data_length),
+            footer_length: Some(footer_length),
+            number_of_rows: Some(self.stripe_rows as u64),
+        });
+        self.stripe_stats.push(StripeStatistics {
+            col_stats: stripe.statistics,
+        });
+        self.offset += index_length + data_length + footer_length;
+        self.num_rows += self.stripe_rows as u64;
+        self.stripe_rows = 0;
+        Ok(())
+    }
+}
+
+// encoded streams, encodings and statistics of all columns in a stripe, in
+// the order of column ids
+#[derive(Default)]
+struct StripeStreams {
+    index_streams: Vec<(u32, StreamKind, Vec<u8>)>,
+    data_streams: Vec<(u32, StreamKind, Vec<u8>)>,
+    encodings: Vec<ColumnEncoding>,
+    statistics: Vec<ColumnStatistics>,
+}
+
+struct ColumnWriter {
+    column_id: u32,
+    data_type: DataType,
+    present: StreamWriter,
+    has_nulls: bool,
+    // data streams, in the order of their positions in row index entries
+    streams: Vec<StreamWriter>,
+    children: Vec<ColumnWriter>,
+    group_stats: Vec<Stats>,
+    file_stats: Stats,
+    bloom_filter_options: Option<(usize, f64)>,
+    bloom_filters: Vec<OrcBloomFilter>,
+}
+
+impl ColumnWriter {
+    fn try_new(
+        data_type: &DataType,
+        types: &mut Vec<Type>,
+        bloom_filter_options: Option<(usize, f64)>,
+        top_level_bloom_filter: &dyn Fn(&str) -> Option<(usize, f64)>,
+    ) -> Result<Self> {
+        let column_id = types.len() as u32;
+        types.push(Type::default());
+
+        let data = |buffer| StreamWriter::new(StreamKind::Data, buffer);
+        let length = || StreamWriter::new(StreamKind::Length, StreamBuffer::Int(vec![], false));
+        let secondary =
+            |signed| StreamWriter::new(StreamKind::Secondary, StreamBuffer::Int(vec![], signed));
+        let mut ty = Type::default();
+        let mut child_types = vec![];
+        let (kind, streams) = match data_type {
+            DataType::Boolean => (TypeKind::Boolean, vec![data(StreamBuffer::bool())]),
+            DataType::Int8 => (TypeKind::Byte, vec![data(StreamBuffer::Byte(vec![]))]),
+            DataType::Int16 => (TypeKind::Short, vec![data(StreamBuffer::Int(vec![], true))]),
+            DataType::Int32 => (TypeKind::Int, vec![data(StreamBuffer::Int(vec![], true))]),
+            DataType::Int64 => (TypeKind::Long, vec![data(StreamBuffer::Int(vec![], true))]),
+            DataType::Date32 => (TypeKind::Date, vec![data(StreamBuffer::Int(vec![], true))]),
+            DataType::Float32 => (TypeKind::Float, vec![data(StreamBuffer::Raw(vec![]))]),
+            DataType::Float64 => (TypeKind::Double, vec![data(StreamBuffer::Raw(vec![]))]),
+            DataType::Utf8 => (
+                TypeKind::String,
+                vec![data(StreamBuffer::Raw(vec![])), length()],
+            ),
+            DataType::Binary => (
+                TypeKind::Binary,
+                vec![data(StreamBuffer::Raw(vec![])), length()],
+            ),
+            DataType::Timestamp(TimeUnit::Microsecond, _) => (
+                TypeKind::Timestamp,
+                vec![data(StreamBuffer::Int(vec![], true)), secondary(false)],
+            ),
+            &DataType::Decimal128(precision, scale) => {
+                ty.precision = Some(precision as u32);
+                ty.scale = Some(scale as u32);
+                (
+                    TypeKind::Decimal,
+                    vec![data(StreamBuffer::Raw(vec![])), secondary(true)],
+                )
+            }
+            DataType::List(field) => {
+                child_types.push(field.data_type().clone());
+                (TypeKind::List, vec![length()])
+            }
+            DataType::Map(entries_field, _) => {
+                match entries_field.data_type() {
+                    DataType::Struct(fields) if fields.len() == 2 => {
+                        child_types.extend(fields.iter().map(|f| f.data_type().clone()));
+                    }
+                    other => return df_unimplemented_err!("orc: unsupported map entries: {other}"),
+                }
+                (TypeKind::Map, vec![length()])
+            }
+            DataType::Struct(fields) => {
+                ty.field_names = fields.iter().map(|f| f.name().clone()).collect();
+                child_types.extend(fields.iter().map(|f| f.data_type().clone()));
+                (TypeKind::Struct, vec![])
+            }
+            other => return df_unimplemented_err!("orc: writing {other} is not supported"),
+        };
+
+        // bloom filters are only supported on top-level primitive columns
+        let is_root = column_id == 0;
+        let children = child_types
+            .iter()
+            .enumerate()
+            .map(|(i, child_type)| {
+                let bloom_filter_options = match is_root {
+                    true => top_level_bloom_filter(&ty.field_names[i]),
+                    false => None,
+                };
+                ColumnWriter::try_new(child_type, types, bloom_filter_options, &|_: &str| None)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let bloom_filter_options = bloom_filter_options.filter(|_| {
+            !matches!(
+                kind,
+                TypeKind::Timestamp
+                    | TypeKind::Decimal
+                    | TypeKind::List
+                    | TypeKind::Map
+                    | TypeKind::Struct
+            )
+        });
+
+        ty.kind = Some(kind as i32);
+        ty.subtypes = children.iter().map(|child| child.column_id).collect();
+        types[column_id as usize] = ty;
+
+        Ok(Self {
+            column_id,
+            data_type: data_type.clone(),
+            present: StreamWriter::new(StreamKind::Present, StreamBuffer::bool()),
+            has_nulls: false,
+            streams,
+            children,
+            group_stats: vec![],
+            file_stats: Stats::default(),
+            bloom_filter_options,
+            bloom_filters: vec![],
+        })
+    }
+
+    fn start_group(&mut self) {
+        self.present.start_group();
+        for stream in &mut self.streams {
+            stream.start_group();
+        }
+        self.group_stats.push(Stats::default());
+        if let Some((expected_entries, fpp)) = self.bloom_filter_options {
+            self.bloom_filters
+                .push(OrcBloomFilter::new(expected_entries, fpp));
+        }
+        for child in &mut self.children {
+            child.start_group();
+        }
+    }
+
+    fn mem_size(&self) -> usize {
+        let streams_mem_size = self.streams.iter().map(|s| s.mem_size()).sum::<usize>();
+        let children_mem_size = self.children.iter().map(|c| c.mem_size()).sum::<usize>();
+        self.present.mem_size() + streams_mem_size + children_mem_size
+    }
+
+    fn write(&mut self, array: &ArrayRef) -> Result<()> {
+        let nulls = array.nulls().filter(|nulls| nulls.null_count() > 0);
+        self.present
+            .append_bools(array.len(), nulls.map(|n| n.inner()));
+        self.has_nulls |= nulls.is_some();
+        self.group_stats.last_mut().unwrap().update(array);
+        let mut bloom_filter = self.bloom_filters.last_mut();
+
+        macro_rules! stream {
+            ($i:expr, $variant:ident) => {{
+                match &mut self.streams[$i].buffer {
+                    StreamBuffer::$variant(values, ..) => values,
+                    _ => unreachable!(),
+                }
+            }};
+        }
+        macro_rules! write_ints {
+            ($arrowty:ty) => {{
+                let values = stream!(0, Int);
+                for v in array.as_primitive::<$arrowty>().iter().flatten() {
+                    values.push(v as i64);
+                    if let Some(bf) = bloom_filter.as_mut() {
+                        bf.add_long(v as i64);
+                    }
+                }
+            }};
+        }
+        macro_rules! write_floats {
+            ($arrowty:ty) => {{
+                let values = stream!(0, Raw);
+                for v in array.as_primitive::<$arrowty>().iter().flatten() {
+                    values.extend_from_slice(&v.to_le_bytes());
+                    if let Some(bf) = bloom_filter.as_mut() {
+                        bf.add_double(v as f64);
+                    }
+                }
+            }};
+        }
+        macro_rules! write_bytes {
+            ($values:expr) => {{
+                for v in $values.iter().flatten() {
+                    let v: &[u8] = v.as_ref();
+                    stream!(0, Raw).extend_from_slice(v);
+                    stream!(1, Int).push(v.len() as i64);
+                    if let Some(bf) = bloom_filter.as_mut() {
+                        bf.add_bytes(v);
+                    }
+                }
+            }};
+        }
+
+        match array.data_type() {
+            DataType::Boolean => {
+                let values = array.as_boolean();
+                let valid_values = match nulls {
+                    Some(nulls) => arrow::compute::filter(
+                        values,
+                        &BooleanArray::new(nulls.inner().clone(), None),
+                    )?,
+                    None => array.clone(),
+                };
+                let valid_values = valid_values.as_boolean().values();
+                self.streams[0].append_bools(valid_values.len(), Some(valid_values));
+                if let Some(bf) = bloom_filter.as_mut() {
+                    for v in valid_values.iter() {
+                        bf.add_long(v as i64);
+                    }
+                }
+            }
+            DataType::Int8 => {
+                let values = stream!(0, Byte);
+                for v in array.as_primitive::<Int8Type>().iter().flatten() {
+                    values.push(v as u8);
+                    if let Some(bf) = bloom_filter.as_mut() {
+                        bf.add_long(v as i64);
+                    }
+                }
+            }
+            DataType::Int16 => write_ints!(Int16Type),
+            DataType::Int32 => write_ints!(Int32Type),
+            DataType::Int64 => write_ints!(Int64Type),
+            DataType::Date32 => write_ints!(Date32Type),
+            DataType::Float32 => write_floats!(Float32Type),
+            DataType::Float64 => write_floats!(Float64Type),
+            DataType::Utf8 => write_bytes!(array.as_string::<i32>()),
+            DataType::Binary => write_bytes!(array.as_binary::<i32>()),
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                for v in array
+                    .as_primitive::<TimestampMicrosecondType>()
+                    .iter()
+                    .flatten()
+                {
+                    // seconds are truncated like java.sql.Timestamp, readers
+                    // correct negative seconds with a fraction
+                    let seconds = v / 1_000_000;
+                    let nanos = v.rem_euclid(1_000_000) * 1000;
+                    stream!(0, Int).push(seconds - ORC_TIMESTAMP_BASE_SECONDS);
+                    stream!(1, Int).push(format_nanos(nanos));
+                }
+            }
+            &DataType::Decimal128(_, scale) => {
+                for v in array.as_primitive::<Decimal128Type>().iter().flatten() {
+                    write_varint_i128(v, stream!(0, Raw));
+                    stream!(1, Int).push(scale as i64);
+                }
+            }
+            DataType::List(_) => {
+                let list = array.as_list::<i32>();
+                let offsets = list.value_offsets();
+                let values = list.values();
+                for range in valid_child_ranges(offsets, nulls.map(|n| n.inner())) {
+                    self.children[0].write(&values.slice(range.0, range.1 - range.0))?;
+                }
+                let lengths = stream!(0, Int);
+                for i in 0..list.len() {
+                    if list.is_valid(i) {
+                        lengths.push((offsets[i + 1] - offsets[i]) as i64);
+                    }
+                }
+            }
+            DataType::Map(..) => {
+                let map = array.as_map();
+                let offsets = map.value_offsets();
+                for range in valid_child_ranges(offsets, nulls.map(|n| n.inner())) {
+                    let len = range.1 - range.0;
+                    self.children[0].write(&map.keys().slice(range.0, len))?;
+                    self.children[1].write(&map.values().slice(range.0, len))?;
+                }
+                let lengths = stream!(0, Int);
+                for i in 0..map.len() {
+                    if map.is_valid(i) {
+                        lengths.push((offsets[i + 1] - offsets[i]) as i64);
+                    }
+                }
+            }
+            DataType::Struct(_) => {
+                // children only contain values of non-null structs
+                let columns = array.as_struct().columns();
+                for (child, column) in self.children.iter_mut().zip(columns) {
+                    match nulls {
+                        Some(nulls) => child.write(&arrow::compute::filter(
+                            column,
+                            &BooleanArray::new(nulls.inner().clone(), None),
+                        )?)?,
+                        None => child.write(column)?,
+                    }
+                }
+            }
+            other => return df_unimplemented_err!("orc: writing {other} is not supported"),
+        }
+        Ok(())
+    }
+
+    // encodes all streams of this column and its children, the present stream
+    // is omitted if there are no nulls in the stripe
+    fn finish_stripe(
+        &mut self,
+        compression: &Compression,
+        with_index: bool,
+        stripe: &mut StripeStreams,
+    ) -> Result<()> {
+        let num_groups = self.group_stats.len();
+        let mut positions = vec![vec![]; num_groups];
+        let mut streams = vec![];
+        if self.has_nulls {
+            streams.push(&mut self.present);
+        } else {
+            self.present.clear();
+        }
+        streams.extend(self.streams.iter_mut());
+
+        for stream in streams {
+            let (data, stream_positions) = stream.finish(compression)?;
+            for (group_positions, p) in positions.iter_mut().zip(stream_positions) {
+                group_positions.extend(p);
+            }
+            stripe
+                .data_streams
+                .push((self.column_id, stream.kind, data));
+        }
+
+        let group_stats = std::mem::take(&mut self.group_stats);
+        if with_index {
+            let row_index = RowIndex {
+                entry: positions
+                    .into_iter()
+                    .zip(&group_stats)
+                    .map(|(positions, stats)| RowIndexEntry {
+                        positions,
+                        statistics: Some(stats.to_proto(&self.data_type)),
+                    })
+                    .collect(),
+            };
+            stripe.index_streams.push((
+                self.column_id,
+                StreamKind::RowIndex,
+                compression.compress(&row_index.encode_to_vec())?,
+            ));
+
+            let bloom_filters = std::mem::take(&mut self.bloom_filters);
+            if !bloom_filters.is_empty() {
+                let bloom_filter_index = BloomFilterIndex {
+                    bloom_filter: bloom_filters.iter().map(|bf| bf.to_proto()).collect(),
+                };
+                stripe.index_streams.push((
+                    self.column_id,
+                    StreamKind::BloomFilterUtf8,
+                    compression.compress(&bloom_filter_index.encode_to_vec())?,
+                ));
+            }
+        }
+        self.bloom_filters.clear();
+
+        let mut stripe_stats = Stats::default();
+        for stats in &group_stats {
+            stripe_stats.merge(stats);
+        }
+        self.file_stats.merge(&stripe_stats);
+        stripe
+            .statistics
+            .push(stripe_stats.to_proto(&self.data_type));
+        stripe.encodings.push(ColumnEncoding {
+            kind: Some(ColumnEncodingKind::Direct as i32),
+            dictionary_size: None,
+        });
+        self.has_nulls = false;
+
+        for child in &mut self.children {
+            child.finish_stripe(compression, with_index, stripe)?;
+        }
+        Ok(())
+    }
+
+    fn collect_file_statistics(&self, statistics: &mut Vec<ColumnStatistics>) {
+        statistics.push(self.file_stats.to_proto(&self.data_type));
+        for child in &self.children {
+            child.collect_file_statistics(statistics);
+        }
+    }
+}
+
+// merges child value ranges of non-null list/map entries
+fn valid_child_ranges(offsets: &[i32], nulls: Option<&BooleanBuffer>) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for i in 0..offsets.len() - 1 {
+        let (start, end) = (offsets[i] as usize, offsets[i + 1] as usize);
+        if start == end || nulls.map(|nulls| !nulls.value(i)).unwrap_or(false) {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.1 == start => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
+// nanos are stored with the number of trailing decimal zeros in the lowest 3
+// bits
+fn format_nanos(nanos: i64) -> i64 {
+    if nanos == 0 || nanos % 100 != 0 {
+        return nanos << 3;
+    }
+    let mut nanos = nanos / 100;
+    let mut zeros = 1;
+    while nanos % 10 == 0 && zeros < 7 {
+        nanos /= 10;
+        zeros += 1;
+    }
+    nanos << 3 | zeros
+}
+
+enum StreamBuffer {
+    Bool(BooleanBufferBuilder),
+    Byte(Vec<u8>),
+    Int(Vec<i64>, bool),
+    Raw(Vec<u8>),
+}
+
+impl StreamBuffer {
+    fn bool() -> Self {
+        StreamBuffer::Bool(BooleanBufferBuilder::new(0))
+    }
+}
+
+// values of a stream in the current stripe, with the start of every row group
+struct StreamWriter {
+    kind: StreamKind,
+    buffer: StreamBuffer,
+    group_starts: Vec<usize>,
+}
+
+impl StreamWriter {
+    fn new(kind: StreamKind, buffer: StreamBuffer) -> Self {
+        Self {
+            kind,
+            buffer,
+            group_starts: vec![],
+        }
+    }
+
+    fn len(&self) -> usize {
+        match &self.buffer {
+            StreamBuffer::Bool(values) => values.len(),
+            StreamBuffer::Byte(values) | StreamBuffer::Raw(values) => values.len(),
+            StreamBuffer::Int(values, _) => values.len(),
+        }
+    }
+
+    fn mem_size(&self) -> usize {
+        match &self.buffer {
+            StreamBuffer::Bool(values) => values.len() / 8,
+            StreamBuffer::Byte(values) | StreamBuffer::Raw(values) => values.len(),
+            StreamBuffer::Int(values, _) => values.len() * 8,
+        }
+    }
+
+    fn start_group(&mut self) {
+        self.group_starts.push(self.len());
+    }
+
+    fn append_bools(&mut self, len: usize, values: Option<&BooleanBuffer>) {
+        if let StreamBuffer::Bool(builder) = &mut self.buffer {
+            match values {
+                Some(values) => values.iter().for_each(|v| builder.append(v)),
+                None => builder.append_n(len, true),
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        match &mut self.buffer {
+            StreamBuffer::Bool(values) => *values = BooleanBufferBuilder::new(0),
+            StreamBuffer::Byte(values) | StreamBuffer::Raw(values) => values.clear(),
+            StreamBuffer::Int(values, _) => values.clear(),
+        }
+        self.group_starts.clear();
+    }
+
+    // encodes and compresses each row group separately, returns the stream
+    // data with the row index positions of each group
+    fn finish(&mut self, compression: &Compression) -> Result<(Vec<u8>, Vec<Vec<u64>>)> {
+        let len = self.len();
+        let bool_bytes = match &mut self.buffer {
+            // orc packs booleans with the most significant bit first
+            StreamBuffer::Bool(values) => values
+                .finish()
+                .sliced()
+                .iter()
+                .map(|b| b.reverse_bits())
+                .collect(),
+            _ => vec![],
+        };
+
+        let mut output = vec![];
+        let mut positions = vec![];
+        let mut segment = vec![];
+        for (i, &start) in self.group_starts.iter().enumerate() {
+            let end = self.group_starts.get(i + 1).cloned().unwrap_or(len);
+            let mut group_positions = vec![output.len() as u64];
+            if compression.kind != CompressionKind::None {
+                group_positions.push(0); // offset in the decompressed chunk
+            }
+
+            segment.clear();
+            let encoded = match &self.buffer {
+                StreamBuffer::Bool(_) => {
+                    // a group may start in the middle of a byte, which is
+                    // shared with the previous group
+                    let end_byte = if end == len { (len + 7) / 8 } else { end / 8 };
+                    write_byte_rle(&bool_bytes[start / 8..end_byte], &mut segment);
+                    group_positions.extend([0, (start % 8) as u64]);
+                    &segment[..]
+                }
+                StreamBuffer::Byte(values) => {
+                    write_byte_rle(&values[start..end], &mut segment);
+                    group_positions.push(0);
+                    &segment[..]
+                }
+                StreamBuffer::Int(values, signed) => {
+                    write_int_rle_v1(&values[start..end], *signed, &mut segment);
+                    group_positions.push(0);
+                    &segment[..]
+                }
+                StreamBuffer::Raw(values) => &values[start..end],
+            };
+            output.extend(compression.compress(encoded)?);
+            positions.push(group_positions);
+        }
+        self.clear();
+        Ok((output, positions))
+    }
+}
+
+// column statistics of a row group, stripe or file
+#[derive(Debug, Clone, Default)]
+struct Stats {
+    num_values: u64,
+    has_null: bool,
+    int: Option<(i64, i64, Option<i64>)>,
+    double: Option<(f64, f64, f64)>,
+    string: Option<(String, String)>,
+    bytes_sum: i64,
+    true_count: u64,
+    date: Option<(i32, i32)>,
+    decimal: Option<(i128, i128)>,
+}
+
+impl Stats {
+    fn update(&mut self, array: &ArrayRef) {
+        self.num_values += (array.len() - array.null_count()) as u64;
+        self.has_null |= array.null_count() > 0;
+
+        macro_rules! update_ints {
+            ($arrowty:ty) => {{
+                for v in array.as_primitive::<$arrowty>().iter().flatten() {
+                    self.update_int(v as i64);
+                }
+            }};
+        }
+        match array.data_type() {
+            DataType::Boolean => self.true_count += array.as_boolean().true_count() as u64,
+            DataType::Int8 => update_ints!(Int8Type),
+            DataType::Int16 => update_ints!(Int16Type),
+            DataType::Int32 => update_ints!(Int32Type),
+            DataType::Int64 => update_ints!(Int64Type),
+            DataType::Float32 => {
+                for v in array.as_primitive::<Float32Type>().iter().flatten() {
+                    self.update_double(v as f64);
+                }
+            }
+            DataType::Float64 => {
+                for v in array.as_primitive::<Float64Type>().iter().flatten() {
+                    self.update_double(v);
+                }
+            }
+            DataType::Utf8 => {
+                for v in array.as_string::<i32>().iter().flatten() {
+                    self.bytes_sum = self.bytes_sum.saturating_add(v.len() as i64);
+                    match &mut self.string {
+                        Some((min, max)) => {
+                            if v < min.as_str() {
+                                *min = v.to_string();
+                            }
+                            if v > max.as_str() {
+                                *max = v.to_string();
+                            }
+                        }
+                        None => self.string = Some((v.to_string(), v.to_string())),
+                    }
+                }
+            }
+            DataType::Binary => {
+                for v in array.as_binary::<i32>().iter().flatten() {
+                    self.bytes_sum = self.bytes_sum.saturating_add(v.len() as i64);
+                }
+            }
+            DataType::Date32 => {
+                for v in array.as_primitive::<Date32Type>().iter().flatten() {
+                    let (min, max) = self.date.unwrap_or((v, v));
+                    self.date = Some((min.min(v), max.max(v)));
+                }
+            }
+            DataType::Decimal128(..) => {
+                for v in array.as_primitive::<Decimal128Type>().iter().flatten() {
+                    let (min, max) = self.decimal.unwrap_or((v, v));
+                    self.decimal = Some((min.min(v), max.max(v)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn update_int(&mut self, v: i64) {
+        self.int = Some(match self.int {
+            Some((min, max, sum)) => (
+                min.min(v),
+                max.max(v),
+                sum.and_then(|sum| sum.checked_add(v)),
+            ),
+            None => (v, v, Some(v)),
+        });
+    }
+
+    fn update_double(&mut self, v: f64) {
+        if v.is_nan() {
+            return;
+        }
+        self.double = Some(match self.double {
+            Some((min, max, sum)) => (min.min(v), max.max(v), sum + v),
+            None => (v, v, v),
+        });
+    }
+
+    fn merge(&mut self, other: &Stats) {
+        self.num_values += other.num_values;
+        self.has_null |= other.has_null;
+        self.bytes_sum = self.bytes_sum.saturating_add(other.bytes_sum);
+        self.true_count += other.true_count;
+        if let Some((min, max, sum)) = other.int {
+            self.int = Some(match self.int {
+                Some((self_min, self_max, self_sum)) => (
+                    self_min.min(min),
+                    self_max.max(max),
+                    self_sum.zip(sum).and_then(|(a, b)| a.checked_add(b)),
+                ),
+                None => (min, max, sum),
+            });
+        }
+        if let Some((min, max, sum)) = other.double {
+            self.double = Some(match self.double {
+                Some((self_min, self_max, self_sum)) => {
+                    (self_min.min(min), self_max.max(max), self_sum + sum)
+                }
+                None => (min, max, sum),
+            });
+        }
+        if let Some((min, max)) = &other.string {
+            match &mut self.string {
+                Some((self_min, self_max)) => {
+                    if min < self_min {
+                        *self_min = min.clone();
+                    }
+                    if max > self_max {
+                        *self_max = max.clone();
+                    }
+                }
+                None => self.string = Some((min.clone(), max.clone())),
+            }
+        }
+        if let Some((min, max)) = other.date {
+            let (self_min, self_max) = self.date.unwrap_or((min, max));
+            self.date = Some((self_min.min(min), self_max.max(max)));
+        }
+        if let Some((min, max)) = other.decimal {
+            let (self_min, self_max) = self.decimal.unwrap_or((min, max));
+            self.decimal = Some((self_min.min(min), self_max.max(max)));
+        }
+    }
+
+    fn to_proto(&self, data_type: &DataType) -> ColumnStatistics {
+        let mut stats = ColumnStatistics {
+            number_of_values: Some(self.num_values),
+            has_null: Some(self.has_null),
+            ..Default::default()
+        };
+        match data_type {
+            DataType::Boolean => {
+                stats.bucket_statistics = Some(BucketStatistics {
+                    count: vec![self.true_count],
+                });
+            }
+            DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+                stats.int_statistics = Some(IntegerStatistics {
+                    minimum: self.int.map(|(min, ..)| min),
+                    maximum: self.int.map(|(_, max, _)| max),
+                    sum: self.int.map(|(.., sum)| sum).unwrap_or(Some(0)),
+                });
+            }
+            DataType::Float32 | DataType::Float64 => {
+                stats.double_statistics = Some(DoubleStatistics {
+                    minimum: self.double.map(|(min, ..)| min),
+                    maximum: self.double.map(|(_, max, _)| max),
+                    sum: Some(self.double.map(|(.., sum)| sum).unwrap_or(0.0)),
+                });
+            }
+            DataType::Utf8 => {
+                stats.string_statistics = Some(StringStatistics {
+                    minimum: self.string.as_ref().map(|(min, _)| min.clone()),
+                    maximum: self.string.as_ref().map(|(_, max)| max.clone()),
+                    sum: Some(self.bytes_sum),
+                });
+            }
+            DataType::Binary => {
+                stats.binary_statistics = Some(BinaryStatistics {
+                    sum: Some(self.bytes_sum),
+                });
+            }
+            DataType::Date32 => {
+                stats.date_statistics = Some(DateStatistics {
+                    minimum: self.date.map(|(min, _)| min),
+                    maximum: self.date.map(|(_, max)| max),
+                });
+            }
+            &DataType::Decimal128(precision, scale) => {
+                let format = |v: i128| Decimal128Type::format_decimal(v, precision, scale);
+                stats.decimal_statistics = Some(DecimalStatistics {
+                    minimum: self.decimal.map(|(min, _)| format(min)),
+                    maximum: self.decimal.map(|(_, max)| format(max)),
+                    sum: None,
+                });
+            }
+            _ => {}
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{ops::Range, sync::Arc};
+
+    use arrow::{array::*, datatypes::*, record_batch::RecordBatch};
+    use datafusion::common::Result;
+
+    use crate::orc::{
+        compression::Compression,
+        proto::CompressionKind,
+        reader::{OrcFileReader, OrcInput},
+        writer::{format_nanos, OrcFileWriter, OrcWriterOptions},
+    };
+
+    struct BytesInput(Vec<u8>);
+
+    impl OrcInput for BytesInput {
+        fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>> {
+            Ok(self.0[range.start as usize..range.end as usize].to_vec())
+        }
+    }
+
+    #[test]
+    fn test_write_read() -> Result<()> {
+        let list_type = DataType::List(Arc::new(Field::new("item", DataType::Int64, true)));
+        let struct_type = DataType::Struct(Fields::from(vec![
+            Field::new("x", DataType::Boolean, true),
+            Field::new("y", DataType::Float64, true),
+        ]));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Decimal128(10, 2), true),
+            Field::new("d", DataType::Timestamp(TimeUnit::Microsecond, None), true),
+            Field::new("e", list_type, true),
+            Field::new("f", struct_type.clone(), true),
+        ]));
+
+        let num_rows = 5;
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![
+                    Some(1),
+                    None,
+                    Some(3),
+                    Some(-4),
+                    Some(5),
+                ])),
+                Arc::new(StringArray::from(vec![
+                    Some("x"),
+                    Some("yz"),
+                    None,
+                    Some(""),
+                    Some("abc"),
+                ])),
+                Arc::new(
+                    Decimal128Array::from(vec![Some(12345), Some(-1), None, Some(0), Some(7)])
+                        .with_precision_and_scale(10, 2)?,
+                ),
+                Arc::new(TimestampMicrosecondArray::from(vec![
+                    Some(1_600_000_000_123_456),
+                    Some(-1_500_000),
+                    Some(0),
+                    None,
+                    Some(1_000_000),
+                ])),
+                Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+                    Some(vec![Some(1), Some(2)]),
+                    None,
+                    Some(vec![]),
+                    Some(vec![None, Some(4)]),
+                    Some(vec![Some(5)]),
+                ])),
+                Arc::new(StructArray::from((
+                    vec![
+                        (
+                            Arc::new(Field::new("x", DataType::Boolean, true)),
+                            Arc::new(BooleanArray::from(vec![
+                                Some(true),
+                                Some(false),
+                                None,
+                                Some(true),
+                                Some(false),
+                            ])) as ArrayRef,
+                        ),
+                        (
+                            Arc::new(Field::new("y", DataType::Float64, true)),
+                            Arc::new(Float64Array::from(vec![1.5, 2.5, 3.5, 4.5, 5.5])) as ArrayRef,
+                        ),
+                    ],
+                    arrow::buffer::Buffer::from([0b11011]),
+                ))),
+            ],
+        )?;
+
+        for (kind, stride) in [
+            (CompressionKind::None, 2),
+            (CompressionKind::Zlib, 3),
+            (CompressionKind::Zstd, 0),
+        ] {
+            let options = OrcWriterOptions {
+                compression: Compression {
+                    kind,
+                    block_size: 1024,
+                },
+                row_index_stride: stride,
+                bloom_filter_columns: vec!["A".to_string(), "b".to_string()],
+                ..Default::default()
+            };
+            let mut writer = OrcFileWriter::try_new(vec![], schema.clone(), options)?;
+            writer.write(&batch.slice(0, 2))?;
+            writer.write(&batch.slice(2, 3))?;
+            let file = writer.close()?;
+
+            let file_size = file.len() as u64;
+            let reader = OrcFileReader::try_new(BytesInput(file), file_size)?;
+            assert_eq!(reader.stripes().len(), 1);
+            assert_eq!(reader.stripes()[0].number_of_rows, Some(num_rows as u64));
+            let stats = reader.stripe_column_statistics(0, 0).unwrap();
+            assert_eq!(stats.number_of_values, Some(4));
+            let int_stats = stats.int_statistics.as_ref().unwrap();
+            assert_eq!(int_stats.minimum, Some(-4));
+            assert_eq!(int_stats.maximum, Some(5));
+
+            let read_batch = reader
+                .stripe_reader(0, &[0, 1, 2, 3, 4, 5])?
+                .next_batch(num_rows)?
+                .unwrap();
+            for i in 0..schema.fields().len() {
+                assert_eq!(read_batch.column(i), batch.column(i), "column {i}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_nanos() {
+        assert_eq!(format_nanos(0), 0);
+        assert_eq!(format_nanos(123), 123 << 3);
+        assert_eq!(format_nanos(1000), 1 << 3 | 2);
+        assert_eq!(format_nanos(123_000_000), 123 << 3 | 5);
+    }
+}
@@ -0,0 +1,836 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Date/timestamp parsing and formatting compatible with spark.
+//!
+//! Dates are days since epoch and timestamps are microseconds since epoch
+//! (UTC), the same as spark's internal representation.
+
+use chrono::{
+    Datelike, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset,
+    TimeZone, Timelike, Utc,
+};
+use chrono_tz::Tz;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+/// time zone id, parsed like java's `ZoneId.of(id, ZoneId.SHORT_IDS)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ZoneId {
+    Offset(FixedOffset),
+    Region(Tz),
+}
+
+impl ZoneId {
+    pub const UTC: ZoneId = ZoneId::Region(Tz::UTC);
+
+    pub fn parse(id: &str) -> Option<Self> {
+        if id == "Z" {
+            return Some(Self::UTC);
+        }
+        if id.starts_with('+') || id.starts_with('-') {
+            return parse_offset(id).map(Self::Offset);
+        }
+        for prefix in ["UTC", "GMT", "UT"] {
+            if let Some(offset) = id.strip_prefix(prefix) {
+                if offset.is_empty() {
+                    return Some(Self::UTC);
+                }
+                if offset.starts_with('+') || offset.starts_with('-') {
+                    return parse_offset(offset).map(Self::Offset);
+                }
+            }
+        }
+        let id = match id {
+            "EST" => return parse_offset("-05:00").map(Self::Offset),
+            "HST" => return parse_offset("-10:00").map(Self::Offset),
+            "MST" => return parse_offset("-07:00").map(Self::Offset),
+            "ACT" => "Australia/Darwin",
+            "AET" => "Australia/Sydney",
+            "AGT" => "America/Argentina/Buenos_Aires",
+            "ART" => "Africa/Cairo",
+            "AST" => "America/Anchorage",
+            "BET" => "America/Sao_Paulo",
+            "BST" => "Asia/Dhaka",
+            "CAT" => "Africa/Harare",
+            "CNT" => "America/St_Johns",
+            "CST" => "America/Chicago",
+            "CTT" => "Asia/Shanghai",
+            "EAT" => "Africa/Addis_Ababa",
+            "ECT" => "Europe/Paris",
+            "IET" => "America/Indiana/Indianapolis",
+            "IST" => "Asia/Kolkata",
+            "JST" => "Asia/Tokyo",
+            "MIT" => "Pacific/Apia",
+            "NET" => "Asia/Yerevan",
+            "NST" => "Pacific/Auckland",
+            "PLT" => "Asia/Karachi",
+            "PNT" => "America/Phoenix",
+            "PRT" => "America/Puerto_Rico",
+            "PST" => "America/Los_Angeles",
+            "SST" => "Pacific/Guadalcanal",
+            "VST" => "Asia/Ho_Chi_Minh",
+            id => id,
+        };
+        id.parse::<Tz>().ok().map(Self::Region)
+    }
+
+    fn offset_from_utc(&self, utc: &NaiveDateTime) -> FixedOffset {
+        match self {
+            ZoneId::Offset(offset) => *offset,
+            ZoneId::Region(tz) => tz.offset_from_utc_datetime(utc).fix(),
+        }
+    }
+
+    fn local_to_utc(&self, local: &NaiveDateTime) -> Option<NaiveDateTime> {
+        let offset = match self {
+            ZoneId::Offset(offset) => *offset,
+            ZoneId::Region(tz) => match tz.offset_from_local_datetime(local) {
+                LocalResult::Single(offset) => offset.fix(),
+                LocalResult::Ambiguous(earlier, _) => earlier.fix(),
+                LocalResult::None => {
+                    // local time in a gap is shifted forward by the length of the gap,
+                    // which is the same as using the offset before the transition
+                    let before = local.checked_sub_signed(Duration::days(1))?;
+                    tz.offset_from_utc_datetime(&before).fix()
+                }
+            },
+        };
+        local.checked_sub_signed(Duration::seconds(offset.local_minus_utc() as i64))
+    }
+}
+
+/// parses offsets like `+h`, `+hh`, `+hh:mm`, `+hhmm`, `+hh:mm:ss` and
+/// `+hhmmss`. `+h:mm` and `+hh:m` are also supported like spark.
+fn parse_offset(s: &str) -> Option<FixedOffset> {
+    let sign = match s.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let body = &s[1..];
+    let parse_2digits = |s: &str, allow_single_digit: bool| -> Option<i32> {
+        let valid_len = s.len() == 2 || allow_single_digit && s.len() == 1;
+        if !valid_len || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok()
+    };
+
+    let (hours, minutes, seconds) = if body.contains(':') {
+        let parts = body.split(':').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [h, m] => (parse_2digits(h, true)?, parse_2digits(m, true)?, 0),
+            [h, m, s] => (
+                parse_2digits(h, true)?,
+                parse_2digits(m, false)?,
+                parse_2digits(s, false)?,
+            ),
+            _ => return None,
+        }
+    } else {
+        match body.len() {
+            1 | 2 => (parse_2digits(body, true)?, 0, 0),
+            4 => (
+                parse_2digits(&body[0..2], false)?,
+                parse_2digits(&body[2..4], false)?,
+                0,
+            ),
+            6 => (
+                parse_2digits(&body[0..2], false)?,
+                parse_2digits(&body[2..4], false)?,
+                parse_2digits(&body[4..6], false)?,
+            ),
+            _ => return None,
+        }
+    };
+    if hours > 18 || minutes > 59 || seconds > 59 {
+        return None;
+    }
+    let total_seconds = hours * 3600 + minutes * 60 + seconds;
+    if total_seconds > 18 * 3600 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * total_seconds)
+}
+
+/// trims leading/trailing whitespaces and ISO control characters, like
+/// spark's `UTF8String.trimAll()`
+pub fn trim_all(s: &str) -> &str {
+    s.trim_matches(|c: char| c.is_whitespace() || c.is_control())
+}
+
+fn epoch_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
+
+fn date_to_days(date: &NaiveDate) -> Option<i32> {
+    i32::try_from(date.signed_duration_since(epoch_date()).num_days()).ok()
+}
+
+fn days_to_date(days: i32) -> Option<NaiveDate> {
+    epoch_date().checked_add_signed(Duration::days(days as i64))
+}
+
+fn naive_to_micros(datetime: &NaiveDateTime) -> Option<i64> {
+    datetime
+        .timestamp()
+        .checked_mul(MICROS_PER_SECOND)?
+        .checked_add(datetime.timestamp_subsec_micros() as i64)
+}
+
+fn micros_to_naive(micros: i64) -> Option<NaiveDateTime> {
+    let secs = micros.div_euclid(MICROS_PER_SECOND);
+    let nanos = micros.rem_euclid(MICROS_PER_SECOND) as u32 * 1000;
+    NaiveDateTime::from_timestamp_opt(secs, nanos)
+}
+
+/// parses date strings in the following formats, same as spark's
+/// `DateTimeUtils.stringToDate()`:
+/// `[+-]yyyy*`
+/// `[+-]yyyy*-[m]m`
+/// `[+-]yyyy*-[m]m-[d]d`
+/// `[+-]yyyy*-[m]m-[d]d `
+/// `[+-]yyyy*-[m]m-[d]d *`
+/// `[+-]yyyy*-[m]m-[d]dT*`
+pub fn string_to_date(s: &str) -> Option<i32> {
+    let is_valid_digits = |segment: usize, digits: usize| {
+        // an integer is able to represent a date within [+-]5 million years
+        (segment == 0 && (4..=7).contains(&digits)) || (segment != 0 && (1..=2).contains(&digits))
+    };
+    let bytes = trim_all(s).as_bytes();
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let mut segments = [1i32, 1, 1];
+    let mut sign = 1;
+    let mut i = 0;
+    let mut current_segment_value = 0i32;
+    let mut current_segment_digits = 0;
+    let mut j = 0;
+    if bytes[j] == b'-' || bytes[j] == b'+' {
+        sign = if bytes[j] == b'-' { -1 } else { 1 };
+        j += 1;
+    }
+    while j < bytes.len() && (i < 3 && !(bytes[j] == b' ' || bytes[j] == b'T')) {
+        let b = bytes[j];
+        if i < 2 && b == b'-' {
+            if !is_valid_digits(i, current_segment_digits) {
+                return None;
+            }
+            segments[i] = current_segment_value;
+            current_segment_value = 0;
+            current_segment_digits = 0;
+            i += 1;
+        } else if b.is_ascii_digit() {
+            current_segment_value = current_segment_value * 10 + (b - b'0') as i32;
+            current_segment_digits += 1;
+        } else {
+            return None;
+        }
+        j += 1;
+    }
+    if !is_valid_digits(i, current_segment_digits) {
+        return None;
+    }
+    if i < 2 && j < bytes.len() {
+        // for the `yyyy` and `yyyy-[m]m` formats, entire input must be consumed
+        return None;
+    }
+    segments[i] = current_segment_value;
+
+    let date = NaiveDate::from_ymd_opt(sign * segments[0], segments[1] as u32, segments[2] as u32)?;
+    date_to_days(&date)
+}
+
+/// parses timestamp strings in the following formats, same as spark's
+/// `DateTimeUtils.stringToTimestamp()`:
+/// `[+-]yyyy*`
+/// `[+-]yyyy*-[m]m`
+/// `[+-]yyyy*-[m]m-[d]d`
+/// `[+-]yyyy*-[m]m-[d]d `
+/// `[+-]yyyy*-[m]m-[d]d [h]h:[m]m:[s]s.[ms][ms][ms][us][us][us][zone_id]`
+/// `[+-]yyyy*-[m]m-[d]dT[h]h:[m]m:[s]s.[ms][ms][ms][us][us][us][zone_id]`
+/// `[h]h:[m]m:[s]s.[ms][ms][ms][us][us][us][zone_id]`
+/// `T[h]h:[m]m:[s]s.[ms][ms][ms][us][us][us][zone_id]`
+///
+/// `zone` is used if no zone id is specified in the string.
+pub fn string_to_timestamp(s: &str, zone: ZoneId) -> Option<i64> {
+    let is_valid_digits = |segment: usize, digits: usize| {
+        // a long is able to represent a timestamp within [+-]200 thousand years.
+        // for the fraction part, more than 6 digits is allowed but truncated.
+        segment == 6
+            || (segment == 0 && (4..=6).contains(&digits))
+            || (segment == 7 && digits <= 2)
+            || (segment != 0 && segment != 6 && segment != 7 && (1..=2).contains(&digits))
+    };
+    let bytes = trim_all(s).as_bytes();
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let mut tz: Option<&[u8]> = None;
+    let mut segments = [1i32, 1, 1, 0, 0, 0, 0, 0, 0];
+    let mut i = 0;
+    let mut current_segment_value = 0i32;
+    let mut current_segment_digits = 0;
+    let mut j = 0;
+    let mut digits_milli = 0;
+    let mut just_time = false;
+    let mut year_sign = None;
+    if bytes[j] == b'-' || bytes[j] == b'+' {
+        year_sign = Some(if bytes[j] == b'-' { -1 } else { 1 });
+        j += 1;
+    }
+
+    macro_rules! next_segment {
+        () => {{
+            if !is_valid_digits(i, current_segment_digits) {
+                return None;
+            }
+            segments[i] = current_segment_value;
+            current_segment_value = 0;
+            current_segment_digits = 0;
+            i += 1;
+        }};
+    }
+
+    while j < bytes.len() {
+        let b = bytes[j];
+        if !b.is_ascii_digit() {
+            if j == 0 && b == b'T' {
+                just_time = true;
+                i += 3;
+            } else if i < 2 {
+                if b == b'-' {
+                    next_segment!();
+                } else if i == 0 && b == b':' && year_sign.is_none() {
+                    just_time = true;
+                    if !is_valid_digits(3, current_segment_digits) {
+                        return None;
+                    }
+                    segments[3] = current_segment_value;
+                    current_segment_value = 0;
+                    current_segment_digits = 0;
+                    i = 4;
+                } else {
+                    return None;
+                }
+            } else if i == 2 {
+                if b == b' ' || b == b'T' {
+                    next_segment!();
+                } else {
+                    return None;
+                }
+            } else if i == 3 || i == 4 {
+                if b == b':' {
+                    next_segment!();
+                } else {
+                    return None;
+                }
+            } else if i == 5 || i == 6 {
+                if b == b'.' && i == 5 {
+                    next_segment!();
+                } else {
+                    next_segment!();
+                    tz = Some(&bytes[j..]);
+                    j = bytes.len() - 1;
+                }
+                if i == 6 && b != b'.' {
+                    i += 1;
+                }
+            } else if i < segments.len() && (b == b':' || b == b' ') {
+                next_segment!();
+            } else {
+                return None;
+            }
+        } else {
+            if i == 6 {
+                digits_milli += 1;
+            }
+            // fraction part is truncated if there are more than 6 digits
+            if i != 6 || current_segment_digits < 6 {
+                current_segment_value = current_segment_value
+                    .checked_mul(10)?
+                    .checked_add((b - b'0') as i32)?;
+            }
+            current_segment_digits += 1;
+        }
+        j += 1;
+    }
+    if !is_valid_digits(i, current_segment_digits) {
+        return None;
+    }
+    segments[i] = current_segment_value;
+    while digits_milli < 6 {
+        segments[6] *= 10;
+        digits_milli += 1;
+    }
+
+    let zone = match tz {
+        Some(tz) => ZoneId::parse(std::str::from_utf8(tz).ok()?.trim())?,
+        None => zone,
+    };
+    segments[0] *= year_sign.unwrap_or(1);
+
+    let time = NaiveTime::from_hms_micro_opt(
+        segments[3] as u32,
+        segments[4] as u32,
+        segments[5] as u32,
+        segments[6] as u32,
+    )?;
+    let date = if just_time {
+        let now = Utc::now().naive_utc();
+        now.checked_add_signed(Duration::seconds(
+            zone.offset_from_utc(&now).local_minus_utc() as i64,
+        ))?
+        .date()
+    } else {
+        NaiveDate::from_ymd_opt(segments[0], segments[1] as u32, segments[2] as u32)?
+    };
+    naive_to_micros(&zone.local_to_utc(&date.and_time(time))?)
+}
+
+/// converts timestamp to the date in the specified zone
+pub fn timestamp_to_date(micros: i64, zone: ZoneId) -> Option<i32> {
+    let local_micros = micros_to_local_micros(micros, zone)?;
+    i32::try_from(local_micros.div_euclid(MICROS_PER_DAY)).ok()
+}
+
+/// converts date to the timestamp at the beginning of the day in the
+/// specified zone
+pub fn date_to_timestamp(days: i32, zone: ZoneId) -> Option<i64> {
+    let local = days_to_date(days)?.and_hms_opt(0, 0, 0)?;
+    naive_to_micros(&zone.local_to_utc(&local)?)
+}
+
+fn micros_to_local_micros(micros: i64, zone: ZoneId) -> Option<i64> {
+    let utc = micros_to_naive(micros)?;
+    let offset_seconds = zone.offset_from_utc(&utc).local_minus_utc() as i64;
+    micros.checked_add(offset_seconds * MICROS_PER_SECOND)
+}
+
+fn local_micros_to_micros(local_micros: i64, zone: ZoneId) -> Option<i64> {
+    naive_to_micros(&zone.local_to_utc(&micros_to_naive(local_micros)?)?)
+}
+
+/// the first day of the proleptic gregorian calendar in the hybrid (julian +
+/// gregorian) calendar, 1582-10-15
+pub const GREGORIAN_SWITCH_DAY: i32 = -141427;
+
+// days where the differences between the julian and the gregorian calendar
+// change, and the differences starting from them, same as spark's
+// `RebaseDateTime`. days before the first switch use the first difference.
+const JULIAN_GREG_DIFF_SWITCH_DAYS: [i32; 14] = [
+    -719164,
+    -682945,
+    -646420,
+    -609895,
+    -536845,
+    -500320,
+    -463795,
+    -390745,
+    -354220,
+    -317695,
+    -244645,
+    -208120,
+    -171595,
+    GREGORIAN_SWITCH_DAY,
+];
+const JULIAN_GREG_DIFFS: [i32; 14] = [2, 1, 0, -1, -2, -3, -4, -5, -6, -7, -8, -9, -10, 0];
+const GREG_JULIAN_DIFF_SWITCH_DAYS: [i32; 14] = [
+    -719162,
+    -682944,
+    -646420,
+    -609896,
+    -536847,
+    -500323,
+    -463799,
+    -390750,
+    -354226,
+    -317702,
+    -244653,
+    -208129,
+    -171605,
+    GREGORIAN_SWITCH_DAY,
+];
+const GREG_JULIAN_DIFFS: [i32; 14] = [-2, -1, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 0];
+
+fn rebase_days(switch_days: &[i32], diffs: &[i32], days: i32) -> i32 {
+    let i = switch_days
+        .iter()
+        .rposition(|&switch_day| days >= switch_day)
+        .unwrap_or(0);
+    days.saturating_add(diffs[i])
+}
+
+/// converts days in the hybrid calendar used by spark 2.x and legacy hive to
+/// days of the same local date in the proleptic gregorian calendar, like
+/// spark's `RebaseDateTime.rebaseJulianToGregorianDays()`
+pub fn rebase_julian_to_gregorian_days(days: i32) -> i32 {
+    rebase_days(&JULIAN_GREG_DIFF_SWITCH_DAYS, &JULIAN_GREG_DIFFS, days)
+}
+
+/// converts days in the proleptic gregorian calendar to days of the same local
+/// date in the hybrid calendar, like spark's
+/// `RebaseDateTime.rebaseGregorianToJulianDays()`
+pub fn rebase_gregorian_to_julian_days(days: i32) -> i32 {
+    rebase_days(&GREG_JULIAN_DIFF_SWITCH_DAYS, &GREG_JULIAN_DIFFS, days)
+}
+
+// rebases the local date of the timestamp in the specified zone and keeps the
+// local time. spark rebases timestamps with precomputed tables of the jvm's
+// zone rules instead, which only differ for some zones before 1900.
+fn rebase_micros(micros: i64, zone: ZoneId, rebase_local_days: fn(i32) -> i32) -> i64 {
+    let rebased = || {
+        let local_micros = micros_to_local_micros(micros, zone)?;
+        let days = i32::try_from(local_micros.div_euclid(MICROS_PER_DAY)).ok()?;
+        if days >= GREGORIAN_SWITCH_DAY {
+            return Some(micros);
+        }
+        let rebased_local_micros = (rebase_local_days(days) as i64 * MICROS_PER_DAY)
+            .checked_add(local_micros.rem_euclid(MICROS_PER_DAY))?;
+        local_micros_to_micros(rebased_local_micros, zone)
+    };
+    rebased().unwrap_or(micros)
+}
+
+/// converts timestamp of a local date-time in the hybrid calendar to the
+/// timestamp of the same local date-time in the proleptic gregorian calendar,
+/// like spark's `RebaseDateTime.rebaseJulianToGregorianMicros()`
+pub fn rebase_julian_to_gregorian_micros(micros: i64, zone: ZoneId) -> i64 {
+    rebase_micros(micros, zone, rebase_julian_to_gregorian_days)
+}
+
+/// converts timestamp of a local date-time in the proleptic gregorian calendar
+/// to the timestamp of the same local date-time in the hybrid calendar, like
+/// spark's `RebaseDateTime.rebaseGregorianToJulianMicros()`
+pub fn rebase_gregorian_to_julian_micros(micros: i64, zone: ZoneId) -> i64 {
+    rebase_micros(micros, zone, rebase_gregorian_to_julian_days)
+}
+
+/// converts timestamp of a local date-time in `from_zone` to the timestamp of
+/// the same local date-time in `to_zone`, like spark's
+/// `DateTimeUtils.convertTz()`
+pub fn convert_tz(micros: i64, from_zone: ZoneId, to_zone: ZoneId) -> Option<i64> {
+    local_micros_to_micros(micros_to_local_micros(micros, from_zone)?, to_zone)
+}
+
+/// formats date as `yyyy-MM-dd`, years out of [0, 9999] are prefixed with
+/// sign like spark
+pub fn date_to_string(days: i32) -> Option<String> {
+    let date = days_to_date(days)?;
+    Some(format!(
+        "{}-{:02}-{:02}",
+        format_year(date.year()),
+        date.month(),
+        date.day()
+    ))
+}
+
+/// formats timestamp as `yyyy-MM-dd HH:mm:ss[.SSSSSS]` in the specified
+/// zone, trailing zeros of the fraction part are omitted like spark
+pub fn timestamp_to_string(micros: i64, zone: ZoneId) -> Option<String> {
+    let local = micros_to_naive(micros_to_local_micros(micros, zone)?)?;
+    let mut s = format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02}",
+        format_year(local.year()),
+        local.month(),
+        local.day(),
+        local.hour(),
+        local.minute(),
+        local.second(),
+    );
+    let fraction_micros = local.timestamp_subsec_micros();
+    if fraction_micros > 0 {
+        let fraction = format!("{fraction_micros:06}");
+        s.push('.');
+        s.push_str(fraction.trim_end_matches('0'));
+    }
+    Some(s)
+}
+
+fn format_year(year: i32) -> String {
+    if year > 9999 {
+        format!("+{year}")
+    } else if year < 0 {
+        format!("-{:04}", -(year as i64))
+    } else {
+        format!("{year:04}")
+    }
+}
+
+/// a subset of java's `DateTimeFormatter` patterns, used to parse the
+/// `timestampFormat` and `dateFormat` options of text data sources.
+/// supported letters are `y`, `u`, `M`, `d`, `H`, `m`, `s` and `S`, other
+/// characters and quoted texts are literals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateTimePattern {
+    items: Vec<PatternItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternItem {
+    Literal(String),
+    Field(char, usize),
+}
+
+impl DateTimePattern {
+    /// compiles the pattern, returns None if it contains unsupported letters
+    pub fn parse(pattern: &str) -> Option<Self> {
+        fn push_literal(items: &mut Vec<PatternItem>, c: char) {
+            match items.last_mut() {
+                Some(PatternItem::Literal(literal)) => literal.push(c),
+                _ => items.push(PatternItem::Literal(c.to_string())),
+            }
+        }
+
+        let mut items = vec![];
+
+        let chars = pattern.chars().collect::<Vec<_>>();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c == '\'' {
+                // quoted text, where '' is an escaped quote
+                i += 1;
+                if chars.get(i) == Some(&'\'') {
+                    push_literal(&mut items, '\'');
+                    i += 1;
+                    continue;
+                }
+                loop {
+                    match chars.get(i) {
+                        Some('\'') if chars.get(i + 1) == Some(&'\'') => {
+                            push_literal(&mut items, '\'');
+                            i += 2;
+                        }
+                        Some('\'') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&c) => {
+                            push_literal(&mut items, c);
+                            i += 1;
+                        }
+                        None => return None,
+                    }
+                }
+            } else if c.is_ascii_alphabetic() {
+                let count = chars[i..].iter().take_while(|&&ch| ch == c).count();
+                let letter = match c {
+                    'y' | 'u' => 'y',
+                    'M' | 'd' | 'H' | 'm' | 's' if count <= 2 => c,
+                    'S' if count <= 9 => c,
+                    _ => return None,
+                };
+                items.push(PatternItem::Field(letter, count));
+                i += count;
+            } else {
+                push_literal(&mut items, c);
+                i += 1;
+            }
+        }
+        Some(Self { items })
+    }
+
+    /// parses a date, the time fields are ignored
+    pub fn parse_date(&self, s: &str) -> Option<i32> {
+        date_to_days(&self.parse_local(s)?.date())
+    }
+
+    /// parses a timestamp as local time in the specified zone
+    pub fn parse_timestamp(&self, s: &str, zone: ZoneId) -> Option<i64> {
+        naive_to_micros(&zone.local_to_utc(&self.parse_local(s)?)?)
+    }
+
+    fn parse_local(&self, s: &str) -> Option<NaiveDateTime> {
+        let bytes = s.as_bytes();
+        let mut pos = 0;
+        let (mut year, mut month, mut day) = (1970, 1, 1);
+        let (mut hour, mut minute, mut second, mut micros) = (0, 0, 0, 0);
+
+        for (idx, item) in self.items.iter().enumerate() {
+            match item {
+                PatternItem::Literal(literal) => {
+                    let end = pos + literal.len();
+                    if !bytes
+                        .get(pos..end)?
+                        .eq_ignore_ascii_case(literal.as_bytes())
+                    {
+                        return None;
+                    }
+                    pos = end;
+                }
+                &PatternItem::Field(letter, count) => {
+                    // fields followed by another field are fixed-width, like java's
+                    // adjacent value parsing
+                    let adjacent = matches!(self.items.get(idx + 1), Some(PatternItem::Field(..)));
+                    let (min_width, max_width) = match letter {
+                        'y' if count == 2 || adjacent => (count, count),
+                        'y' => (count, 9),
+                        'S' => (count, count),
+                        _ if count == 2 || adjacent => (count, count),
+                        _ => (1, 2),
+                    };
+                    let num_digits = bytes[pos..]
+                        .iter()
+                        .take(max_width)
+                        .take_while(|b| b.is_ascii_digit())
+                        .count();
+                    if num_digits < min_width {
+                        return None;
+                    }
+                    let value = s[pos..pos + num_digits].parse::<u32>().ok()?;
+                    pos += num_digits;
+
+                    match letter {
+                        'y' if count == 2 => year = 2000 + value as i32,
+                        'y' => year = value as i32,
+                        'M' => month = value,
+                        'd' => day = value,
+                        'H' => hour = value,
+                        'm' => minute = value,
+                        's' => second = value,
+                        _ => {
+                            // fraction of second, truncated to microseconds
+                            micros = if count <= 6 {
+                                value * 10u32.pow(6 - count as u32)
+                            } else {
+                                value / 10u32.pow(count as u32 - 6)
+                            };
+                        }
+                    }
+                }
+            }
+        }
+        if pos != bytes.len() {
+            return None;
+        }
+        let date = NaiveDate::from_ymd_opt(year, month, day)?;
+        let time = NaiveTime::from_hms_micro_opt(hour, minute, second, micros)?;
+        Some(date.and_time(time))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_datetime::*;
+
+    #[test]
+    fn test_parse_zone_id() {
+        let offset = |secs| Some(ZoneId::Offset(FixedOffset::east_opt(secs).unwrap()));
+        assert_eq!(ZoneId::parse("Z"), Some(ZoneId::UTC));
+        assert_eq!(ZoneId::parse("UTC"), Some(ZoneId::UTC));
+        assert_eq!(ZoneId::parse("+08:00"), offset(8 * 3600));
+        assert_eq!(ZoneId::parse("-8:30"), offset(-8 * 3600 - 30 * 60));
+        assert_eq!(ZoneId::parse("+0830"), offset(8 * 3600 + 30 * 60));
+        assert_eq!(ZoneId::parse("GMT+1"), offset(3600));
+        assert_eq!(ZoneId::parse("EST"), offset(-5 * 3600));
+        assert_eq!(
+            ZoneId::parse("PST"),
+            Some(ZoneId::Region(Tz::America__Los_Angeles))
+        );
+        assert_eq!(
+            ZoneId::parse("Asia/Shanghai"),
+            Some(ZoneId::Region(Tz::Asia__Shanghai))
+        );
+        assert_eq!(ZoneId::parse("+19:00"), None);
+        assert_eq!(ZoneId::parse("Mars/Olympus"), None);
+    }
+
+    #[test]
+    fn test_local_time_in_gap() {
+        // 2020-03-08 02:30 does not exist in America/Los_Angeles, and is shifted to
+        // 03:30 PDT
+        let zone = ZoneId::parse("America/Los_Angeles").unwrap();
+        let micros = string_to_timestamp("2020-03-08 02:30:00", zone).unwrap();
+        assert_eq!(
+            timestamp_to_string(micros, zone).as_deref(),
+            Some("2020-03-08 03:30:00")
+        );
+    }
+
+    #[test]
+    fn test_rebase_datetime() {
+        let days = |s| string_to_date(s).unwrap();
+        assert_eq!(rebase_gregorian_to_julian_days(days("0001-01-01")), -719164);
+        assert_eq!(rebase_julian_to_gregorian_days(-719164), days("0001-01-01"));
+        assert_eq!(
+            rebase_gregorian_to_julian_days(days("1000-02-01")),
+            days("1000-02-01") + 5
+        );
+        assert_eq!(
+            rebase_julian_to_gregorian_days(days("1582-10-04") + 10),
+            days("1582-10-04")
+        );
+        assert_eq!(
+            rebase_gregorian_to_julian_days(days("1582-10-15")),
+            days("1582-10-15")
+        );
+        for day in [days("0100-03-01"), days("1234-05-06"), days("1582-10-01")] {
+            let julian_day = rebase_gregorian_to_julian_days(day);
+            assert_eq!(rebase_julian_to_gregorian_days(julian_day), day);
+        }
+
+        // local time is kept while rebasing timestamps
+        let zone = ZoneId::parse("+08:00").unwrap();
+        let micros = string_to_timestamp("1000-02-01 12:34:56.789", zone).unwrap();
+        let julian_micros = rebase_gregorian_to_julian_micros(micros, zone);
+        assert_eq!(julian_micros, micros + 5 * MICROS_PER_DAY);
+        assert_eq!(
+            rebase_julian_to_gregorian_micros(julian_micros, zone),
+            micros
+        );
+        let micros = string_to_timestamp("2020-01-02 03:04:05", zone).unwrap();
+        assert_eq!(rebase_gregorian_to_julian_micros(micros, zone), micros);
+
+        let micros = string_to_timestamp("2020-01-02 03:04:05", ZoneId::UTC).unwrap();
+        assert_eq!(
+            convert_tz(micros, ZoneId::UTC, zone),
+            string_to_timestamp("2020-01-02 03:04:05", zone)
+        );
+    }
+
+    #[test]
+    fn test_date_time_pattern() {
+        let utc = ZoneId::UTC;
+        let pattern = DateTimePattern::parse("yyyy-MM-dd HH:mm:ss.SSS").unwrap();
+        assert_eq!(
+            pattern.parse_timestamp("2020-01-02 03:04:05.678", utc),
+            string_to_timestamp("2020-01-02 03:04:05.678", utc)
+        );
+        assert_eq!(pattern.parse_timestamp("2020-01-02 03:04:05", utc), None);
+        assert_eq!(pattern.parse_timestamp("2020-1-02 03:04:05.678", utc), None);
+
+        let pattern = DateTimePattern::parse("dd/M/yyyy'T'HH''mm").unwrap();
+        assert_eq!(
+            pattern.parse_timestamp("02/1/2020T03'04", utc),
+            string_to_timestamp("2020-01-02 03:04:00", utc)
+        );
+        assert_eq!(
+            pattern.parse_date("02/12/2020T03'04"),
+            string_to_date("2020-12-02")
+        );
+
+        let pattern = DateTimePattern::parse("yyyyMMdd").unwrap();
+        assert_eq!(pattern.parse_date("20200102"), string_to_date("2020-01-02"));
+        assert_eq!(pattern.parse_date("20201302"), None);
+
+        assert_eq!(DateTimePattern::parse("yyyy-MM-dd HH:mm:ss XXX"), None);
+        assert_eq!(DateTimePattern::parse("yyyy-MM-dd 'T"), None);
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Errors raised in ANSI mode, compatible with spark.
+//!
+//! Every message starts with the spark error class in square brackets, so that
+//! the JVM side can map it back to the corresponding spark exception.
+
+use arrow::datatypes::DataType;
+use datafusion::common::DataFusionError;
+
+const ANSI_HINT: &str =
+    "If necessary set \"spark.sql.ansi.enabled\" to \"false\" to bypass this error.";
+
+pub fn cast_invalid_input_error(value: &str, from: &DataType, to: &DataType) -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "[CAST_INVALID_INPUT] The value '{value}' of the type \"{}\" cannot be cast to \"{}\" \
+            because it is malformed. Correct the value as per the syntax, or change its target \
+            type. Use `try_cast` to tolerate malformed input and return NULL instead. {ANSI_HINT}",
+        spark_type_name(from),
+        spark_type_name(to),
+    ))
+}
+
+pub fn cast_overflow_error(value: &str, from: &DataType, to: &DataType) -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "[CAST_OVERFLOW] The value {value} of the type \"{}\" cannot be cast to \"{}\" due to \
+            an overflow. Use `try_cast` to tolerate overflow and return NULL instead. {ANSI_HINT}",
+        spark_type_name(from),
+        spark_type_name(to),
+    ))
+}
+
+pub fn numeric_value_out_of_range_error(value: &str, to: &DataType) -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "[NUMERIC_VALUE_OUT_OF_RANGE] {value} cannot be represented as {}. {ANSI_HINT}",
+        spark_type_name(to),
+    ))
+}
+
+/// `try_function` is the function name hinted to tolerate the overflow, like
+/// `try_add`
+pub fn arithmetic_overflow_error(data_type: &DataType, try_function: &str) -> DataFusionError {
+    let type_name = match data_type {
+        DataType::Int8 => "byte",
+        DataType::Int16 => "short",
+        DataType::Int32 => "integer",
+        DataType::Int64 => "long",
+        _ => "numeric",
+    };
+    DataFusionError::Execution(format!(
+        "[ARITHMETIC_OVERFLOW] {type_name} overflow. Use '{try_function}' to tolerate overflow \
+            and return NULL instead. {ANSI_HINT}",
+    ))
+}
+
+pub fn divide_by_zero_error() -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "[DIVIDE_BY_ZERO] Division by zero. Use `try_divide` to tolerate divisor being 0 and \
+            return NULL instead. {ANSI_HINT}",
+    ))
+}
+
+pub fn invalid_array_index_error(index: i64, num_elements: usize) -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "[INVALID_ARRAY_INDEX] The index {index} is out of bounds. The array has {num_elements} \
+            elements. Use the SQL function `get()` to tolerate accessing element at invalid \
+            index and return NULL instead. {ANSI_HINT}",
+    ))
+}
+
+/// raised when reading ancient dates/timestamps in EXCEPTION rebase mode,
+/// `format` is the file format like `Parquet` and `config` is the rebase mode
+/// config, like `spark.sql.parquet.datetimeRebaseModeInRead`
+pub fn read_ancient_datetime_error(format: &str, config: &str) -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "[INCONSISTENT_BEHAVIOR_CROSS_VERSION.READ_ANCIENT_DATETIME] You may get a different \
+            result due to the upgrading to Spark >= 3.0: reading dates before 1582-10-15 or \
+            timestamps before 1900-01-01T00:00:00Z from {format} files can be ambiguous, as the \
+            files may be written by Spark 2.x or legacy versions of Hive, which uses a legacy \
+            hybrid calendar that is different from Spark 3.0+'s Proleptic Gregorian calendar. \
+            See more details in SPARK-31404. You can set the SQL config \"{config}\" to \
+            \"LEGACY\" to rebase the datetime values w.r.t. the calendar difference during \
+            reading. To read the datetime values as it is, set the SQL config to \"CORRECTED\".",
+    ))
+}
+
+/// raised when writing ancient dates/timestamps in EXCEPTION rebase mode
+pub fn write_ancient_datetime_error(format: &str, config: &str) -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "[INCONSISTENT_BEHAVIOR_CROSS_VERSION.WRITE_ANCIENT_DATETIME] You may get a different \
+            result due to the upgrading to Spark >= 3.0: writing dates before 1582-10-15 or \
+            timestamps before 1900-01-01T00:00:00Z into {format} files can be dangerous, as the \
+            files may be read by Spark 2.x or legacy versions of Hive later, which uses a legacy \
+            hybrid calendar that is different from Spark 3.0+'s Proleptic Gregorian calendar. \
+            See more details in SPARK-31404. You can set \"{config}\" to \"LEGACY\" to rebase \
+            the datetime values w.r.t. the calendar difference during writing, to get maximum \
+            interoperability. Or set the config to \"CORRECTED\" to write the datetime values \
+            as it is, if you are sure that the written files will only be read by Spark 3.0+ or \
+            other systems that use Proleptic Gregorian calendar.",
+    ))
+}
+
+/// type name displayed in spark error messages
+pub fn spark_type_name(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Null => "VOID".to_string(),
+        DataType::Boolean => "BOOLEAN".to_string(),
+        DataType::Int8 => "TINYINT".to_string(),
+        DataType::Int16 => "SMALLINT".to_string(),
+        DataType::Int32 => "INT".to_string(),
+        DataType::Int64 => "BIGINT".to_string(),
+        DataType::Float32 => "FLOAT".to_string(),
+        DataType::Float64 => "DOUBLE".to_string(),
+        DataType::Utf8 | DataType::LargeUtf8 => "STRING".to_string(),
+        DataType::Binary | DataType::LargeBinary => "BINARY".to_string(),
+        DataType::Date32 => "DATE".to_string(),
+        DataType::Timestamp(..) => "TIMESTAMP".to_string(),
+        DataType::Decimal128(precision, scale) => format!("DECIMAL({precision},{scale})"),
+        DataType::List(field) => format!("ARRAY<{}>", spark_type_name(field.data_type())),
+        other => other.to_string().to_uppercase(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::datatypes::DataType;
+
+    use crate::spark_errors::*;
+
+    #[test]
+    fn test_error_classes() {
+        let err = cast_invalid_input_error("abc", &DataType::Utf8, &DataType::Int32);
+        assert!(err.to_string().contains(
+            "[CAST_INVALID_INPUT] The value 'abc' of the type \"STRING\" cannot be cast to \"INT\""
+        ));
+
+        let err = arithmetic_overflow_error(&DataType::Int64, "try_add");
+        assert!(err
+            .to_string()
+            .contains("[ARITHMETIC_OVERFLOW] long overflow. Use 'try_add'"));
+
+        let err = invalid_array_index_error(5, 3);
+        assert!(err.to_string().contains(
+            "[INVALID_ARRAY_INDEX] The index 5 is out of bounds. The array has 3 elements."
+        ));
+        assert_eq!(
+            spark_type_name(&DataType::Decimal128(10, 2)),
+            "DECIMAL(10,2)"
+        );
+    }
+}
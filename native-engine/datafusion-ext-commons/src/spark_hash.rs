@@ -402,6 +402,127 @@ fn hash_one(col: &ArrayRef, idx: usize, hash: &mut u32) -> Result<()> {
     Ok(())
 }
 
+macro_rules! hive_hash_array {
+    ($array_type:ident, $column:ident, $hashes:ident, $hash_value:expr) => {
+        let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
+        for (i, hash) in $hashes.iter_mut().enumerate() {
+            let value_hash = if array.is_null(i) {
+                0
+            } else {
+                $hash_value(array.value(i))
+            };
+            *hash = hash.wrapping_mul(31).wrapping_add(value_hash);
+        }
+    };
+}
+
+/// Creates hive hash values for every row, like spark's `HiveHash` used for
+/// bucketing hive tables. hashes of the columns are combined as
+/// `31 * hash + column_hash` from 0, and null values are hashed to 0.
+///
+/// The number of rows to hash is determined by `hashes_buffer.len()`.
+/// `hashes_buffer` should be pre-sized appropriately
+pub fn create_hive_hashes(arrays: &[ArrayRef], hashes_buffer: &mut [i32]) -> Result<()> {
+    for col in arrays {
+        match col.data_type() {
+            DataType::Null => {
+                for hash in hashes_buffer.iter_mut() {
+                    *hash = hash.wrapping_mul(31);
+                }
+            }
+            DataType::Boolean => {
+                hive_hash_array!(BooleanArray, col, hashes_buffer, |v: bool| v as i32);
+            }
+            DataType::Int8 => {
+                hive_hash_array!(Int8Array, col, hashes_buffer, |v: i8| v as i32);
+            }
+            DataType::Int16 => {
+                hive_hash_array!(Int16Array, col, hashes_buffer, |v: i16| v as i32);
+            }
+            DataType::Int32 => {
+                hive_hash_array!(Int32Array, col, hashes_buffer, |v: i32| v);
+            }
+            DataType::Date32 => {
+                hive_hash_array!(Date32Array, col, hashes_buffer, |v: i32| v);
+            }
+            DataType::Int64 => {
+                hive_hash_array!(Int64Array, col, hashes_buffer, hive_hash_long);
+            }
+            DataType::Float32 => {
+                hive_hash_array!(Float32Array, col, hashes_buffer, hive_hash_float);
+            }
+            DataType::Float64 => {
+                hive_hash_array!(Float64Array, col, hashes_buffer, hive_hash_double);
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                hive_hash_array!(
+                    TimestampMicrosecondArray,
+                    col,
+                    hashes_buffer,
+                    hive_hash_timestamp
+                );
+            }
+            DataType::Utf8 => {
+                hive_hash_array!(StringArray, col, hashes_buffer, |v: &str| {
+                    hive_hash_bytes(v.as_bytes())
+                });
+            }
+            DataType::LargeUtf8 => {
+                hive_hash_array!(LargeStringArray, col, hashes_buffer, |v: &str| {
+                    hive_hash_bytes(v.as_bytes())
+                });
+            }
+            DataType::Binary => {
+                hive_hash_array!(BinaryArray, col, hashes_buffer, hive_hash_bytes);
+            }
+            DataType::LargeBinary => {
+                hive_hash_array!(LargeBinaryArray, col, hashes_buffer, hive_hash_bytes);
+            }
+            other => df_execution_err!("Unsupported data type in hive hasher: {other}")?,
+        }
+    }
+    Ok(())
+}
+
+fn hive_hash_long(v: i64) -> i32 {
+    (((v as u64) >> 32) as i64 ^ v) as i32
+}
+
+fn hive_hash_float(v: f32) -> i32 {
+    // like java's Float.floatToIntBits, with -0.0 hashed as 0.0
+    if v == 0.0 {
+        0
+    } else if v.is_nan() {
+        0x7fc00000
+    } else {
+        v.to_bits() as i32
+    }
+}
+
+fn hive_hash_double(v: f64) -> i32 {
+    // like java's Double.doubleToLongBits, with -0.0 hashed as 0.0
+    if v == 0.0 {
+        0
+    } else if v.is_nan() {
+        hive_hash_long(0x7ff8000000000000)
+    } else {
+        hive_hash_long(v.to_bits() as i64)
+    }
+}
+
+fn hive_hash_timestamp(micros: i64) -> i32 {
+    // seconds and nanoseconds are truncated toward zero like java
+    let seconds = micros / 1_000_000;
+    let nanos = micros % 1_000_000 * 1000;
+    hive_hash_long((seconds << 30) | nanos)
+}
+
+fn hive_hash_bytes(bytes: &[u8]) -> i32 {
+    bytes.iter().fold(0i32, |hash, &b| {
+        hash.wrapping_mul(31).wrapping_add(b as i8 as i32)
+    })
+}
+
 pub fn pmod(hash: u32, n: usize) -> usize {
     let hash = hash as i32;
     let n = n as i32;
@@ -423,7 +544,9 @@ mod tests {
         datatypes::{DataType, Field, ToByteSlice},
     };
 
-    use crate::spark_hash::{create_hashes, pmod, spark_compatible_murmur3_hash};
+    use crate::spark_hash::{
+        create_hashes, create_hive_hashes, pmod, spark_compatible_murmur3_hash,
+    };
 
     #[test]
     fn test_list() {
@@ -495,6 +618,46 @@ mod tests {
         assert_eq!(hashes, expected);
     }
 
+    #[test]
+    fn test_hive_hash() {
+        // expected values of spark's HiveHash
+        let i = Arc::new(Int32Array::from(vec![
+            Some(0),
+            Some(-1),
+            Some(i32::MAX),
+            None,
+        ]));
+        let mut hashes = vec![0; 4];
+        create_hive_hashes(&[i], &mut hashes).unwrap();
+        assert_eq!(hashes, vec![0, -1, i32::MAX, 0]);
+
+        let i = Arc::new(Int64Array::from(vec![1, 0, -1, i64::MAX, i64::MIN]));
+        let mut hashes = vec![0; 5];
+        create_hive_hashes(&[i], &mut hashes).unwrap();
+        assert_eq!(hashes, vec![1, 0, 0, i32::MIN, i32::MIN]);
+
+        let s = Arc::new(StringArray::from(vec![
+            "apache spark",
+            "!@#$%^&*()_+=-",
+            "abcdefghijklmnopqrstuvwxyz",
+            "AbCdEfGhIjKlMnOpQrStUvWxYz012",
+            "数据砖头",
+        ]));
+        let mut hashes = vec![0; 5];
+        create_hive_hashes(&[s], &mut hashes).unwrap();
+        assert_eq!(
+            hashes,
+            vec![1142704523, -613724358, 958031277, -648013852, -898686242]
+        );
+
+        // hashes of columns are combined
+        let i = Arc::new(Int32Array::from(vec![1])) as ArrayRef;
+        let s = Arc::new(StringArray::from(vec!["a"])) as ArrayRef;
+        let mut hashes = vec![0; 1];
+        create_hive_hashes(&[i, s], &mut hashes).unwrap();
+        assert_eq!(hashes, vec![31 + 97]);
+    }
+
     #[test]
     fn test_pmod() {
         let i: Vec<u32> = vec![0x99f0149d, 0x9c67b85d, 0xc8008529, 0xa05b5d7b, 0xcd1e64fb];
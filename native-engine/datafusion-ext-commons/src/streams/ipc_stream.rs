@@ -15,20 +15,22 @@
 use std::{
     fmt::Debug,
     fs::File,
-    io::{BufReader, Error as IoError, Read, Seek, SeekFrom},
+    io::{BufReader, Error as IoError, Read, Seek, SeekFrom, Take},
     pin::Pin,
     task::{Context, Poll},
 };
 
 use arrow::{datatypes::SchemaRef, record_batch::RecordBatch};
 use blaze_jni_bridge::{
-    jni_call, jni_get_object_class, jni_get_string, jni_new_direct_byte_buffer, jni_new_global_ref,
+    jni_call, jni_convert_byte_array, jni_get_object_class, jni_get_string,
+    jni_new_direct_byte_buffer, jni_new_global_ref,
 };
 use datafusion::{
     error::Result,
     physical_plan::{
-        metrics::{BaselineMetrics, Count},
-        RecordBatchStream,
+        metrics::{BaselineMetrics, Count, Time},
+        stream::RecordBatchStreamAdapter,
+        RecordBatchStream, SendableRecordBatchStream,
     },
 };
 use futures::Stream;
@@ -36,8 +38,13 @@ use jni::{
     objects::{GlobalRef, JObject},
     sys::{jint, jlong},
 };
+use tokio::sync::mpsc::{channel, Receiver};
 
-use crate::io::read_one_batch;
+use crate::{
+    checksum::{ChecksumAlgorithm, ChecksumVerifyReader},
+    df_execution_err,
+    io::read_one_batch,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum IpcReadMode {
@@ -89,32 +96,172 @@ impl IpcReaderStream {
         )?;
 
         let schema = self.schema.clone();
-        self.reader = Some(match self.mode {
-            IpcReadMode::ChannelUncompressed => {
-                get_channel_reader(Some(schema), segment.as_obj(), false)?
+        self.reader = Some(get_segment_reader(schema, self.mode, segment.as_obj())?);
+        Ok(true)
+    }
+}
+
+/// reads segments like `IpcReaderStream`, but segments are fetched ahead on
+/// blocking threads and decoded in parallel. at most `num_prefetch_segments`
+/// segments are fetched ahead, and batches are output in the segments order.
+pub fn prefetched_ipc_reader_stream(
+    schema: SchemaRef,
+    segments: GlobalRef,
+    mode: IpcReadMode,
+    num_prefetch_segments: usize,
+    baseline_metrics: BaselineMetrics,
+    size_counter: Count,
+    fetch_wait_time: Time,
+) -> SendableRecordBatchStream {
+    struct PrefetchState {
+        schema: SchemaRef,
+        segments: GlobalRef,
+        mode: IpcReadMode,
+        num_prefetch_segments: usize,
+        segment_receiver: Option<Receiver<Result<Receiver<Result<RecordBatch>>>>>,
+        batch_receiver: Option<Receiver<Result<RecordBatch>>>,
+        baseline_metrics: BaselineMetrics,
+        size_counter: Count,
+        fetch_wait_time: Time,
+    }
+
+    impl PrefetchState {
+        fn start_fetching(&self) -> Receiver<Result<Receiver<Result<RecordBatch>>>> {
+            let (segment_sender, segment_receiver) = channel(self.num_prefetch_segments);
+            let schema = self.schema.clone();
+            let segments = self.segments.clone();
+            let mode = self.mode;
+            let elapsed_compute = self.baseline_metrics.elapsed_compute().clone();
+            let handle = tokio::runtime::Handle::current();
+
+            // fetches segments in order, each segment is decoded in its own
+            // blocking thread and its batches are sent through its own channel
+            handle.clone().spawn_blocking(move || {
+                let fetch = || -> Result<()> {
+                    while jni_call!(ScalaIterator(segments.as_obj()).hasNext() -> bool)? {
+                        let segment = jni_call!(
+                            ScalaIterator(segments.as_obj()).next() -> JObject
+                        )?;
+                        let segment = jni_new_global_ref!(segment.as_obj())?;
+                        let (batch_sender, batch_receiver) = channel(1);
+                        if segment_sender.blocking_send(Ok(batch_receiver)).is_err() {
+                            break; // output stream is dropped
+                        }
+
+                        let schema = schema.clone();
+                        let elapsed_compute = elapsed_compute.clone();
+                        handle.spawn_blocking(move || {
+                            let decode = || -> Result<()> {
+                                let mut reader =
+                                    get_segment_reader(schema, mode, segment.as_obj())?;
+                                while let Some(batch) = {
+                                    let _timer = elapsed_compute.timer();
+                                    reader.next_batch()?
+                                } {
+                                    if batch_sender.blocking_send(Ok(batch)).is_err() {
+                                        break; // output stream is dropped
+                                    }
+                                }
+                                Ok(())
+                            };
+                            if let Err(err) = decode() {
+                                let _ = batch_sender.blocking_send(Err(err));
+                            }
+                        });
+                    }
+                    Ok(())
+                };
+                if let Err(err) = fetch() {
+                    let _ = segment_sender.blocking_send(Err(err));
+                }
+            });
+            segment_receiver
+        }
+
+        async fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+            if self.segment_receiver.is_none() {
+                self.segment_receiver = Some(self.start_fetching());
             }
-            IpcReadMode::Channel => get_channel_reader(Some(schema), segment.as_obj(), true)?,
-            IpcReadMode::ChannelAndFileSegment => {
-                let segment_class = jni_get_object_class!(segment.as_obj())?;
-                let segment_classname_obj =
-                    jni_call!(Class(segment_class.as_obj()).getName() -> JObject)?;
-                let segment_classname = jni_get_string!(segment_classname_obj.as_obj().into())?;
-
-                if segment_classname == "org.apache.spark.storage.FileSegment" {
-                    get_file_segment_reader(Some(schema), segment.as_obj())?
-                } else {
-                    get_channel_reader(Some(schema), segment.as_obj(), true)?
+            loop {
+                if let Some(batch_receiver) = &mut self.batch_receiver {
+                    let batch = {
+                        let _timer = self.fetch_wait_time.timer();
+                        batch_receiver.recv().await
+                    };
+                    if let Some(batch) = batch.transpose()? {
+                        self.size_counter.add(batch.get_array_memory_size());
+                        self.baseline_metrics.record_output(batch.num_rows());
+                        return Ok(Some(batch));
+                    }
+                }
+
+                // current segment reaches EOF, try next segment
+                let segment_receiver = self.segment_receiver.as_mut().unwrap();
+                let batch_receiver = {
+                    let _timer = self.fetch_wait_time.timer();
+                    segment_receiver.recv().await
+                };
+                match batch_receiver.transpose()? {
+                    Some(batch_receiver) => self.batch_receiver = Some(batch_receiver),
+                    None => {
+                        self.baseline_metrics.done();
+                        return Ok(None);
+                    }
                 }
             }
-        });
-        Ok(true)
+        }
     }
+
+    let state = PrefetchState {
+        schema: schema.clone(),
+        segments,
+        mode,
+        num_prefetch_segments: num_prefetch_segments.max(1),
+        segment_receiver: None,
+        batch_receiver: None,
+        baseline_metrics,
+        size_counter,
+        fetch_wait_time,
+    };
+    let stream = futures::stream::unfold(state, |mut state| async move {
+        let next = state.next_batch().await.transpose()?;
+        Some((next, state))
+    });
+    Box::pin(RecordBatchStreamAdapter::new(schema, stream))
+}
+
+fn get_segment_reader(
+    schema: SchemaRef,
+    mode: IpcReadMode,
+    segment: JObject,
+) -> Result<RecordBatchReader> {
+    Ok(match mode {
+        // compression codecs are written in the batches
+        IpcReadMode::ChannelUncompressed | IpcReadMode::Channel => {
+            get_channel_reader(Some(schema), segment)?
+        }
+        IpcReadMode::ChannelAndFileSegment => {
+            let segment_class = jni_get_object_class!(segment)?;
+            let segment_classname_obj =
+                jni_call!(Class(segment_class.as_obj()).getName() -> JObject)?;
+            let segment_classname = jni_get_string!(segment_classname_obj.as_obj().into())?;
+
+            if segment_classname == "org.apache.spark.storage.FileSegment" {
+                get_file_segment_reader(Some(schema), segment)?
+            } else if segment_classname
+                == "org.apache.spark.sql.execution.blaze.shuffle.ChecksummedFileSegment"
+            {
+                get_checksummed_file_segment_reader(Some(schema), segment)?
+            } else {
+                get_channel_reader(Some(schema), segment)?
+            }
+        }
+    })
 }
 
 pub fn get_channel_reader(
     schema: Option<SchemaRef>,
     channel: JObject,
-    compressed: bool,
 ) -> Result<RecordBatchReader> {
     let global_ref = jni_new_global_ref!(channel)?;
     let channel_reader = ReadableByteChannelReader::new(global_ref);
@@ -122,7 +269,6 @@ pub fn get_channel_reader(
     Ok(RecordBatchReader::new(
         Box::new(BufReader::with_capacity(65536, channel_reader)),
         schema,
-        compressed,
     ))
 }
 
@@ -130,20 +276,59 @@ pub fn get_file_segment_reader(
     schema: Option<SchemaRef>,
     file_segment: JObject,
 ) -> Result<RecordBatchReader> {
+    let (_, file) = open_file_segment(file_segment)?;
+    Ok(RecordBatchReader::new(Box::new(file), schema))
+}
+
+/// reads a local file segment, verifying spark's shuffle checksums of the
+/// partitions in it
+pub fn get_checksummed_file_segment_reader(
+    schema: Option<SchemaRef>,
+    checksummed_file_segment: JObject,
+) -> Result<RecordBatchReader> {
+    let file_segment = jni_call!(
+        BlazeChecksummedFileSegment(checksummed_file_segment).segment() -> JObject
+    )?;
+    let algorithm = jni_call!(
+        BlazeChecksummedFileSegment(checksummed_file_segment).algorithm() -> JObject
+    )?;
+    let algorithm = jni_get_string!(algorithm.as_obj().into())?;
+    let algorithm = match ChecksumAlgorithm::from_name(&algorithm) {
+        Some(algorithm) => algorithm,
+        None => return df_execution_err!("unsupported shuffle checksum algorithm: {algorithm}"),
+    };
+    let checksums = jni_call!(
+        BlazeChecksummedFileSegment(checksummed_file_segment).checksums() -> JObject
+    )?;
+    let checksums = jni_convert_byte_array!(checksums.as_obj())?;
+
+    // (partition length, checksum) pairs encoded as big-endian longs
+    let partitions = checksums
+        .chunks_exact(16)
+        .map(|pair| {
+            let length = i64::from_be_bytes(pair[0..8].try_into().unwrap());
+            let checksum = i64::from_be_bytes(pair[8..16].try_into().unwrap());
+            (length as u64, checksum as u64)
+        })
+        .collect::<Vec<_>>();
+
+    let (path, file) = open_file_segment(file_segment.as_obj())?;
+    Ok(RecordBatchReader::new(
+        Box::new(ChecksumVerifyReader::new(file, algorithm, partitions, path)),
+        schema,
+    ))
+}
+
+fn open_file_segment(file_segment: JObject) -> Result<(String, Take<File>)> {
     let file = jni_call!(SparkFileSegment(file_segment).file() -> JObject)?;
     let path = jni_call!(JavaFile(file.as_obj()).getPath() -> JObject)?;
     let path = jni_get_string!(path.as_obj().into())?;
     let offset = jni_call!(SparkFileSegment(file_segment).offset() -> jlong)?;
     let length = jni_call!(SparkFileSegment(file_segment).length() -> jlong)?;
 
-    let mut file = File::open(path)?;
+    let mut file = File::open(&path)?;
     file.seek(SeekFrom::Start(offset as u64))?;
-
-    Ok(RecordBatchReader::new(
-        Box::new(file.take(length as u64)),
-        schema,
-        true,
-    ))
+    Ok((path, file.take(length as u64)))
 }
 
 impl Stream for IpcReaderStream {
@@ -232,19 +417,14 @@ impl Drop for ReadableByteChannelReader {
 pub struct RecordBatchReader {
     input: Box<dyn Read>,
     schema: Option<SchemaRef>,
-    compress: bool,
 }
 
 impl RecordBatchReader {
-    pub fn new(input: Box<dyn Read>, schema: Option<SchemaRef>, compress: bool) -> Self {
-        Self {
-            input,
-            schema,
-            compress,
-        }
+    pub fn new(input: Box<dyn Read>, schema: Option<SchemaRef>) -> Self {
+        Self { input, schema }
     }
 
     pub fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
-        read_one_batch(&mut self.input, self.schema.clone(), self.compress)
+        read_one_batch(&mut self.input, self.schema.clone())
     }
 }
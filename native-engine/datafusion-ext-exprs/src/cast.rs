@@ -19,10 +19,15 @@ use std::{
     sync::Arc,
 };
 
-use arrow::{datatypes::*, record_batch::RecordBatch};
+use arrow::{
+    array::{Array, ArrayRef},
+    datatypes::*,
+    record_batch::RecordBatch,
+};
 use datafusion::{
     common::Result, logical_expr::ColumnarValue, physical_expr::PhysicalExpr, scalar::ScalarValue,
 };
+use datafusion_ext_commons::spark_datetime::ZoneId;
 
 use crate::down_cast_any_ref;
 
@@ -31,20 +36,52 @@ use crate::down_cast_any_ref;
 pub struct TryCastExpr {
     pub expr: Arc<dyn PhysicalExpr>,
     pub cast_type: DataType,
+    pub fail_on_error: bool,
+    pub zone: ZoneId,
 }
 
 impl PartialEq<dyn Any> for TryCastExpr {
     fn eq(&self, other: &dyn Any) -> bool {
         down_cast_any_ref(other)
             .downcast_ref::<Self>()
-            .map(|x| self.expr.eq(&x.expr) && self.cast_type == x.cast_type)
+            .map(|x| {
+                self.expr.eq(&x.expr)
+                    && self.cast_type == x.cast_type
+                    && self.fail_on_error == x.fail_on_error
+                    && self.zone == x.zone
+            })
             .unwrap_or(false)
     }
 }
 
 impl TryCastExpr {
     pub fn new(expr: Arc<dyn PhysicalExpr>, cast_type: DataType) -> Self {
-        Self { expr, cast_type }
+        Self::new_with_options(expr, cast_type, false, ZoneId::UTC)
+    }
+
+    /// creates a cast with the session time zone, raising errors on invalid
+    /// input if `fail_on_error` is set (spark ANSI mode)
+    pub fn new_with_options(
+        expr: Arc<dyn PhysicalExpr>,
+        cast_type: DataType,
+        fail_on_error: bool,
+        zone: ZoneId,
+    ) -> Self {
+        Self {
+            expr,
+            cast_type,
+            fail_on_error,
+            zone,
+        }
+    }
+
+    fn cast(&self, array: &dyn Array) -> Result<ArrayRef> {
+        datafusion_ext_commons::cast::cast_with_zone(
+            array,
+            &self.cast_type,
+            self.fail_on_error,
+            self.zone,
+        )
     }
 }
 
@@ -69,15 +106,10 @@ impl PhysicalExpr for TryCastExpr {
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         Ok(match self.expr.evaluate(batch)? {
-            ColumnarValue::Array(array) => {
-                ColumnarValue::Array(datafusion_ext_commons::cast::cast(&array, &self.cast_type)?)
-            }
+            ColumnarValue::Array(array) => ColumnarValue::Array(self.cast(&array)?),
             ColumnarValue::Scalar(scalar) => {
                 let array = scalar.to_array();
-                ColumnarValue::Scalar(ScalarValue::try_from_array(
-                    &datafusion_ext_commons::cast::cast(&array, &self.cast_type)?,
-                    0,
-                )?)
+                ColumnarValue::Scalar(ScalarValue::try_from_array(&self.cast(&array)?, 0)?)
             }
         })
     }
@@ -90,9 +122,11 @@ impl PhysicalExpr for TryCastExpr {
         self: Arc<Self>,
         children: Vec<Arc<dyn PhysicalExpr>>,
     ) -> Result<Arc<dyn PhysicalExpr>> {
-        Ok(Arc::new(Self::new(
+        Ok(Arc::new(Self::new_with_options(
             children[0].clone(),
             self.cast_type.clone(),
+            self.fail_on_error,
+            self.zone,
         )))
     }
 
@@ -111,6 +145,7 @@ mod test {
         record_batch::RecordBatch,
     };
     use datafusion::physical_expr::{expressions as phys_expr, PhysicalExpr};
+    use datafusion_ext_commons::spark_datetime::ZoneId;
 
     use crate::cast::TryCastExpr;
 
@@ -233,4 +268,38 @@ mod test {
         ]));
         assert_eq!(&ret, &expected);
     }
+    #[test]
+    fn test_fail_on_error() {
+        // cast Utf8 into Int32 in ANSI mode
+        let string_arr: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("123"),
+            Some("-098"),
+            Some("sda"),
+            None, // null
+        ]));
+
+        let schema = Arc::new(Schema::new(vec![Field::new("col", DataType::Utf8, true)]));
+
+        let batch =
+            RecordBatch::try_new(schema, vec![string_arr]).expect("Error creating RecordBatch");
+
+        let expr = Arc::new(TryCastExpr::new_with_options(
+            phys_expr::col("col", &batch.schema()).unwrap(),
+            DataType::Int32,
+            true,
+            ZoneId::UTC,
+        ));
+        let err = expr.evaluate(&batch).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("[CAST_INVALID_INPUT] The value 'sda' of the type \"STRING\""));
+
+        let valid_batch = batch.slice(0, 2);
+        let ret = expr
+            .evaluate(&valid_batch)
+            .expect("Error evaluating expr")
+            .into_array(valid_batch.num_rows());
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![Some(123), Some(-98)]));
+        assert_eq!(&ret, &expected);
+    }
 }
@@ -0,0 +1,222 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    any::Any,
+    fmt::{Debug, Formatter},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use arrow::{
+    compute::kernels::arithmetic::{
+        add_dyn_checked, divide_dyn_checked, modulus_dyn, multiply_dyn_checked,
+        subtract_dyn_checked,
+    },
+    datatypes::{DataType, Schema},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use datafusion::{
+    common::{Result, ScalarValue},
+    logical_expr::{ColumnarValue, Operator},
+    physical_expr::PhysicalExpr,
+};
+use datafusion_ext_commons::{
+    df_execution_err,
+    spark_errors::{arithmetic_overflow_error, divide_by_zero_error},
+};
+
+use crate::down_cast_any_ref;
+
+/// arithmetic expression raising errors on overflow and division by zero,
+/// compatible with spark ANSI mode.
+#[derive(Debug, Hash)]
+pub struct CheckedArithmeticExpr {
+    l: Arc<dyn PhysicalExpr>,
+    op: Operator,
+    r: Arc<dyn PhysicalExpr>,
+}
+
+impl CheckedArithmeticExpr {
+    pub fn try_new(
+        l: Arc<dyn PhysicalExpr>,
+        op: Operator,
+        r: Arc<dyn PhysicalExpr>,
+    ) -> Result<Self> {
+        match op {
+            Operator::Plus
+            | Operator::Minus
+            | Operator::Multiply
+            | Operator::Divide
+            | Operator::Modulo => Ok(Self { l, op, r }),
+            other => df_execution_err!("unsupported checked arithmetic operator: {other}"),
+        }
+    }
+
+    /// spark function to hint for tolerating the error
+    fn try_function_name(&self) -> &'static str {
+        match self.op {
+            Operator::Plus => "try_add",
+            Operator::Minus => "try_subtract",
+            Operator::Multiply => "try_multiply",
+            _ => "try_divide",
+        }
+    }
+}
+
+impl std::fmt::Display for CheckedArithmeticExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "checked({} {} {})", self.l, self.op, self.r)
+    }
+}
+
+impl PartialEq<dyn Any> for CheckedArithmeticExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| self.l.eq(&x.l) && self.op == x.op && self.r.eq(&x.r))
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for CheckedArithmeticExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        self.l.data_type(input_schema)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        Ok(self.l.nullable(input_schema)? || self.r.nullable(input_schema)?)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let num_rows = batch.num_rows();
+        let l = self.l.evaluate(batch)?;
+        let r = self.r.evaluate(batch)?;
+        let all_scalars = matches!(
+            (&l, &r),
+            (ColumnarValue::Scalar(_), ColumnarValue::Scalar(_))
+        );
+        let num_rows = if all_scalars { 1 } else { num_rows };
+        let l = l.into_array(num_rows);
+        let r = r.into_array(num_rows);
+
+        let result = match self.op {
+            Operator::Plus => add_dyn_checked(&l, &r),
+            Operator::Minus => subtract_dyn_checked(&l, &r),
+            Operator::Multiply => multiply_dyn_checked(&l, &r),
+            Operator::Divide => divide_dyn_checked(&l, &r),
+            Operator::Modulo => modulus_dyn(&l, &r),
+            _ => unreachable!("checked arithmetic operator is validated on creating"),
+        }
+        .map_err(|err| match err {
+            ArrowError::ArithmeticOverflow(_) => {
+                arithmetic_overflow_error(l.data_type(), self.try_function_name())
+            }
+            ArrowError::DivideByZero => divide_by_zero_error(),
+            other => other.into(),
+        })?;
+
+        if all_scalars {
+            return Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                &result, 0,
+            )?));
+        }
+        Ok(ColumnarValue::Array(result))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.l.clone(), self.r.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self::try_new(
+            children[0].clone(),
+            self.op,
+            children[1].clone(),
+        )?))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, Int32Array, Int64Array},
+        record_batch::RecordBatch,
+    };
+    use datafusion::{
+        common::Result,
+        logical_expr::Operator,
+        physical_expr::{
+            expressions::{lit, Column},
+            PhysicalExpr,
+        },
+    };
+
+    use crate::checked_arithmetic::CheckedArithmeticExpr;
+
+    #[test]
+    fn test_checked_arithmetic() -> Result<()> {
+        let l: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), Some(i32::MAX), None]));
+        let r: ArrayRef = Arc::new(Int32Array::from(vec![Some(2), Some(0), Some(0)]));
+        let batch = RecordBatch::try_from_iter(vec![("l", l), ("r", r)])?;
+        let (col_l, col_r) = (Arc::new(Column::new("l", 0)), Arc::new(Column::new("r", 1)));
+
+        let expr = CheckedArithmeticExpr::try_new(col_l.clone(), Operator::Plus, lit(1i32))?;
+        let err = expr.evaluate(&batch).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("[ARITHMETIC_OVERFLOW] integer overflow. Use 'try_add'"));
+
+        let expr = CheckedArithmeticExpr::try_new(col_l.clone(), Operator::Minus, col_r.clone())?;
+        let output = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![Some(-1), Some(i32::MAX), None]));
+        assert_eq!(&output, &expected);
+
+        let expr = CheckedArithmeticExpr::try_new(col_l.clone(), Operator::Divide, col_r)?;
+        let err = expr.evaluate(&batch).unwrap_err();
+        assert!(err.to_string().contains("[DIVIDE_BY_ZERO]"));
+
+        // division by zero in null rows is ignored
+        let output = expr.evaluate(&batch.slice(2, 1))?.into_array(1);
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![None]));
+        assert_eq!(&output, &expected);
+
+        let expr = CheckedArithmeticExpr::try_new(lit(i64::MIN), Operator::Multiply, lit(-1i64))?;
+        let err = expr.evaluate(&batch).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("[ARITHMETIC_OVERFLOW] long overflow"));
+
+        let expr = CheckedArithmeticExpr::try_new(lit(7i64), Operator::Modulo, lit(4i64))?;
+        let output = expr.evaluate(&batch)?.into_array(1);
+        let expected: ArrayRef = Arc::new(Int64Array::from(vec![3]));
+        assert_eq!(&output, &expected);
+        Ok(())
+    }
+}
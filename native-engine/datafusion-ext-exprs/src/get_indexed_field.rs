@@ -29,7 +29,7 @@ use datafusion::{
     logical_expr::ColumnarValue,
     physical_expr::PhysicalExpr,
 };
-use datafusion_ext_commons::df_execution_err;
+use datafusion_ext_commons::{df_execution_err, spark_errors::invalid_array_index_error};
 
 use crate::down_cast_any_ref;
 
@@ -38,12 +38,27 @@ use crate::down_cast_any_ref;
 pub struct GetIndexedFieldExpr {
     arg: Arc<dyn PhysicalExpr>,
     key: ScalarValue,
+    fail_on_error: bool,
 }
 
 impl GetIndexedFieldExpr {
     /// Create new get field expression
     pub fn new(arg: Arc<dyn PhysicalExpr>, key: ScalarValue) -> Self {
-        Self { arg, key }
+        Self::new_with_fail_on_error(arg, key, false)
+    }
+
+    /// Create new get field expression raising errors on out-of-bounds list
+    /// indexes (spark ANSI mode)
+    pub fn new_with_fail_on_error(
+        arg: Arc<dyn PhysicalExpr>,
+        key: ScalarValue,
+        fail_on_error: bool,
+    ) -> Self {
+        Self {
+            arg,
+            key,
+            fail_on_error,
+        }
     }
 
     /// Get the input key
@@ -88,6 +103,16 @@ impl PhysicalExpr for GetIndexedFieldExpr {
             (DataType::List(lst), &ScalarValue::Int64(Some(idx))) => {
                 let as_list_array = as_list_array(&array)?;
 
+                if self.fail_on_error {
+                    for i in 0..as_list_array.len() {
+                        let num_elements = as_list_array.value_length(i) as usize;
+                        if as_list_array.is_valid(i) && (idx < 1 || idx > num_elements as i64) {
+                            // spark index starts from 0
+                            return Err(invalid_array_index_error(idx - 1, num_elements));
+                        }
+                    }
+                }
+
                 if idx < 1 || as_list_array.is_empty() {
                     let scalar_null: ScalarValue = lst.data_type().try_into()?;
                     return Ok(ColumnarValue::Scalar(scalar_null));
@@ -141,9 +166,10 @@ impl PhysicalExpr for GetIndexedFieldExpr {
         self: Arc<Self>,
         children: Vec<Arc<dyn PhysicalExpr>>,
     ) -> Result<Arc<dyn PhysicalExpr>> {
-        Ok(Arc::new(GetIndexedFieldExpr::new(
+        Ok(Arc::new(GetIndexedFieldExpr::new_with_fail_on_error(
             children[0].clone(),
             self.key.clone(),
+            self.fail_on_error,
         )))
     }
 
@@ -157,7 +183,9 @@ impl PartialEq<dyn Any> for GetIndexedFieldExpr {
     fn eq(&self, other: &dyn Any) -> bool {
         down_cast_any_ref(other)
             .downcast_ref::<Self>()
-            .map(|x| self.arg.eq(&x.arg) && self.key == x.key)
+            .map(|x| {
+                self.arg.eq(&x.arg) && self.key == x.key && self.fail_on_error == x.fail_on_error
+            })
             .unwrap_or(false)
     }
 }
@@ -232,4 +260,31 @@ mod test {
         assert_batches_eq!(expected, &[output_batch]);
         Ok(())
     }
+    #[test]
+    fn test_list_fail_on_error() -> Result<(), Box<dyn std::error::Error>> {
+        let array: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(100), Some(101), Some(102)]),
+            None,
+            Some(vec![Some(300)]),
+        ]));
+        let input_batch = RecordBatch::try_from_iter_with_nullable(vec![("cccccc1", array, true)])?;
+
+        let get_indexed = Arc::new(GetIndexedFieldExpr::new_with_fail_on_error(
+            Arc::new(Column::new("cccccc1", 0)),
+            ScalarValue::from(2_i64),
+            true,
+        ));
+        let err = get_indexed.evaluate(&input_batch).unwrap_err();
+        assert!(err.to_string().contains(
+            "[INVALID_ARRAY_INDEX] The index 1 is out of bounds. The array has 1 elements."
+        ));
+
+        // null lists are not checked
+        let output_array = get_indexed
+            .evaluate(&input_batch.slice(0, 2))?
+            .into_array(0);
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![Some(101), None]));
+        assert_eq!(&output_array, &expected);
+        Ok(())
+    }
 }
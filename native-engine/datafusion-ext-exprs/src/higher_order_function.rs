@@ -0,0 +1,881 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    any::Any,
+    fmt::{Debug, Formatter},
+    hash::{Hash, Hasher},
+    ops::Range,
+    sync::Arc,
+};
+
+use arrow::{
+    array::*,
+    buffer::{Buffer, NullBuffer, OffsetBuffer},
+    datatypes::{DataType, Schema},
+    record_batch::RecordBatch,
+};
+use datafusion::{
+    common::{
+        cast::{as_boolean_array, as_list_array},
+        Result,
+    },
+    logical_expr::ColumnarValue,
+    physical_expr::{expr_list_eq_strict_order, PhysicalExpr},
+};
+use datafusion_ext_commons::{cast::cast, df_execution_err, downcast_any};
+
+use crate::{down_cast_any_ref, lambda_function::LambdaFunctionExpr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HigherOrderFunction {
+    Transform,
+    Filter,
+    Exists,
+    Forall,
+    Aggregate,
+    ZipWith,
+    MapFilter,
+    TransformValues,
+}
+
+/// higher-order functions compatible with spark, the lambda functions are
+/// evaluated over the flattened elements of the list/map arguments.
+///
+/// transform(array, x [, i] -> ...)
+/// filter(array, x [, i] -> ...)
+/// exists(array, x -> ...)
+/// forall(array, x -> ...)
+/// aggregate(array, zero, (acc, x) -> ... [, acc -> ...])
+/// zip_with(array1, array2, (x, y) -> ...)
+/// map_filter(map, (k, v) -> ...)
+/// transform_values(map, (k, v) -> ...)
+#[derive(Debug, Hash)]
+pub struct HigherOrderFunctionExpr {
+    func: HigherOrderFunction,
+    args: Vec<Arc<dyn PhysicalExpr>>,
+    lambdas: Vec<Arc<dyn PhysicalExpr>>,
+    return_type: DataType,
+}
+
+impl HigherOrderFunctionExpr {
+    pub fn try_new(
+        func: HigherOrderFunction,
+        args: Vec<Arc<dyn PhysicalExpr>>,
+        lambdas: Vec<Arc<dyn PhysicalExpr>>,
+        return_type: DataType,
+    ) -> Result<Self> {
+        let (num_args, num_lambdas) = match func {
+            HigherOrderFunction::Aggregate => (2, 1..=2),
+            HigherOrderFunction::ZipWith => (2, 1..=1),
+            _ => (1, 1..=1),
+        };
+        if args.len() != num_args || !num_lambdas.contains(&lambdas.len()) {
+            df_execution_err!(
+                "{func:?} expects {num_args} arguments and {num_lambdas:?} lambda functions, \
+                    but got {} and {}",
+                args.len(),
+                lambdas.len(),
+            )?;
+        }
+        for lambda in &lambdas {
+            downcast_any!(lambda, LambdaFunctionExpr)?;
+        }
+        Ok(Self {
+            func,
+            args,
+            lambdas,
+            return_type,
+        })
+    }
+
+    fn lambda(&self, i: usize) -> Result<&LambdaFunctionExpr> {
+        downcast_any!(self.lambdas[i], LambdaFunctionExpr)
+    }
+
+    fn transform(&self, batch: &RecordBatch, arg: &ArrayRef) -> Result<ArrayRef> {
+        let list = as_list_array(arg)?;
+        let flattened = Flattened::new(list.value_offsets());
+        let values = flattened.slice(list.values());
+        let lambda = self.lambda(0)?;
+        let lambda_args = element_args(lambda, values, &flattened)?;
+        let transformed = lambda.evaluate_with_args(batch, &flattened.row_indices, &lambda_args)?;
+        self.build_list(flattened.offsets, transformed, list.nulls().cloned())
+    }
+
+    fn filter(&self, batch: &RecordBatch, arg: &ArrayRef) -> Result<ArrayRef> {
+        let list = as_list_array(arg)?;
+        let flattened = Flattened::new(list.value_offsets());
+        let values = flattened.slice(list.values());
+        let lambda = self.lambda(0)?;
+        let lambda_args = element_args(lambda, values.clone(), &flattened)?;
+        let predicate = lambda.evaluate_with_args(batch, &flattened.row_indices, &lambda_args)?;
+        let mask = selection_mask(&predicate)?;
+        let offsets = flattened.filtered_offsets(&mask);
+        let filtered = arrow::compute::filter(&values, &mask)?;
+        self.build_list(offsets, filtered, list.nulls().cloned())
+    }
+
+    fn exists_or_forall(&self, batch: &RecordBatch, arg: &ArrayRef) -> Result<ArrayRef> {
+        let list = as_list_array(arg)?;
+        let flattened = Flattened::new(list.value_offsets());
+        let values = flattened.slice(list.values());
+        let lambda = self.lambda(0)?;
+        let predicate = lambda.evaluate_with_args(batch, &flattened.row_indices, &[values])?;
+        let predicate = as_boolean_array(&predicate)?;
+
+        // follows spark's three-valued logic: when no element decides the result,
+        // null is returned if any predicate is null
+        let decisive_value = self.func == HigherOrderFunction::Exists;
+        let mut builder = BooleanBuilder::with_capacity(list.len());
+        for i in 0..list.len() {
+            if list.is_null(i) {
+                builder.append_null();
+                continue;
+            }
+            let mut has_null = false;
+            let mut decided = false;
+            for j in flattened.range(i) {
+                if predicate.is_null(j) {
+                    has_null = true;
+                } else if predicate.value(j) == decisive_value {
+                    decided = true;
+                    break;
+                }
+            }
+            match (decided, has_null) {
+                (true, _) => builder.append_value(decisive_value),
+                (false, true) => builder.append_null(),
+                (false, false) => builder.append_value(!decisive_value),
+            }
+        }
+        Ok(Arc::new(builder.finish()))
+    }
+
+    fn aggregate(&self, batch: &RecordBatch, arg: &ArrayRef, zero: &ArrayRef) -> Result<ArrayRef> {
+        let list = as_list_array(arg)?;
+        let flattened = Flattened::new(list.value_offsets());
+        let values = flattened.slice(list.values());
+        let merge = self.lambda(0)?;
+        let num_rows = list.len();
+
+        // merges the k-th elements of all lists in each round
+        let mut acc = zero.clone();
+        let max_len = (0..num_rows)
+            .map(|i| flattened.range(i).len())
+            .max()
+            .unwrap_or(0);
+        for k in 0..max_len {
+            let active_rows = UInt32Array::from_iter_values(
+                (0..num_rows)
+                    .filter(|&i| flattened.range(i).len() > k)
+                    .map(|i| i as u32),
+            );
+            let element_indices = UInt32Array::from_iter_values(
+                active_rows
+                    .values()
+                    .iter()
+                    .map(|&i| (flattened.range(i as usize).start + k) as u32),
+            );
+            let merge_args = vec![
+                arrow::compute::take(&acc, &active_rows, None)?,
+                arrow::compute::take(&values, &element_indices, None)?,
+            ];
+            let merged = merge.evaluate_with_args(batch, &active_rows, &merge_args)?;
+            let merged = cast_if_needed(merged, acc.data_type())?;
+
+            let mut interleave_indices = (0..num_rows).map(|i| (0, i)).collect::<Vec<_>>();
+            for (merged_idx, &i) in active_rows.values().iter().enumerate() {
+                interleave_indices[i as usize] = (1, merged_idx);
+            }
+            acc =
+                arrow::compute::interleave(&[acc.as_ref(), merged.as_ref()], &interleave_indices)?;
+        }
+
+        let result = match self.lambdas.len() {
+            2 => {
+                let all_rows = UInt32Array::from_iter_values(0..num_rows as u32);
+                self.lambda(1)?
+                    .evaluate_with_args(batch, &all_rows, &[acc])?
+            }
+            _ => acc,
+        };
+        let result = cast_if_needed(result, &self.return_type)?;
+        if list.null_count() > 0 {
+            return Ok(arrow::compute::nullif(
+                &result,
+                &arrow::compute::is_null(list)?,
+            )?);
+        }
+        Ok(result)
+    }
+
+    fn zip_with(&self, batch: &RecordBatch, left: &ArrayRef, right: &ArrayRef) -> Result<ArrayRef> {
+        let left = as_list_array(left)?;
+        let right = as_list_array(right)?;
+        let nulls = NullBuffer::union(left.nulls(), right.nulls());
+
+        // the shorter list is padded with nulls
+        let mut offsets = Vec::with_capacity(left.len() + 1);
+        let mut row_indices = vec![];
+        let mut left_indices = vec![];
+        let mut right_indices = vec![];
+        offsets.push(0);
+        for i in 0..left.len() {
+            if nulls.as_ref().map(|nb| nb.is_valid(i)).unwrap_or(true) {
+                let (left_range, right_range) = (list_range(left, i), list_range(right, i));
+                for k in 0..left_range.len().max(right_range.len()) {
+                    row_indices.push(i as u32);
+                    left_indices
+                        .push((k < left_range.len()).then(|| (left_range.start + k) as u32));
+                    right_indices
+                        .push((k < right_range.len()).then(|| (right_range.start + k) as u32));
+                }
+            }
+            offsets.push(row_indices.len() as i32);
+        }
+
+        let lambda_args = vec![
+            arrow::compute::take(left.values(), &UInt32Array::from(left_indices), None)?,
+            arrow::compute::take(right.values(), &UInt32Array::from(right_indices), None)?,
+        ];
+        let row_indices = UInt32Array::from(row_indices);
+        let zipped = self
+            .lambda(0)?
+            .evaluate_with_args(batch, &row_indices, &lambda_args)?;
+        self.build_list(offsets, zipped, nulls)
+    }
+
+    fn map_filter(&self, batch: &RecordBatch, arg: &ArrayRef) -> Result<ArrayRef> {
+        let map = downcast_any!(arg, MapArray)?;
+        let flattened = Flattened::new(map.value_offsets());
+        let keys = flattened.slice(map.keys());
+        let values = flattened.slice(map.values());
+        let predicate = self.lambda(0)?.evaluate_with_args(
+            batch,
+            &flattened.row_indices,
+            &[keys.clone(), values.clone()],
+        )?;
+        let mask = selection_mask(&predicate)?;
+        let offsets = flattened.filtered_offsets(&mask);
+        self.build_map(
+            offsets,
+            arrow::compute::filter(&keys, &mask)?,
+            arrow::compute::filter(&values, &mask)?,
+            map.nulls().cloned(),
+        )
+    }
+
+    fn transform_values(&self, batch: &RecordBatch, arg: &ArrayRef) -> Result<ArrayRef> {
+        let map = downcast_any!(arg, MapArray)?;
+        let flattened = Flattened::new(map.value_offsets());
+        let keys = flattened.slice(map.keys());
+        let values = flattened.slice(map.values());
+        let transformed = self.lambda(0)?.evaluate_with_args(
+            batch,
+            &flattened.row_indices,
+            &[keys.clone(), values],
+        )?;
+        self.build_map(flattened.offsets, keys, transformed, map.nulls().cloned())
+    }
+
+    fn build_list(
+        &self,
+        offsets: Vec<i32>,
+        values: ArrayRef,
+        nulls: Option<NullBuffer>,
+    ) -> Result<ArrayRef> {
+        let field = match &self.return_type {
+            DataType::List(field) => field.clone(),
+            other => df_execution_err!(
+                "{:?} expects returning list type, but got {other}",
+                self.func
+            )?,
+        };
+        let values = cast_if_needed(values, field.data_type())?;
+        Ok(Arc::new(ListArray::try_new(
+            field,
+            OffsetBuffer::new(offsets.into()),
+            values,
+            nulls,
+        )?))
+    }
+
+    fn build_map(
+        &self,
+        offsets: Vec<i32>,
+        keys: ArrayRef,
+        values: ArrayRef,
+        nulls: Option<NullBuffer>,
+    ) -> Result<ArrayRef> {
+        let entry_fields = match &self.return_type {
+            DataType::Map(entries_field, _) => match entries_field.data_type() {
+                DataType::Struct(fields) if fields.len() == 2 => fields.clone(),
+                other => df_execution_err!("invalid map entries type: {other}")?,
+            },
+            other => df_execution_err!(
+                "{:?} expects returning map type, but got {other}",
+                self.func
+            )?,
+        };
+        let keys = cast_if_needed(keys, entry_fields[0].data_type())?;
+        let values = cast_if_needed(values, entry_fields[1].data_type())?;
+        let entries = StructArray::try_new(entry_fields, vec![keys, values], None)?;
+        let map_data = ArrayData::builder(self.return_type.clone())
+            .len(offsets.len() - 1)
+            .add_buffer(Buffer::from_vec(offsets))
+            .add_child_data(entries.into_data())
+            .nulls(nulls)
+            .build()?;
+        Ok(make_array(map_data))
+    }
+}
+
+/// positions of the flattened elements of list/map arrays, with offsets
+/// rebased to the first element.
+struct Flattened {
+    base_offset: usize,
+    offsets: Vec<i32>,
+    row_indices: UInt32Array,
+    positions: Int32Array,
+}
+
+impl Flattened {
+    fn new(offsets: &[i32]) -> Self {
+        let base_offset = offsets[0];
+        let num_elements = (offsets[offsets.len() - 1] - base_offset) as usize;
+        let mut row_indices = Vec::with_capacity(num_elements);
+        let mut positions = Vec::with_capacity(num_elements);
+        for (i, range) in offsets.windows(2).enumerate() {
+            for pos in 0..range[1] - range[0] {
+                row_indices.push(i as u32);
+                positions.push(pos);
+            }
+        }
+        Self {
+            base_offset: base_offset as usize,
+            offsets: offsets.iter().map(|&offset| offset - base_offset).collect(),
+            row_indices: UInt32Array::from(row_indices),
+            positions: Int32Array::from(positions),
+        }
+    }
+
+    fn range(&self, i: usize) -> Range<usize> {
+        self.offsets[i] as usize..self.offsets[i + 1] as usize
+    }
+
+    fn slice(&self, values: &ArrayRef) -> ArrayRef {
+        values.slice(self.base_offset, self.row_indices.len())
+    }
+
+    fn filtered_offsets(&self, mask: &BooleanArray) -> Vec<i32> {
+        let num_rows = self.offsets.len() - 1;
+        let mut offsets = Vec::with_capacity(num_rows + 1);
+        let mut num_selected = 0;
+        offsets.push(0);
+        for i in 0..num_rows {
+            num_selected += self.range(i).filter(|&j| mask.value(j)).count();
+            offsets.push(num_selected as i32);
+        }
+        offsets
+    }
+}
+
+fn element_args(
+    lambda: &LambdaFunctionExpr,
+    values: ArrayRef,
+    flattened: &Flattened,
+) -> Result<Vec<ArrayRef>> {
+    match lambda.arg_fields().len() {
+        1 => Ok(vec![values]),
+        2 => Ok(vec![values, Arc::new(flattened.positions.clone())]),
+        n => df_execution_err!("element lambda function expects 1 or 2 arguments, but got {n}"),
+    }
+}
+
+/// selects elements with true predicate, null is treated as false
+fn selection_mask(predicate: &ArrayRef) -> Result<BooleanArray> {
+    let predicate = as_boolean_array(predicate)?;
+    Ok(predicate.iter().map(|v| Some(v == Some(true))).collect())
+}
+
+fn list_range(list: &ListArray, i: usize) -> Range<usize> {
+    let offsets = list.value_offsets();
+    offsets[i] as usize..offsets[i + 1] as usize
+}
+
+fn cast_if_needed(array: ArrayRef, data_type: &DataType) -> Result<ArrayRef> {
+    if array.data_type() == data_type {
+        return Ok(array);
+    }
+    cast(&array, data_type)
+}
+
+impl std::fmt::Display for HigherOrderFunctionExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let args = self
+            .args
+            .iter()
+            .chain(&self.lambdas)
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{:?}({})", self.func, args.join(", "))
+    }
+}
+
+impl PartialEq<dyn Any> for HigherOrderFunctionExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| {
+                self.func == x.func
+                    && expr_list_eq_strict_order(&self.args, &x.args)
+                    && expr_list_eq_strict_order(&self.lambdas, &x.lambdas)
+                    && self.return_type == x.return_type
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for HigherOrderFunctionExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.return_type.clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let num_rows = batch.num_rows();
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.evaluate(batch).map(|v| v.into_array(num_rows)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let output = match self.func {
+            HigherOrderFunction::Transform => self.transform(batch, &args[0])?,
+            HigherOrderFunction::Filter => self.filter(batch, &args[0])?,
+            HigherOrderFunction::Exists | HigherOrderFunction::Forall => {
+                self.exists_or_forall(batch, &args[0])?
+            }
+            HigherOrderFunction::Aggregate => self.aggregate(batch, &args[0], &args[1])?,
+            HigherOrderFunction::ZipWith => self.zip_with(batch, &args[0], &args[1])?,
+            HigherOrderFunction::MapFilter => self.map_filter(batch, &args[0])?,
+            HigherOrderFunction::TransformValues => self.transform_values(batch, &args[0])?,
+        };
+        Ok(ColumnarValue::Array(output))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        self.args.iter().chain(&self.lambdas).cloned().collect()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let (args, lambdas) = children.split_at(self.args.len());
+        Ok(Arc::new(Self::try_new(
+            self.func,
+            args.to_vec(),
+            lambdas.to_vec(),
+            self.return_type.clone(),
+        )?))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::{array::*, datatypes::*, record_batch::RecordBatch};
+    use datafusion::{
+        common::Result,
+        logical_expr::Operator,
+        physical_expr::{
+            expressions::{lit, BinaryExpr, Column},
+            PhysicalExpr,
+        },
+    };
+
+    use crate::{
+        higher_order_function::{HigherOrderFunction, HigherOrderFunctionExpr},
+        lambda_function::LambdaFunctionExpr,
+        lambda_variable::LambdaVariableExpr,
+    };
+
+    fn var(name: &str, reversed_index: usize) -> Arc<dyn PhysicalExpr> {
+        Arc::new(LambdaVariableExpr::new(
+            name.to_string(),
+            reversed_index,
+            DataType::Int32,
+            true,
+        ))
+    }
+
+    fn binary(
+        l: Arc<dyn PhysicalExpr>,
+        op: Operator,
+        r: Arc<dyn PhysicalExpr>,
+    ) -> Arc<dyn PhysicalExpr> {
+        Arc::new(BinaryExpr::new(l, op, r))
+    }
+
+    fn lambda(
+        body: Arc<dyn PhysicalExpr>,
+        args: &[&str],
+        num_enclosing_args: usize,
+    ) -> Arc<dyn PhysicalExpr> {
+        let arg_fields = args
+            .iter()
+            .map(|name| Arc::new(Field::new(*name, DataType::Int32, true)))
+            .collect();
+        Arc::new(LambdaFunctionExpr::new(
+            body,
+            arg_fields,
+            num_enclosing_args,
+        ))
+    }
+
+    fn int_list_type() -> DataType {
+        DataType::List(Arc::new(Field::new("item", DataType::Int32, true)))
+    }
+
+    fn int_list(values: Vec<Option<Vec<Option<i32>>>>) -> ArrayRef {
+        Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(values))
+    }
+
+    fn test_batch() -> Result<RecordBatch> {
+        let a = int_list(vec![
+            Some(vec![Some(1), Some(2), Some(3)]),
+            Some(vec![Some(4), None]),
+            None,
+            Some(vec![]),
+        ]);
+        let b = int_list(vec![
+            Some(vec![Some(1)]),
+            Some(vec![Some(1), Some(1), Some(1)]),
+            Some(vec![Some(1)]),
+            None,
+        ]);
+        let c: ArrayRef = Arc::new(Int32Array::from(vec![10, 20, 30, 40]));
+        Ok(RecordBatch::try_from_iter(vec![
+            ("a", a),
+            ("b", b),
+            ("c", c),
+        ])?)
+    }
+
+    fn evaluate(
+        func: HigherOrderFunction,
+        args: Vec<Arc<dyn PhysicalExpr>>,
+        lambdas: Vec<Arc<dyn PhysicalExpr>>,
+        return_type: DataType,
+    ) -> Result<ArrayRef> {
+        let batch = test_batch()?;
+        let expr = HigherOrderFunctionExpr::try_new(func, args, lambdas, return_type)?;
+        Ok(expr.evaluate(&batch)?.into_array(batch.num_rows()))
+    }
+
+    #[test]
+    fn test_transform() -> Result<()> {
+        // transform(a, x -> x + c)
+        let output = evaluate(
+            HigherOrderFunction::Transform,
+            vec![Arc::new(Column::new("a", 0))],
+            vec![lambda(
+                binary(var("x", 1), Operator::Plus, Arc::new(Column::new("c", 2))),
+                &["x"],
+                0,
+            )],
+            int_list_type(),
+        )?;
+        let expected = int_list(vec![
+            Some(vec![Some(11), Some(12), Some(13)]),
+            Some(vec![Some(24), None]),
+            None,
+            Some(vec![]),
+        ]);
+        assert_eq!(&output, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_transform() -> Result<()> {
+        // transform(a, x -> transform(b, y -> x + y))
+        let inner = Arc::new(HigherOrderFunctionExpr::try_new(
+            HigherOrderFunction::Transform,
+            vec![Arc::new(Column::new("b", 1))],
+            vec![lambda(
+                binary(var("x", 2), Operator::Plus, var("y", 1)),
+                &["y"],
+                1,
+            )],
+            int_list_type(),
+        )?);
+        let output = evaluate(
+            HigherOrderFunction::Transform,
+            vec![Arc::new(Column::new("a", 0))],
+            vec![lambda(inner, &["x"], 0)],
+            DataType::List(Arc::new(Field::new("item", int_list_type(), true))),
+        )?;
+
+        let mut builder = ListBuilder::new(ListBuilder::new(Int32Builder::new()));
+        for row in [
+            Some(vec![vec![Some(2)], vec![Some(3)], vec![Some(4)]]),
+            Some(vec![vec![Some(5); 3], vec![None; 3]]),
+            None,
+            Some(vec![]),
+        ] {
+            match row {
+                Some(lists) => {
+                    for list in lists {
+                        builder.values().append_value(list);
+                    }
+                    builder.append(true);
+                }
+                None => builder.append(false),
+            }
+        }
+        let expected: ArrayRef = Arc::new(builder.finish());
+        assert_eq!(&output, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter() -> Result<()> {
+        // filter(a, (x, i) -> x > 1 and i < 2)
+        let output = evaluate(
+            HigherOrderFunction::Filter,
+            vec![Arc::new(Column::new("a", 0))],
+            vec![lambda(
+                binary(
+                    binary(var("x", 2), Operator::Gt, lit(1i32)),
+                    Operator::And,
+                    binary(var("i", 1), Operator::Lt, lit(2i32)),
+                ),
+                &["x", "i"],
+                0,
+            )],
+            int_list_type(),
+        )?;
+        let expected = int_list(vec![
+            Some(vec![Some(2)]),
+            Some(vec![Some(4)]),
+            None,
+            Some(vec![]),
+        ]);
+        assert_eq!(&output, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_exists_and_forall() -> Result<()> {
+        // exists(a, x -> x > 3)
+        let output = evaluate(
+            HigherOrderFunction::Exists,
+            vec![Arc::new(Column::new("a", 0))],
+            vec![lambda(
+                binary(var("x", 1), Operator::Gt, lit(3i32)),
+                &["x"],
+                0,
+            )],
+            DataType::Boolean,
+        )?;
+        let expected: ArrayRef = Arc::new(BooleanArray::from(vec![
+            Some(false),
+            Some(true),
+            None,
+            Some(false),
+        ]));
+        assert_eq!(&output, &expected);
+
+        // forall(a, x -> x > 1)
+        let output = evaluate(
+            HigherOrderFunction::Forall,
+            vec![Arc::new(Column::new("a", 0))],
+            vec![lambda(
+                binary(var("x", 1), Operator::Gt, lit(1i32)),
+                &["x"],
+                0,
+            )],
+            DataType::Boolean,
+        )?;
+        let expected: ArrayRef = Arc::new(BooleanArray::from(vec![
+            Some(false),
+            None,
+            None,
+            Some(true),
+        ]));
+        assert_eq!(&output, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregate() -> Result<()> {
+        // aggregate(a, 0, (acc, x) -> acc + x, acc -> acc * 10)
+        let output = evaluate(
+            HigherOrderFunction::Aggregate,
+            vec![Arc::new(Column::new("a", 0)), lit(0i32)],
+            vec![
+                lambda(
+                    binary(var("acc", 2), Operator::Plus, var("x", 1)),
+                    &["acc", "x"],
+                    0,
+                ),
+                lambda(
+                    binary(var("acc", 1), Operator::Multiply, lit(10i32)),
+                    &["acc"],
+                    0,
+                ),
+            ],
+            DataType::Int32,
+        )?;
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![Some(60), None, None, Some(0)]));
+        assert_eq!(&output, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip_with() -> Result<()> {
+        // zip_with(a, b, (x, y) -> x + y)
+        let output = evaluate(
+            HigherOrderFunction::ZipWith,
+            vec![Arc::new(Column::new("a", 0)), Arc::new(Column::new("b", 1))],
+            vec![lambda(
+                binary(var("x", 2), Operator::Plus, var("y", 1)),
+                &["x", "y"],
+                0,
+            )],
+            int_list_type(),
+        )?;
+        let expected = int_list(vec![
+            Some(vec![Some(2), None, None]),
+            Some(vec![Some(5), None, None]),
+            None,
+            None,
+        ]);
+        assert_eq!(&output, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_filter_and_transform_values() -> Result<()> {
+        let keys = vec!["a", "b", "c"];
+        let values = Int32Array::from(vec![1, 2, 3]);
+        let map: ArrayRef = Arc::new(MapArray::new_from_strings(
+            keys.into_iter(),
+            &values,
+            &[0, 2, 3],
+        )?);
+        let map_type = map.data_type().clone();
+        let batch = RecordBatch::try_from_iter(vec![("m", map)])?;
+
+        // map_filter(m, (k, v) -> v > 1)
+        let map_filter = HigherOrderFunctionExpr::try_new(
+            HigherOrderFunction::MapFilter,
+            vec![Arc::new(Column::new("m", 0))],
+            vec![lambda(
+                binary(var("v", 1), Operator::Gt, lit(1i32)),
+                &["k", "v"],
+                0,
+            )],
+            map_type.clone(),
+        )?;
+        let output = map_filter.evaluate(&batch)?.into_array(batch.num_rows());
+        let expected: ArrayRef = Arc::new(MapArray::new_from_strings(
+            vec!["b", "c"].into_iter(),
+            &Int32Array::from(vec![2, 3]),
+            &[0, 1, 2],
+        )?);
+        assert_eq!(&output, &expected);
+
+        // transform_values(m, (k, v) -> v * 10)
+        let transform_values = HigherOrderFunctionExpr::try_new(
+            HigherOrderFunction::TransformValues,
+            vec![Arc::new(Column::new("m", 0))],
+            vec![lambda(
+                binary(var("v", 1), Operator::Multiply, lit(10i32)),
+                &["k", "v"],
+                0,
+            )],
+            map_type,
+        )?;
+        let output = transform_values
+            .evaluate(&batch)?
+            .into_array(batch.num_rows());
+        let expected: ArrayRef = Arc::new(MapArray::new_from_strings(
+            vec!["a", "b", "c"].into_iter(),
+            &Int32Array::from(vec![10, 20, 30]),
+            &[0, 2, 3],
+        )?);
+        assert_eq!(&output, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_filter_casts_keys_to_return_type() -> Result<()> {
+        let map: ArrayRef = Arc::new(MapArray::new_from_strings(
+            vec!["a", "b"].into_iter(),
+            &Int32Array::from(vec![1, 2]),
+            &[0, 2],
+        )?);
+        let batch = RecordBatch::try_from_iter(vec![("m", map)])?;
+        let entries_type = DataType::Struct(Fields::from(vec![
+            Field::new("keys", DataType::LargeUtf8, false),
+            Field::new("values", DataType::Int64, true),
+        ]));
+        let return_type =
+            DataType::Map(Arc::new(Field::new("entries", entries_type, false)), false);
+
+        // map_filter(m, (k, v) -> v > 1) returning map<large_string, bigint>
+        let map_filter = HigherOrderFunctionExpr::try_new(
+            HigherOrderFunction::MapFilter,
+            vec![Arc::new(Column::new("m", 0))],
+            vec![lambda(
+                binary(var("v", 1), Operator::Gt, lit(1i32)),
+                &["k", "v"],
+                0,
+            )],
+            return_type.clone(),
+        )?;
+        let output = map_filter.evaluate(&batch)?.into_array(batch.num_rows());
+        assert_eq!(output.data_type(), &return_type);
+        let output = output.as_any().downcast_ref::<MapArray>().unwrap();
+        let keys = output
+            .keys()
+            .as_any()
+            .downcast_ref::<LargeStringArray>()
+            .unwrap();
+        let values = output
+            .values()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(keys.iter().collect::<Vec<_>>(), vec![Some("b")]);
+        assert_eq!(values.iter().collect::<Vec<_>>(), vec![Some(2)]);
+        Ok(())
+    }
+}
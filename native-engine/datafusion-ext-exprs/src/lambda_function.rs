@@ -0,0 +1,211 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    any::Any,
+    fmt::{Debug, Formatter},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use arrow::{
+    array::{new_null_array, ArrayRef, UInt32Array},
+    datatypes::{DataType, Field, FieldRef, Schema},
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use datafusion::{
+    common::Result,
+    logical_expr::ColumnarValue,
+    physical_expr::{expressions::Column, PhysicalExpr},
+};
+use datafusion_ext_commons::df_execution_err;
+
+use crate::down_cast_any_ref;
+
+/// lambda function used as argument of higher-order functions.
+///
+/// the body is evaluated on a batch consisting of the input columns followed
+/// by the arguments, see `evaluate_with_args()`. the lambda cannot be
+/// evaluated as a standalone expression.
+#[derive(Debug, Hash)]
+pub struct LambdaFunctionExpr {
+    body: Arc<dyn PhysicalExpr>,
+    arg_fields: Vec<FieldRef>,
+    num_enclosing_args: usize,
+    referenced_columns: Vec<usize>,
+}
+
+impl LambdaFunctionExpr {
+    /// `num_enclosing_args` is the total number of arguments of all lambda
+    /// functions enclosing this one, which are the trailing columns of the
+    /// input batch.
+    pub fn new(
+        body: Arc<dyn PhysicalExpr>,
+        arg_fields: Vec<FieldRef>,
+        num_enclosing_args: usize,
+    ) -> Self {
+        let mut referenced_columns = vec![];
+        collect_referenced_columns(&body, &mut referenced_columns);
+        referenced_columns.sort_unstable();
+        referenced_columns.dedup();
+
+        Self {
+            body,
+            arg_fields,
+            num_enclosing_args,
+            referenced_columns,
+        }
+    }
+
+    pub fn body(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.body
+    }
+
+    pub fn arg_fields(&self) -> &[FieldRef] {
+        &self.arg_fields
+    }
+
+    /// evaluates the body with the given arguments. the i-th row of arguments
+    /// is bound to the input row `row_indices[i]`.
+    pub fn evaluate_with_args(
+        &self,
+        batch: &RecordBatch,
+        row_indices: &UInt32Array,
+        args: &[ArrayRef],
+    ) -> Result<ArrayRef> {
+        if args.len() != self.arg_fields.len() {
+            df_execution_err!(
+                "lambda function expects {} arguments, but got {}",
+                self.arg_fields.len(),
+                args.len(),
+            )?;
+        }
+        let num_rows = row_indices.len();
+        let num_columns = batch.num_columns();
+        let enclosing_args_start = num_columns.saturating_sub(self.num_enclosing_args);
+
+        // only columns used by the body are taken, others are filled with nulls
+        let mut fields = Vec::with_capacity(num_columns + args.len());
+        let mut columns = Vec::with_capacity(num_columns + args.len());
+        for (i, field) in batch.schema().fields().iter().enumerate() {
+            if i >= enclosing_args_start || self.referenced_columns.binary_search(&i).is_ok() {
+                fields.push(field.clone());
+                columns.push(arrow::compute::take(batch.column(i), row_indices, None)?);
+            } else {
+                fields.push(Arc::new(Field::new(
+                    field.name(),
+                    field.data_type().clone(),
+                    true,
+                )));
+                columns.push(new_null_array(field.data_type(), num_rows));
+            }
+        }
+        for (field, arg) in self.arg_fields.iter().zip(args) {
+            fields.push(Arc::new(Field::new(
+                field.name(),
+                arg.data_type().clone(),
+                true,
+            )));
+            columns.push(arg.clone());
+        }
+        let lambda_batch = RecordBatch::try_new_with_options(
+            Arc::new(Schema::new(fields)),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )?;
+        Ok(self.body.evaluate(&lambda_batch)?.into_array(num_rows))
+    }
+
+    fn lambda_schema(&self, input_schema: &Schema) -> Schema {
+        Schema::new(
+            input_schema
+                .fields()
+                .iter()
+                .chain(&self.arg_fields)
+                .cloned()
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+fn collect_referenced_columns(expr: &Arc<dyn PhysicalExpr>, indices: &mut Vec<usize>) {
+    if let Some(column) = expr.as_any().downcast_ref::<Column>() {
+        indices.push(column.index());
+    }
+    for child in expr.children() {
+        collect_referenced_columns(&child, indices);
+    }
+}
+
+impl std::fmt::Display for LambdaFunctionExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let arg_names = self
+            .arg_fields
+            .iter()
+            .map(|field| field.name().as_str())
+            .collect::<Vec<_>>();
+        write!(f, "lambda ({}) -> {}", arg_names.join(", "), self.body)
+    }
+}
+
+impl PartialEq<dyn Any> for LambdaFunctionExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| {
+                self.body.eq(&x.body)
+                    && self.arg_fields == x.arg_fields
+                    && self.num_enclosing_args == x.num_enclosing_args
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for LambdaFunctionExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        self.body.data_type(&self.lambda_schema(input_schema))
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        self.body.nullable(&self.lambda_schema(input_schema))
+    }
+
+    fn evaluate(&self, _batch: &RecordBatch) -> Result<ColumnarValue> {
+        df_execution_err!("lambda function cannot be evaluated without arguments")
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.body.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self::new(
+            children[0].clone(),
+            self.arg_fields.clone(),
+            self.num_enclosing_args,
+        )))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    any::Any,
+    fmt::{Debug, Formatter},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use arrow::{
+    datatypes::{DataType, Schema},
+    record_batch::RecordBatch,
+};
+use datafusion::{common::Result, logical_expr::ColumnarValue, physical_expr::PhysicalExpr};
+use datafusion_ext_commons::df_execution_err;
+
+use crate::down_cast_any_ref;
+
+/// expression referencing an argument of the enclosing lambda function.
+///
+/// lambda arguments are appended after the columns of the input batch, so
+/// the variable is located by its position counted from the last column.
+/// this keeps the reference valid when the leading input columns are pruned.
+#[derive(Debug, Hash)]
+pub struct LambdaVariableExpr {
+    name: String,
+    reversed_index: usize,
+    data_type: DataType,
+    nullable: bool,
+}
+
+impl LambdaVariableExpr {
+    pub fn new(name: String, reversed_index: usize, data_type: DataType, nullable: bool) -> Self {
+        Self {
+            name,
+            reversed_index,
+            data_type,
+            nullable,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// position of the variable counted from the last column, starting from 1
+    pub fn reversed_index(&self) -> usize {
+        self.reversed_index
+    }
+}
+
+impl std::fmt::Display for LambdaVariableExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "lambda {}@-{}", self.name, self.reversed_index)
+    }
+}
+
+impl PartialEq<dyn Any> for LambdaVariableExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| {
+                self.name == x.name
+                    && self.reversed_index == x.reversed_index
+                    && self.data_type == x.data_type
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for LambdaVariableExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.data_type.clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(self.nullable)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let num_columns = batch.num_columns();
+        if self.reversed_index == 0 || self.reversed_index > num_columns {
+            df_execution_err!(
+                "lambda variable {} not found in batch with {num_columns} columns",
+                self.name,
+            )?;
+        }
+        let array = batch.column(num_columns - self.reversed_index).clone();
+        Ok(ColumnarValue::Array(array))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(self)
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
@@ -17,8 +17,12 @@ use std::{any::Any, sync::Arc};
 use datafusion::physical_expr::PhysicalExpr;
 
 pub mod cast;
+pub mod checked_arithmetic;
 pub mod get_indexed_field;
 pub mod get_map_value;
+pub mod higher_order_function;
+pub mod lambda_function;
+pub mod lambda_variable;
 pub mod named_struct;
 pub mod spark_scalar_subquery_wrapper;
 pub mod spark_udf_wrapper;
@@ -17,14 +17,21 @@ use std::sync::Arc;
 use datafusion::{common::Result, logical_expr::ScalarFunctionImplementation};
 use datafusion_ext_commons::df_unimplemented_err;
 
+mod spark_arrays;
 mod spark_check_overflow;
+mod spark_conv;
+mod spark_encode;
+mod spark_format_string;
 mod spark_get_json_object;
 mod spark_make_array;
 mod spark_make_decimal;
+mod spark_maps;
 mod spark_murmur3_hash;
 mod spark_null_if_zero;
+mod spark_parse_url;
 mod spark_strings;
 mod spark_unscaled_value;
+mod spark_utils;
 
 pub fn create_spark_ext_function(name: &str) -> Result<ScalarFunctionImplementation> {
     Ok(match name {
@@ -45,6 +52,35 @@ pub fn create_spark_ext_function(name: &str) -> Result<ScalarFunctionImplementat
         "StringConcatWs" => Arc::new(spark_strings::string_concat_ws),
         "StringLower" => Arc::new(spark_strings::string_lower),
         "StringUpper" => Arc::new(spark_strings::string_upper),
+        "StringSubstringIndex" => Arc::new(spark_strings::string_substring_index),
+        "StringLocate" => Arc::new(spark_strings::string_locate),
+        "StringInstr" => Arc::new(spark_strings::string_instr),
+        "StringLevenshtein" => Arc::new(spark_strings::string_levenshtein),
+        "StringSoundex" => Arc::new(spark_strings::string_soundex),
+        "StringTranslate" => Arc::new(spark_strings::string_translate),
+        "StringLeft" => Arc::new(spark_strings::string_left),
+        "StringRight" => Arc::new(spark_strings::string_right),
+        "StringOverlay" => Arc::new(spark_strings::string_overlay),
+        "FormatString" => Arc::new(spark_format_string::spark_format_string),
+        "Conv" => Arc::new(spark_conv::spark_conv),
+        "Hex" => Arc::new(spark_encode::spark_hex),
+        "Unhex" => Arc::new(spark_encode::spark_unhex),
+        "Base64" => Arc::new(spark_encode::spark_base64),
+        "UnBase64" => Arc::new(spark_encode::spark_unbase64),
+        "Encode" => Arc::new(spark_encode::spark_encode),
+        "Decode" => Arc::new(spark_encode::spark_decode),
+        "ParseUrl" => Arc::new(spark_parse_url::spark_parse_url),
+        "ArrayUnion" => Arc::new(spark_arrays::array_union),
+        "ArrayIntersect" => Arc::new(spark_arrays::array_intersect),
+        "ArrayExcept" => Arc::new(spark_arrays::array_except),
+        "ArraysOverlap" => Arc::new(spark_arrays::arrays_overlap),
+        "MapKeys" => Arc::new(spark_maps::map_keys),
+        "MapValues" => Arc::new(spark_maps::map_values),
+        "MapEntries" => Arc::new(spark_maps::map_entries),
+        "MapFromArrays" => Arc::new(spark_maps::map_from_arrays),
+        "MapFromEntries" => Arc::new(spark_maps::map_from_entries),
+        "MapConcat" => Arc::new(spark_maps::map_concat),
+        "StrToMap" => Arc::new(spark_maps::str_to_map),
         _ => df_unimplemented_err!("spark ext function not implemented: {name}")?,
     })
 }
@@ -0,0 +1,319 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Array set operations compatible with spark
+
+use std::{collections::HashSet, ops::Range, sync::Arc};
+
+use arrow::{
+    array::*,
+    buffer::{NullBuffer, OffsetBuffer},
+    datatypes::{DataType, Field, FieldRef},
+    row::{RowConverter, SortField},
+};
+use datafusion::{
+    common::{cast::as_list_array, Result},
+    physical_plan::ColumnarValue,
+};
+use datafusion_ext_commons::df_execution_err;
+
+use crate::spark_utils::{broadcast_args, output};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SetOp {
+    Union,
+    Intersect,
+    Except,
+}
+
+/// array_union() function compatible with spark
+/// array_union(array(1, 2, 3), array(1, 3, 5)) = [1, 2, 3, 5]
+pub fn array_union(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    array_set_op(args, SetOp::Union)
+}
+
+/// array_intersect() function compatible with spark
+/// array_intersect(array(1, 2, 3), array(1, 3, 5)) = [1, 3]
+pub fn array_intersect(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    array_set_op(args, SetOp::Intersect)
+}
+
+/// array_except() function compatible with spark
+/// array_except(array(1, 2, 3), array(1, 3, 5)) = [2]
+pub fn array_except(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    array_set_op(args, SetOp::Except)
+}
+
+/// arrays_overlap() function compatible with spark
+/// returns true if both arrays share a non-null element, null if there is no
+/// such element but both arrays are non-empty and any of them contains null,
+/// otherwise false.
+pub fn arrays_overlap(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let lists = list_args(args, 2)?;
+    let (l, r) = (&lists[0], &lists[1]);
+
+    let mut converter = RowConverter::new(vec![SortField::new(l.value_type())])?;
+    let l_rows = converter.convert_columns(&[l.values().clone()])?;
+    let r_rows = converter.convert_columns(&[r.values().clone()])?;
+
+    let mut set = HashSet::new();
+    let mut builder = BooleanBuilder::with_capacity(l.len());
+    for i in 0..l.len() {
+        if l.is_null(i) || r.is_null(i) {
+            builder.append_null();
+            continue;
+        }
+        let (l_range, r_range) = (list_range(l, i), list_range(r, i));
+        let ((small, small_rows, small_range), (big, big_rows, big_range)) =
+            if l_range.len() <= r_range.len() {
+                ((l, &l_rows, l_range), (r, &r_rows, r_range))
+            } else {
+                ((r, &r_rows, r_range), (l, &l_rows, l_range))
+            };
+        let non_empty = !small_range.is_empty() && !big_range.is_empty();
+
+        set.clear();
+        let mut has_null = false;
+        for j in small_range {
+            if small.values().is_null(j) {
+                has_null = true;
+            } else {
+                set.insert(small_rows.row(j));
+            }
+        }
+
+        let mut found = false;
+        for j in big_range {
+            if big.values().is_null(j) {
+                has_null = true;
+            } else if set.contains(&big_rows.row(j)) {
+                found = true;
+                break;
+            }
+        }
+
+        if found {
+            builder.append_value(true);
+        } else if has_null && non_empty {
+            builder.append_null();
+        } else {
+            builder.append_value(false);
+        }
+    }
+    output(args, Arc::new(builder.finish()))
+}
+
+fn array_set_op(args: &[ColumnarValue], op: SetOp) -> Result<ColumnarValue> {
+    let lists = list_args(args, 2)?;
+    let (l, r) = (&lists[0], &lists[1]);
+    let (l_field, r_field) = (list_field(l), list_field(r));
+    if l_field.data_type() != r_field.data_type() {
+        df_execution_err!(
+            "array set operation requires arrays of the same element type, got {} and {}",
+            l_field.data_type(),
+            r_field.data_type(),
+        )?;
+    }
+
+    let mut converter = RowConverter::new(vec![SortField::new(l.value_type())])?;
+    let l_rows = converter.convert_columns(&[l.values().clone()])?;
+    let r_rows = converter.convert_columns(&[r.values().clone()])?;
+
+    let nulls = NullBuffer::union(l.nulls(), r.nulls());
+    let mut offsets: Vec<i32> = Vec::with_capacity(l.len() + 1);
+    let mut indices: Vec<(usize, usize)> = vec![];
+    let mut seen = HashSet::new();
+    let mut r_set = HashSet::new();
+    offsets.push(0);
+
+    for i in 0..l.len() {
+        if nulls.as_ref().map(|nb| nb.is_valid(i)).unwrap_or(true) {
+            let (l_range, r_range) = (list_range(l, i), list_range(r, i));
+            let mut seen_null = false;
+            seen.clear();
+
+            match op {
+                SetOp::Union => {
+                    let sides = [(0, l_range, &l_rows), (1, r_range, &r_rows)];
+                    for (side, range, rows) in sides {
+                        for j in range {
+                            if lists[side].values().is_null(j) {
+                                if !seen_null {
+                                    seen_null = true;
+                                    indices.push((side, j));
+                                }
+                            } else if seen.insert(rows.row(j)) {
+                                indices.push((side, j));
+                            }
+                        }
+                    }
+                }
+                SetOp::Intersect | SetOp::Except => {
+                    // intersect keeps elements existed in right side, except drops them
+                    let keep_existed = op == SetOp::Intersect;
+                    let mut r_has_null = false;
+                    r_set.clear();
+                    for j in r_range {
+                        if r.values().is_null(j) {
+                            r_has_null = true;
+                        } else {
+                            r_set.insert(r_rows.row(j));
+                        }
+                    }
+                    for j in l_range {
+                        if l.values().is_null(j) {
+                            if !seen_null && r_has_null == keep_existed {
+                                seen_null = true;
+                                indices.push((0, j));
+                            }
+                        } else {
+                            let row = l_rows.row(j);
+                            if r_set.contains(&row) == keep_existed && seen.insert(row) {
+                                indices.push((0, j));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        offsets.push(indices.len() as i32);
+    }
+
+    let values = arrow::compute::interleave(&[l.values().as_ref(), r.values().as_ref()], &indices)?;
+    let contains_null = match op {
+        SetOp::Union => l_field.is_nullable() || r_field.is_nullable(),
+        SetOp::Intersect => l_field.is_nullable() && r_field.is_nullable(),
+        SetOp::Except => l_field.is_nullable(),
+    };
+    let field = Arc::new(Field::new(
+        l_field.name(),
+        l_field.data_type().clone(),
+        contains_null,
+    ));
+    let list = ListArray::new(field, OffsetBuffer::new(offsets.into()), values, nulls);
+    output(args, Arc::new(list))
+}
+
+fn list_args(args: &[ColumnarValue], num_args: usize) -> Result<Vec<ListArray>> {
+    if args.len() != num_args {
+        df_execution_err!(
+            "expect {num_args} array arguments, but got {} arguments",
+            args.len()
+        )?;
+    }
+    broadcast_args(args)?
+        .iter()
+        .map(|array| Ok(as_list_array(array)?.clone()))
+        .collect()
+}
+
+fn list_field(list: &ListArray) -> FieldRef {
+    match list.data_type() {
+        DataType::List(field) => field.clone(),
+        _ => unreachable!("ListArray must have DataType::List"),
+    }
+}
+
+fn list_range(list: &ListArray, i: usize) -> Range<usize> {
+    let offsets = list.value_offsets();
+    offsets[i] as usize..offsets[i + 1] as usize
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, BooleanArray, ListArray},
+        datatypes::Int32Type,
+    };
+    use datafusion::{common::Result, physical_plan::ColumnarValue};
+
+    use crate::spark_arrays::{array_except, array_intersect, array_union, arrays_overlap};
+
+    fn list(values: Vec<Option<Vec<Option<i32>>>>) -> ColumnarValue {
+        ColumnarValue::Array(Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(
+            values,
+        )))
+    }
+
+    fn test_lists() -> Vec<ColumnarValue> {
+        vec![
+            list(vec![
+                Some(vec![Some(1), Some(2), Some(2), Some(3)]),
+                Some(vec![Some(1), None, Some(3), None]),
+                None,
+                Some(vec![]),
+            ]),
+            list(vec![
+                Some(vec![Some(3), Some(1), Some(5)]),
+                Some(vec![None, Some(4)]),
+                Some(vec![Some(1)]),
+                Some(vec![Some(7)]),
+            ]),
+        ]
+    }
+
+    #[test]
+    fn test_array_union() -> Result<()> {
+        let r = array_union(&test_lists())?.into_array(4);
+        let expected: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2), Some(3), Some(5)]),
+            Some(vec![Some(1), None, Some(3), Some(4)]),
+            None,
+            Some(vec![Some(7)]),
+        ]));
+        assert_eq!(&r, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_intersect() -> Result<()> {
+        let r = array_intersect(&test_lists())?.into_array(4);
+        let expected: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(3)]),
+            Some(vec![None]),
+            None,
+            Some(vec![]),
+        ]));
+        assert_eq!(&r, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_except() -> Result<()> {
+        let r = array_except(&test_lists())?.into_array(4);
+        let expected: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(2)]),
+            Some(vec![Some(1), Some(3)]),
+            None,
+            Some(vec![]),
+        ]));
+        assert_eq!(&r, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrays_overlap() -> Result<()> {
+        let r = arrays_overlap(&test_lists())?.into_array(4);
+        let expected: ArrayRef = Arc::new(BooleanArray::from(vec![
+            Some(true),
+            None,
+            None,
+            Some(false),
+        ]));
+        assert_eq!(&r, &expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use arrow::array::StringArray;
+use datafusion::{
+    common::{
+        cast::{as_int32_array, as_string_array},
+        Result,
+    },
+    physical_plan::ColumnarValue,
+};
+
+use crate::spark_strings::{broadcast_args, output};
+
+/// conv() function compatible with spark
+/// converts a number string from from_base to to_base, a negative to_base
+/// means the result is signed. invalid digits and all digits after them are
+/// ignored, overflowed values are clamped to the max unsigned long.
+/// conv('100', 2, 10) = '4', conv('-10', 16, -10) = '-16'
+pub fn spark_conv(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let nums = as_string_array(&arrays[0])?;
+    let from_bases = as_int32_array(&arrays[1])?;
+    let to_bases = as_int32_array(&arrays[2])?;
+
+    let result_array: StringArray = nums
+        .iter()
+        .zip(from_bases)
+        .zip(to_bases)
+        .map(|((num, from_base), to_base)| conv(num?.trim_matches(' '), from_base?, to_base?))
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+// same as spark's NumberConverter.convert()
+fn conv(num: &str, from_base: i32, to_base: i32) -> Option<String> {
+    if !(2..=36).contains(&from_base) || !(2..=36).contains(&to_base.abs()) {
+        return None;
+    }
+    if num.is_empty() {
+        return None;
+    }
+    let (mut negative, digits) = match num.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, num),
+    };
+
+    let mut v = encode(digits.as_bytes(), from_base as i64);
+    if negative && to_base > 0 {
+        v = if v < 0 { -1 } else { v.wrapping_neg() };
+    }
+    if to_base < 0 && v < 0 {
+        v = v.wrapping_neg();
+        negative = true;
+    }
+
+    let mut result = decode(v as u64, to_base.unsigned_abs() as u64);
+    if negative && to_base < 0 {
+        result.insert(0, '-');
+    }
+    Some(result)
+}
+
+// parses digits as an unsigned long, stopping at the first invalid digit.
+// overflow checks follow spark's signed comparisons to produce the same result
+fn encode(digits: &[u8], radix: i64) -> i64 {
+    let unsigned_div = |v: i64, radix: i64| ((v as u64) / (radix as u64)) as i64;
+    let bound = unsigned_div(-1 - radix, radix);
+
+    let mut v = 0i64;
+    for &b in digits {
+        let digit = match (b as char).to_digit(radix as u32) {
+            Some(digit) => digit as i64,
+            None => break,
+        };
+        if v >= bound && unsigned_div(-1 - digit, radix) < v {
+            return -1;
+        }
+        v = v.wrapping_mul(radix).wrapping_add(digit);
+    }
+    v
+}
+
+fn decode(v: u64, radix: u64) -> String {
+    if v == 0 {
+        return "0".to_string();
+    }
+    let mut digits = vec![];
+    let mut v = v;
+    while v != 0 {
+        let digit = char::from_digit((v % radix) as u32, radix as u32).unwrap();
+        digits.push(digit.to_ascii_uppercase());
+        v /= radix;
+    }
+    digits.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::array::{Int32Array, StringArray};
+    use datafusion::{
+        common::{cast::as_string_array, Result},
+        physical_plan::ColumnarValue,
+    };
+
+    use crate::spark_conv::spark_conv;
+
+    #[test]
+    fn test_conv() -> Result<()> {
+        let r = spark_conv(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("100"),
+                Some("-10"),
+                Some("-10"),
+                Some(" 11ab "),
+                Some("zz"),
+                Some("ffffffffffffffffffff"),
+                Some("9223372036854775808"),
+                Some(""),
+                Some("10"),
+                None,
+            ]))),
+            ColumnarValue::Array(Arc::new(Int32Array::from_iter(vec![
+                Some(2),
+                Some(16),
+                Some(16),
+                Some(10),
+                Some(36),
+                Some(16),
+                Some(10),
+                Some(10),
+                Some(37),
+                Some(10),
+            ]))),
+            ColumnarValue::Array(Arc::new(Int32Array::from_iter(vec![
+                Some(10),
+                Some(-10),
+                Some(10),
+                Some(16),
+                Some(10),
+                Some(10),
+                Some(-10),
+                Some(2),
+                Some(10),
+                Some(2),
+            ]))),
+        ])?;
+        let s = r.into_array(10);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![
+                Some("4"),
+                Some("-16"),
+                Some("18446744073709551600"),
+                Some("B"),
+                Some("1295"),
+                Some("18446744073709551615"),
+                Some("-9223372036854775808"),
+                None,
+                None,
+                None,
+            ]
+        );
+        Ok(())
+    }
+}
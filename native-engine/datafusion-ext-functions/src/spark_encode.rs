@@ -0,0 +1,447 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use arrow::{
+    array::{Array, BinaryArray, StringArray},
+    datatypes::DataType,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use datafusion::{
+    common::{
+        cast::{as_binary_array, as_int64_array, as_string_array},
+        Result,
+    },
+    physical_plan::ColumnarValue,
+};
+use datafusion_ext_commons::{df_execution_err, df_unimplemented_err};
+
+use crate::spark_strings::{broadcast_args, output};
+
+/// hex() function compatible with spark
+/// longs are converted to the hex string of their two's complement, strings
+/// and binaries are converted byte by byte
+/// hex(17) = '11', hex(-1) = 'FFFFFFFFFFFFFFFF', hex('Spark') = '537061726B'
+pub fn spark_hex(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let result_array: StringArray = match arrays[0].data_type() {
+        DataType::Int64 => as_int64_array(&arrays[0])?
+            .iter()
+            .map(|v| v.map(|v| format!("{:X}", v as u64)))
+            .collect(),
+        DataType::Utf8 => as_string_array(&arrays[0])?
+            .iter()
+            .map(|v| v.map(|v| hex_bytes(v.as_bytes())))
+            .collect(),
+        DataType::Binary => as_binary_array(&arrays[0])?
+            .iter()
+            .map(|v| v.map(hex_bytes))
+            .collect(),
+        dt => df_unimplemented_err!("hex: unsupported data type: {dt:?}")?,
+    };
+    output(args, Arc::new(result_array))
+}
+
+/// unhex() function compatible with spark
+/// an odd-length input is padded with a leading '0', returns null if the input
+/// contains non-hex characters
+/// unhex('537061726B') = X'537061726B'
+pub fn spark_unhex(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let result_array: BinaryArray = as_string_array(&arrays[0])?
+        .iter()
+        .map(|v| unhex_bytes(v?.as_bytes()))
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+/// base64() function compatible with spark, using the standard alphabet with
+/// padding and without line chunking
+/// base64('Spark SQL') = 'U3BhcmsgU1FM'
+pub fn spark_base64(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let result_array: StringArray = match arrays[0].data_type() {
+        DataType::Utf8 => as_string_array(&arrays[0])?
+            .iter()
+            .map(|v| v.map(|v| STANDARD.encode(v)))
+            .collect(),
+        DataType::Binary => as_binary_array(&arrays[0])?
+            .iter()
+            .map(|v| v.map(|v| STANDARD.encode(v)))
+            .collect(),
+        dt => df_unimplemented_err!("base64: unsupported data type: {dt:?}")?,
+    };
+    output(args, Arc::new(result_array))
+}
+
+/// unbase64() function compatible with spark
+/// decoding is lenient like commons-codec: characters out of the base64
+/// alphabet are ignored and decoding stops at the first padding character
+/// unbase64('U3BhcmsgU1FM') = X'537061726B2053514C'
+pub fn spark_unbase64(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let result_array: BinaryArray = as_string_array(&arrays[0])?
+        .iter()
+        .map(|v| Some(unbase64_lenient(v?.as_bytes())))
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+/// encode() function compatible with spark
+/// unmappable characters are replaced with '?'
+/// encode('abc', 'utf-16le') = X'610062006300'
+pub fn spark_encode(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let strs = as_string_array(&arrays[0])?;
+    let charsets = as_string_array(&arrays[1])?;
+
+    let result_array: BinaryArray = strs
+        .iter()
+        .zip(charsets)
+        .map(|(s, charset)| match (s, charset) {
+            (Some(s), Some(charset)) => Ok(Some(Charset::try_from_name(charset)?.encode(s))),
+            _ => Ok(None),
+        })
+        .collect::<Result<_>>()?;
+    output(args, Arc::new(result_array))
+}
+
+/// decode() function compatible with spark
+/// malformed input is replaced with U+FFFD
+/// decode(X'610062006300', 'utf-16le') = 'abc'
+pub fn spark_decode(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let bins = as_binary_array(&arrays[0])?;
+    let charsets = as_string_array(&arrays[1])?;
+
+    let result_array: StringArray = bins
+        .iter()
+        .zip(charsets)
+        .map(|(bin, charset)| match (bin, charset) {
+            (Some(bin), Some(charset)) => Ok(Some(Charset::try_from_name(charset)?.decode(bin))),
+            _ => Ok(None),
+        })
+        .collect::<Result<_>>()?;
+    output(args, Arc::new(result_array))
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        hex.push(HEX_DIGITS[(b >> 4) as usize] as char);
+        hex.push(HEX_DIGITS[(b & 0x0f) as usize] as char);
+    }
+    hex
+}
+
+fn unhex_bytes(bytes: &[u8]) -> Option<Vec<u8>> {
+    let digit = |b: u8| (b as char).to_digit(16).map(|d| d as u8);
+    let mut out = Vec::with_capacity((bytes.len() + 1) / 2);
+    let mut bytes = bytes;
+    if bytes.len() % 2 == 1 {
+        out.push(digit(bytes[0])?);
+        bytes = &bytes[1..];
+    }
+    for pair in bytes.chunks_exact(2) {
+        out.push(digit(pair[0])? << 4 | digit(pair[1])?);
+    }
+    Some(out)
+}
+
+fn unbase64_lenient(bytes: &[u8]) -> Vec<u8> {
+    let decode_char = |b: u8| -> Option<u32> {
+        Some(match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        } as u32)
+    };
+
+    let mut out = Vec::with_capacity(bytes.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut modulus = 0;
+    for &b in bytes {
+        if b == b'=' {
+            break;
+        }
+        if let Some(v) = decode_char(b) {
+            bits = bits << 6 | v;
+            modulus += 1;
+            if modulus == 4 {
+                out.extend_from_slice(&bits.to_be_bytes()[1..]);
+                bits = 0;
+                modulus = 0;
+            }
+        }
+    }
+
+    // trailing bits, a single remaining character is discarded
+    match modulus {
+        2 => out.push((bits >> 4) as u8),
+        3 => out.extend_from_slice(&((bits >> 2) as u16).to_be_bytes()),
+        _ => {}
+    }
+    out
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Charset {
+    UsAscii,
+    Iso8859_1,
+    Utf8,
+    Utf16,
+    Utf16Be,
+    Utf16Le,
+    Utf32,
+}
+
+impl Charset {
+    fn try_from_name(name: &str) -> Result<Self> {
+        Ok(match name.to_ascii_uppercase().as_str() {
+            "US-ASCII" | "ASCII" => Charset::UsAscii,
+            "ISO-8859-1" | "ISO8859_1" | "LATIN1" => Charset::Iso8859_1,
+            "UTF-8" | "UTF8" => Charset::Utf8,
+            "UTF-16" | "UTF16" => Charset::Utf16,
+            "UTF-16BE" => Charset::Utf16Be,
+            "UTF-16LE" => Charset::Utf16Le,
+            "UTF-32" | "UTF32" => Charset::Utf32,
+            _ => df_execution_err!("unsupported charset: {name}")?,
+        })
+    }
+
+    fn encode(&self, s: &str) -> Vec<u8> {
+        match self {
+            Charset::UsAscii => s
+                .chars()
+                .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+                .collect(),
+            Charset::Iso8859_1 => s
+                .chars()
+                .map(|c| if (c as u32) < 256 { c as u8 } else { b'?' })
+                .collect(),
+            Charset::Utf8 => s.as_bytes().to_vec(),
+            Charset::Utf16 => {
+                // java writes a big-endian byte order mark
+                let mut bytes = vec![0xfe, 0xff];
+                bytes.extend(s.encode_utf16().flat_map(u16::to_be_bytes));
+                bytes
+            }
+            Charset::Utf16Be => s.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            Charset::Utf16Le => s.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            Charset::Utf32 => s.chars().flat_map(|c| (c as u32).to_be_bytes()).collect(),
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            Charset::UsAscii => bytes
+                .iter()
+                .map(|&b| if b.is_ascii() { b as char } else { '\u{fffd}' })
+                .collect(),
+            Charset::Iso8859_1 => bytes.iter().map(|&b| b as char).collect(),
+            Charset::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Charset::Utf16 => match bytes {
+                [0xfe, 0xff, rest @ ..] => decode_utf16(rest, u16::from_be_bytes),
+                [0xff, 0xfe, rest @ ..] => decode_utf16(rest, u16::from_le_bytes),
+                _ => decode_utf16(bytes, u16::from_be_bytes),
+            },
+            Charset::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+            Charset::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+            Charset::Utf32 => {
+                let chunks = bytes.chunks_exact(4);
+                let has_remainder = !chunks.remainder().is_empty();
+                chunks
+                    .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                    .map(|c| char::from_u32(c).unwrap_or('\u{fffd}'))
+                    .chain(has_remainder.then_some('\u{fffd}'))
+                    .collect()
+            }
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let chunks = bytes.chunks_exact(2);
+    let has_remainder = !chunks.remainder().is_empty();
+    char::decode_utf16(chunks.map(|c| from_bytes([c[0], c[1]])))
+        .map(|c| c.unwrap_or('\u{fffd}'))
+        .chain(has_remainder.then_some('\u{fffd}'))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::array::{BinaryArray, Int64Array, StringArray};
+    use datafusion::{
+        common::{
+            cast::{as_binary_array, as_string_array},
+            Result, ScalarValue,
+        },
+        physical_plan::ColumnarValue,
+    };
+
+    use crate::spark_encode::{
+        spark_base64, spark_decode, spark_encode, spark_hex, spark_unbase64, spark_unhex,
+    };
+
+    #[test]
+    fn test_hex_unhex() -> Result<()> {
+        let r = spark_hex(&vec![ColumnarValue::Array(Arc::new(
+            Int64Array::from_iter(vec![Some(17), Some(0), Some(-1), None]),
+        ))])?;
+        let s = r.into_array(4);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("11"), Some("0"), Some("FFFFFFFFFFFFFFFF"), None]
+        );
+
+        let r = spark_hex(&vec![ColumnarValue::Scalar(ScalarValue::from("Spark"))])?;
+        let s = r.into_array(1);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("537061726B")]
+        );
+
+        let r = spark_unhex(&vec![ColumnarValue::Array(Arc::new(
+            StringArray::from_iter(vec![
+                Some("537061726B"),
+                Some("F0a"),
+                Some("GG"),
+                Some(""),
+                None,
+            ]),
+        ))])?;
+        let s = r.into_array(5);
+        assert_eq!(
+            as_binary_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![
+                Some(&b"Spark"[..]),
+                Some(&[0x0f, 0x0a][..]),
+                None,
+                Some(&b""[..]),
+                None,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_base64_unbase64() -> Result<()> {
+        let r = spark_base64(&vec![ColumnarValue::Array(Arc::new(
+            BinaryArray::from_iter(vec![Some(&b"Spark SQL"[..]), Some(&b"ab"[..]), None]),
+        ))])?;
+        let s = r.into_array(3);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("U3BhcmsgU1FM"), Some("YWI="), None]
+        );
+
+        let r = spark_unbase64(&vec![ColumnarValue::Array(Arc::new(
+            StringArray::from_iter(vec![
+                Some("U3BhcmsgU1FM"),
+                Some("YWI="),
+                Some("YW\nI"),
+                Some("YWI=YWI="),
+                Some("Y"),
+                None,
+            ]),
+        ))])?;
+        let s = r.into_array(6);
+        assert_eq!(
+            as_binary_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![
+                Some(&b"Spark SQL"[..]),
+                Some(&b"ab"[..]),
+                Some(&b"ab"[..]),
+                Some(&b"ab"[..]),
+                Some(&b""[..]),
+                None,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_decode() -> Result<()> {
+        let strs = ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+            Some("abc"),
+            Some("abc"),
+            Some("aé砖"),
+            Some("aé砖"),
+            Some("aé砖"),
+            None,
+        ])));
+        let charsets = ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+            Some("utf-16le"),
+            Some("UTF-16"),
+            Some("US-ASCII"),
+            Some("iso-8859-1"),
+            Some("utf-8"),
+            Some("utf-8"),
+        ])));
+        let r = spark_encode(&vec![strs, charsets.clone()])?;
+        let s = r.into_array(6);
+        assert_eq!(
+            as_binary_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![
+                Some(&b"a\0b\0c\0"[..]),
+                Some(&b"\xfe\xff\0a\0b\0c"[..]),
+                Some(&b"a??"[..]),
+                Some(&b"a\xe9?"[..]),
+                Some("aé砖".as_bytes()),
+                None,
+            ]
+        );
+
+        let r = spark_decode(&vec![ColumnarValue::Array(s), charsets])?;
+        let s = r.into_array(6);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![
+                Some("abc"),
+                Some("abc"),
+                Some("a??"),
+                Some("aé?"),
+                Some("aé砖"),
+                None,
+            ]
+        );
+
+        // malformed input
+        let r = spark_decode(&vec![
+            ColumnarValue::Scalar(ScalarValue::Binary(Some(b"a\xff".to_vec()))),
+            ColumnarValue::Scalar(ScalarValue::from("utf-8")),
+        ])?;
+        let s = r.into_array(1);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("a\u{fffd}")]
+        );
+
+        // unsupported charset
+        assert!(spark_encode(&vec![
+            ColumnarValue::Scalar(ScalarValue::from("abc")),
+            ColumnarValue::Scalar(ScalarValue::from("GBK")),
+        ])
+        .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,752 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{Arc, OnceLock};
+
+use arrow::{array::*, datatypes::*};
+use datafusion::{
+    common::{
+        cast::{as_boolean_array, as_decimal128_array, as_string_array},
+        Result,
+    },
+    physical_plan::ColumnarValue,
+};
+use datafusion_ext_commons::{cast::float_to_string, df_execution_err, df_unimplemented_err};
+use regex::Regex;
+
+use crate::spark_utils::{broadcast_args, output};
+
+/// format_string()/printf() function compatible with spark, which formats
+/// arguments like java.util.Formatter with Locale.US.
+/// supported conversions: b/B, s/S, c/C, d, o, x/X, e/E, f, % and n
+/// format_string('Hello World %d %s', 100, 'days') = 'Hello World 100 days'
+pub fn spark_format_string(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let formats = as_string_array(&arrays[0])?;
+    let format_args = &arrays[1..];
+
+    let mut cached_specs: Option<(String, Vec<FormatSpec>)> = None;
+    let mut result_builder = StringBuilder::new();
+    for row in 0..formats.len() {
+        if formats.is_null(row) {
+            result_builder.append_null();
+            continue;
+        }
+        let format = formats.value(row);
+
+        // format is usually a literal, so only the last one is cached
+        if !matches!(&cached_specs, Some((f, _)) if f == format) {
+            cached_specs = Some((format.to_string(), parse_format(format)?));
+        }
+        let specs = &cached_specs.as_ref().unwrap().1;
+        let row_args = format_args
+            .iter()
+            .map(|array| FormatArg::try_from_array(array, row))
+            .collect::<Result<Vec<_>>>()?;
+        result_builder.append_value(format_row(specs, &row_args)?);
+    }
+    output(args, Arc::new(result_builder.finish()))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum FormatArg<'a> {
+    Null,
+    Bool(bool),
+    Int(i64, u32),    // value and bit width
+    Float(f64, bool), // value and whether it comes from a float32
+    Str(&'a str),
+    Decimal(i128, i8),
+}
+
+impl<'a> FormatArg<'a> {
+    fn try_from_array(array: &'a ArrayRef, row: usize) -> Result<Self> {
+        if array.is_null(row) {
+            return Ok(FormatArg::Null);
+        }
+        macro_rules! int {
+            ($arrow_type:ty, $bits:expr) => {{
+                let v = as_primitive_array::<$arrow_type>(array).value(row);
+                FormatArg::Int(v as i64, $bits)
+            }};
+        }
+
+        // dates and timestamps are passed to java formatter as their internal
+        // int/long values in spark
+        Ok(match array.data_type() {
+            DataType::Boolean => FormatArg::Bool(as_boolean_array(array)?.value(row)),
+            DataType::Int8 => int!(Int8Type, 8),
+            DataType::Int16 => int!(Int16Type, 16),
+            DataType::Int32 => int!(Int32Type, 32),
+            DataType::Int64 => int!(Int64Type, 64),
+            DataType::Date32 => int!(Date32Type, 32),
+            DataType::Timestamp(TimeUnit::Microsecond, _) => int!(TimestampMicrosecondType, 64),
+            DataType::Float32 => FormatArg::Float(
+                as_primitive_array::<Float32Type>(array).value(row) as f64,
+                true,
+            ),
+            DataType::Float64 => {
+                FormatArg::Float(as_primitive_array::<Float64Type>(array).value(row), false)
+            }
+            DataType::Utf8 => FormatArg::Str(as_string_array(array)?.value(row)),
+            DataType::Decimal128(_, scale) => {
+                FormatArg::Decimal(as_decimal128_array(array)?.value(row), *scale)
+            }
+            dt => df_unimplemented_err!("format_string: unsupported data type: {dt:?}")?,
+        })
+    }
+
+    // same as java's Object.toString() of the spark internal value
+    fn to_java_string(&self) -> String {
+        match self {
+            FormatArg::Null => "null".to_string(),
+            FormatArg::Bool(v) => v.to_string(),
+            FormatArg::Int(v, _) => v.to_string(),
+            FormatArg::Float(v, true) => float_to_string(*v as f32),
+            FormatArg::Float(v, false) => float_to_string(*v),
+            FormatArg::Str(v) => v.to_string(),
+            FormatArg::Decimal(unscaled, scale) => java_decimal_to_string(*unscaled, *scale),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum FormatSpec {
+    Literal(String),
+    Conversion {
+        index: ArgIndex,
+        flags: Flags,
+        width: Option<usize>,
+        precision: Option<usize>,
+        conversion: char,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ArgIndex {
+    Ordinary,
+    Explicit(usize),
+    Previous,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Flags {
+    left_justify: bool,
+    alternate: bool,
+    plus: bool,
+    leading_space: bool,
+    zero_pad: bool,
+    group: bool,
+    parentheses: bool,
+}
+
+fn parse_format(format: &str) -> Result<Vec<FormatSpec>> {
+    static SPEC_REGEX: OnceLock<Regex> = OnceLock::new();
+    let spec_regex = SPEC_REGEX.get_or_init(|| {
+        Regex::new(r"^%(\d+\$)?([-#+ 0,(<]*)?(\d+)?(\.\d+)?([tT])?([a-zA-Z%])").unwrap()
+    });
+
+    let mut specs = vec![];
+    let mut literal = String::new();
+    let mut pos = 0;
+    while pos < format.len() {
+        let rest = &format[pos..];
+        if !rest.starts_with('%') {
+            let next = rest.find('%').unwrap_or(rest.len());
+            literal.push_str(&rest[..next]);
+            pos += next;
+            continue;
+        }
+
+        let caps = match spec_regex.captures(rest) {
+            Some(caps) => caps,
+            None => {
+                let conversion = rest[1..].chars().next().unwrap_or('%');
+                df_execution_err!("format_string: unknown format conversion: '{conversion}'")?
+            }
+        };
+        pos += caps[0].len();
+        if caps.get(5).is_some() {
+            df_unimplemented_err!("format_string: date/time conversions are not supported")?;
+        }
+
+        let mut index = match caps.get(1) {
+            Some(index) => {
+                let index = index.as_str().trim_end_matches('$');
+                ArgIndex::Explicit(index.parse().unwrap_or(usize::MAX))
+            }
+            None => ArgIndex::Ordinary,
+        };
+        let mut flags = Flags::default();
+        for flag in caps.get(2).map(|f| f.as_str()).unwrap_or("").chars() {
+            let duplicated = match flag {
+                '-' => std::mem::replace(&mut flags.left_justify, true),
+                '#' => std::mem::replace(&mut flags.alternate, true),
+                '+' => std::mem::replace(&mut flags.plus, true),
+                ' ' => std::mem::replace(&mut flags.leading_space, true),
+                '0' => std::mem::replace(&mut flags.zero_pad, true),
+                ',' => std::mem::replace(&mut flags.group, true),
+                '(' => std::mem::replace(&mut flags.parentheses, true),
+                _ => std::mem::replace(&mut index, ArgIndex::Previous) == ArgIndex::Previous,
+            };
+            if duplicated {
+                df_execution_err!("format_string: duplicate format flags: '{flag}'")?;
+            }
+        }
+        let width = caps
+            .get(3)
+            .map(|w| w.as_str().parse().unwrap_or(usize::MAX));
+        let precision = caps
+            .get(4)
+            .map(|p| p.as_str()[1..].parse().unwrap_or(usize::MAX));
+        let conversion = caps[6].chars().next().unwrap();
+
+        let spec = FormatSpec::Conversion {
+            index,
+            flags,
+            width,
+            precision,
+            conversion,
+        };
+        check_spec(&spec)?;
+        match conversion {
+            '%' | 'n' => literal.push_str(&format_row(&[spec], &[])?),
+            _ => {
+                if !literal.is_empty() {
+                    specs.push(FormatSpec::Literal(std::mem::take(&mut literal)));
+                }
+                specs.push(spec);
+            }
+        }
+    }
+    if !literal.is_empty() {
+        specs.push(FormatSpec::Literal(literal));
+    }
+    Ok(specs)
+}
+
+// validates flags/width/precision like java's Formatter
+fn check_spec(spec: &FormatSpec) -> Result<()> {
+    let FormatSpec::Conversion {
+        flags,
+        width,
+        precision,
+        conversion,
+        ..
+    } = spec
+    else {
+        return Ok(());
+    };
+
+    let allowed_flags = match conversion.to_ascii_lowercase() {
+        'b' | 's' | 'c' => "-",
+        'd' => "-+ 0,(",
+        'o' | 'x' => "-#0",
+        'e' => "-#+ 0(",
+        'f' => "-#+ 0,(",
+        '%' => "-",
+        'n' => "",
+        _ => df_execution_err!("format_string: unknown format conversion: '{conversion}'")?,
+    };
+    let used_flags = [
+        (flags.left_justify, '-'),
+        (flags.alternate, '#'),
+        (flags.plus, '+'),
+        (flags.leading_space, ' '),
+        (flags.zero_pad, '0'),
+        (flags.group, ','),
+        (flags.parentheses, '('),
+    ];
+    for (used, flag) in used_flags {
+        if used && !allowed_flags.contains(flag) {
+            df_execution_err!(
+                "format_string: conversion = '{conversion}', flags = '{flag}' mismatches"
+            )?;
+        }
+    }
+    if (flags.plus && flags.leading_space) || (flags.left_justify && flags.zero_pad) {
+        df_execution_err!("format_string: illegal format flags")?;
+    }
+    if (flags.left_justify || flags.zero_pad) && width.is_none() {
+        df_execution_err!("format_string: missing format width")?;
+    }
+    if precision.is_some() && matches!(conversion, 'c' | 'C' | 'd' | 'o' | 'x' | 'X' | '%' | 'n') {
+        df_execution_err!("format_string: illegal format precision for '{conversion}'")?;
+    }
+    if width.is_some() && *conversion == 'n' {
+        df_execution_err!("format_string: illegal format width for 'n'")?;
+    }
+    Ok(())
+}
+
+fn format_row(specs: &[FormatSpec], args: &[FormatArg]) -> Result<String> {
+    let mut formatted = String::new();
+    let mut ordinary_index = 0;
+    let mut last_index: Option<usize> = None;
+
+    for spec in specs {
+        let (index, flags, width, precision, conversion) = match spec {
+            FormatSpec::Literal(literal) => {
+                formatted.push_str(literal);
+                continue;
+            }
+            FormatSpec::Conversion {
+                index,
+                flags,
+                width,
+                precision,
+                conversion,
+            } => (*index, *flags, *width, *precision, *conversion),
+        };
+
+        let arg = match conversion {
+            '%' | 'n' => &FormatArg::Null,
+            _ => {
+                let arg_index = match index {
+                    ArgIndex::Ordinary => {
+                        ordinary_index += 1;
+                        ordinary_index - 1
+                    }
+                    ArgIndex::Explicit(i) => i.saturating_sub(1),
+                    ArgIndex::Previous => match last_index {
+                        Some(i) => i,
+                        None => df_execution_err!(
+                            "format_string: missing format argument for '%<{conversion}'"
+                        )?,
+                    },
+                };
+                last_index = Some(arg_index);
+                match args.get(arg_index) {
+                    Some(arg) => arg,
+                    None => df_execution_err!(
+                        "format_string: missing format argument for '%{conversion}'"
+                    )?,
+                }
+            }
+        };
+
+        let s = match conversion.to_ascii_lowercase() {
+            '%' => "%".to_string(),
+            'n' => "\n".to_string(),
+            'b' => {
+                let s = match arg {
+                    FormatArg::Null => "false".to_string(),
+                    FormatArg::Bool(v) => v.to_string(),
+                    _ => "true".to_string(),
+                };
+                truncate_chars(s, precision)
+            }
+            's' => truncate_chars(arg.to_java_string(), precision),
+            'c' => match arg {
+                FormatArg::Null => "null".to_string(),
+                FormatArg::Int(v, bits) if *bits <= 32 => match char::from_u32(*v as u32) {
+                    Some(c) if *v >= 0 => c.to_string(),
+                    _ => df_execution_err!("format_string: illegal code point: {v}")?,
+                },
+                _ => illegal_conversion(conversion, arg)?,
+            },
+            'd' => match arg {
+                FormatArg::Null => "null".to_string(),
+                FormatArg::Int(v, _) => {
+                    let magnitude = v.unsigned_abs().to_string();
+                    format_number(*v < 0, &magnitude, None, flags, width)
+                }
+                _ => illegal_conversion(conversion, arg)?,
+            },
+            'o' | 'x' => match arg {
+                FormatArg::Null => "null".to_string(),
+                FormatArg::Int(v, bits) => {
+                    // negative values are converted to unsigned by adding 2^n
+                    let unsigned = (*v as u64) & (u64::MAX >> (64 - bits));
+                    let (magnitude, prefix) = match conversion.to_ascii_lowercase() {
+                        'o' => (format!("{unsigned:o}"), "0"),
+                        _ => (format!("{unsigned:x}"), "0x"),
+                    };
+                    let prefix = if flags.alternate { prefix } else { "" };
+                    match (flags.zero_pad, width) {
+                        (true, Some(width)) => {
+                            let width = width.saturating_sub(prefix.len());
+                            format!("{prefix}{magnitude:0>width$}")
+                        }
+                        _ => format!("{prefix}{magnitude}"),
+                    }
+                }
+                _ => illegal_conversion(conversion, arg)?,
+            },
+            'e' | 'f' => match arg {
+                FormatArg::Null => "null".to_string(),
+                FormatArg::Float(v, _) => format_float(*v, conversion, flags, width, precision),
+                FormatArg::Decimal(unscaled, scale) => {
+                    format_decimal(*unscaled, *scale, conversion, flags, width, precision)
+                }
+                _ => illegal_conversion(conversion, arg)?,
+            },
+            _ => df_execution_err!("format_string: unknown format conversion: '{conversion}'")?,
+        };
+
+        let s = if conversion.is_ascii_uppercase() {
+            s.to_uppercase()
+        } else {
+            s
+        };
+        formatted.push_str(&justify(s, flags, width));
+    }
+    Ok(formatted)
+}
+
+fn illegal_conversion(conversion: char, arg: &FormatArg) -> Result<String> {
+    df_execution_err!("format_string: '{conversion}' is not applicable to {arg:?}")
+}
+
+fn truncate_chars(s: String, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => s.chars().take(precision).collect(),
+        None => s,
+    }
+}
+
+fn justify(s: String, flags: Flags, width: Option<usize>) -> String {
+    match width {
+        Some(width) if flags.left_justify => format!("{s:<width$}"),
+        Some(width) => format!("{s:>width$}"),
+        None => s,
+    }
+}
+
+// formats the magnitude of a number with sign, grouping and zero padding
+fn format_number(
+    negative: bool,
+    int_part: &str,
+    frac_part: Option<&str>,
+    flags: Flags,
+    width: Option<usize>,
+) -> String {
+    let leading_sign = match (negative, flags.parentheses) {
+        (true, true) => "(",
+        (true, false) => "-",
+        (false, _) if flags.plus => "+",
+        (false, _) if flags.leading_space => " ",
+        (false, _) => "",
+    };
+    let trailing_sign = if negative && flags.parentheses {
+        ")"
+    } else {
+        ""
+    };
+
+    let mut magnitude = if flags.group {
+        let mut grouped = String::new();
+        for (i, c) in int_part.chars().enumerate() {
+            if i > 0 && (int_part.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(c);
+        }
+        grouped
+    } else {
+        int_part.to_string()
+    };
+    if let Some(frac_part) = frac_part {
+        magnitude.push_str(frac_part);
+    }
+
+    if let (true, Some(width)) = (flags.zero_pad, width) {
+        let width = width.saturating_sub(leading_sign.len() + trailing_sign.len());
+        magnitude = format!("{magnitude:0>width$}");
+    }
+    format!("{leading_sign}{magnitude}{trailing_sign}")
+}
+
+// formats floating values like java's Formatter, which rounds half-up on the
+// shortest decimal representation of the value
+fn format_float(
+    v: f64,
+    conversion: char,
+    flags: Flags,
+    width: Option<usize>,
+    precision: Option<usize>,
+) -> String {
+    if v.is_nan() {
+        return "NaN".to_string();
+    }
+    let negative = v.is_sign_negative();
+    if v.is_infinite() {
+        let no_zero_pad = Flags {
+            zero_pad: false,
+            ..flags
+        };
+        return format_number(negative, "Infinity", None, no_zero_pad, width);
+    }
+
+    let formatted = format!("{:e}", v.abs());
+    let (mantissa, exp) = formatted.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "").into_bytes();
+    let exp: i64 = exp.parse().unwrap();
+    format_digits(negative, digits, exp, conversion, flags, width, precision)
+}
+
+// formats decimal values like java's Formatter on BigDecimal, which rounds
+// half-up on the exact digits of the unscaled value
+fn format_decimal(
+    unscaled: i128,
+    scale: i8,
+    conversion: char,
+    flags: Flags,
+    width: Option<usize>,
+    precision: Option<usize>,
+) -> String {
+    let digits = unscaled.unsigned_abs().to_string().into_bytes();
+    let exp = digits.len() as i64 - 1 - scale as i64;
+    format_digits(
+        unscaled < 0,
+        digits,
+        exp,
+        conversion,
+        flags,
+        width,
+        precision,
+    )
+}
+
+// formats decimal digits (d.ddd * 10^exp) with 'e' or 'f' conversion
+fn format_digits(
+    negative: bool,
+    digits: Vec<u8>,
+    exp: i64,
+    conversion: char,
+    flags: Flags,
+    width: Option<usize>,
+    precision: Option<usize>,
+) -> String {
+    let precision = precision.unwrap_or(6);
+    let point = if flags.alternate || precision > 0 {
+        "."
+    } else {
+        ""
+    };
+
+    if conversion.to_ascii_lowercase() == 'e' {
+        let (mut digits, exp) = round_half_up(digits, exp, precision as i64 + 1);
+        digits.truncate(precision + 1);
+        let (int_part, frac_part) = digits.split_at(1);
+        let int_part = std::str::from_utf8(int_part).unwrap();
+        let frac_part = std::str::from_utf8(frac_part).unwrap();
+        let exp_sign = if exp < 0 { "-" } else { "+" };
+        let frac_part = format!("{point}{frac_part:0<precision$}e{exp_sign}{:02}", exp.abs());
+        return format_number(negative, int_part, Some(&frac_part), flags, width);
+    }
+
+    // keep `precision` digits after the decimal point
+    let (digits, exp) = round_half_up(digits, exp, exp + 1 + precision as i64);
+    let (int_part, frac_part) = if exp >= 0 {
+        let int_len = exp as usize + 1;
+        let padded = format!("{:0<int_len$}", std::str::from_utf8(&digits).unwrap());
+        let (int_part, frac_part) = padded.split_at(int_len);
+        (int_part.to_string(), frac_part.to_string())
+    } else {
+        let leading_zeros = "0".repeat((-exp - 1) as usize);
+        let frac_part = format!("{leading_zeros}{}", std::str::from_utf8(&digits).unwrap());
+        ("0".to_string(), frac_part)
+    };
+    let frac_part = truncate_chars(format!("{frac_part:0<precision$}"), Some(precision));
+    let frac_part = format!("{point}{frac_part}");
+    format_number(negative, &int_part, Some(&frac_part), flags, width)
+}
+
+// rounds digits (d.ddd * 10^exp) half-up to keep `keep` significant digits,
+// returns the rounded digits and the new exponent
+fn round_half_up(mut digits: Vec<u8>, exp: i64, keep: i64) -> (Vec<u8>, i64) {
+    if keep >= digits.len() as i64 {
+        return (digits, exp);
+    }
+    if keep < 0 {
+        return (vec![b'0'], exp);
+    }
+    let keep = keep as usize;
+    let round_up = digits[keep] >= b'5';
+    digits.truncate(keep);
+    if !round_up {
+        if digits.is_empty() {
+            return (vec![b'0'], exp);
+        }
+        return (digits, exp);
+    }
+    for d in digits.iter_mut().rev() {
+        if *d == b'9' {
+            *d = b'0';
+        } else {
+            *d += 1;
+            return (digits, exp);
+        }
+    }
+    // all digits are carried, e.g. 9.99 -> 10.00
+    digits.insert(0, b'1');
+    (digits, exp + 1)
+}
+
+// same as java's BigDecimal.toString()
+fn java_decimal_to_string(unscaled: i128, scale: i8) -> String {
+    let sign = if unscaled < 0 { "-" } else { "" };
+    let digits = unscaled.unsigned_abs().to_string();
+    let scale = scale as i64;
+    let adjusted = -scale + digits.len() as i64 - 1;
+
+    if scale >= 0 && adjusted >= -6 {
+        let scale = scale as usize;
+        if scale == 0 {
+            return format!("{sign}{digits}");
+        }
+        let padded = format!("{digits:0>width$}", width = scale + 1);
+        let (int_part, frac_part) = padded.split_at(padded.len() - scale);
+        return format!("{sign}{int_part}.{frac_part}");
+    }
+
+    let (first, rest) = digits.split_at(1);
+    let point = if rest.is_empty() { "" } else { "." };
+    let exp_sign = if adjusted >= 0 { "+" } else { "" };
+    format!("{sign}{first}{point}{rest}E{exp_sign}{adjusted}")
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::array::{
+        ArrayRef, BooleanArray, Decimal128Array, Float64Array, Int32Array, Int64Array, Int8Array,
+        StringArray,
+    };
+    use datafusion::{
+        common::{cast::as_string_array, Result, ScalarValue},
+        physical_plan::ColumnarValue,
+    };
+
+    use crate::spark_format_string::spark_format_string;
+
+    fn format_scalar(format: &str, args: Vec<ArrayRef>) -> Result<String> {
+        let mut all_args = vec![ColumnarValue::Scalar(ScalarValue::from(format))];
+        all_args.extend(args.into_iter().map(ColumnarValue::Array));
+        let r = spark_format_string(&all_args)?;
+        let s = r.into_array(1);
+        Ok(as_string_array(&s)?.value(0).to_string())
+    }
+
+    #[test]
+    fn test_format_string() -> Result<()> {
+        let r = spark_format_string(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("Hello World %d %s"),
+                Some("%2$s-%1$05d%%%n"),
+                None,
+            ]))),
+            ColumnarValue::Array(Arc::new(Int32Array::from_iter(vec![
+                Some(100),
+                Some(-42),
+                Some(1),
+            ]))),
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("days"),
+                None,
+                Some("x"),
+            ]))),
+        ])?;
+        let s = r.into_array(3);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("Hello World 100 days"), Some("null--0042%\n"), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_integers() -> Result<()> {
+        let ints = || -> Vec<ArrayRef> { vec![Arc::new(Int64Array::from(vec![-1234567]))] };
+        assert_eq!(format_scalar("%,d", ints())?, "-1,234,567");
+        assert_eq!(format_scalar("%(,12d", ints())?, " (1,234,567)");
+        assert_eq!(format_scalar("%-10d|", ints())?, "-1234567  |");
+        assert_eq!(format_scalar("%x", ints())?, "ffffffffffed2979");
+        assert_eq!(format_scalar("%#X", ints())?, "0XFFFFFFFFFFED2979");
+        assert_eq!(
+            format_scalar("%#010x", vec![Arc::new(Int32Array::from(vec![255]))])?,
+            "0x000000ff"
+        );
+
+        let bytes = || -> Vec<ArrayRef> { vec![Arc::new(Int8Array::from(vec![-1]))] };
+        assert_eq!(format_scalar("%x %<o", bytes())?, "ff 377");
+        assert_eq!(format_scalar("%+d", bytes())?, "-1");
+
+        let code_points = vec![Arc::new(Int32Array::from(vec![0x7816])) as ArrayRef];
+        assert_eq!(format_scalar("%c", code_points)?, "砖");
+
+        assert!(format_scalar("%d %d", ints()).is_err());
+        assert!(format_scalar("%#d", ints()).is_err());
+        assert!(format_scalar("%.2d", ints()).is_err());
+        assert!(format_scalar("%f", ints()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_floats() -> Result<()> {
+        let floats = |v: f64| -> Vec<ArrayRef> { vec![Arc::new(Float64Array::from(vec![v]))] };
+        assert_eq!(format_scalar("%f", floats(3.14159265))?, "3.141593");
+        assert_eq!(format_scalar("%.1f", floats(0.15))?, "0.2");
+        assert_eq!(format_scalar("%.2f", floats(1.005))?, "1.01");
+        assert_eq!(format_scalar("%.0f", floats(9.5))?, "10");
+        assert_eq!(format_scalar("%.3f", floats(0.0001))?, "0.000");
+        assert_eq!(format_scalar("%,.2f", floats(1234567.891))?, "1,234,567.89");
+        assert_eq!(format_scalar("%010.2f", floats(-3.5))?, "-000003.50");
+        assert_eq!(format_scalar("%+.1f", floats(0.0))?, "+0.0");
+        assert_eq!(format_scalar("%e", floats(12345.678))?, "1.234568e+04");
+        assert_eq!(format_scalar("%.2E", floats(0.000999))?, "9.99E-04");
+        assert_eq!(format_scalar("%.1E", floats(0.000999))?, "1.0E-03");
+        assert_eq!(format_scalar("%.1e", floats(0.0))?, "0.0e+00");
+        assert_eq!(format_scalar("%f", floats(f64::NAN))?, "NaN");
+        assert_eq!(
+            format_scalar("%(f", floats(f64::NEG_INFINITY))?,
+            "(Infinity)"
+        );
+        assert_eq!(format_scalar("%08f", floats(f64::INFINITY))?, "Infinity");
+        assert_eq!(format_scalar("%s", floats(1e10))?, "1.0E10");
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_others() -> Result<()> {
+        let args = || -> Vec<ArrayRef> {
+            vec![
+                Arc::new(BooleanArray::from(vec![None])),
+                Arc::new(StringArray::from(vec!["数据砖头"])),
+                Arc::new(
+                    Decimal128Array::from(vec![12345])
+                        .with_precision_and_scale(10, 3)
+                        .unwrap(),
+                ),
+                Arc::new(
+                    Decimal128Array::from(vec![0])
+                        .with_precision_and_scale(18, 10)
+                        .unwrap(),
+                ),
+            ]
+        };
+        assert_eq!(format_scalar("%b %<s %2$B", args())?, "false null TRUE");
+        assert_eq!(format_scalar("%2$.2s|%2$-6S|", args())?, "数据|数据砖头  |");
+        assert_eq!(format_scalar("%3$s %4$s", args())?, "12.345 0E-10");
+        assert_eq!(
+            format_scalar("%3$f %3$.2f %3$e", args())?,
+            "12.345000 12.35 1.234500e+01"
+        );
+        assert_eq!(format_scalar("%4$.3f %4$e", args())?, "0.000 0.000000e-10");
+        assert!(format_scalar("%tY", args()).is_err());
+        assert!(format_scalar("%q", args()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,587 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Map expressions compatible with spark
+//!
+//! functions constructing maps take the value of `spark.sql.mapKeyDedupPolicy`
+//! (EXCEPTION or LAST_WIN) as their last literal argument.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::Arc,
+};
+
+use arrow::{
+    array::*,
+    buffer::{Buffer, NullBuffer, OffsetBuffer},
+    datatypes::{DataType, Field, FieldRef},
+    row::{RowConverter, SortField},
+};
+use datafusion::{
+    common::{
+        cast::{as_list_array, as_string_array, as_struct_array},
+        Result, ScalarValue,
+    },
+    physical_plan::ColumnarValue,
+};
+use datafusion_ext_commons::{df_execution_err, downcast_any};
+use regex::Regex;
+
+use crate::spark_utils::{broadcast_args, output};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MapKeyDedupPolicy {
+    Exception,
+    LastWin,
+}
+
+impl MapKeyDedupPolicy {
+    fn try_from_arg(arg: &ColumnarValue) -> Result<Self> {
+        match arg {
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(policy))) => {
+                match policy.to_uppercase().as_str() {
+                    "EXCEPTION" => Ok(Self::Exception),
+                    "LAST_WIN" => Ok(Self::LastWin),
+                    other => df_execution_err!("unsupported map key dedup policy: {other}"),
+                }
+            }
+            _ => df_execution_err!("map key dedup policy only supports literal string"),
+        }
+    }
+}
+
+/// map_keys() function compatible with spark
+pub fn map_keys(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let map = downcast_any!(arrays[0], MapArray)?;
+    let item_field = Arc::new(Field::new("item", map.key_type().clone(), false));
+    let keys = ListArray::new(
+        item_field,
+        map_offsets(map),
+        map.keys().clone(),
+        map.nulls().cloned(),
+    );
+    output(args, Arc::new(keys))
+}
+
+/// map_values() function compatible with spark
+pub fn map_values(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let map = downcast_any!(arrays[0], MapArray)?;
+    let item_field = Arc::new(Field::new(
+        "item",
+        map.value_type().clone(),
+        map_value_field(map).is_nullable(),
+    ));
+    let values = ListArray::new(
+        item_field,
+        map_offsets(map),
+        map.values().clone(),
+        map.nulls().cloned(),
+    );
+    output(args, Arc::new(values))
+}
+
+/// map_entries() function compatible with spark
+/// map_entries(map(1, 'a', 2, 'b')) = [{key: 1, value: a}, {key: 2, value: b}]
+pub fn map_entries(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let map = downcast_any!(arrays[0], MapArray)?;
+    let entries = StructArray::from(vec![
+        (
+            Arc::new(Field::new("key", map.key_type().clone(), false)),
+            map.keys().clone(),
+        ),
+        (
+            Arc::new(Field::new(
+                "value",
+                map.value_type().clone(),
+                map_value_field(map).is_nullable(),
+            )),
+            map.values().clone(),
+        ),
+    ]);
+    let item_field = Arc::new(Field::new("item", entries.data_type().clone(), false));
+    let list = ListArray::new(
+        item_field,
+        map_offsets(map),
+        Arc::new(entries),
+        map.nulls().cloned(),
+    );
+    output(args, Arc::new(list))
+}
+
+/// map_from_arrays() function compatible with spark
+/// map_from_arrays(array(1, 2), array('a', 'b')) = {1: a, 2: b}
+pub fn map_from_arrays(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    if args.len() != 3 {
+        df_execution_err!("map_from_arrays requires keys, values and map key dedup policy")?;
+    }
+    let dedup_policy = MapKeyDedupPolicy::try_from_arg(&args[2])?;
+    let arrays = broadcast_args(&args[..2])?;
+    let keys = as_list_array(&arrays[0])?;
+    let values = as_list_array(&arrays[1])?;
+    let value_nullable = match values.data_type() {
+        DataType::List(field) => field.is_nullable(),
+        _ => unreachable!(),
+    };
+
+    let map = build_map_array(
+        keys.len(),
+        &[keys.values().clone()],
+        &[values.values().clone()],
+        value_nullable,
+        dedup_policy,
+        |i, entries| {
+            if keys.is_null(i) || values.is_null(i) {
+                return Ok(false);
+            }
+            let key_offsets = keys.value_offsets();
+            let value_offsets = values.value_offsets();
+            let key_range = key_offsets[i] as usize..key_offsets[i + 1] as usize;
+            let value_range = value_offsets[i] as usize..value_offsets[i + 1] as usize;
+            if key_range.len() != value_range.len() {
+                df_execution_err!(
+                    "The key array and value array of MapData must have the same length."
+                )?;
+            }
+            for (k, v) in key_range.zip(value_range) {
+                entries.push(((0, k), (0, v)));
+            }
+            Ok(true)
+        },
+    )?;
+    output(args, map)
+}
+
+/// map_from_entries() function compatible with spark
+/// map_from_entries(array(struct(1, 'a'), struct(2, 'b'))) = {1: a, 2: b}
+pub fn map_from_entries(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    if args.len() != 2 {
+        df_execution_err!("map_from_entries requires entries and map key dedup policy")?;
+    }
+    let dedup_policy = MapKeyDedupPolicy::try_from_arg(&args[1])?;
+    let arrays = broadcast_args(&args[..1])?;
+    let list = as_list_array(&arrays[0])?;
+    let entries = as_struct_array(list.values())?;
+    if entries.num_columns() != 2 {
+        df_execution_err!("map_from_entries requires array of key-value struct")?;
+    }
+    let value_nullable = match entries.data_type() {
+        DataType::Struct(fields) => fields[1].is_nullable(),
+        _ => unreachable!(),
+    };
+
+    let map = build_map_array(
+        list.len(),
+        &[entries.column(0).clone()],
+        &[entries.column(1).clone()],
+        value_nullable,
+        dedup_policy,
+        |i, row_entries| {
+            if list.is_null(i) {
+                return Ok(false);
+            }
+            let offsets = list.value_offsets();
+            for j in offsets[i] as usize..offsets[i + 1] as usize {
+                // returns null if any of the entries is null
+                if entries.is_null(j) {
+                    row_entries.clear();
+                    return Ok(false);
+                }
+                row_entries.push(((0, j), (0, j)));
+            }
+            Ok(true)
+        },
+    )?;
+    output(args, map)
+}
+
+/// map_concat() function compatible with spark
+/// map_concat(map(1, 'a'), map(2, 'b')) = {1: a, 2: b}
+pub fn map_concat(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    if args.len() < 2 {
+        df_execution_err!("map_concat requires at least one map argument")?;
+    }
+    let dedup_policy = MapKeyDedupPolicy::try_from_arg(&args[args.len() - 1])?;
+    let arrays = broadcast_args(&args[..args.len() - 1])?;
+    let maps = arrays
+        .iter()
+        .map(|array| downcast_any!(array, MapArray))
+        .collect::<Result<Vec<_>>>()?;
+    let value_nullable = maps.iter().any(|map| map_value_field(map).is_nullable());
+
+    let map = build_map_array(
+        maps[0].len(),
+        &maps
+            .iter()
+            .map(|map| map.keys().clone())
+            .collect::<Vec<_>>(),
+        &maps
+            .iter()
+            .map(|map| map.values().clone())
+            .collect::<Vec<_>>(),
+        value_nullable,
+        dedup_policy,
+        |i, entries| {
+            if maps.iter().any(|map| map.is_null(i)) {
+                return Ok(false);
+            }
+            for (map_idx, map) in maps.iter().enumerate() {
+                let offsets = map.value_offsets();
+                for j in offsets[i] as usize..offsets[i + 1] as usize {
+                    entries.push(((map_idx, j), (map_idx, j)));
+                }
+            }
+            Ok(true)
+        },
+    )?;
+    output(args, map)
+}
+
+/// str_to_map() function compatible with spark
+/// str_to_map('a:1,b:2', ',', ':') = {a: 1, b: 2}
+pub fn str_to_map(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    if args.len() != 4 {
+        df_execution_err!("str_to_map requires text, pair delimiter and key-value delimiter")?;
+    }
+    let dedup_policy = MapKeyDedupPolicy::try_from_arg(&args[3])?;
+    let delim_regex = |arg: &ColumnarValue| match arg {
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(delim))) => Regex::new(delim)
+            .or_else(|err| df_execution_err!("str_to_map invalid delimiter {delim}: {err}")),
+        _ => df_execution_err!("str_to_map delimiters only support literal string"),
+    };
+    let pair_delim = delim_regex(&args[1])?;
+    let key_value_delim = delim_regex(&args[2])?;
+
+    let arrays = broadcast_args(&args[..1])?;
+    let texts = as_string_array(&arrays[0])?;
+    let mut keys = StringBuilder::new();
+    let mut values = StringBuilder::new();
+    let mut offsets = vec![0];
+    for text in texts {
+        // spark splits text with java's String.split(regex, -1), which keeps
+        // trailing empty strings
+        for pair in text.into_iter().flat_map(|text| pair_delim.split(text)) {
+            let mut kv = key_value_delim.splitn(pair, 2);
+            keys.append_value(kv.next().unwrap_or_default());
+            values.append_option(kv.next());
+        }
+        offsets.push(keys.len());
+    }
+    let keys: ArrayRef = Arc::new(keys.finish());
+    let values: ArrayRef = Arc::new(values.finish());
+
+    let map = build_map_array(
+        texts.len(),
+        &[keys],
+        &[values],
+        true,
+        dedup_policy,
+        |i, entries| {
+            if texts.is_null(i) {
+                return Ok(false);
+            }
+            for j in offsets[i]..offsets[i + 1] {
+                entries.push(((0, j), (0, j)));
+            }
+            Ok(true)
+        },
+    )?;
+    output(args, map)
+}
+
+/// builds a map array with entries collected from key/value sources, null or
+/// duplicated keys are handled like spark's ArrayBasedMapBuilder.
+///
+/// `collect_row_entries` pushes (source_idx, entry_idx) of each key and value
+/// of the i-th row, and returns false if the row is null.
+fn build_map_array(
+    num_rows: usize,
+    key_sources: &[ArrayRef],
+    value_sources: &[ArrayRef],
+    value_nullable: bool,
+    dedup_policy: MapKeyDedupPolicy,
+    mut collect_row_entries: impl FnMut(
+        usize,
+        &mut Vec<((usize, usize), (usize, usize))>,
+    ) -> Result<bool>,
+) -> Result<ArrayRef> {
+    let key_type = key_sources[0].data_type().clone();
+    let value_type = value_sources[0].data_type().clone();
+    let mut converter = RowConverter::new(vec![SortField::new(key_type.clone())])?;
+    let key_rows = key_sources
+        .iter()
+        .map(|keys| converter.convert_columns(&[keys.clone()]))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut key_indices: Vec<(usize, usize)> = vec![];
+    let mut value_indices: Vec<(usize, usize)> = vec![];
+    let mut offsets: Vec<i32> = Vec::with_capacity(num_rows + 1);
+    let mut validity = Vec::with_capacity(num_rows);
+    let mut row_entries = vec![];
+    let mut key_positions = HashMap::new();
+    offsets.push(0);
+
+    for i in 0..num_rows {
+        row_entries.clear();
+        key_positions.clear();
+        validity.push(collect_row_entries(i, &mut row_entries)?);
+
+        for &(key_idx, value_idx) in &row_entries {
+            if key_sources[key_idx.0].is_null(key_idx.1) {
+                df_execution_err!("Cannot use null as map key.")?;
+            }
+            match key_positions.entry(key_rows[key_idx.0].row(key_idx.1)) {
+                Entry::Vacant(entry) => {
+                    entry.insert(key_indices.len());
+                    key_indices.push(key_idx);
+                    value_indices.push(value_idx);
+                }
+                Entry::Occupied(entry) => match dedup_policy {
+                    MapKeyDedupPolicy::LastWin => value_indices[*entry.get()] = value_idx,
+                    MapKeyDedupPolicy::Exception => {
+                        let key = ScalarValue::try_from_array(&key_sources[key_idx.0], key_idx.1)?;
+                        df_execution_err!(
+                            "Duplicate map key {key} was found, please check the input data. \
+                            If you want to remove the duplicated keys, you can set \
+                            spark.sql.mapKeyDedupPolicy to LAST_WIN so that the key inserted \
+                            at last takes precedence."
+                        )?;
+                    }
+                },
+            }
+        }
+        offsets.push(key_indices.len() as i32);
+    }
+
+    let key_sources = key_sources.iter().map(|a| a.as_ref()).collect::<Vec<_>>();
+    let value_sources = value_sources.iter().map(|a| a.as_ref()).collect::<Vec<_>>();
+    let keys = arrow::compute::interleave(&key_sources, &key_indices)?;
+    let values = arrow::compute::interleave(&value_sources, &value_indices)?;
+    let entries = StructArray::from(vec![
+        (Arc::new(Field::new("key", key_type, false)), keys),
+        (
+            Arc::new(Field::new("value", value_type, value_nullable)),
+            values,
+        ),
+    ]);
+    let entries_field = Arc::new(Field::new("entries", entries.data_type().clone(), false));
+    let map_data = ArrayData::builder(DataType::Map(entries_field, false))
+        .len(num_rows)
+        .add_buffer(Buffer::from_vec(offsets))
+        .add_child_data(entries.into_data())
+        .nulls(Some(NullBuffer::from(validity)))
+        .build()?;
+    Ok(make_array(map_data))
+}
+
+fn map_offsets(map: &MapArray) -> OffsetBuffer<i32> {
+    OffsetBuffer::new(map.value_offsets().to_vec().into())
+}
+
+fn map_value_field(map: &MapArray) -> FieldRef {
+    match map.entries().data_type() {
+        DataType::Struct(fields) => fields[1].clone(),
+        _ => unreachable!("map entries must be struct"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::*,
+        datatypes::{Int32Type, UInt32Type},
+        record_batch::RecordBatch,
+    };
+    use datafusion::{
+        assert_batches_eq,
+        common::{Result, ScalarValue},
+        physical_plan::ColumnarValue,
+    };
+
+    use crate::spark_maps::{
+        map_concat, map_entries, map_from_arrays, map_from_entries, map_keys, map_values,
+        str_to_map,
+    };
+
+    fn test_map() -> ArrayRef {
+        let keys = vec!["a", "b", "c", "d", "e"];
+        let values = UInt32Array::from(vec![1u32, 2, 3, 4, 5]);
+        Arc::new(MapArray::new_from_strings(keys.into_iter(), &values, &[0, 2, 2, 5]).unwrap())
+    }
+
+    fn assert_array_eq(array: ArrayRef, expected: Vec<&str>) -> Result<()> {
+        let batch = RecordBatch::try_from_iter_with_nullable(vec![("col", array, true)])?;
+        assert_batches_eq!(expected, &[batch]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_keys_values_entries() -> Result<()> {
+        let map = ColumnarValue::Array(test_map());
+        assert_array_eq(
+            map_keys(&[map.clone()])?.into_array(3),
+            vec![
+                "+-----------+",
+                "| col       |",
+                "+-----------+",
+                "| [a, b]    |",
+                "| []        |",
+                "| [c, d, e] |",
+                "+-----------+",
+            ],
+        )?;
+        assert_array_eq(
+            map_values(&[map.clone()])?.into_array(3),
+            vec![
+                "+-----------+",
+                "| col       |",
+                "+-----------+",
+                "| [1, 2]    |",
+                "| []        |",
+                "| [3, 4, 5] |",
+                "+-----------+",
+            ],
+        )?;
+        assert_array_eq(
+            map_entries(&[map])?.into_array(3),
+            vec![
+                "+--------------------------------------------------------------+",
+                "| col                                                          |",
+                "+--------------------------------------------------------------+",
+                "| [{key: a, value: 1}, {key: b, value: 2}]                     |",
+                "| []                                                           |",
+                "| [{key: c, value: 3}, {key: d, value: 4}, {key: e, value: 5}] |",
+                "+--------------------------------------------------------------+",
+            ],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_from_arrays() -> Result<()> {
+        let keys = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2), Some(1)]),
+            None,
+            Some(vec![]),
+        ]);
+        let values = ListArray::from_iter_primitive::<UInt32Type, _, _>(vec![
+            Some(vec![Some(10), Some(20), Some(30)]),
+            Some(vec![Some(40)]),
+            Some(vec![]),
+        ]);
+        let args = vec![
+            ColumnarValue::Array(Arc::new(keys)),
+            ColumnarValue::Array(Arc::new(values)),
+            ColumnarValue::Scalar(ScalarValue::from("LAST_WIN")),
+        ];
+        assert_array_eq(
+            map_from_arrays(&args)?.into_array(3),
+            vec![
+                "+----------------+",
+                "| col            |",
+                "+----------------+",
+                "| {1: 30, 2: 20} |",
+                "|                |",
+                "| {}             |",
+                "+----------------+",
+            ],
+        )?;
+
+        // duplicated keys are not allowed with EXCEPTION policy
+        let mut args = args;
+        args[2] = ColumnarValue::Scalar(ScalarValue::from("EXCEPTION"));
+        assert!(map_from_arrays(&args).is_err());
+
+        // missing dedup policy is an error instead of a panic
+        assert!(map_from_arrays(&args[..2]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_from_entries() -> Result<()> {
+        let entries = map_entries(&[ColumnarValue::Array(test_map())])?;
+        let args = vec![
+            entries,
+            ColumnarValue::Scalar(ScalarValue::from("EXCEPTION")),
+        ];
+        assert_array_eq(
+            map_from_entries(&args)?.into_array(3),
+            vec![
+                "+--------------------+",
+                "| col                |",
+                "+--------------------+",
+                "| {a: 1, b: 2}       |",
+                "| {}                 |",
+                "| {c: 3, d: 4, e: 5} |",
+                "+--------------------+",
+            ],
+        )?;
+
+        // missing dedup policy is an error instead of a panic
+        assert!(map_from_entries(&args[..1]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_concat() -> Result<()> {
+        let args = vec![
+            ColumnarValue::Array(test_map()),
+            ColumnarValue::Array(test_map()),
+            ColumnarValue::Scalar(ScalarValue::from("LAST_WIN")),
+        ];
+        assert_array_eq(
+            map_concat(&args)?.into_array(3),
+            vec![
+                "+--------------------+",
+                "| col                |",
+                "+--------------------+",
+                "| {a: 1, b: 2}       |",
+                "| {}                 |",
+                "| {c: 3, d: 4, e: 5} |",
+                "+--------------------+",
+            ],
+        )
+    }
+
+    #[test]
+    fn test_str_to_map() -> Result<()> {
+        let args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                Some("a:1,b:2,c"),
+                None,
+                Some(""),
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::from(",")),
+            ColumnarValue::Scalar(ScalarValue::from(":")),
+            ColumnarValue::Scalar(ScalarValue::from("EXCEPTION")),
+        ];
+        let r = str_to_map(&args)?.into_array(3);
+        let keys = map_keys(&[ColumnarValue::Array(r.clone())])?.into_array(3);
+        let keys = as_list_array(&keys);
+        assert_eq!(keys.value_offsets(), &[0, 3, 3, 4]);
+        assert!(r.is_null(1));
+
+        let values = map_values(&[ColumnarValue::Array(r)])?.into_array(3);
+        let values = as_list_array(&values);
+        assert_eq!(
+            as_string_array(values.values()).iter().collect::<Vec<_>>(),
+            vec![Some("1"), Some("2"), None, None]
+        );
+        Ok(())
+    }
+}
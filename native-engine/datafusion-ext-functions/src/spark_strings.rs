@@ -12,10 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use arrow::{
-    array::{Array, ArrayRef, ListArray, ListBuilder, StringArray, StringBuilder},
+    array::{Array, ArrayRef, Int32Array, ListArray, ListBuilder, StringArray, StringBuilder},
     datatypes::DataType,
 };
 use datafusion::{
@@ -27,6 +27,8 @@ use datafusion::{
 };
 use datafusion_ext_commons::df_execution_err;
 
+use crate::spark_utils::{broadcast_args, output};
+
 pub fn string_lower(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     match &args[0] {
         ColumnarValue::Array(array) => Ok(ColumnarValue::Array(Arc::new(StringArray::from_iter(
@@ -320,6 +322,367 @@ pub fn string_concat_ws(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     Ok(ColumnarValue::Array(concatenated_string_array))
 }
 
+/// substring_index() function compatible with spark
+/// substring_index('www.apache.org', '.', 2) = 'www.apache'
+/// substring_index('www.apache.org', '.', -2) = 'apache.org'
+pub fn string_substring_index(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let strs = as_string_array(&arrays[0])?;
+    let delims = as_string_array(&arrays[1])?;
+    let counts = as_int32_array(&arrays[2])?;
+
+    let result_array: StringArray = strs
+        .iter()
+        .zip(delims)
+        .zip(counts)
+        .map(|((s, delim), count)| Some(substring_index(s?, delim?, count?)))
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+/// locate() function compatible with spark
+/// returns the 1-based position of the first occurrence of substr in str
+/// after position pos, or 0 if not found. returns 0 if pos is null.
+/// locate('bar', 'foobarbar', 5) = 7
+pub fn string_locate(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let substrs = as_string_array(&arrays[0])?;
+    let strs = as_string_array(&arrays[1])?;
+    let positions = as_int32_array(&arrays[2])?;
+
+    let result_array: Int32Array = substrs
+        .iter()
+        .zip(strs)
+        .zip(positions)
+        .map(|((substr, s), pos)| {
+            let pos = match pos {
+                Some(pos) => pos,
+                None => return Some(0), // conforms to hive
+            };
+            let (substr, s) = (substr?, s?);
+            if pos < 1 {
+                return Some(0);
+            }
+            Some(index_of(s, substr, pos as usize - 1) + 1)
+        })
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+/// instr() function compatible with spark
+/// instr('SparkSQL', 'SQL') = 6
+pub fn string_instr(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let strs = as_string_array(&arrays[0])?;
+    let substrs = as_string_array(&arrays[1])?;
+
+    let result_array: Int32Array = strs
+        .iter()
+        .zip(substrs)
+        .map(|(s, substr)| Some(index_of(s?, substr?, 0) + 1))
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+/// levenshtein() function compatible with spark, distance is computed on
+/// unicode code points
+/// levenshtein('kitten', 'sitting') = 3
+pub fn string_levenshtein(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let lefts = as_string_array(&arrays[0])?;
+    let rights = as_string_array(&arrays[1])?;
+
+    let result_array: Int32Array = lefts
+        .iter()
+        .zip(rights)
+        .map(|(l, r)| Some(levenshtein(l?, r?)))
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+/// soundex() function compatible with spark, strings not starting with an
+/// english letter are returned as they are
+/// soundex('Miller') = 'M460'
+pub fn string_soundex(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let strs = as_string_array(&arrays[0])?;
+
+    let result_array: StringArray = strs.iter().map(|s| Some(soundex(s?))).collect();
+    output(args, Arc::new(result_array))
+}
+
+/// translate() function compatible with spark, characters in matching
+/// without corresponding characters in replace are removed
+/// translate('AaBbCc', 'abc', '12') = 'A1B2C'
+pub fn string_translate(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let strs = as_string_array(&arrays[0])?;
+    let matchings = as_string_array(&arrays[1])?;
+    let replaces = as_string_array(&arrays[2])?;
+
+    let mut cached_dict: Option<((String, String), HashMap<char, Option<char>>)> = None;
+    let result_array: StringArray = strs
+        .iter()
+        .zip(matchings)
+        .zip(replaces)
+        .map(|((s, matching), replace)| {
+            let (s, matching, replace) = (s?, matching?, replace?);
+
+            // dict is usually built from literals, so only the last one is cached
+            let cache_hit = matches!(
+                &cached_dict,
+                Some(((m, r), _)) if m == matching && r == replace,
+            );
+            if !cache_hit {
+                let dict = build_translate_dict(matching, replace);
+                cached_dict = Some(((matching.to_string(), replace.to_string()), dict));
+            }
+            let dict = &cached_dict.as_ref().unwrap().1;
+            Some(
+                s.chars()
+                    .filter_map(|c| dict.get(&c).cloned().unwrap_or(Some(c)))
+                    .collect::<String>(),
+            )
+        })
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+/// left() function compatible with spark, on unicode code points
+/// left('Spark SQL', 3) = 'Spa'
+pub fn string_left(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let strs = as_string_array(&arrays[0])?;
+    let lens = as_int32_array(&arrays[1])?;
+
+    let result_array: StringArray = strs
+        .iter()
+        .zip(lens)
+        .map(|(s, len)| {
+            let (s, len) = (s?, len?);
+            if len <= 0 {
+                return Some("");
+            }
+            Some(substring_sql(s, 1, len as i64))
+        })
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+/// right() function compatible with spark, on unicode code points
+/// right('Spark SQL', 3) = 'SQL'
+pub fn string_right(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let strs = as_string_array(&arrays[0])?;
+    let lens = as_int32_array(&arrays[1])?;
+
+    let result_array: StringArray = strs
+        .iter()
+        .zip(lens)
+        .map(|(s, len)| {
+            let (s, len) = (s?, len?);
+            if len <= 0 {
+                return Some("");
+            }
+            Some(substring_sql(s, -len as i64, len as i64))
+        })
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+/// overlay() function compatible with spark, on unicode code points. the
+/// length of replace is used if len is negative.
+/// overlay('Spark SQL', '_', 6) = 'Spark_SQL'
+/// overlay('Spark SQL', 'CORE', 7, 0) = 'Spark CORESQL'
+pub fn string_overlay(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let arrays = broadcast_args(args)?;
+    let inputs = as_string_array(&arrays[0])?;
+    let replaces = as_string_array(&arrays[1])?;
+    let positions = as_int32_array(&arrays[2])?;
+    let lens = match arrays.get(3) {
+        Some(lens) => as_int32_array(lens)?.clone(),
+        None => Int32Array::from(vec![-1; inputs.len()]),
+    };
+
+    let result_array: StringArray = inputs
+        .iter()
+        .zip(replaces)
+        .zip(positions)
+        .zip(&lens)
+        .map(|(((input, replace), pos), len)| {
+            let (input, replace, pos, len) = (input?, replace?, pos? as i64, len? as i64);
+            let len = if len >= 0 {
+                len
+            } else {
+                replace.chars().count() as i64
+            };
+            Some(format!(
+                "{}{}{}",
+                substring_sql(input, 1, pos - 1),
+                replace,
+                substring_sql(input, pos + len, i32::MAX as i64),
+            ))
+        })
+        .collect();
+    output(args, Arc::new(result_array))
+}
+
+// same as spark's UTF8String.subStringIndex(), occurrences of delim may
+// overlap
+fn substring_index<'a>(s: &'a str, delim: &str, count: i32) -> &'a str {
+    if delim.is_empty() || count == 0 {
+        return "";
+    }
+    let (bytes, delim_bytes) = (s.as_bytes(), delim.as_bytes());
+
+    // a valid utf-8 delim can only be matched at char boundaries
+    if count > 0 {
+        let mut idx = 0;
+        for i in 0..count {
+            let from = if i == 0 { 0 } else { idx + 1 };
+            match find_bytes(bytes, delim_bytes, from) {
+                Some(found) => idx = found,
+                None => return s,
+            }
+        }
+        &s[..idx]
+    } else {
+        let mut idx = bytes.len();
+        for _ in count..0 {
+            match idx
+                .checked_sub(1)
+                .and_then(|start| rfind_bytes(bytes, delim_bytes, start))
+            {
+                Some(found) => idx = found,
+                None => return s,
+            }
+        }
+        &s[idx + delim_bytes.len()..]
+    }
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    let last_start = haystack.len().checked_sub(needle.len())?;
+    (from..=last_start).find(|&i| haystack[i..].starts_with(needle))
+}
+
+// finds the last occurrence starting at or before `start`
+fn rfind_bytes(haystack: &[u8], needle: &[u8], start: usize) -> Option<usize> {
+    let last_start = haystack.len().checked_sub(needle.len())?.min(start);
+    (0..=last_start)
+        .rev()
+        .find(|&i| haystack[i..].starts_with(needle))
+}
+
+// same as spark's UTF8String.indexOf(), returns the 0-based code point index of
+// the first occurrence of substr starting from code point index `start`, or -1
+// if not found
+fn index_of(s: &str, substr: &str, start: usize) -> i32 {
+    if substr.is_empty() {
+        return 0;
+    }
+    s.char_indices()
+        .enumerate()
+        .skip(start)
+        .find(|(_, (i, _))| s[*i..].starts_with(substr))
+        .map(|(c, _)| c as i32)
+        .unwrap_or(-1)
+}
+
+fn levenshtein(l: &str, r: &str) -> i32 {
+    let l = l.chars().collect::<Vec<_>>();
+    let r = r.chars().collect::<Vec<_>>();
+    if l.is_empty() || r.is_empty() {
+        return l.len().max(r.len()) as i32;
+    }
+
+    let mut prev = (0..=r.len()).collect::<Vec<_>>();
+    let mut cur = vec![0; r.len() + 1];
+    for (i, lc) in l.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, rc) in r.iter().enumerate() {
+            let cost = if lc == rc { 0 } else { 1 };
+            cur[j + 1] = (cur[j] + 1).min(prev[j + 1] + 1).min(prev[j] + cost);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[r.len()] as i32
+}
+
+// same as spark's UTF8String.soundex()
+fn soundex(s: &str) -> String {
+    const US_ENGLISH_MAPPING: &[u8; 26] = b"01230127022455012623017202";
+    let bytes = s.as_bytes();
+    if bytes.is_empty() {
+        return String::new();
+    }
+    let first = bytes[0].to_ascii_uppercase();
+    if !first.is_ascii_uppercase() {
+        // first character must be a letter
+        return s.to_string();
+    }
+
+    let mut sx = [first, b'0', b'0', b'0'];
+    let mut sxi = 1;
+    let mut last_code = US_ENGLISH_MAPPING[(first - b'A') as usize];
+    for &b in &bytes[1..] {
+        let b = b.to_ascii_uppercase();
+        if !b.is_ascii_uppercase() {
+            // not a letter, skip it
+            last_code = b'0';
+            continue;
+        }
+        let code = US_ENGLISH_MAPPING[(b - b'A') as usize];
+        if code == b'7' {
+            // ignore it
+            continue;
+        }
+        if code != b'0' && code != last_code {
+            sx[sxi] = code;
+            sxi += 1;
+            if sxi > 3 {
+                break;
+            }
+        }
+        last_code = code;
+    }
+    String::from_utf8(sx.to_vec()).unwrap()
+}
+
+// maps characters in matching to the corresponding characters in replace, or
+// None if they should be removed. the first occurrence wins.
+fn build_translate_dict(matching: &str, replace: &str) -> HashMap<char, Option<char>> {
+    let mut dict = HashMap::new();
+    let mut replace_chars = replace.chars();
+    for m in matching.chars() {
+        let r = replace_chars.next();
+        dict.entry(m).or_insert(r);
+    }
+    dict
+}
+
+// same as spark's UTF8String.substringSQL(), on unicode code points
+fn substring_sql(s: &str, pos: i64, length: i64) -> &str {
+    let num_chars = s.chars().count() as i64;
+    let start = match pos {
+        pos if pos > 0 => pos - 1,
+        pos if pos < 0 => num_chars + pos,
+        _ => 0,
+    };
+    let end = start.saturating_add(length).min(i32::MAX as i64);
+    let start = start.max(0);
+    if start >= end {
+        return "";
+    }
+    let byte_pos = |n: i64| {
+        s.char_indices()
+            .nth(n as usize)
+            .map(|(i, _)| i)
+            .unwrap_or(s.len())
+    };
+    &s[byte_pos(start)..byte_pos(end)]
+}
+
 #[cfg(test)]
 mod test {
     use std::sync::Arc;
@@ -327,15 +690,16 @@ mod test {
     use arrow::array::{Int32Array, ListBuilder, StringArray, StringBuilder};
     use datafusion::{
         common::{
-            cast::{as_list_array, as_string_array},
+            cast::{as_int32_array, as_list_array, as_string_array},
             Result, ScalarValue,
         },
         physical_plan::ColumnarValue,
     };
 
     use crate::spark_strings::{
-        string_concat, string_concat_ws, string_lower, string_repeat, string_space, string_split,
-        string_upper,
+        string_concat, string_concat_ws, string_instr, string_left, string_levenshtein,
+        string_locate, string_lower, string_overlay, string_repeat, string_right, string_soundex,
+        string_space, string_split, string_substring_index, string_translate, string_upper,
     };
 
     #[test]
@@ -523,4 +887,228 @@ mod test {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_string_substring_index() -> Result<()> {
+        let r = string_substring_index(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("www.apache.org"),
+                Some("www.apache.org"),
+                Some("www.apache.org"),
+                Some("www.apache.org"),
+                Some("aaaa"),
+                Some("数据砖头数据"),
+                None,
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::from(".")),
+            ColumnarValue::Array(Arc::new(Int32Array::from_iter(vec![
+                Some(2),
+                Some(-2),
+                Some(0),
+                Some(5),
+                Some(2),
+                Some(1),
+                Some(1),
+            ]))),
+        ])?;
+        let s = r.into_array(7);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![
+                Some("www.apache"),
+                Some("apache.org"),
+                Some(""),
+                Some("www.apache.org"),
+                Some("aaaa"),
+                Some("数据砖头数据"),
+                None,
+            ]
+        );
+
+        // overlapping delimiters
+        let r = string_substring_index(&vec![
+            ColumnarValue::Scalar(ScalarValue::from("aaaa")),
+            ColumnarValue::Scalar(ScalarValue::from("aa")),
+            ColumnarValue::Array(Arc::new(Int32Array::from_iter(vec![2, -2]))),
+        ])?;
+        let s = r.into_array(2);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("a"), Some("a")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_locate_instr() -> Result<()> {
+        let r = string_locate(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("bar"),
+                Some("bar"),
+                Some("bar"),
+                Some("砖"),
+                Some(""),
+                None,
+            ]))),
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("foobarbar"),
+                Some("foobarbar"),
+                Some("foobarbar"),
+                Some("数据砖头砖"),
+                Some("abc"),
+                Some("abc"),
+            ]))),
+            ColumnarValue::Array(Arc::new(Int32Array::from_iter(vec![
+                Some(5),
+                Some(0),
+                None,
+                Some(4),
+                Some(1),
+                Some(1),
+            ]))),
+        ])?;
+        let s = r.into_array(6);
+        assert_eq!(
+            as_int32_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some(7), Some(0), Some(0), Some(5), Some(1), None]
+        );
+
+        let r = string_instr(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("SparkSQL"),
+                Some("数据砖头"),
+                Some("SparkSQL"),
+                None,
+            ]))),
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("SQL"),
+                Some("砖"),
+                Some("Hive"),
+                Some("SQL"),
+            ]))),
+        ])?;
+        let s = r.into_array(4);
+        assert_eq!(
+            as_int32_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some(6), Some(3), Some(0), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_levenshtein_soundex() -> Result<()> {
+        let r = string_levenshtein(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("kitten"),
+                Some(""),
+                Some("数据砖头"),
+                None,
+            ]))),
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("sitting"),
+                Some("abc"),
+                Some("数据头"),
+                Some("abc"),
+            ]))),
+        ])?;
+        let s = r.into_array(4);
+        assert_eq!(
+            as_int32_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some(3), Some(3), Some(1), None]
+        );
+
+        let r = string_soundex(&vec![ColumnarValue::Array(Arc::new(
+            StringArray::from_iter(vec![
+                Some("Miller"),
+                Some("Ashcraft"),
+                Some("Tymczak"),
+                Some("1abc"),
+                Some(""),
+                None,
+            ]),
+        ))])?;
+        let s = r.into_array(6);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![
+                Some("M460"),
+                Some("A261"),
+                Some("T522"),
+                Some("1abc"),
+                Some(""),
+                None,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_translate() -> Result<()> {
+        let r = string_translate(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("AaBbCc"),
+                Some("数据砖头"),
+                None,
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::from("abca砖")),
+            ColumnarValue::Scalar(ScalarValue::from("12x")),
+        ])?;
+        let s = r.into_array(3);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("A1B2Cx"), Some("数据头"), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_left_right_overlay() -> Result<()> {
+        let strs = ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+            Some("Spark SQL"),
+            Some("数据砖头"),
+            Some("Spark SQL"),
+            None,
+        ])));
+        let lens = ColumnarValue::Array(Arc::new(Int32Array::from_iter(vec![
+            Some(3),
+            Some(2),
+            Some(-1),
+            Some(1),
+        ])));
+
+        let r = string_left(&vec![strs.clone(), lens.clone()])?;
+        let s = r.into_array(4);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("Spa"), Some("数据"), Some(""), None]
+        );
+
+        let r = string_right(&vec![strs, lens])?;
+        let s = r.into_array(4);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("SQL"), Some("砖头"), Some(""), None]
+        );
+
+        let r = string_overlay(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("Spark SQL"),
+                Some("Spark SQL"),
+                Some("数据砖头"),
+            ]))),
+            ColumnarValue::Array(Arc::new(StringArray::from_iter(vec![
+                Some("_"),
+                Some("CORE"),
+                Some("湖"),
+            ]))),
+            ColumnarValue::Array(Arc::new(Int32Array::from_iter(vec![6, 7, 3]))),
+            ColumnarValue::Array(Arc::new(Int32Array::from_iter(vec![-1, 0, 2]))),
+        ])?;
+        let s = r.into_array(3);
+        assert_eq!(
+            as_string_array(&s)?.into_iter().collect::<Vec<_>>(),
+            vec![Some("Spark_SQL"), Some("Spark CORESQL"), Some("数据湖")]
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,57 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers shared by spark functions taking columnar arguments
+
+use arrow::array::{Array, ArrayRef};
+use datafusion::{
+    common::{Result, ScalarValue},
+    physical_plan::ColumnarValue,
+};
+use datafusion_ext_commons::df_execution_err;
+
+/// broadcasts all arguments to arrays of the same length
+pub fn broadcast_args(args: &[ColumnarValue]) -> Result<Vec<ArrayRef>> {
+    let num_rows = args
+        .iter()
+        .map(|arg| match arg {
+            ColumnarValue::Array(array) => array.len(),
+            ColumnarValue::Scalar(_) => 1,
+        })
+        .max()
+        .unwrap_or(1);
+
+    args.iter()
+        .map(|arg| {
+            let array = arg.clone().into_array(num_rows);
+            if array.len() != num_rows {
+                df_execution_err!("all arguments of function must have the same length")?;
+            }
+            Ok(array)
+        })
+        .collect()
+}
+
+/// outputs scalar if all arguments are scalars
+pub fn output(args: &[ColumnarValue], array: ArrayRef) -> Result<ColumnarValue> {
+    if args
+        .iter()
+        .all(|arg| matches!(arg, ColumnarValue::Scalar(_)))
+    {
+        return Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+            &array, 0,
+        )?));
+    }
+    Ok(ColumnarValue::Array(array))
+}
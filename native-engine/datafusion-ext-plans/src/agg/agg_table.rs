@@ -28,8 +28,10 @@ use datafusion::{
 };
 use datafusion_ext_commons::{
     bytes_arena::BytesArena,
-    df_execution_err,
-    io::{read_bytes_slice, read_len, read_one_batch, write_len, write_one_batch},
+    io::{
+        read_bytes_slice, read_len, read_one_batch, write_len, write_one_batch, IoCompressionCodec,
+        IoCompressionReader, IoCompressionSite, IoCompressionWriter,
+    },
     loser_tree::{ComparableForLoserTree, LoserTree},
     rdxsort::radix_sort_u16_with_max_key_by,
     slim_bytes::SlimBytes,
@@ -37,7 +39,6 @@ use datafusion_ext_commons::{
 use futures::lock::Mutex;
 use gxhash::GxHasher;
 use hashbrown::raw::RawTable;
-use lz4_flex::frame::{FrameDecoder, FrameEncoder};
 
 use crate::{
     agg::{acc::AccumStateRow, agg_context::AggContext},
@@ -50,7 +51,7 @@ use crate::{
 };
 
 // reserve memory for each spill
-// estimated size: bufread=64KB + lz4dec.src=64KB + lz4dec.dest=64KB
+// estimated size: bufread=64KB + decoder.src=64KB + decoder.dest=64KB
 const SPILL_OFFHEAP_MEM_COST: usize = 200000;
 
 // number of buckets used in merging/spilling
@@ -476,8 +477,8 @@ impl InMemTable {
 }
 
 enum AggSpill {
-    BucketedRecords(Box<dyn Spill>), // from spilled hashmap
-    BucketedBatches(Box<dyn Spill>), // from sorted merging batches
+    BucketedRecords(Box<dyn Spill>, IoCompressionCodec), // from spilled hashmap
+    BucketedBatches(Box<dyn Spill>),                     // from sorted merging batches
 }
 
 enum AggSpillCursor {
@@ -488,8 +489,8 @@ enum AggSpillCursor {
 impl AggSpillCursor {
     fn try_from_spill(spill: &AggSpill, agg_ctx: &Arc<AggContext>) -> Result<Self> {
         Ok(match spill {
-            AggSpill::BucketedRecords(s) => {
-                Self::Records(RecordsSpillCursor::try_from_spill(&s, &agg_ctx)?)
+            AggSpill::BucketedRecords(s, codec) => {
+                Self::Records(RecordsSpillCursor::try_from_spill(&s, *codec, &agg_ctx)?)
             }
             AggSpill::BucketedBatches(s) => {
                 Self::Batches(BatchesSpillCursor::try_from_spill(&s, &agg_ctx)?)
@@ -648,7 +649,8 @@ impl HashingData {
         );
 
         let spill = try_new_spill(&self.spill_metrics)?;
-        let mut writer = FrameEncoder::new(spill.get_buf_writer());
+        let codec = IoCompressionCodec::try_from_conf(IoCompressionSite::Spill)?;
+        let mut writer = IoCompressionWriter::try_new(codec, spill.get_buf_writer())?;
         let mut beg = 0;
 
         for i in 0..NUM_SPILL_BUCKETS {
@@ -672,14 +674,14 @@ impl HashingData {
         }
         write_len(NUM_SPILL_BUCKETS, &mut writer)?; // EOF
         write_len(0, &mut writer)?;
-        writer.finish().or_else(|err| df_execution_err!("{err}"))?;
+        writer.finish()?;
         spill.complete()?;
-        Ok(AggSpill::BucketedRecords(spill))
+        Ok(AggSpill::BucketedRecords(spill, codec))
     }
 }
 
 pub struct RecordsSpillCursor {
-    input: FrameDecoder<BufReader<Box<dyn Read + Send>>>,
+    input: IoCompressionReader<BufReader<Box<dyn Read + Send>>>,
     agg_ctx: Arc<AggContext>,
     cur_bucket_idx: usize,
     cur_bucket_count: usize,
@@ -687,8 +689,12 @@ pub struct RecordsSpillCursor {
 }
 
 impl RecordsSpillCursor {
-    fn try_from_spill(spill: &Box<dyn Spill>, agg_ctx: &Arc<AggContext>) -> Result<Self> {
-        let mut input = FrameDecoder::new(spill.get_buf_reader());
+    fn try_from_spill(
+        spill: &Box<dyn Spill>,
+        codec: IoCompressionCodec,
+        agg_ctx: &Arc<AggContext>,
+    ) -> Result<Self> {
+        let mut input = IoCompressionReader::try_new(codec, spill.get_buf_reader())?;
         Ok(Self {
             agg_ctx: agg_ctx.clone(),
             cur_bucket_idx: read_len(&mut input)?,
@@ -783,7 +789,8 @@ impl MergingData {
 
     fn try_into_spill(self) -> Result<AggSpill> {
         let spill = try_new_spill(&self.spill_metrics)?;
-        let mut writer = FrameEncoder::new(spill.get_buf_writer());
+        let codec = IoCompressionCodec::try_from_conf(IoCompressionSite::Spill)?;
+        let mut writer = spill.get_buf_writer();
 
         let batch_size = self.task_ctx.session_config().batch_size();
         let sub_batch_size = batch_size / batch_size.ilog2() as usize;
@@ -792,11 +799,11 @@ impl MergingData {
             write_len(bucket_id as usize, &mut writer)?;
 
             let mut buf = vec![];
-            write_one_batch(&batch, &mut Cursor::new(&mut buf), false, None)?;
+            write_one_batch(&batch, &mut Cursor::new(&mut buf), codec, None)?;
             writer.write_all(&buf)?;
         }
         write_len(NUM_SPILL_BUCKETS, &mut writer)?; // EOF
-        writer.finish().or_else(|err| df_execution_err!("{err}"))?;
+        writer.flush()?;
         spill.complete()?;
         Ok(AggSpill::BucketedBatches(spill))
     }
@@ -892,14 +899,14 @@ impl MergingData {
 }
 
 pub struct BatchesSpillCursor {
-    input: FrameDecoder<BufReader<Box<dyn Read + Send>>>,
+    input: BufReader<Box<dyn Read + Send>>,
     cur_bucket_idx: usize,
     schema: SchemaRef,
 }
 
 impl BatchesSpillCursor {
     fn try_from_spill(spill: &Box<dyn Spill>, agg_ctx: &Arc<AggContext>) -> Result<Self> {
-        let mut input = FrameDecoder::new(spill.get_buf_reader());
+        let mut input = spill.get_buf_reader();
         let first_bucket_idx = read_len(&mut input)?;
         Ok(Self {
             input,
@@ -911,7 +918,7 @@ impl BatchesSpillCursor {
     fn next_batch(&mut self) -> Result<RecordBatch> {
         assert!(self.cur_bucket_idx < NUM_SPILL_BUCKETS);
 
-        let batch = read_one_batch(&mut self.input, Some(self.schema.clone()), false)?
+        let batch = read_one_batch(&mut self.input, Some(self.schema.clone()))?
             .expect("error reading batch");
         self.cur_bucket_idx = read_len(&mut self.input)?; // read next bucket id
         Ok(batch)
@@ -0,0 +1,336 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bucketing of hive/spark bucketed tables. Rows of spark tables are
+//! distributed to buckets by spark's murmur3 hash of the bucket columns, which
+//! is identical to the hashing of `Partitioning::Hash` in shuffle writers, so a
+//! bucketed scan can be hash partitioned by its bucket columns without
+//! shuffling. Rows of hive tables are distributed by hive hash.
+
+use std::sync::Arc;
+
+use arrow::{array::ArrayRef, datatypes::Schema};
+use datafusion::{
+    common::{Result, ScalarValue},
+    logical_expr::Operator,
+    physical_expr::{
+        expressions::{BinaryExpr, Column, InListExpr, IsNullExpr, Literal},
+        PhysicalExpr,
+    },
+};
+use datafusion_ext_commons::spark_hash::{create_hashes, create_hive_hashes, pmod};
+
+/// bucket spec of a bucketed table
+#[derive(Debug, Clone)]
+pub struct BucketSpec {
+    /// indices of the bucket columns in the input
+    pub bucket_columns: Vec<usize>,
+    pub num_buckets: usize,
+
+    /// indices of the sort columns in the input, rows of each bucket file are
+    /// sorted by them in ascending order with nulls first
+    pub sort_columns: Vec<usize>,
+
+    pub hash: BucketHash,
+}
+
+/// hash function distributing rows to buckets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BucketHash {
+    /// spark's murmur3 hash, used by bucketed spark tables
+    #[default]
+    Murmur3,
+
+    /// spark's `HiveHash`, used by hive compatible bucketed tables
+    HiveHash,
+}
+
+impl BucketSpec {
+    /// computes bucket ids of rows from the bucket columns
+    pub fn bucket_ids(&self, bucket_cols: &[ArrayRef]) -> Result<Vec<usize>> {
+        match self.hash {
+            BucketHash::Murmur3 => bucket_ids(bucket_cols, self.num_buckets),
+            BucketHash::HiveHash => hive_bucket_ids(bucket_cols, self.num_buckets),
+        }
+    }
+}
+
+/// computes bucket ids of rows from the bucket columns
+pub fn bucket_ids(bucket_cols: &[ArrayRef], num_buckets: usize) -> Result<Vec<usize>> {
+    let num_rows = bucket_cols.first().map(|col| col.len()).unwrap_or(0);
+
+    // use identical seed as spark hash partition
+    let mut hashes = vec![42; num_rows];
+    create_hashes(bucket_cols, &mut hashes)?;
+    Ok(hashes
+        .into_iter()
+        .map(|hash| pmod(hash, num_buckets))
+        .collect())
+}
+
+/// computes bucket ids of rows from the bucket columns by hive hash, the
+/// bucket id is `(hash & i32::MAX) % num_buckets` like hive
+pub fn hive_bucket_ids(bucket_cols: &[ArrayRef], num_buckets: usize) -> Result<Vec<usize>> {
+    let num_rows = bucket_cols.first().map(|col| col.len()).unwrap_or(0);
+    let mut hashes = vec![0; num_rows];
+    create_hive_hashes(bucket_cols, &mut hashes)?;
+    Ok(hashes
+        .into_iter()
+        .map(|hash| (hash & i32::MAX) as usize % num_buckets)
+        .collect())
+}
+
+/// gets bucket id from the name of a bucket file, like spark's
+/// `BucketingUtils.getBucketId` matching `.*_(\d+)(?:\..*)?$`
+pub fn bucket_id_of_file(path: &str) -> Option<usize> {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+
+    // the regex is greedy, so the rightmost matched underscore is taken
+    file_name.rmatch_indices('_').find_map(|(pos, _)| {
+        let suffix = &file_name[pos + 1..];
+        let num_digits = suffix
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(suffix.len());
+        let rest = &suffix[num_digits..];
+        if num_digits == 0 || !(rest.is_empty() || rest.starts_with('.')) {
+            return None;
+        }
+        suffix[..num_digits].parse().ok()
+    })
+}
+
+/// finds buckets possibly containing rows matching the predicate, returns
+/// `None` if all buckets may match. like spark, buckets are pruned only for
+/// tables with a single bucket column, by equality/in/is-null conditions on
+/// the bucket column.
+pub fn prune_buckets(
+    predicate: &Arc<dyn PhysicalExpr>,
+    bucket_spec: &BucketSpec,
+    schema: &Schema,
+) -> Result<Option<Vec<bool>>> {
+    if bucket_spec.bucket_columns.len() != 1 {
+        return Ok(None);
+    }
+    let bucket_column = bucket_spec.bucket_columns[0];
+    let bucket_column_type = schema.field(bucket_column).data_type();
+
+    // literals of other types are not pruned, the hashes may differ from the
+    // hashes of the casted values
+    let as_bucket_column = |expr: &Arc<dyn PhysicalExpr>| {
+        expr.as_any()
+            .downcast_ref::<Column>()
+            .filter(|column| column.index() == bucket_column)
+            .is_some()
+    };
+    let as_literal = |expr: &Arc<dyn PhysicalExpr>| {
+        expr.as_any()
+            .downcast_ref::<Literal>()
+            .map(|literal| literal.value().clone())
+            .filter(|value| &value.get_datatype() == bucket_column_type)
+    };
+    let matched_buckets = |values: Vec<ScalarValue>| -> Result<Vec<bool>> {
+        let mut matched = vec![false; bucket_spec.num_buckets];
+        if !values.is_empty() {
+            let array = ScalarValue::iter_to_array(values)?;
+            for bucket_id in bucket_spec.bucket_ids(&[array])? {
+                matched[bucket_id] = true;
+            }
+        }
+        Ok(matched)
+    };
+
+    if let Some(binary) = predicate.as_any().downcast_ref::<BinaryExpr>() {
+        return Ok(match binary.op() {
+            Operator::And => {
+                let left = prune_buckets(binary.left(), bucket_spec, schema)?;
+                let right = prune_buckets(binary.right(), bucket_spec, schema)?;
+                match (left, right) {
+                    (Some(left), Some(right)) => {
+                        Some(left.into_iter().zip(right).map(|(l, r)| l && r).collect())
+                    }
+                    (left, None) => left,
+                    (None, right) => right,
+                }
+            }
+            Operator::Or => {
+                let left = prune_buckets(binary.left(), bucket_spec, schema)?;
+                let right = prune_buckets(binary.right(), bucket_spec, schema)?;
+                match (left, right) {
+                    (Some(left), Some(right)) => {
+                        Some(left.into_iter().zip(right).map(|(l, r)| l || r).collect())
+                    }
+                    _ => None,
+                }
+            }
+            Operator::Eq => match (binary.left(), binary.right()) {
+                (l, r) if as_bucket_column(l) && as_literal(r).is_some() => {
+                    Some(matched_buckets(as_literal(r).into_iter().collect())?)
+                }
+                (l, r) if as_literal(l).is_some() && as_bucket_column(r) => {
+                    Some(matched_buckets(as_literal(l).into_iter().collect())?)
+                }
+                _ => None,
+            },
+            _ => None,
+        });
+    }
+
+    if let Some(in_list) = predicate.as_any().downcast_ref::<InListExpr>() {
+        if !in_list.negated() && as_bucket_column(in_list.expr()) {
+            let values = in_list
+                .list()
+                .iter()
+                .map(as_literal)
+                .collect::<Option<Vec<_>>>();
+            if let Some(values) = values {
+                return Ok(Some(matched_buckets(values)?));
+            }
+        }
+    }
+
+    if let Some(is_null) = predicate.as_any().downcast_ref::<IsNullExpr>() {
+        if as_bucket_column(is_null.arg()) {
+            let null_value = ScalarValue::try_from(bucket_column_type)?;
+            return Ok(Some(matched_buckets(vec![null_value])?));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, Int32Array},
+        datatypes::{DataType, Field, Schema},
+    };
+    use datafusion::{
+        common::{Result, ScalarValue},
+        logical_expr::Operator,
+        physical_expr::{
+            expressions::{binary, col, in_list, is_null, lit, BinaryExpr},
+            PhysicalExpr,
+        },
+    };
+
+    use crate::common::bucketing::{
+        bucket_id_of_file, bucket_ids, hive_bucket_ids, prune_buckets, BucketHash, BucketSpec,
+    };
+
+    #[test]
+    fn test_bucket_id_of_file() {
+        assert_eq!(
+            bucket_id_of_file("/warehouse/t/part-00000-a1b2_00003.c000.snappy.parquet"),
+            Some(3)
+        );
+        assert_eq!(bucket_id_of_file("/warehouse/t/part-00001_2"), Some(2));
+        assert_eq!(bucket_id_of_file("/warehouse/t_7/part-00000.parquet"), None);
+        assert_eq!(bucket_id_of_file("/warehouse/t/part_x.parquet"), None);
+    }
+
+    #[test]
+    fn test_hive_bucket_ids() -> Result<()> {
+        // hive hash of int is the value itself, negative hashes are masked
+        let array: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), Some(9), None, Some(-1)]));
+        assert_eq!(hive_bucket_ids(&[array.clone()], 4)?, vec![1, 1, 0, 3]);
+
+        let bucket_spec = BucketSpec {
+            bucket_columns: vec![0],
+            num_buckets: 4,
+            sort_columns: vec![],
+            hash: BucketHash::HiveHash,
+        };
+        assert_eq!(bucket_spec.bucket_ids(&[array])?, vec![1, 1, 0, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_buckets() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]);
+        let num_buckets = 8;
+        let bucket_spec = BucketSpec {
+            bucket_columns: vec![0],
+            num_buckets,
+            sort_columns: vec![],
+            hash: BucketHash::Murmur3,
+        };
+        let bucket_id = |v: Option<i32>| -> Result<usize> {
+            let array: ArrayRef = Arc::new(Int32Array::from(vec![v]));
+            Ok(bucket_ids(&[array], num_buckets)?[0])
+        };
+        let matched = |ids: &[usize]| {
+            (0..num_buckets)
+                .map(|id| ids.contains(&id))
+                .collect::<Vec<_>>()
+        };
+
+        // a = 1
+        let pred = binary(col("a", &schema)?, Operator::Eq, lit(1i32), &schema)?;
+        assert_eq!(
+            prune_buckets(&pred, &bucket_spec, &schema)?,
+            Some(matched(&[bucket_id(Some(1))?]))
+        );
+
+        // a IN (1, 2) AND b = 3
+        let pred = binary(
+            in_list(
+                col("a", &schema)?,
+                vec![lit(1i32), lit(2i32)],
+                &false,
+                &schema,
+            )?,
+            Operator::And,
+            binary(col("b", &schema)?, Operator::Eq, lit(3i32), &schema)?,
+            &schema,
+        )?;
+        assert_eq!(
+            prune_buckets(&pred, &bucket_spec, &schema)?,
+            Some(matched(&[bucket_id(Some(1))?, bucket_id(Some(2))?]))
+        );
+
+        // a IS NULL OR a = 4
+        let pred = binary(
+            is_null(col("a", &schema)?)?,
+            Operator::Or,
+            binary(col("a", &schema)?, Operator::Eq, lit(4i32), &schema)?,
+            &schema,
+        )?;
+        assert_eq!(
+            prune_buckets(&pred, &bucket_spec, &schema)?,
+            Some(matched(&[bucket_id(None)?, bucket_id(Some(4))?]))
+        );
+
+        // a = 1 OR b = 3 cannot be pruned
+        let pred = binary(
+            binary(col("a", &schema)?, Operator::Eq, lit(1i32), &schema)?,
+            Operator::Or,
+            binary(col("b", &schema)?, Operator::Eq, lit(3i32), &schema)?,
+            &schema,
+        )?;
+        assert_eq!(prune_buckets(&pred, &bucket_spec, &schema)?, None);
+
+        // literals of other types are not pruned
+        let pred: Arc<dyn PhysicalExpr> = Arc::new(BinaryExpr::new(
+            col("a", &schema)?,
+            Operator::Eq,
+            lit(ScalarValue::Int64(Some(1))),
+        ));
+        assert_eq!(prune_buckets(&pred, &bucket_spec, &schema)?, None);
+        Ok(())
+    }
+}
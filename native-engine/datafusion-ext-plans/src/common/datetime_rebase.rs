@@ -0,0 +1,448 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rebasing of dates and timestamps between the proleptic gregorian calendar
+//! and the legacy hybrid (julian + gregorian) calendar used by spark 2.x and
+//! legacy hive, the same as spark's parquet datasource.
+
+use std::sync::Arc;
+
+use arrow::{
+    array::{as_primitive_array, make_array, Array, ArrayRef},
+    datatypes::{
+        DataType, Date32Type, TimeUnit, TimestampMicrosecondType, TimestampMillisecondType,
+        TimestampNanosecondType,
+    },
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use datafusion::{
+    common::{DataFusionError, Result},
+    parquet::format::KeyValue,
+};
+use datafusion_ext_commons::{
+    spark_datetime::{
+        convert_tz, rebase_gregorian_to_julian_days, rebase_gregorian_to_julian_micros,
+        rebase_julian_to_gregorian_days, rebase_julian_to_gregorian_micros, ZoneId,
+        GREGORIAN_SWITCH_DAY,
+    },
+    spark_errors::{read_ancient_datetime_error, write_ancient_datetime_error},
+};
+
+const SPARK_VERSION_METADATA_KEY: &str = "org.apache.spark.version";
+const SPARK_LEGACY_DATETIME_METADATA_KEY: &str = "org.apache.spark.legacyDateTime";
+const SPARK_LEGACY_INT96_METADATA_KEY: &str = "org.apache.spark.legacyINT96";
+const SPARK_TIMEZONE_METADATA_KEY: &str = "org.apache.spark.timeZone";
+
+// timestamps before 1900-01-01T00:00:00Z are ambiguous in EXCEPTION mode
+const ANCIENT_TIMESTAMP_MICROS: i64 = -2208988800000000;
+
+/// How ancient dates/timestamps are handled, the same as spark's
+/// `LegacyBehaviorPolicy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebaseMode {
+    /// fails on ancient values which are ambiguous between the calendars
+    Exception,
+    /// reads/writes values as it is
+    Corrected,
+    /// rebases values from/to the hybrid calendar
+    Legacy,
+}
+
+/// Rebase configs of parquet scans and sinks, taken from session confs.
+#[derive(Debug, Clone)]
+pub struct RebaseConf {
+    /// `spark.sql.parquet.datetimeRebaseModeInRead/Write`
+    pub datetime_rebase_mode: RebaseMode,
+    /// `spark.sql.parquet.int96RebaseModeInRead/Write`
+    pub int96_rebase_mode: RebaseMode,
+    /// session time zone, used by LEGACY rebasing and INT96 conversion
+    pub time_zone: String,
+    /// `spark.sql.parquet.int96TimestampConversion`, only used in reading
+    pub int96_timestamp_conversion: bool,
+    /// spark version written to file metadata, only used in writing
+    pub spark_version: String,
+}
+
+impl Default for RebaseConf {
+    fn default() -> Self {
+        Self {
+            datetime_rebase_mode: RebaseMode::Corrected,
+            int96_rebase_mode: RebaseMode::Corrected,
+            time_zone: "UTC".to_string(),
+            int96_timestamp_conversion: false,
+            spark_version: String::new(),
+        }
+    }
+}
+
+impl RebaseConf {
+    fn zone(&self) -> ZoneId {
+        ZoneId::parse(&self.time_zone).unwrap_or(ZoneId::UTC)
+    }
+
+    /// Rebase spec of reading a file. files written by spark carry their
+    /// rebase modes in the metadata, other files use the session confs.
+    pub fn read_spec(
+        &self,
+        key_value_metadata: Option<&Vec<KeyValue>>,
+        created_by: Option<&str>,
+    ) -> RebaseSpec {
+        let lookup = |key: &str| {
+            key_value_metadata
+                .into_iter()
+                .flatten()
+                .find(|kv| kv.key == key)
+                .map(|kv| kv.value.clone().unwrap_or_default())
+        };
+        let mode_of_version = |version: &str, legacy_key: &str, since_version: &str| {
+            // versions are compared as strings like spark
+            if version < since_version || lookup(legacy_key).is_some() {
+                RebaseMode::Legacy
+            } else {
+                RebaseMode::Corrected
+            }
+        };
+        let (datetime_mode, int96_mode) = match lookup(SPARK_VERSION_METADATA_KEY) {
+            Some(version) => (
+                mode_of_version(&version, SPARK_LEGACY_DATETIME_METADATA_KEY, "3.0.0"),
+                mode_of_version(&version, SPARK_LEGACY_INT96_METADATA_KEY, "3.1.0"),
+            ),
+            None => (self.datetime_rebase_mode, self.int96_rebase_mode),
+        };
+
+        // INT96 timestamps written by impala are stored in local time
+        let int96_convert_zone = (self.int96_timestamp_conversion
+            && !created_by.unwrap_or_default().starts_with("parquet-mr"))
+        .then(|| self.zone());
+
+        RebaseSpec {
+            datetime_mode,
+            int96_mode,
+            zone: lookup(SPARK_TIMEZONE_METADATA_KEY)
+                .and_then(|time_zone| ZoneId::parse(&time_zone))
+                .unwrap_or_else(|| self.zone()),
+            int96_convert_zone,
+            writing: false,
+        }
+    }
+
+    /// Rebase spec of writing files.
+    pub fn write_spec(&self) -> RebaseSpec {
+        RebaseSpec {
+            datetime_mode: self.datetime_rebase_mode,
+            int96_mode: self.int96_rebase_mode,
+            zone: self.zone(),
+            int96_convert_zone: None,
+            writing: true,
+        }
+    }
+
+    /// Metadata written to files, so that readers detect the rebase modes of
+    /// the files like files written by spark.
+    pub fn write_metadata(&self) -> Vec<KeyValue> {
+        if self.spark_version.is_empty() {
+            return vec![];
+        }
+        let mut metadata = vec![KeyValue::new(
+            SPARK_VERSION_METADATA_KEY.to_string(),
+            self.spark_version.clone(),
+        )];
+        if self.datetime_rebase_mode == RebaseMode::Legacy {
+            metadata.push(KeyValue::new(
+                SPARK_LEGACY_DATETIME_METADATA_KEY.to_string(),
+                String::new(),
+            ));
+        }
+        if self.int96_rebase_mode == RebaseMode::Legacy {
+            metadata.push(KeyValue::new(
+                SPARK_LEGACY_INT96_METADATA_KEY.to_string(),
+                String::new(),
+            ));
+        }
+        if metadata.len() > 1 {
+            metadata.push(KeyValue::new(
+                SPARK_TIMEZONE_METADATA_KEY.to_string(),
+                self.time_zone.clone(),
+            ));
+        }
+        metadata
+    }
+}
+
+/// Resolved rebase modes of reading/writing a file. INT96 timestamps, which
+/// are read as nanosecond timestamps, use the INT96 rebase mode, other dates
+/// and timestamps use the datetime rebase mode.
+#[derive(Debug, Clone)]
+pub struct RebaseSpec {
+    datetime_mode: RebaseMode,
+    int96_mode: RebaseMode,
+    zone: ZoneId,
+    int96_convert_zone: Option<ZoneId>,
+    writing: bool,
+}
+
+impl RebaseSpec {
+    pub fn datetime_mode(&self) -> RebaseMode {
+        self.datetime_mode
+    }
+
+    /// Returns true if values are never changed or checked.
+    pub fn is_noop(&self) -> bool {
+        self.datetime_mode == RebaseMode::Corrected
+            && self.int96_mode == RebaseMode::Corrected
+            && self.int96_convert_zone.is_none()
+    }
+
+    /// Rebases dates and timestamps in all columns of the batch.
+    pub fn rebase_batch(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        if self.is_noop() {
+            return Ok(batch.clone());
+        }
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| self.rebase(column))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(RecordBatch::try_new_with_options(
+            batch.schema(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+        )?)
+    }
+
+    /// Rebases dates and timestamps in the array, including the ones inside
+    /// nested types.
+    pub fn rebase(&self, array: &ArrayRef) -> Result<ArrayRef> {
+        if self.is_noop() || !has_datetime(array.data_type()) {
+            return Ok(array.clone());
+        }
+        match array.data_type() {
+            DataType::Date32 => self.rebase_dates(array),
+            DataType::Timestamp(TimeUnit::Second, _) => Ok(array.clone()),
+            DataType::Timestamp(unit, _) => self.rebase_timestamps(array, *unit),
+            DataType::Struct(_)
+            | DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::Map(..) => {
+                let data = array.to_data();
+                let child_data = data
+                    .child_data()
+                    .iter()
+                    .map(|child| Ok(self.rebase(&make_array(child.clone()))?.to_data()))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(make_array(
+                    data.into_builder().child_data(child_data).build()?,
+                ))
+            }
+            _ => Ok(array.clone()),
+        }
+    }
+
+    fn rebase_dates(&self, array: &ArrayRef) -> Result<ArrayRef> {
+        let days = as_primitive_array::<Date32Type>(array);
+        match self.datetime_mode {
+            RebaseMode::Corrected => Ok(array.clone()),
+            RebaseMode::Exception => {
+                if days.iter().flatten().any(|day| day < GREGORIAN_SWITCH_DAY) {
+                    return Err(self.ancient_datetime_error("datetime"));
+                }
+                Ok(array.clone())
+            }
+            RebaseMode::Legacy if self.writing => Ok(Arc::new(
+                days.unary::<_, Date32Type>(rebase_gregorian_to_julian_days),
+            )),
+            RebaseMode::Legacy => Ok(Arc::new(
+                days.unary::<_, Date32Type>(rebase_julian_to_gregorian_days),
+            )),
+        }
+    }
+
+    fn rebase_timestamps(&self, array: &ArrayRef, unit: TimeUnit) -> Result<ArrayRef> {
+        let (mode, config_prefix, convert_zone) = match unit {
+            TimeUnit::Nanosecond => (self.int96_mode, "int96", self.int96_convert_zone),
+            _ => (self.datetime_mode, "datetime", None),
+        };
+        if mode == RebaseMode::Corrected && convert_zone.is_none() {
+            return Ok(array.clone());
+        }
+
+        // values are rebased in microseconds, sub-microsecond parts are kept
+        let rebase_micros = |micros: i64| -> Result<i64> {
+            let rebased = match mode {
+                RebaseMode::Exception if micros < ANCIENT_TIMESTAMP_MICROS => {
+                    return Err(self.ancient_datetime_error(config_prefix));
+                }
+                RebaseMode::Corrected | RebaseMode::Exception => micros,
+                RebaseMode::Legacy if self.writing => {
+                    rebase_gregorian_to_julian_micros(micros, self.zone)
+                }
+                RebaseMode::Legacy => rebase_julian_to_gregorian_micros(micros, self.zone),
+            };
+            Ok(match convert_zone {
+                Some(zone) => convert_tz(rebased, zone, ZoneId::UTC).unwrap_or(rebased),
+                None => rebased,
+            })
+        };
+        Ok(match unit {
+            TimeUnit::Millisecond => {
+                let values = as_primitive_array::<TimestampMillisecondType>(array);
+                let rebased = values.try_unary::<_, TimestampMillisecondType, _>(|v| {
+                    Ok::<_, DataFusionError>(
+                        rebase_micros(v.saturating_mul(1000))?.div_euclid(1000),
+                    )
+                })?;
+                Arc::new(rebased.with_timezone_opt(values.timezone()))
+            }
+            TimeUnit::Microsecond => {
+                let values = as_primitive_array::<TimestampMicrosecondType>(array);
+                let rebased = values.try_unary::<_, TimestampMicrosecondType, _>(rebase_micros)?;
+                Arc::new(rebased.with_timezone_opt(values.timezone()))
+            }
+            TimeUnit::Nanosecond => {
+                let values = as_primitive_array::<TimestampNanosecondType>(array);
+                let rebased = values.try_unary::<_, TimestampNanosecondType, _>(|v| {
+                    let micros = rebase_micros(v.div_euclid(1000))?;
+                    Ok::<_, DataFusionError>(micros * 1000 + v.rem_euclid(1000))
+                })?;
+                Arc::new(rebased.with_timezone_opt(values.timezone()))
+            }
+            TimeUnit::Second => array.clone(),
+        })
+    }
+
+    fn ancient_datetime_error(&self, config_prefix: &str) -> DataFusionError {
+        if self.writing {
+            let config = format!("spark.sql.parquet.{config_prefix}RebaseModeInWrite");
+            write_ancient_datetime_error("Parquet", &config)
+        } else {
+            let config = format!("spark.sql.parquet.{config_prefix}RebaseModeInRead");
+            read_ancient_datetime_error("Parquet", &config)
+        }
+    }
+}
+
+/// Returns true if the data type is or contains dates/timestamps.
+pub fn has_datetime(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Date32 | DataType::Timestamp(..) => true,
+        DataType::Struct(fields) => fields.iter().any(|f| has_datetime(f.data_type())),
+        DataType::List(field) | DataType::LargeList(field) | DataType::Map(field, _) => {
+            has_datetime(field.data_type())
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, Date32Array, StructArray, TimestampMicrosecondArray},
+        datatypes::{DataType, Field},
+    };
+    use datafusion::{common::Result, parquet::format::KeyValue};
+    use datafusion_ext_commons::spark_datetime::{string_to_date, string_to_timestamp, ZoneId};
+
+    use crate::common::datetime_rebase::{RebaseConf, RebaseMode};
+
+    #[test]
+    fn test_read_spec() {
+        let conf = RebaseConf {
+            datetime_rebase_mode: RebaseMode::Exception,
+            int96_rebase_mode: RebaseMode::Exception,
+            ..RebaseConf::default()
+        };
+        let kv = |key: &str| KeyValue::new(key.to_string(), "".to_string());
+        let version = |version: &str| {
+            KeyValue::new("org.apache.spark.version".to_string(), version.to_string())
+        };
+
+        let spec = conf.read_spec(None, Some("impala"));
+        assert_eq!(spec.datetime_mode, RebaseMode::Exception);
+        assert_eq!(spec.int96_mode, RebaseMode::Exception);
+
+        let spec = conf.read_spec(Some(&vec![version("2.4.5")]), None);
+        assert_eq!(spec.datetime_mode, RebaseMode::Legacy);
+        assert_eq!(spec.int96_mode, RebaseMode::Legacy);
+
+        let spec = conf.read_spec(Some(&vec![version("3.0.1")]), None);
+        assert_eq!(spec.datetime_mode, RebaseMode::Corrected);
+        assert_eq!(spec.int96_mode, RebaseMode::Legacy);
+
+        let metadata = vec![version("3.3.0"), kv("org.apache.spark.legacyDateTime")];
+        let spec = conf.read_spec(Some(&metadata), None);
+        assert_eq!(spec.datetime_mode, RebaseMode::Legacy);
+        assert_eq!(spec.int96_mode, RebaseMode::Corrected);
+    }
+
+    #[test]
+    fn test_rebase() -> Result<()> {
+        let zone = ZoneId::UTC;
+        let date = |s| string_to_date(s).unwrap();
+        let timestamp = |s| string_to_timestamp(s, zone).unwrap();
+        let dates: ArrayRef = Arc::new(Date32Array::from(vec![
+            Some(date("1000-02-01")),
+            None,
+            Some(date("2020-01-02")),
+        ]));
+        let timestamps: ArrayRef = Arc::new(TimestampMicrosecondArray::from(vec![
+            Some(timestamp("1000-02-01 01:02:03")),
+            None,
+            Some(timestamp("2020-01-02 03:04:05")),
+        ]));
+        let array: ArrayRef = Arc::new(StructArray::from(vec![
+            (
+                Arc::new(Field::new("d", DataType::Date32, true)),
+                dates.clone(),
+            ),
+            (
+                Arc::new(Field::new("t", timestamps.data_type().clone(), true)),
+                timestamps.clone(),
+            ),
+        ]));
+
+        // values written in LEGACY mode are restored in reading
+        let legacy_conf = RebaseConf {
+            datetime_rebase_mode: RebaseMode::Legacy,
+            spark_version: "3.3.0".to_string(),
+            ..RebaseConf::default()
+        };
+        let written = legacy_conf.write_spec().rebase(&array)?;
+        assert_ne!(&written, &array);
+        let metadata = legacy_conf.write_metadata();
+        let read = RebaseConf::default()
+            .read_spec(Some(&metadata), None)
+            .rebase(&written)?;
+        assert_eq!(&read, &array);
+
+        let exception_conf = RebaseConf {
+            datetime_rebase_mode: RebaseMode::Exception,
+            ..RebaseConf::default()
+        };
+        let err = exception_conf.read_spec(None, None).rebase(&dates);
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("READ_ANCIENT_DATETIME"));
+        let err = exception_conf.write_spec().rebase(&timestamps);
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("WRITE_ANCIENT_DATETIME"));
+        let recent = exception_conf.write_spec().rebase(&dates.slice(1, 2))?;
+        assert_eq!(&recent, &dates.slice(1, 2));
+        Ok(())
+    }
+}
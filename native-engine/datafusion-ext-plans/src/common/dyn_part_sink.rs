@@ -0,0 +1,1074 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Common driver of the file sinks writing hive tables. Input batches are
+//! split by the values of the trailing dynamic partition columns (and by the
+//! buckets of bucketed tables), each partition is written into its own files
+//! by a format specific `PartWriter`.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Write,
+    sync::{Arc, Weak},
+};
+
+use arrow::{
+    array::{BinaryBuilder, Int64Builder, ListBuilder, StringBuilder},
+    compute::{concat_batches, lexsort_to_indices, SortColumn, SortOptions},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    record_batch::{RecordBatch, RecordBatchOptions},
+    row::{RowConverter, SortField},
+};
+use async_trait::async_trait;
+use blaze_jni_bridge::{
+    conf,
+    conf::{IntConf, LongConf},
+    jni_call_static, jni_get_string, jni_new_string,
+};
+use datafusion::{
+    common::{DataFusionError, Result, ScalarValue},
+    execution::context::TaskContext,
+    physical_plan::{
+        metrics::{BaselineMetrics, Count},
+        SendableRecordBatchStream,
+    },
+};
+use datafusion_ext_commons::{
+    cast::cast,
+    df_execution_err,
+    hadoop_fs::{FsDataOutputStream, FsProvider},
+};
+use futures::StreamExt;
+use parking_lot::Mutex;
+
+use crate::{
+    common::{bucketing::BucketSpec, output::TaskOutputter, BatchTaker},
+    memmgr::{MemConsumer, MemConsumerInfo, MemManager},
+};
+
+/// writer of an output file of a single partition
+pub trait PartWriter: Sized + Send + 'static {
+    type SinkContext: Send + Sync + 'static;
+
+    /// name of the sink, used in the output stream and error messages
+    const SINK_NAME: &'static str;
+
+    /// file extension used when the sink names output files by itself
+    const FILE_EXTENSION: &'static str;
+
+    fn try_new(
+        sink_context: Arc<Self::SinkContext>,
+        part_values: &[ScalarValue],
+        path: String,
+    ) -> Result<Self>;
+    fn part_values(&self) -> &[ScalarValue];
+    fn write(&mut self, batch: &RecordBatch) -> Result<()>;
+    fn close(self) -> Result<PartFileStat>;
+
+    /// number of rows written into the current file
+    fn num_rows(&self) -> usize;
+
+    /// estimated size of the current file, including buffered data
+    fn file_size(&self) -> usize;
+
+    /// memory used by buffered data
+    fn mem_size(&self) -> usize;
+}
+
+pub struct PartFileStat {
+    pub path: String,
+    pub part_values: Vec<ScalarValue>,
+    pub num_rows: usize,
+    pub num_bytes: usize,
+    pub column_stats: Vec<ColumnFileStat>,
+}
+
+/// statistics of a leaf column in a written file. min/max values are encoded
+/// in the plain encoding of the file format, and are absent if unknown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnFileStat {
+    pub name: String,
+    pub null_count: Option<i64>,
+    pub min_value: Option<Vec<u8>>,
+    pub max_value: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum DynPartSinkOutput {
+    /// sends an identity batch to the jvm side before creating each file, and
+    /// gets the file path from it. used by hive table writing.
+    Identity,
+
+    /// creates files under the given directory with hive-style partition
+    /// sub-directories, and outputs a batch of file stats (see
+    /// `file_stats_schema`) after files are closed.
+    FileStats(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DynPartSinkOptions {
+    /// max number of concurrently open part writers. input must be sorted by
+    /// the dynamic partition columns if only one writer is allowed
+    pub max_open_writers: usize,
+
+    /// rolls to a new file when the number of rows reaches this limit, 0
+    /// means unlimited
+    pub max_records_per_file: usize,
+
+    /// rolls to a new file when the file size reaches this limit, 0 means
+    /// unlimited
+    pub target_file_size: usize,
+}
+
+impl DynPartSinkOptions {
+    pub fn try_from_conf() -> Result<Self> {
+        Ok(Self {
+            max_open_writers: conf::SINK_MAX_OPEN_WRITERS.value()?.max(1) as usize,
+            max_records_per_file: conf::SINK_MAX_RECORDS_PER_FILE.value()?.max(0) as usize,
+            target_file_size: conf::SINK_TARGET_FILE_SIZE.value()?.max(0) as usize,
+        })
+    }
+}
+
+/// writes input batches into part files. every dynamic partition (and bucket
+/// if `bucket_spec` is given) is written by a writer in the writer pool, and
+/// the output files are reported to the jvm side or in the output stream,
+/// depending on `output`. with the identity output, the bucket id is passed to
+/// the jvm side with the identity batch, which names the file as a bucket
+/// file. a partition may be written into more than one files if its writer is
+/// closed by rolling, or evicted from the pool before all its rows arrive.
+/// files of sorted buckets are written by `SortedPartWriter`.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_dyn_part_sink<W: PartWriter>(
+    context: Arc<TaskContext>,
+    sink_context: Arc<W::SinkContext>,
+    hive_schema: SchemaRef,
+    num_dyn_parts: usize,
+    bucket_spec: Option<BucketSpec>,
+    output: DynPartSinkOutput,
+    mut input: SendableRecordBatchStream,
+    metrics: BaselineMetrics,
+    bytes_written: Count,
+) -> Result<SendableRecordBatchStream> {
+    let desc = W::SINK_NAME;
+    let input_schema = input.schema();
+    let output_schema = match &output {
+        DynPartSinkOutput::Identity => input_schema.clone(),
+        DynPartSinkOutput::FileStats(_) => file_stats_schema(),
+    };
+    let options = DynPartSinkOptions::try_from_conf()?;
+    let pool = Arc::new(PartWriterPool::<W> {
+        name: format!("{desc}PartWriterPool"),
+        mem_consumer_info: None,
+        writers: Mutex::default(),
+        output: output.clone(),
+        closed_files: Mutex::default(),
+        metrics: metrics.clone(),
+        bytes_written,
+    });
+    MemManager::register_consumer(pool.clone(), true);
+
+    context.output_with_sender(desc, output_schema, move |sender| async move {
+        macro_rules! part_writer_init {
+            ($batch:expr, $part_values:expr, $bucket_id:expr) => {{
+                let sink_context_cloned = sink_context.clone();
+                let part_values = $part_values.to_vec();
+                let bucket_id: Option<usize> = $bucket_id;
+                let path = match &output {
+                    DynPartSinkOutput::Identity => {
+                        // send identity batch, after that we can achieve a new output file
+                        sender.send(Ok($batch.slice(0, 1)), None).await;
+                        None
+                    }
+                    DynPartSinkOutput::FileStats(output_dir) => {
+                        let part_names = input_schema
+                            .fields()
+                            .iter()
+                            .skip(input_schema.fields().len() - num_dyn_parts)
+                            .map(|field| field.name().as_str())
+                            .collect::<Vec<_>>();
+                        // bucket files are suffixed with bucket ids like `_00001`
+                        Some(format!(
+                            "{}/{}part-{}{}{}",
+                            output_dir.trim_end_matches('/'),
+                            hive_part_dir(&part_names, &part_values),
+                            uuid::Uuid::new_v4(),
+                            bucket_id
+                                .map(|bucket_id| format!("_{bucket_id:05}"))
+                                .unwrap_or_default(),
+                            W::FILE_EXTENSION,
+                        ))
+                    }
+                };
+                tokio::task::spawn_blocking(move || {
+                    let path = match path {
+                        Some(path) => path,
+                        None => get_task_output_path(bucket_id)?,
+                    };
+                    W::try_new(sink_context_cloned, &part_values, path)
+                })
+                .await
+                .or_else(|e| df_execution_err!("{desc}: creating file error: {e}"))??
+            }};
+        }
+        macro_rules! output_file_stats {
+            () => {{
+                let closed_files = std::mem::take(&mut *pool.closed_files.lock());
+                if !closed_files.is_empty() {
+                    sender
+                        .send(Ok(file_stats_batch(&closed_files)?), None)
+                        .await;
+                }
+            }};
+        }
+
+        while let Some(batch) = input.next().await.transpose()? {
+            let _timer = metrics.elapsed_compute().timer();
+            if batch.num_rows() == 0 {
+                continue;
+            }
+
+            let parts = split_by_dyn_parts(&batch, num_dyn_parts)?;
+            for (part_values, part_batch) in parts {
+                for (bucket_id, mut part_batch) in split_by_buckets(&part_batch, &bucket_spec)? {
+                    let mut maybe_writer = pool.take(&part_values, bucket_id);
+                    while part_batch.num_rows() > 0 {
+                        let mut w = match maybe_writer.take() {
+                            Some(w) => w,
+                            None => {
+                                while pool.len() >= options.max_open_writers {
+                                    pool.close_lru().await?;
+                                }
+                                part_writer_init!(part_batch, &part_values, bucket_id)
+                            }
+                        };
+
+                        // write rows until the current file reaches max records
+                        let num_rows = match options.max_records_per_file {
+                            0 => part_batch.num_rows(),
+                            max_records => part_batch.num_rows().min(max_records - w.num_rows()),
+                        };
+                        let cur_batch = adapt_schema(&part_batch.slice(0, num_rows), &hive_schema)?;
+                        part_batch = part_batch.slice(num_rows, part_batch.num_rows() - num_rows);
+                        w = tokio::task::spawn_blocking(move || {
+                            w.write(&cur_batch)?;
+                            Ok::<_, DataFusionError>(w)
+                        })
+                        .await
+                        .or_else(|e| df_execution_err!("{desc}: writing file error: {e}"))??;
+
+                        // roll to a new file
+                        let rolling = (options.max_records_per_file > 0
+                            && w.num_rows() >= options.max_records_per_file)
+                            || (options.target_file_size > 0
+                                && w.file_size() >= options.target_file_size);
+                        if rolling {
+                            pool.close_writer(w).await?;
+                        } else {
+                            maybe_writer = Some(w);
+                        }
+                    }
+                    if let Some(w) = maybe_writer {
+                        pool.put(bucket_id, w);
+                    }
+                }
+                pool.update_mem_used(pool.mem_size()).await?;
+            }
+            output_file_stats!();
+        }
+        while pool.len() > 0 {
+            pool.close_lru().await?;
+        }
+        pool.update_mem_used(0).await?;
+        output_file_stats!();
+        Ok(())
+    })
+}
+
+/// open part writers of a task, ordered from the least recently used one.
+/// writers are closed in this order when the pool is full, or when the memory
+/// manager requests spilling.
+struct PartWriterPool<W: PartWriter> {
+    name: String,
+    mem_consumer_info: Option<Weak<MemConsumerInfo>>,
+    writers: Mutex<VecDeque<(Option<usize>, W)>>,
+    output: DynPartSinkOutput,
+    closed_files: Mutex<Vec<PartFileStat>>,
+    metrics: BaselineMetrics,
+    bytes_written: Count,
+}
+
+impl<W: PartWriter> PartWriterPool<W> {
+    fn len(&self) -> usize {
+        self.writers.lock().len()
+    }
+
+    fn mem_size(&self) -> usize {
+        self.writers.lock().iter().map(|(_, w)| w.mem_size()).sum()
+    }
+
+    fn take(&self, part_values: &[ScalarValue], bucket_id: Option<usize>) -> Option<W> {
+        let mut writers = self.writers.lock();
+        let idx = writers
+            .iter()
+            .position(|(b, w)| *b == bucket_id && w.part_values() == part_values)?;
+        writers.remove(idx).map(|(_, w)| w)
+    }
+
+    fn put(&self, bucket_id: Option<usize>, w: W) {
+        self.writers.lock().push_back((bucket_id, w));
+    }
+
+    async fn close_lru(&self) -> Result<()> {
+        let maybe_writer = self.writers.lock().pop_front();
+        if let Some((_, w)) = maybe_writer {
+            self.close_writer(w).await?;
+        }
+        Ok(())
+    }
+
+    async fn close_writer(&self, w: W) -> Result<()> {
+        let desc = W::SINK_NAME;
+        let file_stat = tokio::task::spawn_blocking(move || w.close())
+            .await
+            .or_else(|e| df_execution_err!("{desc}: closing file error: {e}"))??;
+
+        self.metrics.output_rows().add(file_stat.num_rows);
+        self.bytes_written.add(file_stat.num_bytes);
+        match &self.output {
+            DynPartSinkOutput::Identity => {
+                jni_call_static!(
+                    BlazeNativeParquetSinkUtils.completeOutput(
+                        jni_new_string!(&file_stat.path)?.as_obj(),
+                        file_stat.num_rows as i64,
+                        file_stat.num_bytes as i64,
+                    ) -> ()
+                )?;
+            }
+            DynPartSinkOutput::FileStats(_) => {
+                self.closed_files.lock().push(file_stat);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<W: PartWriter> MemConsumer for PartWriterPool<W> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_consumer_info(&mut self, consumer_info: Weak<MemConsumerInfo>) {
+        self.mem_consumer_info = Some(consumer_info);
+    }
+
+    fn get_consumer_info(&self) -> &Weak<MemConsumerInfo> {
+        self.mem_consumer_info
+            .as_ref()
+            .expect("consumer info not set")
+    }
+
+    async fn spill(&self) -> Result<()> {
+        // recently used writers are likely to be written next, so only close the
+        // least recently used ones until memory drops below the target
+        let mem_sizes = self
+            .writers
+            .lock()
+            .iter()
+            .map(|(_, w)| w.mem_size())
+            .collect::<Vec<_>>();
+        for _ in 0..num_writers_to_spill(&mem_sizes, self.mem_target()) {
+            self.close_lru().await?;
+        }
+        self.update_mem_used(self.mem_size()).await?;
+        Ok(())
+    }
+}
+
+/// gets the number of the least recently used writers to close in spilling, so
+/// that memory used by the remaining writers drops to the target. at least one
+/// writer is closed when there is any.
+fn num_writers_to_spill(mem_sizes: &[usize], target: usize) -> usize {
+    let mut remaining = mem_sizes.iter().sum::<usize>();
+    let mut num_writers = 0;
+    for &mem_size in mem_sizes {
+        if num_writers > 0 && remaining <= target {
+            break;
+        }
+        remaining -= mem_size;
+        num_writers += 1;
+    }
+    num_writers
+}
+
+impl<W: PartWriter> Drop for PartWriterPool<W> {
+    fn drop(&mut self) {
+        MemManager::deregister_consumer(self);
+    }
+}
+
+/// gets the path of the next output file from the jvm side, which is available
+/// after an identity batch is sent. files of buckets are named with the bucket
+/// id by the jvm side
+fn get_task_output_path(bucket_id: Option<usize>) -> Result<String> {
+    let bucket_id = bucket_id.map(|bucket_id| bucket_id as i32).unwrap_or(-1);
+    jni_get_string!(
+        jni_call_static!(BlazeNativeParquetSinkUtils.getTaskOutputPath(bucket_id) -> JObject)?
+            .as_obj()
+            .into()
+    )
+}
+
+/// creates an output file with the given path
+pub fn create_part_file(
+    fs_provider: &FsProvider,
+    path: &str,
+    bytes_written: &Count,
+) -> Result<FSDataWriter> {
+    log::info!("start writing file: {path}");
+    let fs = fs_provider.provide(path)?;
+    let fout = fs.create(path)?;
+    Ok(FSDataWriter::new(fout, bytes_written))
+}
+
+/// schema of the file stats output, with one row for each written file.
+/// column stats are listed in the order of leaf columns.
+pub fn file_stats_schema() -> SchemaRef {
+    let list_of = |data_type| DataType::List(Arc::new(Field::new("item", data_type, true)));
+    Arc::new(Schema::new(vec![
+        Field::new("path", DataType::Utf8, false),
+        Field::new("partition_values", list_of(DataType::Utf8), false),
+        Field::new("num_rows", DataType::Int64, false),
+        Field::new("num_bytes", DataType::Int64, false),
+        Field::new("column_names", list_of(DataType::Utf8), false),
+        Field::new("column_null_counts", list_of(DataType::Int64), false),
+        Field::new("column_min_values", list_of(DataType::Binary), false),
+        Field::new("column_max_values", list_of(DataType::Binary), false),
+    ]))
+}
+
+fn file_stats_batch(file_stats: &[PartFileStat]) -> Result<RecordBatch> {
+    let mut paths = StringBuilder::new();
+    let mut part_values = ListBuilder::new(StringBuilder::new());
+    let mut num_rows = Int64Builder::new();
+    let mut num_bytes = Int64Builder::new();
+    let mut column_names = ListBuilder::new(StringBuilder::new());
+    let mut column_null_counts = ListBuilder::new(Int64Builder::new());
+    let mut column_min_values = ListBuilder::new(BinaryBuilder::new());
+    let mut column_max_values = ListBuilder::new(BinaryBuilder::new());
+
+    for file_stat in file_stats {
+        paths.append_value(&file_stat.path);
+        for value in &file_stat.part_values {
+            part_values.values().append_option(part_value_string(value));
+        }
+        part_values.append(true);
+        num_rows.append_value(file_stat.num_rows as i64);
+        num_bytes.append_value(file_stat.num_bytes as i64);
+
+        for column_stat in &file_stat.column_stats {
+            column_names.values().append_value(&column_stat.name);
+            column_null_counts
+                .values()
+                .append_option(column_stat.null_count);
+            column_min_values
+                .values()
+                .append_option(column_stat.min_value.as_ref());
+            column_max_values
+                .values()
+                .append_option(column_stat.max_value.as_ref());
+        }
+        column_names.append(true);
+        column_null_counts.append(true);
+        column_min_values.append(true);
+        column_max_values.append(true);
+    }
+
+    Ok(RecordBatch::try_new(
+        file_stats_schema(),
+        vec![
+            Arc::new(paths.finish()),
+            Arc::new(part_values.finish()),
+            Arc::new(num_rows.finish()),
+            Arc::new(num_bytes.finish()),
+            Arc::new(column_names.finish()),
+            Arc::new(column_null_counts.finish()),
+            Arc::new(column_min_values.finish()),
+            Arc::new(column_max_values.finish()),
+        ],
+    )?)
+}
+
+fn part_value_string(value: &ScalarValue) -> Option<String> {
+    match value {
+        _ if value.is_null() => None,
+        ScalarValue::Utf8(Some(s)) | ScalarValue::LargeUtf8(Some(s)) => Some(s.clone()),
+        _ => Some(value.to_string()),
+    }
+}
+
+/// builds hive-style partition directories like `a=1/b=x/`, escaping special
+/// characters in the same way as hive's `FileUtils.escapePathName`
+fn hive_part_dir(part_names: &[&str], part_values: &[ScalarValue]) -> String {
+    fn escape(s: &str) -> String {
+        let mut escaped = String::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '\u{01}'..='\u{1F}'
+                | '"'
+                | '#'
+                | '%'
+                | '\''
+                | '*'
+                | '/'
+                | ':'
+                | '='
+                | '?'
+                | '\\'
+                | '\u{7F}'
+                | '{'
+                | '['
+                | ']'
+                | '^' => escaped.push_str(&format!("%{:02X}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    let mut dir = String::new();
+    for (name, value) in part_names.iter().zip(part_values) {
+        let value = match part_value_string(value) {
+            Some(value) if !value.is_empty() => escape(&value),
+            _ => "__HIVE_DEFAULT_PARTITION__".to_string(),
+        };
+        dir.push_str(&format!("{}={}/", escape(name), value));
+    }
+    dir
+}
+
+fn adapt_schema(batch: &RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
+    let num_rows = batch.num_rows();
+    let mut casted_cols = vec![];
+
+    for (col_idx, casted_field) in schema.fields().iter().enumerate() {
+        casted_cols.push(cast(batch.column(col_idx), casted_field.data_type())?);
+    }
+    Ok(RecordBatch::try_new_with_options(
+        schema.clone(),
+        casted_cols,
+        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+    )?)
+}
+
+/// splits batch by the dynamic partition values, in the order of their first
+/// occurrences. rows of a partition are sliced instead of taken if they are
+/// contiguous, which is the case of sorted input.
+fn split_by_dyn_parts(
+    batch: &RecordBatch,
+    num_dyn_parts: usize,
+) -> Result<Vec<(Vec<ScalarValue>, RecordBatch)>> {
+    if num_dyn_parts == 0 {
+        return Ok(vec![(vec![], batch.clone())]);
+    }
+    let part_cols = &batch.columns()[batch.num_columns() - num_dyn_parts..];
+    let mut row_converter = RowConverter::new(
+        part_cols
+            .iter()
+            .map(|col| SortField::new(col.data_type().clone()))
+            .collect(),
+    )?;
+    let rows = row_converter.convert_columns(part_cols)?;
+
+    let mut part_idx_map = HashMap::new();
+    let mut part_indices: Vec<Vec<usize>> = vec![];
+    for (row_idx, row) in rows.iter().enumerate() {
+        let part_idx = *part_idx_map.entry(row).or_insert_with(|| {
+            part_indices.push(vec![]);
+            part_indices.len() - 1
+        });
+        part_indices[part_idx].push(row_idx);
+    }
+
+    part_indices
+        .into_iter()
+        .map(|indices| {
+            let first = indices[0];
+            let num_rows = indices.len();
+            let part_values = get_dyn_part_values(batch, num_dyn_parts, first)?;
+            let part_batch = if indices[num_rows - 1] - first + 1 == num_rows {
+                batch.slice(first, num_rows)
+            } else {
+                BatchTaker(batch).take(indices)?
+            };
+            Ok((part_values, part_batch))
+        })
+        .collect()
+}
+
+/// splits batch by the bucket ids of rows, in the order of bucket ids. the
+/// bucket ids are computed by the hash function of the bucket spec.
+fn split_by_buckets(
+    batch: &RecordBatch,
+    bucket_spec: &Option<BucketSpec>,
+) -> Result<Vec<(Option<usize>, RecordBatch)>> {
+    let bucket_spec = match bucket_spec {
+        Some(bucket_spec) => bucket_spec,
+        None => return Ok(vec![(None, batch.clone())]),
+    };
+    let bucket_cols = bucket_spec
+        .bucket_columns
+        .iter()
+        .map(|&idx| batch.column(idx).clone())
+        .collect::<Vec<_>>();
+
+    let mut bucket_indices: Vec<Vec<usize>> = vec![vec![]; bucket_spec.num_buckets];
+    for (row_idx, bucket_id) in bucket_spec
+        .bucket_ids(&bucket_cols)?
+        .into_iter()
+        .enumerate()
+    {
+        bucket_indices[bucket_id].push(row_idx);
+    }
+    bucket_indices
+        .into_iter()
+        .enumerate()
+        .filter(|(_, indices)| !indices.is_empty())
+        .map(|(bucket_id, indices)| {
+            let bucket_batch = if indices.len() == batch.num_rows() {
+                batch.clone()
+            } else {
+                BatchTaker(batch).take(indices)?
+            };
+            Ok((Some(bucket_id), bucket_batch))
+        })
+        .collect()
+}
+
+fn get_dyn_part_values(
+    batch: &RecordBatch,
+    num_dyn_parts: usize,
+    row_idx: usize,
+) -> Result<Vec<ScalarValue>> {
+    batch
+        .columns()
+        .iter()
+        .skip(batch.num_columns() - num_dyn_parts)
+        .map(|part_col| ScalarValue::try_from_array(part_col, row_idx))
+        .collect()
+}
+
+pub struct SortedSinkContext<W: PartWriter> {
+    pub inner: Arc<W::SinkContext>,
+    pub sort_columns: Vec<usize>,
+    pub batch_size: usize,
+}
+
+/// part writer keeping rows of each file sorted by the sort columns of
+/// buckets. rows are buffered in memory and written to the inner writer when
+/// the file is closed.
+pub struct SortedPartWriter<W: PartWriter> {
+    inner: W,
+    sink_context: Arc<SortedSinkContext<W>>,
+    buffered_batches: Vec<RecordBatch>,
+    num_buffered_rows: usize,
+    buffered_mem_size: usize,
+}
+
+impl<W: PartWriter> PartWriter for SortedPartWriter<W> {
+    type SinkContext = SortedSinkContext<W>;
+
+    const SINK_NAME: &'static str = W::SINK_NAME;
+    const FILE_EXTENSION: &'static str = W::FILE_EXTENSION;
+
+    fn try_new(
+        sink_context: Arc<Self::SinkContext>,
+        part_values: &[ScalarValue],
+        path: String,
+    ) -> Result<Self> {
+        Ok(Self {
+            inner: W::try_new(sink_context.inner.clone(), part_values, path)?,
+            sink_context,
+            buffered_batches: vec![],
+            num_buffered_rows: 0,
+            buffered_mem_size: 0,
+        })
+    }
+
+    fn part_values(&self) -> &[ScalarValue] {
+        self.inner.part_values()
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.num_buffered_rows += batch.num_rows();
+        self.buffered_mem_size += batch.get_array_memory_size();
+        self.buffered_batches.push(batch.clone());
+        Ok(())
+    }
+
+    fn close(mut self) -> Result<PartFileStat> {
+        if !self.buffered_batches.is_empty() {
+            let schema = self.buffered_batches[0].schema();
+            let batch = concat_batches(&schema, &self.buffered_batches)?;
+            self.buffered_batches.clear();
+
+            let sort_columns = self
+                .sink_context
+                .sort_columns
+                .iter()
+                .map(|&idx| SortColumn {
+                    values: batch.column(idx).clone(),
+                    options: Some(SortOptions {
+                        descending: false,
+                        nulls_first: true,
+                    }),
+                })
+                .collect::<Vec<_>>();
+            let indices = lexsort_to_indices(&sort_columns, None)?;
+            let sorted = BatchTaker(&batch).take(indices.values().iter().copied())?;
+
+            let batch_size = self.sink_context.batch_size.max(1);
+            let mut offset = 0;
+            while offset < sorted.num_rows() {
+                let num_rows = batch_size.min(sorted.num_rows() - offset);
+                self.inner.write(&sorted.slice(offset, num_rows))?;
+                offset += num_rows;
+            }
+        }
+        self.inner.close()
+    }
+
+    fn num_rows(&self) -> usize {
+        self.inner.num_rows() + self.num_buffered_rows
+    }
+
+    fn file_size(&self) -> usize {
+        self.inner.file_size() + self.buffered_mem_size
+    }
+
+    fn mem_size(&self) -> usize {
+        self.inner.mem_size() + self.buffered_mem_size
+    }
+}
+
+// Write wrapper for FSDataOutputStream
+pub struct FSDataWriter {
+    inner: FsDataOutputStream,
+    bytes_written: Count,
+}
+
+impl FSDataWriter {
+    pub fn new(inner: FsDataOutputStream, bytes_written: &Count) -> Self {
+        Self {
+            inner,
+            bytes_written: bytes_written.clone(),
+        }
+    }
+
+    pub fn close(self) -> Result<()> {
+        self.inner.close()
+    }
+}
+
+impl Write for FSDataWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner
+            .write_fully(&buf)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        self.bytes_written.add(buf.len());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::VecDeque, sync::Arc};
+
+    use arrow::{
+        array::{Array, ArrayRef, Int32Array, Int64Array, ListArray, StringArray},
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use datafusion::common::{Result, ScalarValue};
+
+    use crate::common::{
+        bucketing::{bucket_ids, BucketHash, BucketSpec},
+        dyn_part_sink::{
+            file_stats_batch, get_dyn_part_values, hive_part_dir, num_writers_to_spill,
+            split_by_buckets, split_by_dyn_parts, ColumnFileStat, PartFileStat,
+        },
+    };
+
+    #[test]
+    fn test_dyn_part_values() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("v", DataType::Int32, true),
+            Field::new("p", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])),
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("a"),
+                    None,
+                    Some("a"),
+                    None,
+                ])),
+            ],
+        )?;
+        assert_eq!(
+            get_dyn_part_values(&batch, 1, 0)?,
+            vec![ScalarValue::from("a")]
+        );
+        assert_eq!(
+            get_dyn_part_values(&batch, 1, 2)?,
+            vec![ScalarValue::Utf8(None)]
+        );
+        assert!(get_dyn_part_values(&batch, 0, 0)?.is_empty());
+
+        let parts = split_by_dyn_parts(&batch, 1)?;
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].0, vec![ScalarValue::from("a")]);
+        assert_eq!(
+            parts[0].1.column(0).as_ref(),
+            &Int32Array::from(vec![1, 2, 4]) as &dyn Array,
+        );
+        assert_eq!(parts[1].0, vec![ScalarValue::Utf8(None)]);
+        assert_eq!(
+            parts[1].1.column(0).as_ref(),
+            &Int32Array::from(vec![3, 5]) as &dyn Array,
+        );
+
+        // sorted rows are sliced
+        let sorted = batch.slice(0, 2);
+        let parts = split_by_dyn_parts(&sorted, 1)?;
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].1.num_rows(), 2);
+
+        let parts = split_by_dyn_parts(&batch, 0)?;
+        assert_eq!(parts.len(), 1);
+        assert!(parts[0].0.is_empty());
+        assert_eq!(parts[0].1.num_rows(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hive_part_dir() {
+        let part_values = [
+            ScalarValue::from("a/b=c"),
+            ScalarValue::Int32(Some(1)),
+            ScalarValue::Utf8(None),
+            ScalarValue::from(""),
+        ];
+        assert_eq!(
+            hive_part_dir(&["s", "i", "n", "e"], &part_values),
+            "s=a%2Fb%3Dc/i=1/n=__HIVE_DEFAULT_PARTITION__/e=__HIVE_DEFAULT_PARTITION__/",
+        );
+        assert_eq!(hive_part_dir(&[], &[]), "");
+    }
+
+    #[test]
+    fn test_file_stats_batch() -> Result<()> {
+        let file_stats = vec![PartFileStat {
+            path: "/tmp/p=1/part-0.parquet".to_string(),
+            part_values: vec![ScalarValue::Int32(Some(1))],
+            num_rows: 10,
+            num_bytes: 1000,
+            column_stats: vec![
+                ColumnFileStat {
+                    name: "a".to_string(),
+                    null_count: Some(2),
+                    min_value: Some(vec![1]),
+                    max_value: Some(vec![9]),
+                },
+                ColumnFileStat {
+                    name: "b".to_string(),
+                    null_count: None,
+                    min_value: None,
+                    max_value: None,
+                },
+            ],
+        }];
+        let batch = file_stats_batch(&file_stats)?;
+        assert_eq!(batch.num_rows(), 1);
+        assert_eq!(
+            batch.column(0).as_ref(),
+            &StringArray::from(vec!["/tmp/p=1/part-0.parquet"]) as &dyn Array,
+        );
+        assert_eq!(
+            batch.column(2).as_ref(),
+            &Int64Array::from(vec![10]) as &dyn Array,
+        );
+
+        let part_values = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        assert_eq!(
+            part_values.value(0).as_ref(),
+            &StringArray::from(vec!["1"]) as &dyn Array,
+        );
+        let null_counts = batch
+            .column(5)
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        assert_eq!(
+            null_counts.value(0).as_ref(),
+            &Int64Array::from(vec![Some(2), None]) as &dyn Array,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_by_buckets() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("v", DataType::Utf8, true),
+        ]));
+        let ids: ArrayRef = Arc::new(Int32Array::from(vec![
+            Some(1),
+            Some(2),
+            None,
+            Some(4),
+            Some(1),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                ids.clone(),
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d", "e"])),
+            ],
+        )?;
+
+        // without bucket spec, the batch is not split
+        let splits = split_by_buckets(&batch, &None)?;
+        assert_eq!(splits.len(), 1);
+        assert_eq!(splits[0].0, None);
+        assert_eq!(splits[0].1.num_rows(), 5);
+
+        // rows are bucketed in the same way as spark hash partitioning
+        let num_buckets = 3;
+        let bucket_spec = Some(BucketSpec {
+            bucket_columns: vec![0],
+            num_buckets,
+            sort_columns: vec![],
+            hash: BucketHash::Murmur3,
+        });
+        let expected_bucket_ids = bucket_ids(&[ids], num_buckets)?;
+
+        let splits = split_by_buckets(&batch, &bucket_spec)?;
+        let split_bucket_ids = splits
+            .iter()
+            .map(|(bucket_id, _)| bucket_id.unwrap())
+            .collect::<Vec<_>>();
+        assert!(split_bucket_ids.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            splits.iter().map(|(_, b)| b.num_rows()).sum::<usize>(),
+            batch.num_rows()
+        );
+        for (bucket_id, bucket_batch) in &splits {
+            let values = bucket_batch
+                .column(1)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            let expected_values = expected_bucket_ids
+                .iter()
+                .enumerate()
+                .filter(|&(_, &expected)| Some(expected) == *bucket_id)
+                .map(|(row_idx, _)| ["a", "b", "c", "d", "e"][row_idx])
+                .collect::<Vec<_>>();
+            assert_eq!(values.iter().flatten().collect::<Vec<_>>(), expected_values);
+        }
+
+        // rows of hive compatible buckets are bucketed by hive hash
+        let bucket_spec = Some(BucketSpec {
+            bucket_columns: vec![0],
+            num_buckets,
+            sort_columns: vec![],
+            hash: BucketHash::HiveHash,
+        });
+        let splits = split_by_buckets(&batch, &bucket_spec)?;
+        let split_values = splits
+            .iter()
+            .map(|(bucket_id, bucket_batch)| {
+                let values = bucket_batch
+                    .column(1)
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .unwrap();
+                (
+                    bucket_id.unwrap(),
+                    values.iter().flatten().collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            split_values,
+            vec![(0, vec!["c"]), (1, vec!["a", "d", "e"]), (2, vec!["b"])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_num_writers_to_spill() {
+        assert_eq!(num_writers_to_spill(&[], 10), 0);
+        assert_eq!(num_writers_to_spill(&[4, 4, 4], 100), 1);
+        assert_eq!(num_writers_to_spill(&[4, 4, 4], 8), 1);
+        assert_eq!(num_writers_to_spill(&[4, 4, 4], 7), 2);
+        assert_eq!(num_writers_to_spill(&[4, 4, 4], 0), 3);
+
+        // simulates a sink writing a sliding window of 3 partitions, with writers
+        // buffering at most 4 units of memory, and spilling above 12 units
+        let mut writers = VecDeque::<(usize, usize)>::new();
+        let mut num_files = 0;
+        let mut num_spills = 0;
+        for i in 0..1000 {
+            let part = (i % 3 + i / 30) % 16;
+            let mut w = match writers.iter().position(|&(p, _)| p == part) {
+                Some(idx) => writers.remove(idx).unwrap(),
+                None => {
+                    num_files += 1;
+                    (part, 0)
+                }
+            };
+            w.1 = (w.1 + 1).min(4);
+            writers.push_back(w);
+
+            let mem_sizes = writers
+                .iter()
+                .map(|&(_, mem_size)| mem_size)
+                .collect::<Vec<_>>();
+            if mem_sizes.iter().sum::<usize>() > 12 {
+                num_spills += 1;
+                for _ in 0..num_writers_to_spill(&mem_sizes, 12) {
+                    writers.pop_front();
+                }
+            }
+        }
+        // every spill only closes the writer of the partition that left the window,
+        // closing all writers but the latest one would create 102 files
+        assert_eq!(num_spills, 33);
+        assert_eq!(num_files, 36);
+    }
+}
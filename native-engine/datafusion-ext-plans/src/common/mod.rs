@@ -21,9 +21,16 @@ use arrow::{
 use datafusion::common::Result;
 
 pub mod batch_statisitcs;
+pub mod bucketing;
 pub mod cached_exprs_evaluator;
 pub mod column_pruning;
+pub mod datetime_rebase;
+pub mod dyn_part_sink;
 pub mod output;
+pub mod parquet_metadata_cache;
+pub mod row_deletes;
+pub mod schema_adapter;
+pub mod text_scan;
 
 pub struct BatchTaker<'a>(pub &'a RecordBatch);
 
@@ -32,7 +32,7 @@ use datafusion::{
 };
 use datafusion_ext_commons::{
     df_execution_err,
-    io::{read_one_batch, write_one_batch},
+    io::{read_one_batch, write_one_batch, IoCompressionCodec, IoCompressionSite},
 };
 use futures::{FutureExt, StreamExt};
 use once_cell::sync::OnceCell;
@@ -183,12 +183,13 @@ impl TaskOutputter for Arc<TaskContext> {
                 if MemManager::get().num_consumers() > 1 && mem_consumer.mem_used_percent() > 0.8 {
                     let spill = try_new_spill(&spill_metrics)?;
                     let mut spill_writer = spill.get_buf_writer();
+                    let codec = IoCompressionCodec::try_from_conf(IoCompressionSite::Spill)?;
 
                     // write all batches to spill
                     while let Some(batch) = stream.next().await.transpose()? {
                         let _timer = baseline_metrics.elapsed_compute().timer();
                         let mut buf = vec![];
-                        write_one_batch(&batch, &mut Cursor::new(&mut buf), true, None)?;
+                        write_one_batch(&batch, &mut Cursor::new(&mut buf), codec, None)?;
                         spill_writer.write_all(&buf)?;
                     }
                     let mut timer = baseline_metrics.elapsed_compute().timer();
@@ -197,8 +198,7 @@ impl TaskOutputter for Arc<TaskContext> {
 
                     // read all batches from spill and output
                     let mut spill_reader = spill.get_buf_reader();
-                    while let Some(batch) =
-                        read_one_batch(&mut spill_reader, Some(schema.clone()), true)?
+                    while let Some(batch) = read_one_batch(&mut spill_reader, Some(schema.clone()))?
                     {
                         sender.send(Ok(batch), Some(&mut timer)).await;
                     }
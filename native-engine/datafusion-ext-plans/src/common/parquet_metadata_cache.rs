@@ -0,0 +1,346 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Process-wide LRU cache of decoded parquet metadata, shared by all tasks in
+//! the executor.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem::size_of,
+    sync::{Arc, Weak},
+};
+
+use async_trait::async_trait;
+use blaze_jni_bridge::{conf, conf::LongConf};
+use datafusion::{
+    common::Result,
+    parquet::{
+        file::{
+            metadata::{ColumnChunkMetaData, ParquetMetaData, RowGroupMetaData},
+            page_index::index::Index,
+        },
+        format::PageLocation,
+        schema::types::ColumnDescriptor,
+    },
+};
+use object_store::ObjectMeta;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+
+use crate::memmgr::{MemConsumer, MemConsumerInfo, MemManager};
+
+static PARQUET_METADATA_CACHE: OnceCell<Option<Arc<ParquetMetadataCache>>> = OnceCell::new();
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ParquetMetadataCacheKey {
+    path: String,
+    size: usize,
+    last_modified_ns: i64,
+}
+
+impl ParquetMetadataCacheKey {
+    /// returns None if the last modified time is unknown, in which case a
+    /// rewritten file cannot be distinguished and should not be cached
+    pub fn try_new(meta: &ObjectMeta) -> Option<Self> {
+        let last_modified_ns = meta.last_modified.timestamp_nanos_opt()?;
+        if last_modified_ns == 0 {
+            return None;
+        }
+        Some(Self {
+            path: meta.location.to_string(),
+            size: meta.size,
+            last_modified_ns,
+        })
+    }
+}
+
+pub struct ParquetMetadataCache {
+    name: String,
+    mem_consumer_info: Option<Weak<MemConsumerInfo>>,
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<ParquetMetadataCacheKey, CacheEntry>,
+    lru: BTreeMap<u64, ParquetMetadataCacheKey>,
+    next_seq: u64,
+    mem_used: usize,
+}
+
+struct CacheEntry {
+    metadata: Arc<ParquetMetaData>,
+    mem_size: usize,
+    seq: u64,
+}
+
+impl ParquetMetadataCache {
+    /// returns the shared cache, or None if caching is disabled
+    pub fn get_or_init() -> Result<Option<Arc<Self>>> {
+        let cache = PARQUET_METADATA_CACHE.get_or_try_init(|| -> Result<_> {
+            let capacity = conf::PARQUET_METADATA_CACHE_SIZE.value()?.max(0) as usize;
+            if capacity == 0 {
+                return Ok(None);
+            }
+            let cache = Arc::new(Self::new(capacity));
+            MemManager::register_consumer(cache.clone(), false);
+            Ok(Some(cache))
+        })?;
+        Ok(cache.clone())
+    }
+
+    fn new(capacity: usize) -> Self {
+        Self {
+            name: "ParquetMetadataCache".to_string(),
+            mem_consumer_info: None,
+            capacity,
+            inner: Mutex::default(),
+        }
+    }
+
+    pub fn get(&self, key: &ParquetMetadataCacheKey) -> Option<Arc<ParquetMetaData>> {
+        let mut inner = self.inner.lock();
+        let seq = inner.next_seq;
+        let entry = inner.entries.get_mut(key)?;
+        let old_seq = std::mem::replace(&mut entry.seq, seq);
+        let metadata = entry.metadata.clone();
+
+        let key = inner.lru.remove(&old_seq).expect("missing lru entry");
+        inner.lru.insert(seq, key);
+        inner.next_seq += 1;
+        Some(metadata)
+    }
+
+    pub async fn put(
+        &self,
+        key: ParquetMetadataCacheKey,
+        metadata: Arc<ParquetMetaData>,
+    ) -> Result<()> {
+        let mem_size = estimate_metadata_mem_size(&metadata);
+        let diff_used = self.insert(key, metadata, mem_size);
+        if diff_used != 0 {
+            self.update_mem_used_with_diff(diff_used).await?;
+        }
+        Ok(())
+    }
+
+    // inserts an entry and evicts least recently used entries exceeding the
+    // capacity, returns the difference of memory usage
+    fn insert(
+        &self,
+        key: ParquetMetadataCacheKey,
+        metadata: Arc<ParquetMetaData>,
+        mem_size: usize,
+    ) -> isize {
+        if mem_size > self.capacity {
+            return 0;
+        }
+        let mut inner = self.inner.lock();
+        let old_used = inner.mem_used;
+        let seq = inner.next_seq;
+        inner.next_seq += 1;
+
+        let entry = CacheEntry {
+            metadata,
+            mem_size,
+            seq,
+        };
+        if let Some(old_entry) = inner.entries.insert(key.clone(), entry) {
+            inner.lru.remove(&old_entry.seq);
+            inner.mem_used -= old_entry.mem_size;
+        }
+        inner.lru.insert(seq, key);
+        inner.mem_used += mem_size;
+
+        while inner.mem_used > self.capacity {
+            let (_, evicted_key) = inner.lru.pop_first().expect("missing lru entry");
+            let evicted = inner
+                .entries
+                .remove(&evicted_key)
+                .expect("missing cache entry");
+            inner.mem_used -= evicted.mem_size;
+        }
+        inner.mem_used as isize - old_used as isize
+    }
+
+    fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner.entries.clear();
+        inner.lru.clear();
+        inner.mem_used = 0;
+    }
+}
+
+#[async_trait]
+impl MemConsumer for ParquetMetadataCache {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_consumer_info(&mut self, consumer_info: Weak<MemConsumerInfo>) {
+        self.mem_consumer_info = Some(consumer_info);
+    }
+
+    fn get_consumer_info(&self) -> &Weak<MemConsumerInfo> {
+        self.mem_consumer_info
+            .as_ref()
+            .expect("consumer info not set")
+    }
+
+    async fn spill(&self) -> Result<()> {
+        self.clear();
+        self.update_mem_used(0).await?;
+        Ok(())
+    }
+}
+
+// a rough estimation of heap memory used by decoded metadata, which is
+// dominated by column chunks, statistics and page indexes
+fn estimate_metadata_mem_size(metadata: &ParquetMetaData) -> usize {
+    let file_metadata = metadata.file_metadata();
+    let mut mem_size = size_of::<ParquetMetaData>();
+
+    mem_size += file_metadata
+        .key_value_metadata()
+        .into_iter()
+        .flatten()
+        .map(|kv| kv.key.len() + kv.value.as_ref().map(|v| v.len()).unwrap_or(0))
+        .sum::<usize>();
+    mem_size += file_metadata
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|column| {
+            size_of::<ColumnDescriptor>()
+                + column.path().parts().iter().map(|p| p.len()).sum::<usize>()
+        })
+        .sum::<usize>();
+
+    for row_group in metadata.row_groups() {
+        mem_size += size_of::<RowGroupMetaData>();
+        for column in row_group.columns() {
+            mem_size += size_of::<ColumnChunkMetaData>();
+            if let Some(statistics) = column.statistics() {
+                if statistics.has_min_max_set() {
+                    mem_size += statistics.min_bytes().len() + statistics.max_bytes().len();
+                }
+            }
+        }
+    }
+
+    let num_pages = metadata
+        .offset_index()
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|page_locations| page_locations.len())
+        .sum::<usize>();
+    if metadata.offset_index().is_some() {
+        mem_size += num_pages * size_of::<PageLocation>();
+    }
+    if metadata.column_index().is_some() {
+        // min/max values of each page, assume 16 bytes per page in average
+        mem_size += num_pages * (size_of::<Index>() + 16);
+    }
+    mem_size
+}
+
+#[cfg(test)]
+mod test {
+    use std::{mem::size_of, sync::Arc};
+
+    use datafusion::parquet::{
+        file::{
+            metadata::{FileMetaData, ParquetMetaData},
+            page_index::index::Index,
+        },
+        format::PageLocation,
+        schema::{parser::parse_message_type, types::SchemaDescriptor},
+    };
+
+    use crate::common::parquet_metadata_cache::{
+        estimate_metadata_mem_size, ParquetMetadataCache, ParquetMetadataCacheKey,
+    };
+
+    fn key(path: &str) -> ParquetMetadataCacheKey {
+        ParquetMetadataCacheKey {
+            path: path.to_string(),
+            size: 100,
+            last_modified_ns: 1,
+        }
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let schema = parse_message_type("message test { required int32 a; }").unwrap();
+        let schema_descr = Arc::new(SchemaDescriptor::new(Arc::new(schema)));
+        let metadata = Arc::new(ParquetMetaData::new(
+            FileMetaData::new(1, 0, None, None, schema_descr, None),
+            vec![],
+        ));
+
+        let cache = ParquetMetadataCache::new(300);
+        assert_eq!(cache.insert(key("a"), metadata.clone(), 100), 100);
+        assert_eq!(cache.insert(key("b"), metadata.clone(), 100), 100);
+        assert_eq!(cache.insert(key("c"), metadata.clone(), 100), 100);
+        assert!(cache.get(&key("a")).is_some()); // b becomes the least recently used
+
+        assert_eq!(cache.insert(key("d"), metadata.clone(), 100), 0);
+        assert!(cache.get(&key("b")).is_none());
+        assert!(cache.get(&key("a")).is_some());
+        assert!(cache.get(&key("c")).is_some());
+        assert!(cache.get(&key("d")).is_some());
+
+        // entries larger than capacity are not cached
+        assert_eq!(cache.insert(key("e"), metadata.clone(), 400), 0);
+        assert!(cache.get(&key("e")).is_none());
+
+        // replacing an entry
+        assert_eq!(cache.insert(key("a"), metadata.clone(), 50), -50);
+        assert!(cache.get(&key("a")).is_some());
+
+        // same path with different modified time is another entry
+        let modified_key = ParquetMetadataCacheKey {
+            last_modified_ns: 2,
+            ..key("a")
+        };
+        assert!(cache.get(&modified_key).is_none());
+    }
+
+    #[test]
+    fn test_page_index_mem_size() {
+        let schema = parse_message_type("message test { required int32 a; }").unwrap();
+        let schema_descr = Arc::new(SchemaDescriptor::new(Arc::new(schema)));
+        let file_metadata = FileMetaData::new(1, 0, None, None, schema_descr, None);
+        let page_location = PageLocation {
+            offset: 0,
+            compressed_page_size: 100,
+            first_row_index: 0,
+        };
+
+        let metadata = ParquetMetaData::new(file_metadata.clone(), vec![]);
+        let metadata_with_page_index = ParquetMetaData::new_with_page_index(
+            file_metadata,
+            vec![],
+            Some(vec![vec![Index::NONE]]),
+            Some(vec![vec![vec![page_location; 10]]]),
+        );
+        assert_eq!(
+            estimate_metadata_mem_size(&metadata_with_page_index),
+            estimate_metadata_mem_size(&metadata)
+                + 10 * (size_of::<PageLocation>() + size_of::<Index>() + 16),
+        );
+    }
+}
@@ -0,0 +1,385 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deletes of merge-on-read tables attached to scanned data files, applied to
+//! decoded rows by their positions in the data file.
+
+use std::collections::HashSet;
+
+use arrow::{
+    array::{ArrayRef, BooleanArray},
+    record_batch::RecordBatch,
+    row::{OwnedRow, RowConverter},
+};
+use bytes::{Buf, Bytes};
+use datafusion::common::Result;
+use datafusion_ext_commons::df_execution_err;
+use object_store::ObjectMeta;
+
+/// Delete descriptors attached to a data file, passed to file openers as the
+/// extensions of partitioned files.
+#[derive(Debug, Clone)]
+pub enum DeleteFile {
+    /// iceberg position delete file with `file_path` and `pos` columns
+    PositionDeletes(ObjectMeta),
+
+    /// iceberg equality delete file, rows equal to any delete row in all the
+    /// equality columns are deleted
+    EqualityDeletes {
+        meta: ObjectMeta,
+        equality_columns: Vec<String>,
+    },
+
+    /// delta deletion vector of deleted row positions
+    DeletionVector(DeletionVector),
+}
+
+#[derive(Debug, Clone)]
+pub enum DeletionVector {
+    /// serialized bitmap stored in the delete descriptor
+    Inline(Bytes),
+
+    /// serialized bitmap stored in a deletion vector file at the offset,
+    /// prefixed with its size and followed by its checksum
+    Stored {
+        path: String,
+        offset: u64,
+        size: usize,
+    },
+}
+
+/// Deleted rows of a data file.
+#[derive(Default)]
+pub struct RowDeletes {
+    // sorted and deduplicated row positions
+    deleted_positions: Vec<i64>,
+    equality_deletes: Vec<EqualityDeletes>,
+}
+
+/// Deleted keys of equality columns, converted with the data file types.
+pub struct EqualityDeletes {
+    /// indices of equality columns in the data file schema
+    pub file_indices: Vec<usize>,
+    pub converter: RowConverter,
+    pub keys: HashSet<OwnedRow>,
+}
+
+impl RowDeletes {
+    pub fn new(mut deleted_positions: Vec<i64>, equality_deletes: Vec<EqualityDeletes>) -> Self {
+        deleted_positions.sort_unstable();
+        deleted_positions.dedup();
+        Self {
+            deleted_positions,
+            equality_deletes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deleted_positions.is_empty()
+            && self
+                .equality_deletes
+                .iter()
+                .all(|equality_deletes| equality_deletes.keys.is_empty())
+    }
+
+    /// indices of data file columns required by equality deletes
+    pub fn required_file_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.equality_deletes
+            .iter()
+            .flat_map(|equality_deletes| equality_deletes.file_indices.iter().copied())
+    }
+
+    /// returns the mask of retained rows of a file batch, or None if no rows
+    /// are deleted. row_indices are the positions of rows in the data file,
+    /// and file_projection are the file indices of columns in the batch.
+    pub fn retained_rows(
+        &mut self,
+        file_batch: &RecordBatch,
+        row_indices: &[i64],
+        file_projection: &[usize],
+    ) -> Result<Option<BooleanArray>> {
+        let mut retained = vec![true; file_batch.num_rows()];
+        let mut num_deleted = 0;
+
+        // row indices are increasing within a batch, so only deleted positions
+        // in the range of this batch are checked
+        if let (Some(&first), Some(&last)) = (row_indices.first(), row_indices.last()) {
+            let start = self.deleted_positions.partition_point(|&pos| pos < first);
+            let end = self.deleted_positions.partition_point(|&pos| pos <= last);
+            for pos in &self.deleted_positions[start..end] {
+                if let Ok(row_idx) = row_indices.binary_search(pos) {
+                    retained[row_idx] = false;
+                    num_deleted += 1;
+                }
+            }
+        }
+
+        for equality_deletes in &mut self.equality_deletes {
+            if equality_deletes.keys.is_empty() {
+                continue;
+            }
+            let columns = equality_deletes
+                .file_indices
+                .iter()
+                .map(|file_idx| {
+                    let pos = file_projection.iter().position(|idx| idx == file_idx);
+                    match pos {
+                        Some(pos) => Ok(file_batch.column(pos).clone()),
+                        None => df_execution_err!("missing equality column: {file_idx}"),
+                    }
+                })
+                .collect::<Result<Vec<ArrayRef>>>()?;
+            let rows = equality_deletes.converter.convert_columns(&columns)?;
+            for (row_idx, row) in rows.iter().enumerate() {
+                if retained[row_idx] && equality_deletes.keys.contains(&row.owned()) {
+                    retained[row_idx] = false;
+                    num_deleted += 1;
+                }
+            }
+        }
+
+        if num_deleted == 0 {
+            return Ok(None);
+        }
+        Ok(Some(BooleanArray::from(retained)))
+    }
+}
+
+// magic number of delta deletion vectors in the portable format
+const DELETION_VECTOR_MAGIC: u32 = 1681511377;
+
+/// decodes positions from a delta deletion vector, which is a 64-bit roaring
+/// bitmap serialized as an array of 32-bit roaring bitmaps in the portable
+/// format, each keyed by the high 32 bits of its values.
+pub fn decode_deletion_vector(data: &[u8]) -> Result<Vec<i64>> {
+    let mut buf = data;
+    ensure_remaining(buf, 12)?;
+    let magic = buf.get_u32_le();
+    if magic != DELETION_VECTOR_MAGIC {
+        return df_execution_err!("unsupported deletion vector magic number: {magic}");
+    }
+
+    let num_bitmaps = buf.get_u64_le();
+    let mut positions = vec![];
+    for _ in 0..num_bitmaps {
+        ensure_remaining(buf, 4)?;
+        let high = (buf.get_u32_le() as i64) << 32;
+        for low in decode_roaring_bitmap(&mut buf)? {
+            positions.push(high | low as i64);
+        }
+    }
+    Ok(positions)
+}
+
+const SERIAL_COOKIE_NO_RUNCONTAINER: u32 = 12346;
+const SERIAL_COOKIE: u32 = 12347;
+const NO_OFFSET_THRESHOLD: usize = 4;
+const MAX_ARRAY_CONTAINER_SIZE: usize = 4096;
+
+// decodes a 32-bit roaring bitmap in the portable format, see
+// https://github.com/RoaringBitmap/RoaringFormatSpec
+fn decode_roaring_bitmap(buf: &mut &[u8]) -> Result<Vec<u32>> {
+    ensure_remaining(buf, 4)?;
+    let cookie = buf.get_u32_le();
+    let (num_containers, run_flags) = if cookie & 0xffff == SERIAL_COOKIE {
+        let num_containers = (cookie >> 16) as usize + 1;
+        let num_flag_bytes = (num_containers + 7) / 8;
+        ensure_remaining(buf, num_flag_bytes)?;
+        let run_flags = buf[..num_flag_bytes].to_vec();
+        buf.advance(num_flag_bytes);
+        (num_containers, Some(run_flags))
+    } else if cookie == SERIAL_COOKIE_NO_RUNCONTAINER {
+        ensure_remaining(buf, 4)?;
+        (buf.get_u32_le() as usize, None)
+    } else {
+        return df_execution_err!("invalid roaring bitmap cookie: {cookie}");
+    };
+
+    ensure_remaining(buf, num_containers * 4)?;
+    let headers = (0..num_containers)
+        .map(|_| {
+            let key = buf.get_u16_le() as u32;
+            let cardinality = buf.get_u16_le() as usize + 1;
+            (key, cardinality)
+        })
+        .collect::<Vec<_>>();
+
+    // offsets of containers are not needed since containers are read in order
+    if run_flags.is_none() || num_containers >= NO_OFFSET_THRESHOLD {
+        ensure_remaining(buf, num_containers * 4)?;
+        buf.advance(num_containers * 4);
+    }
+
+    let mut values = vec![];
+    for (i, (key, cardinality)) in headers.into_iter().enumerate() {
+        let high = key << 16;
+        let is_run = run_flags
+            .as_ref()
+            .is_some_and(|flags| flags[i / 8] & (1 << (i % 8)) != 0);
+
+        if is_run {
+            ensure_remaining(buf, 2)?;
+            let num_runs = buf.get_u16_le() as usize;
+            ensure_remaining(buf, num_runs * 4)?;
+            for _ in 0..num_runs {
+                let start = buf.get_u16_le() as u32;
+                let len = buf.get_u16_le() as u32;
+                values.extend((start..=start + len).map(|low| high | low));
+            }
+        } else if cardinality > MAX_ARRAY_CONTAINER_SIZE {
+            ensure_remaining(buf, 8192)?;
+            for word_idx in 0..1024u32 {
+                let mut word = buf.get_u64_le();
+                while word != 0 {
+                    let bit = word.trailing_zeros();
+                    values.push(high | (word_idx * 64 + bit));
+                    word &= word - 1;
+                }
+            }
+        } else {
+            ensure_remaining(buf, cardinality * 2)?;
+            values.extend((0..cardinality).map(|_| high | buf.get_u16_le() as u32));
+        }
+    }
+    Ok(values)
+}
+
+fn ensure_remaining(buf: &[u8], len: usize) -> Result<()> {
+    if buf.len() < len {
+        return df_execution_err!("unexpected end of serialized roaring bitmap");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashSet, sync::Arc};
+
+    use arrow::{
+        array::{ArrayRef, BooleanArray, Int32Array, StringArray},
+        datatypes::DataType,
+        record_batch::RecordBatch,
+        row::{RowConverter, SortField},
+    };
+    use bytes::BufMut;
+
+    use crate::common::row_deletes::{
+        decode_deletion_vector, EqualityDeletes, RowDeletes, DELETION_VECTOR_MAGIC, SERIAL_COOKIE,
+        SERIAL_COOKIE_NO_RUNCONTAINER,
+    };
+
+    #[test]
+    fn test_decode_deletion_vector() {
+        let mut data = vec![];
+        data.put_u32_le(DELETION_VECTOR_MAGIC);
+        data.put_u64_le(2);
+
+        // bitmap of key 0 with an array container and a bitmap container
+        data.put_u32_le(0);
+        data.put_u32_le(SERIAL_COOKIE_NO_RUNCONTAINER);
+        data.put_u32_le(2);
+        data.put_u16_le(0);
+        data.put_u16_le(2); // cardinality - 1
+        data.put_u16_le(1);
+        data.put_u16_le(5000 - 1);
+        data.put_u64_le(0); // offsets
+        data.put_u32_le(3);
+        data.put_u32_le(7);
+        data.put_u32_le(65535);
+        for word_idx in 0..1024 {
+            // even values of the first 5000 * 2 values
+            data.put_u64_le(if word_idx < 10000 / 64 {
+                0x5555555555555555
+            } else if word_idx == 10000 / 64 {
+                0x5555
+            } else {
+                0
+            });
+        }
+
+        // bitmap of key 1 with a run container
+        data.put_u32_le(1);
+        data.put_u32_le(SERIAL_COOKIE); // one container
+        data.put_u8(0b1);
+        data.put_u16_le(2);
+        data.put_u16_le(9);
+        data.put_u16_le(1); // one run
+        data.put_u16_le(100);
+        data.put_u16_le(9);
+
+        let mut expected = vec![3, 7, 65535];
+        expected.extend((0..5000).map(|i| 65536 + i * 2));
+        expected.extend((100..110).map(|i| (1 << 32) + 65536 * 2 + i));
+        assert_eq!(decode_deletion_vector(&data).unwrap(), expected);
+
+        // truncated data
+        assert!(decode_deletion_vector(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_retained_rows() {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "a",
+                Arc::new(Int32Array::from(vec![
+                    Some(1),
+                    Some(2),
+                    None,
+                    Some(4),
+                    Some(5),
+                ])) as ArrayRef,
+            ),
+            (
+                "b",
+                Arc::new(StringArray::from(vec!["x", "y", "z", "w", "v"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let mut converter = RowConverter::new(vec![SortField::new(DataType::Int32)]).unwrap();
+        let delete_keys: ArrayRef = Arc::new(Int32Array::from(vec![None, Some(5), Some(6)]));
+        let keys = converter
+            .convert_columns(&[delete_keys])
+            .unwrap()
+            .iter()
+            .map(|row| row.owned())
+            .collect::<HashSet<_>>();
+        let equality_deletes = EqualityDeletes {
+            file_indices: vec![3],
+            converter,
+            keys,
+        };
+
+        let mut row_deletes = RowDeletes::new(vec![101, 9, 0, 9], vec![equality_deletes]);
+        assert!(!row_deletes.is_empty());
+        assert_eq!(
+            row_deletes.required_file_indices().collect::<Vec<_>>(),
+            vec![3]
+        );
+
+        // batch columns a and b are file columns 3 and 4
+        let retained = row_deletes
+            .retained_rows(&batch, &[8, 9, 10, 100, 101], &[3, 4])
+            .unwrap();
+        assert_eq!(
+            retained,
+            Some(BooleanArray::from(vec![true, false, false, true, false]))
+        );
+
+        let retained = row_deletes
+            .retained_rows(&batch.slice(0, 2), &[20, 21], &[3, 4])
+            .unwrap();
+        assert_eq!(retained, None);
+    }
+}
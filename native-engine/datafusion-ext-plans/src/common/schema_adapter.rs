@@ -0,0 +1,508 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Adapts batches read from data files to the table schema requested by file
+//! scans.
+
+use std::sync::Arc;
+
+use arrow::{
+    array::{make_array, new_null_array, Array, ArrayRef},
+    datatypes::{DataType, Field, FieldRef, Fields, Schema, SchemaRef},
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use datafusion::{
+    common::{
+        cast::{as_list_array, as_map_array, as_struct_array},
+        Result,
+    },
+    parquet::arrow::PARQUET_FIELD_ID_META_KEY,
+};
+use datafusion_ext_commons::cast::cast_scan_input_array;
+
+/// Returns the parquet field id carried in the metadata of the field.
+pub fn field_id(field: &Field) -> Option<i32> {
+    field
+        .metadata()
+        .get(PARQUET_FIELD_ID_META_KEY)?
+        .parse()
+        .ok()
+}
+
+/// Finds the file field matching the table field. fields are matched by
+/// parquet field ids if the table field has an id and the file fields have
+/// ids, otherwise by names.
+pub fn match_file_field(
+    table_field: &Field,
+    file_fields: &Fields,
+    case_insensitive: bool,
+) -> Option<usize> {
+    let file_has_ids = file_fields.iter().any(|field| field_id(field).is_some());
+    if let Some(id) = field_id(table_field).filter(|_| file_has_ids) {
+        return file_fields
+            .iter()
+            .position(|field| field_id(field) == Some(id));
+    }
+    file_fields.iter().position(|field| {
+        if case_insensitive {
+            field.name().eq_ignore_ascii_case(table_field.name())
+        } else {
+            field.name() == table_field.name()
+        }
+    })
+}
+
+/// Maps each table field to the matching file field, see [`match_file_field`],
+/// top-level names are case insensitive. files written by old hive versions
+/// have no real column names, whose fields are mapped by position.
+pub fn map_table_fields(table_schema: &Schema, file_schema: &Schema) -> Vec<Option<usize>> {
+    let is_hive_positional = !file_schema.fields().is_empty()
+        && file_schema.fields().iter().all(|field| {
+            field
+                .name()
+                .strip_prefix("_col")
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        });
+
+    table_schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(table_idx, table_field)| {
+            if is_hive_positional {
+                return (table_idx < file_schema.fields().len()).then_some(table_idx);
+            }
+            match_file_field(table_field, file_schema.fields(), true)
+        })
+        .collect()
+}
+
+/// Casts the file columns to the projected table schema, missing columns are
+/// filled with nulls. struct fields are matched by name (or by field id if the
+/// table type has ids), so pruned structs in the file batch are also filled
+/// with nulls for their missing fields.
+pub fn adapt_file_batch(
+    file_batch: &RecordBatch,
+    output_schema: &SchemaRef,
+    projected_mapping: &[Option<usize>],
+    file_projection: &[usize],
+) -> Result<RecordBatch> {
+    let num_rows = file_batch.num_rows();
+    let columns = output_schema
+        .fields()
+        .iter()
+        .zip(projected_mapping)
+        .map(|(field, file_idx)| {
+            let file_column = file_idx.and_then(|file_idx| {
+                let pos = file_projection.iter().position(|&i| i == file_idx)?;
+                Some(file_batch.column(pos))
+            });
+            match file_column {
+                Some(column) => {
+                    let column = resolve_nested_fields_by_id(column, field.data_type())?;
+                    cast_scan_input_array(column.as_ref(), field.data_type())
+                }
+                None => Ok(new_null_array(field.data_type(), num_rows)),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new_with_options(
+        output_schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+    )?)
+}
+
+// renames nested struct fields of a file array to the names of table fields
+// with the same field ids, so that it can be cast to the table type by names.
+// file fields not matching any table fields are removed
+fn resolve_nested_fields_by_id(array: &ArrayRef, table_type: &DataType) -> Result<ArrayRef> {
+    if !has_field_ids(table_type) {
+        return Ok(array.clone());
+    }
+    resolve_nested_fields(array, table_type)
+}
+
+fn resolve_nested_fields(array: &ArrayRef, table_type: &DataType) -> Result<ArrayRef> {
+    let with_children = |data_type: DataType, children: Vec<ArrayRef>| -> Result<ArrayRef> {
+        Ok(make_array(
+            array
+                .to_data()
+                .into_builder()
+                .data_type(data_type)
+                .child_data(
+                    children
+                        .into_iter()
+                        .map(|child| child.into_data())
+                        .collect(),
+                )
+                .build()?,
+        ))
+    };
+
+    Ok(match (array.data_type(), table_type) {
+        (DataType::Struct(file_fields), DataType::Struct(table_fields)) => {
+            let struct_ = as_struct_array(array)?;
+            let mut fields = vec![];
+            let mut columns = vec![];
+            for table_field in table_fields {
+                if let Some(file_idx) = match_file_field(table_field, file_fields, false) {
+                    let file_field = &file_fields[file_idx];
+                    let column =
+                        resolve_nested_fields(struct_.column(file_idx), table_field.data_type())?;
+                    fields.push(Arc::new(
+                        Field::new(
+                            table_field.name(),
+                            column.data_type().clone(),
+                            file_field.is_nullable(),
+                        )
+                        .with_metadata(file_field.metadata().clone()),
+                    ));
+                    columns.push(column);
+                }
+            }
+            with_children(DataType::Struct(fields.into()), columns)?
+        }
+        (DataType::List(file_item), DataType::List(table_item)) => {
+            let values = as_list_array(array)?.values();
+            let values = resolve_nested_fields(values, table_item.data_type())?;
+            let item = with_data_type(file_item, values.data_type().clone());
+            with_children(DataType::List(item), vec![values])?
+        }
+        // key and value fields are matched by position
+        (DataType::Map(file_entries, sorted), DataType::Map(table_entries, _)) => {
+            let entries = as_map_array(array)?.entries();
+            let (file_kv, table_kv) = match (file_entries.data_type(), table_entries.data_type()) {
+                (DataType::Struct(file_kv), DataType::Struct(table_kv))
+                    if file_kv.len() == 2 && table_kv.len() == 2 =>
+                {
+                    (file_kv, table_kv)
+                }
+                _ => return Ok(array.clone()),
+            };
+            let mut kv_fields = vec![];
+            let mut kv_columns = vec![];
+            for (i, (file_field, table_field)) in file_kv.iter().zip(table_kv.iter()).enumerate() {
+                let column = resolve_nested_fields(entries.column(i), table_field.data_type())?;
+                kv_fields.push(Arc::new(
+                    Field::new(
+                        table_field.name(),
+                        column.data_type().clone(),
+                        file_field.is_nullable(),
+                    )
+                    .with_metadata(file_field.metadata().clone()),
+                ));
+                kv_columns.push(column);
+            }
+            let entries_type = DataType::Struct(kv_fields.into());
+            let entries = make_array(
+                entries
+                    .to_data()
+                    .into_builder()
+                    .data_type(entries_type.clone())
+                    .child_data(kv_columns.into_iter().map(|c| c.into_data()).collect())
+                    .build()?,
+            );
+            let entries_field = with_data_type(file_entries, entries_type);
+            with_children(DataType::Map(entries_field, *sorted), vec![entries])?
+        }
+        _ => array.clone(),
+    })
+}
+
+// whether any nested fields of the data type have field ids
+fn has_field_ids(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Struct(fields) => fields
+            .iter()
+            .any(|field| field_id(field).is_some() || has_field_ids(field.data_type())),
+        DataType::List(item) | DataType::LargeList(item) | DataType::Map(item, _) => {
+            has_field_ids(item.data_type())
+        }
+        _ => false,
+    }
+}
+
+/// Prunes nested fields of table columns to the required paths. each path
+/// starts with a top-level column name followed by names of nested struct
+/// fields, lists and maps are traversed through their elements and values.
+/// columns without any paths are kept as is.
+pub fn prune_nested_fields(schema: &Schema, paths: &[Vec<String>]) -> Schema {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let field_paths = paths
+                .iter()
+                .filter(|path| path.first() == Some(field.name()))
+                .map(|path| &path[1..])
+                .collect::<Vec<_>>();
+            if field_paths.is_empty() {
+                return field.clone();
+            }
+            with_data_type(field, prune_data_type(field.data_type(), &field_paths))
+        })
+        .collect::<Vec<_>>();
+    Schema::new_with_metadata(fields, schema.metadata().clone())
+}
+
+fn prune_data_type(data_type: &DataType, paths: &[&[String]]) -> DataType {
+    // an exhausted path requires the whole field
+    if paths.iter().any(|path| path.is_empty()) {
+        return data_type.clone();
+    }
+    match data_type {
+        DataType::Struct(fields) => DataType::Struct(
+            fields
+                .iter()
+                .filter_map(|field| {
+                    let field_paths = paths
+                        .iter()
+                        .filter(|path| &path[0] == field.name())
+                        .map(|path| &path[1..])
+                        .collect::<Vec<_>>();
+                    if field_paths.is_empty() {
+                        return None;
+                    }
+                    Some(with_data_type(
+                        field,
+                        prune_data_type(field.data_type(), &field_paths),
+                    ))
+                })
+                .collect(),
+        ),
+        DataType::List(item) => DataType::List(with_data_type(
+            item,
+            prune_data_type(item.data_type(), paths),
+        )),
+        DataType::LargeList(item) => DataType::LargeList(with_data_type(
+            item,
+            prune_data_type(item.data_type(), paths),
+        )),
+        DataType::Map(entries, sorted) => match entries.data_type() {
+            // keys are always kept as a whole
+            DataType::Struct(kv) if kv.len() == 2 => {
+                let pruned_value =
+                    with_data_type(&kv[1], prune_data_type(kv[1].data_type(), paths));
+                let pruned_entries = DataType::Struct(vec![kv[0].clone(), pruned_value].into());
+                DataType::Map(with_data_type(entries, pruned_entries), *sorted)
+            }
+            _ => data_type.clone(),
+        },
+        _ => data_type.clone(),
+    }
+}
+
+fn with_data_type(field: &FieldRef, data_type: DataType) -> FieldRef {
+    Arc::new(
+        Field::new(field.name(), data_type, field.is_nullable())
+            .with_metadata(field.metadata().clone()),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use arrow::{
+        array::{Array, ArrayRef, Float32Array, Float64Array, Int32Array, Int64Array, StructArray},
+        datatypes::{DataType, Field, Fields, Schema},
+        record_batch::RecordBatch,
+    };
+    use datafusion::{
+        common::{
+            cast::{as_float64_array, as_int64_array, as_struct_array},
+            Result,
+        },
+        parquet::arrow::PARQUET_FIELD_ID_META_KEY,
+    };
+
+    use crate::common::schema_adapter::{adapt_file_batch, map_table_fields, prune_nested_fields};
+
+    #[test]
+    fn test_map_table_fields() {
+        let table_schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("B", DataType::Utf8, true),
+            Field::new("c", DataType::Utf8, true),
+        ]);
+        let file_schema = Schema::new(vec![
+            Field::new("b", DataType::Utf8, true),
+            Field::new("a", DataType::Int32, true),
+        ]);
+        assert_eq!(
+            map_table_fields(&table_schema, &file_schema),
+            vec![Some(1), Some(0), None]
+        );
+
+        let file_schema = Schema::new(vec![
+            Field::new("_col0", DataType::Int32, true),
+            Field::new("_col1", DataType::Utf8, true),
+        ]);
+        assert_eq!(
+            map_table_fields(&table_schema, &file_schema),
+            vec![Some(0), Some(1), None]
+        );
+    }
+
+    #[test]
+    fn test_adapt_file_batch_by_field_id() -> Result<()> {
+        let with_id = |field: Field, id: i32| {
+            field.with_metadata(HashMap::from([(
+                PARQUET_FIELD_ID_META_KEY.to_string(),
+                id.to_string(),
+            )]))
+        };
+        let file_struct_fields = Fields::from(vec![
+            with_id(Field::new("p", DataType::Int32, true), 3),
+            with_id(Field::new("q", DataType::Float32, true), 4),
+        ]);
+        let file_schema = Arc::new(Schema::new(vec![
+            with_id(Field::new("x", DataType::Int32, true), 1),
+            with_id(
+                Field::new("s", DataType::Struct(file_struct_fields.clone()), true),
+                2,
+            ),
+        ]));
+        let file_batch = RecordBatch::try_new(
+            file_schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StructArray::new(
+                    file_struct_fields,
+                    vec![
+                        Arc::new(Int32Array::from(vec![10, 20])) as ArrayRef,
+                        Arc::new(Float32Array::from(vec![1.5, 2.5])),
+                    ],
+                    None,
+                )),
+            ],
+        )?;
+
+        // columns and nested fields are renamed, reordered and promoted
+        let table_struct_fields = Fields::from(vec![
+            with_id(Field::new("q2", DataType::Float64, true), 4),
+            with_id(Field::new("r", DataType::Utf8, true), 5),
+            with_id(Field::new("p2", DataType::Int64, true), 3),
+        ]);
+        let table_schema = Arc::new(Schema::new(vec![
+            with_id(
+                Field::new("s2", DataType::Struct(table_struct_fields), true),
+                2,
+            ),
+            with_id(Field::new("x2", DataType::Int64, true), 1),
+            with_id(Field::new("x", DataType::Int64, true), 6),
+        ]));
+        let mapping = map_table_fields(&table_schema, &file_schema);
+        assert_eq!(mapping, vec![Some(1), Some(0), None]);
+
+        let batch = adapt_file_batch(&file_batch, &table_schema, &mapping, &[0, 1])?;
+        let s2 = as_struct_array(batch.column(0))?;
+        assert_eq!(
+            as_float64_array(s2.column(0))?,
+            &Float64Array::from(vec![1.5, 2.5])
+        );
+        assert_eq!(s2.column(1).null_count(), 2);
+        assert_eq!(
+            as_int64_array(s2.column(2))?,
+            &Int64Array::from(vec![10, 20])
+        );
+        assert_eq!(
+            as_int64_array(batch.column(1))?,
+            &Int64Array::from(vec![1, 2])
+        );
+        assert_eq!(batch.column(2).null_count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_nested_fields() {
+        let struct_type = |fields: Vec<Field>| DataType::Struct(Fields::from(fields));
+        let list_type = |item: DataType| DataType::List(Arc::new(Field::new("item", item, true)));
+        let map_type = |key: DataType, value: DataType| {
+            let entries = struct_type(vec![
+                Field::new("key", key, false),
+                Field::new("value", value, true),
+            ]);
+            DataType::Map(Arc::new(Field::new("entries", entries, false)), false)
+        };
+        let abc = || {
+            struct_type(vec![
+                Field::new("a", DataType::Int32, true),
+                Field::new("b", DataType::Utf8, true),
+                Field::new(
+                    "c",
+                    struct_type(vec![Field::new("d", DataType::Int64, true)]),
+                    true,
+                ),
+            ])
+        };
+        let schema = Schema::new(vec![
+            Field::new("s", abc(), true),
+            Field::new("l", list_type(abc()), true),
+            Field::new("m", map_type(DataType::Utf8, abc()), true),
+            Field::new("x", DataType::Int32, true),
+        ]);
+
+        let path = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let pruned = prune_nested_fields(
+            &schema,
+            &[
+                path(&["s", "b"]),
+                path(&["s", "c", "d"]),
+                path(&["l", "a"]),
+                path(&["m", "c"]),
+            ],
+        );
+        assert_eq!(
+            pruned,
+            Schema::new(vec![
+                Field::new(
+                    "s",
+                    struct_type(vec![
+                        Field::new("b", DataType::Utf8, true),
+                        Field::new(
+                            "c",
+                            struct_type(vec![Field::new("d", DataType::Int64, true)]),
+                            true
+                        ),
+                    ]),
+                    true
+                ),
+                Field::new(
+                    "l",
+                    list_type(struct_type(vec![Field::new("a", DataType::Int32, true)])),
+                    true
+                ),
+                Field::new(
+                    "m",
+                    map_type(
+                        DataType::Utf8,
+                        struct_type(vec![Field::new(
+                            "c",
+                            struct_type(vec![Field::new("d", DataType::Int64, true)]),
+                            true
+                        )])
+                    ),
+                    true
+                ),
+                Field::new("x", DataType::Int32, true),
+            ])
+        );
+
+        // a path to the column itself keeps the whole column
+        let pruned = prune_nested_fields(&schema, &[path(&["s"]), path(&["s", "a"])]);
+        assert_eq!(pruned, schema);
+    }
+}
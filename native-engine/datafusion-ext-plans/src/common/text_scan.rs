@@ -0,0 +1,453 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Common utilities of the line-based text file scans (csv and json lines)
+
+use std::{io::Read, sync::Arc};
+
+use arrow::{
+    array::{
+        new_null_array, Array, ArrayRef, AsArray, BooleanArray, Date32Array,
+        TimestampMicrosecondArray,
+    },
+    compute::{concat_batches, filter_record_batch},
+    datatypes::{DataType, Field, Fields, SchemaRef, TimeUnit},
+    error::ArrowError,
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use datafusion::{
+    common::Result, datasource::physical_plan::FileMeta, physical_plan::metrics::Count,
+};
+use datafusion_ext_commons::{
+    cast::cast_impl,
+    df_execution_err,
+    hadoop_fs::{FsDataInputStream, FsProvider},
+    spark_datetime::{DateTimePattern, ZoneId},
+};
+
+const READ_BLOCK_SIZE: usize = 1 << 20;
+
+/// how to deal with malformed records, same as spark's `mode` option
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    Permissive,
+    DropMalformed,
+    FailFast,
+}
+
+/// options shared by the text file scans
+#[derive(Debug, Clone)]
+pub struct TextParseOptions {
+    pub mode: ParseMode,
+    pub multi_line: bool,
+    pub timestamp_format: Option<DateTimePattern>,
+    pub date_format: Option<DateTimePattern>,
+    pub zone: ZoneId,
+}
+
+/// opens the split of a text file. multi-line files are never split by spark
+/// and are always read as a whole.
+pub fn open_text_split(
+    fs_provider: &FsProvider,
+    file_meta: &FileMeta,
+    multi_line: bool,
+    bytes_scanned: Count,
+) -> Result<SplitLineReader<FsInputReader>> {
+    let filename = file_meta.object_meta.location.filename();
+    let path = BASE64_URL_SAFE_NO_PAD
+        .decode(filename.expect("missing filename"))
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        .or_else(|_| df_execution_err!("cannot decode filename: {filename:?}"))?;
+    let fs = fs_provider.provide(&path)?;
+
+    let file_size = file_meta.object_meta.size as u64;
+    let (start, end) = match &file_meta.range {
+        Some(range) if !multi_line => (range.start as u64, range.end as u64),
+        _ => (0, file_size),
+    };
+    let input = FsInputReader {
+        input: fs.open(&path)?,
+        pos: start,
+        file_size,
+        bytes_scanned,
+    };
+    Ok(SplitLineReader::new(input, start, end))
+}
+
+/// sequential reader of a hadoop input stream
+pub struct FsInputReader {
+    input: FsDataInputStream,
+    pos: u64,
+    file_size: u64,
+    bytes_scanned: Count,
+}
+
+impl Read for FsInputReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf
+            .len()
+            .min(self.file_size.saturating_sub(self.pos) as usize);
+        self.input
+            .read_fully(self.pos, &mut buf[..len])
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        self.pos += len as u64;
+        self.bytes_scanned.add(len);
+        Ok(len)
+    }
+}
+
+/// reads the lines of a file split with the same rule as hadoop's
+/// `LineRecordReader`: a split starting at `start` owns the lines beginning in
+/// `(start, end]`, or `[0, end]` for the first split of a file. the input
+/// must be positioned at `start`.
+pub struct SplitLineReader<R: Read> {
+    input: R,
+    end: u64,
+    line_start: u64, // file offset of pending[0], always the start of a line
+    pending: Vec<u8>,
+    eof: bool,
+    first_line_skipped: bool,
+}
+
+impl<R: Read> SplitLineReader<R> {
+    pub fn new(input: R, start: u64, end: u64) -> Self {
+        Self {
+            input,
+            end,
+            line_start: start,
+            pending: vec![],
+            eof: false,
+            first_line_skipped: start == 0,
+        }
+    }
+
+    /// reads up to `max_lines` complete lines, including their line breaks.
+    /// returns None if there are no more lines in this split.
+    pub fn next_lines(&mut self, max_lines: usize) -> Result<Option<Vec<u8>>> {
+        if !self.first_line_skipped {
+            // the first (maybe partial) line belongs to the previous split
+            loop {
+                if let Some(newline_pos) = find_newline(&self.pending) {
+                    self.pending.drain(..=newline_pos);
+                    self.line_start += newline_pos as u64 + 1;
+                    break;
+                }
+                self.line_start += self.pending.len() as u64;
+                self.pending.clear();
+                if !self.fill()? {
+                    break;
+                }
+            }
+            self.first_line_skipped = true;
+        }
+
+        let mut num_lines = 0;
+        let mut lines_len = 0;
+        while num_lines < max_lines && self.line_start + lines_len as u64 <= self.end {
+            match find_newline(&self.pending[lines_len..]) {
+                Some(newline_pos) => {
+                    lines_len += newline_pos + 1;
+                    num_lines += 1;
+                }
+                None if self.eof => {
+                    // the last line without a line break
+                    lines_len = self.pending.len();
+                    break;
+                }
+                None => {
+                    self.fill()?;
+                }
+            }
+        }
+        if lines_len == 0 {
+            return Ok(None);
+        }
+        let remaining = self.pending.split_off(lines_len);
+        let lines = std::mem::replace(&mut self.pending, remaining);
+        self.line_start += lines_len as u64;
+        Ok(Some(lines))
+    }
+
+    fn fill(&mut self) -> Result<bool> {
+        let old_len = self.pending.len();
+        self.pending.resize(old_len + READ_BLOCK_SIZE, 0);
+        let num_read = self.input.read(&mut self.pending[old_len..])?;
+        self.pending.truncate(old_len + num_read);
+        self.eof = num_read == 0;
+        Ok(!self.eof)
+    }
+}
+
+fn find_newline(bytes: &[u8]) -> Option<usize> {
+    bytes.iter().position(|&b| b == b'\n')
+}
+
+/// removes the first line, used to skip the csv header
+pub fn skip_first_line(lines: &mut Vec<u8>) {
+    let first_line_len = find_newline(lines)
+        .map(|pos| pos + 1)
+        .unwrap_or(lines.len());
+    lines.drain(..first_line_len);
+}
+
+/// decodes the lines into a batch of `schema`. if the lines cannot be decoded
+/// as a whole, each line is decoded separately and the malformed lines are
+/// processed by the parse mode. in multi-line mode the input is split into
+/// records by `split_multi_line`.
+pub fn decode_lines(
+    lines: &[u8],
+    schema: &SchemaRef,
+    options: &TextParseOptions,
+    split_multi_line: fn(&[u8]) -> Vec<&[u8]>,
+    decode: impl Fn(&[u8]) -> Result<RecordBatch>,
+) -> Result<RecordBatch> {
+    let err = match decode(lines) {
+        Ok(batch) => return Ok(batch),
+        Err(err) => err,
+    };
+    if options.mode == ParseMode::FailFast {
+        return df_execution_err!(
+            "Malformed records are detected in record parsing. Parse Mode: FAILFAST. {err}"
+        );
+    }
+
+    let records = if options.multi_line {
+        split_multi_line(lines)
+    } else {
+        lines.split_inclusive(|&b| b == b'\n').collect()
+    };
+    let mut batches = vec![];
+    for record in records {
+        match decode(record) {
+            Ok(batch) => batches.push(batch),
+            Err(_) if options.mode == ParseMode::Permissive => {
+                let columns = schema
+                    .fields()
+                    .iter()
+                    .map(|field| new_null_array(field.data_type(), 1))
+                    .collect();
+                batches.push(RecordBatch::try_new_with_options(
+                    schema.clone(),
+                    columns,
+                    &RecordBatchOptions::new().with_row_count(Some(1)),
+                )?);
+            }
+            Err(_) => {} // dropped
+        }
+    }
+    Ok(concat_batches(schema, &batches)?)
+}
+
+/// converts the decoded columns to the output types with spark's rules, and
+/// processes the rows with malformed values by the parse mode. malformed
+/// values in permissive mode are converted to nulls.
+pub fn convert_text_batch(
+    batch: &RecordBatch,
+    output_schema: &SchemaRef,
+    options: &TextParseOptions,
+) -> Result<RecordBatch> {
+    let num_rows = batch.num_rows();
+    let mut malformed: Option<BooleanArray> = None;
+    let mut columns = vec![];
+
+    for (column, field) in batch.columns().iter().zip(output_schema.fields()) {
+        let converted = convert_text_column(column, field.data_type(), options)?;
+
+        // values of nested types are not checked
+        if options.mode != ParseMode::Permissive && column.data_type() == &DataType::Utf8 {
+            let column_malformed = BooleanArray::from_iter(
+                (0..num_rows).map(|i| Some(column.is_valid(i) && converted.is_null(i))),
+            );
+            malformed = Some(match malformed {
+                Some(malformed) => arrow::compute::or(&malformed, &column_malformed)?,
+                None => column_malformed,
+            });
+        }
+        columns.push(converted);
+    }
+    let converted_batch = RecordBatch::try_new_with_options(
+        output_schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+    )?;
+
+    let malformed = match malformed {
+        Some(malformed) if malformed.true_count() > 0 => malformed,
+        _ => return Ok(converted_batch),
+    };
+    match options.mode {
+        ParseMode::FailFast => {
+            df_execution_err!(
+                "Malformed records are detected in record parsing. Parse Mode: FAILFAST."
+            )
+        }
+        _ => Ok(filter_record_batch(
+            &converted_batch,
+            &arrow::compute::not(&malformed)?,
+        )?),
+    }
+}
+
+fn convert_text_column(
+    column: &ArrayRef,
+    data_type: &DataType,
+    options: &TextParseOptions,
+) -> Result<ArrayRef> {
+    let is_string = column.data_type() == &DataType::Utf8;
+    Ok(match data_type {
+        DataType::Timestamp(TimeUnit::Microsecond, tz)
+            if is_string && options.timestamp_format.is_some() =>
+        {
+            let pattern = options.timestamp_format.as_ref().unwrap();
+            Arc::new(
+                column
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|s| s.and_then(|s| pattern.parse_timestamp(s, options.zone)))
+                    .collect::<TimestampMicrosecondArray>()
+                    .with_timezone_opt(tz.clone()),
+            )
+        }
+        DataType::Date32 if is_string && options.date_format.is_some() => {
+            let pattern = options.date_format.as_ref().unwrap();
+            Arc::new(
+                column
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|s| s.and_then(|s| pattern.parse_date(s)))
+                    .collect::<Date32Array>(),
+            )
+        }
+        _ => cast_impl(column, data_type, true, false, options.zone)?,
+    })
+}
+
+/// replaces all primitive types with nullable strings, nested types are kept
+/// so that the json reader decodes their structures
+pub fn to_string_leaf_type(data_type: &DataType) -> DataType {
+    let convert_field =
+        |field: &Field| Field::new(field.name(), to_string_leaf_type(field.data_type()), true);
+    match data_type {
+        DataType::List(field) => DataType::List(Arc::new(convert_field(field))),
+        DataType::Struct(fields) => DataType::Struct(Fields::from(
+            fields
+                .iter()
+                .map(|field| convert_field(field))
+                .collect::<Vec<_>>(),
+        )),
+        DataType::Map(field, sorted) => DataType::Map(Arc::new(convert_field(field)), *sorted),
+        _ => DataType::Utf8,
+    }
+}
+
+/// slices the batches to the specified batch size
+pub fn split_batches(
+    batches: impl Iterator<Item = Result<RecordBatch>>,
+    batch_size: usize,
+) -> impl Iterator<Item = std::result::Result<RecordBatch, ArrowError>> {
+    batches.flat_map(move |batch| match batch {
+        Ok(batch) => (0..batch.num_rows())
+            .step_by(batch_size)
+            .map(|offset| {
+                let len = batch_size.min(batch.num_rows() - offset);
+                Ok(batch.slice(offset, len))
+            })
+            .collect::<Vec<_>>(),
+        Err(e) => vec![Err(ArrowError::ExternalError(Box::new(e)))],
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Cursor, sync::Arc};
+
+    use arrow::{
+        array::{Int32Array, StringArray},
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use datafusion::common::Result;
+    use datafusion_ext_commons::spark_datetime::ZoneId;
+
+    use crate::common::text_scan::{
+        convert_text_batch, ParseMode, SplitLineReader, TextParseOptions,
+    };
+
+    fn read_split(data: &[u8], start: usize, end: usize, max_lines: usize) -> Vec<String> {
+        let mut reader =
+            SplitLineReader::new(Cursor::new(&data[start..]), start as u64, end as u64);
+        let mut chunks = vec![];
+        while let Some(lines) = reader.next_lines(max_lines).unwrap() {
+            chunks.push(String::from_utf8(lines).unwrap());
+        }
+        chunks
+    }
+
+    #[test]
+    fn test_split_line_reader() {
+        let data = b"aa\nbbb\ncc\ndddd";
+        assert_eq!(
+            read_split(data, 0, data.len(), 2),
+            vec!["aa\nbbb\n", "cc\ndddd"]
+        );
+
+        // every line is read by exactly one split
+        for split_point in 1..=data.len() {
+            let mut lines = read_split(data, 0, split_point, 1);
+            lines.extend(read_split(data, split_point, data.len(), 1));
+            assert_eq!(
+                lines.concat(),
+                "aa\nbbb\ncc\ndddd",
+                "split at {split_point}"
+            );
+        }
+        assert_eq!(read_split(data, 0, 3, 10), vec!["aa\nbbb\n"]);
+        assert_eq!(read_split(data, 3, 7, 10), vec!["cc\n"]);
+        assert_eq!(read_split(data, 4, 7, 10), vec!["cc\n"]);
+    }
+
+    #[test]
+    fn test_convert_text_batch() -> Result<()> {
+        let batch = RecordBatch::try_from_iter([(
+            "a",
+            Arc::new(StringArray::from(vec![Some("1"), Some("x"), None])) as _,
+        )])?;
+        let output_schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let mut options = TextParseOptions {
+            mode: ParseMode::Permissive,
+            multi_line: false,
+            timestamp_format: None,
+            date_format: None,
+            zone: ZoneId::UTC,
+        };
+
+        let converted = convert_text_batch(&batch, &output_schema, &options)?;
+        assert_eq!(
+            converted.column(0).as_ref(),
+            &Int32Array::from(vec![Some(1), None, None])
+        );
+
+        options.mode = ParseMode::DropMalformed;
+        let converted = convert_text_batch(&batch, &output_schema, &options)?;
+        assert_eq!(
+            converted.column(0).as_ref(),
+            &Int32Array::from(vec![Some(1), None])
+        );
+
+        options.mode = ParseMode::FailFast;
+        assert!(convert_text_batch(&batch, &output_schema, &options).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution plan for reading CSV files
+
+use std::{any::Any, fmt, fmt::Formatter, sync::Arc};
+
+use arrow::{
+    compute::concat_batches,
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    record_batch::RecordBatch,
+};
+use blaze_jni_bridge::{
+    conf, conf::BooleanConf, jni_call_static, jni_new_global_ref, jni_new_string,
+};
+use datafusion::{
+    datasource::physical_plan::{
+        FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream, OnError,
+    },
+    error::Result,
+    execution::context::TaskContext,
+    physical_plan::{
+        expressions::PhysicalSortExpr,
+        metrics::{
+            BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricValue,
+            MetricsSet, Time,
+        },
+        stream::RecordBatchStreamAdapter,
+        DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, RecordBatchStream,
+        SendableRecordBatchStream, Statistics,
+    },
+};
+use datafusion_ext_commons::{hadoop_fs::FsProvider, streams::coalesce_stream::CoalesceInput};
+use futures::{stream::once, StreamExt, TryStreamExt};
+
+use crate::common::{
+    output::TaskOutputter,
+    text_scan::{
+        convert_text_batch, decode_lines, open_text_split, skip_first_line, split_batches,
+        TextParseOptions,
+    },
+};
+
+/// csv dialect options, same as spark's `sep`, `quote`, `escape` and `header`
+#[derive(Debug, Clone, Copy)]
+pub struct CsvFormat {
+    pub delimiter: u8,
+    pub quote: u8,
+    pub escape: u8,
+    pub header: bool,
+}
+
+/// Execution plan for scanning one or more CSV partitions
+#[derive(Debug, Clone)]
+pub struct CsvExec {
+    fs_resource_id: String,
+    base_config: FileScanConfig,
+    format: CsvFormat,
+    parse_options: TextParseOptions,
+    projected_statistics: Statistics,
+    projected_schema: SchemaRef,
+    projected_output_ordering: Vec<Vec<PhysicalSortExpr>>,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl CsvExec {
+    /// Create a new CSV reader execution plan provided file list and
+    /// schema.
+    pub fn new(
+        base_config: FileScanConfig,
+        fs_resource_id: String,
+        format: CsvFormat,
+        parse_options: TextParseOptions,
+    ) -> Self {
+        let metrics = ExecutionPlanMetricsSet::new();
+        let (projected_schema, projected_statistics, projected_output_ordering) =
+            base_config.project();
+
+        Self {
+            fs_resource_id,
+            base_config,
+            format,
+            parse_options,
+            projected_schema,
+            projected_statistics,
+            projected_output_ordering,
+            metrics,
+        }
+    }
+}
+
+impl DisplayAs for CsvExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> fmt::Result {
+        let limit = self.base_config.limit;
+        let file_group = self
+            .base_config
+            .file_groups
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "CsvExec: limit={:?}, file_group={:?}, format={:?}",
+            limit, file_group, self.format,
+        )
+    }
+}
+
+impl ExecutionPlan for CsvExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.projected_schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.projected_output_ordering
+            .first()
+            .map(|ordering| ordering.as_slice())
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition_index: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
+        let _timer = baseline_metrics.elapsed_compute().timer();
+
+        let io_time = Time::default();
+        let io_time_metric = Arc::new(Metric::new(
+            MetricValue::Time {
+                name: "io_time".into(),
+                time: io_time.clone(),
+            },
+            Some(partition_index),
+        ));
+        self.metrics.register(io_time_metric);
+
+        // get fs object from jni bridge resource
+        let resource_id = jni_new_string!(&self.fs_resource_id)?;
+        let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+        let fs_provider = Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time));
+
+        let projection = match self.base_config.file_column_projection_indices() {
+            Some(proj) => proj,
+            None => (0..self.base_config.file_schema.fields().len()).collect(),
+        };
+
+        let opener = CsvOpener {
+            projection: Arc::from(projection),
+            batch_size: context.session_config().batch_size(),
+            table_schema: self.base_config.file_schema.clone(),
+            format: self.format,
+            parse_options: self.parse_options.clone(),
+            fs_provider,
+            bytes_scanned: MetricBuilder::new(&self.metrics)
+                .counter("bytes_scanned", partition_index),
+        };
+
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
+        let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+        let mut file_stream =
+            FileStream::new(&self.base_config, partition_index, opener, &self.metrics)?;
+        if conf::IGNORE_CORRUPTED_FILES.value()? {
+            file_stream = file_stream.with_on_error(OnError::Skip);
+        }
+        let mut stream = Box::pin(file_stream);
+        let context_cloned = context.clone();
+        let timed_stream = Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            once(async move {
+                context_cloned.output_with_sender(
+                    "CsvScan",
+                    stream.schema(),
+                    move |sender| async move {
+                        let mut timer = elapsed_compute.timer();
+                        while let Some(batch) = stream.next().await.transpose()? {
+                            sender.send(Ok(batch), Some(&mut timer)).await;
+                        }
+                        Ok(())
+                    },
+                )
+            })
+            .try_flatten(),
+        ));
+        context.coalesce_with_default_batch_size(timed_stream, &baseline_metrics)
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.projected_statistics.clone()
+    }
+}
+
+#[derive(Clone)]
+struct CsvOpener {
+    projection: Arc<[usize]>,
+    batch_size: usize,
+    table_schema: SchemaRef,
+    format: CsvFormat,
+    parse_options: TextParseOptions,
+    fs_provider: Arc<FsProvider>,
+    bytes_scanned: Count,
+}
+
+impl FileOpener for CsvOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let opener = self.clone();
+        Ok(Box::pin(async move {
+            let multi_line = opener.parse_options.multi_line;
+            let is_file_start = multi_line
+                || file_meta
+                    .range
+                    .as_ref()
+                    .map(|range| range.start == 0)
+                    .unwrap_or(true);
+            let mut reader = open_text_split(
+                &opener.fs_provider,
+                &file_meta,
+                multi_line,
+                opener.bytes_scanned.clone(),
+            )?;
+
+            // csv columns are matched by position, all of them are decoded as
+            // strings and converted with spark's rules
+            let string_schema = Arc::new(Schema::new(
+                opener
+                    .table_schema
+                    .fields()
+                    .iter()
+                    .map(|field| Field::new(field.name(), DataType::Utf8, true))
+                    .collect::<Vec<_>>(),
+            ));
+            let projected_string_schema = Arc::new(string_schema.project(&opener.projection)?);
+            let output_schema = Arc::new(opener.table_schema.project(&opener.projection)?);
+            let max_lines = if multi_line {
+                usize::MAX
+            } else {
+                opener.batch_size
+            };
+            let batch_size = opener.batch_size;
+            let mut skip_header = opener.format.header && is_file_start;
+
+            let batches = std::iter::from_fn(move || {
+                let lines = match reader.next_lines(max_lines) {
+                    Ok(Some(mut lines)) => {
+                        if std::mem::take(&mut skip_header) {
+                            skip_first_line(&mut lines);
+                        }
+                        lines
+                    }
+                    Ok(None) => return None,
+                    Err(e) => return Some(Err(e)),
+                };
+                let batch = decode_lines(
+                    &lines,
+                    &projected_string_schema,
+                    &opener.parse_options,
+                    |data| vec![data],
+                    |data| {
+                        decode_csv(
+                            data,
+                            &string_schema,
+                            &opener.projection,
+                            &opener.format,
+                            opener.batch_size,
+                        )
+                    },
+                )
+                .and_then(|batch| {
+                    convert_text_batch(&batch, &output_schema, &opener.parse_options)
+                });
+                Some(batch)
+            });
+            Ok(futures::stream::iter(split_batches(batches, batch_size)).boxed())
+        }))
+    }
+}
+
+fn decode_csv(
+    data: &[u8],
+    string_schema: &SchemaRef,
+    projection: &[usize],
+    format: &CsvFormat,
+    batch_size: usize,
+) -> Result<RecordBatch> {
+    let reader = arrow::csv::ReaderBuilder::new(string_schema.clone())
+        .has_header(false)
+        .with_delimiter(format.delimiter)
+        .with_quote(format.quote)
+        .with_escape(format.escape)
+        .with_projection(projection.to_vec())
+        .with_batch_size(batch_size)
+        .build(data)?;
+    let batches = reader.collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(concat_batches(
+        &Arc::new(string_schema.project(projection)?),
+        &batches,
+    )?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::StringArray,
+        datatypes::{DataType, Field, Schema},
+    };
+    use datafusion::common::Result;
+
+    use crate::csv_exec::{decode_csv, CsvFormat};
+
+    #[test]
+    fn test_decode_csv() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Utf8, true),
+        ]));
+        let format = CsvFormat {
+            delimiter: b'|',
+            quote: b'\'',
+            escape: b'\\',
+            header: false,
+        };
+        let data = b"1|'x|y'|a\n2||'it\\'s'\n";
+        let batch = decode_csv(data, &schema, &[2, 1], &format, 1024)?;
+        assert_eq!(batch.num_columns(), 2);
+        assert_eq!(
+            batch.column(0).as_ref(),
+            &StringArray::from(vec![Some("a"), Some("it's")])
+        );
+        assert_eq!(
+            batch.column(1).as_ref(),
+            &StringArray::from(vec![Some("x|y"), None])
+        );
+        Ok(())
+    }
+}
@@ -20,7 +20,9 @@ use std::{
 
 use arrow::datatypes::SchemaRef;
 use async_trait::async_trait;
-use blaze_jni_bridge::{jni_call, jni_call_static, jni_new_global_ref, jni_new_string};
+use blaze_jni_bridge::{
+    conf, conf::IntConf, jni_call, jni_call_static, jni_new_global_ref, jni_new_string,
+};
 use datafusion::{
     error::Result,
     execution::context::TaskContext,
@@ -34,7 +36,7 @@ use datafusion::{
 };
 use datafusion_ext_commons::streams::{
     coalesce_stream::CoalesceInput,
-    ipc_stream::{IpcReadMode, IpcReaderStream},
+    ipc_stream::{prefetched_ipc_reader_stream, IpcReadMode, IpcReaderStream},
 };
 use jni::objects::JObject;
 
@@ -110,6 +112,8 @@ impl ExecutionPlan for IpcReaderExec {
     ) -> Result<SendableRecordBatchStream> {
         let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
         let size_counter = MetricBuilder::new(&self.metrics).counter("size", partition);
+        let fetch_wait_time =
+            MetricBuilder::new(&self.metrics).subset_time("fetch_wait_time", partition);
 
         let elapsed_compute = baseline_metrics.elapsed_compute().clone();
         let _timer = elapsed_compute.timer();
@@ -125,13 +129,33 @@ impl ExecutionPlan for IpcReaderExec {
 
         let schema = self.schema.clone();
         let mode = self.mode;
-        let ipc_stream = Box::pin(IpcReaderStream::new(
-            schema,
-            segments,
-            mode,
-            baseline_metrics,
-            size_counter,
-        ));
+
+        // only shuffle segments are prefetched
+        let num_prefetch_segments = match mode {
+            IpcReadMode::ChannelAndFileSegment => {
+                conf::SHUFFLE_READ_PREFETCH_SEGMENTS.value()?.max(0) as usize
+            }
+            _ => 0,
+        };
+        let ipc_stream: SendableRecordBatchStream = if num_prefetch_segments > 0 {
+            prefetched_ipc_reader_stream(
+                schema,
+                segments,
+                mode,
+                num_prefetch_segments,
+                baseline_metrics,
+                size_counter,
+                fetch_wait_time,
+            )
+        } else {
+            Box::pin(IpcReaderStream::new(
+                schema,
+                segments,
+                mode,
+                baseline_metrics,
+                size_counter,
+            ))
+        };
         Ok(context.coalesce_with_default_batch_size(
             ipc_stream,
             &BaselineMetrics::new(&self.metrics, partition),
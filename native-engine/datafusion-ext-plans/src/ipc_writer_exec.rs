@@ -30,7 +30,10 @@ use datafusion::{
         Statistics,
     },
 };
-use datafusion_ext_commons::{io::write_one_batch, streams::coalesce_stream::CoalesceInput};
+use datafusion_ext_commons::{
+    io::{write_one_batch, IoCompressionCodec, IoCompressionSite},
+    streams::coalesce_stream::CoalesceInput,
+};
 use futures::{stream::once, StreamExt, TryStreamExt};
 use jni::objects::{GlobalRef, JObject};
 
@@ -133,13 +136,14 @@ pub async fn write_ipc(
     metrics: BaselineMetrics,
 ) -> Result<SendableRecordBatchStream> {
     let schema = input.schema();
+    let codec = IoCompressionCodec::try_from_conf(IoCompressionSite::Broadcast)?;
     context.output_with_sender("IpcWrite", schema.clone(), move |_sender| async move {
         while let Some(batch) = input.next().await.transpose()? {
             let timer = metrics.elapsed_compute().timer();
             let num_rows = batch.num_rows();
 
             let mut buffer = vec![];
-            write_one_batch(&batch, &mut Cursor::new(&mut buffer), true, None)?;
+            write_one_batch(&batch, &mut Cursor::new(&mut buffer), codec, None)?;
             drop(timer);
             metrics.record_output(num_rows);
 
@@ -0,0 +1,445 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution plan for reading JSON lines files
+
+use std::{any::Any, fmt, fmt::Formatter, sync::Arc};
+
+use arrow::{
+    compute::concat_batches,
+    datatypes::{Field, Schema, SchemaRef},
+    record_batch::RecordBatch,
+};
+use blaze_jni_bridge::{
+    conf, conf::BooleanConf, jni_call_static, jni_new_global_ref, jni_new_string,
+};
+use datafusion::{
+    datasource::physical_plan::{
+        FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream, OnError,
+    },
+    error::Result,
+    execution::context::TaskContext,
+    physical_plan::{
+        expressions::PhysicalSortExpr,
+        metrics::{
+            BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricValue,
+            MetricsSet, Time,
+        },
+        stream::RecordBatchStreamAdapter,
+        DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, RecordBatchStream,
+        SendableRecordBatchStream, Statistics,
+    },
+};
+use datafusion_ext_commons::{hadoop_fs::FsProvider, streams::coalesce_stream::CoalesceInput};
+use futures::{stream::once, StreamExt, TryStreamExt};
+
+use crate::common::{
+    output::TaskOutputter,
+    text_scan::{
+        convert_text_batch, decode_lines, open_text_split, split_batches, to_string_leaf_type,
+        TextParseOptions,
+    },
+};
+
+/// Execution plan for scanning one or more JSON partitions
+#[derive(Debug, Clone)]
+pub struct JsonExec {
+    fs_resource_id: String,
+    base_config: FileScanConfig,
+    parse_options: TextParseOptions,
+    projected_statistics: Statistics,
+    projected_schema: SchemaRef,
+    projected_output_ordering: Vec<Vec<PhysicalSortExpr>>,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl JsonExec {
+    /// Create a new JSON reader execution plan provided file list and
+    /// schema.
+    pub fn new(
+        base_config: FileScanConfig,
+        fs_resource_id: String,
+        parse_options: TextParseOptions,
+    ) -> Self {
+        let metrics = ExecutionPlanMetricsSet::new();
+        let (projected_schema, projected_statistics, projected_output_ordering) =
+            base_config.project();
+
+        Self {
+            fs_resource_id,
+            base_config,
+            parse_options,
+            projected_schema,
+            projected_statistics,
+            projected_output_ordering,
+            metrics,
+        }
+    }
+}
+
+impl DisplayAs for JsonExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> fmt::Result {
+        let limit = self.base_config.limit;
+        let file_group = self
+            .base_config
+            .file_groups
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "JsonExec: limit={:?}, file_group={:?}",
+            limit, file_group,
+        )
+    }
+}
+
+impl ExecutionPlan for JsonExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.projected_schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.projected_output_ordering
+            .first()
+            .map(|ordering| ordering.as_slice())
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition_index: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
+        let _timer = baseline_metrics.elapsed_compute().timer();
+
+        let io_time = Time::default();
+        let io_time_metric = Arc::new(Metric::new(
+            MetricValue::Time {
+                name: "io_time".into(),
+                time: io_time.clone(),
+            },
+            Some(partition_index),
+        ));
+        self.metrics.register(io_time_metric);
+
+        // get fs object from jni bridge resource
+        let resource_id = jni_new_string!(&self.fs_resource_id)?;
+        let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+        let fs_provider = Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time));
+
+        let projection = match self.base_config.file_column_projection_indices() {
+            Some(proj) => proj,
+            None => (0..self.base_config.file_schema.fields().len()).collect(),
+        };
+
+        let opener = JsonOpener {
+            projection: Arc::from(projection),
+            batch_size: context.session_config().batch_size(),
+            table_schema: self.base_config.file_schema.clone(),
+            parse_options: self.parse_options.clone(),
+            fs_provider,
+            bytes_scanned: MetricBuilder::new(&self.metrics)
+                .counter("bytes_scanned", partition_index),
+        };
+
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
+        let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+        let mut file_stream =
+            FileStream::new(&self.base_config, partition_index, opener, &self.metrics)?;
+        if conf::IGNORE_CORRUPTED_FILES.value()? {
+            file_stream = file_stream.with_on_error(OnError::Skip);
+        }
+        let mut stream = Box::pin(file_stream);
+        let context_cloned = context.clone();
+        let timed_stream = Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            once(async move {
+                context_cloned.output_with_sender(
+                    "JsonScan",
+                    stream.schema(),
+                    move |sender| async move {
+                        let mut timer = elapsed_compute.timer();
+                        while let Some(batch) = stream.next().await.transpose()? {
+                            sender.send(Ok(batch), Some(&mut timer)).await;
+                        }
+                        Ok(())
+                    },
+                )
+            })
+            .try_flatten(),
+        ));
+        context.coalesce_with_default_batch_size(timed_stream, &baseline_metrics)
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.projected_statistics.clone()
+    }
+}
+
+#[derive(Clone)]
+struct JsonOpener {
+    projection: Arc<[usize]>,
+    batch_size: usize,
+    table_schema: SchemaRef,
+    parse_options: TextParseOptions,
+    fs_provider: Arc<FsProvider>,
+    bytes_scanned: Count,
+}
+
+impl FileOpener for JsonOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let opener = self.clone();
+        Ok(Box::pin(async move {
+            let multi_line = opener.parse_options.multi_line;
+            let mut reader = open_text_split(
+                &opener.fs_provider,
+                &file_meta,
+                multi_line,
+                opener.bytes_scanned.clone(),
+            )?;
+
+            // json fields are matched by name, primitive values are decoded as
+            // strings and converted with spark's rules
+            let output_schema = Arc::new(opener.table_schema.project(&opener.projection)?);
+            let string_schema = Arc::new(Schema::new(
+                output_schema
+                    .fields()
+                    .iter()
+                    .map(|field| {
+                        Field::new(field.name(), to_string_leaf_type(field.data_type()), true)
+                    })
+                    .collect::<Vec<_>>(),
+            ));
+            let max_lines = if multi_line {
+                usize::MAX
+            } else {
+                opener.batch_size
+            };
+            let batch_size = opener.batch_size;
+
+            let batches = std::iter::from_fn(move || {
+                let lines = match reader.next_lines(max_lines) {
+                    Ok(Some(lines)) => lines,
+                    Ok(None) => return None,
+                    Err(e) => return Some(Err(e)),
+                };
+                let batch = decode_lines(
+                    &lines,
+                    &string_schema,
+                    &opener.parse_options,
+                    split_json_records,
+                    |data| decode_json(data, &string_schema, opener.batch_size),
+                )
+                .and_then(|batch| {
+                    convert_text_batch(&batch, &output_schema, &opener.parse_options)
+                });
+                Some(batch)
+            });
+            Ok(futures::stream::iter(split_batches(batches, batch_size)).boxed())
+        }))
+    }
+}
+
+fn decode_json(data: &[u8], string_schema: &SchemaRef, batch_size: usize) -> Result<RecordBatch> {
+    let reader = arrow::json::ReaderBuilder::new(string_schema.clone())
+        .with_batch_size(batch_size)
+        .with_coerce_primitive(true)
+        .build(data)?;
+    let batches = reader.collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(concat_batches(string_schema, &batches)?)
+}
+
+/// splits the content of a multi-line json file into records: the top-level
+/// values, or the elements of a top-level array. an unterminated value is kept
+/// to the end of the content, so it is processed as a malformed record.
+fn split_json_records(data: &[u8]) -> Vec<&[u8]> {
+    let mut records = vec![];
+    let mut record_start = None;
+    let mut depth = 0usize;
+    let mut in_top_array = false;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, &b) in data.iter().enumerate() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        let record_depth = in_top_array as usize;
+        match b {
+            b'[' if depth == 0 && record_start.is_none() => {
+                in_top_array = true;
+                depth = 1;
+            }
+            b']' if in_top_array && depth == 1 => {
+                // end of the top-level array, also ends a scalar element
+                records.extend(record_start.take().map(|start| &data[start..i]));
+                in_top_array = false;
+                depth = 0;
+            }
+            b'{' | b'[' => {
+                record_start.get_or_insert(i);
+                depth += 1;
+            }
+            b'}' | b']' if depth > record_depth => {
+                depth -= 1;
+                if depth == record_depth {
+                    records.extend(record_start.take().map(|start| &data[start..=i]));
+                }
+            }
+            b',' | b' ' | b'\t' | b'\r' | b'\n' if depth == record_depth => {
+                // ends a scalar value
+                records.extend(record_start.take().map(|start| &data[start..i]));
+            }
+            _ => {
+                in_string = b == b'"';
+                if depth == record_depth {
+                    record_start.get_or_insert(i);
+                }
+            }
+        }
+    }
+    records.extend(record_start.map(|start| &data[start..]));
+    records
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{Array, AsArray, StringArray},
+        datatypes::{DataType, Field, Schema},
+    };
+    use datafusion::common::Result;
+    use datafusion_ext_commons::spark_datetime::ZoneId;
+
+    use crate::{
+        common::text_scan::{decode_lines, to_string_leaf_type, ParseMode, TextParseOptions},
+        json_exec::{decode_json, split_json_records},
+    };
+
+    #[test]
+    fn test_decode_json() -> Result<()> {
+        let struct_type = DataType::Struct(
+            vec![
+                Field::new("x", DataType::Int32, true),
+                Field::new("y", DataType::Boolean, true),
+            ]
+            .into(),
+        );
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", to_string_leaf_type(&struct_type), true),
+        ]));
+        let data = b"{\"a\": 1.5, \"b\": {\"x\": 1, \"y\": true}}\n\n{\"c\": 3}\n";
+        let batch = decode_json(data, &schema, 1024)?;
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(
+            batch.column(0).as_ref(),
+            &StringArray::from(vec![Some("1.5"), None])
+        );
+        let b = batch.column(1).as_struct();
+        assert!(b.is_null(1));
+        assert_eq!(
+            b.column(0).as_ref(),
+            &StringArray::from(vec![Some("1"), None])
+        );
+        assert_eq!(
+            b.column(1).as_ref(),
+            &StringArray::from(vec![Some("true"), None])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_json_records() {
+        let data = b"[{\"a\": \"x]}\\\"\"},\n {\"a\": [1, 2]}, 3]\n{\"a\": {}} \"s\" {\"a\"";
+        assert_eq!(
+            split_json_records(data),
+            vec![
+                &b"{\"a\": \"x]}\\\"\"}"[..],
+                &b"{\"a\": [1, 2]}"[..],
+                &b"3"[..],
+                &b"{\"a\": {}}"[..],
+                &b"\"s\""[..],
+                &b"{\"a\""[..],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_multi_line_json() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Utf8, true)]));
+        let data = b"[\n  {\"a\": 1},\n  {\"a\" 2},\n  {\n    \"a\": 3\n  }\n]\n";
+        let mut options = TextParseOptions {
+            mode: ParseMode::Permissive,
+            multi_line: true,
+            timestamp_format: None,
+            date_format: None,
+            zone: ZoneId::UTC,
+        };
+        let decode = |data: &[u8]| decode_json(data, &schema, 1024);
+
+        // the malformed record is converted to a null row
+        let batch = decode_lines(data, &schema, &options, split_json_records, decode)?;
+        assert_eq!(
+            batch.column(0).as_ref(),
+            &StringArray::from(vec![Some("1"), None, Some("3")])
+        );
+
+        // the malformed record is dropped
+        options.mode = ParseMode::DropMalformed;
+        let batch = decode_lines(data, &schema, &options, split_json_records, decode)?;
+        assert_eq!(
+            batch.column(0).as_ref(),
+            &StringArray::from(vec![Some("1"), Some("3")])
+        );
+
+        options.mode = ParseMode::FailFast;
+        assert!(decode_lines(data, &schema, &options, split_json_records, decode).is_err());
+        Ok(())
+    }
+}
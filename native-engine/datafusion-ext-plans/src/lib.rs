@@ -19,6 +19,7 @@ pub mod agg_exec;
 pub mod broadcast_join_exec;
 pub mod broadcast_nested_loop_join_exec;
 pub mod common;
+pub mod csv_exec;
 pub mod debug_exec;
 pub mod empty_partitions_exec;
 pub mod expand_exec;
@@ -28,8 +29,11 @@ pub mod generate;
 pub mod generate_exec;
 pub mod ipc_reader_exec;
 pub mod ipc_writer_exec;
+pub mod json_exec;
 pub mod limit_exec;
 pub mod memmgr;
+pub mod orc_exec;
+pub mod orc_sink_exec;
 pub mod parquet_exec;
 pub mod parquet_sink_exec;
 pub mod project_exec;
@@ -63,6 +63,11 @@ impl MemManager {
         self.consumers.lock().len()
     }
 
+    /// memory not used by any consumer
+    pub fn free_mem(&self) -> usize {
+        self.total.saturating_sub(self.status.lock().total_used)
+    }
+
     pub fn register_consumer(mut consumer: Arc<dyn MemConsumer>, spillable: bool) {
         let consumer_info = Arc::new(MemConsumerInfo {
             status: Mutex::new(MemConsumerStatus {
@@ -179,6 +184,18 @@ pub trait MemConsumer: Send + Sync {
         mem_used as f64 / (total as f64 / num_consumers as f64)
     }
 
+    /// memory this consumer is expected to use, which is its share of the
+    /// memory not used by unspillable consumers. consumers exceeding it are
+    /// spilled by the mem manager
+    fn mem_target(&self) -> usize {
+        let mm = MemManager::get();
+        let mm_status = mm.status.lock();
+        let mem_unspillables = mm_status
+            .total_used
+            .saturating_sub(mm_status.mem_spillables);
+        mm.total.saturating_sub(mem_unspillables) / mm_status.num_spillables.max(1)
+    }
+
     fn set_spillable(&self, spillable: bool) {
         let consumer_info = self.consumer_info();
         let mut consumer_status = consumer_info.status.lock();
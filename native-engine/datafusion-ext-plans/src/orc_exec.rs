@@ -0,0 +1,530 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution plan for reading ORC files
+
+use std::{any::Any, fmt, fmt::Formatter, ops::Range, sync::Arc};
+
+use arrow::{
+    array::{
+        ArrayRef, Date32Array, Decimal128Array, Float64Array, Int64Array, StringArray, UInt64Array,
+    },
+    datatypes::{DataType, Schema, SchemaRef},
+    error::ArrowError,
+};
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use blaze_jni_bridge::{
+    conf, conf::BooleanConf, jni_call_static, jni_new_global_ref, jni_new_string,
+};
+use datafusion::{
+    common::Column,
+    datasource::physical_plan::{
+        FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream, OnError,
+    },
+    error::Result,
+    execution::context::TaskContext,
+    physical_optimizer::pruning::{PruningPredicate, PruningStatistics},
+    physical_plan::{
+        expressions::PhysicalSortExpr,
+        metrics::{
+            BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricValue,
+            MetricsSet, Time,
+        },
+        stream::RecordBatchStreamAdapter,
+        DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, PhysicalExpr,
+        RecordBatchStream, SendableRecordBatchStream, Statistics,
+    },
+};
+use datafusion_ext_commons::{
+    df_execution_err,
+    hadoop_fs::{FsDataInputStream, FsProvider},
+    orc::{
+        proto::ColumnStatistics,
+        reader::{OrcFileReader, OrcInput, StripeReader},
+    },
+    streams::coalesce_stream::CoalesceInput,
+};
+use futures::{stream::once, StreamExt, TryStreamExt};
+
+use crate::common::{
+    output::TaskOutputter,
+    schema_adapter::{adapt_file_batch, map_table_fields},
+};
+
+/// Execution plan for scanning one or more ORC partitions
+#[derive(Debug, Clone)]
+pub struct OrcExec {
+    fs_resource_id: String,
+    base_config: FileScanConfig,
+    projected_statistics: Statistics,
+    projected_schema: SchemaRef,
+    projected_output_ordering: Vec<Vec<PhysicalSortExpr>>,
+    metrics: ExecutionPlanMetricsSet,
+    pruning_predicate: Option<Arc<PruningPredicate>>,
+}
+
+impl OrcExec {
+    /// Create a new ORC reader execution plan provided file list and
+    /// schema.
+    pub fn new(
+        base_config: FileScanConfig,
+        fs_resource_id: String,
+        predicate: Option<Arc<dyn PhysicalExpr>>,
+    ) -> Self {
+        let metrics = ExecutionPlanMetricsSet::new();
+        let predicate_creation_errors =
+            MetricBuilder::new(&metrics).global_counter("num_predicate_creation_errors");
+
+        let file_schema = &base_config.file_schema;
+        let pruning_predicate = predicate
+            .and_then(|predicate_expr| {
+                match PruningPredicate::try_new(predicate_expr, file_schema.clone()) {
+                    Ok(pruning_predicate) => Some(Arc::new(pruning_predicate)),
+                    Err(e) => {
+                        log::warn!("Could not create pruning predicate: {e}");
+                        predicate_creation_errors.add(1);
+                        None
+                    }
+                }
+            })
+            .filter(|p| !p.allways_true());
+
+        let (projected_schema, projected_statistics, projected_output_ordering) =
+            base_config.project();
+
+        Self {
+            fs_resource_id,
+            base_config,
+            projected_schema,
+            projected_statistics,
+            projected_output_ordering,
+            metrics,
+            pruning_predicate,
+        }
+    }
+}
+
+impl DisplayAs for OrcExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> fmt::Result {
+        let limit = self.base_config.limit;
+        let file_group = self
+            .base_config
+            .file_groups
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "OrcExec: limit={:?}, file_group={:?}, predicate={}",
+            limit,
+            file_group,
+            self.pruning_predicate
+                .as_ref()
+                .map(|pre| format!("{}", pre.predicate_expr()))
+                .unwrap_or(format!("<empty>")),
+        )
+    }
+}
+
+impl ExecutionPlan for OrcExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.projected_schema)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.projected_output_ordering
+            .first()
+            .map(|ordering| ordering.as_slice())
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition_index: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
+        let _timer = baseline_metrics.elapsed_compute().timer();
+
+        let io_time = Time::default();
+        let io_time_metric = Arc::new(Metric::new(
+            MetricValue::Time {
+                name: "io_time".into(),
+                time: io_time.clone(),
+            },
+            Some(partition_index),
+        ));
+        self.metrics.register(io_time_metric);
+
+        // get fs object from jni bridge resource
+        let resource_id = jni_new_string!(&self.fs_resource_id)?;
+        let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+        let fs_provider = Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time));
+
+        let projection = match self.base_config.file_column_projection_indices() {
+            Some(proj) => proj,
+            None => (0..self.base_config.file_schema.fields().len()).collect(),
+        };
+
+        let opener = OrcOpener {
+            projection: Arc::from(projection),
+            batch_size: context.session_config().batch_size(),
+            table_schema: self.base_config.file_schema.clone(),
+            pruning_predicate: self.pruning_predicate.clone(),
+            fs_provider,
+            bytes_scanned: MetricBuilder::new(&self.metrics)
+                .counter("bytes_scanned", partition_index),
+            stripes_pruned: MetricBuilder::new(&self.metrics)
+                .counter("stripes_pruned", partition_index),
+        };
+
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
+        let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+        let mut file_stream =
+            FileStream::new(&self.base_config, partition_index, opener, &self.metrics)?;
+        if conf::IGNORE_CORRUPTED_FILES.value()? {
+            file_stream = file_stream.with_on_error(OnError::Skip);
+        }
+        let mut stream = Box::pin(file_stream);
+        let context_cloned = context.clone();
+        let timed_stream = Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            once(async move {
+                context_cloned.output_with_sender(
+                    "OrcScan",
+                    stream.schema(),
+                    move |sender| async move {
+                        let mut timer = elapsed_compute.timer();
+                        while let Some(batch) = stream.next().await.transpose()? {
+                            sender.send(Ok(batch), Some(&mut timer)).await;
+                        }
+                        Ok(())
+                    },
+                )
+            })
+            .try_flatten(),
+        ));
+        context.coalesce_with_default_batch_size(timed_stream, &baseline_metrics)
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.projected_statistics.clone()
+    }
+}
+
+#[derive(Clone)]
+struct OrcOpener {
+    projection: Arc<[usize]>,
+    batch_size: usize,
+    table_schema: SchemaRef,
+    pruning_predicate: Option<Arc<PruningPredicate>>,
+    fs_provider: Arc<FsProvider>,
+    bytes_scanned: Count,
+    stripes_pruned: Count,
+}
+
+impl FileOpener for OrcOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let opener = self.clone();
+        Ok(Box::pin(async move {
+            let input = OrcFileInput::try_new(
+                &opener.fs_provider,
+                &file_meta,
+                opener.bytes_scanned.clone(),
+            )?;
+            let reader = OrcFileReader::try_new(input, file_meta.object_meta.size as u64)?;
+            let field_mapping = map_table_fields(&opener.table_schema, &reader.schema());
+
+            // a stripe belongs to the split containing its first byte
+            let mut stripes = reader
+                .stripes()
+                .iter()
+                .enumerate()
+                .filter(|(_, stripe)| match &file_meta.range {
+                    Some(range) => {
+                        let offset = stripe.offset() as i64;
+                        offset >= range.start && offset < range.end
+                    }
+                    None => true,
+                })
+                .map(|(stripe_idx, _)| stripe_idx)
+                .collect::<Vec<_>>();
+
+            if let Some(pruning_predicate) = &opener.pruning_predicate {
+                let stats = StripePruningStatistics {
+                    reader: &reader,
+                    stripes: &stripes,
+                    table_schema: &opener.table_schema,
+                    field_mapping: &field_mapping,
+                };
+                match pruning_predicate.prune(&stats) {
+                    Ok(keep) => {
+                        let num_stripes = stripes.len();
+                        stripes = stripes
+                            .into_iter()
+                            .zip(keep)
+                            .filter(|(_, keep)| *keep)
+                            .map(|(stripe_idx, _)| stripe_idx)
+                            .collect();
+                        opener.stripes_pruned.add(num_stripes - stripes.len());
+                    }
+                    Err(e) => log::warn!("error pruning orc stripes: {e}"),
+                }
+            }
+
+            // only file columns used by the projected table columns are read
+            let projected_mapping = opener
+                .projection
+                .iter()
+                .map(|&i| field_mapping[i])
+                .collect::<Vec<_>>();
+            let mut file_projection = projected_mapping
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>();
+            file_projection.sort();
+            file_projection.dedup();
+            let output_schema = Arc::new(opener.table_schema.project(&opener.projection)?);
+
+            // stripes are decoded incrementally, at most one batch of each stripe
+            // is materialized at a time
+            let mut stripes = stripes.into_iter();
+            let mut stripe_reader: Option<StripeReader> = None;
+            let batches = std::iter::from_fn(move || loop {
+                if stripe_reader.is_none() {
+                    let stripe_idx = stripes.next()?;
+                    match reader.stripe_reader(stripe_idx, &file_projection) {
+                        Ok(reader) => stripe_reader = Some(reader),
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                match stripe_reader
+                    .as_mut()
+                    .unwrap()
+                    .next_batch(opener.batch_size)
+                {
+                    Ok(Some(file_batch)) => {
+                        return Some(adapt_file_batch(
+                            &file_batch,
+                            &output_schema,
+                            &projected_mapping,
+                            &file_projection,
+                        ));
+                    }
+                    Ok(None) => stripe_reader = None,
+                    Err(e) => return Some(Err(e)),
+                }
+            })
+            .map(|batch| batch.map_err(|e| ArrowError::ExternalError(Box::new(e))));
+            Ok(futures::stream::iter(batches).boxed())
+        }))
+    }
+}
+
+struct OrcFileInput {
+    input: FsDataInputStream,
+    bytes_scanned: Count,
+}
+
+impl OrcFileInput {
+    fn try_new(
+        fs_provider: &FsProvider,
+        file_meta: &FileMeta,
+        bytes_scanned: Count,
+    ) -> Result<Self> {
+        let filename = file_meta.object_meta.location.filename();
+        let path = BASE64_URL_SAFE_NO_PAD
+            .decode(filename.expect("missing filename"))
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .or_else(|_| df_execution_err!("cannot decode filename: {filename:?}"))?;
+        let fs = fs_provider.provide(&path)?;
+        Ok(Self {
+            input: fs.open(&path)?,
+            bytes_scanned,
+        })
+    }
+}
+
+impl OrcInput for OrcFileInput {
+    fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>> {
+        let mut bytes = vec![0u8; (range.end - range.start) as usize];
+        self.input.read_fully(range.start, &mut bytes)?;
+        self.bytes_scanned.add(bytes.len());
+        Ok(bytes)
+    }
+}
+
+// min/max and null count statistics of the selected stripes
+struct StripePruningStatistics<'a> {
+    reader: &'a OrcFileReader<OrcFileInput>,
+    stripes: &'a [usize],
+    table_schema: &'a Schema,
+    field_mapping: &'a [Option<usize>],
+}
+
+impl StripePruningStatistics<'_> {
+    fn column_stats(&self, column: &Column) -> Option<(&DataType, Vec<Option<&ColumnStatistics>>)> {
+        let table_idx = self.table_schema.index_of(&column.name).ok()?;
+        let file_idx = self.field_mapping[table_idx]?;
+        let data_type = self.table_schema.field(table_idx).data_type();
+
+        // statistics are only used if the column is not evolved
+        if self.reader.schema().field(file_idx).data_type() != data_type {
+            return None;
+        }
+        let stats = self
+            .stripes
+            .iter()
+            .map(|&stripe_idx| self.reader.stripe_column_statistics(stripe_idx, file_idx))
+            .collect();
+        Some((data_type, stats))
+    }
+
+    fn min_max_values(&self, column: &Column, is_min: bool) -> Option<ArrayRef> {
+        let (data_type, stats) = self.column_stats(column)?;
+        let array: ArrayRef = match data_type {
+            DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+                let values = stats.iter().map(|stats| {
+                    let int_stats = stats?.int_statistics.as_ref()?;
+                    pick(is_min, int_stats.minimum, int_stats.maximum)
+                });
+                arrow::compute::cast(&Int64Array::from_iter(values), data_type).ok()?
+            }
+            DataType::Float32 | DataType::Float64 => {
+                let values = stats.iter().map(|stats| {
+                    let double_stats = stats?.double_statistics.as_ref()?;
+                    pick(is_min, double_stats.minimum, double_stats.maximum)
+                });
+                arrow::compute::cast(&Float64Array::from_iter(values), data_type).ok()?
+            }
+            DataType::Utf8 => Arc::new(StringArray::from_iter(stats.iter().map(|stats| {
+                let string_stats = stats?.string_statistics.as_ref()?;
+                pick(is_min, &string_stats.minimum, &string_stats.maximum).clone()
+            }))),
+            DataType::Date32 => Arc::new(Date32Array::from_iter(stats.iter().map(|stats| {
+                let date_stats = stats?.date_statistics.as_ref()?;
+                pick(is_min, date_stats.minimum, date_stats.maximum)
+            }))),
+            &DataType::Decimal128(precision, scale) => Arc::new(
+                Decimal128Array::from_iter(stats.iter().map(|stats| {
+                    let decimal_stats = stats?.decimal_statistics.as_ref()?;
+                    let value =
+                        pick(is_min, &decimal_stats.minimum, &decimal_stats.maximum).as_ref()?;
+                    parse_decimal(value, scale)
+                }))
+                .with_precision_and_scale(precision, scale)
+                .ok()?,
+            ),
+            _ => return None,
+        };
+        Some(array)
+    }
+}
+
+impl PruningStatistics for StripePruningStatistics<'_> {
+    fn min_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.min_max_values(column, true)
+    }
+
+    fn max_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.min_max_values(column, false)
+    }
+
+    fn num_containers(&self) -> usize {
+        self.stripes.len()
+    }
+
+    fn null_counts(&self, column: &Column) -> Option<ArrayRef> {
+        let (_, stats) = self.column_stats(column)?;
+        let null_counts = self.stripes.iter().zip(stats).map(|(&stripe_idx, stats)| {
+            let num_rows = self.reader.stripes()[stripe_idx].number_of_rows();
+            Some(num_rows.saturating_sub(stats?.number_of_values?))
+        });
+        Some(Arc::new(UInt64Array::from_iter(null_counts)))
+    }
+}
+
+fn pick<T>(is_min: bool, min: T, max: T) -> T {
+    if is_min {
+        min
+    } else {
+        max
+    }
+}
+
+// parses a plain decimal string of the statistics, returns None if the value
+// cannot be exactly represented in the specified scale
+fn parse_decimal(s: &str, scale: i8) -> Option<i128> {
+    if scale < 0 {
+        return None;
+    }
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.len() > scale as usize
+        || !int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut digits = format!("{int_part}{frac_part}");
+    digits.extend(std::iter::repeat('0').take(scale as usize - frac_part.len()));
+    let value = digits.parse::<i128>().ok()?;
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::orc_exec::parse_decimal;
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(parse_decimal("123.45", 2), Some(12345));
+        assert_eq!(parse_decimal("-1.5", 3), Some(-1500));
+        assert_eq!(parse_decimal("7", 0), Some(7));
+        assert_eq!(parse_decimal("1.230", 2), Some(123));
+        assert_eq!(parse_decimal("1.234", 2), None);
+        assert_eq!(parse_decimal("1E+3", 2), None);
+    }
+}
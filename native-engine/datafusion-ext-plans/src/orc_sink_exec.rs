@@ -0,0 +1,336 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution plan for writing hive ORC tables
+
+use std::{any::Any, fmt::Formatter, sync::Arc};
+
+use arrow::{datatypes::SchemaRef, record_batch::RecordBatch};
+use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
+use datafusion::{
+    common::{Result, ScalarValue, Statistics},
+    execution::context::TaskContext,
+    physical_expr::PhysicalSortExpr,
+    physical_plan::{
+        metrics::{BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricValue, MetricsSet, Time},
+        stream::RecordBatchStreamAdapter,
+        DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning,
+        SendableRecordBatchStream,
+    },
+};
+use datafusion_ext_commons::{
+    hadoop_fs::FsProvider,
+    orc::{
+        proto::CompressionKind,
+        writer::{OrcFileWriter, OrcWriterOptions},
+    },
+};
+use futures::{stream::once, TryStreamExt};
+
+use crate::common::dyn_part_sink::{
+    create_part_file, execute_dyn_part_sink, DynPartSinkOutput, FSDataWriter, PartFileStat,
+    PartWriter,
+};
+
+#[derive(Debug)]
+pub struct OrcSinkExec {
+    fs_resource_id: String,
+    input: Arc<dyn ExecutionPlan>,
+    num_dyn_parts: usize,
+    hive_schema: SchemaRef,
+    props: Vec<(String, String)>,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl OrcSinkExec {
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        fs_resource_id: String,
+        num_dyn_parts: usize,
+        hive_schema: SchemaRef,
+        props: Vec<(String, String)>,
+    ) -> Self {
+        Self {
+            input,
+            fs_resource_id,
+            num_dyn_parts,
+            hive_schema,
+            props,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl DisplayAs for OrcSinkExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "OrcSink")
+    }
+}
+
+impl ExecutionPlan for OrcSinkExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::new(
+            children[0].clone(),
+            self.fs_resource_id.clone(),
+            self.num_dyn_parts,
+            self.hive_schema.clone(),
+            self.props.clone(),
+        )))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let metrics = BaselineMetrics::new(&self.metrics, partition);
+        let elapsed_compute = metrics.elapsed_compute().clone();
+        let _timer = elapsed_compute.timer();
+
+        // register io_time metric
+        let io_time = Time::default();
+        let io_time_metric = Arc::new(Metric::new(
+            MetricValue::Time {
+                name: "io_time".into(),
+                time: io_time.clone(),
+            },
+            Some(partition),
+        ));
+        self.metrics.register(io_time_metric);
+
+        // register bytes_written metric
+        let bytes_written = Count::default();
+        let bytes_written_metric = Arc::new(Metric::new(
+            MetricValue::Count {
+                name: "bytes_written".into(),
+                count: bytes_written.clone(),
+            },
+            Some(partition),
+        ));
+        self.metrics.register(bytes_written_metric);
+
+        let fs_provider = {
+            let resource_id = jni_new_string!(&self.fs_resource_id)?;
+            let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+            FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time)
+        };
+        let orc_sink_context = Arc::new(OrcSinkContext {
+            fs_provider,
+            hive_schema: self.hive_schema.clone(),
+            options: parse_writer_options(&self.props),
+        });
+
+        let input = self.input.execute(partition, context.clone())?;
+        let output = Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            once(execute_dyn_part_sink::<OrcPartWriter>(
+                context,
+                orc_sink_context,
+                self.hive_schema.clone(),
+                self.num_dyn_parts,
+                None,
+                DynPartSinkOutput::Identity,
+                input,
+                metrics,
+                bytes_written,
+            ))
+            .try_flatten(),
+        ));
+        Ok(output)
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+struct OrcSinkContext {
+    fs_provider: FsProvider,
+    hive_schema: SchemaRef,
+    options: OrcWriterOptions,
+}
+
+// maps orc table properties to writer options, unknown properties are ignored
+fn parse_writer_options(prop_kvs: &[(String, String)]) -> OrcWriterOptions {
+    let mut options = OrcWriterOptions::default();
+
+    macro_rules! setprop {
+        ($key:expr, $value:expr, $tnum:ty, $field:expr) => {{
+            if let Ok(value) = $value.trim().parse::<$tnum>() {
+                $field = value;
+            } else {
+                log::warn!("invalid orc prop value: {}={}", $key, $value);
+            }
+        }};
+    }
+
+    for (key, value) in prop_kvs {
+        match key.as_ref() {
+            "orc.compress" => {
+                options.compression.kind = match value.to_ascii_uppercase().as_ref() {
+                    "NONE" => CompressionKind::None,
+                    "ZLIB" => CompressionKind::Zlib,
+                    "SNAPPY" => CompressionKind::Snappy,
+                    "LZ4" => CompressionKind::Lz4,
+                    "ZSTD" => CompressionKind::Zstd,
+                    _ => {
+                        log::warn!("unsupported orc compression: {}", value);
+                        CompressionKind::Zlib
+                    }
+                }
+            }
+            "orc.compress.size" => setprop!(key, value, usize, options.compression.block_size),
+            "orc.stripe.size" => setprop!(key, value, usize, options.stripe_size),
+            "orc.row.index.stride" => setprop!(key, value, usize, options.row_index_stride),
+            "orc.bloom.filter.fpp" => setprop!(key, value, f64, options.bloom_filter_fpp),
+            "orc.bloom.filter.columns" => {
+                options.bloom_filter_columns = value
+                    .split(',')
+                    .map(|column| column.trim().to_string())
+                    .filter(|column| !column.is_empty())
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+    options
+}
+
+struct OrcPartWriter {
+    path: String,
+    orc_writer: OrcFileWriter<FSDataWriter>,
+    part_values: Vec<ScalarValue>,
+    rows_written: Count,
+    bytes_written: Count,
+}
+
+impl PartWriter for OrcPartWriter {
+    type SinkContext = OrcSinkContext;
+
+    const SINK_NAME: &'static str = "OrcSink";
+    const FILE_EXTENSION: &'static str = ".orc";
+
+    fn try_new(
+        orc_sink_context: Arc<OrcSinkContext>,
+        part_values: &[ScalarValue],
+        path: String,
+    ) -> Result<Self> {
+        if !part_values.is_empty() {
+            log::info!("start outputting dynamic partition: {part_values:?}");
+        }
+        let bytes_written = Count::new();
+        let rows_written = Count::new();
+        let data_writer = create_part_file(&orc_sink_context.fs_provider, &path, &bytes_written)?;
+        let orc_writer = OrcFileWriter::try_new(
+            data_writer,
+            orc_sink_context.hive_schema.clone(),
+            orc_sink_context.options.clone(),
+        )?;
+        Ok(Self {
+            path,
+            orc_writer,
+            part_values: part_values.to_vec(),
+            rows_written,
+            bytes_written,
+        })
+    }
+
+    fn part_values(&self) -> &[ScalarValue] {
+        &self.part_values
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.orc_writer.write(batch)?;
+        self.rows_written.add(batch.num_rows());
+        Ok(())
+    }
+
+    fn num_rows(&self) -> usize {
+        self.rows_written.value()
+    }
+
+    fn file_size(&self) -> usize {
+        self.bytes_written.value() + self.orc_writer.buffered_size()
+    }
+
+    fn mem_size(&self) -> usize {
+        self.orc_writer.buffered_size()
+    }
+
+    fn close(self) -> Result<PartFileStat> {
+        self.orc_writer.close()?.close()?;
+        Ok(PartFileStat {
+            path: self.path,
+            part_values: self.part_values,
+            num_rows: self.rows_written.value(),
+            num_bytes: self.bytes_written.value(),
+            column_stats: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion_ext_commons::orc::proto::CompressionKind;
+
+    use crate::orc_sink_exec::parse_writer_options;
+
+    #[test]
+    fn test_parse_writer_options() {
+        let props = [
+            ("orc.compress", "snappy"),
+            ("orc.stripe.size", "1048576"),
+            ("orc.row.index.stride", "invalid"),
+            ("orc.bloom.filter.columns", "a, b,"),
+            ("orc.bloom.filter.fpp", "0.05"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<Vec<_>>();
+
+        let options = parse_writer_options(&props);
+        assert_eq!(options.compression.kind, CompressionKind::Snappy);
+        assert_eq!(options.stripe_size, 1048576);
+        assert_eq!(options.row_index_stride, 10000);
+        assert_eq!(options.bloom_filter_columns, vec!["a", "b"]);
+        assert_eq!(options.bloom_filter_fpp, 0.05);
+    }
+}
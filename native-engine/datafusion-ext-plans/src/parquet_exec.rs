@@ -17,40 +17,80 @@
 
 //! Execution plan for reading Parquet files
 
-use std::{any::Any, fmt, fmt::Formatter, ops::Range, sync::Arc};
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    fmt::Formatter,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst},
+        Arc, Weak,
+    },
+};
 
 use arrow::{
-    array::ArrayRef,
-    datatypes::{DataType, SchemaRef},
+    array::{
+        ArrayRef, BooleanArray, Decimal128Array, Float32Array, Float64Array, Int32Array,
+        Int64Array, StringArray, TimestampMicrosecondArray, UInt64Array,
+    },
+    compute::{cast, filter_record_batch},
+    datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
+    error::ArrowError,
+    record_batch::{RecordBatch, RecordBatchOptions},
+    row::{RowConverter, SortField},
 };
+use async_trait::async_trait;
 use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
 use blaze_jni_bridge::{
-    conf, conf::BooleanConf, jni_call_static, jni_new_global_ref, jni_new_string,
+    conf,
+    conf::{BooleanConf, IntConf},
+    jni_call_static, jni_new_global_ref, jni_new_string,
 };
-use bytes::Bytes;
+use bytes::{Buf, Bytes};
 use datafusion::{
-    common::DataFusionError,
-    datasource::physical_plan::{
-        parquet::{page_filter::PagePruningPredicate, ParquetOpener},
-        FileMeta, FileScanConfig, FileStream, OnError, ParquetFileMetrics,
-        ParquetFileReaderFactory,
+    common::{
+        cast::{as_int64_array, as_string_array},
+        DataFusionError, ScalarValue,
+    },
+    datasource::{
+        listing::FileRange,
+        physical_plan::{
+            FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream, OnError,
+            ParquetFileMetrics, ParquetFileReaderFactory,
+        },
     },
     error::Result,
     execution::context::TaskContext,
+    logical_expr::Operator,
     parquet::{
-        arrow::async_reader::{fetch_parquet_metadata, AsyncFileReader},
+        arrow::{
+            arrow_reader::ArrowReaderOptions,
+            async_reader::{fetch_parquet_metadata, AsyncFileReader, MetadataLoader},
+            ParquetRecordBatchStreamBuilder, ProjectionMask,
+        },
+        basic::{ConvertedType, Type as PhysicalType},
+        bloom_filter::Sbbf,
+        data_type::{ByteArray, FixedLenByteArray},
         errors::ParquetError,
-        file::metadata::ParquetMetaData,
+        file::{
+            metadata::{ColumnChunkMetaData, ParquetMetaData, RowGroupMetaData},
+            reader::{ChunkReader, Length},
+            statistics::Statistics as ParquetStatistics,
+        },
+        schema::types::ColumnDescriptor,
     },
-    physical_optimizer::pruning::PruningPredicate,
+    physical_expr::expressions::{BinaryExpr, Column, InListExpr, Literal},
+    physical_optimizer::pruning::{PruningPredicate, PruningStatistics},
     physical_plan::{
         expressions::PhysicalSortExpr,
         metrics::{
-            BaselineMetrics, ExecutionPlanMetricsSet, MetricBuilder, MetricValue, MetricsSet, Time,
+            BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricValue,
+            MetricsSet, Time,
         },
         stream::RecordBatchStreamAdapter,
-        DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, PhysicalExpr,
-        RecordBatchStream, SendableRecordBatchStream, Statistics,
+        ColumnStatistics, DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning,
+        PhysicalExpr, RecordBatchStream, SendableRecordBatchStream, Statistics,
     },
 };
 use datafusion_ext_commons::{
@@ -59,11 +99,25 @@ use datafusion_ext_commons::{
     streams::coalesce_stream::CoalesceInput,
 };
 use fmt::Debug;
-use futures::{future::BoxFuture, stream::once, FutureExt, StreamExt, TryFutureExt, TryStreamExt};
-use object_store::ObjectMeta;
+use futures::{future::BoxFuture, stream::once, FutureExt, StreamExt, TryStreamExt};
+use object_store::{path::Path, ObjectMeta};
 use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use tokio::{sync::OnceCell as AsyncOnceCell, task::JoinHandle};
 
-use crate::common::output::TaskOutputter;
+use crate::{
+    common::{
+        bucketing::{bucket_id_of_file, prune_buckets, BucketHash, BucketSpec},
+        datetime_rebase::{has_datetime, RebaseConf, RebaseMode},
+        output::TaskOutputter,
+        parquet_metadata_cache::{ParquetMetadataCache, ParquetMetadataCacheKey},
+        row_deletes::{
+            decode_deletion_vector, DeleteFile, DeletionVector, EqualityDeletes, RowDeletes,
+        },
+        schema_adapter::{adapt_file_batch, map_table_fields, match_file_field},
+    },
+    memmgr::{MemConsumer, MemConsumerInfo, MemManager},
+};
 
 #[no_mangle]
 fn schema_adapter_cast_column(
@@ -82,26 +136,35 @@ pub struct ParquetExec {
     projected_schema: SchemaRef,
     projected_output_ordering: Vec<Vec<PhysicalSortExpr>>,
     metrics: ExecutionPlanMetricsSet,
-    predicate: Option<Arc<dyn PhysicalExpr>>,
     pruning_predicate: Option<Arc<PruningPredicate>>,
-    page_pruning_predicate: Option<Arc<PagePruningPredicate>>,
+    bloom_filter_predicate: Option<Arc<BloomFilterPredicate>>,
+    metadata_columns: Vec<FileMetadataColumn>,
+    rebase_conf: RebaseConf,
+    bucket_spec: Option<BucketSpec>,
 }
 
 impl ParquetExec {
     /// Create a new Parquet reader execution plan provided file list and
-    /// schema.
+    /// schema. metadata columns are appended to the output after partition
+    /// columns. ancient dates/timestamps are rebased with `rebase_conf`.
+    /// files of bucketed tables are grouped by their bucket ids, so that the
+    /// output is hash partitioned by the bucket columns.
     pub fn new(
         base_config: FileScanConfig,
         fs_resource_id: String,
         predicate: Option<Arc<dyn PhysicalExpr>>,
+        metadata_columns: Vec<(String, FileMetadataColumn)>,
+        rebase_conf: RebaseConf,
+        bucket_spec: Option<BucketSpec>,
     ) -> Self {
         let metrics = ExecutionPlanMetricsSet::new();
         let predicate_creation_errors =
             MetricBuilder::new(&metrics).global_counter("num_predicate_creation_errors");
 
+        let (base_config, bucket_spec) =
+            with_bucket_groups(base_config, bucket_spec, predicate.as_ref());
         let file_schema = &base_config.file_schema;
         let pruning_predicate = predicate
-            .clone()
             .and_then(|predicate_expr| {
                 match PruningPredicate::try_new(predicate_expr, file_schema.clone()) {
                     Ok(pruning_predicate) => Some(Arc::new(pruning_predicate)),
@@ -114,17 +177,14 @@ impl ParquetExec {
             })
             .filter(|p| !p.allways_true());
 
-        let page_pruning_predicate = predicate.as_ref().and_then(|predicate_expr| {
-            match PagePruningPredicate::try_new(predicate_expr, file_schema.clone()) {
-                Ok(pruning_predicate) => Some(Arc::new(pruning_predicate)),
-                Err(e) => {
-                    log::warn!("Could not create page pruning predicate: {}", e);
-                    predicate_creation_errors.add(1);
-                    None
-                }
-            }
-        });
+        let bloom_filter_predicate = pruning_predicate
+            .as_ref()
+            .and_then(|pruning_predicate| {
+                BloomFilterPredicate::try_new(pruning_predicate.orig_expr())
+            })
+            .map(Arc::new);
 
+        let base_config = with_metadata_columns(base_config, &metadata_columns);
         let (projected_schema, projected_statistics, projected_output_ordering) =
             base_config.project();
 
@@ -135,13 +195,143 @@ impl ParquetExec {
             projected_statistics,
             projected_output_ordering,
             metrics,
-            predicate,
             pruning_predicate,
-            page_pruning_predicate,
+            bloom_filter_predicate,
+            metadata_columns: metadata_columns
+                .into_iter()
+                .map(|(_, column)| column)
+                .collect(),
+            rebase_conf,
+            bucket_spec,
         }
     }
 }
 
+// regroups files by their bucket ids, files of buckets not matching the
+// predicate are pruned. the scan falls back to non-bucketed if the bucket id
+// of any file is unknown
+fn with_bucket_groups(
+    mut config: FileScanConfig,
+    bucket_spec: Option<BucketSpec>,
+    predicate: Option<&Arc<dyn PhysicalExpr>>,
+) -> (FileScanConfig, Option<BucketSpec>) {
+    let bucket_spec = match bucket_spec {
+        Some(bucket_spec) => bucket_spec,
+        None => return (config, None),
+    };
+    let num_buckets = bucket_spec.num_buckets;
+    if config.file_groups.len() != num_buckets {
+        log::warn!(
+            "number of file groups {} mismatches number of buckets {num_buckets}, \
+                scanning as non-bucketed",
+            config.file_groups.len(),
+        );
+        return (config, None);
+    }
+
+    let matched_buckets = predicate
+        .map(|predicate| prune_buckets(predicate, &bucket_spec, &config.file_schema))
+        .transpose()
+        .unwrap_or_else(|e| {
+            log::warn!("Could not prune buckets: {e}");
+            None
+        })
+        .flatten();
+
+    let mut file_groups = vec![vec![]; num_buckets];
+    for file in config.file_groups.iter().flatten() {
+        let path = BASE64_URL_SAFE_NO_PAD
+            .decode(file.object_meta.location.filename().unwrap_or_default())
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .unwrap_or_default();
+        let bucket_id = match bucket_id_of_file(&path).filter(|&id| id < num_buckets) {
+            Some(bucket_id) => bucket_id,
+            None => {
+                log::warn!("cannot get bucket id of file: {path}, scanning as non-bucketed");
+                return (config, None);
+            }
+        };
+        if matched_buckets
+            .as_ref()
+            .map(|matched| matched[bucket_id])
+            .unwrap_or(true)
+        {
+            file_groups[bucket_id].push(file.clone());
+        }
+    }
+    config.file_groups = file_groups;
+    (config, Some(bucket_spec))
+}
+
+/// Hidden columns synthesized from the metadata of scanned files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileMetadataColumn {
+    FilePath,
+    FileName,
+    FileSize,
+    FileModificationTime,
+    /// position of the row in the file, counted from the first row group
+    RowIndex,
+}
+
+impl FileMetadataColumn {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            FileMetadataColumn::FilePath | FileMetadataColumn::FileName => DataType::Utf8,
+            FileMetadataColumn::FileSize | FileMetadataColumn::RowIndex => DataType::Int64,
+            FileMetadataColumn::FileModificationTime => {
+                DataType::Timestamp(TimeUnit::Microsecond, None)
+            }
+        }
+    }
+}
+
+// metadata columns are scanned as extra file columns after the data columns,
+// so that file stream places them after partition columns in the output
+fn with_metadata_columns(
+    mut config: FileScanConfig,
+    metadata_columns: &[(String, FileMetadataColumn)],
+) -> FileScanConfig {
+    if metadata_columns.is_empty() {
+        return config;
+    }
+    let num_file_fields = config.file_schema.fields().len();
+    let num_metadata_columns = metadata_columns.len();
+    let mut fields = config.file_schema.fields().to_vec();
+    fields.extend(
+        metadata_columns
+            .iter()
+            .map(|(name, column)| Arc::new(Field::new(name, column.data_type(), true))),
+    );
+    config.file_schema = Arc::new(Schema::new_with_metadata(
+        fields,
+        config.file_schema.metadata().clone(),
+    ));
+
+    // partition columns are indexed after all file columns
+    let projection = config
+        .projection
+        .take()
+        .unwrap_or_else(|| (0..num_file_fields + config.table_partition_cols.len()).collect());
+    let mut projection = projection
+        .into_iter()
+        .map(|idx| {
+            if idx >= num_file_fields {
+                idx + num_metadata_columns
+            } else {
+                idx
+            }
+        })
+        .collect::<Vec<_>>();
+    projection.extend(num_file_fields..num_file_fields + num_metadata_columns);
+    config.projection = Some(projection);
+
+    if let Some(column_statistics) = &mut config.statistics.column_statistics {
+        column_statistics.extend((0..num_metadata_columns).map(|_| ColumnStatistics::default()));
+    }
+    config
+}
+
 impl DisplayAs for ParquetExec {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> fmt::Result {
         let limit = self.base_config.limit;
@@ -180,7 +370,32 @@ impl ExecutionPlan for ParquetExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+        let num_partitions = self.base_config.file_groups.len();
+
+        // bucketed output is hash partitioned only if all bucket columns are projected,
+        // and the buckets are hashed like shuffle partitions
+        let bucket_spec = self
+            .bucket_spec
+            .as_ref()
+            .filter(|bucket_spec| bucket_spec.hash == BucketHash::Murmur3);
+        if let Some(bucket_spec) = bucket_spec {
+            let hash_exprs = bucket_spec
+                .bucket_columns
+                .iter()
+                .map(|&bucket_column| {
+                    let idx = match &self.base_config.projection {
+                        Some(projection) => projection.iter().position(|&i| i == bucket_column),
+                        None => Some(bucket_column),
+                    }?;
+                    let name = self.projected_schema.field(idx).name();
+                    Some(Arc::new(Column::new(name, idx)) as Arc<dyn PhysicalExpr>)
+                })
+                .collect::<Option<Vec<_>>>();
+            if let Some(hash_exprs) = hash_exprs {
+                return Partitioning::Hash(hash_exprs, num_partitions);
+            }
+        }
+        Partitioning::UnknownPartitioning(num_partitions)
     }
 
     fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
@@ -229,6 +444,21 @@ impl ExecutionPlan for ParquetExec {
             None => (0..self.base_config.file_schema.fields().len()).collect(),
         };
 
+        let bloom_filter_predicate = if conf::PARQUET_BLOOM_FILTER_ENABLE.value()? {
+            self.bloom_filter_predicate.clone()
+        } else {
+            None
+        };
+        let metadata_cache = ParquetMetadataCache::get_or_init()?;
+        let enable_page_index = conf::PARQUET_PAGE_INDEX_ENABLE.value()?;
+        let read_options = ParquetReadOptions::try_from_conf()?;
+        let read_ahead_mem = Arc::new(ReadAheadMemConsumer {
+            name: format!("ParquetReadAhead[partition={partition_index}]"),
+            mem_consumer_info: None,
+            prefetched_size: AtomicUsize::new(0),
+        });
+        MemManager::register_consumer(read_ahead_mem.clone(), false);
+
         let batch_size = context.session_config().batch_size();
         let sub_batch_size = batch_size / batch_size.ilog2() as usize;
         let opener = ParquetOpener {
@@ -236,16 +466,20 @@ impl ExecutionPlan for ParquetExec {
             projection: Arc::from(projection),
             batch_size: sub_batch_size,
             limit: self.base_config.limit,
-            predicate: self.predicate.clone(),
-            pruning_predicate: self.pruning_predicate.clone(),
-            page_pruning_predicate: self.page_pruning_predicate.clone(),
             table_schema: self.base_config.file_schema.clone(),
-            metadata_size_hint: None,
+            metadata_columns: Arc::from(self.metadata_columns.as_slice()),
+            rebase_conf: Arc::new(self.rebase_conf.clone()),
+            pruning_predicate: self.pruning_predicate.clone(),
+            bloom_filter_predicate,
             metrics: self.metrics.clone(),
-            parquet_file_reader_factory: Arc::new(FsReaderFactory::new(fs_provider)),
-            pushdown_filters: false, // still buggy
-            reorder_filters: false,
-            enable_page_index: false,
+            reader_factory: Arc::new(FsReaderFactory::new(
+                fs_provider,
+                metadata_cache,
+                enable_page_index,
+                read_options,
+                read_ahead_mem,
+            )),
+            delete_file_batches: Arc::default(),
         };
 
         let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
@@ -286,17 +520,815 @@ impl ExecutionPlan for ParquetExec {
     }
 }
 
+#[derive(Clone)]
+struct ParquetOpener {
+    partition_index: usize,
+    projection: Arc<[usize]>,
+    batch_size: usize,
+    limit: Option<usize>,
+    table_schema: SchemaRef,
+    metadata_columns: Arc<[FileMetadataColumn]>,
+    rebase_conf: Arc<RebaseConf>,
+    pruning_predicate: Option<Arc<PruningPredicate>>,
+    bloom_filter_predicate: Option<Arc<BloomFilterPredicate>>,
+    metrics: ExecutionPlanMetricsSet,
+    reader_factory: Arc<FsReaderFactory>,
+
+    // delete files are usually attached to many data files of the task, their
+    // rows are read once and shared, keyed by location and read columns
+    delete_file_batches: Arc<Mutex<HashMap<(Path, Vec<String>), DeleteFileBatches>>>,
+}
+
+type DeleteFileBatches = Arc<AsyncOnceCell<Arc<Vec<RecordBatch>>>>;
+
+impl FileOpener for ParquetOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let opener = self.clone();
+        let file_range = file_meta.range.clone();
+        let filename = file_meta
+            .object_meta
+            .location
+            .filename()
+            .unwrap_or("__default_filename__")
+            .to_string();
+        let file_metrics = ParquetFileMetrics::new(self.partition_index, &filename, &self.metrics);
+        let row_groups_pruned_bloom_filter = MetricBuilder::new(&self.metrics)
+            .with_new_label("filename", filename)
+            .counter("row_groups_pruned_bloom_filter", self.partition_index);
+        let delete_files = file_meta
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.downcast_ref::<Vec<DeleteFile>>())
+            .cloned()
+            .unwrap_or_default();
+        let file_reader =
+            self.reader_factory
+                .create_file_reader(self.partition_index, file_meta, &self.metrics);
+
+        Ok(Box::pin(async move {
+            let options = ArrowReaderOptions::new().with_page_index(file_reader.enable_page_index);
+            let reader = ParquetFileReaderRef(file_reader.clone());
+            let mut builder =
+                ParquetRecordBatchStreamBuilder::new_with_options(reader, options).await?;
+            let file_schema = builder.schema().clone();
+            let num_table_fields =
+                opener.table_schema.fields().len() - opener.metadata_columns.len();
+            let data_schema = Arc::new(Schema::new(
+                opener.table_schema.fields()[..num_table_fields].to_vec(),
+            ));
+            let field_mapping = map_table_fields(&data_schema, &file_schema);
+            let leaf_offsets = file_schema
+                .fields()
+                .iter()
+                .scan(0, |offset, field| {
+                    let leaf_offset = *offset;
+                    *offset += num_leaves(field.data_type());
+                    Some(leaf_offset)
+                })
+                .collect::<Vec<_>>();
+
+            // metadata columns are always projected after data columns
+            let data_projection = opener
+                .projection
+                .iter()
+                .copied()
+                .filter(|&table_idx| table_idx < num_table_fields)
+                .collect::<Vec<_>>();
+            let metadata_projection = opener
+                .projection
+                .iter()
+                .filter_map(|&table_idx| table_idx.checked_sub(num_table_fields))
+                .map(|metadata_idx| opener.metadata_columns[metadata_idx])
+                .collect::<Vec<_>>();
+
+            // only leaf columns used by the projected table columns are read,
+            // nested fields missing in the table types are pruned
+            let mut leaves = vec![];
+            let projected_mapping = data_projection
+                .iter()
+                .map(|&table_idx| {
+                    let file_idx = field_mapping[table_idx]?;
+                    let num_selected = leaves.len();
+                    select_leaves(
+                        file_schema.field(file_idx).data_type(),
+                        data_schema.field(table_idx).data_type(),
+                        leaf_offsets[file_idx],
+                        &mut leaves,
+                    );
+                    // columns without any matched nested fields are read as nulls
+                    (leaves.len() > num_selected).then_some(file_idx)
+                })
+                .collect::<Vec<_>>();
+            let mut file_projection = projected_mapping
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>();
+
+            // deletes of merge-on-read tables are applied to decoded rows by
+            // their positions, equality columns are also read for matching
+            // equality deletes
+            let row_deletes = opener
+                .load_row_deletes(&delete_files, &file_reader.file_path()?, &file_schema)
+                .await?;
+            for file_idx in row_deletes.required_file_indices() {
+                let leaf_offset = leaf_offsets[file_idx];
+                let num_column_leaves = num_leaves(file_schema.field(file_idx).data_type());
+                leaves.extend(leaf_offset..leaf_offset + num_column_leaves);
+                file_projection.push(file_idx);
+            }
+            let mut row_deletes = Some(row_deletes).filter(|deletes| !deletes.is_empty());
+            file_projection.sort();
+            file_projection.dedup();
+            let data_output_schema = Arc::new(data_schema.project(&data_projection)?);
+            let output_schema = Arc::new(opener.table_schema.project(&opener.projection)?);
+
+            let metadata = builder.metadata().clone();
+            let mut row_groups = metadata
+                .row_groups()
+                .iter()
+                .enumerate()
+                .filter(|(_, row_group)| row_group_in_range(row_group, file_range.as_ref()))
+                .map(|(row_group_idx, _)| row_group_idx)
+                .collect::<Vec<_>>();
+
+            // dates and timestamps written in the hybrid calendar are rebased
+            // after decoding. statistics of legacy files are in the hybrid
+            // calendar, so they are not used for pruning datetime columns.
+            let file_metadata = metadata.file_metadata();
+            let rebase_spec = opener.rebase_conf.read_spec(
+                file_metadata.key_value_metadata(),
+                file_metadata.created_by(),
+            );
+            let prune_by_stats = rebase_spec.datetime_mode() != RebaseMode::Legacy
+                || !data_schema
+                    .fields()
+                    .iter()
+                    .any(|field| has_datetime(field.data_type()));
+
+            if let Some(pruning_predicate) =
+                opener.pruning_predicate.as_ref().filter(|_| prune_by_stats)
+            {
+                let stats = RowGroupPruningStatistics {
+                    metadata: &metadata,
+                    row_groups: &row_groups,
+                    table_schema: &data_schema,
+                    file_schema: &file_schema,
+                    field_mapping: &field_mapping,
+                    leaf_offsets: &leaf_offsets,
+                };
+                match pruning_predicate.prune(&stats) {
+                    Ok(keep) => {
+                        let num_row_groups = row_groups.len();
+                        row_groups = row_groups
+                            .into_iter()
+                            .zip(keep)
+                            .filter(|(_, keep)| *keep)
+                            .map(|(row_group_idx, _)| row_group_idx)
+                            .collect();
+                        file_metrics
+                            .row_groups_pruned
+                            .add(num_row_groups - row_groups.len());
+                    }
+                    Err(e) => {
+                        log::warn!("error pruning parquet row groups: {e}");
+                        file_metrics.predicate_evaluation_errors.add(1);
+                    }
+                }
+            }
+
+            // bloom filters are checked after min/max statistics since reading
+            // them requires extra io
+            let bloom_filter_predicate = opener.bloom_filter_predicate.as_ref().map(|predicate| {
+                predicate.map_columns(|column_name| {
+                    let table_idx = data_schema.index_of(column_name).ok()?;
+                    let file_idx = field_mapping[table_idx]?;
+                    Some(file_schema.field(file_idx).name().clone())
+                })
+            });
+            if let Some(bloom_filter_predicate) = &bloom_filter_predicate {
+                let num_row_groups = row_groups.len();
+                row_groups.retain(|&row_group_idx| {
+                    let row_group = metadata.row_group(row_group_idx);
+                    bloom_filter_predicate
+                        .may_match(row_group, |column_idx| {
+                            Sbbf::read_from_column_chunk(
+                                row_group.column(column_idx),
+                                file_reader.clone(),
+                            )
+                        })
+                        .unwrap_or_else(|err| {
+                            log::warn!("error reading parquet bloom filter: {err}");
+                            true
+                        })
+                });
+                row_groups_pruned_bloom_filter.add(num_row_groups - row_groups.len());
+            }
+            let _ = file_reader.selected_row_groups.set(row_groups.clone());
+
+            // row indices are counted from the first row of the file, including
+            // rows in row groups which are not read
+            let row_group_first_rows = metadata
+                .row_groups()
+                .iter()
+                .scan(0, |num_rows, row_group| {
+                    let first_row = *num_rows;
+                    *num_rows += row_group.num_rows();
+                    Some(first_row)
+                })
+                .collect::<Vec<_>>();
+            let row_ranges = row_groups
+                .iter()
+                .map(|&row_group_idx| {
+                    let first_row = row_group_first_rows[row_group_idx];
+                    first_row..first_row + metadata.row_group(row_group_idx).num_rows()
+                })
+                .collect();
+            let mut metadata_values = FileMetadataValues {
+                file_path: file_reader.file_path()?,
+                file_size: file_reader.meta.size as i64,
+                modification_time: Some(file_reader.meta.last_modified.timestamp_micros())
+                    .filter(|&micros| micros != 0),
+                row_ranges,
+            };
+
+            let mask = ProjectionMask::leaves(builder.parquet_schema(), leaves);
+            builder = builder
+                .with_projection(mask)
+                .with_batch_size(opener.batch_size)
+                .with_row_groups(row_groups);
+            // limit is applied by file stream if rows may be deleted
+            if let Some(limit) = opener.limit.filter(|_| row_deletes.is_none()) {
+                builder = builder.with_limit(limit);
+            }
+            let need_row_indices = row_deletes.is_some() || !metadata_projection.is_empty();
+            let stream = builder.build()?.map(move |batch| {
+                let mut file_batch = rebase_spec
+                    .rebase_batch(&batch.map_err(|e| ArrowError::ExternalError(Box::new(e)))?)
+                    .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+                let mut row_indices = if need_row_indices {
+                    metadata_values.take_row_indices(file_batch.num_rows())
+                } else {
+                    vec![]
+                };
+                if let Some(row_deletes) = &mut row_deletes {
+                    let retained = row_deletes
+                        .retained_rows(&file_batch, &row_indices, &file_projection)
+                        .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+                    if let Some(retained) = retained {
+                        file_batch = filter_record_batch(&file_batch, &retained)?;
+                        row_indices = row_indices
+                            .into_iter()
+                            .zip(retained.values().iter())
+                            .filter(|(_, retained)| *retained)
+                            .map(|(row_idx, _)| row_idx)
+                            .collect();
+                    }
+                }
+                let data_batch = adapt_file_batch(
+                    &file_batch,
+                    &data_output_schema,
+                    &projected_mapping,
+                    &file_projection,
+                )
+                .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+                if metadata_projection.is_empty() {
+                    return Ok(data_batch);
+                }
+
+                let num_rows = data_batch.num_rows();
+                let mut columns = data_batch.columns().to_vec();
+                columns.extend(metadata_values.arrays(&metadata_projection, &row_indices));
+                RecordBatch::try_new_with_options(
+                    output_schema.clone(),
+                    columns,
+                    &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+                )
+            });
+            Ok(stream.boxed())
+        }))
+    }
+}
+
+impl ParquetOpener {
+    // loads deletes attached to the data file, keys of equality deletes are
+    // converted with the types of equality columns in the data file
+    async fn load_row_deletes(
+        &self,
+        delete_files: &[DeleteFile],
+        data_file_path: &str,
+        file_schema: &Schema,
+    ) -> Result<RowDeletes> {
+        let mut deleted_positions = vec![];
+        let mut equality_deletes = vec![];
+
+        for delete_file in delete_files {
+            match delete_file {
+                DeleteFile::PositionDeletes(meta) => {
+                    for batch in self
+                        .read_delete_file(meta, &["file_path", "pos"])
+                        .await?
+                        .iter()
+                    {
+                        let paths = as_string_array(batch.column(0))?;
+                        let positions = as_int64_array(batch.column(1))?;
+                        deleted_positions.extend(
+                            paths
+                                .iter()
+                                .zip(positions.iter())
+                                .filter(|(path, _)| *path == Some(data_file_path))
+                                .filter_map(|(_, pos)| pos),
+                        );
+                    }
+                }
+                DeleteFile::EqualityDeletes {
+                    meta,
+                    equality_columns,
+                } => {
+                    let file_indices = equality_columns
+                        .iter()
+                        .map(|name| file_schema.index_of(name))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let data_types = file_indices
+                        .iter()
+                        .map(|&file_idx| file_schema.field(file_idx).data_type().clone())
+                        .collect::<Vec<_>>();
+                    let mut converter = RowConverter::new(
+                        data_types.iter().cloned().map(SortField::new).collect(),
+                    )?;
+
+                    let mut keys = HashSet::new();
+                    let column_names = equality_columns
+                        .iter()
+                        .map(|name| name.as_str())
+                        .collect::<Vec<_>>();
+                    for batch in self.read_delete_file(meta, &column_names).await?.iter() {
+                        let columns = batch
+                            .columns()
+                            .iter()
+                            .zip(&data_types)
+                            .map(|(column, data_type)| Ok(cast(column, data_type)?))
+                            .collect::<Result<Vec<_>>>()?;
+                        let rows = converter.convert_columns(&columns)?;
+                        keys.extend(rows.iter().map(|row| row.owned()));
+                    }
+                    equality_deletes.push(EqualityDeletes {
+                        file_indices,
+                        converter,
+                        keys,
+                    });
+                }
+                DeleteFile::DeletionVector(deletion_vector) => {
+                    let data = match deletion_vector {
+                        DeletionVector::Inline(data) => data.clone(),
+                        DeletionVector::Stored { path, offset, size } => {
+                            self.reader_factory
+                                .read_deletion_vector(path, *offset, *size)
+                                .await?
+                        }
+                    };
+                    deleted_positions.extend(decode_deletion_vector(&data)?);
+                }
+            }
+        }
+        Ok(RowDeletes::new(deleted_positions, equality_deletes))
+    }
+
+    // reads all rows of the named columns from a delete file, output columns
+    // are in the same order as the names. rows are read only once by the task
+    // and shared by all the data files the delete file is attached to
+    async fn read_delete_file(
+        &self,
+        meta: &ObjectMeta,
+        column_names: &[&str],
+    ) -> Result<Arc<Vec<RecordBatch>>> {
+        let key = (
+            meta.location.clone(),
+            column_names.iter().map(|name| name.to_string()).collect(),
+        );
+        let batches = self
+            .delete_file_batches
+            .lock()
+            .entry(key)
+            .or_default()
+            .clone();
+        let batches = batches
+            .get_or_try_init(|| async {
+                self.read_delete_file_batches(meta, column_names)
+                    .await
+                    .map(Arc::new)
+            })
+            .await?;
+        Ok(batches.clone())
+    }
+
+    async fn read_delete_file_batches(
+        &self,
+        meta: &ObjectMeta,
+        column_names: &[&str],
+    ) -> Result<Vec<RecordBatch>> {
+        let file_reader = self.reader_factory.create_file_reader(
+            self.partition_index,
+            FileMeta::from(meta.clone()),
+            &self.metrics,
+        );
+        let builder =
+            ParquetRecordBatchStreamBuilder::new(ParquetFileReaderRef(file_reader)).await?;
+        let root_indices = column_names
+            .iter()
+            .map(|name| builder.schema().index_of(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mask = ProjectionMask::roots(builder.parquet_schema(), root_indices);
+        let batches: Vec<RecordBatch> = builder
+            .with_projection(mask)
+            .with_batch_size(self.batch_size)
+            .build()?
+            .try_collect()
+            .await?;
+
+        batches
+            .into_iter()
+            .map(|batch| {
+                let indices = column_names
+                    .iter()
+                    .map(|name| batch.schema().index_of(name))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(batch.project(&indices)?)
+            })
+            .collect()
+    }
+}
+
+// values of metadata columns of the scanned file
+struct FileMetadataValues {
+    file_path: String,
+    file_size: i64,
+    modification_time: Option<i64>,
+
+    // row ranges of the selected row groups, rows are decoded sequentially
+    // from these ranges since pages are never skipped
+    row_ranges: VecDeque<Range<i64>>,
+}
+
+impl FileMetadataValues {
+    // row_indices are the positions of output rows in the file
+    fn arrays(&self, columns: &[FileMetadataColumn], row_indices: &[i64]) -> Vec<ArrayRef> {
+        let num_rows = row_indices.len();
+
+        columns
+            .iter()
+            .map(|column| -> ArrayRef {
+                match column {
+                    FileMetadataColumn::FilePath => Arc::new(StringArray::from_iter_values(
+                        std::iter::repeat(&self.file_path).take(num_rows),
+                    )),
+                    FileMetadataColumn::FileName => {
+                        let file_name = self.file_path.rsplit('/').next().unwrap_or_default();
+                        Arc::new(StringArray::from_iter_values(
+                            std::iter::repeat(file_name).take(num_rows),
+                        ))
+                    }
+                    FileMetadataColumn::FileSize => {
+                        Arc::new(Int64Array::from_value(self.file_size, num_rows))
+                    }
+                    FileMetadataColumn::FileModificationTime => {
+                        Arc::new(TimestampMicrosecondArray::from(vec![
+                            self.modification_time;
+                            num_rows
+                        ]))
+                    }
+                    FileMetadataColumn::RowIndex => {
+                        Arc::new(Int64Array::from(row_indices.to_vec()))
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn take_row_indices(&mut self, num_rows: usize) -> Vec<i64> {
+        let mut row_indices = Vec::with_capacity(num_rows);
+        while row_indices.len() < num_rows {
+            let range = match self.row_ranges.front_mut() {
+                Some(range) => range,
+                None => break,
+            };
+            let num_taken = ((num_rows - row_indices.len()) as i64).min(range.end - range.start);
+            row_indices.extend(range.start..range.start + num_taken);
+            range.start += num_taken;
+            if range.is_empty() {
+                self.row_ranges.pop_front();
+            }
+        }
+        row_indices
+    }
+}
+
+// number of parquet leaf columns of a field converted from parquet schema
+fn num_leaves(data_type: &DataType) -> usize {
+    match data_type {
+        DataType::Struct(fields) => fields
+            .iter()
+            .map(|field| num_leaves(field.data_type()))
+            .sum(),
+        DataType::List(item)
+        | DataType::LargeList(item)
+        | DataType::FixedSizeList(item, _)
+        | DataType::Map(item, _) => num_leaves(item.data_type()),
+        _ => 1,
+    }
+}
+
+// selects leaf columns of a file field which are required by the table type,
+// leaves are numbered in depth-first order starting from leaf_offset, which is
+// the same as the order of parquet leaf columns. struct fields are matched by
+// name or field id (same as adapting), and fields missing in the table type
+// are pruned
+fn select_leaves(
+    file_type: &DataType,
+    table_type: &DataType,
+    leaf_offset: usize,
+    leaves: &mut Vec<usize>,
+) {
+    match (file_type, table_type) {
+        (DataType::Struct(file_fields), DataType::Struct(table_fields)) => {
+            let offsets = file_fields
+                .iter()
+                .scan(leaf_offset, |offset, file_field| {
+                    let field_offset = *offset;
+                    *offset += num_leaves(file_field.data_type());
+                    Some(field_offset)
+                })
+                .collect::<Vec<_>>();
+            for table_field in table_fields {
+                if let Some(file_idx) = match_file_field(table_field, file_fields, false) {
+                    select_leaves(
+                        file_fields[file_idx].data_type(),
+                        table_field.data_type(),
+                        offsets[file_idx],
+                        leaves,
+                    );
+                }
+            }
+        }
+        (
+            DataType::List(file_item) | DataType::LargeList(file_item),
+            DataType::List(table_item) | DataType::LargeList(table_item),
+        ) => {
+            select_leaves(
+                file_item.data_type(),
+                table_item.data_type(),
+                leaf_offset,
+                leaves,
+            );
+        }
+        (DataType::Map(file_entries, _), DataType::Map(table_entries, _)) => {
+            match (file_entries.data_type(), table_entries.data_type()) {
+                (DataType::Struct(file_kv), DataType::Struct(table_kv))
+                    if file_kv.len() == 2 && table_kv.len() == 2 =>
+                {
+                    // keys are always read as a whole, and values cannot be
+                    // pruned to empty since a map requires both of them
+                    let num_key_leaves = num_leaves(file_kv[0].data_type());
+                    let value_offset = leaf_offset + num_key_leaves;
+                    leaves.extend(leaf_offset..value_offset);
+
+                    let num_selected = leaves.len();
+                    select_leaves(
+                        file_kv[1].data_type(),
+                        table_kv[1].data_type(),
+                        value_offset,
+                        leaves,
+                    );
+                    if leaves.len() == num_selected {
+                        leaves.extend(
+                            value_offset..value_offset + num_leaves(file_kv[1].data_type()),
+                        );
+                    }
+                }
+                _ => leaves.extend(leaf_offset..leaf_offset + num_leaves(file_type)),
+            }
+        }
+        _ => leaves.extend(leaf_offset..leaf_offset + num_leaves(file_type)),
+    }
+}
+
+// min/max and null count statistics of the selected row groups, only top-level
+// primitive columns whose types are not evolved are supported
+struct RowGroupPruningStatistics<'a> {
+    metadata: &'a ParquetMetaData,
+    row_groups: &'a [usize],
+    table_schema: &'a Schema,
+    file_schema: &'a Schema,
+    field_mapping: &'a [Option<usize>],
+    leaf_offsets: &'a [usize],
+}
+
+impl RowGroupPruningStatistics<'_> {
+    fn column_stats(
+        &self,
+        column: &datafusion::common::Column,
+    ) -> Option<(&DataType, Vec<Option<&ParquetStatistics>>)> {
+        let table_idx = self.table_schema.index_of(&column.name).ok()?;
+        let file_idx = self.field_mapping[table_idx]?;
+        let data_type = self.table_schema.field(table_idx).data_type();
+        if data_type.is_nested() || self.file_schema.field(file_idx).data_type() != data_type {
+            return None;
+        }
+        let column_idx = self.leaf_offsets[file_idx];
+        let stats = self
+            .row_groups
+            .iter()
+            .map(|&row_group_idx| {
+                self.metadata
+                    .row_group(row_group_idx)
+                    .column(column_idx)
+                    .statistics()
+            })
+            .collect();
+        Some((data_type, stats))
+    }
+
+    fn min_max_values(
+        &self,
+        column: &datafusion::common::Column,
+        is_min: bool,
+    ) -> Option<ArrayRef> {
+        let (data_type, stats) = self.column_stats(column)?;
+        let stats = stats
+            .into_iter()
+            .map(|stats| stats.filter(|stats| stats.has_min_max_set()));
+
+        macro_rules! min_max {
+            ($stats:expr) => {{
+                if is_min {
+                    $stats.min()
+                } else {
+                    $stats.max()
+                }
+            }};
+        }
+        let array: ArrayRef = match data_type {
+            DataType::Boolean => {
+                Arc::new(BooleanArray::from_iter(stats.map(|stats| match stats? {
+                    ParquetStatistics::Boolean(s) => Some(*min_max!(s)),
+                    _ => None,
+                })))
+            }
+            DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Date32 => {
+                let values = stats.map(|stats| match stats? {
+                    ParquetStatistics::Int32(s) => Some(*min_max!(s)),
+                    _ => None,
+                });
+                arrow::compute::cast(&Int32Array::from_iter(values), data_type).ok()?
+            }
+            DataType::Int64 => Arc::new(Int64Array::from_iter(stats.map(|stats| match stats? {
+                ParquetStatistics::Int64(s) => Some(*min_max!(s)),
+                _ => None,
+            }))),
+            DataType::Float32 => {
+                Arc::new(Float32Array::from_iter(stats.map(|stats| match stats? {
+                    ParquetStatistics::Float(s) => Some(*min_max!(s)),
+                    _ => None,
+                })))
+            }
+            DataType::Float64 => {
+                Arc::new(Float64Array::from_iter(stats.map(|stats| match stats? {
+                    ParquetStatistics::Double(s) => Some(*min_max!(s)),
+                    _ => None,
+                })))
+            }
+            // deprecated min/max of byte arrays may be compared as signed bytes
+            DataType::Utf8 => Arc::new(StringArray::from_iter(stats.map(|stats| {
+                let stats = stats.filter(|stats| !stats.is_min_max_deprecated())?;
+                match stats {
+                    ParquetStatistics::ByteArray(s) => min_max!(s).as_utf8().ok(),
+                    _ => None,
+                }
+            }))),
+            &DataType::Decimal128(precision, scale) => Arc::new(
+                Decimal128Array::from_iter(stats.map(|stats| match stats? {
+                    ParquetStatistics::Int32(s) => Some(*min_max!(s) as i128),
+                    ParquetStatistics::Int64(s) => Some(*min_max!(s) as i128),
+                    stats if stats.is_min_max_deprecated() => None,
+                    ParquetStatistics::ByteArray(s) => decimal_from_be_bytes(min_max!(s).data()),
+                    ParquetStatistics::FixedLenByteArray(s) => {
+                        decimal_from_be_bytes(min_max!(s).data())
+                    }
+                    _ => None,
+                }))
+                .with_precision_and_scale(precision, scale)
+                .ok()?,
+            ),
+            _ => return None,
+        };
+        Some(array)
+    }
+}
+
+impl PruningStatistics for RowGroupPruningStatistics<'_> {
+    fn min_values(&self, column: &datafusion::common::Column) -> Option<ArrayRef> {
+        self.min_max_values(column, true)
+    }
+
+    fn max_values(&self, column: &datafusion::common::Column) -> Option<ArrayRef> {
+        self.min_max_values(column, false)
+    }
+
+    fn num_containers(&self) -> usize {
+        self.row_groups.len()
+    }
+
+    fn null_counts(&self, column: &datafusion::common::Column) -> Option<ArrayRef> {
+        let (_, stats) = self.column_stats(column)?;
+        let null_counts = stats.into_iter().map(|stats| Some(stats?.null_count()));
+        Some(Arc::new(UInt64Array::from_iter(null_counts)))
+    }
+}
+
+// decodes a big-endian two's complement integer of at most 16 bytes
+fn decimal_from_be_bytes(bytes: &[u8]) -> Option<i128> {
+    if bytes.is_empty() || bytes.len() > 16 {
+        return None;
+    }
+    let sign = if bytes[0] & 0x80 != 0 { 0xff } else { 0x00 };
+    let mut be_bytes = [sign; 16];
+    be_bytes[16 - bytes.len()..].copy_from_slice(bytes);
+    Some(i128::from_be_bytes(be_bytes))
+}
+
 #[derive(Clone)]
 pub struct FsReaderFactory {
     fs_provider: Arc<FsProvider>,
+    metadata_cache: Option<Arc<ParquetMetadataCache>>,
+    enable_page_index: bool,
+    read_options: ParquetReadOptions,
+    read_ahead_mem: Arc<ReadAheadMemConsumer>,
 }
 
 impl FsReaderFactory {
-    pub fn new(fs_provider: Arc<FsProvider>) -> Self {
-        Self { fs_provider }
+    pub fn new(
+        fs_provider: Arc<FsProvider>,
+        metadata_cache: Option<Arc<ParquetMetadataCache>>,
+        enable_page_index: bool,
+        read_options: ParquetReadOptions,
+        read_ahead_mem: Arc<ReadAheadMemConsumer>,
+    ) -> Self {
+        Self {
+            fs_provider,
+            metadata_cache,
+            enable_page_index,
+            read_options,
+            read_ahead_mem,
+        }
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct ParquetReadOptions {
+    pub coalesce_gap: usize,
+    pub max_coalesced_size: usize,
+    pub parallelism: usize,
+    pub read_ahead: bool,
+}
+
+impl ParquetReadOptions {
+    pub fn try_from_conf() -> Result<Self> {
+        Ok(Self {
+            coalesce_gap: conf::PARQUET_READ_COALESCE_GAP.value()?.max(0) as usize,
+            max_coalesced_size: conf::PARQUET_READ_MAX_COALESCED_SIZE.value()?.max(0) as usize,
+            parallelism: conf::PARQUET_READ_PARALLELISM.value()?.max(1) as usize,
+            read_ahead: conf::PARQUET_READ_AHEAD_ENABLE.value()?,
+        })
+    }
+}
+
+// merges overlapping ranges, and nearby ranges with gaps not larger than
+// coalesce_gap if the merged size does not exceed max_coalesced_size. the
+// output ranges are sorted and not overlapping
+fn coalesce_ranges(
+    ranges: &[Range<usize>],
+    coalesce_gap: usize,
+    max_coalesced_size: usize,
+) -> Vec<Range<usize>> {
+    let mut sorted_ranges = ranges.to_vec();
+    sorted_ranges.sort_unstable_by_key(|range| range.start);
+
+    let mut coalesced: Vec<Range<usize>> = Vec::with_capacity(sorted_ranges.len());
+    for range in sorted_ranges {
+        match coalesced.last_mut() {
+            Some(last)
+                if range.start < last.end
+                    || (range.start - last.end <= coalesce_gap
+                        && range.end - last.start <= max_coalesced_size) =>
+            {
+                last.end = last.end.max(range.end);
+            }
+            _ => coalesced.push(range),
+        }
+    }
+    coalesced
+}
+
 impl Debug for FsReaderFactory {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "FsReaderFactory")
@@ -311,59 +1343,308 @@ impl ParquetFileReaderFactory for FsReaderFactory {
         _metadata_size_hint: Option<usize>,
         metrics: &ExecutionPlanMetricsSet,
     ) -> Result<Box<dyn AsyncFileReader + Send>> {
-        let reader = ParquetFileReaderRef(Arc::new(ParquetFileReader {
+        let reader = self.create_file_reader(partition_index, file_meta, metrics);
+        Ok(Box::new(ParquetFileReaderRef(reader)))
+    }
+}
+
+impl FsReaderFactory {
+    // reads a serialized deletion vector, which is prefixed with its size in
+    // big-endian at the offset of the deletion vector file
+    async fn read_deletion_vector(&self, path: &str, offset: u64, size: usize) -> Result<Bytes> {
+        let fs_provider = self.fs_provider.clone();
+        let path = path.to_string();
+        let handle = tokio::task::spawn_blocking(move || -> Result<Bytes> {
+            let input = fs_provider.provide(&path)?.open(&path)?;
+            let mut bytes = vec![0u8; size + 4];
+            input.read_fully(offset, &mut bytes)?;
+
+            let stored_size = (&bytes[..4]).get_u32() as usize;
+            if stored_size != size {
+                return df_execution_err!(
+                    "deletion vector size mismatch in {path}: expected {size}, found {stored_size}"
+                );
+            }
+            Ok(Bytes::from(bytes).slice(4..))
+        });
+        handle
+            .await
+            .or_else(|e| df_execution_err!("error reading deletion vector: {e}"))?
+    }
+
+    fn create_file_reader(
+        &self,
+        partition_index: usize,
+        file_meta: FileMeta,
+        metrics: &ExecutionPlanMetricsSet,
+    ) -> Arc<ParquetFileReader> {
+        let filename = file_meta
+            .object_meta
+            .location
+            .filename()
+            .unwrap_or("__default_filename__");
+        let metadata_cache_hits =
+            MetricBuilder::new(metrics).counter("metadata_cache_hits", partition_index);
+        let metadata_cache_misses =
+            MetricBuilder::new(metrics).counter("metadata_cache_misses", partition_index);
+        Arc::new(ParquetFileReader {
             fs_provider: self.fs_provider.clone(),
-            input: OnceCell::new(),
-            metrics: ParquetFileMetrics::new(
-                partition_index,
-                file_meta
-                    .object_meta
-                    .location
-                    .filename()
-                    .unwrap_or("__default_filename__"),
-                metrics,
-            ),
+            idle_inputs: Mutex::default(),
+            metrics: ParquetFileMetrics::new(partition_index, filename, metrics),
+            metadata_cache: self.metadata_cache.clone(),
+            metadata_cache_hits,
+            metadata_cache_misses,
+            enable_page_index: self.enable_page_index,
+            read_options: self.read_options,
+            read_ahead_mem: self.read_ahead_mem.clone(),
+            metadata: OnceCell::new(),
+            selected_row_groups: OnceCell::new(),
+            read_ahead: Mutex::default(),
             meta: file_meta.object_meta,
-        }));
-        Ok(Box::new(reader))
+        })
     }
 }
 
 struct ParquetFileReader {
     fs_provider: Arc<FsProvider>,
-    input: OnceCell<Arc<FsDataInputStream>>,
+    idle_inputs: Mutex<Vec<FsDataInputStream>>,
     meta: ObjectMeta,
     metrics: ParquetFileMetrics,
+    metadata_cache: Option<Arc<ParquetMetadataCache>>,
+    metadata_cache_hits: Count,
+    metadata_cache_misses: Count,
+    enable_page_index: bool,
+    read_options: ParquetReadOptions,
+    read_ahead_mem: Arc<ReadAheadMemConsumer>,
+    metadata: OnceCell<Arc<ParquetMetaData>>,
+    selected_row_groups: OnceCell<Vec<usize>>,
+    read_ahead: Mutex<Option<ReadAhead>>,
+}
+
+// byte ranges of the next row group being fetched in background
+struct ReadAhead {
+    ranges: Vec<Range<usize>>,
+    mem_size: usize,
+    cancelled: Arc<AtomicBool>,
+    handle: JoinHandle<datafusion::parquet::errors::Result<Vec<Bytes>>>,
+}
+
+impl ReadAhead {
+    // aborting the task does not stop blocking reads which are already
+    // spawned, so they are also notified to skip reading if not started
+    fn cancel(self, read_ahead_mem: &ReadAheadMemConsumer) {
+        self.cancelled.store(true, SeqCst);
+        self.handle.abort();
+        read_ahead_mem.release(self.mem_size);
+    }
+}
+
+/// memory of prefetched row groups of a scan task. the consumer is not
+/// spillable, instead read-ahead is skipped if there is no free memory.
+pub struct ReadAheadMemConsumer {
+    name: String,
+    mem_consumer_info: Option<Weak<MemConsumerInfo>>,
+    prefetched_size: AtomicUsize,
+}
+
+impl ReadAheadMemConsumer {
+    fn acquire(&self, mem_size: usize) {
+        self.prefetched_size.fetch_add(mem_size, SeqCst);
+    }
+
+    fn release(&self, mem_size: usize) {
+        self.prefetched_size.fetch_sub(mem_size, SeqCst);
+    }
+
+    // releasing may happen in drop() without updating the mem manager, so the
+    // used memory is synchronized on the next reading
+    async fn update_mem_used_to_prefetched(&self) -> Result<()> {
+        self.update_mem_used(self.prefetched_size.load(SeqCst))
+            .await
+    }
+}
+
+#[async_trait]
+impl MemConsumer for ReadAheadMemConsumer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_consumer_info(&mut self, consumer_info: Weak<MemConsumerInfo>) {
+        self.mem_consumer_info = Some(consumer_info);
+    }
+
+    fn get_consumer_info(&self) -> &Weak<MemConsumerInfo> {
+        self.mem_consumer_info
+            .as_ref()
+            .expect("consumer info not set")
+    }
+}
+
+impl Drop for ReadAheadMemConsumer {
+    fn drop(&mut self) {
+        MemManager::deregister_consumer(self);
+    }
 }
 
-#[derive(Clone)]
 struct ParquetFileReaderRef(Arc<ParquetFileReader>);
 
 impl ParquetFileReader {
-    fn get_input(&self) -> datafusion::parquet::errors::Result<Arc<FsDataInputStream>> {
-        let input = self
-            .input
-            .get_or_try_init(|| {
-                let path = BASE64_URL_SAFE_NO_PAD
-                    .decode(self.meta.location.filename().expect("missing filename"))
-                    .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
-                    .or_else(|_| {
-                        let filename = self.meta.location.filename();
-                        df_execution_err!("cannot decode filename: {filename:?}")
-                    })?;
-                let fs = self.fs_provider.provide(&path)?;
-                Ok(Arc::new(fs.open(&path)?))
+    fn file_path(&self) -> Result<String> {
+        BASE64_URL_SAFE_NO_PAD
+            .decode(self.meta.location.filename().expect("missing filename"))
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .or_else(|_| {
+                let filename = self.meta.location.filename();
+                df_execution_err!("cannot decode filename: {filename:?}")
             })
-            .map_err(|e| ParquetError::External(e))?;
-        Ok(input.clone())
     }
 
+    fn open_input(&self) -> Result<FsDataInputStream> {
+        let path = self.file_path()?;
+        let fs = self.fs_provider.provide(&path)?;
+        fs.open(&path)
+    }
+
+    // reads with an idle input stream, or a newly opened one if all streams
+    // are in use, since concurrent reads on the same stream are serialized
     fn read_fully(&self, range: Range<usize>) -> Result<Bytes> {
+        let idle_input = self.idle_inputs.lock().pop();
+        let input = match idle_input {
+            Some(input) => input,
+            None => self.open_input()?,
+        };
         let mut bytes = vec![0u8; range.len()];
-        self.get_input()?
-            .read_fully(range.start as u64, &mut bytes)?;
+        input.read_fully(range.start as u64, &mut bytes)?;
+        self.idle_inputs.lock().push(input);
         Ok(Bytes::from(bytes))
     }
+
+    // fetches byte ranges in parallel, nearby ranges are coalesced into one
+    // read to reduce the number of io requests. reads not started yet are
+    // skipped once `cancelled` is set
+    async fn fetch_ranges(
+        self: Arc<Self>,
+        ranges: Vec<Range<usize>>,
+        cancelled: Arc<AtomicBool>,
+    ) -> datafusion::parquet::errors::Result<Vec<Bytes>> {
+        let coalesced = coalesce_ranges(
+            &ranges,
+            self.read_options.coalesce_gap,
+            self.read_options.max_coalesced_size,
+        );
+        let fetched: Vec<Bytes> = futures::stream::iter(coalesced.clone())
+            .map(|range| {
+                let inner = self.clone();
+                let cancelled = cancelled.clone();
+                tokio::task::spawn_blocking(move || {
+                    if cancelled.load(SeqCst) {
+                        return df_execution_err!("reading cancelled");
+                    }
+                    inner.metrics.bytes_scanned.add(range.len());
+                    inner.read_fully(range)
+                })
+            })
+            .buffered(self.read_options.parallelism)
+            .map(|joined| match joined {
+                Ok(result) => result.map_err(|e| ParquetError::External(Box::new(e))),
+                Err(e) => Err(ParquetError::External(Box::new(e))),
+            })
+            .try_collect()
+            .await?;
+
+        Ok(ranges
+            .iter()
+            .map(|range| {
+                let idx = coalesced.partition_point(|c| c.start <= range.start) - 1;
+                let offset = coalesced[idx].start;
+                fetched[idx].slice(range.start - offset..range.end - offset)
+            })
+            .collect())
+    }
+
+    // finds byte ranges of the same columns in the next selected row group, if
+    // the requested ranges are column chunks of a row group
+    fn next_row_group_ranges(&self, ranges: &[Range<usize>]) -> Option<Vec<Range<usize>>> {
+        let metadata = self.metadata.get()?;
+        let row_groups = metadata.row_groups();
+        let column_range = |column: &ColumnChunkMetaData| {
+            let (start, len) = column.byte_range();
+            start as usize..(start + len) as usize
+        };
+
+        let first_range = ranges.first()?;
+        let row_group_idx = row_groups.iter().position(|row_group| {
+            row_group
+                .columns()
+                .iter()
+                .any(|column| column_range(column) == *first_range)
+        })?;
+        let column_indices = ranges
+            .iter()
+            .map(|range| {
+                row_groups[row_group_idx]
+                    .columns()
+                    .iter()
+                    .position(|column| column_range(column) == *range)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let next_row_group_idx = self
+            .selected_row_groups
+            .get()?
+            .iter()
+            .find(|&&selected_idx| selected_idx > row_group_idx)?;
+        let next_row_group = &row_groups[*next_row_group_idx];
+        Some(
+            column_indices
+                .into_iter()
+                .map(|column_idx| column_range(next_row_group.column(column_idx)))
+                .collect(),
+        )
+    }
+}
+
+// a row group belongs to the split containing its first page
+fn row_group_in_range(row_group: &RowGroupMetaData, range: Option<&FileRange>) -> bool {
+    match range {
+        Some(range) => {
+            let column = row_group.column(0);
+            let offset = column
+                .dictionary_page_offset()
+                .unwrap_or_else(|| column.data_page_offset());
+            offset >= range.start && offset < range.end
+        }
+        None => true,
+    }
+}
+
+impl Length for ParquetFileReader {
+    fn len(&self) -> u64 {
+        self.meta.size as u64
+    }
+}
+
+// used for reading bloom filters
+impl ChunkReader for ParquetFileReader {
+    type T = bytes::buf::Reader<Bytes>;
+
+    // bloom filters are read with get_bytes(), reading to the end of file is
+    // never expected
+    fn get_read(&self, start: u64) -> datafusion::parquet::errors::Result<Self::T> {
+        Err(ParquetError::General(format!(
+            "unsupported ChunkReader::get_read() at offset {start}"
+        )))
+    }
+
+    fn get_bytes(&self, start: u64, length: usize) -> datafusion::parquet::errors::Result<Bytes> {
+        // length may be an estimation exceeding the end of file
+        let start = start as usize;
+        let end = (start + length).min(self.meta.size);
+        self.metrics.bytes_scanned.add(end.saturating_sub(start));
+        self.read_fully(start..end)
+            .map_err(|e| ParquetError::External(Box::new(e)))
+    }
 }
 
 impl AsyncFileReader for ParquetFileReaderRef {
@@ -381,13 +1662,112 @@ impl AsyncFileReader for ParquetFileReaderRef {
         .boxed()
     }
 
+    fn get_byte_ranges(
+        &mut self,
+        ranges: Vec<Range<usize>>,
+    ) -> BoxFuture<'_, datafusion::parquet::errors::Result<Vec<Bytes>>> {
+        let inner = self.0.clone();
+        async move {
+            let read_ahead = inner.read_ahead.lock().take();
+            let data = match read_ahead {
+                Some(read_ahead) if read_ahead.ranges == ranges => {
+                    inner.read_ahead_mem.release(read_ahead.mem_size);
+                    read_ahead
+                        .handle
+                        .await
+                        .map_err(|e| ParquetError::External(Box::new(e)))??
+                }
+                read_ahead => {
+                    if let Some(read_ahead) = read_ahead {
+                        read_ahead.cancel(&inner.read_ahead_mem);
+                    }
+                    inner
+                        .clone()
+                        .fetch_ranges(ranges.clone(), Arc::default())
+                        .await?
+                }
+            };
+
+            // fetches the next row group while the current one is being
+            // decoded, skipped if there is not enough free memory
+            if inner.read_options.read_ahead {
+                if let Some(next_ranges) = inner.next_row_group_ranges(&ranges) {
+                    let mem_size = next_ranges.iter().map(|range| range.len()).sum();
+                    if mem_size <= MemManager::get().free_mem() {
+                        let cancelled = Arc::new(AtomicBool::new(false));
+                        let handle = tokio::spawn(
+                            inner
+                                .clone()
+                                .fetch_ranges(next_ranges.clone(), cancelled.clone()),
+                        );
+                        inner.read_ahead_mem.acquire(mem_size);
+                        *inner.read_ahead.lock() = Some(ReadAhead {
+                            ranges: next_ranges,
+                            mem_size,
+                            cancelled,
+                            handle,
+                        });
+                    }
+                }
+            }
+            inner
+                .read_ahead_mem
+                .update_mem_used_to_prefetched()
+                .await
+                .map_err(|e| ParquetError::External(Box::new(e)))?;
+            Ok(data)
+        }
+        .boxed()
+    }
+
     fn get_metadata(
         &mut self,
     ) -> BoxFuture<'_, datafusion::parquet::errors::Result<Arc<ParquetMetaData>>> {
+        let reader = self;
+        async move {
+            let inner = reader.0.clone();
+            let cache_key = ParquetMetadataCacheKey::try_new(&inner.meta);
+            let metadata = match (&inner.metadata_cache, cache_key) {
+                (Some(cache), Some(cache_key)) => match cache.get(&cache_key) {
+                    Some(metadata) => {
+                        inner.metadata_cache_hits.add(1);
+                        metadata
+                    }
+                    None => {
+                        inner.metadata_cache_misses.add(1);
+                        let metadata = reader.load_metadata().await?;
+                        cache
+                            .put(cache_key, metadata.clone())
+                            .await
+                            .map_err(|e| ParquetError::External(Box::new(e)))?;
+                        metadata
+                    }
+                },
+                _ => reader.load_metadata().await?,
+            };
+            let _ = inner.metadata.set(metadata.clone());
+            Ok(metadata)
+        }
+        .boxed()
+    }
+}
+
+impl Drop for ParquetFileReaderRef {
+    fn drop(&mut self) {
+        // the reader is dropped when the file is done or the scan is cancelled
+        if let Some(read_ahead) = self.0.read_ahead.lock().take() {
+            read_ahead.cancel(&self.0.read_ahead_mem);
+        }
+    }
+}
+
+impl ParquetFileReaderRef {
+    async fn load_metadata(&mut self) -> datafusion::parquet::errors::Result<Arc<ParquetMetaData>> {
         let inner = self.0.clone();
+        let enable_page_index = inner.enable_page_index;
         let meta_size = inner.meta.size;
         let size_hint = Some(2097152);
-        fetch_parquet_metadata(
+        let metadata = fetch_parquet_metadata(
             move |range| {
                 let inner = inner.clone();
                 inner.metrics.bytes_scanned.add(range.end - range.start);
@@ -400,7 +1780,676 @@ impl AsyncFileReader for ParquetFileReaderRef {
             meta_size,
             size_hint,
         )
-        .and_then(|metadata| futures::future::ok(Arc::new(metadata)))
-        .boxed()
+        .await?;
+
+        // load page index together so that it can also be cached
+        if !enable_page_index {
+            return Ok(Arc::new(metadata));
+        }
+        let mut loader = MetadataLoader::new(self, metadata);
+        loader.load_page_index(true, true).await?;
+        Ok(Arc::new(loader.finish()))
+    }
+}
+
+/// Equality conditions extracted from the scan predicate, used for pruning row
+/// groups with parquet split-block bloom filters.
+#[derive(Debug)]
+pub struct BloomFilterPredicate {
+    // conjuncts of the predicate, each means the column equals to any of the
+    // values
+    conditions: Vec<(String, Vec<ScalarValue>)>,
+}
+
+impl BloomFilterPredicate {
+    pub fn try_new(expr: &Arc<dyn PhysicalExpr>) -> Option<Self> {
+        let mut conditions = vec![];
+        collect_bloom_filter_conditions(expr, &mut conditions);
+        if conditions.is_empty() {
+            return None;
+        }
+        Some(Self { conditions })
+    }
+
+    /// maps table column names in the conditions to file column names, the
+    /// conditions of columns missing in the file are removed
+    pub fn map_columns(&self, file_column_name: impl Fn(&str) -> Option<String>) -> Self {
+        let conditions = self
+            .conditions
+            .iter()
+            .filter_map(|(column_name, values)| {
+                Some((file_column_name(column_name)?, values.clone()))
+            })
+            .collect();
+        Self { conditions }
+    }
+
+    /// returns false if any of the conditions is proved to be unsatisfied by
+    /// the bloom filters of the row group
+    pub fn may_match(
+        &self,
+        row_group: &RowGroupMetaData,
+        mut read_bloom_filter: impl FnMut(usize) -> datafusion::parquet::errors::Result<Option<Sbbf>>,
+    ) -> datafusion::parquet::errors::Result<bool> {
+        for (column_name, values) in &self.conditions {
+            let column_idx = row_group.columns().iter().position(|column| {
+                let parts = column.column_path().parts();
+                parts.len() == 1 && &parts[0] == column_name
+            });
+            let column_idx = match column_idx {
+                Some(column_idx) => column_idx,
+                None => continue,
+            };
+            let column_descr = row_group.column(column_idx).column_descr();
+            if column_descr.max_rep_level() > 0 {
+                continue;
+            }
+
+            if let Some(sbbf) = read_bloom_filter(column_idx)? {
+                if !values
+                    .iter()
+                    .any(|value| bloom_filter_may_contain(&sbbf, column_descr, value))
+                {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn collect_bloom_filter_conditions(
+    expr: &Arc<dyn PhysicalExpr>,
+    conditions: &mut Vec<(String, Vec<ScalarValue>)>,
+) {
+    if let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() {
+        if *binary.op() == Operator::And {
+            collect_bloom_filter_conditions(binary.left(), conditions);
+            collect_bloom_filter_conditions(binary.right(), conditions);
+            return;
+        }
+    }
+    conditions.extend(extract_bloom_filter_condition(expr));
+}
+
+// extracts `col = lit`, `col IN (lits)` and disjunctions of them on the same
+// column
+fn extract_bloom_filter_condition(
+    expr: &Arc<dyn PhysicalExpr>,
+) -> Option<(String, Vec<ScalarValue>)> {
+    let as_column = |expr: &Arc<dyn PhysicalExpr>| {
+        expr.as_any()
+            .downcast_ref::<Column>()
+            .map(|column| column.name().to_string())
+    };
+    let as_literal = |expr: &Arc<dyn PhysicalExpr>| {
+        expr.as_any()
+            .downcast_ref::<Literal>()
+            .map(|literal| literal.value().clone())
+    };
+
+    if let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() {
+        return match binary.op() {
+            Operator::Eq => match (binary.left(), binary.right()) {
+                (l, r) if as_column(l).is_some() && as_literal(r).is_some() => {
+                    Some((as_column(l)?, vec![as_literal(r)?]))
+                }
+                (l, r) if as_literal(l).is_some() && as_column(r).is_some() => {
+                    Some((as_column(r)?, vec![as_literal(l)?]))
+                }
+                _ => None,
+            },
+            Operator::Or => {
+                let (left_column, mut values) = extract_bloom_filter_condition(binary.left())?;
+                let (right_column, right_values) = extract_bloom_filter_condition(binary.right())?;
+                if left_column != right_column {
+                    return None;
+                }
+                values.extend(right_values);
+                Some((left_column, values))
+            }
+            _ => None,
+        };
+    }
+
+    if let Some(in_list) = expr.as_any().downcast_ref::<InListExpr>() {
+        if !in_list.negated() {
+            let values = in_list
+                .list()
+                .iter()
+                .map(as_literal)
+                .collect::<Option<Vec<_>>>()?;
+            return Some((as_column(in_list.expr())?, values));
+        }
+    }
+    None
+}
+
+// checks whether the value may exist in the bloom filter. values are hashed
+// in parquet plain encoding, so true is returned if the value cannot be
+// encoded exactly as the column's physical type
+fn bloom_filter_may_contain(sbbf: &Sbbf, column: &ColumnDescriptor, value: &ScalarValue) -> bool {
+    if value.is_null() {
+        return true;
+    }
+    let converted_type = column.converted_type();
+    let is_plain_int = matches!(
+        converted_type,
+        ConvertedType::NONE
+            | ConvertedType::INT_8
+            | ConvertedType::INT_16
+            | ConvertedType::INT_32
+            | ConvertedType::INT_64
+    );
+    let is_decimal_with_scale =
+        |scale: i8| converted_type == ConvertedType::DECIMAL && column.type_scale() == scale as i32;
+    let int_value = match value {
+        ScalarValue::Int8(Some(v)) => Some(*v as i64),
+        ScalarValue::Int16(Some(v)) => Some(*v as i64),
+        ScalarValue::Int32(Some(v)) => Some(*v as i64),
+        ScalarValue::Int64(Some(v)) => Some(*v),
+        _ => None,
+    };
+
+    match (column.physical_type(), value, int_value) {
+        // values out of range cannot exist in the column
+        (PhysicalType::INT32, _, Some(v)) if is_plain_int => {
+            i32::try_from(v).map(|v| sbbf.check(&v)).unwrap_or(false)
+        }
+        (PhysicalType::INT64, _, Some(v)) if is_plain_int => sbbf.check(&v),
+        (PhysicalType::INT32, ScalarValue::Date32(Some(v)), _)
+            if converted_type == ConvertedType::DATE =>
+        {
+            sbbf.check(v)
+        }
+
+        // 0.0 equals to -0.0 and NaN has multiple representations, they cannot
+        // be checked by hash
+        (PhysicalType::FLOAT, ScalarValue::Float32(Some(v)), _) if *v != 0.0 && !v.is_nan() => {
+            sbbf.check(v)
+        }
+        (PhysicalType::DOUBLE, ScalarValue::Float64(Some(v)), _) if *v != 0.0 && !v.is_nan() => {
+            sbbf.check(v)
+        }
+
+        (PhysicalType::BYTE_ARRAY, ScalarValue::Utf8(Some(v)), _)
+        | (PhysicalType::BYTE_ARRAY, ScalarValue::LargeUtf8(Some(v)), _) => {
+            sbbf.check(&ByteArray::from(v.as_str()))
+        }
+        (PhysicalType::BYTE_ARRAY, ScalarValue::Binary(Some(v)), _)
+        | (PhysicalType::BYTE_ARRAY, ScalarValue::LargeBinary(Some(v)), _) => {
+            sbbf.check(&ByteArray::from(v.clone()))
+        }
+
+        (PhysicalType::INT32, ScalarValue::Decimal128(Some(v), _, scale), _)
+            if is_decimal_with_scale(*scale) =>
+        {
+            i32::try_from(*v).map(|v| sbbf.check(&v)).unwrap_or(false)
+        }
+        (PhysicalType::INT64, ScalarValue::Decimal128(Some(v), _, scale), _)
+            if is_decimal_with_scale(*scale) =>
+        {
+            i64::try_from(*v).map(|v| sbbf.check(&v)).unwrap_or(false)
+        }
+        (PhysicalType::FIXED_LEN_BYTE_ARRAY, ScalarValue::Decimal128(Some(v), _, scale), _)
+            if is_decimal_with_scale(*scale) =>
+        {
+            // big-endian two's complement with the fixed length
+            let len = column.type_length() as usize;
+            let sign = if *v < 0 { 0xff } else { 0x00 };
+            let be_bytes = v.to_be_bytes();
+            let fixed_bytes = if len >= be_bytes.len() {
+                let mut fixed_bytes = vec![sign; len - be_bytes.len()];
+                fixed_bytes.extend_from_slice(&be_bytes);
+                fixed_bytes
+            } else {
+                let (truncated, fixed_bytes) = be_bytes.split_at(be_bytes.len() - len);
+                let fits = truncated.iter().all(|&b| b == sign)
+                    && fixed_bytes.first().map(|&b| b & 0x80) == Some(sign & 0x80);
+                if !fits {
+                    return false;
+                }
+                fixed_bytes.to_vec()
+            };
+            sbbf.check(&FixedLenByteArray::from(ByteArray::from(fixed_bytes)))
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::VecDeque, sync::Arc};
+
+    use arrow::{
+        array::{ArrayRef, Int32Array, Int64Array, StringArray, StructArray},
+        datatypes::{DataType, Field, Fields, Schema},
+        record_batch::RecordBatch,
+    };
+    use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+    use bytes::Bytes;
+    use datafusion::{
+        common::{
+            cast::{as_int64_array, as_string_array, as_struct_array},
+            Result,
+        },
+        datasource::{
+            listing::PartitionedFile, object_store::ObjectStoreUrl, physical_plan::FileScanConfig,
+        },
+        logical_expr::Operator,
+        parquet::{
+            arrow::{
+                arrow_reader::ParquetRecordBatchReaderBuilder, arrow_to_parquet_schema,
+                ArrowWriter, ProjectionMask,
+            },
+            bloom_filter::Sbbf,
+            file::{footer::parse_metadata, properties::WriterProperties},
+        },
+        physical_expr::{
+            expressions::{binary, col, in_list, lit, Column},
+            PhysicalExpr,
+        },
+        physical_plan::{ExecutionPlan, Partitioning, Statistics},
+    };
+
+    use crate::{
+        common::{
+            bucketing::{bucket_ids, BucketHash, BucketSpec},
+            datetime_rebase::RebaseConf,
+            schema_adapter::adapt_file_batch,
+        },
+        parquet_exec::{
+            coalesce_ranges, num_leaves, select_leaves, with_metadata_columns,
+            BloomFilterPredicate, FileMetadataColumn, FileMetadataValues, ParquetExec,
+        },
+    };
+
+    #[test]
+    fn test_coalesce_ranges() {
+        assert_eq!(coalesce_ranges(&[], 10, 100), vec![]);
+        assert_eq!(
+            coalesce_ranges(&[50..60, 0..10, 15..20, 5..12], 5, 100),
+            vec![0..20, 50..60],
+        );
+        assert_eq!(
+            coalesce_ranges(&[0..10, 15..20], 4, 100),
+            vec![0..10, 15..20]
+        );
+
+        // gaps are not coalesced beyond the max size, but overlapping ranges are
+        assert_eq!(
+            coalesce_ranges(&[0..50, 55..100, 100..120], 10, 100),
+            vec![0..100, 100..120],
+        );
+        assert_eq!(coalesce_ranges(&[0..80, 60..150], 10, 100), vec![0..150]);
+    }
+
+    #[test]
+    fn test_bloom_filter_predicate() -> Result<()> {
+        let batch = RecordBatch::try_from_iter([
+            ("a", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef),
+            (
+                "b",
+                Arc::new(StringArray::from(vec!["x", "y", "z"])) as ArrayRef,
+            ),
+        ])?;
+        let schema = batch.schema();
+        let props = WriterProperties::builder()
+            .set_bloom_filter_enabled(true)
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, schema.clone(), Some(props))?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        let data = Bytes::from(buf);
+        let metadata = parse_metadata(&data)?;
+        let row_group = metadata.row_group(0);
+        let may_match = |expr: Arc<dyn PhysicalExpr>| -> Result<bool> {
+            let predicate =
+                BloomFilterPredicate::try_new(&expr).expect("missing bloom filter conditions");
+            Ok(predicate.may_match(row_group, |column_idx| {
+                Sbbf::read_from_column_chunk(row_group.column(column_idx), Arc::new(data.clone()))
+            })?)
+        };
+        let a = || col("a", &schema).unwrap();
+        let b = || col("b", &schema).unwrap();
+        let eq = |l, r| binary(l, Operator::Eq, r, &schema).unwrap();
+        let and = |l, r| binary(l, Operator::And, r, &schema).unwrap();
+        let or = |l, r| binary(l, Operator::Or, r, &schema).unwrap();
+
+        assert!(may_match(eq(a(), lit(2i32)))?);
+        assert!(!may_match(eq(a(), lit(100i32)))?);
+        assert!(!may_match(eq(lit(100i32), a()))?);
+        assert!(may_match(eq(b(), lit("y")))?);
+        assert!(!may_match(eq(b(), lit("w")))?);
+        assert!(may_match(in_list(
+            a(),
+            vec![lit(5i32), lit(3i32)],
+            &false,
+            &schema
+        )?)?);
+        assert!(!may_match(in_list(
+            a(),
+            vec![lit(4i32), lit(5i32)],
+            &false,
+            &schema
+        )?)?);
+        assert!(!may_match(and(eq(a(), lit(100i32)), eq(b(), lit("y"))))?);
+        assert!(may_match(or(eq(a(), lit(100i32)), eq(a(), lit(2i32))))?);
+        assert!(!may_match(or(eq(a(), lit(100i32)), eq(a(), lit(200i32))))?);
+        assert!(
+            BloomFilterPredicate::try_new(&or(eq(a(), lit(100i32)), eq(b(), lit("y")))).is_none()
+        );
+        assert!(
+            BloomFilterPredicate::try_new(&in_list(a(), vec![lit(4i32)], &true, &schema)?)
+                .is_none()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_leaves() -> Result<()> {
+        let struct_type = |fields: Vec<Field>| DataType::Struct(Fields::from(fields));
+        let list_type = |item: DataType| DataType::List(Arc::new(Field::new("item", item, true)));
+        let map_type = |key: DataType, value: DataType| {
+            let entries = struct_type(vec![
+                Field::new("key", key, false),
+                Field::new("value", value, true),
+            ]);
+            DataType::Map(Arc::new(Field::new("entries", entries, false)), false)
+        };
+        let s_type = struct_type(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new(
+                "c",
+                list_type(struct_type(vec![
+                    Field::new("d", DataType::Int64, true),
+                    Field::new("e", DataType::Utf8, true),
+                ])),
+                true,
+            ),
+        ]);
+        let m_type = map_type(
+            DataType::Utf8,
+            struct_type(vec![
+                Field::new("f", DataType::Int32, true),
+                Field::new("g", DataType::Int32, true),
+            ]),
+        );
+        let file_schema = Schema::new(vec![
+            Field::new("s", s_type.clone(), true),
+            Field::new("m", m_type.clone(), true),
+            Field::new("x", DataType::Int32, true),
+        ]);
+        let parquet_schema = arrow_to_parquet_schema(&file_schema)?;
+        assert_eq!(
+            file_schema
+                .fields()
+                .iter()
+                .map(|field| num_leaves(field.data_type()))
+                .sum::<usize>(),
+            parquet_schema.num_columns(),
+        );
+
+        let mut leaves = vec![];
+        let pruned_s_type = struct_type(vec![
+            Field::new("b", DataType::Utf8, true),
+            Field::new(
+                "c",
+                list_type(struct_type(vec![Field::new("e", DataType::Utf8, true)])),
+                true,
+            ),
+        ]);
+        let pruned_m_type = map_type(
+            DataType::Utf8,
+            struct_type(vec![Field::new("g", DataType::Int32, true)]),
+        );
+        select_leaves(&s_type, &pruned_s_type, 0, &mut leaves);
+        select_leaves(&m_type, &pruned_m_type, 4, &mut leaves);
+        select_leaves(&DataType::Int32, &DataType::Int32, 7, &mut leaves);
+        let selected_paths = leaves
+            .iter()
+            .map(|&leaf| {
+                let parts = parquet_schema.column(leaf).path().parts().to_vec();
+                (parts[0].clone(), parts[parts.len() - 1].clone())
+            })
+            .collect::<Vec<_>>();
+        let expected_paths = [("s", "b"), ("s", "e"), ("m", "key"), ("m", "g"), ("x", "x")];
+        assert_eq!(
+            selected_paths,
+            expected_paths
+                .iter()
+                .map(|(root, leaf)| (root.to_string(), leaf.to_string()))
+                .collect::<Vec<_>>(),
+        );
+
+        // reads a pruned struct and adapts it to the table type
+        let s: ArrayRef = Arc::new(StructArray::from(vec![
+            (
+                Arc::new(Field::new("a", DataType::Int32, true)),
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+            ),
+            (
+                Arc::new(Field::new("b", DataType::Utf8, true)),
+                Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef,
+            ),
+        ]));
+        let batch = RecordBatch::try_from_iter([("s", s)])?;
+        let mut data = vec![];
+        let mut writer = ArrowWriter::try_new(&mut data, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        let table_type = struct_type(vec![
+            Field::new("b", DataType::Utf8, true),
+            Field::new("z", DataType::Int32, true),
+        ]);
+        let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(data))?;
+        let mut leaves = vec![];
+        select_leaves(
+            builder.schema().field(0).data_type(),
+            &table_type,
+            0,
+            &mut leaves,
+        );
+        assert_eq!(leaves, vec![1]);
+
+        let mask = ProjectionMask::leaves(builder.parquet_schema(), leaves);
+        let file_batch = builder.with_projection(mask).build()?.next().unwrap()?;
+        let output_schema = Arc::new(Schema::new(vec![Field::new("s", table_type, true)]));
+        let output = adapt_file_batch(&file_batch, &output_schema, &[Some(0)], &[0])?;
+        let expected = StructArray::from(vec![
+            (
+                Arc::new(Field::new("b", DataType::Utf8, true)),
+                Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef,
+            ),
+            (
+                Arc::new(Field::new("z", DataType::Int32, true)),
+                Arc::new(Int32Array::from(vec![None, None])) as ArrayRef,
+            ),
+        ]);
+        assert_eq!(as_struct_array(output.column(0))?, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_columns() -> Result<()> {
+        let config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::local_filesystem(),
+            file_schema: Arc::new(Schema::new(vec![
+                Field::new("a", DataType::Int32, true),
+                Field::new("b", DataType::Int32, true),
+            ])),
+            file_groups: vec![],
+            statistics: Statistics::default(),
+            projection: Some(vec![1, 2]),
+            limit: None,
+            table_partition_cols: vec![("p".to_string(), DataType::Utf8)],
+            output_ordering: vec![],
+            infinite_source: false,
+        };
+        let metadata_columns = vec![
+            ("_row_index".to_string(), FileMetadataColumn::RowIndex),
+            ("_file_name".to_string(), FileMetadataColumn::FileName),
+        ];
+        let config = with_metadata_columns(config, &metadata_columns);
+        assert_eq!(config.projection, Some(vec![1, 4, 2, 3]));
+        assert_eq!(
+            config
+                .project()
+                .0
+                .fields()
+                .iter()
+                .map(|field| field.name().as_str())
+                .collect::<Vec<_>>(),
+            vec!["b", "p", "_row_index", "_file_name"],
+        );
+
+        // row indices continue across selected row groups
+        let mut values = FileMetadataValues {
+            file_path: "hdfs://ns/t/part-0.parquet".to_string(),
+            file_size: 100,
+            modification_time: None,
+            row_ranges: VecDeque::from(vec![0..3, 10..12]),
+        };
+        let columns = [FileMetadataColumn::RowIndex, FileMetadataColumn::FileName];
+        let row_indices = values.take_row_indices(4);
+        let arrays = values.arrays(&columns, &row_indices);
+        assert_eq!(
+            as_int64_array(&arrays[0])?,
+            &Int64Array::from(vec![0, 1, 2, 10])
+        );
+        assert_eq!(
+            as_string_array(&arrays[1])?,
+            &StringArray::from(vec!["part-0.parquet"; 4]),
+        );
+        let row_indices = values.take_row_indices(1);
+        let arrays = values.arrays(&columns, &row_indices);
+        assert_eq!(as_int64_array(&arrays[0])?, &Int64Array::from(vec![11]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bucketed_scan() -> Result<()> {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let num_buckets = 4;
+        let bucket_file = |bucket_id: usize| {
+            let path = format!("hdfs://ns/t/part-00000-uuid_{bucket_id:05}.c000.parquet");
+            PartitionedFile::new(BASE64_URL_SAFE_NO_PAD.encode(path), 100)
+        };
+        let config = |files: Vec<PartitionedFile>, projection: Vec<usize>| {
+            let mut file_groups = vec![vec![]; num_buckets];
+            file_groups[0] = files;
+            FileScanConfig {
+                object_store_url: ObjectStoreUrl::local_filesystem(),
+                file_schema: file_schema.clone(),
+                file_groups,
+                statistics: Statistics::default(),
+                projection: Some(projection),
+                limit: None,
+                table_partition_cols: vec![],
+                output_ordering: vec![],
+                infinite_source: false,
+            }
+        };
+        let bucket_spec = BucketSpec {
+            bucket_columns: vec![0],
+            num_buckets,
+            sort_columns: vec![],
+            hash: BucketHash::Murmur3,
+        };
+        let num_files = |exec: &ParquetExec| {
+            exec.base_config
+                .file_groups
+                .iter()
+                .map(|files| files.len())
+                .collect::<Vec<_>>()
+        };
+
+        // files are grouped by bucket ids, output is hash partitioned by the
+        // projected bucket column
+        let exec = ParquetExec::new(
+            config(
+                vec![bucket_file(2), bucket_file(1), bucket_file(2)],
+                vec![1, 0],
+            ),
+            "".to_string(),
+            None,
+            vec![],
+            RebaseConf::default(),
+            Some(bucket_spec.clone()),
+        );
+        assert_eq!(num_files(&exec), vec![0, 1, 2, 0]);
+        match exec.output_partitioning() {
+            Partitioning::Hash(exprs, n) => {
+                assert_eq!(n, num_buckets);
+                let column = exprs[0].as_any().downcast_ref::<Column>().unwrap();
+                assert_eq!((column.name(), column.index()), ("a", 1));
+            }
+            partitioning => panic!("unexpected partitioning: {partitioning:?}"),
+        }
+
+        // buckets not matching the predicate are pruned
+        let a_bucket_id = bucket_ids(
+            &[Arc::new(Int32Array::from(vec![1])) as ArrayRef],
+            num_buckets,
+        )?[0];
+        let files = (0..num_buckets).map(bucket_file).collect::<Vec<_>>();
+        let predicate = binary(
+            col("a", &file_schema)?,
+            Operator::Eq,
+            lit(1i32),
+            &file_schema,
+        )?;
+        let exec = ParquetExec::new(
+            config(files, vec![0, 1]),
+            "".to_string(),
+            Some(predicate),
+            vec![],
+            RebaseConf::default(),
+            Some(bucket_spec.clone()),
+        );
+        let mut expected_num_files = vec![0; num_buckets];
+        expected_num_files[a_bucket_id] = 1;
+        assert_eq!(num_files(&exec), expected_num_files);
+
+        // bucket column is not projected
+        let exec = ParquetExec::new(
+            config(vec![bucket_file(1)], vec![1]),
+            "".to_string(),
+            None,
+            vec![],
+            RebaseConf::default(),
+            Some(bucket_spec.clone()),
+        );
+        assert!(matches!(
+            exec.output_partitioning(),
+            Partitioning::UnknownPartitioning(4)
+        ));
+
+        // scanned as non-bucketed with files not named by bucket ids
+        let files = vec![PartitionedFile::new(
+            BASE64_URL_SAFE_NO_PAD.encode("hdfs://ns/t/part-00000.parquet"),
+            100,
+        )];
+        let exec = ParquetExec::new(
+            config(files, vec![0, 1]),
+            "".to_string(),
+            None,
+            vec![],
+            RebaseConf::default(),
+            Some(bucket_spec),
+        );
+        assert_eq!(num_files(&exec), vec![1, 0, 0, 0]);
+        assert!(matches!(
+            exec.output_partitioning(),
+            Partitioning::UnknownPartitioning(4)
+        ));
+        Ok(())
     }
 }
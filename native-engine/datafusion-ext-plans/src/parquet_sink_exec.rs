@@ -15,21 +15,32 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::{any::Any, fmt::Formatter, io::Write, sync::Arc};
-
-use arrow::{
-    datatypes::SchemaRef,
-    record_batch::{RecordBatch, RecordBatchOptions},
+use std::{
+    any::Any,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::Formatter,
+    io::Write,
+    sync::Arc,
 };
-use blaze_jni_bridge::{jni_call_static, jni_get_string, jni_new_global_ref, jni_new_string};
+
+use arrow::{datatypes::SchemaRef, record_batch::RecordBatch};
+use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
 use datafusion::{
     common::{Result, ScalarValue, Statistics},
     execution::context::TaskContext,
     parquet::{
         arrow::{parquet_to_arrow_schema, ArrowWriter},
-        basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel},
-        file::properties::{WriterProperties, WriterVersion},
-        schema::{parser::parse_message_type, types::SchemaDescriptor},
+        basic::{BrotliLevel, Compression, GzipLevel, Type as PhysicalType, ZstdLevel},
+        file::{
+            metadata::RowGroupMetaData,
+            properties::{WriterProperties, WriterVersion},
+        },
+        format::{FileMetaData, KeyValue},
+        schema::{
+            parser::parse_message_type,
+            types::{from_thrift, ColumnPath, SchemaDescriptor},
+        },
     },
     physical_expr::PhysicalSortExpr,
     physical_plan::{
@@ -39,22 +50,29 @@ use datafusion::{
         SendableRecordBatchStream,
     },
 };
-use datafusion_ext_commons::{
-    cast::cast,
-    df_execution_err,
-    hadoop_fs::{FsDataOutputStream, FsProvider},
-};
-use futures::{stream::once, StreamExt, TryStreamExt};
+use datafusion_ext_commons::{df_execution_err, hadoop_fs::FsProvider};
+use futures::{stream::once, TryStreamExt};
 use parking_lot::Mutex;
 
-use crate::common::output::TaskOutputter;
+use crate::common::{
+    bucketing::BucketSpec,
+    datetime_rebase::{RebaseConf, RebaseSpec},
+    dyn_part_sink::{
+        create_part_file, execute_dyn_part_sink, file_stats_schema, ColumnFileStat,
+        DynPartSinkOutput, FSDataWriter, PartFileStat, PartWriter, SortedPartWriter,
+        SortedSinkContext,
+    },
+};
 
 #[derive(Debug)]
 pub struct ParquetSinkExec {
     fs_resource_id: String,
     input: Arc<dyn ExecutionPlan>,
     num_dyn_parts: usize,
+    bucket_spec: Option<BucketSpec>,
+    output: DynPartSinkOutput,
     props: Vec<(String, String)>,
+    rebase_conf: RebaseConf,
     metrics: ExecutionPlanMetricsSet,
 }
 
@@ -63,13 +81,19 @@ impl ParquetSinkExec {
         input: Arc<dyn ExecutionPlan>,
         fs_resource_id: String,
         num_dyn_parts: usize,
+        bucket_spec: Option<BucketSpec>,
+        output: DynPartSinkOutput,
         props: Vec<(String, String)>,
+        rebase_conf: RebaseConf,
     ) -> Self {
         Self {
             input,
             fs_resource_id,
             num_dyn_parts,
+            bucket_spec,
+            output,
             props,
+            rebase_conf,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
@@ -87,7 +111,10 @@ impl ExecutionPlan for ParquetSinkExec {
     }
 
     fn schema(&self) -> SchemaRef {
-        self.input.schema()
+        match &self.output {
+            DynPartSinkOutput::Identity => self.input.schema(),
+            DynPartSinkOutput::FileStats(_) => file_stats_schema(),
+        }
     }
 
     fn output_partitioning(&self) -> Partitioning {
@@ -110,7 +137,10 @@ impl ExecutionPlan for ParquetSinkExec {
             children[0].clone(),
             self.fs_resource_id.clone(),
             self.num_dyn_parts,
+            self.bucket_spec.clone(),
+            self.output.clone(),
             self.props.clone(),
+            self.rebase_conf.clone(),
         )))
     }
 
@@ -151,21 +181,50 @@ impl ExecutionPlan for ParquetSinkExec {
             self.num_dyn_parts,
             &io_time,
             &self.props,
+            &self.rebase_conf,
         )?);
 
+        let hive_schema = parquet_sink_context.hive_schema.clone();
+        let num_dyn_parts = parquet_sink_context.num_dyn_parts;
+        let bucket_spec = self.bucket_spec.clone();
         let input = self.input.execute(partition, context.clone())?;
-        let output = Box::pin(RecordBatchStreamAdapter::new(
-            self.schema(),
-            once(execute_parquet_sink(
-                context,
-                parquet_sink_context,
-                input,
-                metrics,
-                bytes_written,
-            ))
-            .try_flatten(),
-        ));
-        Ok(output)
+        macro_rules! execute_sink {
+            ($writer:ty, $sink_context:expr) => {{
+                Box::pin(RecordBatchStreamAdapter::new(
+                    self.schema(),
+                    once(execute_dyn_part_sink::<$writer>(
+                        context,
+                        $sink_context,
+                        hive_schema,
+                        num_dyn_parts,
+                        bucket_spec,
+                        self.output.clone(),
+                        input,
+                        metrics,
+                        bytes_written,
+                    ))
+                    .try_flatten(),
+                ))
+            }};
+        }
+
+        // bucket files with sort columns are sorted before writing
+        let sort_columns = bucket_spec
+            .as_ref()
+            .map(|bucket_spec| bucket_spec.sort_columns.clone())
+            .unwrap_or_default();
+        if sort_columns.is_empty() {
+            return Ok(execute_sink!(ParquetPartWriter, parquet_sink_context));
+        }
+        let sorted_sink_context = Arc::new(SortedSinkContext::<ParquetPartWriter> {
+            inner: parquet_sink_context,
+            sort_columns,
+            batch_size: context.session_config().batch_size(),
+        });
+        Ok(execute_sink!(
+            SortedPartWriter<ParquetPartWriter>,
+            sorted_sink_context
+        ))
     }
 
     fn metrics(&self) -> Option<MetricsSet> {
@@ -184,6 +243,7 @@ struct ParquetSinkContext {
     num_dyn_parts: usize,
     row_group_block_size: usize,
     props: WriterProperties,
+    rebase_spec: RebaseSpec,
 }
 
 impl ParquetSinkContext {
@@ -193,6 +253,7 @@ impl ParquetSinkContext {
         num_dyn_parts: usize,
         io_time: &Time,
         props: &[(String, String)],
+        rebase_conf: &RebaseConf,
     ) -> Result<Self> {
         let fs_provider = {
             let resource_id = jni_new_string!(&fs_resource_id)?;
@@ -226,126 +287,19 @@ impl ParquetSinkContext {
             hive_schema,
             num_dyn_parts,
             row_group_block_size,
-            props: parse_writer_props(props),
+            props: parse_writer_props(props, rebase_conf.write_metadata()),
+            rebase_spec: rebase_conf.write_spec(),
         })
     }
 }
 
-async fn execute_parquet_sink(
-    context: Arc<TaskContext>,
-    parquet_sink_context: Arc<ParquetSinkContext>,
-    mut input: SendableRecordBatchStream,
-    metrics: BaselineMetrics,
-    bytes_written: Count,
-) -> Result<SendableRecordBatchStream> {
-    let schema = input.schema();
-    let part_writer: Arc<Mutex<Option<PartWriter>>> = Arc::default();
-
-    context.output_with_sender("ParquetSink", schema.clone(), move |sender| async move {
-        macro_rules! part_writer_init {
-            ($batch:expr, $part_values:expr) => {{
-                let parquet_sink_context_cloned = parquet_sink_context.clone();
-                *part_writer.lock() = Some({
-                    // send identity batch, after that we can achieve a new output file
-                    sender.send(Ok($batch.slice(0, 1)), None).await;
-                    tokio::task::spawn_blocking(move || {
-                        PartWriter::try_new(parquet_sink_context_cloned, $part_values)
-                    })
-                    .await
-                    .or_else(|e| df_execution_err!("closing parquet file error: {e}"))??
-                });
-            }};
-        }
-        macro_rules! part_writer_output {
-            ($batch:expr) => {{
-                let part_writer = part_writer.clone();
-                let batch = adapt_schema($batch, &parquet_sink_context.hive_schema)?;
-                tokio::task::spawn_blocking(move || {
-                    let mut part_writer = part_writer.lock();
-                    let w = part_writer.as_mut().unwrap();
-                    w.write(&batch)
-                })
-                .await
-                .or_else(|e| df_execution_err!("writing parquet file error: {e}"))??;
-            }};
-        }
-        macro_rules! part_writer_close {
-            () => {{
-                let maybe_writer = part_writer.lock().take();
-                if let Some(w) = maybe_writer {
-                    let file_stat = tokio::task::spawn_blocking(move || w.close())
-                        .await
-                        .or_else(|e| df_execution_err!("closing parquet file error: {e}"))??;
-
-                    jni_call_static!(
-                        BlazeNativeParquetSinkUtils.completeOutput(
-                            jni_new_string!(&file_stat.path)?.as_obj(),
-                            file_stat.num_rows as i64,
-                            file_stat.num_bytes as i64,
-                        ) -> ()
-                    )?;
-                    metrics.output_rows().add(file_stat.num_rows);
-                    bytes_written.add(file_stat.num_bytes);
-                }
-            }}
-        }
-
-        // write parquet data
-        while let Some(mut batch) = input.next().await.transpose()? {
-            let _timer = metrics.elapsed_compute().timer();
-            if batch.num_rows() == 0 {
-                continue;
-            }
-
-            while batch.num_rows() > 0 {
-                let part_values =
-                    get_dyn_part_values(&batch, parquet_sink_context.num_dyn_parts, 0)?;
-                let part_writer_outdated =
-                    part_writer.lock().as_ref().map(|w| &w.part_values) != Some(&part_values);
-
-                if part_writer_outdated {
-                    part_writer_close!();
-                    part_writer_init!(batch, &part_values);
-                    continue;
-                }
-
-                // split batch into current part and rest parts, then write current part
-                let m = rfind_part_values(&batch, &part_values)?;
-                let cur_batch = batch.slice(0, m);
-                batch = batch.slice(m, batch.num_rows() - m);
-                part_writer_output!(&cur_batch);
-            }
-        }
-        part_writer_close!();
-        Ok(())
-    })
-}
-
-fn adapt_schema(batch: &RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
-    let num_rows = batch.num_rows();
-    let mut casted_cols = vec![];
-
-    for (col_idx, casted_field) in schema.fields().iter().enumerate() {
-        casted_cols.push(cast(batch.column(col_idx), casted_field.data_type())?);
-    }
-    Ok(RecordBatch::try_new_with_options(
-        schema.clone(),
-        casted_cols,
-        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
-    )?)
-}
-
-fn rfind_part_values(batch: &RecordBatch, part_values: &[ScalarValue]) -> Result<usize> {
-    for row_idx in (0..batch.num_rows()).rev() {
-        if get_dyn_part_values(batch, part_values.len(), row_idx)? == part_values {
-            return Ok(row_idx + 1);
-        }
-    }
-    Ok(0)
-}
-
-fn parse_writer_props(prop_kvs: &[(String, String)]) -> WriterProperties {
-    let mut builder = WriterProperties::builder().set_created_by(format!("blaze-engine"));
+fn parse_writer_props(
+    prop_kvs: &[(String, String)],
+    key_value_metadata: Vec<KeyValue>,
+) -> WriterProperties {
+    let mut builder = WriterProperties::builder()
+        .set_created_by(format!("blaze-engine"))
+        .set_key_value_metadata(Some(key_value_metadata).filter(|kvs| !kvs.is_empty()));
 
     macro_rules! setprop {
         ($key:expr, $value:expr, $tnum:ty, $setfn:ident) => {{
@@ -358,7 +312,47 @@ fn parse_writer_props(prop_kvs: &[(String, String)]) -> WriterProperties {
         }};
     }
 
+    // bloom filter props are collected first because ndv/fpp only take effect
+    // on columns with bloom filter enabled, which is the same as parquet-mr
+    let mut bloom_filter_enabled = false;
+    let mut bloom_filter_columns: HashMap<String, bool> = HashMap::new();
+    let mut bloom_filter_ndvs: HashMap<String, u64> = HashMap::new();
+    let mut bloom_filter_fpps: HashMap<String, f64> = HashMap::new();
+
+    macro_rules! setcolprop {
+        ($key:expr, $value:expr, $tnum:ty, $column:expr, $map:expr) => {{
+            if let Ok(value) = $value.trim().parse::<$tnum>() {
+                $map.insert($column.to_string(), value);
+            } else {
+                log::warn!("invalid parquet prop value: {}={}", $key, $value);
+            }
+        }};
+    }
+
     for (key, value) in prop_kvs {
+        if let Some((prop, column)) = key.split_once('#') {
+            match prop {
+                "parquet.bloom.filter.enabled" => {
+                    setcolprop!(key, value, bool, column, bloom_filter_columns)
+                }
+                "parquet.bloom.filter.expected.ndv" => {
+                    setcolprop!(key, value, u64, column, bloom_filter_ndvs)
+                }
+                "parquet.bloom.filter.fpp" => {
+                    setcolprop!(key, value, f64, column, bloom_filter_fpps)
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if key == "parquet.bloom.filter.enabled" {
+            match value.trim().parse::<bool>() {
+                Ok(enabled) => bloom_filter_enabled = enabled,
+                Err(_) => log::warn!("invalid parquet prop value: {}={}", key, value),
+            }
+            continue;
+        }
+
         builder = match key.as_ref() {
             "parquet.page.size" => setprop!(key, value, usize, set_data_page_size_limit),
             "parquet.enable.dictionary" => setprop!(key, value, bool, set_dictionary_enabled),
@@ -396,61 +390,89 @@ fn parse_writer_props(prop_kvs: &[(String, String)]) -> WriterProperties {
             _ => builder,
         }
     }
-    builder.build()
-}
 
-struct PartFileStat {
-    path: String,
-    num_rows: usize,
-    num_bytes: usize,
+    builder = builder.set_bloom_filter_enabled(bloom_filter_enabled);
+    let configured_columns = bloom_filter_columns
+        .keys()
+        .chain(bloom_filter_ndvs.keys())
+        .chain(bloom_filter_fpps.keys())
+        .cloned()
+        .collect::<HashSet<_>>();
+    for column in configured_columns {
+        let column_path = ColumnPath::new(column.split('.').map(|s| s.to_string()).collect());
+        let enabled = bloom_filter_columns
+            .get(&column)
+            .cloned()
+            .unwrap_or(bloom_filter_enabled);
+        if !enabled {
+            builder = builder.set_column_bloom_filter_enabled(column_path, false);
+            continue;
+        }
+        builder = builder.set_column_bloom_filter_enabled(column_path.clone(), true);
+        if let Some(&ndv) = bloom_filter_ndvs.get(&column) {
+            builder = builder.set_column_bloom_filter_ndv(column_path.clone(), ndv);
+        }
+        if let Some(&fpp) = bloom_filter_fpps.get(&column) {
+            builder = builder.set_column_bloom_filter_fpp(column_path, fpp);
+        }
+    }
+    builder.build()
 }
 
-struct PartWriter {
+struct ParquetPartWriter {
     path: String,
     parquet_sink_context: Arc<ParquetSinkContext>,
-    parquet_writer: ArrowWriter<FSDataWriter>,
+    parquet_writer: ArrowWriter<SharedFSDataWriter>,
+    data_writer: SharedFSDataWriter,
     part_values: Vec<ScalarValue>,
     rows_written: Count,
     bytes_written: Count,
 }
 
-impl PartWriter {
+impl PartWriter for ParquetPartWriter {
+    type SinkContext = ParquetSinkContext;
+
+    const SINK_NAME: &'static str = "ParquetSink";
+    const FILE_EXTENSION: &'static str = ".parquet";
+
     fn try_new(
         parquet_sink_context: Arc<ParquetSinkContext>,
         part_values: &[ScalarValue],
+        path: String,
     ) -> Result<Self> {
         if !part_values.is_empty() {
             log::info!("start outputting dynamic partition: {part_values:?}");
         }
-        let part_file = jni_get_string!(
-            jni_call_static!(BlazeNativeParquetSinkUtils.getTaskOutputPath() -> JObject)?
-                .as_obj()
-                .into()
-        )?;
-        log::info!("start writing parquet file: {part_file}");
-
-        let fs = parquet_sink_context.fs_provider.provide(&part_file)?;
         let bytes_written = Count::new();
         let rows_written = Count::new();
-        let fout = fs.create(&part_file)?;
-        let data_writer = FSDataWriter::new(fout, &bytes_written);
+        let data_writer = SharedFSDataWriter(Arc::new(Mutex::new(Some(create_part_file(
+            &parquet_sink_context.fs_provider,
+            &path,
+            &bytes_written,
+        )?))));
         let parquet_writer = ArrowWriter::try_new(
-            data_writer,
+            data_writer.clone(),
             parquet_sink_context.hive_schema.clone(),
             Some(parquet_sink_context.props.clone()),
         )?;
         Ok(Self {
-            path: part_file,
+            path,
             parquet_sink_context,
             parquet_writer,
+            data_writer,
             part_values: part_values.to_vec(),
             rows_written,
             bytes_written,
         })
     }
 
+    fn part_values(&self) -> &[ScalarValue] {
+        &self.part_values
+    }
+
     fn write(&mut self, batch: &RecordBatch) -> Result<()> {
         let row_group_block_size = self.parquet_sink_context.row_group_block_size;
+        let batch = self.parquet_sink_context.rebase_spec.rebase_batch(batch)?;
         self.parquet_writer.write(&batch)?;
         if self.parquet_writer.in_progress_size() >= row_group_block_size {
             self.parquet_writer.flush()?;
@@ -459,57 +481,236 @@ impl PartWriter {
         Ok(())
     }
 
+    fn num_rows(&self) -> usize {
+        self.rows_written.value()
+    }
+
+    fn file_size(&self) -> usize {
+        self.bytes_written.value() + self.parquet_writer.in_progress_size()
+    }
+
+    fn mem_size(&self) -> usize {
+        self.parquet_writer.in_progress_size()
+    }
+
     fn close(self) -> Result<PartFileStat> {
-        self.parquet_writer.into_inner()?.close()?;
+        let file_meta = self.parquet_writer.close()?;
+        self.data_writer.close()?;
         Ok(PartFileStat {
             path: self.path,
+            part_values: self.part_values,
             num_rows: self.rows_written.value(),
             num_bytes: self.bytes_written.value(),
+            column_stats: column_file_stats(&file_meta)?,
         })
     }
 }
 
-fn get_dyn_part_values(
-    batch: &RecordBatch,
-    num_dyn_parts: usize,
-    row_idx: usize,
-) -> Result<Vec<ScalarValue>> {
-    batch
-        .columns()
-        .iter()
-        .skip(batch.num_columns() - num_dyn_parts)
-        .map(|part_col| ScalarValue::try_from_array(part_col, row_idx))
-        .collect()
+// output stream shared with the parquet writer. `ArrowWriter::close()` drops
+// the underlying writer after returning the file metadata, so the stream is
+// closed through the other reference.
+#[derive(Clone)]
+struct SharedFSDataWriter(Arc<Mutex<Option<FSDataWriter>>>);
+
+impl SharedFSDataWriter {
+    fn close(&self) -> Result<()> {
+        match self.0.lock().take() {
+            Some(data_writer) => data_writer.close(),
+            None => Ok(()),
+        }
+    }
 }
 
-// Write wrapper for FSDataOutputStream
-struct FSDataWriter {
-    inner: FsDataOutputStream,
-    bytes_written: Count,
+impl Write for SharedFSDataWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.0.lock().as_mut() {
+            Some(data_writer) => data_writer.write(buf),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "writing closed file",
+            )),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
-impl FSDataWriter {
-    pub fn new(inner: FsDataOutputStream, bytes_written: &Count) -> Self {
-        Self {
-            inner,
-            bytes_written: bytes_written.clone(),
+/// merges statistics of all row groups for every leaf column
+fn column_file_stats(file_meta: &FileMetaData) -> Result<Vec<ColumnFileStat>> {
+    let schema_descr = Arc::new(SchemaDescriptor::new(from_thrift(&file_meta.schema)?));
+    let mut column_stats = schema_descr
+        .columns()
+        .iter()
+        .map(|column| ColumnFileStat {
+            name: column.path().string(),
+            null_count: Some(0),
+            min_value: None,
+            max_value: None,
+        })
+        .collect::<Vec<_>>();
+    let mut min_max_missing = vec![false; column_stats.len()];
+
+    for row_group in &file_meta.row_groups {
+        let row_group = RowGroupMetaData::from_thrift(schema_descr.clone(), row_group.clone())?;
+        for (i, column) in row_group.columns().iter().enumerate() {
+            let column_stat = &mut column_stats[i];
+            let physical_type = column.column_type();
+            match column.statistics() {
+                Some(stats) => {
+                    column_stat.null_count = column_stat
+                        .null_count
+                        .map(|null_count| null_count + stats.null_count() as i64);
+
+                    // a row group of all nulls has no min/max values
+                    if stats.has_min_max_set() {
+                        let (min, max) = (stats.min_bytes(), stats.max_bytes());
+                        if column_stat.min_value.as_ref().map_or(true, |cur| {
+                            compare_plain_values(physical_type, min, cur).is_lt()
+                        }) {
+                            column_stat.min_value = Some(min.to_vec());
+                        }
+                        if column_stat.max_value.as_ref().map_or(true, |cur| {
+                            compare_plain_values(physical_type, max, cur).is_gt()
+                        }) {
+                            column_stat.max_value = Some(max.to_vec());
+                        }
+                    } else if stats.null_count() < row_group.num_rows() as u64 {
+                        min_max_missing[i] = true;
+                    }
+                }
+                None => {
+                    column_stat.null_count = None;
+                    min_max_missing[i] = true;
+                }
+            }
         }
     }
 
-    pub fn close(self) -> Result<()> {
-        self.inner.close()
+    for (column_stat, missing) in column_stats.iter_mut().zip(min_max_missing) {
+        if missing {
+            column_stat.min_value = None;
+            column_stat.max_value = None;
+        }
     }
+    Ok(column_stats)
 }
-impl Write for FSDataWriter {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.inner
-            .write_fully(&buf)
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
-        self.bytes_written.add(buf.len());
-        Ok(buf.len())
+
+/// compares plain encoded values in the order used by parquet statistics
+fn compare_plain_values(physical_type: PhysicalType, a: &[u8], b: &[u8]) -> Ordering {
+    macro_rules! compare_le {
+        ($tnum:ty) => {{
+            match (a.try_into(), b.try_into()) {
+                (Ok(a), Ok(b)) => <$tnum>::from_le_bytes(a)
+                    .partial_cmp(&<$tnum>::from_le_bytes(b))
+                    .unwrap_or(Ordering::Equal),
+                _ => a.cmp(b),
+            }
+        }};
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
-        Ok(())
+    match physical_type {
+        PhysicalType::INT32 => compare_le!(i32),
+        PhysicalType::INT64 => compare_le!(i64),
+        PhysicalType::FLOAT => compare_le!(f32),
+        PhysicalType::DOUBLE => compare_le!(f64),
+        PhysicalType::FIXED_LEN_BYTE_ARRAY => {
+            // decimals in big-endian two's complement
+            let sign_a = a.first().map(|&b| b as i8 >= 0);
+            let sign_b = b.first().map(|&b| b as i8 >= 0);
+            sign_a.cmp(&sign_b).then_with(|| a.cmp(b))
+        }
+        _ => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use datafusion::parquet::{basic::Type as PhysicalType, schema::types::ColumnPath};
+
+    use crate::parquet_sink_exec::{compare_plain_values, parse_writer_props};
+
+    #[test]
+    fn test_parse_bloom_filter_props() {
+        let props = [
+            ("parquet.bloom.filter.enabled#a", "true"),
+            ("parquet.bloom.filter.expected.ndv#a", "1000"),
+            ("parquet.bloom.filter.fpp#a", "0.01"),
+            ("parquet.bloom.filter.expected.ndv#b", "1000"),
+            ("parquet.bloom.filter.enabled#c.d", "true"),
+            ("parquet.bloom.filter.fpp#c.d", "invalid"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<Vec<_>>();
+
+        let writer_props = parse_writer_props(&props, vec![]);
+        let a = writer_props
+            .bloom_filter_properties(&ColumnPath::from("a"))
+            .expect("bloom filter of column a should be enabled");
+        assert_eq!(a.ndv, 1000);
+        assert_eq!(a.fpp, 0.01);
+        assert!(writer_props
+            .bloom_filter_properties(&ColumnPath::from("b"))
+            .is_none());
+        assert!(writer_props
+            .bloom_filter_properties(&ColumnPath::new(vec!["c".to_string(), "d".to_string()]))
+            .is_some());
+        assert!(writer_props
+            .bloom_filter_properties(&ColumnPath::from("e"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_compare_plain_values() {
+        let cmp = |physical_type, a: &[u8], b: &[u8]| compare_plain_values(physical_type, a, b);
+        assert_eq!(
+            cmp(
+                PhysicalType::INT32,
+                &(-1i32).to_le_bytes(),
+                &2i32.to_le_bytes()
+            ),
+            Ordering::Less,
+        );
+        assert_eq!(
+            cmp(
+                PhysicalType::INT64,
+                &300i64.to_le_bytes(),
+                &2i64.to_le_bytes()
+            ),
+            Ordering::Greater,
+        );
+        assert_eq!(
+            cmp(
+                PhysicalType::DOUBLE,
+                &(-0.5f64).to_le_bytes(),
+                &0.25f64.to_le_bytes()
+            ),
+            Ordering::Less,
+        );
+        assert_eq!(cmp(PhysicalType::BYTE_ARRAY, b"ab", b"b"), Ordering::Less);
+        assert_eq!(
+            cmp(PhysicalType::BYTE_ARRAY, b"\xff", b"a"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            cmp(
+                PhysicalType::FIXED_LEN_BYTE_ARRAY,
+                &[0xff, 0x00],
+                &[0x00, 0x01]
+            ),
+            Ordering::Less,
+        );
+        assert_eq!(
+            cmp(
+                PhysicalType::FIXED_LEN_BYTE_ARRAY,
+                &[0x00, 0x02],
+                &[0x00, 0x01]
+            ),
+            Ordering::Greater,
+        );
     }
 }
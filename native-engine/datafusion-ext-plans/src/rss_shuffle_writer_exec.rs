@@ -30,6 +30,7 @@ use datafusion::{
         Statistics,
     },
 };
+use datafusion_ext_commons::io::{IoCompressionCodec, IoCompressionSite};
 use futures::{stream::once, TryStreamExt};
 
 use crate::{
@@ -118,17 +119,20 @@ impl ExecutionPlan for RssShuffleWriterExec {
 
         // record uncompressed data size
         let data_size_metric = MetricBuilder::new(&self.metrics).counter("data_size", partition);
+        let codec = IoCompressionCodec::try_from_conf(IoCompressionSite::Rss)?;
 
         let input = self.input.execute(partition, context.clone())?;
         let repartitioner: Arc<dyn ShuffleRepartitioner> = match &self.partitioning {
             p if p.partition_count() == 1 => Arc::new(RssSingleShuffleRepartitioner::new(
                 rss_partition_writer,
+                codec,
                 data_size_metric,
             )),
             Partitioning::Hash(..) => {
                 let partitioner = Arc::new(RssSortShuffleRepartitioner::new(
                     partition,
                     rss_partition_writer,
+                    codec,
                     self.partitioning.clone(),
                     data_size_metric,
                     context.clone(),
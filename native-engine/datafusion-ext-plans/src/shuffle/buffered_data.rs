@@ -3,7 +3,8 @@ use std::io::{Cursor, Write};
 use arrow::record_batch::RecordBatch;
 use datafusion::common;
 use datafusion_ext_commons::{
-    io::write_one_batch,
+    checksum::PartitionChecksums,
+    io::{write_one_batch, IoCompressionCodec},
     loser_tree::{ComparableForLoserTree, LoserTree},
 };
 
@@ -18,13 +19,16 @@ pub struct BufferedData {
 }
 
 impl BufferedData {
-    // write batch to spill/target file, returns offsets to each partition
+    // write batch to spill/target file, returns offsets to each partition.
+    // checksums of partitions are updated with the written bytes if provided
     pub fn write<W: Write>(
         self,
         mut w: W,
         batch_size: usize,
         num_partitions: usize,
+        codec: IoCompressionCodec,
         uncompressed_size: &mut usize,
+        mut checksums: Option<&mut PartitionChecksums>,
     ) -> common::Result<Vec<u64>> {
         let mut cur_partition_id = 0;
         let mut offsets = vec![0];
@@ -39,10 +43,13 @@ impl BufferedData {
             write_one_batch(
                 &batch,
                 &mut Cursor::new(&mut buf),
-                true,
+                codec,
                 Some(uncompressed_size),
             )?;
             w.write_all(&buf)?;
+            if let Some(checksums) = &mut checksums {
+                checksums.update(partition_id as usize, &buf);
+            }
             cur_offset += buf.len() as u64;
         }
         while cur_partition_id < num_partitions as u32 {
@@ -23,6 +23,7 @@ use datafusion::{
     physical_plan::{metrics::BaselineMetrics, Partitioning, SendableRecordBatchStream},
 };
 use datafusion_ext_commons::{
+    checksum::ChecksumAlgorithm,
     rdxsort::radix_sort_u16_with_max_key_by,
     spark_hash::{create_hashes, pmod},
     streams::coalesce_stream::CoalesceInput,
@@ -79,6 +80,13 @@ impl dyn ShuffleRepartitioner {
     }
 }
 
+/// conf of writing spark-compatible checksums of the output partitions
+#[derive(Debug, Clone)]
+pub struct ShuffleChecksumConf {
+    pub algorithm: ChecksumAlgorithm,
+    pub output_checksum_file: String,
+}
+
 struct ShuffleSpill {
     spill: Box<dyn Spill>,
     offsets: Vec<u64>,
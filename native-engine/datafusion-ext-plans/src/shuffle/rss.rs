@@ -17,13 +17,14 @@ use std::io::Cursor;
 use arrow::record_batch::RecordBatch;
 use blaze_jni_bridge::{jni_call, jni_new_direct_byte_buffer};
 use datafusion::common::Result;
-use datafusion_ext_commons::io::write_one_batch;
+use datafusion_ext_commons::io::{write_one_batch, IoCompressionCodec};
 use jni::objects::GlobalRef;
 
 pub fn rss_write_batch(
     rss_partition_writer: &GlobalRef,
     partition_id: usize,
     batch: RecordBatch,
+    codec: IoCompressionCodec,
     uncompressed_size: &mut usize,
 ) -> Result<()> {
     let mut data = vec![];
@@ -31,7 +32,7 @@ pub fn rss_write_batch(
     write_one_batch(
         &batch,
         &mut Cursor::new(&mut data),
-        true,
+        codec,
         Some(uncompressed_size),
     )?;
     let data_len = data.len();
@@ -17,20 +17,26 @@ use std::io::Cursor;
 use async_trait::async_trait;
 use blaze_jni_bridge::{jni_call, jni_new_direct_byte_buffer};
 use datafusion::{arrow::record_batch::RecordBatch, common::Result, physical_plan::metrics::Count};
-use datafusion_ext_commons::io::write_one_batch;
+use datafusion_ext_commons::io::{write_one_batch, IoCompressionCodec};
 use jni::objects::GlobalRef;
 
 use crate::shuffle::ShuffleRepartitioner;
 
 pub struct RssSingleShuffleRepartitioner {
     rss_partition_writer: GlobalRef,
+    codec: IoCompressionCodec,
     data_size_metric: Count,
 }
 
 impl RssSingleShuffleRepartitioner {
-    pub fn new(rss_partition_writer: GlobalRef, data_size_metric: Count) -> Self {
+    pub fn new(
+        rss_partition_writer: GlobalRef,
+        codec: IoCompressionCodec,
+        data_size_metric: Count,
+    ) -> Self {
         Self {
             rss_partition_writer,
+            codec,
             data_size_metric,
         }
     }
@@ -44,7 +50,7 @@ impl ShuffleRepartitioner for RssSingleShuffleRepartitioner {
         write_one_batch(
             &input,
             &mut cursor,
-            true,
+            self.codec,
             Some(&mut num_bytes_written_uncompressed),
         )?;
         self.data_size_metric.add(num_bytes_written_uncompressed);
@@ -21,6 +21,7 @@ use datafusion::{
     execution::context::TaskContext,
     physical_plan::{metrics::Count, Partitioning},
 };
+use datafusion_ext_commons::io::IoCompressionCodec;
 use futures::lock::Mutex;
 use jni::objects::GlobalRef;
 
@@ -39,6 +40,7 @@ pub struct RssSortShuffleRepartitioner {
     data: Mutex<BufferedData>,
     partitioning: Partitioning,
     rss_partition_writer: GlobalRef,
+    codec: IoCompressionCodec,
     batch_size: usize,
     data_size_metric: Count,
 }
@@ -47,6 +49,7 @@ impl RssSortShuffleRepartitioner {
     pub fn new(
         partition_id: usize,
         rss_partition_writer: GlobalRef,
+        codec: IoCompressionCodec,
         partitioning: Partitioning,
         data_size_metric: Count,
         context: Arc<TaskContext>,
@@ -58,6 +61,7 @@ impl RssSortShuffleRepartitioner {
             data: Mutex::default(),
             partitioning,
             rss_partition_writer,
+            codec,
             batch_size,
             data_size_metric,
         }
@@ -89,6 +93,7 @@ impl MemConsumer for RssSortShuffleRepartitioner {
                 &self.rss_partition_writer,
                 partition_id as usize,
                 batch,
+                self.codec,
                 &mut uncompressed_size,
             )?;
             self.data_size_metric.add(uncompressed_size);
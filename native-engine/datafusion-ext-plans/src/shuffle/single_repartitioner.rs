@@ -14,7 +14,7 @@
 
 use std::{
     fs::{File, OpenOptions},
-    io::{Seek, Write},
+    io::{Cursor, Seek, Write},
 };
 
 use arrow::record_batch::RecordBatch;
@@ -24,15 +24,21 @@ use datafusion::{
     error::DataFusionError,
     physical_plan::metrics::{BaselineMetrics, Count},
 };
-use datafusion_ext_commons::io::write_one_batch;
+use datafusion_ext_commons::{
+    checksum::PartitionChecksums,
+    io::{write_one_batch, IoCompressionCodec},
+};
 use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
 
-use crate::shuffle::ShuffleRepartitioner;
+use crate::shuffle::{ShuffleChecksumConf, ShuffleRepartitioner};
 
 pub struct SingleShuffleRepartitioner {
     output_data_file: String,
     output_index_file: String,
     output_data: OnceCell<File>,
+    checksum_file_and_checksums: Option<(String, Mutex<PartitionChecksums>)>,
+    codec: IoCompressionCodec,
     metrics: BaselineMetrics,
     data_size_metric: Count,
 }
@@ -41,6 +47,8 @@ impl SingleShuffleRepartitioner {
     pub fn new(
         output_data_file: String,
         output_index_file: String,
+        checksum_conf: Option<ShuffleChecksumConf>,
+        codec: IoCompressionCodec,
         metrics: BaselineMetrics,
         data_size_metric: Count,
     ) -> Self {
@@ -48,6 +56,11 @@ impl SingleShuffleRepartitioner {
             output_data_file,
             output_index_file,
             output_data: OnceCell::new(),
+            checksum_file_and_checksums: checksum_conf.map(|conf| {
+                let checksums = PartitionChecksums::new(conf.algorithm, 1);
+                (conf.output_checksum_file, Mutex::new(checksums))
+            }),
+            codec,
             metrics,
             data_size_metric,
         }
@@ -71,12 +84,17 @@ impl ShuffleRepartitioner for SingleShuffleRepartitioner {
     async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
         let _timer = self.metrics.elapsed_compute().timer();
         let mut num_bytes_written_uncompressed = 0;
+        let mut buf = vec![];
         write_one_batch(
             &input,
-            &mut self.get_output_data()?.try_clone()?,
-            true,
+            &mut Cursor::new(&mut buf),
+            self.codec,
             Some(&mut num_bytes_written_uncompressed),
         )?;
+        self.get_output_data()?.write_all(&buf)?;
+        if let Some((_, checksums)) = &self.checksum_file_and_checksums {
+            checksums.lock().update(0, &buf);
+        }
         self.data_size_metric.add(num_bytes_written_uncompressed);
         Ok(())
     }
@@ -89,6 +107,10 @@ impl ShuffleRepartitioner for SingleShuffleRepartitioner {
         output_index.write_all(&[0u8; 8])?;
         output_index.write_all(&(offset as i64).to_le_bytes()[..])?;
         output_index.sync_data()?;
+
+        if let Some((checksum_file, checksums)) = &self.checksum_file_and_checksums {
+            checksums.lock().write_checksum_file(checksum_file)?;
+        }
         Ok(())
     }
 }
@@ -29,7 +29,9 @@ use datafusion::{
     },
 };
 use datafusion_ext_commons::{
+    checksum::PartitionChecksums,
     df_execution_err,
+    io::IoCompressionCodec,
     loser_tree::{ComparableForLoserTree, LoserTree},
 };
 use futures::lock::Mutex;
@@ -41,7 +43,9 @@ use crate::{
         MemConsumer, MemConsumerInfo, MemManager,
     },
     shuffle,
-    shuffle::{buffered_data::BufferedData, ShuffleRepartitioner, ShuffleSpill},
+    shuffle::{
+        buffered_data::BufferedData, ShuffleChecksumConf, ShuffleRepartitioner, ShuffleSpill,
+    },
 };
 
 pub struct SortShuffleRepartitioner {
@@ -49,6 +53,8 @@ pub struct SortShuffleRepartitioner {
     mem_consumer_info: Option<Weak<MemConsumerInfo>>,
     output_data_file: String,
     output_index_file: String,
+    checksum_conf: Option<ShuffleChecksumConf>,
+    codec: IoCompressionCodec,
     data: Mutex<BufferedData>,
     spills: Mutex<Vec<ShuffleSpill>>,
     partitioning: Partitioning,
@@ -63,6 +69,8 @@ impl SortShuffleRepartitioner {
         partition_id: usize,
         output_data_file: String,
         output_index_file: String,
+        checksum_conf: Option<ShuffleChecksumConf>,
+        codec: IoCompressionCodec,
         partitioning: Partitioning,
         metrics: &ExecutionPlanMetricsSet,
         data_size_metric: Count,
@@ -76,6 +84,8 @@ impl SortShuffleRepartitioner {
             mem_consumer_info: None,
             output_data_file,
             output_index_file,
+            checksum_conf,
+            codec,
             data: Mutex::default(),
             spills: Mutex::default(),
             partitioning,
@@ -112,7 +122,9 @@ impl MemConsumer for SortShuffleRepartitioner {
             spill.get_buf_writer(),
             self.batch_size,
             self.partitioning.partition_count(),
+            self.codec,
             &mut uncompressed_size,
+            None,
         )?;
         self.data_size_metric.add(uncompressed_size);
         spill.complete()?;
@@ -163,10 +175,18 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
 
         let partitioning = self.partitioning.clone();
         let batch_size = self.batch_size;
+        let codec = self.codec;
         let data_size_metric = self.data_size_metric.clone();
         let data_file = self.output_data_file.clone();
         let index_file = self.output_index_file.clone();
 
+        // checksums are computed over the bytes of each partition in the final data
+        // file
+        let mut checksums = self.checksum_conf.clone().map(|conf| {
+            let checksums = PartitionChecksums::new(conf.algorithm, partitioning.partition_count());
+            (checksums, conf.output_checksum_file)
+        });
+
         // no spills - directly write current batches into final file
         if spills.is_empty() {
             tokio::task::spawn_blocking(move || {
@@ -181,7 +201,9 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
                     &mut output_data,
                     batch_size,
                     partitioning.partition_count(),
+                    codec,
                     &mut uncompressed_size,
+                    checksums.as_mut().map(|(checksums, _)| checksums),
                 )?;
                 data_size_metric.add(uncompressed_size);
                 output_data.sync_data()?;
@@ -193,6 +215,10 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
                 }
                 output_index.sync_data()?;
                 output_index.flush()?;
+
+                if let Some((checksums, checksum_file)) = checksums {
+                    checksums.write_checksum_file(&checksum_file)?;
+                }
                 Ok::<(), DataFusionError>(())
             })
             .await
@@ -239,7 +265,9 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
                 spill.get_buf_writer(),
                 batch_size,
                 partitioning.partition_count(),
+                codec,
                 &mut uncompressed_size,
+                None,
             )?;
             self.data_size_metric.add(uncompressed_size);
             spill.complete()?;
@@ -293,8 +321,14 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
                     );
 
                     let spill_range = spill_offset_start as usize..spill_offset_end as usize;
-                    let reader = &mut min_spill.reader;
-                    std::io::copy(&mut reader.take(spill_range.len() as u64), &mut output_data)?;
+                    let mut reader = (&mut min_spill.reader).take(spill_range.len() as u64);
+                    match &mut checksums {
+                        Some((checksums, _)) => std::io::copy(
+                            &mut reader,
+                            &mut checksums.writer(cur_partition_id, &mut output_data),
+                        )?,
+                        None => std::io::copy(&mut reader, &mut output_data)?,
+                    };
 
                     // forward partition id in min_spill
                     min_spill.cur += 1;
@@ -313,6 +347,10 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
             }
             output_index.sync_data()?;
             output_index.flush()?;
+
+            if let Some((checksums, checksum_file)) = checksums {
+                checksums.write_checksum_file(&checksum_file)?;
+            }
             Ok::<(), DataFusionError>(())
         })
         .await
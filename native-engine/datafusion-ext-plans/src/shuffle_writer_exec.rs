@@ -29,9 +29,13 @@ use datafusion::{
         Statistics,
     },
 };
-use datafusion_ext_commons::df_execution_err;
+use datafusion_ext_commons::{
+    df_execution_err,
+    io::{IoCompressionCodec, IoCompressionSite},
+};
 use futures::{stream::once, TryStreamExt};
 
+pub use crate::shuffle::ShuffleChecksumConf;
 use crate::{
     common::batch_statisitcs::{stat_input, InputBatchStatistics},
     memmgr::MemManager,
@@ -54,6 +58,9 @@ pub struct ShuffleWriterExec {
     output_data_file: String,
     /// Output index file path
     output_index_file: String,
+    /// Output checksum file path and algorithm, checksums are not written if
+    /// absent
+    checksum_conf: Option<ShuffleChecksumConf>,
     /// Metrics
     metrics: ExecutionPlanMetricsSet,
 }
@@ -98,6 +105,7 @@ impl ExecutionPlan for ShuffleWriterExec {
                 self.partitioning.clone(),
                 self.output_data_file.clone(),
                 self.output_index_file.clone(),
+                self.checksum_conf.clone(),
             )?)),
             _ => df_execution_err!("ShuffleWriterExec wrong number of children"),
         }
@@ -110,11 +118,14 @@ impl ExecutionPlan for ShuffleWriterExec {
     ) -> Result<SendableRecordBatchStream> {
         // record uncompressed data size
         let data_size_metric = MetricBuilder::new(&self.metrics).counter("data_size", partition);
+        let codec = IoCompressionCodec::try_from_conf(IoCompressionSite::Shuffle)?;
 
         let repartitioner: Arc<dyn ShuffleRepartitioner> = match &self.partitioning {
             p if p.partition_count() == 1 => Arc::new(SingleShuffleRepartitioner::new(
                 self.output_data_file.clone(),
                 self.output_index_file.clone(),
+                self.checksum_conf.clone(),
+                codec,
                 BaselineMetrics::new(&self.metrics, partition),
                 data_size_metric,
             )),
@@ -123,6 +134,8 @@ impl ExecutionPlan for ShuffleWriterExec {
                     partition,
                     self.output_data_file.clone(),
                     self.output_index_file.clone(),
+                    self.checksum_conf.clone(),
+                    codec,
                     self.partitioning.clone(),
                     &self.metrics,
                     data_size_metric,
@@ -165,6 +178,7 @@ impl ShuffleWriterExec {
         partitioning: Partitioning,
         output_data_file: String,
         output_index_file: String,
+        checksum_conf: Option<ShuffleChecksumConf>,
     ) -> Result<Self> {
         Ok(ShuffleWriterExec {
             input,
@@ -172,6 +186,7 @@ impl ShuffleWriterExec {
             metrics: ExecutionPlanMetricsSet::new(),
             output_data_file,
             output_index_file,
+            checksum_conf,
         })
     }
 }
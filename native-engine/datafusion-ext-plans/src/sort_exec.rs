@@ -42,7 +42,10 @@ use datafusion::{
 };
 use datafusion_ext_commons::{
     df_execution_err,
-    io::{read_bytes_slice, read_len, read_one_batch, write_len, write_one_batch},
+    io::{
+        read_bytes_slice, read_len, read_one_batch, write_len, write_one_batch, IoCompressionCodec,
+        IoCompressionSite,
+    },
     loser_tree::{ComparableForLoserTree, LoserTree},
     slim_bytes::SlimBytes,
     streams::coalesce_stream::CoalesceInput,
@@ -238,11 +241,12 @@ impl BufferedData {
         limit: usize,
     ) -> Result<Box<dyn Spill>> {
         let spill = try_new_spill(spill_metrics)?;
+        let codec = IoCompressionCodec::try_from_conf(IoCompressionSite::Spill)?;
         let mut writer = lz4_flex::frame::FrameEncoder::new(spill.get_buf_writer());
 
         for (keys, batch) in self.into_sorted_batches(sub_batch_size, limit) {
             let mut buf = vec![];
-            write_one_batch(&batch, &mut Cursor::new(&mut buf), true, None)?;
+            write_one_batch(&batch, &mut Cursor::new(&mut buf), codec, None)?;
             writer.write_all(&buf)?;
 
             for key in keys {
@@ -732,7 +736,6 @@ impl SpillCursor {
         if let Some(batch) = read_one_batch(
             &mut self.input,
             Some(self.sorter.prune_sort_keys_from_batch.pruned_schema()),
-            true,
         )? {
             self.cur_batch_num_rows = batch.num_rows();
             self.cur_loaded_num_rows = 0;
@@ -33,8 +33,14 @@ import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
 import org.apache.spark.sql.catalyst.expressions.Expression
 import org.apache.spark.sql.catalyst.expressions.aggregate.First
 import org.apache.spark.sql.catalyst.expressions.Like
+import org.apache.spark.sql.catalyst.expressions.Add
+import org.apache.spark.sql.catalyst.expressions.Cast
+import org.apache.spark.sql.catalyst.expressions.Multiply
+import org.apache.spark.sql.catalyst.expressions.Subtract
+import org.apache.spark.sql.internal.SQLConf
 import org.apache.spark.sql.catalyst.expressions.Literal
 import org.apache.spark.sql.catalyst.expressions.StringSplit
+import org.apache.spark.sql.catalyst.expressions.Decode
 import org.apache.spark.sql.catalyst.plans.physical.BroadcastMode
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.execution.SparkPlan
@@ -49,6 +55,12 @@ import org.apache.spark.sql.execution.ShufflePartitionSpec
 import org.apache.spark.sql.execution.blaze.shuffle.BlazeBlockStoreShuffleReader
 import org.apache.spark.sql.execution.PartialMapperPartitionSpec
 import org.apache.spark.sql.execution.PartialReducerPartitionSpec
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcScanBase
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcScanExec
+import org.apache.spark.sql.execution.blaze.plan.NativeCsvScanBase
+import org.apache.spark.sql.execution.blaze.plan.NativeCsvScanExec
+import org.apache.spark.sql.execution.blaze.plan.NativeJsonScanBase
+import org.apache.spark.sql.execution.blaze.plan.NativeJsonScanExec
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetScanBase
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetScanExec
 import org.apache.spark.sql.execution.blaze.plan.NativeShuffleExchangeBase
@@ -93,6 +105,8 @@ import org.apache.spark.sql.execution.blaze.plan.NativeLocalLimitBase
 import org.apache.spark.sql.execution.blaze.plan.NativeLocalLimitExec
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHiveTableBase
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHiveTableExec
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcInsertIntoHiveTableBase
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcInsertIntoHiveTableExec
 import org.apache.spark.sql.execution.blaze.plan.NativePartialTakeOrderedBase
 import org.apache.spark.sql.execution.blaze.plan.NativePartialTakeOrderedExec
 import org.apache.spark.sql.execution.blaze.plan.NativeProjectBase
@@ -116,6 +130,8 @@ import org.apache.spark.sql.SparkSession
 import org.apache.spark.sql.catalyst.catalog.CatalogTable
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetSinkBase
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetSinkExec
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcSinkBase
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcSinkExec
 import org.blaze.{protobuf => pb}
 import org.blaze.protobuf.PhysicalExprNode
 
@@ -202,14 +218,29 @@ class ShimsImpl extends Shims with Logging {
   override def createNativeLocalLimitExec(limit: Long, child: SparkPlan): NativeLocalLimitBase =
     NativeLocalLimitExec(limit, child)
 
+  override def createNativeOrcScanExec(basedFileScan: FileSourceScanExec): NativeOrcScanBase =
+    NativeOrcScanExec(basedFileScan)
+
+  override def createNativeCsvScanExec(basedFileScan: FileSourceScanExec): NativeCsvScanBase =
+    NativeCsvScanExec(basedFileScan)
+
+  override def createNativeJsonScanExec(basedFileScan: FileSourceScanExec): NativeJsonScanBase =
+    NativeJsonScanExec(basedFileScan)
+
   override def createNativeParquetInsertIntoHiveTableExec(
       cmd: InsertIntoHiveTable,
       child: SparkPlan): NativeParquetInsertIntoHiveTableBase =
     NativeParquetInsertIntoHiveTableExec(cmd, child)
 
+  override def createNativeOrcInsertIntoHiveTableExec(
+      cmd: InsertIntoHiveTable,
+      child: SparkPlan): NativeOrcInsertIntoHiveTableBase =
+    NativeOrcInsertIntoHiveTableExec(cmd, child)
+
   override def createNativeParquetScanExec(
-      basedFileScan: FileSourceScanExec): NativeParquetScanBase =
-    NativeParquetScanExec(basedFileScan)
+      basedFileScan: FileSourceScanExec,
+      metadataColumns: Seq[(Attribute, pb.FileMetadataColumnKind)]): NativeParquetScanBase =
+    NativeParquetScanExec(basedFileScan, metadataColumns)
 
   override def createNativeProjectExec(
       projectList: Seq[NamedExpression],
@@ -261,8 +292,25 @@ class ShimsImpl extends Shims with Logging {
       table: CatalogTable,
       partition: Map[String, Option[String]],
       child: SparkPlan,
-      metrics: Map[String, SQLMetric]): NativeParquetSinkBase =
-    NativeParquetSinkExec(sparkSession, table, partition, child, metrics)
+      metrics: Map[String, SQLMetric],
+      fileStatsOutputDir: Option[String]): NativeParquetSinkBase =
+    NativeParquetSinkExec(sparkSession, table, partition, child, metrics, fileStatsOutputDir)
+
+  override def createNativeOrcSinkExec(
+      sparkSession: SparkSession,
+      table: CatalogTable,
+      partition: Map[String, Option[String]],
+      child: SparkPlan,
+      metrics: Map[String, SQLMetric]): NativeOrcSinkBase =
+    NativeOrcSinkExec(sparkSession, table, partition, child, metrics)
+
+  // concurrent output writers are not supported until spark 3.2
+  override def isUnsortedSinkWriteSupported(
+      table: CatalogTable,
+      partition: Map[String, Option[String]]): Boolean = false
+
+  // bucketed hive tables are written without bucketing until spark 3.3
+  override def getInsertBucketHash(table: CatalogTable): Option[pb.BucketHash] = None
 
   override def getUnderlyingBroadcast(plan: SparkPlan): BroadcastExchangeLike = {
     plan match {
@@ -330,12 +378,16 @@ class ShimsImpl extends Shims with Logging {
       length: Long,
       numRecords: Long): FileSegment = new FileSegment(file, offset, length)
 
+  // shuffle checksums are not supported before spark 3.2
+  override def getShuffleChecksumAlgorithm: Option[String] = None
+
   override def commit(
       dep: ShuffleDependency[_, _, _],
       shuffleBlockResolver: IndexShuffleBlockResolver,
       tempDataFile: File,
       mapId: Long,
       partitionLengths: Array[Long],
+      checksums: Array[Long],
       dataSize: Long,
       context: TaskContext): MapStatus = {
 
@@ -398,6 +450,20 @@ class ShimsImpl extends Shims with Logging {
                 .setReturnType(NativeConverters.convertDataType(StringType)))
             .build())
 
+      case e @ Decode(bin, charset) if NativeConverters.isNativeSupportedCharset(charset) =>
+        Some(
+          pb.PhysicalExprNode
+            .newBuilder()
+            .setScalarFunction(
+              pb.PhysicalScalarFunctionNode
+                .newBuilder()
+                .setFun(pb.ScalarFunction.SparkExtFunctions)
+                .setName("Decode")
+                .addArgs(NativeConverters.convertExpr(bin))
+                .addArgs(NativeConverters.convertExpr(charset))
+                .setReturnType(NativeConverters.convertDataType(e.dataType)))
+            .build())
+
       case _ => None
     }
   }
@@ -406,6 +472,13 @@ class ShimsImpl extends Shims with Logging {
     expr.asInstanceOf[Like].escapeChar
   }
 
+  override def getFailOnError(expr: Expression): Boolean = {
+    expr match {
+      case _: Cast | _: Add | _: Subtract | _: Multiply => SQLConf.get.ansiEnabled
+      case _ => false
+    }
+  }
+
   override def convertAggregateExpr(e: AggregateExpression): Option[pb.PhysicalExprNode] = {
     assert(getAggregateExpressionFilter(e).isEmpty)
     val aggBuilder = pb.PhysicalAggExprNode.newBuilder()
@@ -15,6 +15,8 @@
  */
 package org.apache.spark.sql.execution.blaze.plan
 
+import scala.collection.JavaConverters._
+
 import org.apache.hadoop.conf.Configuration
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
@@ -88,12 +90,12 @@ class BlazeInsertIntoHiveTable(
         new BasicWriteTaskStatsTracker(serializableHadoopConf.value) {
           override def newRow(_row: InternalRow): Unit = {}
           override def getFinalStats(): WriteTaskStats = {
-            val outputFileStat = ParquetSinkTaskContext.get.processedOutputFiles.remove()
+            val outputFileStats = ParquetSinkTaskContext.get.processedOutputFiles.asScala
             BasicWriteTaskStats(
               numPartitions = 1,
-              numFiles = 1,
-              numBytes = outputFileStat.numBytes,
-              numRows = outputFileStat.numRows)
+              numFiles = outputFileStats.size,
+              numBytes = outputFileStats.map(_.numBytes).sum,
+              numRows = outputFileStats.map(_.numRows).sum)
           }
         }
       }
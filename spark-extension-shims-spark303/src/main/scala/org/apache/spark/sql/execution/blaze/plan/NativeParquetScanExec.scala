@@ -15,11 +15,15 @@
  */
 package org.apache.spark.sql.execution.blaze.plan
 
+import org.blaze.{protobuf => pb}
+import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.execution.FileSourceScanExec
 import org.apache.spark.sql.execution.SparkPlan
 
-case class NativeParquetScanExec(basedFileScan: FileSourceScanExec)
-    extends NativeParquetScanBase(basedFileScan) {
+case class NativeParquetScanExec(
+    basedFileScan: FileSourceScanExec,
+    metadataColumns: Seq[(Attribute, pb.FileMetadataColumnKind)])
+    extends NativeParquetScanBase(basedFileScan, metadataColumns) {
 
   override def simpleString(maxFields: Int): String =
     s"$nodeName (${basedFileScan.simpleString(maxFields)})"
@@ -24,6 +24,7 @@ import org.apache.spark.SparkEnv
 import org.apache.spark.SparkException
 import org.apache.spark.TaskContext
 import org.apache.spark.internal.Logging
+import org.apache.spark.internal.config
 import org.apache.spark.rdd.RDD
 import org.apache.spark.scheduler.MapStatus
 import org.apache.spark.shuffle.IndexShuffleBlockResolver
@@ -33,8 +34,18 @@ import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
 import org.apache.spark.sql.catalyst.expressions.Expression
 import org.apache.spark.sql.catalyst.expressions.aggregate.First
 import org.apache.spark.sql.catalyst.expressions.Like
+import org.apache.spark.sql.catalyst.expressions.Add
+import org.apache.spark.sql.catalyst.expressions.Cast
+import org.apache.spark.sql.catalyst.expressions.Divide
+import org.apache.spark.sql.catalyst.expressions.GetArrayItem
+import org.apache.spark.sql.catalyst.expressions.Multiply
+import org.apache.spark.sql.catalyst.expressions.ParseUrl
+import org.apache.spark.sql.catalyst.expressions.Remainder
+import org.apache.spark.sql.catalyst.expressions.Subtract
+import org.apache.spark.sql.catalyst.expressions.Unhex
 import org.apache.spark.sql.catalyst.expressions.Literal
 import org.apache.spark.sql.catalyst.expressions.StringSplit
+import org.apache.spark.sql.catalyst.expressions.StringDecode
 import org.apache.spark.sql.catalyst.plans.physical.BroadcastMode
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.execution.SparkPlan
@@ -48,6 +59,12 @@ import org.apache.spark.sql.execution.ShufflePartitionSpec
 import org.apache.spark.sql.execution.blaze.shuffle.BlazeBlockStoreShuffleReader
 import org.apache.spark.sql.execution.PartialMapperPartitionSpec
 import org.apache.spark.sql.execution.PartialReducerPartitionSpec
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcScanBase
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcScanExec
+import org.apache.spark.sql.execution.blaze.plan.NativeCsvScanBase
+import org.apache.spark.sql.execution.blaze.plan.NativeCsvScanExec
+import org.apache.spark.sql.execution.blaze.plan.NativeJsonScanBase
+import org.apache.spark.sql.execution.blaze.plan.NativeJsonScanExec
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetScanBase
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetScanExec
 import org.apache.spark.sql.execution.blaze.plan.NativeShuffleExchangeBase
@@ -90,6 +107,8 @@ import org.apache.spark.sql.execution.blaze.plan.NativeLocalLimitBase
 import org.apache.spark.sql.execution.blaze.plan.NativeLocalLimitExec
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHiveTableBase
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetInsertIntoHiveTableExec
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcInsertIntoHiveTableBase
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcInsertIntoHiveTableExec
 import org.apache.spark.sql.execution.blaze.plan.NativeProjectBase
 import org.apache.spark.sql.execution.blaze.plan.NativeProjectExec
 import org.apache.spark.sql.execution.blaze.plan.NativeRenameColumnsBase
@@ -199,14 +218,33 @@ class ShimsImpl extends Shims with Logging {
   override def createNativeLocalLimitExec(limit: Long, child: SparkPlan): NativeLocalLimitBase =
     NativeLocalLimitExec(limit, child)
 
+  override def createNativeOrcScanExec(basedFileScan: FileSourceScanExec): NativeOrcScanBase =
+    NativeOrcScanExec(basedFileScan)
+
+  override def createNativeCsvScanExec(basedFileScan: FileSourceScanExec): NativeCsvScanBase =
+    NativeCsvScanExec(basedFileScan)
+
+  override def createNativeJsonScanExec(basedFileScan: FileSourceScanExec): NativeJsonScanBase =
+    NativeJsonScanExec(basedFileScan)
+
   override def createNativeParquetInsertIntoHiveTableExec(
       cmd: InsertIntoHiveTable,
       child: SparkPlan): NativeParquetInsertIntoHiveTableBase =
     NativeParquetInsertIntoHiveTableExec(cmd, child)
 
+  override def createNativeOrcInsertIntoHiveTableExec(
+      cmd: InsertIntoHiveTable,
+      child: SparkPlan): NativeOrcInsertIntoHiveTableBase =
+    NativeOrcInsertIntoHiveTableExec(cmd, child)
+
   override def createNativeParquetScanExec(
-      basedFileScan: FileSourceScanExec): NativeParquetScanBase =
-    NativeParquetScanExec(basedFileScan)
+      basedFileScan: FileSourceScanExec,
+      metadataColumns: Seq[(Attribute, pb.FileMetadataColumnKind)]): NativeParquetScanBase = {
+    val fileSourceMetadataColumns = NativeParquetScanExec
+      .fileSourceMetadataColumns(basedFileScan.output)
+      .filterNot(c => metadataColumns.exists(_._1.exprId == c._1.exprId))
+    NativeParquetScanExec(basedFileScan, fileSourceMetadataColumns ++ metadataColumns)
+  }
 
   override def createNativeProjectExec(
       projectList: Seq[NamedExpression],
@@ -258,8 +296,31 @@ class ShimsImpl extends Shims with Logging {
       table: CatalogTable,
       partition: Map[String, Option[String]],
       child: SparkPlan,
-      metrics: Map[String, SQLMetric]): NativeParquetSinkBase =
-    NativeParquetSinkExec(sparkSession, table, partition, child, metrics)
+      metrics: Map[String, SQLMetric],
+      fileStatsOutputDir: Option[String]): NativeParquetSinkBase =
+    NativeParquetSinkExec(sparkSession, table, partition, child, metrics, fileStatsOutputDir)
+
+  override def createNativeOrcSinkExec(
+      sparkSession: SparkSession,
+      table: CatalogTable,
+      partition: Map[String, Option[String]],
+      child: SparkPlan,
+      metrics: Map[String, SQLMetric]): NativeOrcSinkBase =
+    NativeOrcSinkExec(sparkSession, table, partition, child, metrics)
+
+  // rows are written with spark's concurrent output writers, which are disabled for tables
+  // with sort columns
+  override def isUnsortedSinkWriteSupported(
+      table: CatalogTable,
+      partition: Map[String, Option[String]]): Boolean = {
+    BlazeConf.SINK_MAX_OPEN_WRITERS.intConf() > 1 &&
+    partition.exists(_._2.isEmpty) &&
+    table.bucketSpec.isEmpty
+  }
+
+  // hive tables are written with hive compatible buckets since spark 3.3
+  override def getInsertBucketHash(table: CatalogTable): Option[pb.BucketHash] =
+    table.bucketSpec.map(_ => pb.BucketHash.HIVE_HASH)
 
   override def getUnderlyingBroadcast(plan: SparkPlan): BroadcastExchangeLike = {
     plan match {
@@ -328,16 +389,25 @@ class ShimsImpl extends Shims with Logging {
       length: Long,
       numRecords: Long): FileSegment = new FileSegment(file, offset, length)
 
+  override def getShuffleChecksumAlgorithm: Option[String] = {
+    val conf = SparkEnv.get.conf
+    if (conf.get(config.SHUFFLE_CHECKSUM_ENABLED)) {
+      Some(conf.get(config.SHUFFLE_CHECKSUM_ALGORITHM))
+    } else {
+      None
+    }
+  }
+
   override def commit(
       dep: ShuffleDependency[_, _, _],
       shuffleBlockResolver: IndexShuffleBlockResolver,
       tempDataFile: File,
       mapId: Long,
       partitionLengths: Array[Long],
+      checksums: Array[Long],
       dataSize: Long,
       context: TaskContext): MapStatus = {
 
-    val checksums = Array[Long]()
     shuffleBlockResolver.writeMetadataFileAndCommit(
       dep.shuffleId,
       mapId,
@@ -398,6 +468,20 @@ class ShimsImpl extends Shims with Logging {
                 .setReturnType(NativeConverters.convertDataType(StringType)))
             .build())
 
+      case e @ StringDecode(bin, charset) if NativeConverters.isNativeSupportedCharset(charset) =>
+        Some(
+          pb.PhysicalExprNode
+            .newBuilder()
+            .setScalarFunction(
+              pb.PhysicalScalarFunctionNode
+                .newBuilder()
+                .setFun(pb.ScalarFunction.SparkExtFunctions)
+                .setName("Decode")
+                .addArgs(NativeConverters.convertExpr(bin))
+                .addArgs(NativeConverters.convertExpr(charset))
+                .setReturnType(NativeConverters.convertDataType(e.dataType)))
+            .build())
+
       case _ => None
     }
   }
@@ -406,6 +490,21 @@ class ShimsImpl extends Shims with Logging {
     expr.asInstanceOf[Like].escapeChar
   }
 
+  override def getFailOnError(expr: Expression): Boolean = {
+    expr match {
+      case e: Cast => e.ansiEnabled
+      case e: Add => e.failOnError
+      case e: Subtract => e.failOnError
+      case e: Multiply => e.failOnError
+      case e: Divide => e.failOnError
+      case e: Remainder => e.failOnError
+      case e: GetArrayItem => e.failOnError
+      case e: ParseUrl => e.failOnError
+      case e: Unhex => e.failOnError
+      case _ => false
+    }
+  }
+
   override def convertAggregateExpr(e: AggregateExpression): Option[pb.PhysicalExprNode] = {
     assert(getAggregateExpressionFilter(e).isEmpty)
     val aggBuilder = pb.PhysicalAggExprNode.newBuilder()
@@ -0,0 +1,115 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import org.apache.hadoop.conf.Configuration
+import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
+import org.apache.spark.sql.execution.datasources.BasicWriteTaskStatsTracker
+import org.apache.spark.sql.execution.datasources.WriteTaskStats
+import org.apache.spark.sql.execution.datasources.WriteTaskStatsTracker
+import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
+import org.apache.spark.sql.SparkSession
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.Row
+import org.apache.spark.sql.catalyst.catalog.CatalogTable
+import org.apache.spark.sql.catalyst.plans.logical.LogicalPlan
+import org.apache.spark.util.SerializableConfiguration
+
+case class NativeOrcInsertIntoHiveTableExec(
+    cmd: InsertIntoHiveTable,
+    override val child: SparkPlan)
+    extends NativeOrcInsertIntoHiveTableBase(cmd, child) {
+
+  override protected def getInsertIntoHiveTableCommand(
+      table: CatalogTable,
+      partition: Map[String, Option[String]],
+      query: LogicalPlan,
+      overwrite: Boolean,
+      ifPartitionNotExists: Boolean,
+      outputColumnNames: Seq[String],
+      metrics: Map[String, SQLMetric]): InsertIntoHiveTable = new BlazeOrcInsertIntoHiveTable(
+    table,
+    partition,
+    query,
+    overwrite,
+    ifPartitionNotExists,
+    outputColumnNames,
+    metrics)
+
+  override protected def withNewChildInternal(newChild: SparkPlan): SparkPlan =
+    copy(child = newChild)
+}
+
+class BlazeOrcInsertIntoHiveTable(
+    table: CatalogTable,
+    partition: Map[String, Option[String]],
+    query: LogicalPlan,
+    overwrite: Boolean,
+    ifPartitionNotExists: Boolean,
+    outputColumnNames: Seq[String],
+    outerMetrics: Map[String, SQLMetric])
+    extends InsertIntoHiveTable(
+      table,
+      partition,
+      query,
+      overwrite,
+      ifPartitionNotExists,
+      outputColumnNames) {
+
+  override lazy val metrics: Map[String, SQLMetric] = outerMetrics
+
+  override def run(sparkSession: SparkSession, child: SparkPlan): Seq[Row] = {
+    val nativeOrcSink =
+      Shims.get.createNativeOrcSinkExec(sparkSession, table, partition, child, metrics)
+    if (Shims.get.isUnsortedSinkWriteSupported(table, partition)) {
+      NativeParquetInsertIntoHiveTableBase.withConcurrentOutputWriters(sparkSession, table) {
+        session => super.run(session, nativeOrcSink)
+      }
+    } else {
+      super.run(sparkSession, nativeOrcSink)
+    }
+  }
+
+  override def basicWriteJobStatsTracker(hadoopConf: Configuration): BasicWriteJobStatsTracker = {
+    val serializableHadoopConf = new SerializableConfiguration(hadoopConf)
+    new BasicWriteJobStatsTracker(serializableHadoopConf, metrics) {
+      override def newTaskInstance(): WriteTaskStatsTracker = {
+        new BasicWriteTaskStatsTracker(serializableHadoopConf.value) {
+          override def newRow(_filePath: String, _row: InternalRow): Unit = {}
+          override def closeFile(_filePath: String): Unit = {}
+
+          // native files are not completed in the same order as the jvm side files, and a jvm
+          // side file may have more than one native files, so stats are collected at last
+          override def getFinalStats(taskCommitTime: Long): WriteTaskStats = {
+            val processedOutputFiles = ParquetSinkTaskContext.get.processedOutputFiles
+            while (!processedOutputFiles.isEmpty) {
+              val outputFileStat = processedOutputFiles.remove()
+              super.newFile(outputFileStat.path)
+              for (_ <- 0L until outputFileStat.numRows) {
+                super.newRow(outputFileStat.path, null)
+              }
+              super.closeFile(outputFileStat.path)
+            }
+            super.getFinalStats(taskCommitTime)
+          }
+        }
+      }
+    }
+  }
+}
@@ -21,6 +21,7 @@ import org.apache.spark.sql.catalyst.InternalRow
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
 import org.apache.spark.sql.execution.datasources.BasicWriteTaskStatsTracker
+import org.apache.spark.sql.execution.datasources.WriteTaskStats
 import org.apache.spark.sql.execution.datasources.WriteTaskStatsTracker
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
 import org.apache.spark.sql.SparkSession
@@ -55,6 +56,16 @@ case class NativeParquetInsertIntoHiveTableExec(
     copy(child = newChild)
 }
 
+object BlazeInsertIntoHiveTable {
+
+  // rows are bucketed by native sink, which names the bucket files with the bucket ids. spark
+  // writes the identity rows as a non-bucketed table, instead of sorting them by bucket ids,
+  // which blocks forever like the sorting by dynamic partitions
+  def withoutNativeBuckets(table: CatalogTable): CatalogTable = {
+    table.copy(bucketSpec = None)
+  }
+}
+
 class BlazeInsertIntoHiveTable(
     table: CatalogTable,
     partition: Map[String, Option[String]],
@@ -64,7 +75,7 @@ class BlazeInsertIntoHiveTable(
     outputColumnNames: Seq[String],
     outerMetrics: Map[String, SQLMetric])
     extends InsertIntoHiveTable(
-      table,
+      BlazeInsertIntoHiveTable.withoutNativeBuckets(table),
       partition,
       query,
       overwrite,
@@ -74,9 +85,16 @@ class BlazeInsertIntoHiveTable(
   override lazy val metrics: Map[String, SQLMetric] = outerMetrics
 
   override def run(sparkSession: SparkSession, child: SparkPlan): Seq[Row] = {
+    // native sink writes the table with its original bucket spec
     val nativeParquetSink =
       Shims.get.createNativeParquetSinkExec(sparkSession, table, partition, child, metrics)
-    super.run(sparkSession, nativeParquetSink)
+    if (Shims.get.isUnsortedSinkWriteSupported(table, partition)) {
+      NativeParquetInsertIntoHiveTableBase.withConcurrentOutputWriters(sparkSession, table) {
+        session => super.run(session, nativeParquetSink)
+      }
+    } else {
+      super.run(sparkSession, nativeParquetSink)
+    }
   }
 
   override def basicWriteJobStatsTracker(hadoopConf: Configuration): BasicWriteJobStatsTracker = {
@@ -85,12 +103,21 @@ class BlazeInsertIntoHiveTable(
       override def newTaskInstance(): WriteTaskStatsTracker = {
         new BasicWriteTaskStatsTracker(serializableHadoopConf.value) {
           override def newRow(_filePath: String, _row: InternalRow): Unit = {}
-          override def closeFile(filePath: String): Unit = {
-            val outputFileStat = ParquetSinkTaskContext.get.processedOutputFiles.remove()
-            for (_ <- 0L until outputFileStat.numRows) {
-              super.newRow(filePath, null)
+          override def closeFile(_filePath: String): Unit = {}
+
+          // native files are not completed in the same order as the jvm side files, and a jvm
+          // side file may have more than one native files, so stats are collected at last
+          override def getFinalStats(taskCommitTime: Long): WriteTaskStats = {
+            val processedOutputFiles = ParquetSinkTaskContext.get.processedOutputFiles
+            while (!processedOutputFiles.isEmpty) {
+              val outputFileStat = processedOutputFiles.remove()
+              super.newFile(outputFileStat.path)
+              for (_ <- 0L until outputFileStat.numRows) {
+                super.newRow(outputFileStat.path, null)
+              }
+              super.closeFile(outputFileStat.path)
             }
-            super.closeFile(filePath)
+            super.getFinalStats(taskCommitTime)
           }
         }
       }
@@ -15,11 +15,38 @@
  */
 package org.apache.spark.sql.execution.blaze.plan
 
+import org.blaze.{protobuf => pb}
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.AttributeReference
+import org.apache.spark.sql.catalyst.expressions.FileSourceMetadataAttribute
 import org.apache.spark.sql.execution.FileSourceScanExec
+import org.apache.spark.sql.execution.datasources.FileFormat
 
-case class NativeParquetScanExec(basedFileScan: FileSourceScanExec)
-    extends NativeParquetScanBase(basedFileScan) {
+case class NativeParquetScanExec(
+    basedFileScan: FileSourceScanExec,
+    metadataColumns: Seq[(Attribute, pb.FileMetadataColumnKind)])
+    extends NativeParquetScanBase(basedFileScan, metadataColumns) {
 
   override def simpleString(maxFields: Int): String =
     s"$nodeName (${basedFileScan.simpleString(maxFields)})"
 }
+
+object NativeParquetScanExec {
+
+  // _metadata fields referenced by the query are appended to the file scan output as flat
+  // file source metadata attributes. they are mapped to metadata columns synthesized by the
+  // native scan, other fields are not supported and the scan falls back
+  def fileSourceMetadataColumns(
+      output: Seq[Attribute]): Seq[(Attribute, pb.FileMetadataColumnKind)] = {
+    output.collect { case FileSourceMetadataAttribute(attr: AttributeReference) =>
+      val kind = attr.name match {
+        case FileFormat.FILE_PATH => pb.FileMetadataColumnKind.FILE_PATH
+        case FileFormat.FILE_NAME => pb.FileMetadataColumnKind.FILE_NAME
+        case FileFormat.FILE_SIZE => pb.FileMetadataColumnKind.FILE_SIZE
+        case FileFormat.FILE_MODIFICATION_TIME => pb.FileMetadataColumnKind.FILE_MODIFICATION_TIME
+        case name => throw new NotImplementedError(s"unsupported file metadata column: $name")
+      }
+      attr -> kind
+    }
+  }
+}
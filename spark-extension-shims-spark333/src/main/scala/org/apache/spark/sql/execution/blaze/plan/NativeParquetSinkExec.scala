@@ -25,8 +25,15 @@ case class NativeParquetSinkExec(
     table: CatalogTable,
     partition: Map[String, Option[String]],
     override val child: SparkPlan,
-    override val metrics: Map[String, SQLMetric])
-    extends NativeParquetSinkBase(sparkSession, table, partition, child, metrics) {
+    override val metrics: Map[String, SQLMetric],
+    fileStatsOutputDir: Option[String] = None)
+    extends NativeParquetSinkBase(
+      sparkSession,
+      table,
+      partition,
+      child,
+      metrics,
+      fileStatsOutputDir) {
 
   override protected def withNewChildInternal(newChild: SparkPlan): SparkPlan =
     copy(child = newChild)
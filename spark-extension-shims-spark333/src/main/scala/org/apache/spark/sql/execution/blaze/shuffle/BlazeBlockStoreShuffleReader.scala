@@ -25,10 +25,15 @@ import org.apache.spark.internal.Logging
 import org.apache.spark.internal.config
 import org.apache.spark.io.CompressionCodec
 import org.apache.spark.shuffle.BaseShuffleHandle
+import org.apache.spark.shuffle.IndexShuffleBlockResolver
 import org.apache.spark.shuffle.ShuffleReadMetricsReporter
+import org.apache.spark.sql.blaze.BlazeConf
 import org.apache.spark.storage.BlockId
 import org.apache.spark.storage.BlockManager
+import org.apache.spark.storage.FileSegment
+import org.apache.spark.storage.ShuffleBlockBatchId
 import org.apache.spark.storage.ShuffleBlockFetcherIterator
+import org.apache.spark.storage.ShuffleBlockId
 
 class BlazeBlockStoreShuffleReader[K, C](
     handle: BaseShuffleHandle[K, _, C],
@@ -73,6 +78,43 @@ class BlazeBlockStoreShuffleReader[K, C](
       fetchContinuousBlocksInBatch).toCompletionIterator
   }
 
+  private lazy val checksumAlgorithm: Option[String] = {
+    val conf = SparkEnv.get.conf
+    if (conf.get(config.SHUFFLE_CHECKSUM_ENABLED) &&
+      BlazeConf.SHUFFLE_CHECKSUM_VERIFY_ENABLE.booleanConf()) {
+      Some(conf.get(config.SHUFFLE_CHECKSUM_ALGORITHM))
+    } else {
+      None
+    }
+  }
+
+  override protected def wrapFileSegment(blockId: BlockId, fileSegment: FileSegment): Object = {
+    val reduceRange = blockId match {
+      case ShuffleBlockId(shuffleId, mapId, reduceId) =>
+        Some((shuffleId, mapId, reduceId, reduceId + 1))
+      case ShuffleBlockBatchId(shuffleId, mapId, startReduceId, endReduceId) =>
+        Some((shuffleId, mapId, startReduceId, endReduceId))
+      case _ =>
+        None
+    }
+    val checksummed = for {
+      algorithm <- checksumAlgorithm
+      (shuffleId, mapId, startReduceId, endReduceId) <- reduceRange
+      resolver <- SparkEnv.get.shuffleManager.shuffleBlockResolver match {
+        case resolver: IndexShuffleBlockResolver => Some(resolver)
+        case _ => None
+      }
+      checksummed <- ChecksummedFileSegment.create(
+        fileSegment,
+        algorithm,
+        resolver.getIndexFile(shuffleId, mapId),
+        resolver.getChecksumFile(shuffleId, mapId, algorithm),
+        startReduceId,
+        endReduceId)
+    } yield checksummed
+    checksummed.getOrElse(fileSegment)
+  }
+
   private def fetchContinuousBlocksInBatch: Boolean = {
     val conf = SparkEnv.get.conf
     val serializerRelocatable = dep.serializer.supportsRelocationOfSerializedObjects
@@ -0,0 +1,140 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.nio.file.Files
+
+import org.apache.hadoop.fs.Path
+import org.apache.hadoop.hive.ql.exec.vector.LongColumnVector
+import org.apache.orc.OrcFile
+import org.apache.spark.sql.DataFrame
+import org.apache.spark.sql.SparkSession
+import org.apache.spark.sql.catalyst.TableIdentifier
+import org.scalatest.BeforeAndAfterAll
+import org.scalatest.funsuite.AnyFunSuite
+
+// native orc reader and writer are tested against files of the orc java library used by spark
+class NativeOrcInteropSuite extends AnyFunSuite with BeforeAndAfterAll {
+
+  // native orc scan/sink are tested only if the native library is built and packaged
+  private lazy val isNativeLibAvailable =
+    getClass.getClassLoader.getResource(System.mapLibraryName("blaze")) != null
+
+  private lazy val spark = SparkSession
+    .builder()
+    .master("local[2]")
+    .config("spark.sql.extensions", "org.apache.spark.sql.blaze.BlazeSparkSessionExtension")
+    .config(
+      "spark.shuffle.manager",
+      "org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleManager")
+    .config("spark.sql.warehouse.dir", Files.createTempDirectory("blaze-warehouse").toString)
+    .config("spark.sql.hive.convertMetastoreOrc", "false")
+    .config("spark.sql.adaptive.enabled", "false")
+    .enableHiveSupport()
+    .getOrCreate()
+
+  override def afterAll(): Unit = {
+    if (isNativeLibAvailable) {
+      spark.stop()
+    }
+  }
+
+  private def testData: DataFrame = spark
+    .range(10000)
+    .selectExpr(
+      "id % 2 = 0 as b",
+      "cast(id as int) as i",
+      "if(id % 7 = 0, null, id * 1000003) as l",
+      "id / 3.0d as d",
+      "if(id % 5 = 0, null, concat('s', id % 100)) as s",
+      "cast(id as decimal(18, 2)) / 7 as dec",
+      "date_add(date'2020-01-01', cast(id as int)) as dt",
+      "timestamp'2020-01-01 00:00:00' + make_interval(0, 0, 0, 0, 0, 0, id * 1.5) as ts",
+      "array(id, id + 1) as arr",
+      "map(cast(id as string), id) as m",
+      "named_struct('x', id, 'y', cast(id as string)) as st")
+
+  test("native orc scan reads files written by spark") {
+    assume(isNativeLibAvailable, "native library is not available")
+    val expected = testData.collect().sortBy(_.getInt(1)).toSeq
+
+    for (codec <- Seq("none", "zlib", "snappy", "lz4", "zstd")) {
+      val dir = Files.createTempDirectory(s"orc-$codec").resolve("data").toString
+      testData.write.option("compression", codec).orc(dir)
+
+      val df = spark.read.orc(dir)
+      val rows = df.collect().sortBy(_.getInt(1)).toSeq
+      assert(
+        df.queryExecution.executedPlan.find(_.isInstanceOf[NativeOrcScanBase]).isDefined,
+        s"not read by native orc scan: ${df.queryExecution.executedPlan}")
+      assert(rows == expected, s"codec: $codec")
+    }
+  }
+
+  test("files written by native orc sink are read by orc library") {
+    assume(isNativeLibAvailable, "native library is not available")
+    spark.sql("create table orc_t (i int, l bigint, s string) stored as orc")
+    try {
+      spark.sql("""
+        insert into orc_t
+        select cast(id as int), if(id % 7 = 0, null, id), concat('s', id)
+        from range(10000)
+      """)
+
+      val location = spark.sessionState.catalog
+        .getTableMetadata(TableIdentifier("orc_t"))
+        .location
+      val hadoopConf = spark.sessionState.newHadoopConf()
+      val fs = new Path(location).getFileSystem(hadoopConf)
+      val files = fs
+        .listStatus(new Path(location))
+        .map(_.getPath)
+        .filterNot(path => path.getName.startsWith(".") || path.getName.startsWith("_"))
+      assert(files.nonEmpty)
+
+      var numRows = 0L
+      var numNulls = 0L
+      var sum = 0L
+      files.foreach { file =>
+        val reader = OrcFile.createReader(file, OrcFile.readerOptions(hadoopConf))
+        assert(reader.getSchema.toString == "struct<i:int,l:bigint,s:string>")
+        val batch = reader.getSchema.createRowBatch()
+        val rows = reader.rows()
+        var numFileRows = 0L
+        while (rows.nextBatch(batch)) {
+          val l = batch.cols(1).asInstanceOf[LongColumnVector]
+          for (rowIdx <- 0 until batch.size) {
+            val idx = if (l.isRepeating) 0 else rowIdx
+            if (!l.noNulls && l.isNull(idx)) {
+              numNulls += 1
+            } else {
+              sum += l.vector(idx)
+            }
+          }
+          numFileRows += batch.size
+        }
+        rows.close()
+        assert(numFileRows == reader.getNumberOfRows)
+        numRows += numFileRows
+      }
+      assert(numRows == 10000)
+      assert(numNulls == (0 until 10000).count(_ % 7 == 0))
+      assert(sum == (0L until 10000L).filter(_ % 7 != 0).sum)
+    } finally {
+      spark.sql("drop table orc_t")
+    }
+  }
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.nio.file.Files
+
+import org.apache.hadoop.fs.Path
+import org.apache.spark.sql.SparkSession
+import org.apache.spark.sql.catalyst.TableIdentifier
+import org.apache.spark.sql.catalyst.expressions.HiveHash
+import org.apache.spark.sql.catalyst.expressions.Literal
+import org.scalatest.BeforeAndAfterAll
+import org.scalatest.funsuite.AnyFunSuite
+
+class NativeParquetInsertIntoHiveTableSuite extends AnyFunSuite with BeforeAndAfterAll {
+
+  // native sinks are tested only if the native library is built and packaged
+  private lazy val isNativeLibAvailable =
+    getClass.getClassLoader.getResource(System.mapLibraryName("blaze")) != null
+
+  private lazy val spark = SparkSession
+    .builder()
+    .master("local[2]")
+    .config("spark.sql.extensions", "org.apache.spark.sql.blaze.BlazeSparkSessionExtension")
+    .config(
+      "spark.shuffle.manager",
+      "org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleManager")
+    .config("spark.sql.warehouse.dir", Files.createTempDirectory("blaze-warehouse").toString)
+    .config("spark.sql.hive.convertMetastoreParquet", "false")
+    .config("spark.blaze.sink.maxOpenWriters", "4")
+    .config("hive.exec.dynamic.partition.mode", "nonstrict")
+    .enableHiveSupport()
+    .getOrCreate()
+
+  override def afterAll(): Unit = {
+    if (isNativeLibAvailable) {
+      spark.stop()
+    }
+  }
+
+  test("bucket files are named like spark's hive compatible bucket files") {
+    val path = "hdfs://ns/warehouse/t/.hive-staging/-ext-10000/part-00000-abc-c000-1"
+    assert(
+      ParquetSinkTaskContext.bucketFilePath(path, 3) ==
+        "hdfs://ns/warehouse/t/.hive-staging/-ext-10000/00003_0_part-00000-abc-c000-1")
+    assert(
+      ParquetSinkTaskContext.bucketFilePath(path, 12345).endsWith(
+        "/12345_0_part-00000-abc-c000-1"))
+  }
+
+  test("bucketed hive table is written with hive compatible bucket files") {
+    assume(isNativeLibAvailable, "native library is not available")
+    val numBuckets = 4
+    spark.sql(s"""
+      create table bucketed_t (id int, v string)
+      clustered by (id) sorted by (v) into $numBuckets buckets
+      stored as parquet
+    """)
+    try {
+      spark.sql("insert into bucketed_t select id, cast(id as string) from range(1000)")
+
+      val location = spark.sessionState.catalog
+        .getTableMetadata(TableIdentifier("bucketed_t"))
+        .location
+      val fs = new Path(location).getFileSystem(spark.sessionState.newHadoopConf())
+      val files = fs
+        .listStatus(new Path(location))
+        .map(_.getPath)
+        .filterNot(path => path.getName.startsWith(".") || path.getName.startsWith("_"))
+      assert(files.nonEmpty)
+
+      // every file contains only rows of the bucket in its name, sorted by the sort column
+      var numRows = 0L
+      files.foreach { file =>
+        val bucketId = file.getName.take(5).toInt
+        assert(file.getName.matches("""\d{5}_0_.*"""), s"not a bucket file: $file")
+        val rows = spark.read.parquet(file.toString).collect()
+        rows.foreach { row =>
+          val hash = new HiveHash(Seq(Literal(row.getInt(0)))).eval().asInstanceOf[Int]
+          assert((hash & Int.MaxValue) % numBuckets == bucketId)
+        }
+        val values = rows.map(_.getString(1)).toSeq
+        assert(values == values.sorted)
+        numRows += rows.length
+      }
+      assert(numRows == 1000)
+      assert(spark.table("bucketed_t").count() == 1000)
+    } finally {
+      spark.sql("drop table bucketed_t")
+    }
+  }
+
+  test("dynamic partitions are written without changing the session conf") {
+    assume(isNativeLibAvailable, "native library is not available")
+    val key = "spark.sql.maxConcurrentOutputFileWriters"
+    spark.conf.set(key, "1")
+    spark.sql(
+      "create table partitioned_t (id bigint) partitioned by (p bigint) stored as parquet")
+    try {
+      spark.sql("insert into partitioned_t partition (p) select id, id % 5 from range(1000)")
+      assert(spark.conf.get(key) == "1")
+      assert(spark.table("partitioned_t").count() == 1000)
+      assert(spark.table("partitioned_t").select("p").distinct().count() == 5)
+    } finally {
+      spark.conf.unset(key)
+      spark.sql("drop table partitioned_t")
+    }
+  }
+}
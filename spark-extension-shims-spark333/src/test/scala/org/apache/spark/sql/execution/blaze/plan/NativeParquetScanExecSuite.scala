@@ -0,0 +1,62 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import org.blaze.{protobuf => pb}
+import org.apache.spark.sql.catalyst.expressions.AttributeReference
+import org.apache.spark.sql.catalyst.expressions.FileSourceMetadataAttribute
+import org.apache.spark.sql.execution.datasources.FileFormat
+import org.apache.spark.sql.types.IntegerType
+import org.apache.spark.sql.types.LongType
+import org.apache.spark.sql.types.StringType
+import org.apache.spark.sql.types.TimestampType
+import org.scalatest.funsuite.AnyFunSuite
+
+class NativeParquetScanExecSuite extends AnyFunSuite {
+
+  test("file source metadata attributes are mapped to native metadata columns") {
+    val id = AttributeReference("id", IntegerType)()
+    val filePath = FileSourceMetadataAttribute(FileFormat.FILE_PATH, StringType)
+    val fileName = FileSourceMetadataAttribute(FileFormat.FILE_NAME, StringType)
+    val fileSize = FileSourceMetadataAttribute(FileFormat.FILE_SIZE, LongType)
+    val fileModificationTime =
+      FileSourceMetadataAttribute(FileFormat.FILE_MODIFICATION_TIME, TimestampType)
+
+    val metadataColumns = NativeParquetScanExec.fileSourceMetadataColumns(
+      Seq(id, filePath, fileName, fileSize, fileModificationTime))
+    assert(
+      metadataColumns == Seq(
+        filePath -> pb.FileMetadataColumnKind.FILE_PATH,
+        fileName -> pb.FileMetadataColumnKind.FILE_NAME,
+        fileSize -> pb.FileMetadataColumnKind.FILE_SIZE,
+        fileModificationTime -> pb.FileMetadataColumnKind.FILE_MODIFICATION_TIME))
+    assert(!metadataColumns.exists(_._2 == pb.FileMetadataColumnKind.ROW_INDEX))
+  }
+
+  test("regular attributes are not metadata columns") {
+    val attrs = Seq(
+      AttributeReference("id", IntegerType)(),
+      AttributeReference(FileFormat.FILE_PATH, StringType)())
+    assert(NativeParquetScanExec.fileSourceMetadataColumns(attrs).isEmpty)
+  }
+
+  test("unsupported file source metadata attributes are rejected") {
+    val unknown = FileSourceMetadataAttribute("row_index", LongType)
+    intercept[NotImplementedError] {
+      NativeParquetScanExec.fileSourceMetadataColumns(Seq(unknown))
+    }
+  }
+}
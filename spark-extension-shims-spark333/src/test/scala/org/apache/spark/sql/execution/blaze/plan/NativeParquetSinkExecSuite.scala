@@ -0,0 +1,142 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.nio.ByteBuffer
+import java.nio.ByteOrder
+import java.nio.charset.StandardCharsets
+import java.nio.file.Files
+
+import scala.collection.JavaConverters._
+
+import org.apache.hadoop.fs.Path
+import org.apache.parquet.hadoop.ParquetFileReader
+import org.apache.parquet.hadoop.util.HadoopInputFile
+import org.apache.spark.sql.SparkSession
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.sql.catalyst.TableIdentifier
+import org.scalatest.BeforeAndAfterAll
+import org.scalatest.funsuite.AnyFunSuite
+
+class NativeParquetSinkExecSuite extends AnyFunSuite with BeforeAndAfterAll {
+
+  // native sinks are tested only if the native library is built and packaged
+  private lazy val isNativeLibAvailable =
+    getClass.getClassLoader.getResource(System.mapLibraryName("blaze")) != null
+
+  private lazy val spark = SparkSession
+    .builder()
+    .master("local[2]")
+    .config("spark.sql.extensions", "org.apache.spark.sql.blaze.BlazeSparkSessionExtension")
+    .config(
+      "spark.shuffle.manager",
+      "org.apache.spark.sql.execution.blaze.shuffle.BlazeShuffleManager")
+    .config("spark.sql.warehouse.dir", Files.createTempDirectory("blaze-warehouse").toString)
+    .config("spark.sql.adaptive.enabled", "false")
+    .enableHiveSupport()
+    .getOrCreate()
+
+  override def afterAll(): Unit = {
+    if (isNativeLibAvailable) {
+      spark.stop()
+    }
+  }
+
+  test("partitioned sink outputs stats of written files matching their footers") {
+    assume(isNativeLibAvailable, "native library is not available")
+    spark.sql("""
+      create table file_stats_t (id bigint, s string)
+      partitioned by (p string)
+      stored as parquet
+    """)
+    try {
+      val table = spark.sessionState.catalog.getTableMetadata(TableIdentifier("file_stats_t"))
+      val input = spark
+        .range(1000)
+        .selectExpr(
+          "id",
+          "if(id % 10 = 0, null, cast(id as string)) as s",
+          "cast(id % 3 as string) as p")
+        .sortWithinPartitions("p")
+      val child = input.queryExecution.executedPlan match {
+        case plan: NativeSupports => plan
+        case plan => Shims.get.createConvertToNativeExec(plan)
+      }
+      val outputDir = Files.createTempDirectory("blaze-file-stats").toString
+      val sink = Shims.get.createNativeParquetSinkExec(
+        spark,
+        table,
+        Map("p" -> None),
+        child,
+        Map.empty,
+        Some(outputDir))
+      val fileStats = sink.collectFileStats()
+      assert(fileStats.nonEmpty)
+      assert(fileStats.map(_.numRows).sum == 1000)
+
+      val hadoopConf = spark.sessionState.newHadoopConf()
+      fileStats.foreach { fileStat =>
+        val partitionValue = fileStat.partitionValues match {
+          case Seq(Some(value)) => value
+          case values => fail(s"unexpected partition values: $values")
+        }
+        assert(fileStat.path.startsWith(new Path(outputDir, s"p=$partitionValue").toString))
+
+        val path = new Path(fileStat.path)
+        val fs = path.getFileSystem(hadoopConf)
+        assert(fileStat.numBytes == fs.getFileStatus(path).getLen)
+
+        val reader = ParquetFileReader.open(HadoopInputFile.fromPath(path, hadoopConf))
+        val footer =
+          try {
+            reader.getFooter
+          } finally {
+            reader.close()
+          }
+        val blocks = footer.getBlocks.asScala
+        assert(fileStat.numRows == blocks.map(_.getRowCount).sum)
+
+        val columnNames = footer.getFileMetaData.getSchema.getColumns.asScala
+          .map(_.getPath.mkString("."))
+        assert(fileStat.columnStats.map(_.name) == columnNames)
+        fileStat.columnStats.zipWithIndex.foreach { case (columnStat, i) =>
+          val footerStats = blocks.map(_.getColumns.get(i).getStatistics)
+          assert(columnStat.nullCount.contains(footerStats.map(_.getNumNulls).sum))
+        }
+
+        // min/max values are checked against the rows of the file
+        val rows = spark.read.parquet(fileStat.path).collect()
+        val ids = rows.map(_.getLong(0))
+        assert(ids.forall(_ % 3 == partitionValue.toLong))
+        val strings = rows.flatMap(row => Option(row.getString(1)))
+        val Seq(idStat, stringStat) = fileStat.columnStats
+        val decodeLong = (bytes: Array[Byte]) =>
+          ByteBuffer.wrap(bytes).order(ByteOrder.LITTLE_ENDIAN).getLong
+        assert(idStat.nullCount.contains(0L))
+        assert(idStat.minValue.map(decodeLong).contains(ids.min))
+        assert(idStat.maxValue.map(decodeLong).contains(ids.max))
+        assert(stringStat.nullCount.contains(rows.length - strings.length.toLong))
+        assert(
+          stringStat.minValue.map(new String(_, StandardCharsets.UTF_8)).contains(strings.min))
+        assert(
+          stringStat.maxValue.map(new String(_, StandardCharsets.UTF_8)).contains(strings.max))
+      }
+    } finally {
+      spark.sql("drop table file_stats_t")
+    }
+  }
+}
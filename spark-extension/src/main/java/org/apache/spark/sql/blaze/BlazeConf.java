@@ -64,6 +64,69 @@ public enum BlazeConf {
 
     /// mininum number of rows to trigger partial aggregate skipping
     PARTIAL_AGG_SKIPPING_MIN_ROWS("spark.blaze.partialAggSkipping.minRows", BATCH_SIZE.intConf() * 2),
+
+    /// max number of concurrently open partition writers per task in native parquet/orc sinks.
+    /// if greater than 1, input is no longer sorted by dynamic partition columns before writing.
+    SINK_MAX_OPEN_WRITERS("spark.blaze.sink.maxOpenWriters", 1),
+
+    /// native parquet/orc sinks roll to a new file after writing this number of rows into a
+    /// file, 0 means unlimited
+    SINK_MAX_RECORDS_PER_FILE("spark.blaze.sink.maxRecordsPerFile", 0L),
+
+    /// native parquet/orc sinks roll to a new file after a file reaches this size in bytes,
+    /// 0 means unlimited
+    SINK_TARGET_FILE_SIZE("spark.blaze.sink.targetFileSize", 0L),
+
+    /// prune row groups with parquet bloom filters for equality and in-list predicates in
+    /// native parquet scan. requires extra io for reading bloom filters.
+    PARQUET_BLOOM_FILTER_ENABLE("spark.blaze.parquet.bloomFilter.enable", true),
+
+    /// max memory in bytes of decoded parquet metadata cached in each executor, shared by all
+    /// tasks. 0 means disabled.
+    PARQUET_METADATA_CACHE_SIZE("spark.blaze.parquet.metadataCache.size", 67108864L),
+
+    /// load page indexes of parquet files in native parquet scan and pass them to the reader.
+    /// page indexes are cached together with the metadata and counted in the cache size.
+    PARQUET_PAGE_INDEX_ENABLE("spark.blaze.parquet.pageIndex.enable", false),
+
+    /// nearby byte ranges with gaps not larger than this size are coalesced into one read in
+    /// native parquet scan
+    PARQUET_READ_COALESCE_GAP("spark.blaze.parquet.read.coalesceGap", 1048576),
+
+    /// max size of a coalesced read in native parquet scan
+    PARQUET_READ_MAX_COALESCED_SIZE("spark.blaze.parquet.read.maxCoalescedSize", 16777216),
+
+    /// number of concurrent reads for fetching column chunks of a row group in native parquet scan
+    PARQUET_READ_PARALLELISM("spark.blaze.parquet.read.parallelism", 4),
+
+    /// prefetch the next row group while the current one is being decoded in native parquet scan
+    PARQUET_READ_AHEAD_ENABLE("spark.blaze.parquet.readAhead.enable", true),
+
+    /// verify checksums of local shuffle blocks read by native shuffle reader, requires
+    /// spark.shuffle.checksum.enabled = true (since spark 3.2)
+    SHUFFLE_CHECKSUM_VERIFY_ENABLE("spark.blaze.shuffle.checksum.verify.enable", true),
+
+    /// number of shuffle segments fetched and decoded ahead by native shuffle reader, 0 to disable
+    SHUFFLE_READ_PREFETCH_SEGMENTS("spark.blaze.shuffle.read.prefetchSegments", 4),
+
+    /// compression codec of native shuffle data, one of none/lz4/snappy/zstd
+    SHUFFLE_COMPRESSION_CODEC("spark.blaze.shuffle.compression.codec", "zstd"),
+
+    /// compression codec of shuffle data written to remote shuffle services
+    RSS_COMPRESSION_CODEC("spark.blaze.rss.compression.codec", "zstd"),
+
+    /// compression codec of broadcasted data
+    BROADCAST_COMPRESSION_CODEC("spark.blaze.broadcast.compression.codec", "zstd"),
+
+    /// compression codec of batches spilled by native sort and memory-intensive operators
+    SPILL_COMPRESSION_CODEC("spark.blaze.spill.compression.codec", "zstd"),
+
+    /// compression level used when any of the above codecs is zstd
+    COMPRESSION_ZSTD_LEVEL("spark.blaze.compression.zstd.level", 1),
+
+    /// version of the format of batches written by native engine, 0 writes plain columns that
+    /// can be read by older native libraries, 1 writes columns with adaptive encodings
+    BATCH_SERDE_WRITE_VERSION("spark.blaze.batchSerde.writeVersion", 0),
     ;
 
     private String key;
@@ -90,6 +153,10 @@ public enum BlazeConf {
         return conf().getDouble(key, (double) defaultValue);
     }
 
+    public String stringConf() {
+        return conf().get(key, (String) defaultValue);
+    }
+
     public static boolean booleanConf(String confName) {
         return BlazeConf.valueOf(confName).booleanConf();
     }
@@ -106,6 +173,10 @@ public enum BlazeConf {
         return BlazeConf.valueOf(confName).doubleConf();
     }
 
+    public static String stringConf(String confName) {
+        return BlazeConf.valueOf(confName).stringConf();
+    }
+
     private static SparkConf conf() {
         return SparkEnv$.MODULE$.get().conf();
     }
@@ -18,8 +18,13 @@ package org.apache.spark.sql.execution.blaze.plan;
 // for jni_bridge usage
 @SuppressWarnings("unused")
 public class NativeParquetSinkUtils {
-    public static String getTaskOutputPath() throws InterruptedException {
-        return ParquetSinkTaskContext$.MODULE$.get().processingOutputFiles().take();
+    // bucketId is -1 if the output file is not a bucket file
+    public static String getTaskOutputPath(int bucketId) throws InterruptedException {
+        String path = ParquetSinkTaskContext$.MODULE$.get().processingOutputFiles().take();
+        if (bucketId < 0) {
+            return path;
+        }
+        return ParquetSinkTaskContext$.MODULE$.bucketFilePath(path, bucketId);
     }
 
     public static void completeOutput(String path, long numRows, long numFiles) {
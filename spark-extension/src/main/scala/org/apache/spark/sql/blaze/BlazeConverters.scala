@@ -21,6 +21,7 @@ import scala.annotation.tailrec
 import scala.collection.mutable
 import scala.collection.mutable.ArrayBuffer
 
+import org.apache.hadoop.hive.ql.io.orc.OrcOutputFormat
 import org.apache.hadoop.hive.ql.io.parquet.MapredParquetOutputFormat
 import org.apache.spark.SparkEnv
 import org.apache.spark.broadcast.Broadcast
@@ -32,6 +33,7 @@ import org.apache.spark.sql.catalyst.expressions.Alias
 import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.catalyst.expressions.AttributeReference
 import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.expressions.InputFileName
 import org.apache.spark.sql.catalyst.expressions.NamedExpression
 import org.apache.spark.sql.catalyst.expressions.aggregate.Final
 import org.apache.spark.sql.catalyst.expressions.aggregate.Partial
@@ -59,6 +61,9 @@ import org.apache.spark.sql.execution.blaze.plan.NativeProjectBase
 import org.apache.spark.sql.execution.blaze.plan.NativeUnionBase
 import org.apache.spark.sql.execution.blaze.plan.Util
 import org.apache.spark.sql.execution.command.DataWritingCommandExec
+import org.apache.spark.sql.execution.datasources.csv.CSVFileFormat
+import org.apache.spark.sql.execution.datasources.json.JsonFileFormat
+import org.apache.spark.sql.execution.datasources.orc.OrcFileFormat
 import org.apache.spark.sql.execution.datasources.parquet.ParquetFileFormat
 import org.apache.spark.sql.execution.exchange.BroadcastExchangeExec
 import org.apache.spark.sql.execution.exchange.ShuffleExchangeExec
@@ -70,12 +75,23 @@ import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastExchangeBase
 import org.apache.spark.sql.execution.GenerateExec
 import org.apache.spark.sql.execution.LocalTableScanExec
 import org.apache.spark.sql.execution.UnaryExecNode
+import org.apache.spark.sql.execution.blaze.plan.NativeOrcScanBase
 import org.apache.spark.sql.execution.blaze.plan.NativeParquetScanBase
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
+import org.apache.spark.sql.types.StringType
+import org.blaze.{protobuf => pb}
 
 object BlazeConverters extends Logging {
   val enableScan: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.scan", defaultValue = true)
+  val enableScanParquet: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.scan.parquet", defaultValue = true)
+  val enableScanOrc: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.scan.orc", defaultValue = true)
+  val enableScanCsv: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.scan.csv", defaultValue = true)
+  val enableScanJson: Boolean =
+    SparkEnv.get.conf.getBoolean("spark.blaze.enable.scan.json", defaultValue = true)
   val enableProject: Boolean =
     SparkEnv.get.conf.getBoolean("spark.blaze.enable.project", defaultValue = true)
   val enableFilter: Boolean =
@@ -270,9 +286,6 @@ object BlazeConverters extends Logging {
       exec.optionalBucketSet,
       exec.dataFilters,
       exec.tableIdentifier)
-    assert(
-      relation.fileFormat.isInstanceOf[ParquetFileFormat],
-      "Cannot convert non-parquet scan exec")
     logDebug(s"Converting FileSourceScanExec: ${Shims.get.simpleStringWithNodeId(exec)}")
     logDebug(s"  relation: ${relation}")
     logDebug(s"  relation.location: ${relation.location}")
@@ -282,16 +295,58 @@ object BlazeConverters extends Logging {
     logDebug(s"  optionalBucketSet: ${optionalBucketSet}")
     logDebug(s"  dataFilters: ${dataFilters}")
     logDebug(s"  tableIdentifier: ${tableIdentifier}")
-    addRenameColumnsExec(Shims.get.createNativeParquetScanExec(exec))
+    relation.fileFormat match {
+      case _: ParquetFileFormat if enableScanParquet =>
+        addRenameColumnsExec(Shims.get.createNativeParquetScanExec(exec))
+      case _: OrcFileFormat if enableScanOrc =>
+        addRenameColumnsExec(Shims.get.createNativeOrcScanExec(exec))
+      case _: CSVFileFormat if enableScanCsv =>
+        addRenameColumnsExec(Shims.get.createNativeCsvScanExec(exec))
+      case _: JsonFileFormat if enableScanJson =>
+        addRenameColumnsExec(Shims.get.createNativeJsonScanExec(exec))
+      case fileFormat =>
+        throw new NotImplementedError(s"Cannot convert scan exec of file format: $fileFormat")
+    }
   }
 
   def convertProjectExec(exec: ProjectExec): SparkPlan = {
-    val (projectList, child) = (exec.projectList, exec.child)
+    val (projectList, child) = withInputFileNameColumn(exec.projectList, exec.child)
     logDebug(s"Converting ProjectExec: ${Shims.get.simpleStringWithNodeId(exec)}")
     projectList.foreach(p => logDebug(s"  projectExpr: ${p}"))
     Shims.get.createNativeProjectExec(projectList, addRenameColumnsExec(convertToNative(child)))
   }
 
+  // rewrites input_file_name() to the file path metadata column of the underlying native
+  // parquet scan, which is only possible when the scan is reached through filters
+  private def withInputFileNameColumn(
+      projectList: Seq[NamedExpression],
+      child: SparkPlan): (Seq[NamedExpression], SparkPlan) = {
+    @tailrec
+    def isParquetScanThroughFilters(plan: SparkPlan): Boolean = plan match {
+      case _: NativeParquetScanBase => true
+      case filter: NativeFilterBase => isParquetScanThroughFilters(filter.child)
+      case rename: NativeRenameColumnsBase => isParquetScanThroughFilters(rename.child)
+      case _ => false
+    }
+    if (!projectList.exists(_.find(_.isInstanceOf[InputFileName]).isDefined) ||
+      !isParquetScanThroughFilters(child)) {
+      return (projectList, child)
+    }
+
+    val inputFileNameAttr = AttributeReference("__input_file_name__", StringType)()
+    val newChild = child.transformUp {
+      case scan: NativeParquetScanBase =>
+        Shims.get.createNativeParquetScanExec(
+          scan.fileScan,
+          scan.fileMetadataColumns :+ (inputFileNameAttr -> pb.FileMetadataColumnKind.FILE_PATH))
+      case rename: NativeRenameColumnsBase => addRenameColumnsExec(rename.child)
+    }
+    val newProjectList = projectList.map(_.transform { case _: InputFileName =>
+      inputFileNameAttr
+    }.asInstanceOf[NamedExpression])
+    (newProjectList, newChild)
+  }
+
   def convertFilterExec(exec: FilterExec): SparkPlan =
     exec match {
       case exec: FilterExec =>
@@ -778,24 +833,36 @@ object BlazeConverters extends Logging {
       case DataWritingCommandExec(cmd: InsertIntoHiveTable, child)
           if cmd.table.storage.outputFormat.contains(
             classOf[MapredParquetOutputFormat].getName) =>
-        // add an extra SortExec to sort child with dynamic columns
-        var sortedChild = convertToNative(child)
-        val numDynParts = cmd.partition.count(_._2.isEmpty)
-        val requiredOrdering =
-          child.output.slice(child.output.length - numDynParts, child.output.length)
-        if (child.outputOrdering.map(_.child) != requiredOrdering) {
-          sortedChild = Shims.get.createNativeSortExec(
-            requiredOrdering.map(SortOrder(_, Ascending)),
-            global = false,
-            sortedChild)
-        }
-        Shims.get.createNativeParquetInsertIntoHiveTableExec(cmd, sortedChild)
+        Shims.get.createNativeParquetInsertIntoHiveTableExec(
+          cmd,
+          sortChildByDynParts(cmd, child))
+
+      case DataWritingCommandExec(cmd: InsertIntoHiveTable, child)
+          if cmd.table.storage.outputFormat.contains(classOf[OrcOutputFormat].getName) =>
+        Shims.get.createNativeOrcInsertIntoHiveTableExec(cmd, sortChildByDynParts(cmd, child))
 
       case _ =>
         throw new NotImplementedError("unsupported DataWritingCommandExec")
     }
   }
 
+  private def sortChildByDynParts(cmd: InsertIntoHiveTable, child: SparkPlan): SparkPlan = {
+    // add an extra SortExec to sort child with dynamic columns, not necessary if native sink
+    // keeps more than one partition writers open and spark accepts the unsorted rows
+    var sortedChild = convertToNative(child)
+    val numDynParts = cmd.partition.count(_._2.isEmpty)
+    val requiredOrdering =
+      child.output.slice(child.output.length - numDynParts, child.output.length)
+    if (!Shims.get.isUnsortedSinkWriteSupported(cmd.table, cmd.partition) &&
+      child.outputOrdering.map(_.child) != requiredOrdering) {
+      sortedChild = Shims.get.createNativeSortExec(
+        requiredOrdering.map(SortOrder(_, Ascending)),
+        global = false,
+        sortedChild)
+    }
+    sortedChild
+  }
+
   def convertToNative(exec: SparkPlan): SparkPlan = {
     exec match {
       case exec if NativeHelper.isNative(exec) => exec
@@ -811,7 +878,9 @@ object BlazeConverters extends Logging {
       return false
     }
     plan match {
-      case _: NativeParquetScanBase | _: NativeUnionBase => true
+      case _: NativeParquetScanBase | _: NativeOrcScanBase | _: NativeCsvScanBase |
+          _: NativeJsonScanBase | _: NativeUnionBase =>
+        true
       case _: ConvertToNativeBase => needRenameColumns(plan.children.head)
       case exec if NativeHelper.isNative(exec) =>
         NativeHelper.getUnderlyingNativePlan(exec).output != plan.output
@@ -19,15 +19,17 @@ import java.io.ByteArrayInputStream
 import java.io.ByteArrayOutputStream
 import java.io.ObjectInputStream
 import java.io.ObjectOutputStream
+import java.util.Locale
 
 import scala.collection.JavaConverters._
 import scala.collection.mutable
 
 import com.google.protobuf.ByteString
+import org.apache.spark.SPARK_VERSION_SHORT
 import org.apache.spark.SparkEnv
 import org.blaze.{protobuf => pb}
 import org.apache.spark.internal.Logging
-import org.apache.spark.sql.catalyst.expressions.{Abs, Acos, Add, Alias, And, Asin, Atan, AttributeReference, BitwiseAnd, BitwiseOr, BoundReference, CaseWhen, Cast, Ceil, CheckOverflow, Coalesce, Concat, ConcatWs, Contains, Cos, CreateArray, CreateNamedStruct, Divide, EndsWith, EqualTo, Exp, Expression, Floor, GetArrayItem, GetMapValue, GetStructField, GreaterThan, GreaterThanOrEqual, If, In, InSet, IsNotNull, IsNull, Length, LessThan, LessThanOrEqual, Like, Literal, Log, Log10, Log2, Lower, MakeDecimal, Md5, Multiply, Murmur3Hash, Not, NullIf, OctetLength, Or, Pmod, PromotePrecision, Remainder, Sha2, ShiftLeft, ShiftRight, Signum, Sin, Sqrt, StartsWith, StringRepeat, StringSpace, StringTrim, StringTrimLeft, StringTrimRight, Substring, Subtract, Tan, TruncDate, Unevaluable, UnscaledValue, Upper}
+import org.apache.spark.sql.catalyst.expressions.{Abs, Acos, Add, Alias, And, ArrayAggregate, ArrayExcept, ArrayExists, ArrayFilter, ArrayForAll, ArrayIntersect, ArraysOverlap, ArrayTransform, ArrayUnion, Asin, Atan, AttributeReference, Base64, BitwiseAnd, BitwiseOr, BoundReference, CaseWhen, Cast, Ceil, CheckOverflow, Coalesce, Concat, ConcatWs, Contains, Conv, Cos, CreateArray, CreateNamedStruct, Divide, Encode, EndsWith, EqualTo, Exp, Expression, Floor, FormatString, GetArrayItem, GetMapValue, GetStructField, GreaterThan, GreaterThanOrEqual, Hex, If, In, InSet, IsNotNull, IsNull, Length, LessThan, LessThanOrEqual, Levenshtein, Like, Literal, Log, Log10, Log2, LambdaFunction, Lower, MakeDecimal, MapConcat, MapEntries, MapFilter, MapFromArrays, MapFromEntries, MapKeys, MapValues, Md5, Multiply, Murmur3Hash, NamedLambdaVariable, Not, NullIf, OctetLength, Or, Overlay, ParseUrl, Pmod, PromotePrecision, Remainder, Sha2, ShiftLeft, ShiftRight, Signum, Sin, SoundEx, Sqrt, StartsWith, StringInstr, StringLocate, StringRepeat, StringSpace, StringToMap, StringTranslate, StringTrim, StringTrimLeft, StringTrimRight, Substring, SubstringIndex, Subtract, Tan, TransformValues, TruncDate, UnBase64, Unevaluable, Unhex, UnscaledValue, Upper, ZipWith}
 import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
 import org.apache.spark.sql.catalyst.expressions.aggregate.Average
 import org.apache.spark.sql.catalyst.expressions.aggregate.CollectList
@@ -110,7 +112,11 @@ object NativeConverters extends Logging {
     scalarTypeBuilder.build()
   }
 
-  def convertDataType(sparkDataType: DataType): pb.ArrowType = {
+  // metadata key of parquet field ids in struct fields, used when
+  // spark.sql.parquet.fieldId.read.enabled is true
+  private val parquetFieldIdMetadataKey = "parquet.field.id"
+
+  def convertDataType(sparkDataType: DataType, withFieldIds: Boolean = false): pb.ArrowType = {
     val arrowTypeBuilder = pb.ArrowType.newBuilder()
     sparkDataType match {
       case NullType => arrowTypeBuilder.setNONE(pb.EmptyMessage.getDefaultInstance)
@@ -148,7 +154,7 @@ object NativeConverters extends Logging {
               pb.Field
                 .newBuilder()
                 .setName("item")
-                .setArrowType(convertDataType(a.elementType))
+                .setArrowType(convertDataType(a.elementType, withFieldIds))
                 .setNullable(a.containsNull))
             .build())
 
@@ -160,30 +166,20 @@ object NativeConverters extends Logging {
               pb.Field
                 .newBuilder()
                 .setName("key")
-                .setArrowType(convertDataType(m.keyType))
+                .setArrowType(convertDataType(m.keyType, withFieldIds))
                 .setNullable(false))
             .setValueType(
               pb.Field
                 .newBuilder()
                 .setName("value")
-                .setArrowType(convertDataType(m.valueType))
+                .setArrowType(convertDataType(m.valueType, withFieldIds))
                 .setNullable(m.valueContainsNull))
             .build())
       case s: StructType =>
         arrowTypeBuilder.setSTRUCT(
           org.blaze.protobuf.Struct
             .newBuilder()
-            .addAllSubFieldTypes(
-              s.fields
-                .map(e =>
-                  pb.Field
-                    .newBuilder()
-                    .setArrowType(convertDataType(e.dataType))
-                    .setName(e.name)
-                    .setNullable(e.nullable)
-                    .build())
-                .toList
-                .asJava)
+            .addAllSubFieldTypes(s.fields.map(convertField(_, withFieldIds)).toList.asJava)
             .build())
 
       case _ =>
@@ -236,20 +232,46 @@ object NativeConverters extends Logging {
     scalarValueBuilder.build()
   }
 
-  def convertField(sparkField: StructField): pb.Field = {
-    pb.Field
+  def convertField(sparkField: StructField, withFieldIds: Boolean = false): pb.Field = {
+    val fieldBuilder = pb.Field
       .newBuilder()
       .setName(sparkField.name)
       .setNullable(sparkField.nullable)
-      .setArrowType(convertDataType(sparkField.dataType))
-      .build()
+      .setArrowType(convertDataType(sparkField.dataType, withFieldIds))
+    if (withFieldIds && sparkField.metadata.contains(parquetFieldIdMetadataKey)) {
+      fieldBuilder.setFieldId(sparkField.metadata.getLong(parquetFieldIdMetadataKey).toInt)
+    }
+    fieldBuilder.build()
   }
 
-  def convertSchema(sparkSchema: StructType): pb.Schema = {
+  def convertSchema(sparkSchema: StructType, withFieldIds: Boolean = false): pb.Schema = {
     val schemaBuilder = pb.Schema.newBuilder()
-    sparkSchema.foreach(sparkField => schemaBuilder.addColumns(convertField(sparkField)))
+    sparkSchema.foreach(sparkField =>
+      schemaBuilder.addColumns(convertField(sparkField, withFieldIds)))
     schemaBuilder.build()
   }
+  // rebase modes of parquet reading/writing, the conf keys are prefixed with
+  // spark.sql.legacy before spark 3.2. must be called on driver side.
+  def convertParquetRebaseConf(isWrite: Boolean): pb.ParquetRebaseConf = {
+    val sqlConf = SQLConf.get
+    val suffix = if (isWrite) "InWrite" else "InRead"
+    def rebaseMode(name: String): pb.RebaseMode = {
+      val mode = Seq(s"spark.sql.parquet.$name$suffix", s"spark.sql.legacy.parquet.$name$suffix")
+        .flatMap(key => Option(sqlConf.getConfString(key, null)))
+        .headOption
+        .getOrElse("EXCEPTION")
+      pb.RebaseMode.valueOf(mode.toUpperCase(Locale.ROOT))
+    }
+    pb.ParquetRebaseConf
+      .newBuilder()
+      .setDatetimeRebaseMode(rebaseMode("datetimeRebaseMode"))
+      .setInt96RebaseMode(rebaseMode("int96RebaseMode"))
+      .setTimeZone(sqlConf.sessionLocalTimeZone)
+      .setInt96TimestampConversion(sqlConf.isParquetINT96TimestampConversion)
+      .setSparkVersion(SPARK_VERSION_SHORT)
+      .build()
+  }
+
 
   def convertJoinFilter(
       filterExpr: Expression,
@@ -283,6 +305,18 @@ object NativeConverters extends Logging {
       .build()
   }
 
+  // format specifiers of java.util.Formatter, see spark_format_string.rs
+  private val nativeFormatSpecRegex = "%[-#+ 0,(<$.\\d]*[a-zA-Z%]".r
+
+  def isNativeSupportedCharset(charset: Expression): Boolean = {
+    charset match {
+      case Literal(charset, StringType) if charset != null =>
+        Seq("US-ASCII", "ISO-8859-1", "UTF-8", "UTF-16", "UTF-16BE", "UTF-16LE", "UTF-32")
+          .contains(charset.toString.toUpperCase)
+      case _ => false
+    }
+  }
+
   abstract class NativeExprWrapperBase(
       _wrapped: pb.PhysicalExprNode,
       override val dataType: DataType = NullType,
@@ -403,14 +437,16 @@ object NativeConverters extends Logging {
     def buildBinaryExprNode(
         left: Expression,
         right: Expression,
-        op: String): pb.PhysicalExprNode =
+        op: String,
+        failOnError: Boolean = false): pb.PhysicalExprNode =
       buildExprNode {
         _.setBinaryExpr(
           pb.PhysicalBinaryExprNode
             .newBuilder()
             .setL(convertExprWithFallback(left, isPruningExpr, fallback))
             .setR(convertExprWithFallback(right, isPruningExpr, fallback))
-            .setOp(op))
+            .setOp(op)
+            .setFailOnError(failOnError))
       }
 
     def buildScalarFunction(
@@ -443,6 +479,47 @@ object NativeConverters extends Logging {
             .setReturnType(convertDataType(dataType)))
       }
 
+    def mapKeyDedupPolicy: Literal =
+      Literal(SQLConf.get.getConfString("spark.sql.mapKeyDedupPolicy", "EXCEPTION"))
+
+    // lambda variables are named with exprId to be unique in the whole expression
+    def buildLambdaVariable(v: NamedLambdaVariable): pb.PhysicalLambdaVariableExprNode =
+      pb.PhysicalLambdaVariableExprNode
+        .newBuilder()
+        .setName(s"${v.name}_${v.exprId.id}")
+        .setDataType(convertDataType(v.dataType))
+        .setNullable(v.nullable)
+        .build()
+
+    // lambda functions cannot be partially fallen back, since lambda variables
+    // are only bound in native
+    def buildLambdaFunction(lambda: LambdaFunction): pb.PhysicalLambdaFunctionExprNode =
+      pb.PhysicalLambdaFunctionExprNode
+        .newBuilder()
+        .setBody(convertExprWithFallback(
+          lambda.function,
+          isPruningExpr,
+          e => throw new NotImplementedError(s"unsupported expression in lambda: $e")))
+        .addAllArguments(lambda.arguments
+          .map(arg => buildLambdaVariable(arg.asInstanceOf[NamedLambdaVariable]))
+          .asJava)
+        .build()
+
+    def buildHigherOrderFunction(
+        func: pb.HigherOrderFunction,
+        args: Seq[Expression],
+        lambdas: Seq[LambdaFunction],
+        dataType: DataType): pb.PhysicalExprNode =
+      buildExprNode {
+        _.setHigherOrderFunction(
+          pb.PhysicalHigherOrderFunctionExprNode
+            .newBuilder()
+            .setFunc(func)
+            .addAllArgs(args.map(convertExprWithFallback(_, isPruningExpr, fallback)).asJava)
+            .addAllLambdas(lambdas.map(buildLambdaFunction).asJava)
+            .setReturnType(convertDataType(dataType)))
+      }
+
     def castIfNecessary(expr: Expression, dataType: DataType): Expression = {
       if (expr.dataType == dataType) {
         return expr
@@ -526,16 +603,21 @@ object NativeConverters extends Logging {
         }
 
       // cast
-      // not performing native cast for timestamp/dates (will use UDFWrapper instead)
+      // timestamp/dates are only natively casted from/to strings and each other
+      // (will use UDFWrapper instead for other types)
       case cast: Cast
-          if !Seq(cast.dataType, cast.child.dataType).contains(TimestampType) &&
-            !Seq(cast.dataType, cast.child.dataType).contains(DateType) =>
+          if Seq(cast.dataType, cast.child.dataType).forall(t =>
+            t != TimestampType && t != DateType) ||
+            Seq(cast.dataType, cast.child.dataType).forall(t =>
+              t == StringType || t == TimestampType || t == DateType) =>
         buildExprNode {
           _.setTryCast(
             pb.PhysicalTryCastNode
               .newBuilder()
               .setExpr(convertExprWithFallback(cast.child, isPruningExpr, fallback))
               .setArrowType(convertDataType(cast.dataType))
+              .setFailOnError(Shims.get.getFailOnError(cast))
+              .setTimezone(cast.timeZoneId.getOrElse(SQLConf.get.sessionLocalTimeZone))
               .build())
         }
 
@@ -615,7 +697,7 @@ object NativeConverters extends Logging {
                 .setOp("Plus"))
           }
         } else {
-          buildBinaryExprNode(lhs, rhs, "Plus")
+          buildBinaryExprNode(lhs, rhs, "Plus", Shims.get.getFailOnError(e))
         }
 
       case e: Subtract =>
@@ -632,7 +714,7 @@ object NativeConverters extends Logging {
                 .setOp("Minus"))
           }
         } else {
-          buildBinaryExprNode(lhs, rhs, "Minus")
+          buildBinaryExprNode(lhs, rhs, "Minus", Shims.get.getFailOnError(e))
         }
 
       case e: Multiply =>
@@ -649,7 +731,7 @@ object NativeConverters extends Logging {
                 .setOp("Multiply"))
           }
         } else {
-          buildBinaryExprNode(lhs, rhs, "Multiply")
+          buildBinaryExprNode(lhs, rhs, "Multiply", Shims.get.getFailOnError(e))
         }
 
       case e: Divide =>
@@ -665,6 +747,11 @@ object NativeConverters extends Logging {
                 .setR(convertExprWithFallback(rhs, isPruningExpr, fallback))
                 .setOp("Divide"))
           }
+        } else if (Shims.get.getFailOnError(e)) {
+          val resultType = e.dataType
+          val lhsCasted = castIfNecessary(lhs, resultType)
+          val rhsCasted = castIfNecessary(rhs, resultType)
+          buildBinaryExprNode(lhsCasted, rhsCasted, "Divide", failOnError = true)
         } else {
           val resultType = e.dataType
           val lhsCasted = castIfNecessary(lhs, resultType)
@@ -684,6 +771,10 @@ object NativeConverters extends Logging {
         val rhs = e.right
         val resultType = arithDecimalReturnType(e)
         rhs match {
+          case rhs if Shims.get.getFailOnError(e) =>
+            val lhsCasted = castIfNecessary(lhs, resultType)
+            val rhsCasted = castIfNecessary(rhs, resultType)
+            buildBinaryExprNode(lhsCasted, rhsCasted, "Modulo", failOnError = true)
           case rhs: Literal if rhs == Literal.default(rhs.dataType) =>
             buildExprNode(_.setLiteral(convertValue(null, e.dataType)))
           case rhs: Literal if rhs != Literal.default(rhs.dataType) =>
@@ -876,6 +967,43 @@ object NativeConverters extends Logging {
               c.dataType == StringType || c.dataType == ArrayType(StringType)) =>
         buildExtScalarFunction("StringConcatWs", e.children, e.dataType)
 
+      case e: SubstringIndex =>
+        buildExtScalarFunction("StringSubstringIndex", e.children, StringType)
+      case e: StringLocate => buildExtScalarFunction("StringLocate", e.children, IntegerType)
+      case e: StringInstr => buildExtScalarFunction("StringInstr", e.children, IntegerType)
+      case e: Levenshtein =>
+        buildExtScalarFunction("StringLevenshtein", e.children, IntegerType)
+      case e: SoundEx => buildExtScalarFunction("StringSoundex", e.children, StringType)
+      case e: StringTranslate =>
+        buildExtScalarFunction("StringTranslate", e.children, StringType)
+      case e: Overlay if e.dataType == StringType =>
+        buildExtScalarFunction("StringOverlay", e.children, StringType)
+
+      case e @ FormatString(Literal(format, StringType), args @ _*)
+          if format != null
+            && nativeFormatSpecRegex
+              .findAllIn(format.toString)
+              .forall(spec => "bBsScCdoxXeEf%n".contains(spec.last))
+            && args.forall(arg =>
+              arg.dataType match {
+                case BooleanType | ByteType | ShortType | IntegerType | LongType => true
+                case FloatType | DoubleType | StringType | DateType | TimestampType => true
+                case _: DecimalType => true
+                case _ => false
+              }) =>
+        buildExtScalarFunction("FormatString", e.children, StringType)
+
+      case e: Conv => buildExtScalarFunction("Conv", e.children, StringType)
+      case e: Hex => buildExtScalarFunction("Hex", e.children, StringType)
+      case e: Unhex if !Shims.get.getFailOnError(e) =>
+        buildExtScalarFunction("Unhex", e.child :: Nil, BinaryType)
+      case e: Base64 => buildExtScalarFunction("Base64", e.children, StringType)
+      case e: UnBase64 => buildExtScalarFunction("UnBase64", e.child :: Nil, BinaryType)
+      case e @ Encode(_, charset) if isNativeSupportedCharset(charset) =>
+        buildExtScalarFunction("Encode", e.children, BinaryType)
+      case e: ParseUrl if !Shims.get.getFailOnError(e) =>
+        buildExtScalarFunction("ParseUrl", e.children, StringType)
+
       case e: Coalesce => buildScalarFunction(pb.ScalarFunction.Coalesce, e.children, e.dataType)
 
       case If(predicate, trueValue, falseValue) =>
@@ -928,6 +1056,80 @@ object NativeConverters extends Logging {
 
       case e: CreateArray => buildExtScalarFunction("MakeArray", e.children, e.dataType)
 
+      // array set operations
+      case e: ArrayUnion => buildExtScalarFunction("ArrayUnion", e.children, e.dataType)
+      case e: ArrayIntersect =>
+        buildExtScalarFunction("ArrayIntersect", e.children, e.dataType)
+      case e: ArrayExcept => buildExtScalarFunction("ArrayExcept", e.children, e.dataType)
+      case e: ArraysOverlap => buildExtScalarFunction("ArraysOverlap", e.children, e.dataType)
+
+      // map functions, map constructing functions take dedup policy as the last argument
+      case e: MapKeys => buildExtScalarFunction("MapKeys", e.children, e.dataType)
+      case e: MapValues => buildExtScalarFunction("MapValues", e.children, e.dataType)
+      case e: MapEntries => buildExtScalarFunction("MapEntries", e.children, e.dataType)
+      case e: MapFromArrays =>
+        buildExtScalarFunction("MapFromArrays", e.children :+ mapKeyDedupPolicy, e.dataType)
+      case e: MapFromEntries =>
+        buildExtScalarFunction("MapFromEntries", e.children :+ mapKeyDedupPolicy, e.dataType)
+      case e: MapConcat if e.children.nonEmpty =>
+        buildExtScalarFunction("MapConcat", e.children :+ mapKeyDedupPolicy, e.dataType)
+      case e @ StringToMap(_, _: Literal, _: Literal) =>
+        buildExtScalarFunction("StrToMap", e.children :+ mapKeyDedupPolicy, e.dataType)
+
+      // higher-order functions
+      case e: NamedLambdaVariable =>
+        buildExprNode(_.setLambdaVariable(buildLambdaVariable(e)))
+      case e: LambdaFunction =>
+        buildExprNode(_.setLambdaFunction(buildLambdaFunction(e)))
+      case ArrayTransform(arg, lambda: LambdaFunction) =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.TRANSFORM,
+          arg :: Nil,
+          lambda :: Nil,
+          sparkExpr.dataType)
+      case ArrayFilter(arg, lambda: LambdaFunction) =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.FILTER,
+          arg :: Nil,
+          lambda :: Nil,
+          sparkExpr.dataType)
+      case e @ ArrayExists(arg, lambda: LambdaFunction, _) if e.followThreeValuedLogic =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.EXISTS,
+          arg :: Nil,
+          lambda :: Nil,
+          e.dataType)
+      case ArrayForAll(arg, lambda: LambdaFunction) =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.FORALL,
+          arg :: Nil,
+          lambda :: Nil,
+          sparkExpr.dataType)
+      case ArrayAggregate(arg, zero, merge: LambdaFunction, finish: LambdaFunction) =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.AGGREGATE,
+          arg :: zero :: Nil,
+          merge :: finish :: Nil,
+          sparkExpr.dataType)
+      case ZipWith(left, right, lambda: LambdaFunction) =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.ZIP_WITH,
+          left :: right :: Nil,
+          lambda :: Nil,
+          sparkExpr.dataType)
+      case MapFilter(arg, lambda: LambdaFunction) =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.MAP_FILTER,
+          arg :: Nil,
+          lambda :: Nil,
+          sparkExpr.dataType)
+      case TransformValues(arg, lambda: LambdaFunction) =>
+        buildHigherOrderFunction(
+          pb.HigherOrderFunction.TRANSFORM_VALUES,
+          arg :: Nil,
+          lambda :: Nil,
+          sparkExpr.dataType)
+
       case e: CreateNamedStruct =>
         buildExprNode {
           _.setNamedStruct(
@@ -952,7 +1154,8 @@ object NativeConverters extends Logging {
               .setExpr(convertExprWithFallback(e.child, isPruningExpr, fallback))
               .setKey(convertValue(
                 ordinalValue.longValue() + 1, // NOTE: data-fusion index starts from 1
-                LongType)))
+                LongType))
+              .setFailOnError(Shims.get.getFailOnError(e)))
         }
 
       case e: GetMapValue if e.key.isInstanceOf[Literal] =>
@@ -113,11 +113,23 @@ abstract class Shims {
 
   def createNativeLocalLimitExec(limit: Long, child: SparkPlan): NativeLocalLimitBase
 
+  def createNativeOrcScanExec(basedFileScan: FileSourceScanExec): NativeOrcScanBase
+
+  def createNativeCsvScanExec(basedFileScan: FileSourceScanExec): NativeCsvScanBase
+
+  def createNativeJsonScanExec(basedFileScan: FileSourceScanExec): NativeJsonScanBase
+
   def createNativeParquetInsertIntoHiveTableExec(
       cmd: InsertIntoHiveTable,
       child: SparkPlan): NativeParquetInsertIntoHiveTableBase
 
-  def createNativeParquetScanExec(basedFileScan: FileSourceScanExec): NativeParquetScanBase
+  def createNativeOrcInsertIntoHiveTableExec(
+      cmd: InsertIntoHiveTable,
+      child: SparkPlan): NativeOrcInsertIntoHiveTableBase
+
+  def createNativeParquetScanExec(
+      basedFileScan: FileSourceScanExec,
+      metadataColumns: Seq[(Attribute, pb.FileMetadataColumnKind)] = Nil): NativeParquetScanBase
 
   def createNativeProjectExec(
       projectList: Seq[NamedExpression],
@@ -161,7 +173,29 @@ abstract class Shims {
       table: CatalogTable,
       partition: Map[String, Option[String]],
       child: SparkPlan,
-      metrics: Map[String, SQLMetric]): NativeParquetSinkBase
+      metrics: Map[String, SQLMetric],
+      fileStatsOutputDir: Option[String] = None): NativeParquetSinkBase
+
+  def createNativeOrcSinkExec(
+      sparkSession: SparkSession,
+      table: CatalogTable,
+      partition: Map[String, Option[String]],
+      child: SparkPlan,
+      metrics: Map[String, SQLMetric]): NativeOrcSinkBase
+
+  /**
+   * whether native sinks can write rows of dynamic partitions without sorting them, which
+   * requires spark to accept unsorted rows in its file writers
+   */
+  def isUnsortedSinkWriteSupported(
+      table: CatalogTable,
+      partition: Map[String, Option[String]]): Boolean
+
+  /**
+   * hash function of the buckets written by inserting into the table, or None if spark writes
+   * the table without bucketing
+   */
+  def getInsertBucketHash(table: CatalogTable): Option[pb.BucketHash]
 
   def isNative(plan: SparkPlan): Boolean
 
@@ -193,16 +227,23 @@ abstract class Shims {
 
   def getLikeEscapeChar(expr: Expression): Char
 
+  /** whether the expression raises errors instead of returning nulls (ANSI mode) */
+  def getFailOnError(expr: Expression): Boolean
+
   def getAggregateExpressionFilter(expr: Expression): Option[Expression]
 
   def createFileSegment(file: File, offset: Long, length: Long, numRecords: Long): FileSegment
 
+  /** algorithm of spark's shuffle checksums, or None if shuffle checksums are disabled */
+  def getShuffleChecksumAlgorithm: Option[String]
+
   def commit(
       dep: ShuffleDependency[_, _, _],
       shuffleBlockResolver: IndexShuffleBlockResolver,
       tempDataFile: File,
       mapId: Long,
       partitionLengths: Array[Long],
+      checksums: Array[Long],
       dataSize: Long,
       context: TaskContext): MapStatus
 
@@ -0,0 +1,119 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.util.Locale
+
+import scala.collection.immutable.SortedMap
+
+import org.apache.hadoop.hive.ql.io.orc.OrcSerde
+import org.apache.spark.rdd.RDD
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.catalog.CatalogTable
+import org.apache.spark.sql.catalyst.plans.logical.LogicalPlan
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.command.DataWritingCommandExec
+import org.apache.spark.sql.execution.UnaryExecNode
+import org.apache.spark.sql.execution.datasources.orc.OrcOptions
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.execution.metric.SQLMetrics
+import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
+
+abstract class NativeOrcInsertIntoHiveTableBase(
+    cmd: InsertIntoHiveTable,
+    override val child: SparkPlan)
+    extends UnaryExecNode
+    with NativeSupports {
+
+  override lazy val metrics: Map[String, SQLMetric] = SortedMap[String, SQLMetric]() ++ Map(
+    NativeHelper
+      .getDefaultNativeMetrics(sparkContext)
+      .filterKeys(Set("output_rows", "elapsed_compute"))
+      .toSeq
+      :+ ("io_time", SQLMetrics.createNanoTimingMetric(sparkContext, "Native.io_time"))
+      :+ ("bytes_written", SQLMetrics
+        .createSizeMetric(sparkContext, "Native.bytes_written")): _*)
+
+  def check(): Unit = {
+    val tblStorage = cmd.table.storage
+    val outputFormatClassName = tblStorage.outputFormat.getOrElse("").toLowerCase(Locale.ROOT)
+    val compressionCodec =
+      new OrcOptions(cmd.table.properties ++ tblStorage.properties, conf).compressionCodec
+
+    assert(outputFormatClassName.endsWith("orcoutputformat"), "not orc format")
+    assert(compressionCodec != "LZO", "not supported writing orc table with LZO compression")
+    assert(cmd.table.bucketSpec.isEmpty, "not supported writing bucketed orc table")
+  }
+  check()
+
+  @transient
+  val wrapped: DataWritingCommandExec = {
+    // the dummy output format of parquet sink only passes output paths to the
+    // native side, so it is reused for orc sink
+    val transformedTable = {
+      val tblStorage = cmd.table.storage
+      cmd.table.withNewStorage(
+        tblStorage.locationUri,
+        tblStorage.inputFormat,
+        outputFormat = Some(classOf[BlazeMapredParquetOutputFormat].getName),
+        tblStorage.compressed,
+        serde = Some(classOf[OrcSerde].getName),
+        tblStorage.properties)
+    }
+
+    val transformedCmd = getInsertIntoHiveTableCommand(
+      transformedTable,
+      cmd.partition,
+      cmd.query,
+      cmd.overwrite,
+      cmd.ifPartitionNotExists,
+      cmd.outputColumnNames,
+      metrics)
+    DataWritingCommandExec(transformedCmd, child)
+  }
+
+  override def output: Seq[Attribute] = wrapped.output
+  override def outputPartitioning: Partitioning = wrapped.outputPartitioning
+  override def outputOrdering: Seq[SortOrder] = wrapped.outputOrdering
+  override def doExecute(): RDD[InternalRow] = wrapped.execute()
+
+  override def executeCollect(): Array[InternalRow] = wrapped.executeCollect()
+  override def executeTake(n: Int): Array[InternalRow] = wrapped.executeTake(n)
+  override def executeToIterator(): Iterator[InternalRow] = wrapped.executeToIterator()
+
+  override def doExecuteNative(): NativeRDD = {
+    Shims.get.createConvertToNativeExec(wrapped).executeNative()
+  }
+
+  override def nodeName: String =
+    s"NativeOrcInsert ${cmd.table.identifier.unquotedString}"
+
+  protected def getInsertIntoHiveTableCommand(
+      table: CatalogTable,
+      partition: Map[String, Option[String]],
+      query: LogicalPlan,
+      overwrite: Boolean,
+      ifPartitionNotExists: Boolean,
+      outputColumnNames: Seq[String],
+      metrics: Map[String, SQLMetric]): InsertIntoHiveTable
+}
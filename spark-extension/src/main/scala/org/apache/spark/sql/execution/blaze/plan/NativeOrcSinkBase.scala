@@ -0,0 +1,129 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.net.URI
+import java.security.PrivilegedExceptionAction
+import java.util.UUID
+
+import scala.collection.JavaConverters._
+
+import org.apache.hadoop.fs.FileSystem
+import org.apache.hadoop.hive.ql.plan.TableDesc
+import org.apache.spark.sql.blaze.JniBridge
+import org.apache.spark.sql.SparkSession
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.catalyst.catalog.CatalogTable
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.datasources.orc.OrcOptions
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.hive.blaze.HiveClientHelper
+import org.apache.spark.util.SerializableConfiguration
+import org.apache.spark.OneToOneDependency
+import org.apache.spark.sql.execution.UnaryExecNode
+import org.blaze.protobuf.OrcProp
+import org.blaze.protobuf.OrcSinkExecNode
+import org.blaze.protobuf.PhysicalPlanNode
+
+abstract class NativeOrcSinkBase(
+    sparkSession: SparkSession,
+    table: CatalogTable,
+    partition: Map[String, Option[String]],
+    override val child: SparkPlan,
+    override val metrics: Map[String, SQLMetric])
+    extends UnaryExecNode
+    with NativeSupports {
+
+  override def output: Seq[Attribute] = child.output
+
+  override def outputPartitioning: Partitioning = child.outputPartitioning
+
+  override def outputOrdering: Seq[SortOrder] = child.outputOrdering
+
+  override def doExecuteNative(): NativeRDD = {
+    val hadoopConf = sparkSession.sessionState.newHadoopConf()
+    val hiveQlTable = HiveClientHelper.toHiveTable(table)
+    val tableDesc = new TableDesc(
+      hiveQlTable.getInputFormatClass,
+      hiveQlTable.getOutputFormatClass,
+      hiveQlTable.getMetadata)
+    val tableProperties = tableDesc.getProperties.asScala.toMap
+
+    // init orc props, table properties take precedence over hadoop conf, and
+    // compression is resolved the same way as spark's hive writer
+    val orcConfKeys = Seq(
+      "orc.compress.size" -> "hive.exec.orc.default.buffer.size",
+      "orc.stripe.size" -> "hive.exec.orc.default.stripe.size",
+      "orc.row.index.stride" -> "hive.exec.orc.default.row.index.stride",
+      "orc.bloom.filter.columns" -> "orc.bloom.filter.columns",
+      "orc.bloom.filter.fpp" -> "orc.default.bloom.fpp")
+    val orcProps = orcConfKeys.flatMap { case (key, altKey) =>
+      tableProperties
+        .get(key)
+        .orElse(Option(hadoopConf.get(key)))
+        .orElse(Option(hadoopConf.get(altKey)))
+        .map(key -> _)
+    } :+ ("orc.compress" -> new OrcOptions(tableProperties, conf).compressionCodec)
+    val nativeProps = orcProps.map { case (key, value) =>
+      OrcProp.newBuilder().setKey(key).setValue(value).build()
+    }
+    val nativeSchema = NativeConverters.convertSchema(table.dataSchema)
+
+    val serializableConf = new SerializableConfiguration(hadoopConf)
+    val numDynParts = partition.count(_._2.isEmpty)
+
+    val inputRDD = NativeHelper.executeNative(child)
+    val nativeMetrics = MetricNode(metrics, inputRDD.metrics :: Nil)
+    val nativeDependencies = new OneToOneDependency(inputRDD) :: Nil
+    new NativeRDD(
+      sparkSession.sparkContext,
+      nativeMetrics,
+      inputRDD.partitions,
+      nativeDependencies,
+      inputRDD.isShuffleReadFull,
+      (partition, context) => {
+
+        // init hadoop fs
+        val resourceId = s"NativeOrcSinkExec:${UUID.randomUUID().toString}"
+        JniBridge.resourcesMap.put(
+          resourceId,
+          (location: String) => {
+            NativeHelper.currentUser.doAs(new PrivilegedExceptionAction[FileSystem] {
+              override def run(): FileSystem =
+                FileSystem.get(new URI(location), serializableConf.value)
+            })
+          })
+
+        val inputPartition = inputRDD.partitions(partition.index)
+        val orcSink = OrcSinkExecNode
+          .newBuilder()
+          .setInput(inputRDD.nativePlan(inputPartition, context))
+          .setFsResourceId(resourceId)
+          .setNumDynParts(numDynParts)
+          .setSchema(nativeSchema)
+          .addAllProp(nativeProps.asJava)
+        PhysicalPlanNode.newBuilder().setOrcSink(orcSink).build()
+      },
+      "OrcSink")
+  }
+}
@@ -44,6 +44,7 @@ import org.apache.spark.sql.blaze.Shims
 import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.catalyst.expressions.SortOrder
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.SparkSession
 import org.apache.spark.sql.catalyst.InternalRow
 import org.apache.spark.sql.catalyst.catalog.CatalogTable
 import org.apache.spark.sql.catalyst.plans.logical.LogicalPlan
@@ -54,6 +55,19 @@ import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.execution.metric.SQLMetrics
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
+import org.apache.spark.sql.types.BinaryType
+import org.apache.spark.sql.types.BooleanType
+import org.apache.spark.sql.types.ByteType
+import org.apache.spark.sql.types.DataType
+import org.apache.spark.sql.types.DateType
+import org.apache.spark.sql.types.DoubleType
+import org.apache.spark.sql.types.FloatType
+import org.apache.spark.sql.types.IntegerType
+import org.apache.spark.sql.types.LongType
+import org.apache.spark.sql.types.ShortType
+import org.apache.spark.sql.types.StringType
+import org.apache.spark.sql.types.TimestampType
+import org.blaze.{protobuf => pb}
 
 abstract class NativeParquetInsertIntoHiveTableBase(
     cmd: InsertIntoHiveTable,
@@ -78,6 +92,16 @@ abstract class NativeParquetInsertIntoHiveTableBase(
 
     assert(outputFormatClassName.endsWith("mapredparquetoutputformat"), "not parquet format")
     assert(!encryptEnabled, "not supported writting encrypted table")
+
+    // hive compatible buckets are computed by native hive hash, which supports only primitive
+    // types of bucket columns
+    if (Shims.get.getInsertBucketHash(cmd.table).contains(pb.BucketHash.HIVE_HASH)) {
+      val bucketColumnTypes =
+        cmd.table.bucketSpec.get.bucketColumnNames.map(cmd.table.schema(_).dataType)
+      assert(
+        bucketColumnTypes.forall(NativeParquetInsertIntoHiveTableBase.isHiveHashSupported),
+        s"not supported hive hash of bucket column types: $bucketColumnTypes")
+    }
   }
   check()
 
@@ -131,6 +155,34 @@ abstract class NativeParquetInsertIntoHiveTableBase(
       metrics: Map[String, SQLMetric]): InsertIntoHiveTable
 }
 
+object NativeParquetInsertIntoHiveTableBase {
+
+  // with concurrent output writers, spark writes the unsorted identity rows of native sinks
+  // instead of adding a sort before writing, which blocks forever as native sink is waiting
+  // for the output paths of the identity rows. the jvm side writers are cheap so there is no
+  // limit of open writers. the insert command runs in a cloned session with the setting, so
+  // the session conf is never changed
+  def withConcurrentOutputWriters[T](sparkSession: SparkSession, table: CatalogTable)(
+      run: SparkSession => T): T = {
+    val clonedSession = sparkSession.cloneSession()
+    clonedSession.sessionState.conf
+      .setConfString("spark.sql.maxConcurrentOutputFileWriters", Int.MaxValue.toString)
+    try {
+      run(clonedSession)
+    } finally {
+      // the inserted table is refreshed in the catalog of the cloned session only
+      sparkSession.sessionState.catalog.refreshTable(table.identifier)
+    }
+  }
+
+  def isHiveHashSupported(dataType: DataType): Boolean = dataType match {
+    case BooleanType | ByteType | ShortType | IntegerType | LongType | FloatType | DoubleType =>
+      true
+    case DateType | TimestampType | StringType | BinaryType => true
+    case _ => false
+  }
+}
+
 // A dummy output format which does not write anything but only pass output path to native ParquetSinkExec.
 class BlazeMapredParquetOutputFormat
     extends FileOutputFormat[NullWritable, NullWritable]
@@ -153,7 +205,7 @@ class BlazeMapredParquetOutputFormat
 
     new FileSinkOperator.RecordWriter {
       override def write(w: Writable): Unit = {
-        ParquetSinkTaskContext.get.processingOutputFiles.offer(finalOutPath.toString)
+        ParquetSinkTaskContext.get.offerOutputPath(finalOutPath)
       }
 
       override def close(abort: Boolean): Unit = {}
@@ -166,11 +218,34 @@ case class OutputFileStat(path: String, numRows: Long, numBytes: Long)
 class ParquetSinkTaskContext {
   val processingOutputFiles = new LinkedBlockingDeque[String]()
   val processedOutputFiles = new util.ArrayDeque[OutputFileStat]()
+  private val numOutputsOfPaths = mutable.Map[Path, Int]()
+
+  // native sink may request more than one files for the same output path, when rolling files
+  // or writing a partition again after its writer is closed. following files are suffixed with
+  // a sequence number to avoid overwriting the previous ones.
+  def offerOutputPath(path: Path): Unit = {
+    val numOutputs = numOutputsOfPaths.getOrElse(path, 0)
+    numOutputsOfPaths.put(path, numOutputs + 1)
+
+    val outputPath = if (numOutputs == 0) {
+      path
+    } else {
+      new Path(path.getParent, s"${path.getName}-$numOutputs")
+    }
+    processingOutputFiles.offer(outputPath.toString)
+  }
 }
 
 object ParquetSinkTaskContext {
   private val instances = mutable.Map[Long, ParquetSinkTaskContext]()
 
+  // names bucket files like spark's hive compatible bucketed writes, which prefixes the file
+  // names with the bucket ids
+  def bucketFilePath(path: String, bucketId: Int): String = {
+    val outputPath = new Path(path)
+    new Path(outputPath.getParent, f"$bucketId%05d_0_${outputPath.getName}").toString
+  }
+
   def get: ParquetSinkTaskContext = {
     val taskId = TaskContext.get.taskAttemptId()
     instances.getOrElseUpdate(
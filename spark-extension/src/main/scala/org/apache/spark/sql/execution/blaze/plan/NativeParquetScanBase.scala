@@ -36,6 +36,7 @@ import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.blaze.NativeSupports
 import org.apache.spark.sql.blaze.Shims
 import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.plans.physical.HashPartitioning
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.execution.FileSourceScanExec
 import org.apache.spark.sql.execution.LeafExecNode
@@ -43,14 +44,20 @@ import org.apache.spark.sql.execution.datasources.FileScanRDD
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.execution.datasources.FilePartition
 import org.apache.spark.sql.execution.datasources.PartitionedFile
+import org.apache.spark.sql.execution.datasources.PartitioningAwareFileIndex
 import org.apache.spark.sql.execution.metric.SQLMetrics
 import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.types.ArrayType
+import org.apache.spark.sql.types.DataType
+import org.apache.spark.sql.types.MapType
 import org.apache.spark.sql.types.NullType
 import org.apache.spark.sql.types.StructField
 import org.apache.spark.sql.types.StructType
 import org.apache.spark.util.SerializableConfiguration
 
-abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
+abstract class NativeParquetScanBase(
+    basedFileScan: FileSourceScanExec,
+    metadataColumns: Seq[(Attribute, pb.FileMetadataColumnKind)])
     extends LeafExecNode
     with NativeSupports {
 
@@ -63,12 +70,24 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
         .createMetric(sparkContext, "Native.predicate_evaluation_errors")) :+
       ("row_groups_pruned", SQLMetrics
         .createMetric(sparkContext, "Native.row_groups_pruned")) :+
+      ("row_groups_pruned_bloom_filter", SQLMetrics
+        .createMetric(sparkContext, "Native.row_groups_pruned_bloom_filter")) :+
+      ("metadata_cache_hits", SQLMetrics
+        .createMetric(sparkContext, "Native.metadata_cache_hits")) :+
+      ("metadata_cache_misses", SQLMetrics
+        .createMetric(sparkContext, "Native.metadata_cache_misses")) :+
       ("bytes_scanned", SQLMetrics.createSizeMetric(sparkContext, "Native.bytes_scanned")) :+
       ("io_time", SQLMetrics.createNanoTimingMetric(sparkContext, "Native.io_time")) :+
       ("io_time_getfs", SQLMetrics
         .createNanoTimingMetric(sparkContext, "Native.io_time_getfs")): _*)
 
-  override val output: Seq[Attribute] = basedFileScan.output
+  // file source metadata columns (spark 3.3+) are part of the file scan output, but they are
+  // not read from the files and are excluded from the scanned columns
+  private val scannedOutput =
+    basedFileScan.output.filterNot(attr => metadataColumns.exists(_._1.exprId == attr.exprId))
+
+  // metadata columns are synthesized by native scan and appended after the scanned columns
+  override val output: Seq[Attribute] = scannedOutput ++ metadataColumns.map(_._1)
   override val outputPartitioning: Partitioning = basedFileScan.outputPartitioning
 
   private val inputFileScanRDD = {
@@ -86,21 +105,88 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
     .mapValues(_.map(_.length).sum)
     .map(identity) // make this map serializable
 
+  // modification times are used for validating cached metadata in native side. files not
+  // found in the file index are sent with 0 and their metadata are never cached
+  private val fileModificationTimes = basedFileScan.relation.location match {
+    case fileIndex: PartitioningAwareFileIndex =>
+      fileIndex
+        .allFiles()
+        .map(status => status.getPath.toUri.toString -> status.getModificationTime)
+        .filter(kv => fileSizes.contains(kv._1))
+        .toMap
+    case _ => Map[String, Long]()
+  }
+
   private def nativePruningPredicateFilters = basedFileScan.dataFilters
     .map(expr => NativeConverters.convertScanPruningExpr(expr))
 
+  // columns are resolved by parquet field ids in native side if the schema carries them
+  private def readFieldIds =
+    conf.getConfString("spark.sql.parquet.fieldId.read.enabled", "false").toBoolean
+
   private def nativeFileSchema =
-    NativeConverters.convertSchema(StructType(basedFileScan.relation.dataSchema.map {
-      case field if basedFileScan.requiredSchema.exists(_.name == field.name) =>
-        field.copy(nullable = true)
-      case field =>
-        // avoid converting unsupported type in non-used fields
-        StructField(field.name, NullType, nullable = true)
-    }))
+    NativeConverters.convertSchema(
+      StructType(basedFileScan.relation.dataSchema.map {
+        case field if basedFileScan.requiredSchema.exists(_.name == field.name) =>
+          field.copy(nullable = true)
+        case field =>
+          // avoid converting unsupported type in non-used fields
+          StructField(field.name, NullType, nullable = true, field.metadata)
+      }),
+      withFieldIds = readFieldIds)
+
+  // paths of required nested fields in columns pruned by spark, native scan reads only the
+  // parquet leaf columns of these paths. map keys are always read as a whole
+  private def nativeNestedProjection = {
+    def collectPaths(path: Seq[String], dataType: DataType): Seq[Seq[String]] = dataType match {
+      case StructType(fields) => fields.flatMap(f => collectPaths(path :+ f.name, f.dataType))
+      case ArrayType(elementType, _) => collectPaths(path, elementType)
+      case MapType(_, valueType, _) => collectPaths(path, valueType)
+      case _ => Seq(path)
+    }
+    basedFileScan.requiredSchema
+      .flatMap { field =>
+        basedFileScan.relation.dataSchema.find(_.name == field.name) match {
+          case Some(dataField) if dataField.dataType != field.dataType =>
+            collectPaths(Seq(field.name), field.dataType)
+          case _ => Nil
+        }
+      }
+      .map(path => pb.NestedProjectionPath.newBuilder().addAllNames(path.asJava).build())
+  }
 
   private def nativePartitionSchema =
     NativeConverters.convertSchema(partitionSchema)
 
+  private def nativeMetadataColumns = metadataColumns.map { case (attr, kind) =>
+    pb.FileMetadataColumn
+      .newBuilder()
+      .setName(attr.name)
+      .setKind(kind)
+      .build()
+  }
+
+  // files of each bucket are scanned in their own partition if the scan is hash partitioned by
+  // bucket columns (that is, buckets are not coalesced). native scan then regroups the files by
+  // their bucket ids and reports the same partitioning
+  private def nativeBucketSpec = basedFileScan.relation.bucketSpec.flatMap { spec =>
+    val dataSchema = basedFileScan.relation.dataSchema
+    val bucketColumnIndices = spec.bucketColumnNames.map { name =>
+      dataSchema.fields.indexWhere(field => conf.resolver(field.name, name))
+    }
+    basedFileScan.outputPartitioning match {
+      case HashPartitioning(_, numPartitions)
+          if numPartitions == spec.numBuckets && bucketColumnIndices.forall(_ >= 0) =>
+        Some(
+          pb.BucketSpec
+            .newBuilder()
+            .addAllBucketColumnIndices(bucketColumnIndices.map(Integer.valueOf).asJava)
+            .setNumBuckets(spec.numBuckets)
+            .build())
+      case _ => None
+    }
+  }
+
   private def nativeFileGroups = (partition: FilePartition) => {
     // list input file statuses
     val nativePartitionedFile = (file: PartitionedFile) => {
@@ -114,7 +200,7 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
         .setPath(file.filePath)
         .setSize(fileSizes(file.filePath))
         .addAllPartitionValues(nativePartitionValues.asJava)
-        .setLastModifiedNs(0)
+        .setLastModifiedNs(fileModificationTimes.getOrElse(file.filePath, 0L) * 1000000L)
         .setRange(
           pb.FileRange
             .newBuilder()
@@ -151,10 +237,15 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
       }))
     val nativePruningPredicateFilters = this.nativePruningPredicateFilters
     val nativeFileSchema = this.nativeFileSchema
+    val nativeNestedProjection = this.nativeNestedProjection
     val nativeFileGroups = this.nativeFileGroups
     val nativePartitionSchema = this.nativePartitionSchema
+    val nativeMetadataColumns = this.nativeMetadataColumns
+    val nativeRebaseConf = NativeConverters.convertParquetRebaseConf(isWrite = false)
+    val nativeBucketSpec = this.nativeBucketSpec
 
-    val projection = schema.map(field => basedFileScan.relation.schema.fieldIndex(field.name))
+    val projection =
+      scannedOutput.map(attr => basedFileScan.relation.schema.fieldIndex(attr.name))
     val sparkSession = Shims.get.getSqlContext(basedFileScan).sparkSession
     val hadoopConf =
       sparkSession.sessionState.newHadoopConfWithOptions(basedFileScan.relation.options)
@@ -186,7 +277,7 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
           })
 
         val nativeFileGroup = nativeFileGroups(partition.asInstanceOf[FilePartition])
-        val nativeParquetScanConf = pb.FileScanExecConf
+        val nativeParquetScanConfBuilder = pb.FileScanExecConf
           .newBuilder()
           .setNumPartitions(numPartitions)
           .setPartitionIndex(partition.index)
@@ -195,13 +286,17 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
           .setFileGroup(nativeFileGroup)
           .addAllProjection(projection.map(Integer.valueOf).asJava)
           .setPartitionSchema(nativePartitionSchema)
-          .build()
+          .addAllNestedProjection(nativeNestedProjection.asJava)
+          .addAllMetadataColumns(nativeMetadataColumns.asJava)
+        nativeBucketSpec.foreach(spec => nativeParquetScanConfBuilder.setBucketSpec(spec))
+        val nativeParquetScanConf = nativeParquetScanConfBuilder.build()
 
         val nativeParquetScanExecBuilder = pb.ParquetScanExecNode
           .newBuilder()
           .setBaseConf(nativeParquetScanConf)
           .setFsResourceId(resourceId)
           .addAllPruningPredicates(nativePruningPredicateFilters.asJava)
+          .setRebaseConf(nativeRebaseConf)
 
         pb.PhysicalPlanNode
           .newBuilder()
@@ -211,6 +306,10 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
       friendlyName = "NativeRDD.ParquetScan")
   }
 
+  def fileScan: FileSourceScanExec = basedFileScan
+
+  def fileMetadataColumns: Seq[(Attribute, pb.FileMetadataColumnKind)] = metadataColumns
+
   override val nodeName: String =
     s"NativeParquetScan ${basedFileScan.tableIdentifier.map(_.unquotedString).getOrElse("")}"
 
@@ -36,20 +36,31 @@ import org.apache.hadoop.mapreduce.Job
 import org.apache.spark.sql.blaze.JniBridge
 import org.apache.spark.sql.SparkSession
 import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
 import org.apache.spark.sql.blaze.NativeHelper
 import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.sql.catalyst.InternalRow
 import org.apache.spark.sql.catalyst.catalog.CatalogTable
 import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.AttributeReference
 import org.apache.spark.sql.catalyst.expressions.SortOrder
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.catalyst.plans.physical.UnknownPartitioning
+import org.apache.spark.sql.catalyst.util.ArrayData
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.datasources.parquet.ParquetFileFormat
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.hive.blaze.HiveClientHelper
+import org.apache.spark.sql.types.ArrayType
+import org.apache.spark.sql.types.BinaryType
+import org.apache.spark.sql.types.LongType
+import org.apache.spark.sql.types.StringType
 import org.apache.spark.util.SerializableConfiguration
 import org.apache.spark.OneToOneDependency
 import org.apache.spark.sql.execution.UnaryExecNode
+import org.blaze.protobuf.BucketSpec
 import org.blaze.protobuf.ParquetProp
 import org.blaze.protobuf.ParquetSinkExecNode
 import org.blaze.protobuf.PhysicalPlanNode
@@ -59,15 +70,34 @@ abstract class NativeParquetSinkBase(
     table: CatalogTable,
     partition: Map[String, Option[String]],
     override val child: SparkPlan,
-    override val metrics: Map[String, SQLMetric])
+    override val metrics: Map[String, SQLMetric],
+    fileStatsOutputDir: Option[String])
     extends UnaryExecNode
     with NativeSupports {
 
-  override def output: Seq[Attribute] = child.output
+  // with file stats output dir, native sink writes files under the directory by itself, and
+  // outputs stats of the written files instead of identity rows
+  override lazy val output: Seq[Attribute] = fileStatsOutputDir match {
+    case Some(_) => NativeParquetSinkBase.fileStatsOutput
+    case None => child.output
+  }
+
+  override def outputPartitioning: Partitioning = fileStatsOutputDir match {
+    case Some(_) => UnknownPartitioning(child.outputPartitioning.numPartitions)
+    case None => child.outputPartitioning
+  }
 
-  override def outputPartitioning: Partitioning = child.outputPartitioning
+  override def outputOrdering: Seq[SortOrder] = fileStatsOutputDir match {
+    case Some(_) => Nil
+    case None => child.outputOrdering
+  }
 
-  override def outputOrdering: Seq[SortOrder] = child.outputOrdering
+  // executes the sink with file stats output, and collects stats of all written files, so
+  // table formats can commit the files without reading their footers
+  def collectFileStats(): Seq[WrittenFileStat] = {
+    assert(fileStatsOutputDir.isDefined, "file stats output dir is not set")
+    execute().map(WrittenFileStat.fromRow).collect().toSeq
+  }
 
   override def doExecuteNative(): NativeRDD = {
     val hadoopConf = sparkSession.sessionState.newHadoopConf()
@@ -83,8 +113,29 @@ abstract class NativeParquetSinkBase(
     parquetFileFormat.prepareWrite(sparkSession, job, Map(), tableSchema)
 
     val serializableConf = new SerializableConfiguration(job.getConfiguration)
+    val nativeRebaseConf = NativeConverters.convertParquetRebaseConf(isWrite = true)
     val numDynParts = partition.count(_._2.isEmpty)
 
+    // bucket/sort columns are referred by their indices in the input
+    val resolver = sparkSession.sessionState.conf.resolver
+    val columnIndex = (name: String) => {
+      val idx = child.output.indexWhere(attr => resolver(attr.name, name))
+      assert(idx >= 0, s"bucket/sort column not found in input: $name")
+      idx: Integer
+    }
+    val nativeBucketSpec = for {
+      spec <- table.bucketSpec
+      hash <- Shims.get.getInsertBucketHash(table)
+    } yield {
+      BucketSpec
+        .newBuilder()
+        .addAllBucketColumnIndices(spec.bucketColumnNames.map(columnIndex).asJava)
+        .setNumBuckets(spec.numBuckets)
+        .addAllSortColumnIndices(spec.sortColumnNames.map(columnIndex).asJava)
+        .setHash(hash)
+        .build()
+    }
+
     val inputRDD = NativeHelper.executeNative(child)
     val nativeMetrics = MetricNode(metrics, inputRDD.metrics :: Nil)
     val nativeDependencies = new OneToOneDependency(inputRDD) :: Nil
@@ -129,15 +180,17 @@ abstract class NativeParquetSinkBase(
         val schema = HiveSchemaConverter.convert(columnNames, columnTypes)
         DataWritableWriteSupport.setSchema(schema, job.getConfiguration)
 
-        // init parquet props
-        val nativeProps = job.getConfiguration.asScala
-          .filter(_.getKey.startsWith("parquet."))
-          .map(entry =>
+        // init parquet props, table properties (like bloom filter settings) take precedence
+        val confProps = job.getConfiguration.asScala.map(entry => entry.getKey -> entry.getValue)
+        val nativeProps = (confProps.toMap ++ tableProperties.asScala)
+          .filter(_._1.startsWith("parquet."))
+          .map { case (key, value) =>
             ParquetProp
               .newBuilder()
-              .setKey(entry.getKey)
-              .setValue(entry.getValue)
-              .build())
+              .setKey(key)
+              .setValue(value)
+              .build()
+          }
 
         val inputPartition = inputRDD.partitions(partition.index)
         val parquetSink = ParquetSinkExecNode
@@ -146,8 +199,66 @@ abstract class NativeParquetSinkBase(
           .setFsResourceId(resourceId)
           .setNumDynParts(numDynParts)
           .addAllProp(nativeProps.asJava)
+          .setRebaseConf(nativeRebaseConf)
+        nativeBucketSpec.foreach(spec => parquetSink.setBucketSpec(spec))
+        fileStatsOutputDir.foreach(dir => parquetSink.setFileStatsOutputDir(dir))
         PhysicalPlanNode.newBuilder().setParquetSink(parquetSink).build()
       },
       "ParquetSink")
   }
 }
+
+object NativeParquetSinkBase {
+
+  // schema of the file stats output of native sink, with one row for each written file. column
+  // stats are listed in the order of leaf columns
+  def fileStatsOutput: Seq[Attribute] = Seq(
+    AttributeReference("path", StringType, nullable = false)(),
+    AttributeReference("partition_values", ArrayType(StringType), nullable = false)(),
+    AttributeReference("num_rows", LongType, nullable = false)(),
+    AttributeReference("num_bytes", LongType, nullable = false)(),
+    AttributeReference("column_names", ArrayType(StringType), nullable = false)(),
+    AttributeReference("column_null_counts", ArrayType(LongType), nullable = false)(),
+    AttributeReference("column_min_values", ArrayType(BinaryType), nullable = false)(),
+    AttributeReference("column_max_values", ArrayType(BinaryType), nullable = false)())
+}
+
+// statistics of a leaf column in a written file. min/max values are encoded in the plain
+// encoding of parquet, and are absent if unknown
+case class ColumnFileStat(
+    name: String,
+    nullCount: Option[Long],
+    minValue: Option[Array[Byte]],
+    maxValue: Option[Array[Byte]])
+
+case class WrittenFileStat(
+    path: String,
+    partitionValues: Seq[Option[String]],
+    numRows: Long,
+    numBytes: Long,
+    columnStats: Seq[ColumnFileStat])
+
+object WrittenFileStat {
+  def fromRow(row: InternalRow): WrittenFileStat = {
+    def elements[T](array: ArrayData)(get: Int => T): Seq[Option[T]] =
+      (0 until array.numElements()).map(i => if (array.isNullAt(i)) None else Some(get(i)))
+    def strings(array: ArrayData) = elements(array)(array.getUTF8String(_).toString)
+    def binaries(array: ArrayData) = elements(array)(array.getBinary(_).clone())
+
+    val columnNames = strings(row.getArray(4)).map(_.get)
+    val nullCounts = {
+      val array = row.getArray(5)
+      elements(array)(array.getLong)
+    }
+    val minValues = binaries(row.getArray(6))
+    val maxValues = binaries(row.getArray(7))
+    WrittenFileStat(
+      path = row.getUTF8String(0).toString,
+      partitionValues = strings(row.getArray(1)),
+      numRows = row.getLong(2),
+      numBytes = row.getLong(3),
+      columnStats = columnNames.indices.map { i =>
+        ColumnFileStat(columnNames(i), nullCounts(i), minValues(i), maxValues(i))
+      })
+  }
+}
@@ -0,0 +1,99 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.util.Locale
+
+import org.apache.hadoop.conf.Configuration
+import org.apache.hadoop.fs.Path
+import org.apache.hadoop.io.compress.CompressionCodecFactory
+import org.blaze.{protobuf => pb}
+import org.apache.spark.sql.catalyst.util.CaseInsensitiveMap
+import org.apache.spark.sql.execution.FileSourceScanExec
+import org.apache.spark.sql.internal.SQLConf
+
+/**
+ * Converts the data source options shared by the csv and json scans. Options which cannot be
+ * handled natively throw NotImplementedError, so that the scan falls back to spark.
+ */
+object NativeTextScanOptions {
+
+  // subset of java's DateTimeFormatter patterns supported by the native parser
+  private val supportedDateTimePattern =
+    ("('([^']|'')*'|[yu]+|M{1,2}(?!M)|d{1,2}(?!d)|H{1,2}(?!H)|m{1,2}(?!m)|s{1,2}(?!s)" +
+      "|S{1,9}(?!S)|[^a-zA-Z'])*").r
+
+  def options(basedFileScan: FileSourceScanExec): CaseInsensitiveMap[String] =
+    CaseInsensitiveMap(basedFileScan.relation.options)
+
+  def convert(
+      basedFileScan: FileSourceScanExec,
+      filePaths: Seq[String],
+      hadoopConf: Configuration): pb.TextScanOptions = {
+    val options = this.options(basedFileScan)
+
+    val mode = options.getOrElse("mode", "PERMISSIVE").toUpperCase(Locale.ROOT) match {
+      case "DROPMALFORMED" => pb.TextParseMode.DROP_MALFORMED
+      case "FAILFAST" => pb.TextParseMode.FAIL_FAST
+      case _ => pb.TextParseMode.PERMISSIVE
+    }
+    val dateTimePattern = (key: String) =>
+      options.get(key) match {
+        case Some(pattern) if !supportedDateTimePattern.pattern.matcher(pattern).matches() =>
+          throw new NotImplementedError(s"unsupported $key: $pattern")
+        case pattern => pattern.getOrElse("")
+      }
+
+    val encoding = options.get("encoding").orElse(options.get("charset"))
+    if (!encoding.forall(e => Set("utf-8", "utf8").contains(e.toLowerCase(Locale.ROOT)))) {
+      throw new NotImplementedError(s"unsupported encoding: ${encoding.get}")
+    }
+    assertDefaultOption(options, "lineSep", "\n")
+
+    // corrupt records are not kept natively
+    val columnNameOfCorruptRecord =
+      options.getOrElse("columnNameOfCorruptRecord", SQLConf.get.columnNameOfCorruptRecord)
+    if (basedFileScan.requiredSchema.fieldNames.contains(columnNameOfCorruptRecord)) {
+      throw new NotImplementedError("unsupported corrupt record column")
+    }
+
+    // compressed text files are not supported
+    val codecFactory = new CompressionCodecFactory(hadoopConf)
+    filePaths.find(path => codecFactory.getCodec(new Path(path)) != null).foreach { path =>
+      throw new NotImplementedError(s"unsupported compressed text file: $path")
+    }
+
+    pb.TextScanOptions
+      .newBuilder()
+      .setMode(mode)
+      .setMultiLine(options.get("multiLine").exists(_.toBoolean))
+      .setTimestampFormat(dateTimePattern("timestampFormat"))
+      .setDateFormat(dateTimePattern("dateFormat"))
+      .setTimezone(options.getOrElse("timeZone", SQLConf.get.sessionLocalTimeZone))
+      .build()
+  }
+
+  def assertDefaultOption(
+      options: CaseInsensitiveMap[String],
+      key: String,
+      defaultValues: String*): Unit = {
+    options.get(key).foreach { value =>
+      if (!defaultValues.contains(value)) {
+        throw new NotImplementedError(s"unsupported option $key: $value")
+      }
+    }
+  }
+}
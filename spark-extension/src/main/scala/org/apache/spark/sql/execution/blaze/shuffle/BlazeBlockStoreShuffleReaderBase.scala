@@ -43,11 +43,16 @@ abstract class BlazeBlockStoreShuffleReaderBase[K, C](
   protected val dep: ShuffleDependency[K, _, C] = handle.dependency
   protected def readBlocks(): Iterator[(BlockId, InputStream)]
 
-  def readIpc(): Iterator[Object] = { // FileSegment | ReadableByteChannel
-    val ipcIterator = readBlocks().map { case (_, inputStream) =>
+  /** wraps a local file segment, like attaching checksums to be verified by native reader */
+  protected def wrapFileSegment(blockId: BlockId, fileSegment: FileSegment): Object =
+    fileSegment
+
+  // FileSegment | ChecksummedFileSegment | ReadableByteChannel
+  def readIpc(): Iterator[Object] = {
+    val ipcIterator = readBlocks().map { case (blockId, inputStream) =>
       getFileSegmentFromInputStream(inputStream) match {
         case Some(fileSegment) =>
-          fileSegment
+          wrapFileSegment(blockId, fileSegment)
         case None =>
           Channels.newChannel(inputStream)
       }
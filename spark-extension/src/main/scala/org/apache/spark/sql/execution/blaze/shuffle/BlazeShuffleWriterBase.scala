@@ -25,6 +25,7 @@ import org.apache.spark.ShuffleDependency
 import org.apache.spark.SparkEnv
 import org.apache.spark.TaskContext
 import org.blaze.protobuf.PhysicalPlanNode
+import org.blaze.protobuf.ShuffleChecksumAlgorithm
 import org.blaze.protobuf.ShuffleWriterExecNode
 
 import org.apache.spark.internal.Logging
@@ -61,17 +62,27 @@ abstract class BlazeShuffleWriterBase[K, V](metrics: ShuffleWriteMetricsReporter
     val dataFile = shuffleBlockResolver.getDataFile(dep.shuffleId, mapId)
     val tempDataFilename = dataFile.getPath.replace(".data", ".data.tmp")
     val tempIndexFilename = dataFile.getPath.replace(".data", ".index.tmp")
+    val tempChecksumFilename = dataFile.getPath.replace(".data", ".checksum.tmp")
     val tempDataFilePath = Paths.get(tempDataFilename)
     val tempIndexFilePath = Paths.get(tempIndexFilename)
+    val tempChecksumFilePath = Paths.get(tempChecksumFilename)
+
+    val nativeShuffleWriterBuilder = ShuffleWriterExecNode
+      .newBuilder(nativeShuffleRDD.nativePlan(partition, context).getShuffleWriter)
+      .setOutputDataFile(tempDataFilename)
+      .setOutputIndexFile(tempIndexFilename)
+
+    // write spark-compatible checksums of partitions if enabled
+    val checksumAlgorithm = Shims.get.getShuffleChecksumAlgorithm
+    checksumAlgorithm.foreach { algorithm =>
+      nativeShuffleWriterBuilder
+        .setOutputChecksumFile(tempChecksumFilename)
+        .setChecksumAlgorithm(ShuffleChecksumAlgorithm.valueOf(algorithm.toUpperCase))
+    }
 
     val nativeShuffleWriterExec = PhysicalPlanNode
       .newBuilder()
-      .setShuffleWriter(
-        ShuffleWriterExecNode
-          .newBuilder(nativeShuffleRDD.nativePlan(partition, context).getShuffleWriter)
-          .setOutputDataFile(tempDataFilename)
-          .setOutputIndexFile(tempIndexFilename)
-          .build())
+      .setShuffleWriter(nativeShuffleWriterBuilder.build())
       .build()
     val iterator = NativeHelper.executeNativePlan(
       nativeShuffleWriterExec,
@@ -95,6 +106,18 @@ abstract class BlazeShuffleWriterBase[K, V](metrics: ShuffleWriteMetricsReporter
       })
       .toArray
 
+    // get partition checksums from shuffle write output checksum file
+    val checksums = if (checksumAlgorithm.isDefined) {
+      val checksumBytes = Files.readAllBytes(tempChecksumFilePath)
+      Files.delete(tempChecksumFilePath)
+      checksumBytes
+        .grouped(8)
+        .map(checksumBytes => ByteBuffer.wrap(checksumBytes).getLong)
+        .toArray
+    } else {
+      Array[Long]()
+    }
+
     // update metrics
     val dataSize = Files.size(tempDataFilePath)
     metrics.incBytesWritten(dataSize)
@@ -105,6 +128,7 @@ abstract class BlazeShuffleWriterBase[K, V](metrics: ShuffleWriteMetricsReporter
       tempDataFilePath.toFile,
       mapId,
       partitionLengths,
+      checksums,
       dataSize,
       context)
   }
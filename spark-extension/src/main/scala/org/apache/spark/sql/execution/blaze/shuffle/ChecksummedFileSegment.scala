@@ -0,0 +1,79 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.shuffle
+
+import java.io.DataInputStream
+import java.io.File
+import java.io.FileInputStream
+import java.nio.ByteBuffer
+
+import org.apache.spark.storage.FileSegment
+
+/**
+ * A local shuffle file segment with checksums of the consecutive reduce partitions it contains,
+ * verified by native shuffle reader.
+ *
+ * @param checksums (partition length, checksum) of each partition, encoded as big-endian longs
+ */
+class ChecksummedFileSegment(
+    val segment: FileSegment,
+    val algorithm: String,
+    val checksums: Array[Byte])
+
+object ChecksummedFileSegment {
+
+  /**
+   * creates a checksummed segment of reduce partitions [startReduceId, endReduceId) from spark's
+   * shuffle index and checksum files, returns None if the files are missing or inconsistent
+   * with the segment.
+   */
+  def create(
+      segment: FileSegment,
+      algorithm: String,
+      indexFile: File,
+      checksumFile: File,
+      startReduceId: Int,
+      endReduceId: Int): Option[ChecksummedFileSegment] = {
+
+    if (!indexFile.exists() || !checksumFile.exists()) {
+      return None
+    }
+    val numPartitions = endReduceId - startReduceId
+    val offsets = readLongs(indexFile, startReduceId, numPartitions + 1)
+    val checksums = readLongs(checksumFile, startReduceId, numPartitions)
+    if (offsets.head != segment.offset || offsets.last - offsets.head != segment.length) {
+      return None
+    }
+
+    val buf = ByteBuffer.allocate(numPartitions * 16)
+    for (i <- 0 until numPartitions) {
+      buf.putLong(offsets(i + 1) - offsets(i))
+      buf.putLong(checksums(i))
+    }
+    Some(new ChecksummedFileSegment(segment, algorithm, buf.array()))
+  }
+
+  // reads consecutive big-endian longs written by spark's DataOutputStream
+  private def readLongs(file: File, start: Int, count: Int): Array[Long] = {
+    val in = new DataInputStream(new FileInputStream(file))
+    try {
+      in.skipBytes(start * 8)
+      Array.fill(count)(in.readLong())
+    } finally {
+      in.close()
+    }
+  }
+}